@@ -13,15 +13,23 @@
 
 use std::sync::Arc;
 
-use rundler_bls::BlsSignatureAggregatorV0_7;
+use alloy_primitives::Address;
+use anyhow::{bail, Context};
+use rundler_bls::{BlsAggregationMode, BlsSignatureAggregator};
+use rundler_generic_aggregator::{
+    AggregatorUserOperation, GenericSignatureAggregator, SignatureAggregatorDefinition,
+    SubmissionProxyDefinition,
+};
 use rundler_pbh::PbhSignatureAggregator;
-use rundler_provider::Providers;
+use rundler_provider::{EvmProvider, Providers};
 use rundler_types::{
     aggregator::SignatureAggregator,
     chain::{ChainSpec, ContractRegistry},
+    v0_6, v0_7, v0_8, EntryPointVersion, UserOperation,
 };
+use serde::Deserialize;
 
-use super::CommonArgs;
+use super::{json::get_json_config, CommonArgs};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 #[clap(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -30,39 +38,162 @@ pub enum AggregatorType {
     Pbh,
 }
 
+/// Signature aggregators and submission proxies defined in the aggregators config file
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AggregatorDefinitions {
+    // Signature aggregators called with the arguments of the `IAggregator` interface
+    #[serde(default)]
+    pub(crate) aggregators: Vec<SignatureAggregatorDefinition>,
+    // Submission proxies, used by builders configured with their address
+    #[serde(default)]
+    pub(crate) submission_proxies: Vec<SubmissionProxyDefinition>,
+}
+
+/// Load the aggregator definitions, empty if no config file is set
+pub(crate) async fn load_aggregator_definitions(
+    args: &CommonArgs,
+) -> anyhow::Result<AggregatorDefinitions> {
+    let Some(path) = &args.aggregators_config_path else {
+        return Ok(AggregatorDefinitions::default());
+    };
+
+    let definitions = get_json_config::<AggregatorDefinitions>(path)
+        .await
+        .with_context(|| format!("should load aggregators config from {path}"))?;
+    tracing::info!("Aggregator definitions: {:?}", definitions);
+
+    Ok(definitions)
+}
+
 /// Instantiate aggregators and pass to chain spec
 pub fn instantiate_aggregators(
     args: &CommonArgs,
     chain_spec: &mut ChainSpec,
     providers: &(impl Providers + 'static),
-) {
+    definitions: &[SignatureAggregatorDefinition],
+) -> anyhow::Result<()> {
     let mut registry = ContractRegistry::<Arc<dyn SignatureAggregator>>::default();
 
     if args.enabled_aggregators.contains(&AggregatorType::Bls) {
         let bls_address = get_option_value(&args.aggregator_options, "BLS_ADDRESS")
-            .map(|v| v.parse().expect("invalid BLS_ADDRESS"));
-
-        let ep_v0_7 = providers
-            .ep_v0_7()
-            .as_ref()
-            .expect("BLS aggregator requires entry point v0.7");
-        let bls_aggregator = BlsSignatureAggregatorV0_7::new(ep_v0_7.clone(), bls_address);
-        registry.register(bls_aggregator.address(), Arc::new(bls_aggregator));
+            .map(|v| v.parse::<Address>().context("invalid BLS_ADDRESS"))
+            .transpose()?;
+        let bls_mode = get_option_value(&args.aggregator_options, "BLS_MODE")
+            .map(|v| v.parse::<BlsAggregationMode>().map_err(anyhow::Error::msg))
+            .transpose()?
+            .unwrap_or_default();
+        let version = get_entry_point_version(args, "BLS_ENTRY_POINT_VERSION")?;
+
+        let bls_aggregator: Arc<dyn SignatureAggregator> = match version {
+            EntryPointVersion::V0_6 => Arc::new(BlsSignatureAggregator::new(
+                require_entry_point(providers.ep_v0_6(), "BLS", version)?,
+                providers.evm().clone(),
+                bls_address,
+                bls_mode,
+            )),
+            EntryPointVersion::V0_7 => Arc::new(BlsSignatureAggregator::new(
+                require_entry_point(providers.ep_v0_7(), "BLS", version)?,
+                providers.evm().clone(),
+                bls_address,
+                bls_mode,
+            )),
+            _ => bail!("BLS aggregator does not support entry point {version:?}"),
+        };
+        registry.register(bls_aggregator.address(), bls_aggregator);
     }
 
     if args.enabled_aggregators.contains(&AggregatorType::Pbh) {
         let pbh_address = get_option_value(&args.aggregator_options, "PBH_ADDRESS")
-            .map(|v| v.parse().expect("invalid PBH_ADDRESS"));
-
-        let ep_v0_7 = providers
-            .ep_v0_7()
-            .as_ref()
-            .expect("PBH aggregator requires entry point v0.7");
-        let pbh_aggregator = PbhSignatureAggregator::new(ep_v0_7.clone(), pbh_address);
-        registry.register(pbh_aggregator.address(), Arc::new(pbh_aggregator));
+            .map(|v| v.parse::<Address>().context("invalid PBH_ADDRESS"))
+            .transpose()?;
+        let version = get_entry_point_version(args, "PBH_ENTRY_POINT_VERSION")?;
+
+        let pbh_aggregator: Arc<dyn SignatureAggregator> = match version {
+            EntryPointVersion::V0_6 => Arc::new(PbhSignatureAggregator::new(
+                require_entry_point(providers.ep_v0_6(), "PBH", version)?,
+                pbh_address,
+            )),
+            EntryPointVersion::V0_7 => Arc::new(PbhSignatureAggregator::new(
+                require_entry_point(providers.ep_v0_7(), "PBH", version)?,
+                pbh_address,
+            )),
+            EntryPointVersion::V0_8 => Arc::new(PbhSignatureAggregator::new(
+                require_entry_point(providers.ep_v0_8(), "PBH", version)?,
+                pbh_address,
+            )),
+            EntryPointVersion::Unspecified => {
+                bail!("PBH aggregator does not support entry point {version:?}")
+            }
+        };
+        registry.register(pbh_aggregator.address(), pbh_aggregator);
+    }
+
+    for definition in definitions {
+        let evm = providers.evm().clone();
+        let aggregator = match definition.entry_point_version {
+            EntryPointVersion::V0_6 => generic_aggregator::<v0_6::UserOperation, _, _>(
+                providers.ep_v0_6(),
+                evm,
+                definition,
+            )?,
+            EntryPointVersion::V0_7 => generic_aggregator::<v0_7::UserOperation, _, _>(
+                providers.ep_v0_7(),
+                evm,
+                definition,
+            )?,
+            EntryPointVersion::V0_8 => generic_aggregator::<v0_8::UserOperation, _, _>(
+                providers.ep_v0_8(),
+                evm,
+                definition,
+            )?,
+            EntryPointVersion::Unspecified => bail!(
+                "aggregator {} requires an entry point version",
+                definition.address
+            ),
+        };
+        registry.register(definition.address, aggregator);
     }
 
     chain_spec.set_signature_aggregators(Arc::new(registry));
+    Ok(())
+}
+
+/// Entry point version of a built-in aggregator, `v0.6`, `v0.7` or `v0.8` (default: `v0.7`)
+fn get_entry_point_version(args: &CommonArgs, key: &str) -> anyhow::Result<EntryPointVersion> {
+    match get_option_value(&args.aggregator_options, key) {
+        None | Some("v0.7") => Ok(EntryPointVersion::V0_7),
+        Some("v0.6") => Ok(EntryPointVersion::V0_6),
+        Some("v0.8") => Ok(EntryPointVersion::V0_8),
+        Some(v) => bail!("invalid {key}: {v}, expected v0.6, v0.7 or v0.8"),
+    }
+}
+
+fn generic_aggregator<UO, EP, P>(
+    entry_point: &Option<EP>,
+    evm: P,
+    definition: &SignatureAggregatorDefinition,
+) -> anyhow::Result<Arc<dyn SignatureAggregator>>
+where
+    UO: AggregatorUserOperation,
+    EP: Clone,
+    P: EvmProvider + 'static,
+{
+    let address = definition.address;
+    require_entry_point(entry_point, &address.to_string(), UO::entry_point_version())?;
+    let aggregator = GenericSignatureAggregator::<UO, _>::new(evm, definition.clone())
+        .with_context(|| format!("invalid aggregator {address}"))?;
+    Ok(Arc::new(aggregator))
+}
+
+fn require_entry_point<EP: Clone>(
+    entry_point: &Option<EP>,
+    aggregator: &str,
+    version: EntryPointVersion,
+) -> anyhow::Result<EP> {
+    entry_point
+        .clone()
+        .with_context(|| format!("{aggregator} aggregator requires entry point {version:?}"))
 }
 
 fn get_option_value<'a>(options: &'a [(String, String)], key: &str) -> Option<&'a str> {
@@ -17,10 +17,12 @@ use alloy_primitives::Address;
 use anyhow::Context;
 use clap::Args;
 use rundler_builder::{
-    self, BloxrouteSenderArgs, BuilderEvent, BuilderEventKind, BuilderSettings, BuilderTask,
-    BuilderTaskArgs, EntryPointBuilderSettings, FlashbotsSenderArgs, LocalBuilderBuilder,
+    self, AssignmentBackend, BlockBuilderEndpoint, BloxrouteSenderArgs, BuilderEvent,
+    BuilderEventKind, BuilderSettings, BuilderTask, BuilderTaskArgs, BundleOrderingStrategy,
+    BundleSenderArgs, EntryPointBuilderSettings, FlashbotsSenderArgs, LocalBuilderBuilder,
     RawSenderArgs, TransactionSenderArgs, TransactionSenderKind,
 };
+use rundler_generic_aggregator::{GenericSubmissionProxy, SubmissionProxyDefinition};
 use rundler_pbh::PbhSubmissionProxy;
 use rundler_pool::RemotePoolClient;
 use rundler_provider::Providers;
@@ -30,7 +32,7 @@ use rundler_task::{
     TaskSpawnerExt,
 };
 use rundler_types::{
-    chain::{ChainSpec, ContractRegistry},
+    chain::{ChainSpec, ContractRegistry, TryIntoWithSpec},
     proxy::SubmissionProxy,
     EntryPointVersion,
 };
@@ -39,6 +41,7 @@ use serde::Deserialize;
 use tokio::sync::broadcast;
 
 use super::{
+    json::get_json_config,
     proxy::{PassThroughProxy, SubmissionProxyType},
     signer::SignerArgs,
     CommonArgs,
@@ -82,7 +85,7 @@ pub struct BuilderArgs {
 
     /// Choice of what sender type to to use for transaction submission.
     /// Defaults to the value of `raw`. Other options include `flashbots`,
-    /// `conditional` and `bloxroute`
+    /// `conditional`, `bloxroute` and `bundle`
     #[arg(
         long = "builder.sender",
         name = "builder.sender",
@@ -147,6 +150,62 @@ pub struct BuilderArgs {
     )]
     bloxroute_auth_header: Option<String>,
 
+    /// Path to a JSON file listing the block builders to send bundles to, with
+    /// their URL, submission method and authentication. Can be a local path or
+    /// an S3 URI.
+    ///
+    /// Only used when BUILDER_SENDER is "bundle"
+    #[arg(
+        long = "builder.bundle_builders_path",
+        name = "builder.bundle_builders_path",
+        env = "BUILDER_BUNDLE_BUILDERS_PATH"
+    )]
+    bundle_builders_path: Option<String>,
+
+    /// Number of blocks following the current block that each bundle targets.
+    /// The bundle is considered dropped once they pass.
+    ///
+    /// Only used when BUILDER_SENDER is "bundle"
+    #[arg(
+        long = "builder.bundle_target_blocks",
+        name = "builder.bundle_target_blocks",
+        env = "BUILDER_BUNDLE_TARGET_BLOCKS",
+        default_value = "3"
+    )]
+    bundle_target_blocks: u64,
+
+    /// Percent of the MEV generated by a bundle to request as a refund from
+    /// builders that support refunds.
+    ///
+    /// Only used when BUILDER_SENDER is "bundle"
+    #[arg(
+        long = "builder.bundle_refund_percent",
+        name = "builder.bundle_refund_percent",
+        env = "BUILDER_BUNDLE_REFUND_PERCENT"
+    )]
+    bundle_refund_percent: Option<u8>,
+
+    /// Recipient of bundle refunds. Defaults to the bundle sender.
+    ///
+    /// Only used when BUILDER_SENDER is "bundle"
+    #[arg(
+        long = "builder.bundle_refund_recipient",
+        name = "builder.bundle_refund_recipient",
+        env = "BUILDER_BUNDLE_REFUND_RECIPIENT"
+    )]
+    bundle_refund_recipient: Option<Address>,
+
+    /// Allow builders to include bundles whose transaction reverts.
+    ///
+    /// Only used when BUILDER_SENDER is "bundle"
+    #[arg(
+        long = "builder.bundle_allow_revert",
+        name = "builder.bundle_allow_revert",
+        env = "BUILDER_BUNDLE_ALLOW_REVERT",
+        default_value = "false"
+    )]
+    bundle_allow_revert: bool,
+
     /// After submitting a bundle transaction, the maximum number of blocks to
     /// wait for that transaction to mine before we try resending with higher
     /// gas fees.
@@ -187,6 +246,44 @@ pub struct BuilderArgs {
         default_value = "20"
     )]
     max_replacement_underpriced_blocks: u64,
+
+    /// The maximum number of bundle transactions a sender can have pending at once,
+    /// each with its own nonce.
+    ///
+    /// Above 1, the next bundle is sent before the previous one mines. Only used
+    /// in auto bundling mode.
+    #[arg(
+        long = "builder.max_pending_bundles",
+        name = "builder.max_pending_bundles",
+        env = "BUILDER_MAX_PENDING_BUNDLES",
+        default_value = "1"
+    )]
+    max_pending_bundles: u64,
+
+    /// The number of consecutive failures of the same kind after which a builder's
+    /// signer is quarantined and replaced with a spare signer.
+    ///
+    /// Counted failures are nonce too low, insufficient funds, cancellations abandoned
+    /// at the max fee increases and nonces used by other transactions. If not set,
+    /// signers are never quarantined.
+    #[arg(
+        long = "builder.signer_quarantine_threshold",
+        name = "builder.signer_quarantine_threshold",
+        env = "BUILDER_SIGNER_QUARANTINE_THRESHOLD"
+    )]
+    signer_quarantine_threshold: Option<u64>,
+
+    /// Where the assignments of user operation senders to builders are kept.
+    ///
+    /// `local` coordinates the builders of this process only. `pool` leases the
+    /// senders from the pool, coordinating builders running in separate processes.
+    #[arg(
+        long = "builder.assignment_backend",
+        name = "builder.assignment_backend",
+        env = "BUILDER_ASSIGNMENT_BACKEND",
+        default_value = "local"
+    )]
+    assignment_backend: AssignmentBackend,
 }
 
 impl BuilderArgs {
@@ -247,7 +344,28 @@ impl BuilderArgs {
             num_builders += common.num_builders_v0_7;
         }
 
-        let sender_args = self.sender_args(&chain_spec, &rpc_url)?;
+        if common.enable_entry_point_v0_8 {
+            let builders = entry_point_builders
+                .as_ref()
+                .and_then(|builder_configs| {
+                    builder_configs
+                        .get_for_entry_point(chain_spec.entry_point_address_v0_8)
+                        .map(|ep| ep.builders())
+                })
+                .unwrap_or_else(|| builder_settings_from_cli(common.num_builders_v0_8));
+
+            entry_points.push(EntryPointBuilderSettings {
+                address: chain_spec.entry_point_address_v0_8,
+                version: EntryPointVersion::V0_8,
+                mempool_configs: mempool_configs
+                    .get_for_entry_point(chain_spec.entry_point_address_v0_8),
+                builders,
+            });
+
+            num_builders += common.num_builders_v0_8;
+        }
+
+        let sender_args = self.sender_args(&chain_spec, &rpc_url).await?;
         let signing_scheme = self
             .signer_args
             .signing_scheme(Some(num_builders as usize))?;
@@ -278,11 +396,14 @@ impl BuilderArgs {
             max_bundle_gas: chain_spec
                 .block_gas_limit_mult(common.max_bundle_block_gas_limit_ratio),
             sender_args,
-            sim_settings: common.try_into()?,
+            sim_settings: common.try_into_with_spec(&chain_spec)?,
             max_blocks_to_wait_for_mine: self.max_blocks_to_wait_for_mine,
             replacement_fee_percent_increase: self.replacement_fee_percent_increase,
             max_cancellation_fee_increases: self.max_cancellation_fee_increases,
             max_replacement_underpriced_blocks: self.max_replacement_underpriced_blocks,
+            max_pending_bundles: self.max_pending_bundles,
+            signer_quarantine_threshold: self.signer_quarantine_threshold,
+            assignment_backend: self.assignment_backend,
             remote_address,
             da_gas_tracking_enabled,
             provider_client_timeout_seconds,
@@ -293,7 +414,7 @@ impl BuilderArgs {
         })
     }
 
-    fn sender_args(
+    async fn sender_args(
         &self,
         chain_spec: &ChainSpec,
         rpc_url: &str,
@@ -331,6 +452,23 @@ impl BuilderArgs {
                         .context("should have a bloxroute auth header")?,
                 }))
             }
+            TransactionSenderKind::Bundle => {
+                let path = self
+                    .bundle_builders_path
+                    .as_ref()
+                    .context("should have a bundle builders path (cli: bundle_builders_path)")?;
+                let builders = get_json_config::<Vec<BlockBuilderEndpoint>>(path)
+                    .await
+                    .with_context(|| format!("should load bundle builders from {path}"))?;
+
+                Ok(TransactionSenderArgs::Bundle(BundleSenderArgs {
+                    builders,
+                    target_blocks: self.bundle_target_blocks,
+                    refund_percent: self.bundle_refund_percent,
+                    refund_recipient: self.bundle_refund_recipient,
+                    allow_revert: self.bundle_allow_revert,
+                }))
+            }
         }
     }
 }
@@ -362,6 +500,11 @@ pub(crate) struct BuilderConfig {
     pub(crate) proxy_type: Option<String>,
     // Optional filter to apply to the builders
     pub(crate) filter_id: Option<String>,
+    // Strategy used to order user operations in bundles, defaults to gas price
+    #[serde(default)]
+    pub(crate) ordering: BundleOrderingStrategy,
+    // Optional minimum expected profit margin of bundles, in percent of their cost
+    pub(crate) min_profit_margin_pct: Option<i32>,
 }
 
 impl EntryPointBuilderConfigs {
@@ -369,15 +512,22 @@ impl EntryPointBuilderConfigs {
         self.entry_points.iter().find(|ep| ep.address == address)
     }
 
-    pub(crate) fn set_proxies(&self, chain_spec: &mut ChainSpec) {
+    pub(crate) fn set_proxies(
+        &self,
+        chain_spec: &mut ChainSpec,
+        definitions: &[SubmissionProxyDefinition],
+    ) -> anyhow::Result<()> {
         let mut registry = ContractRegistry::<Arc<dyn SubmissionProxy>>::default();
 
         for entry_point in &self.entry_points {
             for builder in &entry_point.builders {
                 if let Some(proxy) = builder.proxy {
+                    let definition = definitions.iter().find(|d| d.address == proxy);
                     let proxy_type = if let Some(proxy_type) = &builder.proxy_type {
                         SubmissionProxyType::from_str(proxy_type)
-                            .unwrap_or_else(|_| panic!("proxyType not supported: {}", proxy_type))
+                            .map_err(|_| anyhow::anyhow!("proxyType not supported: {proxy_type}"))?
+                    } else if definition.is_some() {
+                        SubmissionProxyType::Generic
                     } else {
                         SubmissionProxyType::PassThrough
                     };
@@ -389,12 +539,21 @@ impl EntryPointBuilderConfigs {
                         SubmissionProxyType::Pbh => {
                             registry.register(proxy, Arc::new(PbhSubmissionProxy::new(proxy)));
                         }
+                        SubmissionProxyType::Generic => {
+                            let definition = definition.with_context(|| {
+                                format!("no definition for generic proxy {proxy}")
+                            })?;
+                            let generic = GenericSubmissionProxy::new(definition)
+                                .with_context(|| format!("invalid proxy {proxy}"))?;
+                            registry.register(proxy, Arc::new(generic));
+                        }
                     }
                 }
             }
         }
 
         chain_spec.set_submission_proxies(Arc::new(registry));
+        Ok(())
     }
 }
 
@@ -405,6 +564,8 @@ impl EntryPointBuilderConfig {
             builders.extend((0..builder.count).map(|_| BuilderSettings {
                 submission_proxy: builder.proxy,
                 filter_id: builder.filter_id.clone(),
+                ordering: builder.ordering,
+                min_profit_margin_pct: builder.min_profit_margin_pct,
             }));
         }
         builders
@@ -416,6 +577,8 @@ fn builder_settings_from_cli(count: u64) -> Vec<BuilderSettings> {
         .map(|_| BuilderSettings {
             submission_proxy: None,
             filter_id: None,
+            ordering: BundleOrderingStrategy::default(),
+            min_profit_margin_pct: None,
         })
         .collect()
 }
@@ -42,17 +42,18 @@ use pool::PoolCliArgs;
 use reth_tasks::TaskManager;
 use rpc::RpcCliArgs;
 use rundler_provider::{
-    AlloyEntryPointV0_6, AlloyEntryPointV0_7, AlloyEvmProvider, DAGasOracle, DAGasOracleSync,
-    EntryPointProvider, EvmProvider, FeeEstimator, Providers,
+    AlloyEntryPointV0_6, AlloyEntryPointV0_7, AlloyEntryPointV0_8, AlloyEvmProvider, DAGasOracle,
+    DAGasOracleSync, EntryPointProvider, EvmProvider, FeeEstimator, NodeConfig, Providers,
 };
 use rundler_sim::{
     EstimationSettings, MempoolConfigs, PrecheckSettings, SimulationSettings, MIN_CALL_GAS_LIMIT,
 };
 use rundler_types::{
-    chain::{ChainSpec, TryFromWithSpec},
+    chain::{ChainSpec, SimulationEngine, TryFromWithSpec},
     da::DAGasOracleType,
     v0_6::UserOperation as UserOperationV0_6,
     v0_7::UserOperation as UserOperationV0_7,
+    v0_8::UserOperation as UserOperationV0_8,
     PriorityFeeMode,
 };
 
@@ -81,12 +82,18 @@ pub async fn run() -> anyhow::Result<()> {
     let mut cs = chain_spec::resolve_chain_spec(&opt.common.network, &opt.common.chain_spec);
 
     let (mempool_configs, entry_point_builders) = load_configs(&opt.common).await?;
+    let aggregator_definitions = aggregator::load_aggregator_definitions(&opt.common).await?;
     if let Some(entry_point_builders) = &entry_point_builders {
-        entry_point_builders.set_proxies(&mut cs);
+        entry_point_builders.set_proxies(&mut cs, &aggregator_definitions.submission_proxies)?;
     }
 
     let providers = construct_providers(&opt.common, &cs)?;
-    aggregator::instantiate_aggregators(&opt.common, &mut cs, &providers);
+    aggregator::instantiate_aggregators(
+        &opt.common,
+        &mut cs,
+        &providers,
+        &aggregator_definitions.aggregators,
+    )?;
 
     tracing::info!("Chain spec: {:#?}", cs);
 
@@ -201,7 +208,10 @@ pub struct CommonArgs {
     )]
     chain_spec: Option<String>,
 
-    /// ETH Node HTTP URL to connect to
+    /// ETH Node HTTP URL to connect to.
+    ///
+    /// A comma separated list of URLs may be given, in which case requests fail over
+    /// between them in order.
     #[arg(
         long = "node_http",
         name = "node_http",
@@ -210,6 +220,46 @@ pub struct CommonArgs {
     )]
     node_http: Option<String>,
 
+    /// ETH Node HTTP URLs to route tracing (`debug_*`, `trace_*`) requests to before `node_http`
+    #[arg(
+        long = "node_http_trace",
+        name = "node_http_trace",
+        env = "NODE_HTTP_TRACE",
+        global = true,
+        value_delimiter = ','
+    )]
+    node_http_trace: Vec<String>,
+
+    /// ETH Node HTTP URLs to route transaction submission requests to before `node_http`
+    #[arg(
+        long = "node_http_submit",
+        name = "node_http_submit",
+        env = "NODE_HTTP_SUBMIT",
+        global = true,
+        value_delimiter = ','
+    )]
+    node_http_submit: Vec<String>,
+
+    /// ETH Node HTTP URLs to route read-only requests to before `node_http`
+    #[arg(
+        long = "node_http_read",
+        name = "node_http_read",
+        env = "NODE_HTTP_READ",
+        global = true,
+        value_delimiter = ','
+    )]
+    node_http_read: Vec<String>,
+
+    /// Number of nodes that must agree on a block fetched by hash or number. 0 or 1 disables quorum reads.
+    #[arg(
+        long = "node_http_block_quorum",
+        name = "node_http_block_quorum",
+        env = "NODE_HTTP_BLOCK_QUORUM",
+        default_value = "0",
+        global = true
+    )]
+    node_http_block_quorum: usize,
+
     /// Flag for turning unsafe bundling mode on
     #[arg(long = "unsafe", env = "UNSAFE", global = true)]
     unsafe_mode: bool,
@@ -424,6 +474,17 @@ pub struct CommonArgs {
     )]
     pub disable_entry_point_v0_7: bool,
 
+    // Entry point v0.8 is opt-in so that existing deployments don't need
+    // additional builder keys
+    #[arg(
+        long = "enable_entry_point_v0_8",
+        name = "enable_entry_point_v0_8",
+        env = "ENABLE_ENTRY_POINT_V0_8",
+        default_value = "false",
+        global = true
+    )]
+    pub enable_entry_point_v0_8: bool,
+
     // Ignored if disable_entry_point_v0_6 is true
     // Ignored if entry_point_builders_path is set
     #[arg(
@@ -446,6 +507,17 @@ pub struct CommonArgs {
     )]
     pub num_builders_v0_7: u64,
 
+    // Ignored if enable_entry_point_v0_8 is false
+    // Ignored if entry_point_builders_path is set
+    #[arg(
+        long = "num_builders_v0_8",
+        name = "num_builders_v0_8",
+        env = "NUM_BUILDERS_V0_8",
+        default_value = "1",
+        global = true
+    )]
+    pub num_builders_v0_8: u64,
+
     #[arg(
         long = "da_gas_tracking_enabled",
         name = "da_gas_tracking_enabled",
@@ -489,6 +561,14 @@ pub struct CommonArgs {
         value_parser = ValueParser::new(parse_key_val)
     )]
     pub aggregator_options: Vec<(String, String)>,
+
+    #[arg(
+        long = "aggregators_config_path",
+        name = "aggregators_config_path",
+        env = "AGGREGATORS_CONFIG_PATH",
+        global = true
+    )]
+    pub aggregators_config_path: Option<String>,
 }
 
 fn parse_key_val(s: &str) -> Result<(String, String), anyhow::Error> {
@@ -571,19 +651,25 @@ impl TryFromWithSpec<&CommonArgs> for PrecheckSettings {
     }
 }
 
-impl TryFrom<&CommonArgs> for SimulationSettings {
+impl TryFromWithSpec<&CommonArgs> for SimulationSettings {
     type Error = anyhow::Error;
 
-    fn try_from(value: &CommonArgs) -> Result<Self, Self::Error> {
+    fn try_from_with_spec(value: &CommonArgs, chain_spec: &ChainSpec) -> Result<Self, Self::Error> {
         if go_parse_duration::parse_duration(&value.tracer_timeout).is_err() {
             bail!("Invalid value for tracer_timeout, must be parsable by the ParseDuration function. See docs https://pkg.go.dev/time#ParseDuration")
         }
+        if chain_spec.simulation_engine == SimulationEngine::Revm && !value.disable_entry_point_v0_6
+        {
+            bail!("Simulation engine REVM is not supported for entry point v0.6, set disable_entry_point_v0_6 or use another simulation engine")
+        }
 
         Ok(Self {
             min_unstake_delay: value.min_unstake_delay,
             min_stake_value: U256::from(value.min_stake_value),
             tracer_timeout: value.tracer_timeout.clone(),
             enable_unsafe_fallback: value.enable_unsafe_fallback,
+            simulation_engine: chain_spec.simulation_engine,
+            chain_spec: chain_spec.clone(),
         })
     }
 }
@@ -712,20 +798,22 @@ pub struct Cli {
 }
 
 #[derive(Clone)]
-pub struct RundlerProviders<P, EP06, EP07, D, DS, F> {
+pub struct RundlerProviders<P, EP06, EP07, EP08, D, DS, F> {
     provider: P,
     ep_v0_6: Option<EP06>,
     ep_v0_7: Option<EP07>,
+    ep_v0_8: Option<EP08>,
     da_gas_oracle: D,
     da_gas_oracle_sync: Option<DS>,
     fee_estimator: F,
 }
 
-impl<P, EP06, EP07, D, DS, F> Providers for RundlerProviders<P, EP06, EP07, D, DS, F>
+impl<P, EP06, EP07, EP08, D, DS, F> Providers for RundlerProviders<P, EP06, EP07, EP08, D, DS, F>
 where
     P: EvmProvider + Clone,
     EP06: EntryPointProvider<UserOperationV0_6> + Clone,
     EP07: EntryPointProvider<UserOperationV0_7> + Clone,
+    EP08: EntryPointProvider<UserOperationV0_8> + Clone,
     D: DAGasOracle + Clone,
     DS: DAGasOracleSync + Clone,
     F: FeeEstimator + Clone,
@@ -733,6 +821,7 @@ where
     type Evm = P;
     type EntryPointV0_6 = EP06;
     type EntryPointV0_7 = EP07;
+    type EntryPointV0_8 = EP08;
     type DAGasOracle = D;
     type DAGasOracleSync = DS;
     type FeeEstimator = F;
@@ -749,6 +838,10 @@ where
         &self.ep_v0_7
     }
 
+    fn ep_v0_8(&self) -> &Option<Self::EntryPointV0_8> {
+        &self.ep_v0_8
+    }
+
     fn da_gas_oracle(&self) -> &Self::DAGasOracle {
         &self.da_gas_oracle
     }
@@ -766,9 +859,18 @@ pub fn construct_providers(
     args: &CommonArgs,
     chain_spec: &ChainSpec,
 ) -> anyhow::Result<impl Providers + 'static> {
-    let provider = Arc::new(rundler_provider::new_alloy_provider(
-        args.node_http.as_ref().context("must provide node_http")?,
-        args.provider_client_timeout_seconds,
+    let node_config = NodeConfig {
+        urls: rundler_provider::split_urls(
+            args.node_http.as_ref().context("must provide node_http")?,
+        ),
+        trace_urls: args.node_http_trace.clone(),
+        submit_urls: args.node_http_submit.clone(),
+        read_urls: args.node_http_read.clone(),
+        block_quorum: args.node_http_block_quorum,
+        client_timeout_seconds: args.provider_client_timeout_seconds,
+    };
+    let provider = Arc::new(rundler_provider::new_alloy_provider_with_config(
+        &node_config,
     )?);
     let (da_gas_oracle, da_gas_oracle_sync) =
         rundler_provider::new_alloy_da_gas_oracle(chain_spec, provider.clone());
@@ -805,6 +907,19 @@ pub fn construct_providers(
         ))
     };
 
+    let ep_v0_8 = if args.enable_entry_point_v0_8 {
+        Some(AlloyEntryPointV0_8::new(
+            chain_spec.clone(),
+            args.max_verification_gas,
+            max_bundle_execution_gas,
+            max_bundle_execution_gas,
+            provider.clone(),
+            da_gas_oracle.clone(),
+        ))
+    } else {
+        None
+    };
+
     let priority_fee_mode = PriorityFeeMode::try_from(
         args.priority_fee_mode_kind.as_str(),
         args.priority_fee_mode_value,
@@ -821,6 +936,7 @@ pub fn construct_providers(
         provider: evm,
         ep_v0_6,
         ep_v0_7,
+        ep_v0_8,
         da_gas_oracle,
         da_gas_oracle_sync,
         fee_estimator,
@@ -835,10 +951,8 @@ fn lint_da_gas_tracking(da_gas_tracking_enabled: bool, chain_spec: &ChainSpec) -
     if !chain_spec.da_pre_verification_gas {
         tracing::warn!("DA tracking is disabled because DA pre-verification gas is not enabled");
         false
-    } else if !(chain_spec.da_gas_oracle_type == DAGasOracleType::CachedNitro
-        || chain_spec.da_gas_oracle_type == DAGasOracleType::LocalBedrock)
-    {
-        tracing::warn!("DA tracking is disabled because DA gas oracle contract type {:?} does not support caching", chain_spec.da_gas_oracle_type);
+    } else if chain_spec.da_gas_oracle_type == DAGasOracleType::None {
+        tracing::warn!("DA tracking is disabled because no DA gas oracle is configured");
         false
     } else {
         true
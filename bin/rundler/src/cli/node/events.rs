@@ -13,8 +13,9 @@
 
 use std::fmt::Display;
 
-use rundler_builder::BuilderEvent;
-use rundler_pool::PoolEvent;
+use rundler_builder::{BuilderEvent, BuilderEventKind};
+use rundler_pool::{OpRemovalReason, PoolEvent};
+use rundler_rpc::{BundleStatus, OpStatus, RpcEvent};
 
 #[derive(Clone, Debug)]
 #[allow(clippy::large_enum_variant)]
@@ -43,3 +44,98 @@ impl Display for Event {
         }
     }
 }
+
+/// Converts a pool event into the events served to RPC subscribers
+pub fn pool_rpc_events(event: &PoolEvent) -> Vec<RpcEvent> {
+    match event {
+        PoolEvent::ReceivedOp { op_hash, op, .. } => vec![RpcEvent::NewPendingOp {
+            op_hash: *op_hash,
+            op: op.clone(),
+        }],
+        PoolEvent::RemovedOp { op_hash, reason } => {
+            let status = match reason {
+                OpRemovalReason::Mined {
+                    block_number,
+                    block_hash,
+                    tx_hash,
+                } => OpStatus::Mined {
+                    tx_hash: *tx_hash,
+                    block_number: *block_number,
+                    block_hash: *block_hash,
+                },
+                OpRemovalReason::Replaced { replaced_by } => OpStatus::Replaced {
+                    replaced_by: *replaced_by,
+                },
+                reason => OpStatus::Dropped {
+                    reason: format!("{reason:?}"),
+                },
+            };
+            vec![RpcEvent::OpStatus {
+                op_hash: *op_hash,
+                status,
+            }]
+        }
+        _ => vec![],
+    }
+}
+
+/// Converts a builder event into the events served to RPC subscribers
+pub fn builder_rpc_events(event: &BuilderEvent) -> Vec<RpcEvent> {
+    let builder_tag = event.tag.clone();
+    match &event.kind {
+        BuilderEventKind::FormedBundle {
+            tx_details: Some(tx_details),
+            nonce,
+            ..
+        } => {
+            let op_hashes: Vec<_> = tx_details.ops.iter().map(|(_, hash)| *hash).collect();
+            op_hashes
+                .iter()
+                .map(|op_hash| RpcEvent::OpStatus {
+                    op_hash: *op_hash,
+                    status: OpStatus::Bundled {
+                        tx_hash: tx_details.tx_hash,
+                        builder_tag: builder_tag.clone(),
+                    },
+                })
+                .chain([RpcEvent::Bundle {
+                    builder_tag: builder_tag.clone(),
+                    status: BundleStatus::Submitted {
+                        tx_hash: tx_details.tx_hash,
+                        nonce: *nonce,
+                        op_hashes: op_hashes.clone(),
+                    },
+                }])
+                .collect()
+        }
+        BuilderEventKind::TransactionMined {
+            tx_hash,
+            nonce,
+            block_number,
+        } => vec![RpcEvent::Bundle {
+            builder_tag,
+            status: BundleStatus::Mined {
+                tx_hash: *tx_hash,
+                nonce: *nonce,
+                block_number: *block_number,
+            },
+        }],
+        BuilderEventKind::LatestTransactionDropped { nonce } => vec![RpcEvent::Bundle {
+            builder_tag,
+            status: BundleStatus::Dropped { nonce: *nonce },
+        }],
+        BuilderEventKind::SkippedOp { op_hash, reason } => vec![RpcEvent::OpStatus {
+            op_hash: *op_hash,
+            status: OpStatus::Skipped {
+                reason: format!("{reason:?}"),
+            },
+        }],
+        BuilderEventKind::RejectedOp { op_hash, reason } => vec![RpcEvent::OpStatus {
+            op_hash: *op_hash,
+            status: OpStatus::Rejected {
+                reason: format!("{reason:?}"),
+            },
+        }],
+        _ => vec![],
+    }
+}
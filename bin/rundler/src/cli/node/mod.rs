@@ -13,11 +13,12 @@
 
 use std::sync::Arc;
 
+use alloy_primitives::Address;
 use clap::Args;
 use rundler_builder::{BuilderEvent, BuilderTask, LocalBuilderBuilder};
 use rundler_pool::{LocalPoolBuilder, PoolEvent, PoolTask};
 use rundler_provider::Providers;
-use rundler_rpc::RpcTask;
+use rundler_rpc::{RpcEvent, RpcTask};
 use rundler_sim::MempoolConfigs;
 use rundler_task::TaskSpawnerExt;
 use rundler_types::chain::ChainSpec;
@@ -82,7 +83,7 @@ pub async fn spawn_tasks<T: TaskSpawnerExt + 'static>(
             entry_point_builders,
         )
         .await?;
-    let rpc_task_args = rpc_args.to_args(chain_spec.clone(), &common_args)?;
+    let rpc_task_args = rpc_args.to_args(chain_spec.clone(), &common_args).await?;
 
     let (event_sender, event_rx) =
         broadcast::channel::<WithEntryPoint<Event>>(EVENT_CHANNEL_CAPACITY);
@@ -90,6 +91,8 @@ pub async fn spawn_tasks<T: TaskSpawnerExt + 'static>(
         broadcast::channel::<WithEntryPoint<PoolEvent>>(EVENT_CHANNEL_CAPACITY);
     let (builder_event_sender, builder_event_rx) =
         broadcast::channel::<WithEntryPoint<BuilderEvent>>(EVENT_CHANNEL_CAPACITY);
+    let (rpc_event_sender, _) =
+        broadcast::channel::<WithEntryPoint<RpcEvent>>(EVENT_CHANNEL_CAPACITY);
 
     task_spawner.spawn_critical(
         "recv and log events",
@@ -99,7 +102,11 @@ pub async fn spawn_tasks<T: TaskSpawnerExt + 'static>(
         "recv op pool events",
         Box::pin(emit::receive_events("op pool", op_pool_event_rx, {
             let event_sender = event_sender.clone();
+            let rpc_event_sender = rpc_event_sender.clone();
             move |event| {
+                send_rpc_events(&rpc_event_sender, event.entry_point, || {
+                    events::pool_rpc_events(&event.event)
+                });
                 let _ = event_sender.send(WithEntryPoint::of(event));
             }
         })),
@@ -108,7 +115,11 @@ pub async fn spawn_tasks<T: TaskSpawnerExt + 'static>(
         "recv builder events",
         Box::pin(emit::receive_events("builder", builder_event_rx, {
             let event_sender = event_sender.clone();
+            let rpc_event_sender = rpc_event_sender.clone();
             move |event| {
+                send_rpc_events(&rpc_event_sender, event.entry_point, || {
+                    events::builder_rpc_events(&event.event)
+                });
                 if builder::is_nonspammy_event(&event) {
                     let _ = event_sender.send(WithEntryPoint::of(event));
                 }
@@ -156,9 +167,29 @@ pub async fn spawn_tasks<T: TaskSpawnerExt + 'static>(
     .spawn(task_spawner.clone())
     .await?;
 
-    RpcTask::new(rpc_task_args, pool_handle, builder_handle, providers)
-        .spawn(task_spawner)
-        .await?;
+    RpcTask::new(
+        rpc_task_args,
+        pool_handle,
+        builder_handle,
+        providers,
+        Some(rpc_event_sender),
+    )
+    .spawn(task_spawner)
+    .await?;
 
     Ok(())
 }
+
+fn send_rpc_events(
+    sender: &broadcast::Sender<WithEntryPoint<RpcEvent>>,
+    entry_point: Address,
+    events: impl FnOnce() -> Vec<RpcEvent>,
+) {
+    // Skip the conversion when there are no subscribers
+    if sender.receiver_count() == 0 {
+        return;
+    }
+    for event in events() {
+        let _ = sender.send(WithEntryPoint { entry_point, event });
+    }
+}
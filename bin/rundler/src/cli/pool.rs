@@ -11,12 +11,15 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::{collections::HashMap, net::SocketAddr, time::Duration};
+use std::{collections::HashMap, net::SocketAddr, path::PathBuf, time::Duration};
 
-use alloy_primitives::Address;
+use alloy_primitives::{Address, B256};
 use anyhow::Context;
 use clap::Args;
-use rundler_pool::{LocalPoolBuilder, PoolConfig, PoolTask, PoolTaskArgs};
+use rundler_pool::{
+    LocalPoolBuilder, P2pSettings, PoolConfig, PoolStoreSettings, PoolTask, PoolTaskArgs,
+    RateLimit, RateLimitConfig,
+};
 use rundler_provider::Providers;
 use rundler_sim::MempoolConfigs;
 use rundler_task::TaskSpawnerExt;
@@ -183,9 +186,152 @@ pub struct PoolArgs {
         default_value = "false"
     )]
     pub support_7702: bool,
+
+    /// Maximum rate of user operations admitted to the mempool per sender, per second.
+    ///
+    /// Not rate limited if not set.
+    #[arg(
+        long = "pool.sender_rate_limit_per_sec",
+        name = "pool.sender_rate_limit_per_sec",
+        env = "POOL_SENDER_RATE_LIMIT_PER_SEC"
+    )]
+    pub sender_rate_limit_per_sec: Option<f64>,
+
+    /// Maximum rate of user operations admitted to the mempool per paymaster, per second.
+    ///
+    /// Not rate limited if not set.
+    #[arg(
+        long = "pool.paymaster_rate_limit_per_sec",
+        name = "pool.paymaster_rate_limit_per_sec",
+        env = "POOL_PAYMASTER_RATE_LIMIT_PER_SEC"
+    )]
+    pub paymaster_rate_limit_per_sec: Option<f64>,
+
+    /// Maximum rate of user operations admitted to the mempool per factory, per second.
+    ///
+    /// Not rate limited if not set.
+    #[arg(
+        long = "pool.factory_rate_limit_per_sec",
+        name = "pool.factory_rate_limit_per_sec",
+        env = "POOL_FACTORY_RATE_LIMIT_PER_SEC"
+    )]
+    pub factory_rate_limit_per_sec: Option<f64>,
+
+    /// Maximum rate of user operations admitted to the mempool per caller identity, per second.
+    ///
+    /// The caller identity is the id of the API key of the RPC caller.
+    /// Not rate limited if not set.
+    #[arg(
+        long = "pool.caller_rate_limit_per_sec",
+        name = "pool.caller_rate_limit_per_sec",
+        env = "POOL_CALLER_RATE_LIMIT_PER_SEC"
+    )]
+    pub caller_rate_limit_per_sec: Option<f64>,
+
+    /// Number of seconds worth of rate limited user operations that can be admitted at once
+    #[arg(
+        long = "pool.rate_limit_burst_secs",
+        name = "pool.rate_limit_burst_secs",
+        env = "POOL_RATE_LIMIT_BURST_SECS",
+        default_value = "10"
+    )]
+    pub rate_limit_burst_secs: u64,
+
+    /// Enable gossiping user operations with peers over the ERC-4337 p2p network
+    #[arg(
+        long = "pool.p2p_enabled",
+        name = "pool.p2p_enabled",
+        env = "POOL_P2P_ENABLED",
+        default_value = "false"
+    )]
+    pub p2p_enabled: bool,
+
+    /// Host to listen on for p2p connections
+    #[arg(
+        long = "pool.p2p_host",
+        name = "pool.p2p_host",
+        env = "POOL_P2P_HOST",
+        default_value = "0.0.0.0"
+    )]
+    pub p2p_host: String,
+
+    /// Port to listen on for p2p connections
+    #[arg(
+        long = "pool.p2p_port",
+        name = "pool.p2p_port",
+        env = "POOL_P2P_PORT",
+        default_value = "4337"
+    )]
+    pub p2p_port: u16,
+
+    /// Comma separated multiaddrs of peers to connect to on startup
+    #[arg(
+        long = "pool.p2p_bootnodes",
+        name = "pool.p2p_bootnodes",
+        env = "POOL_P2P_BOOTNODES",
+        value_delimiter = ','
+    )]
+    pub p2p_bootnodes: Vec<String>,
+
+    /// Hex encoded secp256k1 private key for the p2p node identity.
+    ///
+    /// A random identity is generated on startup if not set.
+    #[arg(
+        long = "pool.p2p_private_key",
+        name = "pool.p2p_private_key",
+        env = "POOL_P2P_PRIVATE_KEY"
+    )]
+    pub p2p_private_key: Option<B256>,
+
+    /// Path of the database file used to persist pool state across restarts.
+    ///
+    /// Pool state is not persisted if not set.
+    #[arg(
+        long = "pool.store_path",
+        name = "pool.store_path",
+        env = "POOL_STORE_PATH"
+    )]
+    pub store_path: Option<PathBuf>,
+
+    /// Interval at which pool state is saved to the store, in seconds
+    #[arg(
+        long = "pool.store_snapshot_interval_secs",
+        name = "pool.store_snapshot_interval_secs",
+        env = "POOL_STORE_SNAPSHOT_INTERVAL_SECS",
+        default_value = "60"
+    )]
+    pub store_snapshot_interval_secs: u64,
+
+    /// Time after which the senders assigned to a builder that stopped renewing its
+    /// lease are released to other builders, in seconds.
+    ///
+    /// Only used by builders that assign senders through the pool.
+    #[arg(
+        long = "pool.sender_lease_secs",
+        name = "pool.sender_lease_secs",
+        env = "POOL_SENDER_LEASE_SECS",
+        default_value = "60"
+    )]
+    pub sender_lease_secs: u64,
 }
 
 impl PoolArgs {
+    fn rate_limit_config(&self) -> RateLimitConfig {
+        let limit = |per_second: Option<f64>| {
+            per_second.map(|per_second| RateLimit {
+                per_second,
+                burst: ((per_second * self.rate_limit_burst_secs as f64).ceil() as u32).max(1),
+            })
+        };
+
+        RateLimitConfig {
+            sender: limit(self.sender_rate_limit_per_sec),
+            paymaster: limit(self.paymaster_rate_limit_per_sec),
+            factory: limit(self.factory_rate_limit_per_sec),
+            caller: limit(self.caller_rate_limit_per_sec),
+        }
+    }
+
     /// Convert the CLI arguments into the arguments for the OP Pool combining
     /// common and op pool specific arguments.
     pub async fn to_args(
@@ -224,7 +370,7 @@ impl PoolArgs {
             blocklist: blocklist.clone(),
             allowlist: allowlist.clone(),
             precheck_settings: common.try_into_with_spec(&chain_spec)?,
-            sim_settings: common.try_into()?,
+            sim_settings: common.try_into_with_spec(&chain_spec)?,
             throttled_entity_mempool_count: self.throttled_entity_mempool_count,
             throttled_entity_live_blocks: self.throttled_entity_live_blocks,
             paymaster_tracking_enabled: self.paymaster_tracking_enabled,
@@ -239,6 +385,7 @@ impl PoolArgs {
             max_time_in_pool: self.max_time_in_pool_secs.map(Duration::from_secs),
             max_expected_storage_slots: common.max_expected_storage_slots.unwrap_or(usize::MAX),
             support_7702: self.support_7702,
+            rate_limits: self.rate_limit_config(),
         };
 
         let mut pool_configs = vec![];
@@ -263,6 +410,32 @@ impl PoolArgs {
                 ..pool_config_base.clone()
             });
         }
+        if common.enable_entry_point_v0_8 {
+            pool_configs.push(PoolConfig {
+                entry_point: chain_spec.entry_point_address_v0_8,
+                entry_point_version: EntryPointVersion::V0_8,
+                mempool_channel_configs: mempool_channel_configs
+                    .get_for_entry_point(chain_spec.entry_point_address_v0_8),
+                ..pool_config_base.clone()
+            });
+        }
+
+        let p2p_settings = if self.p2p_enabled {
+            Some(P2pSettings {
+                listen_address: format!("{}:{}", self.p2p_host, self.p2p_port)
+                    .parse()
+                    .context("invalid p2p listen address")?,
+                bootnodes: self.p2p_bootnodes.clone(),
+                private_key: self.p2p_private_key,
+            })
+        } else {
+            None
+        };
+
+        let store_settings = self.store_path.clone().map(|path| PoolStoreSettings {
+            path,
+            snapshot_interval: Duration::from_secs(self.store_snapshot_interval_secs),
+        });
 
         Ok(PoolTaskArgs {
             chain_spec,
@@ -273,6 +446,9 @@ impl PoolArgs {
             pool_configs,
             remote_address,
             chain_update_channel_capacity: self.chain_update_channel_capacity.unwrap_or(1024),
+            p2p_settings,
+            store_settings,
+            sender_lease_duration: Duration::from_secs(self.sender_lease_secs),
         })
     }
 }
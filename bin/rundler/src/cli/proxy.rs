@@ -19,6 +19,7 @@ use rundler_types::{proxy::SubmissionProxy, UserOperationVariant, UserOpsPerAggr
 pub(crate) enum SubmissionProxyType {
     PassThrough,
     Pbh,
+    Generic,
 }
 
 #[derive(Debug)]
@@ -11,18 +11,19 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
 use anyhow::Context;
 use clap::Args;
 use rundler_builder::RemoteBuilderClient;
 use rundler_pool::RemotePoolClient;
 use rundler_provider::Providers;
-use rundler_rpc::{EthApiSettings, RpcTask, RpcTaskArgs};
+use rundler_rpc::{EthApiSettings, IndexerSettings, RpcTask, RpcTaskArgs};
 use rundler_task::{server::connect_with_retries_shutdown, TaskSpawnerExt};
 use rundler_types::chain::{ChainSpec, TryIntoWithSpec};
 
 use super::CommonArgs;
+use crate::cli::json::get_json_config;
 
 /// CLI options for the RPC server
 #[derive(Args, Debug)]
@@ -98,12 +99,51 @@ pub struct RpcArgs {
         default_value = "false"
     )]
     permissions_enabled: bool,
+
+    /// Path to the API key authentication config file.
+    ///
+    /// Requests are not authenticated if not set.
+    #[arg(
+        long = "rpc.auth_config_path",
+        name = "rpc.auth_config_path",
+        env = "RPC_AUTH_CONFIG_PATH"
+    )]
+    auth_config_path: Option<String>,
+
+    /// Directory of the user operation event index.
+    ///
+    /// If set, mined user operation events are indexed locally to serve receipts and
+    /// history queries without scanning logs on the node.
+    #[arg(
+        long = "rpc.indexer_path",
+        name = "rpc.indexer_path",
+        env = "RPC_INDEXER_PATH"
+    )]
+    indexer_path: Option<PathBuf>,
+
+    /// Block to start indexing from when the index is empty. Defaults to the latest block.
+    #[arg(
+        long = "rpc.indexer_start_block",
+        name = "rpc.indexer_start_block",
+        env = "RPC_INDEXER_START_BLOCK"
+    )]
+    indexer_start_block: Option<u64>,
+
+    /// Maximum number of blocks to query logs for at once while indexing
+    #[arg(
+        long = "rpc.indexer_max_block_range",
+        name = "rpc.indexer_max_block_range",
+        env = "RPC_INDEXER_MAX_BLOCK_RANGE",
+        default_value = "1000",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    indexer_max_block_range: u64,
 }
 
 impl RpcArgs {
     /// Convert the CLI arguments into the arguments for the RPC server combining
     /// common and rpc specific arguments.
-    pub fn to_args(
+    pub async fn to_args(
         &self,
         chain_spec: ChainSpec,
         common: &CommonArgs,
@@ -114,6 +154,17 @@ impl RpcArgs {
             .map(|api| api.parse())
             .collect::<Result<Vec<_>, _>>()?;
 
+        let auth = match &self.auth_config_path {
+            Some(path) => Some(get_json_config(path).await?),
+            None => None,
+        };
+
+        let indexer = self.indexer_path.clone().map(|path| IndexerSettings {
+            path,
+            start_block: self.indexer_start_block,
+            max_block_range: self.indexer_max_block_range,
+        });
+
         let eth_api_settings = EthApiSettings {
             permissions_enabled: self.permissions_enabled,
             user_operation_event_block_distance: common.user_operation_event_block_distance,
@@ -134,7 +185,10 @@ impl RpcArgs {
             max_connections: self.max_connections,
             entry_point_v0_6_enabled: !common.disable_entry_point_v0_6,
             entry_point_v0_7_enabled: !common.disable_entry_point_v0_7,
+            entry_point_v0_8_enabled: common.enable_entry_point_v0_8,
             corsdomain: self.corsdomain.clone(),
+            auth,
+            indexer,
             chain_spec,
         })
     }
@@ -178,7 +232,7 @@ pub async fn spawn_tasks<T: TaskSpawnerExt + 'static>(
         builder_url,
     } = rpc_args;
 
-    let task_args = rpc_args.to_args(chain_spec.clone(), &common_args)?;
+    let task_args = rpc_args.to_args(chain_spec.clone(), &common_args).await?;
 
     let pool = connect_with_retries_shutdown(
         "op pool from rpc",
@@ -196,7 +250,7 @@ pub async fn spawn_tasks<T: TaskSpawnerExt + 'static>(
     )
     .await?;
 
-    RpcTask::new(task_args, pool, builder, providers)
+    RpcTask::new(task_args, pool, builder, providers, None)
         .spawn(task_spawner)
         .await?;
 
@@ -13,10 +13,10 @@
 
 use std::time::Duration;
 
-use alloy_primitives::U256;
+use alloy_primitives::{Address, U256};
 use anyhow::{bail, Context};
 use clap::Args;
-use rundler_signer::{FundingSettings, KmsLockingSettings, SigningScheme};
+use rundler_signer::{FundingSettings, KmsLockingSettings, SigningScheme, VaultSettings};
 
 #[derive(Args, Debug)]
 #[command(next_help_heading = "SIGNER")]
@@ -97,6 +97,85 @@ pub struct SignerArgs {
     )]
     pub redis_lock_ttl_millis: u64,
 
+    /// URL of a remote signer speaking the `eth_signTransaction` JSON-RPC protocol, i.e. Web3Signer
+    #[arg(
+        long = "signer.remote_url",
+        name = "signer.remote_url",
+        env = "SIGNER_REMOTE_URL"
+    )]
+    pub remote_url: Option<String>,
+
+    /// Addresses of the remote signer to use for signing transactions
+    ///
+    /// If not set, all of the accounts of the remote signer are used
+    #[arg(
+        long = "signer.remote_addresses",
+        name = "signer.remote_addresses",
+        env = "SIGNER_REMOTE_ADDRESSES",
+        value_delimiter = ','
+    )]
+    pub remote_addresses: Vec<Address>,
+
+    /// Address of the remote signer to use for funding signers
+    #[arg(
+        long = "signer.remote_funding_address",
+        name = "signer.remote_funding_address",
+        env = "SIGNER_REMOTE_FUNDING_ADDRESS"
+    )]
+    pub remote_funding_address: Option<Address>,
+
+    /// Vault server address
+    #[arg(
+        long = "signer.vault_address",
+        name = "signer.vault_address",
+        env = "SIGNER_VAULT_ADDRESS"
+    )]
+    pub vault_address: Option<String>,
+
+    /// Vault token
+    #[arg(
+        long = "signer.vault_token",
+        name = "signer.vault_token",
+        env = "SIGNER_VAULT_TOKEN"
+    )]
+    pub vault_token: Option<String>,
+
+    /// Mount path of the Vault transit secrets engine
+    #[arg(
+        long = "signer.vault_transit_mount",
+        name = "signer.vault_transit_mount",
+        env = "SIGNER_VAULT_TRANSIT_MOUNT",
+        default_value = "transit"
+    )]
+    pub vault_transit_mount: String,
+
+    /// Vault transit key names to use for signing transactions
+    #[arg(
+        long = "signer.vault_key_names",
+        name = "signer.vault_key_names",
+        env = "SIGNER_VAULT_KEY_NAMES",
+        value_delimiter = ','
+    )]
+    pub vault_key_names: Vec<String>,
+
+    /// Vault transit key name to use for funding signers
+    #[arg(
+        long = "signer.vault_funding_key_name",
+        name = "signer.vault_funding_key_name",
+        env = "SIGNER_VAULT_FUNDING_KEY_NAME"
+    )]
+    pub vault_funding_key_name: Option<String>,
+
+    /// The number of signers to load in addition to one per builder, used to replace
+    /// quarantined signers
+    #[arg(
+        long = "signer.spare_keys",
+        name = "signer.spare_keys",
+        env = "SIGNER_SPARE_KEYS",
+        default_value = "0"
+    )]
+    pub spare_keys: usize,
+
     /// The balance below which signers will be funded
     #[arg(
         long = "signer.fund_below",
@@ -159,10 +238,24 @@ fn parse_aws_kms_key_groups(s: &str) -> Result<Vec<String>, String> {
 
 impl SignerArgs {
     pub fn signing_scheme(&self, num_signers: Option<usize>) -> anyhow::Result<SigningScheme> {
+        let num_signers = num_signers.map(|num_signers| num_signers + self.spare_keys);
         if self.enable_kms_funding {
             return self.funding_signer_scheme(num_signers);
         }
 
+        let scheme = self.unfunded_signing_scheme(num_signers)?;
+        if let Some(funder) = self.funder_signing_scheme()? {
+            return Ok(SigningScheme::Funding {
+                funder: Box::new(funder),
+                subkeys: Box::new(scheme),
+                funding_settings: self.funding_settings()?,
+            });
+        }
+
+        Ok(scheme)
+    }
+
+    fn unfunded_signing_scheme(&self, num_signers: Option<usize>) -> anyhow::Result<SigningScheme> {
         if !self.private_keys.is_empty() {
             if num_signers.is_some_and(|num_signers| num_signers > self.private_keys.len()) {
                 bail!(
@@ -207,7 +300,83 @@ impl SignerArgs {
             }
         }
 
-        bail!("No signing scheme provided (unfunded). Provide either signer.private_keys, signer.mnemonic, or signer.aws_kms_key_ids");
+        if let Some(url) = &self.remote_url {
+            if num_signers.is_some_and(|num_signers| {
+                !self.remote_addresses.is_empty() && num_signers > self.remote_addresses.len()
+            }) {
+                bail!(
+                        "Not enough remote signer addresses for the number of builders. Need {} addresses, found {}. You may need to disable one of the entry points.",
+                        num_signers.unwrap(), self.remote_addresses.len()
+                    );
+            }
+
+            return Ok(SigningScheme::Remote {
+                url: url.clone(),
+                addresses: self.remote_addresses.clone(),
+            });
+        }
+
+        if !self.vault_key_names.is_empty() {
+            if num_signers.is_some_and(|num_signers| num_signers > self.vault_key_names.len()) {
+                bail!(
+                        "Not enough Vault key names for the number of builders. Need {} keys, found {}. You may need to disable one of the entry points.",
+                        num_signers.unwrap(), self.vault_key_names.len()
+                    );
+            }
+
+            return Ok(SigningScheme::VaultTransit {
+                settings: self.vault_settings()?,
+                key_names: self.vault_key_names.clone(),
+            });
+        }
+
+        bail!("No signing scheme provided (unfunded). Provide either signer.private_keys, signer.mnemonic, signer.aws_kms_key_ids, signer.remote_url, or signer.vault_key_names");
+    }
+
+    fn funder_signing_scheme(&self) -> anyhow::Result<Option<SigningScheme>> {
+        if let Some(address) = self.remote_funding_address {
+            return Ok(Some(SigningScheme::Remote {
+                url: self
+                    .remote_url
+                    .clone()
+                    .context("Remote funding address is set but signer.remote_url is not set")?,
+                addresses: vec![address],
+            }));
+        }
+
+        if let Some(key_name) = &self.vault_funding_key_name {
+            return Ok(Some(SigningScheme::VaultTransit {
+                settings: self.vault_settings()?,
+                key_names: vec![key_name.clone()],
+            }));
+        }
+
+        Ok(None)
+    }
+
+    fn vault_settings(&self) -> anyhow::Result<VaultSettings> {
+        Ok(VaultSettings {
+            address: self
+                .vault_address
+                .clone()
+                .context("Vault address not set. Please set signer.vault_address")?,
+            token: self
+                .vault_token
+                .clone()
+                .context("Vault token not set. Please set signer.vault_token")?,
+            mount: self.vault_transit_mount.clone(),
+        })
+    }
+
+    fn funding_settings(&self) -> anyhow::Result<FundingSettings> {
+        Ok(FundingSettings {
+            fund_below_balance: self.fund_below.context("Fund below balance not set")?,
+            fund_to_balance: self.fund_to.context("Fund to balance not set")?,
+            poll_interval: Duration::from_millis(self.funding_txn_poll_interval_ms),
+            poll_max_retries: self.funding_txn_poll_max_retries,
+            priority_fee_multiplier: self.funding_txn_priority_fee_multiplier,
+            base_fee_multiplier: self.funding_txn_base_fee_multiplier,
+        })
     }
 
     fn funding_signer_scheme(&self, num_signers: Option<usize>) -> anyhow::Result<SigningScheme> {
@@ -267,25 +436,38 @@ impl SignerArgs {
                 num_keys: num_signers.unwrap_or(1),
             };
 
+            self.aws_kms_key_ids
+                .iter()
+                .map(|key_id| (key_id.to_string(), keys.clone()))
+                .collect()
+        } else if let Some(url) = &self.remote_url {
+            let keys = SigningScheme::Remote {
+                url: url.clone(),
+                addresses: self.remote_addresses.clone(),
+            };
+
+            self.aws_kms_key_ids
+                .iter()
+                .map(|key_id| (key_id.to_string(), keys.clone()))
+                .collect()
+        } else if !self.vault_key_names.is_empty() {
+            let keys = SigningScheme::VaultTransit {
+                settings: self.vault_settings()?,
+                key_names: self.vault_key_names.clone(),
+            };
+
             self.aws_kms_key_ids
                 .iter()
                 .map(|key_id| (key_id.to_string(), keys.clone()))
                 .collect()
         } else {
-            bail!("No signing scheme provided (funded). Provide either signer.private_keys, signer.mnemonic, signer.aws_kms_key_groups");
+            bail!("No signing scheme provided (funded). Provide either signer.private_keys, signer.mnemonic, signer.aws_kms_key_groups, signer.remote_url, or signer.vault_key_names");
         };
 
         Ok(SigningScheme::KmsFunding {
             subkeys_by_key_id,
             lock_settings,
-            funding_settings: FundingSettings {
-                fund_below_balance: self.fund_below.context("Fund below balance not set")?,
-                fund_to_balance: self.fund_to.context("Fund to balance not set")?,
-                poll_interval: Duration::from_millis(self.funding_txn_poll_interval_ms),
-                poll_max_retries: self.funding_txn_poll_max_retries,
-                priority_fee_multiplier: self.funding_txn_priority_fee_multiplier,
-                base_fee_multiplier: self.funding_txn_base_fee_multiplier,
-            },
+            funding_settings: self.funding_settings()?,
         })
     }
 }
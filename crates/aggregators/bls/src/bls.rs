@@ -11,24 +11,78 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::fmt::Debug;
+use std::{fmt::Debug, str::FromStr};
 
-use alloy_primitives::{address, bytes, Address, Bytes};
-use rundler_provider::{AggregatorOut, SignatureAggregator as EpSignatureAggregator};
+use alloy_primitives::{address, bytes, keccak256, Address, Bytes, B256, U256};
+use alloy_sol_types::{sol, Revert, SolCall, SolError, SolValue};
+use rundler_provider::{
+    AggregatorOut, EvmProvider, SignatureAggregator as EpSignatureAggregator, StateOverride,
+    TransactionRequest,
+};
 use rundler_types::{
     aggregator::{
         AggregatorCosts, SignatureAggregator, SignatureAggregatorError, SignatureAggregatorResult,
     },
-    v0_7::UserOperation,
-    UserOperationVariant,
+    v0_6, v0_7, UserOperation, UserOperationVariant,
 };
 
-const BLS_AGGREGATOR_ADDRESS: Address = address!("9d3a231e887a495ce6c454e7a38ed5e734bd5de4");
+use crate::bn254;
+
+sol! {
+    interface IBLSAccount {
+        function getBlsPublicKey() external view returns (uint256[4] memory);
+    }
+
+    struct UserOperationPackedForHashV0_6 {
+        address sender;
+        uint256 nonce;
+        bytes32 hashInitCode;
+        bytes32 hashCallData;
+        uint256 callGasLimit;
+        uint256 verificationGasLimit;
+        uint256 preVerificationGas;
+        uint256 maxFeePerGas;
+        uint256 maxPriorityFeePerGas;
+        bytes32 hashPaymasterAndData;
+    }
+
+    struct BlsUserOperationHashV0_6 {
+        bytes32 internalUserOpHash;
+        bytes32 publicKeyHash;
+        address aggregator;
+        uint256 chainId;
+    }
+
+    struct UserOperationPackedForHash {
+        address sender;
+        uint256 nonce;
+        bytes32 hashInitCode;
+        bytes32 hashCallData;
+        bytes32 accountGasLimits;
+        uint256 preVerificationGas;
+        bytes32 gasFees;
+        bytes32 hashPaymasterAndData;
+    }
+
+    struct BlsUserOperationHash {
+        bytes32 internalUserOpHash;
+        bytes32 publicKeyHash;
+        address aggregator;
+        uint256 chainId;
+        address entryPoint;
+    }
+}
+
+const BLS_AGGREGATOR_ADDRESS_V0_7: Address = address!("9d3a231e887a495ce6c454e7a38ed5e734bd5de4");
 const BLS_AGGREGATOR_FIXED_GAS: u128 = 125_000;
 const BLS_AGGREGATOR_VARIABLE_GAS: u128 = 120_000;
 const BLS_AGGREGATOR_SIG_FIXED_LENGTH: u128 = 64;
 const BLS_AGGREGATOR_SIG_VARIABLE_LENGTH: u128 = 0;
 
+const BLS_DOMAIN: &str = "eip4337.bls.domain";
+const BLS_PUBLIC_KEY_LENGTH: usize = 128;
+const BLS_SIGNATURE_LENGTH: usize = 64;
+
 static BLS_DUMMY_UO_SIG: Bytes = bytes!(""); // UO signatures are empty for BLS
 static BLS_AGGREGATOR_COSTS: AggregatorCosts = AggregatorCosts {
     execution_fixed_gas: BLS_AGGREGATOR_FIXED_GAS,
@@ -37,16 +91,129 @@ static BLS_AGGREGATOR_COSTS: AggregatorCosts = AggregatorCosts {
     sig_variable_length: BLS_AGGREGATOR_SIG_VARIABLE_LENGTH,
 };
 
+/// How the BLS aggregator verifies and aggregates signatures
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlsAggregationMode {
+    /// Verify and aggregate signatures in process
+    #[default]
+    Native,
+    /// Call the aggregator contract through the entry point
+    OnChain,
+    /// Run both, logging any disagreement and using the on-chain result
+    CrossCheck,
+}
+
+impl FromStr for BlsAggregationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "native" => Ok(Self::Native),
+            "onchain" => Ok(Self::OnChain),
+            "crosscheck" => Ok(Self::CrossCheck),
+            _ => Err(format!("invalid BLS aggregation mode: {s}")),
+        }
+    }
+}
+
+/// User operation of an entry point version supported by the BLS aggregator
+pub trait BlsUserOperation: UserOperation + From<UserOperationVariant> {
+    /// Address of the aggregator deployment for this entry point version, if any
+    const DEFAULT_AGGREGATOR_ADDRESS: Option<Address>;
+
+    /// Init code of the user operation
+    fn init_code(&self) -> &Bytes;
+
+    /// `_getUserOpHash` of the aggregator contract
+    fn bls_hash(&self, public_key_hash: B256, aggregator: Address) -> B256;
+}
+
+impl BlsUserOperation for v0_6::UserOperation {
+    const DEFAULT_AGGREGATOR_ADDRESS: Option<Address> = None;
+
+    fn init_code(&self) -> &Bytes {
+        v0_6::UserOperation::init_code(self)
+    }
+
+    fn bls_hash(&self, public_key_hash: B256, aggregator: Address) -> B256 {
+        let internal_hash = keccak256(
+            UserOperationPackedForHashV0_6 {
+                sender: self.sender(),
+                nonce: self.nonce(),
+                hashInitCode: keccak256(self.init_code()),
+                hashCallData: keccak256(self.call_data()),
+                callGasLimit: U256::from(self.call_gas_limit()),
+                verificationGasLimit: U256::from(self.verification_gas_limit()),
+                preVerificationGas: U256::from(self.pre_verification_gas()),
+                maxFeePerGas: U256::from(self.max_fee_per_gas()),
+                maxPriorityFeePerGas: U256::from(self.max_priority_fee_per_gas()),
+                hashPaymasterAndData: keccak256(self.paymaster_and_data()),
+            }
+            .abi_encode(),
+        );
+
+        keccak256(
+            BlsUserOperationHashV0_6 {
+                internalUserOpHash: internal_hash,
+                publicKeyHash: public_key_hash,
+                aggregator,
+                chainId: U256::from(self.chain_id()),
+            }
+            .abi_encode(),
+        )
+    }
+}
+
+impl BlsUserOperation for v0_7::UserOperation {
+    const DEFAULT_AGGREGATOR_ADDRESS: Option<Address> = Some(BLS_AGGREGATOR_ADDRESS_V0_7);
+
+    fn init_code(&self) -> &Bytes {
+        &self.packed().initCode
+    }
+
+    fn bls_hash(&self, public_key_hash: B256, aggregator: Address) -> B256 {
+        let packed = self.packed();
+        let internal_hash = keccak256(
+            UserOperationPackedForHash {
+                sender: packed.sender,
+                nonce: packed.nonce,
+                hashInitCode: keccak256(&packed.initCode),
+                hashCallData: keccak256(&packed.callData),
+                accountGasLimits: packed.accountGasLimits,
+                preVerificationGas: packed.preVerificationGas,
+                gasFees: packed.gasFees,
+                hashPaymasterAndData: keccak256(&packed.paymasterAndData),
+            }
+            .abi_encode(),
+        );
+
+        keccak256(
+            BlsUserOperationHash {
+                internalUserOpHash: internal_hash,
+                publicKeyHash: public_key_hash,
+                aggregator,
+                chainId: U256::from(self.chain_id()),
+                entryPoint: self.entry_point(),
+            }
+            .abi_encode(),
+        )
+    }
+}
+
 /// BLS signature aggregator
-pub struct BlsSignatureAggregatorV0_7<EP> {
+pub struct BlsSignatureAggregator<EP, P> {
     entry_point: EP,
+    provider: P,
     address: Address,
+    mode: BlsAggregationMode,
 }
 
 #[async_trait::async_trait]
-impl<EP> SignatureAggregator for BlsSignatureAggregatorV0_7<EP>
+impl<EP, P> SignatureAggregator for BlsSignatureAggregator<EP, P>
 where
-    EP: EpSignatureAggregator<UO = UserOperation>,
+    EP: EpSignatureAggregator,
+    EP::UO: BlsUserOperation,
+    P: EvmProvider,
 {
     fn address(&self) -> Address {
         self.address
@@ -64,13 +231,105 @@ where
         &self,
         user_op: &UserOperationVariant,
     ) -> SignatureAggregatorResult<Bytes> {
-        if !user_op.is_v0_7() {
-            return Err(SignatureAggregatorError::InvalidUserOperation(
-                "User operation is not v0.7".to_string(),
-            ));
+        let uo = check_version::<EP::UO>(user_op.clone())?;
+        match self.mode {
+            BlsAggregationMode::Native => self.validate_native(&uo).await,
+            BlsAggregationMode::OnChain => self.validate_on_chain(uo).await,
+            BlsAggregationMode::CrossCheck => {
+                let native = self.validate_native(&uo).await;
+                let hash = uo.hash();
+                let on_chain = self.validate_on_chain(uo).await;
+                if native.is_ok() != on_chain.is_ok() {
+                    tracing::error!("BLS cross-check mismatch validating user operation {hash:?}: native {native:?}, on-chain {on_chain:?}");
+                }
+                on_chain
+            }
+        }
+    }
+
+    async fn aggregate_signatures(
+        &self,
+        uos: Vec<UserOperationVariant>,
+    ) -> SignatureAggregatorResult<Bytes> {
+        let uos = uos
+            .into_iter()
+            .map(check_version::<EP::UO>)
+            .collect::<SignatureAggregatorResult<Vec<_>>>()?;
+
+        match self.mode {
+            BlsAggregationMode::Native => aggregate_native(&uos),
+            BlsAggregationMode::OnChain => self.aggregate_on_chain(uos).await,
+            BlsAggregationMode::CrossCheck => {
+                let native = aggregate_native(&uos);
+                let on_chain = self.aggregate_on_chain(uos).await;
+                if native.as_ref().ok() != on_chain.as_ref().ok() {
+                    tracing::error!("BLS cross-check mismatch aggregating signatures: native {native:?}, on-chain {on_chain:?}");
+                }
+                on_chain
+            }
+        }
+    }
+}
+
+impl<EP, P> BlsSignatureAggregator<EP, P>
+where
+    EP: EpSignatureAggregator,
+    EP::UO: BlsUserOperation,
+    P: EvmProvider,
+{
+    /// Create a new BLS signature aggregator
+    ///
+    /// The provider is used to fetch the public keys of deployed accounts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no address is given and there is no known aggregator deployment for the
+    /// entry point version.
+    pub fn new(
+        entry_point: EP,
+        provider: P,
+        address_override: Option<Address>,
+        mode: BlsAggregationMode,
+    ) -> Self {
+        let address = address_override
+            .or(EP::UO::DEFAULT_AGGREGATOR_ADDRESS)
+            .unwrap_or_else(|| {
+                panic!(
+                    "BLS aggregator address is required for entry point {:?}",
+                    EP::UO::entry_point_version()
+                )
+            });
+
+        Self {
+            entry_point,
+            provider,
+            address,
+            mode,
+        }
+    }
+
+    async fn validate_native(&self, uo: &EP::UO) -> SignatureAggregatorResult<Bytes> {
+        let signature = decode_signature(uo.signature())?;
+        let public_key = self.public_key(uo).await?;
+        let message = self.message(uo, public_key);
+        let public_key = bn254::decode_g2(public_key).ok_or_else(|| {
+            SignatureAggregatorError::InvalidUserOperation("invalid BLS public key".to_string())
+        })?;
+
+        if bn254::verify_multiple(signature, &[public_key], &[message]) {
+            Ok(Bytes::new())
+        } else {
+            Err(SignatureAggregatorError::ValidationReverted(
+                Revert {
+                    reason: "BLS: wrong sig".to_string(),
+                }
+                .abi_encode()
+                .into(),
+            ))
         }
+    }
 
-        let uo = user_op.clone().into();
+    async fn validate_on_chain(&self, uo: EP::UO) -> SignatureAggregatorResult<Bytes> {
         match self
             .entry_point
             .validate_user_op_signature(self.address, uo)
@@ -86,23 +345,7 @@ where
         }
     }
 
-    async fn aggregate_signatures(
-        &self,
-        uos: Vec<UserOperationVariant>,
-    ) -> SignatureAggregatorResult<Bytes> {
-        let uos = uos
-            .into_iter()
-            .map(|uo| {
-                if !uo.is_v0_7() {
-                    Err(SignatureAggregatorError::InvalidUserOperation(
-                        "User operation is not v0.7".to_string(),
-                    ))
-                } else {
-                    Ok(uo.into())
-                }
-            })
-            .collect::<SignatureAggregatorResult<Vec<UserOperation>>>()?;
-
+    async fn aggregate_on_chain(&self, uos: Vec<EP::UO>) -> SignatureAggregatorResult<Bytes> {
         match self
             .entry_point
             .aggregate_signatures(self.address, uos)
@@ -112,24 +355,345 @@ where
             Err(e) => Err(SignatureAggregatorError::ProviderError(e.to_string())),
         }
     }
+
+    /// `getUserOpPublicKey`, the public key is the last 128 bytes of the init code of
+    /// undeployed accounts, otherwise it is read from the account.
+    async fn public_key(&self, uo: &EP::UO) -> SignatureAggregatorResult<[U256; 4]> {
+        let init_code = uo.init_code();
+        if !init_code.is_empty() {
+            if init_code.len() <= BLS_PUBLIC_KEY_LENGTH {
+                return Err(SignatureAggregatorError::InvalidUserOperation(
+                    "init code too short for BLS public key".to_string(),
+                ));
+            }
+            let key = &init_code[init_code.len() - BLS_PUBLIC_KEY_LENGTH..];
+            return Ok(std::array::from_fn(|i| {
+                U256::from_be_slice(&key[i * 32..(i + 1) * 32])
+            }));
+        }
+
+        let tx = TransactionRequest::default()
+            .to(uo.sender())
+            .input(IBLSAccount::getBlsPublicKeyCall {}.abi_encode().into());
+        let ret = self
+            .provider
+            .call(&tx, None, &StateOverride::default())
+            .await
+            .map_err(|e| SignatureAggregatorError::ProviderError(e.to_string()))?;
+
+        IBLSAccount::getBlsPublicKeyCall::abi_decode_returns(&ret, true)
+            .map(|ret| ret._0)
+            .map_err(|e| {
+                SignatureAggregatorError::InvalidUserOperation(format!(
+                    "failed to decode BLS public key of sender: {e}"
+                ))
+            })
+    }
+
+    /// `_userOpToMessage`, hashes the user operation with its public key to a G1 point
+    fn message(&self, uo: &EP::UO, public_key: [U256; 4]) -> ark_bn254::G1Affine {
+        let hash = uo.bls_hash(keccak256(public_key.abi_encode()), self.address);
+        bn254::hash_to_point(keccak256(BLS_DOMAIN), hash.as_slice())
+    }
 }
 
-impl<EP> BlsSignatureAggregatorV0_7<EP> {
-    /// Create a new BLS signature aggregator
-    pub fn new(entry_point: EP, address_override: Option<Address>) -> Self {
-        let address = address_override.unwrap_or(BLS_AGGREGATOR_ADDRESS);
+/// `aggregateSignatures`, sums the signatures of the user operations
+fn aggregate_native<UO: UserOperation>(uos: &[UO]) -> SignatureAggregatorResult<Bytes> {
+    let signatures = uos
+        .iter()
+        .map(|uo| decode_signature(uo.signature()))
+        .collect::<SignatureAggregatorResult<Vec<_>>>()?;
 
-        Self {
-            entry_point,
-            address,
-        }
+    Ok(bn254::encode_g1(bn254::aggregate(signatures))
+        .abi_encode()
+        .into())
+}
+
+fn check_version<UO: BlsUserOperation>(uo: UserOperationVariant) -> SignatureAggregatorResult<UO> {
+    if uo.uo_type() != UO::entry_point_version() {
+        return Err(SignatureAggregatorError::InvalidUserOperation(format!(
+            "User operation is not {:?}",
+            UO::entry_point_version()
+        )));
     }
+    Ok(uo.into())
 }
 
-impl<EP> Debug for BlsSignatureAggregatorV0_7<EP> {
+fn decode_signature(signature: &Bytes) -> SignatureAggregatorResult<ark_bn254::G1Affine> {
+    if signature.len() < BLS_SIGNATURE_LENGTH {
+        return Err(SignatureAggregatorError::InvalidUserOperation(format!(
+            "BLS signature is not the correct length: {} < {}",
+            signature.len(),
+            BLS_SIGNATURE_LENGTH
+        )));
+    }
+
+    let point = [
+        U256::from_be_slice(&signature[..32]),
+        U256::from_be_slice(&signature[32..64]),
+    ];
+    bn254::decode_g1(point).ok_or_else(|| {
+        SignatureAggregatorError::InvalidUserOperation(
+            "BLS signature is not a valid point".to_string(),
+        )
+    })
+}
+
+impl<EP, P> Debug for BlsSignatureAggregator<EP, P> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("BlsSignatureAggregator")
             .field("address", &self.address)
+            .field("mode", &self.mode)
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{b256, uint};
+    use ark_bn254::{Fr, G2Affine};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use rundler_provider::{MockEntryPointV0_6, MockEntryPointV0_7, MockEvmProvider};
+    use rundler_types::{
+        chain::ChainSpec,
+        v0_7::{UserOperationBuilder, UserOperationRequiredFields},
+    };
+
+    use super::*;
+
+    fn aggregator(
+        entry_point: MockEntryPointV0_7,
+        mode: BlsAggregationMode,
+    ) -> BlsSignatureAggregator<MockEntryPointV0_7, MockEvmProvider> {
+        BlsSignatureAggregator::new(entry_point, MockEvmProvider::default(), None, mode)
+    }
+
+    fn required_fields(nonce: u64, signature: Bytes) -> UserOperationRequiredFields {
+        UserOperationRequiredFields {
+            sender: Address::repeat_byte(1),
+            nonce: U256::from(nonce),
+            call_data: Bytes::new(),
+            signature,
+            call_gas_limit: 0,
+            verification_gas_limit: 0,
+            pre_verification_gas: 0,
+            max_fee_per_gas: 0,
+            max_priority_fee_per_gas: 0,
+        }
+    }
+
+    /// Builds an undeployed account's user operation with the public key of `secret`,
+    /// signed by `signer`
+    fn signed_op(
+        aggregator: &BlsSignatureAggregator<MockEntryPointV0_7, MockEvmProvider>,
+        nonce: u64,
+        secret: u64,
+        signer: u64,
+    ) -> (v0_7::UserOperation, ark_bn254::G1Affine) {
+        let chain_spec = ChainSpec::default();
+        let public_key = bn254::encode_g2((G2Affine::generator() * Fr::from(secret)).into_affine());
+        let factory_data: Bytes = [&[0u8; 4][..], &public_key.abi_encode()].concat().into();
+
+        let unsigned = UserOperationBuilder::new(&chain_spec, required_fields(nonce, Bytes::new()))
+            .factory(Address::repeat_byte(2), factory_data.clone())
+            .build();
+        let message = aggregator.message(&unsigned, public_key);
+        let signature = (message * Fr::from(signer)).into_affine();
+
+        let uo = UserOperationBuilder::new(
+            &chain_spec,
+            required_fields(nonce, bn254::encode_g1(signature).abi_encode().into()),
+        )
+        .factory(Address::repeat_byte(2), factory_data)
+        .build();
+        (uo, signature)
+    }
+
+    #[tokio::test]
+    async fn test_validate_native() {
+        let aggregator = aggregator(MockEntryPointV0_7::new(), BlsAggregationMode::Native);
+
+        let (uo, _) = signed_op(&aggregator, 0, 1234, 1234);
+        let ret = aggregator
+            .validate_user_op_signature(&uo.into())
+            .await
+            .unwrap();
+        assert!(ret.is_empty());
+
+        let (uo, _) = signed_op(&aggregator, 0, 1234, 5678);
+        let ret = aggregator.validate_user_op_signature(&uo.into()).await;
+        assert!(matches!(
+            ret,
+            Err(SignatureAggregatorError::ValidationReverted(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_native() {
+        let aggregator = aggregator(MockEntryPointV0_7::new(), BlsAggregationMode::Native);
+
+        let (uo0, sig0) = signed_op(&aggregator, 0, 11, 11);
+        let (uo1, sig1) = signed_op(&aggregator, 1, 22, 22);
+        let ret = aggregator
+            .aggregate_signatures(vec![uo0.into(), uo1.into()])
+            .await
+            .unwrap();
+
+        let expected = bn254::encode_g1((sig0 + sig1).into_affine());
+        assert_eq!(ret, Bytes::from(expected.abi_encode()));
+    }
+
+    #[tokio::test]
+    async fn test_cross_check_returns_on_chain_result() {
+        let mut entry_point = MockEntryPointV0_7::new();
+        entry_point
+            .expect_validate_user_op_signature()
+            .times(1)
+            .returning(|_, _| Ok(AggregatorOut::ValidationReverted(Bytes::new())));
+        let aggregator = aggregator(entry_point, BlsAggregationMode::CrossCheck);
+
+        let (uo, _) = signed_op(&aggregator, 0, 1234, 1234);
+        let ret = aggregator.validate_user_op_signature(&uo.into()).await;
+        assert!(matches!(
+            ret,
+            Err(SignatureAggregatorError::ValidationReverted(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_validate_native_v0_6() {
+        let aggregator = BlsSignatureAggregator::new(
+            MockEntryPointV0_6::new(),
+            MockEvmProvider::default(),
+            Some(Address::repeat_byte(3)),
+            BlsAggregationMode::Native,
+        );
+        let public_key =
+            bn254::encode_g2((G2Affine::generator() * Fr::from(1234u64)).into_affine());
+        let op = |signature: Bytes| {
+            v0_6::UserOperationBuilder::new(
+                &ChainSpec::default(),
+                v0_6::UserOperationRequiredFields {
+                    sender: Address::repeat_byte(1),
+                    init_code: [&[2u8; 24][..], &public_key.abi_encode()].concat().into(),
+                    signature,
+                    ..Default::default()
+                },
+            )
+            .build()
+        };
+        let sign = |secret: u64| {
+            let message = aggregator.message(&op(Bytes::new()), public_key);
+            op(bn254::encode_g1((message * Fr::from(secret)).into_affine())
+                .abi_encode()
+                .into())
+        };
+
+        let ret = aggregator
+            .validate_user_op_signature(&sign(1234).into())
+            .await
+            .unwrap();
+        assert!(ret.is_empty());
+
+        let ret = aggregator
+            .validate_user_op_signature(&sign(5678).into())
+            .await;
+        assert!(matches!(
+            ret,
+            Err(SignatureAggregatorError::ValidationReverted(_))
+        ));
+
+        // v0.7 user operations are rejected
+        let ret = aggregator
+            .validate_user_op_signature(&v0_7::UserOperation::default().into())
+            .await;
+        assert!(matches!(
+            ret,
+            Err(SignatureAggregatorError::InvalidUserOperation(_))
+        ));
+    }
+
+    /// Known answers from a transcription of `BLSSignatureAggregator._getUserOpHash` and
+    /// `BLS.sol` in plain modular arithmetic, independent of arkworks
+    #[tokio::test]
+    async fn test_known_answer_v0_7() {
+        let chain_spec = ChainSpec {
+            id: 1,
+            ..Default::default()
+        };
+        let public_key = [
+            uint!(0x19821845c148be14e877499bdc06823062bcab4393e080581f02c339e81ff504_U256),
+            uint!(0x2d6479d0ad029e95c2bb5e856ac411e6f4ba0e5f6b0347a0f7801af53f3fe185_U256),
+            uint!(0x1cccdbc0113136f01e43d326e97485b7a654ae21f33c76a229c14b4520c0aa3a_U256),
+            uint!(0x03d3a3e7ce9bbafe7a67848c85d35aa8b2ed20f77e4b058e7fd39471ae6cd5fe_U256),
+        ];
+        let signature = [
+            uint!(0x13d6b750d392591e3ac02a03aba803dfbeb60c753c1e7c50731affeaac37e560_U256),
+            uint!(0x29d0703ef78deeec9b9cb9f7232c19a8090c7486eb311586bd8e3badcf0e1298_U256),
+        ];
+        let factory_data: Bytes = [&bytes!("5fbfb9cf")[..], &public_key.abi_encode()]
+            .concat()
+            .into();
+        let op = |signature: Bytes| {
+            UserOperationBuilder::new(
+                &chain_spec,
+                UserOperationRequiredFields {
+                    sender: Address::repeat_byte(0x11),
+                    nonce: U256::from(7),
+                    call_data: bytes!("deadbeef"),
+                    signature,
+                    call_gas_limit: 200_000,
+                    verification_gas_limit: 100_000,
+                    pre_verification_gas: 50_000,
+                    max_fee_per_gas: 3_000_000_000,
+                    max_priority_fee_per_gas: 1_000_000_000,
+                },
+            )
+            .factory(Address::repeat_byte(0x22), factory_data.clone())
+            .build()
+        };
+        let aggregator = aggregator(MockEntryPointV0_7::new(), BlsAggregationMode::Native);
+
+        let uo = op(Bytes::new());
+        assert_eq!(
+            keccak256(public_key.abi_encode()),
+            b256!("c6fcadc2d18b72baaa682d7ca5e7125c5517291041768f38c05cf4963d0e1354")
+        );
+        assert_eq!(
+            uo.bls_hash(keccak256(public_key.abi_encode()), aggregator.address),
+            b256!("b3f53f38657d4745867f168f7b2312271551bc6641dc2b28852f570b64365533")
+        );
+        assert_eq!(
+            bn254::encode_g1(aggregator.message(&uo, public_key)),
+            [
+                uint!(0x2ed903999335b9fdb7abcc7c7655f8fe62b0ae62b16edd76e103758ba92ccd93_U256),
+                uint!(0x2b875c1eb36eba60827194444b14acdaa9c94ba3b569eda69171e96c3632d0d7_U256),
+            ]
+        );
+
+        let ret = aggregator
+            .validate_user_op_signature(&op(signature.abi_encode().into()).into())
+            .await
+            .unwrap();
+        assert!(ret.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_v0_6_requires_address() {
+        BlsSignatureAggregator::new(
+            MockEntryPointV0_6::new(),
+            MockEvmProvider::default(),
+            None,
+            BlsAggregationMode::Native,
+        );
+    }
+
+    #[test]
+    fn test_mode_from_str() {
+        assert_eq!("native".parse(), Ok(BlsAggregationMode::Native));
+        assert_eq!("ONCHAIN".parse(), Ok(BlsAggregationMode::OnChain));
+        assert_eq!("CrossCheck".parse(), Ok(BlsAggregationMode::CrossCheck));
+        assert!("other".parse::<BlsAggregationMode>().is_err());
+    }
+}
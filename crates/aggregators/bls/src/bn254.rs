@@ -0,0 +1,295 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+//! BN254 BLS signatures as implemented by the `BLS.sol` library used by the
+//! eth-infinitism `BLSSignatureAggregator`.
+//!
+//! Signatures and messages are G1 points, public keys are G2 points. Points are encoded
+//! the same way as the EIP-196/197 precompiles, with `(0, 0)` as the point at infinity.
+
+use alloy_primitives::{uint, B256, U256};
+use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G1Projective, G2Affine};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{BigInt, Field, One, PrimeField, Zero};
+use sha2::{Digest, Sha256};
+
+/// sqrt(-3) mod p
+const Z0: U256 = uint!(0x0000000000000000b3c4d79d41a91759a9e4c7e359b6b89eaec68e62effffffd_U256);
+/// (sqrt(-3) - 1) / 2 mod p
+const Z1: U256 = uint!(0x000000000000000059e26bcea0d48bacd4f263f1acdb5c4f5763473177fffffe_U256);
+/// (p + 1) / 4, the exponent of a square root in a field where p = 3 mod 4
+const SQRT_EXP: U256 =
+    uint!(0x0c19139cb84c680a6e14116da060561765e05aa45a1c72a34f082305b61f3f52_U256);
+
+/// Hashes a message to a G1 point, `BLS.hashToPoint`
+pub(crate) fn hash_to_point(domain: B256, message: &[u8]) -> G1Affine {
+    let [u0, u1] = hash_to_field(domain, message);
+    (map_to_point(u0) + map_to_point(u1)).into_affine()
+}
+
+/// Decodes a G1 point, returns `None` if it is not on the curve
+pub(crate) fn decode_g1(point: [U256; 2]) -> Option<G1Affine> {
+    if point == [U256::ZERO; 2] {
+        return Some(G1Affine::identity());
+    }
+
+    let p = G1Affine::new_unchecked(fq(point[0])?, fq(point[1])?);
+    p.is_on_curve().then_some(p)
+}
+
+/// Encodes a G1 point
+pub(crate) fn encode_g1(point: G1Affine) -> [U256; 2] {
+    match point.xy() {
+        Some((x, y)) => [from_fq(*x), from_fq(*y)],
+        None => [U256::ZERO; 2],
+    }
+}
+
+/// Decodes a G2 public key `[x.c0, x.c1, y.c0, y.c1]`, returns `None` if it is not on the
+/// curve or not in the prime order subgroup
+pub(crate) fn decode_g2(point: [U256; 4]) -> Option<G2Affine> {
+    if point == [U256::ZERO; 4] {
+        return Some(G2Affine::identity());
+    }
+
+    let p = G2Affine::new_unchecked(
+        Fq2::new(fq(point[0])?, fq(point[1])?),
+        Fq2::new(fq(point[2])?, fq(point[3])?),
+    );
+    (p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve()).then_some(p)
+}
+
+/// Encodes a G2 point as `[x.c0, x.c1, y.c0, y.c1]`
+#[cfg(test)]
+pub(crate) fn encode_g2(point: G2Affine) -> [U256; 4] {
+    match point.xy() {
+        Some((x, y)) => [from_fq(x.c0), from_fq(x.c1), from_fq(y.c0), from_fq(y.c1)],
+        None => [U256::ZERO; 4],
+    }
+}
+
+/// Sums signatures into an aggregated signature
+pub(crate) fn aggregate(signatures: impl IntoIterator<Item = G1Affine>) -> G1Affine {
+    signatures
+        .into_iter()
+        .fold(G1Projective::zero(), |sum, s| sum + s)
+        .into_affine()
+}
+
+/// Verifies an aggregated signature over a message for each public key, `BLS.verifyMultiple`
+pub(crate) fn verify_multiple(
+    signature: G1Affine,
+    public_keys: &[G2Affine],
+    messages: &[G1Affine],
+) -> bool {
+    if public_keys.len() != messages.len() {
+        return false;
+    }
+
+    // e(signature, -g2) * prod(e(message_i, public_key_i)) == 1
+    let g1 = std::iter::once(signature).chain(messages.iter().copied());
+    let g2 = std::iter::once(-G2Affine::generator()).chain(public_keys.iter().copied());
+    Bn254::multi_pairing(g1, g2).is_zero()
+}
+
+/// `BLS.hashToField`, reduces 48 byte chunks of the expanded message
+fn hash_to_field(domain: B256, message: &[u8]) -> [Fq; 2] {
+    let expanded = expand_msg_to_96(domain, message);
+    [
+        Fq::from_be_bytes_mod_order(&expanded[..48]),
+        Fq::from_be_bytes_mod_order(&expanded[48..]),
+    ]
+}
+
+/// `BLS.expandMsgTo96`, expand_message_xmd with SHA-256 and a 32 byte domain
+fn expand_msg_to_96(domain: B256, message: &[u8]) -> [u8; 96] {
+    const LEN_IN_BYTES: [u8; 2] = [0, 96];
+    const DST_LEN: u8 = 32;
+
+    let mut hasher = Sha256::new();
+    hasher.update([0u8; 64]);
+    hasher.update(message);
+    hasher.update(LEN_IN_BYTES);
+    hasher.update([0]);
+    hasher.update(domain);
+    hasher.update([DST_LEN]);
+    let b0: [u8; 32] = hasher.finalize().into();
+
+    let mut out = [0u8; 96];
+    let mut bi = [0u8; 32];
+    for i in 0..3 {
+        let mut input = b0;
+        if i > 0 {
+            input.iter_mut().zip(bi).for_each(|(a, b)| *a ^= b);
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(input);
+        hasher.update([i as u8 + 1]);
+        hasher.update(domain);
+        hasher.update([DST_LEN]);
+        bi = hasher.finalize().into();
+        out[i * 32..(i + 1) * 32].copy_from_slice(&bi);
+    }
+    out
+}
+
+/// `BLS.mapToPoint`, the Fouque-Tibouchi map to the curve y^2 = x^3 + 3
+fn map_to_point(t: Fq) -> G1Affine {
+    let z0 = fq(Z0).expect("Z0 should be a field element");
+    let z1 = fq(Z1).expect("Z1 should be a field element");
+    let decision = sqrt(t).is_some();
+
+    let a0 = t.square() + Fq::from(4u64);
+    let mut a1 = t * z0;
+    // inverse with modexp, i.e. zero for zero
+    let a2 = (a1 * a0).inverse().unwrap_or_default();
+    a1 = a1.square() * a2;
+    a1 *= t;
+
+    let x1 = z1 - a1;
+    let x2 = -(x1 + Fq::one());
+    let x3 = a0.square().square() * a2.square() + Fq::one();
+
+    for x in [x1, x2, x3] {
+        if let Some(y) = sqrt(x * x.square() + Fq::from(3u64)) {
+            let y = if decision { y } else { -y };
+            return G1Affine::new_unchecked(x, y);
+        }
+    }
+    // one of x1, x2 or x3 is always on the curve
+    unreachable!("BLS: bad ft mapping implementation")
+}
+
+/// Square root with modexp, the same root as `BLS.sqrt`
+fn sqrt(a: Fq) -> Option<Fq> {
+    let root = a.pow(SQRT_EXP.into_limbs());
+    (root.square() == a).then_some(root)
+}
+
+fn fq(value: U256) -> Option<Fq> {
+    Fq::from_bigint(BigInt::new(value.into_limbs()))
+}
+
+fn from_fq(value: Fq) -> U256 {
+    U256::from_limbs(value.into_bigint().0)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{keccak256, uint};
+    use ark_bn254::Fr;
+
+    use super::*;
+
+    fn domain() -> B256 {
+        keccak256("eip4337.bls.domain")
+    }
+
+    fn key_pair(secret: u64) -> (Fr, G2Affine) {
+        let secret = Fr::from(secret);
+        (secret, (G2Affine::generator() * secret).into_affine())
+    }
+
+    fn sign(secret: Fr, message: G1Affine) -> G1Affine {
+        (message * secret).into_affine()
+    }
+
+    #[test]
+    fn test_hash_to_point_on_curve() {
+        for i in 0..32u8 {
+            let point = hash_to_point(domain(), &[i; 32]);
+            assert!(point.is_on_curve());
+            assert!(!point.is_zero());
+        }
+        assert_eq!(
+            hash_to_point(domain(), b"message"),
+            hash_to_point(domain(), b"message")
+        );
+        assert_ne!(
+            hash_to_point(domain(), b"message"),
+            hash_to_point(B256::ZERO, b"message")
+        );
+    }
+
+    /// Known answers from a transcription of `BLS.hashToPoint` in plain modular arithmetic,
+    /// independent of arkworks
+    #[test]
+    fn test_hash_to_point_known_answer() {
+        assert_eq!(
+            encode_g1(hash_to_point(domain(), &[0; 32])),
+            [
+                uint!(0x278a66849330b585543898c68a5cf513e299b9da1addad933efd4e6dd85bf33b_U256),
+                uint!(0x0fd07ab98a8ac6277e311d78c2a4b62f6b03564c28cc050bd9cc4967f4cc11d1_U256),
+            ]
+        );
+        assert_eq!(
+            encode_g1(hash_to_point(domain(), b"message")),
+            [
+                uint!(0x11e890cb49fd9fa968b60697e50381603bc9fc735b718161b35d6f4fbbe1c1f8_U256),
+                uint!(0x2c9d2df31c6d2cc806848e109333f515f22026885286f405118f68478112c7ff_U256),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_single() {
+        let (secret, public_key) = key_pair(1234);
+        let message = hash_to_point(domain(), b"message");
+        let signature = sign(secret, message);
+
+        assert!(verify_multiple(signature, &[public_key], &[message]));
+
+        let other = hash_to_point(domain(), b"other message");
+        assert!(!verify_multiple(signature, &[public_key], &[other]));
+        let (_, other_key) = key_pair(5678);
+        assert!(!verify_multiple(signature, &[other_key], &[message]));
+    }
+
+    #[test]
+    fn test_verify_aggregated() {
+        let keys = [key_pair(1), key_pair(22), key_pair(333)];
+        let messages = [b"a", b"b", b"c"].map(|m| hash_to_point(domain(), m));
+        let signatures = keys
+            .iter()
+            .zip(messages)
+            .map(|((secret, _), message)| sign(*secret, message))
+            .collect::<Vec<_>>();
+        let public_keys = keys.map(|(_, public_key)| public_key);
+
+        let signature = aggregate(signatures.clone());
+        assert!(verify_multiple(signature, &public_keys, &messages));
+
+        // missing a signature
+        let signature = aggregate(signatures[..2].iter().copied());
+        assert!(!verify_multiple(signature, &public_keys, &messages));
+    }
+
+    #[test]
+    fn test_encoding_round_trip() {
+        let (secret, public_key) = key_pair(42);
+        let signature = sign(secret, hash_to_point(domain(), b"message"));
+
+        assert_eq!(decode_g1(encode_g1(signature)), Some(signature));
+        assert_eq!(decode_g2(encode_g2(public_key)), Some(public_key));
+        assert_eq!(decode_g1([U256::ZERO; 2]), Some(G1Affine::identity()));
+
+        // not on the curve
+        let [x, y] = encode_g1(signature);
+        assert_eq!(decode_g1([x, y + U256::from(1)]), None);
+        let [x0, x1, y0, y1] = encode_g2(public_key);
+        assert_eq!(decode_g2([x0, x1, y0, y1 + U256::from(1)]), None);
+        // not a field element
+        assert_eq!(decode_g1([U256::MAX, y]), None);
+    }
+}
@@ -23,4 +23,6 @@
 //! Contracts found here: https://github.com/eth-infinitism/account-abstraction-samples/tree/master/contracts/bls
 
 mod bls;
-pub use bls::BlsSignatureAggregatorV0_7;
+pub use bls::{BlsAggregationMode, BlsSignatureAggregator, BlsUserOperation};
+
+mod bn254;
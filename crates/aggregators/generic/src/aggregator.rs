@@ -0,0 +1,492 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::{fmt::Debug, marker::PhantomData, str::FromStr};
+
+use alloy_json_abi::Function;
+use alloy_primitives::{Address, Bytes, Selector};
+use alloy_sol_types::{SolCall, SolValue};
+use rundler_provider::{EvmProvider, ProviderError, StateOverride, TransactionRequest};
+use rundler_types::{
+    aggregator::{
+        AggregatorCosts, SignatureAggregator, SignatureAggregatorError, SignatureAggregatorResult,
+    },
+    v0_6, v0_7, v0_8, EntryPointVersion, UserOperation, UserOperationVariant,
+};
+use serde::Deserialize;
+
+use crate::DefinitionError;
+
+/// Definition of a signature aggregator
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureAggregatorDefinition {
+    /// Onchain address of the aggregator
+    pub address: Address,
+    /// Version of the entry point the aggregator is used with
+    pub entry_point_version: EntryPointVersion,
+    /// Costs of the aggregator, charged via pre-verification gas
+    pub costs: AggregatorCosts,
+    /// Signature used in place of the user operation's signature during gas estimation
+    pub dummy_signature: Bytes,
+    /// Function that validates the signature of a user operation, defaults to
+    /// `IAggregator.validateUserOpSignature`
+    pub validate_signature_function: Option<String>,
+    /// Function that aggregates the signatures of user operations, defaults to
+    /// `IAggregator.aggregateSignatures`
+    pub aggregate_signatures_function: Option<String>,
+}
+
+/// User operation that can be passed to the `IAggregator` functions of its entry point version
+pub trait AggregatorUserOperation: UserOperation + From<UserOperationVariant> {
+    /// `validateUserOpSignature` call
+    type ValidateCall: SolCall + Send;
+    /// `aggregateSignatures` call
+    type AggregateCall: SolCall + Send;
+
+    /// Returns the `validateUserOpSignature` call for the user operation
+    fn validate_call(self) -> Self::ValidateCall;
+
+    /// Returns the `aggregateSignatures` call for the user operations
+    fn aggregate_call(uos: Vec<Self>) -> Self::AggregateCall;
+}
+
+impl AggregatorUserOperation for v0_6::UserOperation {
+    type ValidateCall = rundler_contracts::v0_6::IAggregator::validateUserOpSignatureCall;
+    type AggregateCall = rundler_contracts::v0_6::IAggregator::aggregateSignaturesCall;
+
+    fn validate_call(self) -> Self::ValidateCall {
+        Self::ValidateCall {
+            userOp: self.into(),
+        }
+    }
+
+    fn aggregate_call(uos: Vec<Self>) -> Self::AggregateCall {
+        Self::AggregateCall {
+            userOps: uos.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl AggregatorUserOperation for v0_7::UserOperation {
+    type ValidateCall = rundler_contracts::v0_7::IAggregator::validateUserOpSignatureCall;
+    type AggregateCall = rundler_contracts::v0_7::IAggregator::aggregateSignaturesCall;
+
+    fn validate_call(self) -> Self::ValidateCall {
+        Self::ValidateCall {
+            userOp: self.pack(),
+        }
+    }
+
+    fn aggregate_call(uos: Vec<Self>) -> Self::AggregateCall {
+        Self::AggregateCall {
+            userOps: uos.into_iter().map(|uo| uo.pack()).collect(),
+        }
+    }
+}
+
+impl AggregatorUserOperation for v0_8::UserOperation {
+    type ValidateCall = rundler_contracts::v0_8::IAggregator::validateUserOpSignatureCall;
+    type AggregateCall = rundler_contracts::v0_8::IAggregator::aggregateSignaturesCall;
+
+    fn validate_call(self) -> Self::ValidateCall {
+        Self::ValidateCall {
+            userOp: self.pack(),
+        }
+    }
+
+    fn aggregate_call(uos: Vec<Self>) -> Self::AggregateCall {
+        Self::AggregateCall {
+            userOps: uos.into_iter().map(|uo| uo.pack()).collect(),
+        }
+    }
+}
+
+/// Signature aggregator that validates and aggregates signatures with `eth_call`s to the
+/// functions of the aggregator contract set in its definition
+pub struct GenericSignatureAggregator<UO, P> {
+    provider: P,
+    definition: SignatureAggregatorDefinition,
+    validate_selector: Selector,
+    aggregate_selector: Selector,
+    _uo: PhantomData<fn() -> UO>,
+}
+
+impl<UO, P: Clone> Clone for GenericSignatureAggregator<UO, P> {
+    fn clone(&self) -> Self {
+        Self {
+            provider: self.provider.clone(),
+            definition: self.definition.clone(),
+            validate_selector: self.validate_selector,
+            aggregate_selector: self.aggregate_selector,
+            _uo: PhantomData,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<UO, P> SignatureAggregator for GenericSignatureAggregator<UO, P>
+where
+    UO: AggregatorUserOperation,
+    P: EvmProvider,
+{
+    fn address(&self) -> Address {
+        self.definition.address
+    }
+
+    fn costs(&self) -> &AggregatorCosts {
+        &self.definition.costs
+    }
+
+    fn dummy_uo_signature(&self) -> &Bytes {
+        &self.definition.dummy_signature
+    }
+
+    async fn validate_user_op_signature(
+        &self,
+        user_op: &UserOperationVariant,
+    ) -> SignatureAggregatorResult<Bytes> {
+        let uo = self.check_version(user_op.clone())?;
+        match self
+            .call(self.validate_selector, uo.validate_call())
+            .await?
+        {
+            Ok(signature) => Ok(signature),
+            Err(revert) => Err(SignatureAggregatorError::ValidationReverted(revert)),
+        }
+    }
+
+    async fn aggregate_signatures(
+        &self,
+        uos: Vec<UserOperationVariant>,
+    ) -> SignatureAggregatorResult<Bytes> {
+        let uos = uos
+            .into_iter()
+            .map(|uo| self.check_version(uo))
+            .collect::<SignatureAggregatorResult<Vec<_>>>()?;
+
+        match self
+            .call(self.aggregate_selector, UO::aggregate_call(uos))
+            .await?
+        {
+            Ok(signature) => Ok(signature),
+            Err(revert) => Err(SignatureAggregatorError::ProviderError(format!(
+                "aggregator {} reverted aggregating signatures: {revert}",
+                self.definition.address
+            ))),
+        }
+    }
+}
+
+impl<UO, P> GenericSignatureAggregator<UO, P>
+where
+    UO: AggregatorUserOperation,
+    P: EvmProvider,
+{
+    /// Create a new signature aggregator from its definition
+    ///
+    /// Fails if the definition is not for the entry point version of `UO`, or if its functions
+    /// don't take the arguments of the `IAggregator` functions and return `bytes`.
+    pub fn new(
+        provider: P,
+        definition: SignatureAggregatorDefinition,
+    ) -> Result<Self, DefinitionError> {
+        if UO::entry_point_version() != definition.entry_point_version {
+            return Err(DefinitionError::EntryPointVersionMismatch(
+                definition.address,
+                definition.entry_point_version,
+                UO::entry_point_version(),
+            ));
+        }
+
+        let validate_selector = compile_function::<UO::ValidateCall>(
+            definition.validate_signature_function.as_deref(),
+        )?;
+        let aggregate_selector = compile_function::<UO::AggregateCall>(
+            definition.aggregate_signatures_function.as_deref(),
+        )?;
+
+        Ok(Self {
+            provider,
+            definition,
+            validate_selector,
+            aggregate_selector,
+            _uo: PhantomData,
+        })
+    }
+
+    fn check_version(&self, uo: UserOperationVariant) -> SignatureAggregatorResult<UO> {
+        if uo.uo_type() != self.definition.entry_point_version {
+            return Err(SignatureAggregatorError::InvalidUserOperation(format!(
+                "User operation is not {:?}",
+                self.definition.entry_point_version
+            )));
+        }
+        Ok(uo.into())
+    }
+
+    /// Calls the aggregator with the arguments of `call` and the function `selector`,
+    /// returning the `bytes` it returns, or its revert data
+    async fn call(
+        &self,
+        selector: Selector,
+        call: impl SolCall + Send,
+    ) -> SignatureAggregatorResult<Result<Bytes, Bytes>> {
+        let mut input = call.abi_encode();
+        input[..4].copy_from_slice(selector.as_slice());
+        let tx = TransactionRequest::default()
+            .to(self.definition.address)
+            .input(input.into());
+
+        let ret = match self
+            .provider
+            .call(&tx, None, &StateOverride::default())
+            .await
+        {
+            Ok(ret) => ret,
+            Err(ProviderError::RPC(e)) => {
+                match e.as_error_resp().and_then(|resp| resp.as_revert_data()) {
+                    Some(revert) => return Ok(Err(revert)),
+                    None => return Err(SignatureAggregatorError::ProviderError(e.to_string())),
+                }
+            }
+            Err(e) => return Err(SignatureAggregatorError::ProviderError(e.to_string())),
+        };
+
+        Bytes::abi_decode(&ret, true).map(Ok).map_err(|e| {
+            SignatureAggregatorError::ProviderError(format!(
+                "failed to decode bytes returned by aggregator {}: {e}",
+                self.definition.address
+            ))
+        })
+    }
+}
+
+impl<UO, P> Debug for GenericSignatureAggregator<UO, P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GenericSignatureAggregator")
+            .field("address", &self.definition.address)
+            .field("entry_point_version", &self.definition.entry_point_version)
+            .field("validate_selector", &self.validate_selector)
+            .field("aggregate_selector", &self.aggregate_selector)
+            .finish()
+    }
+}
+
+/// Returns the selector of a configured function, either a 4 byte selector or a Solidity
+/// function signature, that must take the arguments of the `IAggregator` function `C` and
+/// return `bytes`. Defaults to the selector of `C`.
+fn compile_function<C: SolCall>(function: Option<&str>) -> Result<Selector, DefinitionError> {
+    let Some(function) = function else {
+        return Ok(C::SELECTOR.into());
+    };
+    if let Ok(selector) = Selector::from_str(function) {
+        return Ok(selector);
+    }
+
+    let parsed = Function::parse(function)
+        .map_err(|e| DefinitionError::InvalidSignature(function.to_string(), e.to_string()))?;
+
+    let signature = parsed.signature();
+    let expected_inputs = &C::SIGNATURE[C::SIGNATURE.find('(').unwrap_or_default()..];
+    let inputs = &signature[signature.find('(').unwrap_or_default()..];
+    let returns_bytes = parsed.outputs.len() == 1 && parsed.outputs[0].selector_type() == "bytes";
+    if inputs != expected_inputs || !returns_bytes {
+        return Err(DefinitionError::InvalidFunction(
+            function.to_string(),
+            format!("{expected_inputs} returns (bytes)"),
+        ));
+    }
+
+    Ok(parsed.selector())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, bytes};
+    use rundler_provider::MockEvmProvider;
+
+    use super::*;
+
+    const AGGREGATOR: Address = address!("0000000000000000000000000000000000000abc");
+
+    fn definition() -> SignatureAggregatorDefinition {
+        serde_json::from_str(
+            r#"{
+                "address": "0x0000000000000000000000000000000000000abc",
+                "entryPointVersion": "v0.7",
+                "costs": {
+                    "executionFixedGas": 100000,
+                    "executionVariableGas": 20000,
+                    "sigFixedLength": 64,
+                    "sigVariableLength": 0
+                },
+                "dummySignature": "0xffff"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn expect_call(provider: &mut MockEvmProvider, selector: Selector, ret: Bytes) {
+        provider
+            .expect_call()
+            .withf(move |tx, _, _| {
+                tx.to == Some(AGGREGATOR.into())
+                    && tx
+                        .input
+                        .input()
+                        .is_some_and(|input| input.starts_with(selector.as_slice()))
+            })
+            .returning(move |_, _, _| Ok(ret.abi_encode().into()));
+    }
+
+    #[test]
+    fn test_deserialize_definition() {
+        let definition = definition();
+        assert_eq!(definition.address, AGGREGATOR);
+        assert_eq!(definition.entry_point_version, EntryPointVersion::V0_7);
+        assert_eq!(
+            definition.costs,
+            AggregatorCosts {
+                execution_fixed_gas: 100_000,
+                execution_variable_gas: 20_000,
+                sig_fixed_length: 64,
+                sig_variable_length: 0,
+            }
+        );
+        assert_eq!(definition.dummy_signature, bytes!("ffff"));
+        assert_eq!(definition.validate_signature_function, None);
+        assert_eq!(definition.aggregate_signatures_function, None);
+    }
+
+    #[tokio::test]
+    async fn test_validate_and_aggregate() {
+        let mut provider = MockEvmProvider::default();
+        expect_call(
+            &mut provider,
+            rundler_contracts::v0_7::IAggregator::validateUserOpSignatureCall::SELECTOR.into(),
+            bytes!("1234"),
+        );
+        expect_call(
+            &mut provider,
+            rundler_contracts::v0_7::IAggregator::aggregateSignaturesCall::SELECTOR.into(),
+            bytes!("5678"),
+        );
+        let aggregator =
+            GenericSignatureAggregator::<v0_7::UserOperation, _>::new(provider, definition())
+                .unwrap();
+
+        let uo: UserOperationVariant = v0_7::UserOperation::default().into();
+        assert_eq!(
+            aggregator.validate_user_op_signature(&uo).await.unwrap(),
+            bytes!("1234")
+        );
+        assert_eq!(
+            aggregator
+                .aggregate_signatures(vec![uo.clone(), uo])
+                .await
+                .unwrap(),
+            bytes!("5678")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_configured_functions() {
+        let validate = "function validateSignature((address,uint256,bytes,bytes,bytes32,uint256,bytes32,bytes,bytes) userOp) external view returns (bytes)";
+        let validate_selector = Function::parse(validate).unwrap().selector();
+        let aggregate_selector = Selector::from_str("0x12345678").unwrap();
+
+        let mut provider = MockEvmProvider::default();
+        expect_call(&mut provider, validate_selector, bytes!("1234"));
+        expect_call(&mut provider, aggregate_selector, bytes!("5678"));
+        let aggregator = GenericSignatureAggregator::<v0_7::UserOperation, _>::new(
+            provider,
+            SignatureAggregatorDefinition {
+                validate_signature_function: Some(validate.to_string()),
+                aggregate_signatures_function: Some("0x12345678".to_string()),
+                ..definition()
+            },
+        )
+        .unwrap();
+
+        let uo: UserOperationVariant = v0_7::UserOperation::default().into();
+        assert_eq!(
+            aggregator.validate_user_op_signature(&uo).await.unwrap(),
+            bytes!("1234")
+        );
+        assert_eq!(
+            aggregator.aggregate_signatures(vec![uo]).await.unwrap(),
+            bytes!("5678")
+        );
+    }
+
+    #[test]
+    fn test_invalid_definition() {
+        let new = |definition| {
+            GenericSignatureAggregator::<v0_7::UserOperation, _>::new(
+                MockEvmProvider::default(),
+                definition,
+            )
+        };
+
+        assert!(matches!(
+            GenericSignatureAggregator::<v0_6::UserOperation, _>::new(
+                MockEvmProvider::default(),
+                definition()
+            ),
+            Err(DefinitionError::EntryPointVersionMismatch(..))
+        ));
+        assert!(matches!(
+            new(SignatureAggregatorDefinition {
+                validate_signature_function: Some("function validate(".to_string()),
+                ..definition()
+            }),
+            Err(DefinitionError::InvalidSignature(..))
+        ));
+        // v0.6 user operation argument
+        assert!(matches!(
+            new(SignatureAggregatorDefinition {
+                validate_signature_function: Some("function validate((address,uint256,bytes,bytes,uint256,uint256,uint256,uint256,uint256,bytes,bytes) userOp) returns (bytes)".to_string()),
+                ..definition()
+            }),
+            Err(DefinitionError::InvalidFunction(..))
+        ));
+        // no return value
+        assert!(matches!(
+            new(SignatureAggregatorDefinition {
+                aggregate_signatures_function: Some("function aggregate((address,uint256,bytes,bytes,bytes32,uint256,bytes32,bytes,bytes)[] userOps)".to_string()),
+                ..definition()
+            }),
+            Err(DefinitionError::InvalidFunction(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_version() {
+        let aggregator = GenericSignatureAggregator::<v0_7::UserOperation, _>::new(
+            MockEvmProvider::default(),
+            definition(),
+        )
+        .unwrap();
+
+        let uo: UserOperationVariant = v0_6::UserOperation::default().into();
+        assert!(matches!(
+            aggregator.validate_user_op_signature(&uo).await,
+            Err(SignatureAggregatorError::InvalidUserOperation(_))
+        ));
+        assert!(matches!(
+            aggregator.aggregate_signatures(vec![uo]).await,
+            Err(SignatureAggregatorError::InvalidUserOperation(_))
+        ));
+    }
+}
@@ -0,0 +1,36 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+#![warn(missing_docs, unreachable_pub, unused_crate_dependencies)]
+#![deny(unused_must_use, rust_2018_idioms)]
+#![doc(test(
+    no_crate_inject,
+    attr(deny(warnings, rust_2018_idioms), allow(dead_code, unused_variables))
+))]
+
+//! Config defined signature aggregators and submission proxies for Rundler
+//!
+//! Supports aggregators whose signatures are validated and aggregated by functions taking the
+//! arguments of the standard ERC-4337 `IAggregator` interface, and
+//! submission proxies whose reverts identify the failed user operation, without a
+//! dedicated implementation per contract.
+
+mod aggregator;
+pub use aggregator::{
+    AggregatorUserOperation, GenericSignatureAggregator, SignatureAggregatorDefinition,
+};
+
+mod proxy;
+pub use proxy::{
+    DefinitionError, GenericSubmissionProxy, OpIdentifier, RevertRule, SubmissionProxyDefinition,
+};
@@ -0,0 +1,396 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use alloy_dyn_abi::{DynSolType, JsonAbiExt, Specifier};
+use alloy_json_abi::Error;
+use alloy_primitives::{Address, Bytes, B256};
+use rundler_types::{
+    proxy::SubmissionProxy, EntryPointVersion, UserOperation as _, UserOperationVariant,
+    UserOpsPerAggregator,
+};
+use serde::Deserialize;
+
+/// Definition of a submission proxy
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmissionProxyDefinition {
+    /// Onchain address of the submission proxy
+    pub address: Address,
+    /// Rules to find the user operation that caused a revert of the proxy
+    #[serde(default)]
+    pub revert_rules: Vec<RevertRule>,
+}
+
+/// Rule to decode a custom error of a submission proxy
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevertRule {
+    /// Solidity signature of the error, i.e. `error InvalidNullifier(uint256 nullifierHash, bytes32 userOpHash)`
+    pub error: String,
+    /// Name of the error parameter that identifies the failed user operation
+    pub param: String,
+    /// How the parameter identifies the failed user operation
+    pub identifies: OpIdentifier,
+}
+
+/// How an error parameter identifies a user operation in a bundle
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OpIdentifier {
+    /// The user operation hash, a `bytes32` or `uint256`
+    UserOpHash,
+    /// The sender of the user operation, an `address`. All of the sender's user operations
+    /// in the bundle are rejected.
+    Sender,
+    /// Index of the user operation in the bundle across all aggregators, a `uint`
+    OpIndex,
+}
+
+/// Errors in a signature aggregator or submission proxy definition
+#[derive(Debug, thiserror::Error)]
+pub enum DefinitionError {
+    /// The error or function signature could not be parsed
+    #[error("invalid signature {0}: {1}")]
+    InvalidSignature(String, String),
+    /// The aggregator function doesn't take the arguments of its `IAggregator` function or
+    /// doesn't return `bytes`
+    #[error("aggregator function {0} should have arguments and return {1}")]
+    InvalidFunction(String, String),
+    /// The aggregator is defined for another entry point version
+    #[error("aggregator {0} is defined for entry point {1:?}, not {2:?}")]
+    EntryPointVersionMismatch(Address, EntryPointVersion, EntryPointVersion),
+    /// The error has no parameter with the given name
+    #[error("error {0} has no parameter {1}")]
+    UnknownParameter(String, String),
+    /// The parameter type can't identify a user operation
+    #[error("parameter {1} of error {0} of type {2} can't identify a user operation by {3:?}")]
+    InvalidParameterType(String, String, String, OpIdentifier),
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    error: Error,
+    param_index: usize,
+    identifies: OpIdentifier,
+}
+
+/// Submission proxy that decodes its reverts with the rules of its definition
+#[derive(Debug)]
+pub struct GenericSubmissionProxy {
+    address: Address,
+    rules: Vec<CompiledRule>,
+}
+
+#[async_trait::async_trait]
+impl SubmissionProxy for GenericSubmissionProxy {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn process_revert(
+        &self,
+        revert_data: &Bytes,
+        ops: &[UserOpsPerAggregator<UserOperationVariant>],
+    ) -> Vec<B256> {
+        let Some(rule) = self
+            .rules
+            .iter()
+            .find(|r| revert_data.starts_with(r.error.selector().as_slice()))
+        else {
+            tracing::warn!(
+                "unknown revert data for submission proxy {:?}: {revert_data:?}",
+                self.address
+            );
+            return vec![];
+        };
+
+        let values = match rule.error.abi_decode_input(&revert_data[4..], true) {
+            Ok(values) => values,
+            Err(e) => {
+                tracing::warn!(
+                    "failed to decode error {} of submission proxy {:?}: {e:?}",
+                    rule.error.name,
+                    self.address
+                );
+                return vec![];
+            }
+        };
+        tracing::info!(
+            "submission proxy {:?} decoded error: {}: {values:?}",
+            self.address,
+            rule.error.name
+        );
+
+        let mut ops = ops.iter().flat_map(|a| a.user_ops.iter());
+        let value = &values[rule.param_index];
+        let hashes = match rule.identifies {
+            OpIdentifier::UserOpHash => {
+                let hash = value.as_word().unwrap_or_default();
+                ops.map(|uo| uo.hash()).filter(|h| *h == hash).collect()
+            }
+            OpIdentifier::Sender => {
+                let sender = value.as_address().unwrap_or_default();
+                ops.filter(|uo| uo.sender() == sender)
+                    .map(|uo| uo.hash())
+                    .collect()
+            }
+            OpIdentifier::OpIndex => value
+                .as_uint()
+                .and_then(|(index, _)| index.try_into().ok())
+                .and_then(|index: usize| ops.nth(index))
+                .map(|uo| vec![uo.hash()])
+                .unwrap_or_default(),
+        };
+
+        if hashes.is_empty() {
+            tracing::warn!(
+                "submission proxy {:?} error {} did not match a user operation in the bundle",
+                self.address,
+                rule.error.name
+            );
+        } else {
+            tracing::info!(
+                "submission proxy {:?} process_revert found invalid user operations: {hashes:?}",
+                self.address
+            );
+        }
+        hashes
+    }
+}
+
+impl GenericSubmissionProxy {
+    /// Create a new submission proxy from its definition
+    pub fn new(definition: &SubmissionProxyDefinition) -> Result<Self, DefinitionError> {
+        let rules = definition
+            .revert_rules
+            .iter()
+            .map(CompiledRule::new)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            address: definition.address,
+            rules,
+        })
+    }
+}
+
+impl CompiledRule {
+    fn new(rule: &RevertRule) -> Result<Self, DefinitionError> {
+        let error = Error::parse(&rule.error)
+            .map_err(|e| DefinitionError::InvalidSignature(rule.error.clone(), e.to_string()))?;
+        let param_index = error
+            .inputs
+            .iter()
+            .position(|p| p.name == rule.param)
+            .ok_or_else(|| {
+                DefinitionError::UnknownParameter(error.signature(), rule.param.clone())
+            })?;
+
+        let param = &error.inputs[param_index];
+        let ty = param
+            .resolve()
+            .map_err(|e| DefinitionError::InvalidSignature(rule.error.clone(), e.to_string()))?;
+        let valid = match rule.identifies {
+            OpIdentifier::UserOpHash => {
+                matches!(ty, DynSolType::FixedBytes(32) | DynSolType::Uint(256))
+            }
+            OpIdentifier::Sender => ty == DynSolType::Address,
+            OpIdentifier::OpIndex => matches!(ty, DynSolType::Uint(_)),
+        };
+        if !valid {
+            return Err(DefinitionError::InvalidParameterType(
+                error.signature(),
+                rule.param.clone(),
+                param.ty.clone(),
+                rule.identifies,
+            ));
+        }
+
+        Ok(Self {
+            error,
+            param_index,
+            identifies: rule.identifies,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, U256};
+    use alloy_sol_types::{sol, SolError};
+    use rundler_types::v0_7::{UserOperationBuilder, UserOperationRequiredFields};
+
+    use super::*;
+
+    sol! {
+        error InvalidNullifier(uint256 nullifierHash, bytes32 userOpHash);
+        error SenderBanned(address sender);
+        error FailedOp(uint256 opIndex, string reason);
+    }
+
+    const PROXY: Address = address!("0000000000000000000000000000000000000def");
+
+    fn proxy() -> GenericSubmissionProxy {
+        let definition: SubmissionProxyDefinition = serde_json::from_str(
+            r#"{
+                "address": "0x0000000000000000000000000000000000000def",
+                "revertRules": [
+                    {
+                        "error": "error InvalidNullifier(uint256 nullifierHash, bytes32 userOpHash)",
+                        "param": "userOpHash",
+                        "identifies": "userOpHash"
+                    },
+                    {
+                        "error": "SenderBanned(address sender)",
+                        "param": "sender",
+                        "identifies": "sender"
+                    },
+                    {
+                        "error": "FailedOp(uint256 opIndex, string reason)",
+                        "param": "opIndex",
+                        "identifies": "opIndex"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        GenericSubmissionProxy::new(&definition).unwrap()
+    }
+
+    fn ops() -> Vec<UserOpsPerAggregator<UserOperationVariant>> {
+        let op = |sender: u8, nonce: u64| -> UserOperationVariant {
+            UserOperationBuilder::new(
+                &Default::default(),
+                UserOperationRequiredFields {
+                    sender: Address::repeat_byte(sender),
+                    nonce: U256::from(nonce),
+                    call_data: Bytes::new(),
+                    call_gas_limit: 0,
+                    verification_gas_limit: 0,
+                    pre_verification_gas: 0,
+                    max_priority_fee_per_gas: 0,
+                    max_fee_per_gas: 0,
+                    signature: Bytes::new(),
+                },
+            )
+            .build()
+            .into()
+        };
+
+        vec![
+            UserOpsPerAggregator {
+                user_ops: vec![op(1, 0), op(2, 0)],
+                ..Default::default()
+            },
+            UserOpsPerAggregator {
+                user_ops: vec![op(1, 1)],
+                aggregator: Address::repeat_byte(0xaa),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_process_revert() {
+        let proxy = proxy();
+        assert_eq!(proxy.address(), PROXY);
+        let ops = ops();
+        let hashes = ops
+            .iter()
+            .flat_map(|a| a.user_ops.iter().map(|uo| uo.hash()))
+            .collect::<Vec<_>>();
+
+        let revert = InvalidNullifier {
+            nullifierHash: U256::from(1),
+            userOpHash: hashes[1],
+        }
+        .abi_encode();
+        assert_eq!(
+            proxy.process_revert(&revert.into(), &ops).await,
+            vec![hashes[1]]
+        );
+
+        let revert = SenderBanned {
+            sender: Address::repeat_byte(1),
+        }
+        .abi_encode();
+        assert_eq!(
+            proxy.process_revert(&revert.into(), &ops).await,
+            vec![hashes[0], hashes[2]]
+        );
+
+        let revert = FailedOp {
+            opIndex: U256::from(2),
+            reason: "AA24 signature error".to_string(),
+        }
+        .abi_encode();
+        assert_eq!(
+            proxy.process_revert(&revert.into(), &ops).await,
+            vec![hashes[2]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_revert_no_match() {
+        let proxy = proxy();
+        let ops = ops();
+
+        // unknown error
+        assert!(proxy
+            .process_revert(&Bytes::from_static(&[1, 2, 3, 4]), &ops)
+            .await
+            .is_empty());
+        // no op in the bundle
+        let revert = FailedOp {
+            opIndex: U256::from(3),
+            reason: String::new(),
+        }
+        .abi_encode();
+        assert!(proxy.process_revert(&revert.into(), &ops).await.is_empty());
+        // malformed error data
+        let revert = InvalidNullifier::SELECTOR.to_vec();
+        assert!(proxy.process_revert(&revert.into(), &ops).await.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let rule = |error: &str, param: &str, identifies| {
+            GenericSubmissionProxy::new(&SubmissionProxyDefinition {
+                address: PROXY,
+                revert_rules: vec![RevertRule {
+                    error: error.to_string(),
+                    param: param.to_string(),
+                    identifies,
+                }],
+            })
+        };
+
+        assert!(matches!(
+            rule("Broken(uint256", "a", OpIdentifier::OpIndex),
+            Err(DefinitionError::InvalidSignature(..))
+        ));
+        assert!(matches!(
+            rule("Err(uint256 a)", "b", OpIdentifier::OpIndex),
+            Err(DefinitionError::UnknownParameter(..))
+        ));
+        assert!(matches!(
+            rule("Err(uint128 a)", "a", OpIdentifier::UserOpHash),
+            Err(DefinitionError::InvalidParameterType(..))
+        ));
+        assert!(matches!(
+            rule("Err(uint256 a)", "a", OpIdentifier::Sender),
+            Err(DefinitionError::InvalidParameterType(..))
+        ));
+        assert!(rule("Err(uint256 a)", "a", OpIdentifier::UserOpHash).is_ok());
+    }
+}
@@ -20,8 +20,7 @@ use rundler_types::{
     aggregator::{
         AggregatorCosts, SignatureAggregator, SignatureAggregatorError, SignatureAggregatorResult,
     },
-    v0_7::UserOperation,
-    UserOperation as _, UserOperationVariant,
+    UserOperation, UserOperationVariant,
 };
 
 sol! {
@@ -62,7 +61,8 @@ pub struct PbhSignatureAggregator<EP> {
 #[async_trait::async_trait]
 impl<EP> SignatureAggregator for PbhSignatureAggregator<EP>
 where
-    EP: EpSignatureAggregator<UO = UserOperation>,
+    EP: EpSignatureAggregator,
+    EP::UO: From<UserOperationVariant>,
 {
     fn address(&self) -> Address {
         self.address
@@ -81,13 +81,7 @@ where
         &self,
         user_op: &UserOperationVariant,
     ) -> SignatureAggregatorResult<Bytes> {
-        if !user_op.is_v0_7() {
-            return Err(SignatureAggregatorError::InvalidUserOperation(
-                "User operation is not v0.7".to_string(),
-            ));
-        }
-
-        let uo = user_op.clone().into();
+        let uo = check_version::<EP::UO>(user_op.clone())?;
         match self
             .entry_point
             .validate_user_op_signature(self.address, uo)
@@ -110,12 +104,7 @@ where
         let mut agg_proofs = Vec::new();
 
         for user_op in uos {
-            if !user_op.is_v0_7() {
-                return Err(SignatureAggregatorError::InvalidUserOperation(
-                    "User operation is not v0.7".to_string(),
-                ));
-            }
-            let uo: UserOperation = user_op.clone().into();
+            let uo = check_version::<EP::UO>(user_op)?;
 
             if uo.signature().len() < PBH_PROOF_LENGTH {
                 return Err(SignatureAggregatorError::InvalidUserOperation(format!(
@@ -152,6 +141,19 @@ impl<EP> PbhSignatureAggregator<EP> {
     }
 }
 
+fn check_version<UO>(uo: UserOperationVariant) -> SignatureAggregatorResult<UO>
+where
+    UO: UserOperation + From<UserOperationVariant>,
+{
+    if uo.uo_type() != UO::entry_point_version() {
+        return Err(SignatureAggregatorError::InvalidUserOperation(format!(
+            "User operation is not {:?}",
+            UO::entry_point_version()
+        )));
+    }
+    Ok(uo.into())
+}
+
 impl<EP> Debug for PbhSignatureAggregator<EP> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("PbhSignatureAggregator")
@@ -11,51 +11,59 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::{
-    collections::{HashMap, HashSet},
-    sync::Mutex,
-};
+use std::{collections::HashSet, sync::Mutex};
 
 use alloy_primitives::Address;
 use metrics::Gauge;
 use metrics_derive::Metrics;
-use rundler_types::pool::{Pool, PoolOperation};
+use rundler_types::pool::{Pool, PoolOperation, SenderAssignments};
+
+use crate::bundle_ordering::BundleOrderingStrategy;
+
+/// Where the assignments of senders to builders are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, strum::EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum AssignmentBackend {
+    /// In the builder process, coordinating only the builders of this process
+    #[default]
+    Local,
+    /// In the pool, coordinating the builders of all processes using the same pool
+    Pool,
+}
 
 // The Assigner is responsible for assigning operations to builder addresses.
 //
 // It is used to ensure that no two builders attempt to include operations from the same sender in bundles simultaneously.
+//
+// With the local backend the assignments are kept in this process. With the pool backend they are leased from the pool,
+// which releases the senders of a builder that stops renewing its lease.
 pub(crate) struct Assigner {
     pool: Box<dyn Pool>,
-    state: Mutex<State>,
+    backend: Backend,
     max_pool_ops_per_request: u64,
     max_bundle_size: u64,
     metrics: GlobalMetrics,
 }
 
-#[derive(Default)]
-struct State {
-    uo_sender_to_builder_state: HashMap<Address, (Address, LockState)>,
-    builder_to_uo_senders: HashMap<Address, HashSet<Address>>,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum LockState {
-    // The sender is assigned to the builder, but has not yet been confirmed.
-    // The builder has yet to include the sender in a bundle.
-    Assigned,
-    // The sender is confirmed to the builder. The builder has included the sender in a bundle.
-    Confirmed,
+enum Backend {
+    Local(Mutex<SenderAssignments>),
+    Pool,
 }
 
 impl Assigner {
     pub(crate) fn new(
         pool: Box<dyn Pool>,
+        backend: AssignmentBackend,
         max_pool_ops_per_request: u64,
         max_bundle_size: u64,
     ) -> Self {
+        let backend = match backend {
+            AssignmentBackend::Local => Backend::Local(Mutex::new(SenderAssignments::new(None))),
+            AssignmentBackend::Pool => Backend::Pool,
+        };
         Self {
             pool,
-            state: Mutex::new(State::default()),
+            backend,
             max_pool_ops_per_request,
             max_bundle_size,
             metrics: GlobalMetrics::default(),
@@ -68,62 +76,61 @@ impl Assigner {
     // These senders enter the "assigned" state and cannot be assigned to another builder until the current builder drops or confirms them.
     //
     // This method is called to receive operations from the pool for a builder prior to forming a bundle.
+    //
+    // Builders that keep the pool's order are assigned at most `max_bundle_size` operations. Builders that reorder
+    // operations are assigned every candidate, so that the proposer truncates to `max_bundle_size` after ordering.
     pub(crate) async fn assign_operations(
         &self,
         builder_address: Address,
         entry_point: Address,
         filter_id: Option<String>,
+        ordering: BundleOrderingStrategy,
     ) -> anyhow::Result<Vec<PoolOperation>> {
-        let per_builder_metrics =
-            PerBuilderMetrics::new_with_labels(&[("builder_address", builder_address.to_string())]);
+        let max_ops = if ordering.is_pool_order() {
+            self.max_bundle_size
+        } else {
+            self.max_pool_ops_per_request
+        };
+
         let ops = self
             .pool
             .get_ops_summaries(entry_point, self.max_pool_ops_per_request, filter_id)
             .await?;
-        let mut return_ops_summaries = Vec::new();
-
-        {
-            let mut state = self.state.lock().unwrap();
-            for op in ops {
-                let (locked_builder_address, _) = state
-                    .uo_sender_to_builder_state
-                    .entry(op.sender)
-                    .or_insert_with(|| {
-                        tracing::debug!(
-                            "op {:?} sender {:?} assigned to builder {:?}",
-                            op.hash,
-                            op.sender,
-                            builder_address
-                        );
-                        per_builder_metrics.senders_assigned.increment(1);
-                        (builder_address, LockState::Assigned)
-                    });
-
-                if *locked_builder_address != builder_address {
+        if ops.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let senders = ops.iter().map(|op| op.sender);
+        let assigned = match &self.backend {
+            Backend::Local(state) => {
+                let mut state = state.lock().unwrap();
+                let assigned = state.assign(builder_address, senders, max_ops as usize);
+                self.update_metrics(&state, builder_address);
+                assigned
+            }
+            Backend::Pool => {
+                self.pool
+                    .assign_senders(builder_address, senders.collect(), max_ops)
+                    .await?
+            }
+        };
+        let assigned: HashSet<Address> = assigned.into_iter().collect();
+
+        let return_ops_summaries = ops
+            .into_iter()
+            .filter(|op| {
+                let is_assigned = assigned.contains(&op.sender);
+                if !is_assigned {
                     tracing::debug!(
-                        "op {:?} sender {:?} already assigned to another builder {:?}, skipping",
+                        "op {:?} sender {:?} already assigned to another builder, skipping",
                         op.hash,
                         op.sender,
-                        locked_builder_address
                     );
-                    continue;
                 }
-
-                state
-                    .builder_to_uo_senders
-                    .entry(builder_address)
-                    .or_insert_with(|| {
-                        self.metrics.active_builders.increment(1);
-                        HashSet::new()
-                    })
-                    .insert(op.sender);
-
-                return_ops_summaries.push(op);
-                if return_ops_summaries.len() >= self.max_bundle_size as usize {
-                    break;
-                }
-            }
-        }
+                is_assigned
+            })
+            .take(max_ops as usize)
+            .collect::<Vec<_>>();
 
         if return_ops_summaries.is_empty() {
             return Ok(vec![]);
@@ -147,112 +154,103 @@ impl Assigner {
     //
     // This method is typically called when the builder is done forming and sending a bundle. Confirmed senders are the senders that were included in the bundle.
     //
-    // PANICS:
+    // PANICS (local backend):
     // - If the confirmed_sender is not found in the state, the builder must have been assigned this sender via the assign_operations method.
     // - If the confirmed_sender is assigned to another builder, the builder must have been assigned this sender via the assign_operations method.
-    // - If the builder_address is not found in the state, the builder must have been assigned via the assign_operations method.
-    // - If the builder is assigned a sender that is not found in the uo_sender_to_builder_state map, this is an internal error.
-    pub(crate) fn confirm_senders_drop_unused<'a>(
+    //
+    // With the pool backend these are logged instead, as the lease of the builder may have expired.
+    pub(crate) async fn confirm_senders_drop_unused<'a>(
         &self,
         builder_address: Address,
         confirmed_senders: impl IntoIterator<Item = &'a Address>,
     ) {
-        let mut state = self.state.lock().unwrap();
-        let per_builder_metrics =
-            PerBuilderMetrics::new_with_labels(&[("builder_address", builder_address.to_string())]);
-
-        // Confirm all of the senders in state
-        for confirmed_sender in confirmed_senders.into_iter() {
-            let (locked_builder_address, lock_state) = state
-                .uo_sender_to_builder_state
-                .get_mut(confirmed_sender)
-                .expect("BUG: confirmed_sender not found in state, lock contract broken");
-
-            if *locked_builder_address != builder_address {
-                panic!("BUG: confirmed_sender {:?} is assigned to another builder expected: {:?} found: {:?}, lock contract broken", confirmed_sender, builder_address, locked_builder_address);
+        let confirmed_senders: Vec<Address> = confirmed_senders.into_iter().copied().collect();
+        match &self.backend {
+            Backend::Local(state) => {
+                let mut state = state.lock().unwrap();
+                if let Err(e) = state.confirm(builder_address, confirmed_senders) {
+                    panic!("BUG: {e}, lock contract broken");
+                }
+                self.update_metrics(&state, builder_address);
             }
-
-            // Confirm the sender to the builder
-            if *lock_state == LockState::Assigned {
-                tracing::debug!(
-                    "confirmed_sender {:?} confirmed to builder {:?}",
-                    confirmed_sender,
-                    builder_address
-                );
-                per_builder_metrics.senders_confirmed.increment(1);
-                per_builder_metrics.senders_assigned.decrement(1);
-                *lock_state = LockState::Confirmed;
+            Backend::Pool => {
+                if let Err(e) = self
+                    .pool
+                    .confirm_senders(builder_address, confirmed_senders)
+                    .await
+                {
+                    tracing::error!(
+                        "Failed to confirm senders of builder {builder_address:?}: {e:?}"
+                    );
+                }
             }
         }
+    }
 
-        // Drop locks for all senders that are still in the assigned state
-        let builder_senders = state
-            .builder_to_uo_senders
-            .get(&builder_address)
-            .expect("BUG: builder_address not found in state, lock contract broken");
-
-        let mut to_remove = Vec::new();
-
-        for sender in builder_senders.iter() {
-            let entry = state
-                .uo_sender_to_builder_state
-                .get(sender)
-                .expect("BUG: sender not found in state, lock contract broken");
-            if entry.1 != LockState::Confirmed {
-                to_remove.push(*sender);
-            }
-        }
+    // This method releases all of the senders assigned to the builder.
+    // This is typically done when the builder is done forming a bundle and is ready to start forming the next bundle.
+    pub(crate) async fn release_all(&self, builder_address: Address) {
+        self.release_all_except(builder_address, &[]).await;
+    }
 
-        for sender in to_remove {
-            state.uo_sender_to_builder_state.remove(&sender);
-            let builder_senders = state
-                .builder_to_uo_senders
-                .get_mut(&builder_address)
-                .unwrap();
-            builder_senders.remove(&sender);
-            tracing::debug!(
-                "sender {:?} removed from builder {:?}",
-                sender,
-                builder_address
-            );
-            per_builder_metrics.senders_assigned.decrement(1);
-
-            if builder_senders.is_empty() {
-                self.metrics.active_builders.decrement(1);
-                state.builder_to_uo_senders.remove(&builder_address);
+    // This method releases all of the senders assigned to the builder except for the `kept_senders`.
+    //
+    // This is used when the builder has multiple bundles pending: once one of them mines, only the senders that are
+    // not included in any of the other pending bundles are released.
+    //
+    // With the pool backend, failures are logged and the senders are released once the lease of the builder expires.
+    pub(crate) async fn release_all_except<'a>(
+        &self,
+        builder_address: Address,
+        kept_senders: impl IntoIterator<Item = &'a Address>,
+    ) {
+        let kept_senders: Vec<Address> = kept_senders.into_iter().copied().collect();
+        match &self.backend {
+            Backend::Local(state) => {
+                let mut state = state.lock().unwrap();
+                state.release_all_except(builder_address, kept_senders);
+                self.update_metrics(&state, builder_address);
+            }
+            Backend::Pool => {
+                if let Err(e) = self
+                    .pool
+                    .release_senders(builder_address, kept_senders)
+                    .await
+                {
+                    tracing::error!(
+                        "Failed to release senders of builder {builder_address:?}: {e:?}"
+                    );
+                }
             }
         }
     }
 
-    // This method releases all of the senders assigned to the builder.
-    // This is typically done when the builder is done forming a bundle and is ready to start forming the next bundle.
-    pub(crate) fn release_all(&self, builder_address: Address) {
-        let mut state = self.state.lock().unwrap();
-        let Some(builder_senders) = state.builder_to_uo_senders.remove(&builder_address) else {
-            return;
-        };
-        let per_builder_metrics =
-            PerBuilderMetrics::new_with_labels(&[("builder_address", builder_address.to_string())]);
-
-        for sender in builder_senders {
-            if let Some((_, state)) = state.uo_sender_to_builder_state.remove(&sender) {
-                tracing::debug!(
-                    "sender {:?} removed from builder {:?}",
-                    sender,
-                    builder_address
-                );
-                match state {
-                    LockState::Assigned => {
-                        per_builder_metrics.senders_assigned.decrement(1);
-                    }
-                    LockState::Confirmed => {
-                        per_builder_metrics.senders_confirmed.decrement(1);
-                    }
+    // This method renews the lease of the builder so that the pool keeps its senders assigned.
+    //
+    // It is called periodically while the builder waits, for example for a pending bundle to mine. The local backend
+    // doesn't expire leases, so there is nothing to renew.
+    pub(crate) async fn renew_lease(&self, builder_address: Address) {
+        match &self.backend {
+            Backend::Local(_) => {}
+            Backend::Pool => {
+                if let Err(e) = self.pool.renew_senders(builder_address).await {
+                    tracing::error!(
+                        "Failed to renew the lease of builder {builder_address:?}: {e:?}"
+                    );
                 }
             }
         }
+    }
 
-        self.metrics.active_builders.decrement(1);
+    fn update_metrics(&self, state: &SenderAssignments, builder_address: Address) {
+        let per_builder_metrics =
+            PerBuilderMetrics::new_with_labels(&[("builder_address", builder_address.to_string())]);
+        let (assigned, confirmed) = state.num_senders(builder_address);
+        per_builder_metrics.senders_assigned.set(assigned as f64);
+        per_builder_metrics.senders_confirmed.set(confirmed as f64);
+        self.metrics
+            .active_builders
+            .set(state.num_builders() as f64);
     }
 }
 
@@ -337,11 +335,16 @@ mod tests {
     async fn test_no_operations() {
         let mut mock_pool = MockPool::new();
         mock_pool_get_ops(&mut mock_pool, vec![]);
-        let assigner = Assigner::new(Box::new(mock_pool), 10, 10);
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 10);
 
         // First assignment should succeed
         let assigned_ops = assigner
-            .assign_operations(address(0), address(0), None)
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
         assert_eq!(assigned_ops.len(), 0); // TestPool returns empty by default
@@ -353,9 +356,14 @@ mod tests {
         let ops = create_test_ops(&[address(1), address(2)]);
         mock_pool_get_ops(&mut mock_pool, ops.clone());
 
-        let assigner = Assigner::new(Box::new(mock_pool), 10, 10);
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 10);
         let assigned_ops = assigner
-            .assign_operations(address(0), address(0), None)
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
         assert_eq!(assigned_ops.len(), 2);
@@ -363,27 +371,77 @@ mod tests {
         assert_eq!(assigned_ops[1].uo.sender(), address(2));
     }
 
+    #[tokio::test]
+    async fn test_assign_operations_reordering_builder() {
+        let ops = create_test_ops(&[address(1), address(2), address(3)]);
+
+        // builders keeping the pool's order are assigned up to the max bundle size
+        let mut mock_pool = MockPool::new();
+        mock_pool_get_ops(&mut mock_pool, ops.clone());
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 2);
+        let assigned_ops = assigner
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
+            .await
+            .unwrap();
+        assert_eq!(assigned_ops.len(), 2);
+
+        // builders reordering operations are assigned every candidate
+        let mut mock_pool = MockPool::new();
+        mock_pool_get_ops(&mut mock_pool, ops);
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 2);
+        let assigned_ops = assigner
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::Knapsack,
+            )
+            .await
+            .unwrap();
+        assert_eq!(assigned_ops.len(), 3);
+    }
+
     #[tokio::test]
     async fn test_assign_twice() {
         let mut mock_pool = MockPool::new();
         let ops = create_test_ops(&[address(1), address(2)]);
         mock_pool_get_ops(&mut mock_pool, ops.clone());
 
-        let assigner = Assigner::new(Box::new(mock_pool), 10, 10);
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 10);
         let _ = assigner
-            .assign_operations(address(0), address(0), None)
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
 
         // Same builder address should assign again
         let assigned_ops = assigner
-            .assign_operations(address(0), address(0), None)
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
         assert_eq!(assigned_ops.len(), 2);
         // Different builder address should not assign
         let assigned_ops = assigner
-            .assign_operations(address(1), address(0), None)
+            .assign_operations(
+                address(1),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
         assert_eq!(assigned_ops.len(), 0);
@@ -395,18 +453,30 @@ mod tests {
         let ops = create_test_ops(&[address(1), address(2)]);
         mock_pool_get_ops(&mut mock_pool, ops.clone());
 
-        let assigner = Assigner::new(Box::new(mock_pool), 10, 10);
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 10);
         let _ = assigner
-            .assign_operations(address(0), address(0), None)
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
 
         // Same builder address should assign again
-        assigner.confirm_senders_drop_unused(address(0), &[address(1)]);
+        assigner
+            .confirm_senders_drop_unused(address(0), &[address(1)])
+            .await;
 
         // Different builder should be able go receive address(2)
         let assigned_ops = assigner
-            .assign_operations(address(1), address(0), None)
+            .assign_operations(
+                address(1),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
         assert_eq!(assigned_ops.len(), 1);
@@ -419,17 +489,27 @@ mod tests {
         let ops = create_test_ops(&[address(1), address(2)]);
         mock_pool_get_ops(&mut mock_pool, ops.clone());
 
-        let assigner = Assigner::new(Box::new(mock_pool), 10, 10);
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 10);
         let _ = assigner
-            .assign_operations(address(0), address(0), None)
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
 
         // Same builder address should assign again
-        assigner.release_all(address(0));
+        assigner.release_all(address(0)).await;
 
         let assigned_ops = assigner
-            .assign_operations(address(1), address(0), None)
+            .assign_operations(
+                address(1),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
         assert_eq!(assigned_ops.len(), 2);
@@ -437,26 +517,87 @@ mod tests {
         assert_eq!(assigned_ops[1].uo.sender(), address(2));
     }
 
+    #[tokio::test]
+    async fn test_release_all_except() {
+        let mut mock_pool = MockPool::new();
+        let ops = create_test_ops(&[address(1), address(2)]);
+        mock_pool_get_ops(&mut mock_pool, ops.clone());
+
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 10);
+        let _ = assigner
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
+            .await
+            .unwrap();
+        assigner
+            .confirm_senders_drop_unused(address(0), &[address(1), address(2)])
+            .await;
+
+        // address(1) is still pending in another bundle
+        assigner.release_all_except(address(0), &[address(1)]).await;
+
+        let assigned_ops = assigner
+            .assign_operations(
+                address(1),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
+            .await
+            .unwrap();
+        assert_eq!(assigned_ops.len(), 1);
+        assert_eq!(assigned_ops[0].uo.sender(), address(2));
+
+        // the original builder still holds address(1)
+        let assigned_ops = assigner
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
+            .await
+            .unwrap();
+        assert_eq!(assigned_ops.len(), 1);
+        assert_eq!(assigned_ops[0].uo.sender(), address(1));
+    }
+
     #[tokio::test]
     async fn test_cannot_drop_confirmed_senders() {
         let mut mock_pool = MockPool::new();
         let ops = create_test_ops(&[address(1), address(2)]);
         mock_pool_get_ops(&mut mock_pool, ops.clone());
 
-        let assigner = Assigner::new(Box::new(mock_pool), 10, 10);
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 10);
         let _ = assigner
-            .assign_operations(address(0), address(0), None)
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
 
         // confirm address(1)
-        assigner.confirm_senders_drop_unused(address(0), &[address(1)]);
+        assigner
+            .confirm_senders_drop_unused(address(0), &[address(1)])
+            .await;
         // this should not drop lock on address(1)
-        assigner.confirm_senders_drop_unused(address(0), &[]);
+        assigner.confirm_senders_drop_unused(address(0), &[]).await;
 
         // Different builder should be able go receive address(2)
         let assigned_ops = assigner
-            .assign_operations(address(1), address(0), None)
+            .assign_operations(
+                address(1),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
         assert_eq!(assigned_ops.len(), 1);
@@ -470,16 +611,20 @@ mod tests {
         let ops = create_test_ops(&[address(1), address(2)]);
         mock_pool_get_ops(&mut mock_pool, ops.clone());
 
-        let assigner = Assigner::new(Box::new(mock_pool), 10, 10);
-        assigner.confirm_senders_drop_unused(address(0), &[address(3)]);
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 10);
+        assigner
+            .confirm_senders_drop_unused(address(0), &[address(3)])
+            .await;
     }
 
     #[tokio::test]
     #[should_panic]
     async fn test_try_confirm_sender_not_assigned() {
         let mock_pool = MockPool::new();
-        let assigner = Assigner::new(Box::new(mock_pool), 10, 10);
-        assigner.confirm_senders_drop_unused(address(1), &[address(1)]);
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 10);
+        assigner
+            .confirm_senders_drop_unused(address(1), &[address(1)])
+            .await;
     }
 
     #[tokio::test]
@@ -489,13 +634,68 @@ mod tests {
         let ops = create_test_ops(&[address(1), address(2)]);
         mock_pool_get_ops(&mut mock_pool, ops.clone());
 
-        let assigner = Assigner::new(Box::new(mock_pool), 10, 10);
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Local, 10, 10);
         let _ = assigner
-            .assign_operations(address(0), address(0), None)
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
+            .await
+            .unwrap();
+
+        assigner
+            .confirm_senders_drop_unused(address(1), &[address(1)])
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_pool_backend() {
+        let mut mock_pool = MockPool::new();
+        let ops = create_test_ops(&[address(1), address(2)]);
+        mock_pool_get_ops(&mut mock_pool, ops.clone());
+        mock_pool
+            .expect_assign_senders()
+            .withf(|builder, senders, max_ops| {
+                *builder == address(0) && *senders == vec![address(1), address(2)] && *max_ops == 10
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(vec![address(2)]));
+        mock_pool
+            .expect_confirm_senders()
+            .withf(|builder, confirmed| *builder == address(0) && *confirmed == vec![address(2)])
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_pool
+            .expect_renew_senders()
+            .withf(|builder| *builder == address(0))
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_pool
+            .expect_release_senders()
+            .withf(|builder, kept| *builder == address(0) && kept.is_empty())
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let assigner = Assigner::new(Box::new(mock_pool), AssignmentBackend::Pool, 10, 10);
+        let assigned_ops = assigner
+            .assign_operations(
+                address(0),
+                address(0),
+                None,
+                BundleOrderingStrategy::GasPrice,
+            )
             .await
             .unwrap();
+        assert_eq!(assigned_ops.len(), 1);
+        assert_eq!(assigned_ops[0].uo.sender(), address(2));
 
-        assigner.confirm_senders_drop_unused(address(1), &[address(1)]);
+        assigner
+            .confirm_senders_drop_unused(address(0), &[address(2)])
+            .await;
+        assigner.renew_lease(address(0)).await;
+        assigner.release_all(address(0)).await;
     }
 
     fn address(i: u64) -> Address {
@@ -0,0 +1,240 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::cmp::Reverse;
+
+use serde::Deserialize;
+
+// Number of gas units the knapsack capacity is divided into
+const KNAPSACK_GAS_BUCKETS: u128 = 1000;
+
+/// Strategy used to order candidate user operations before they are packed into a bundle.
+///
+/// The proposer packs operations greedily in the resulting order until the target bundle
+/// gas is reached, so operations ordered first are the most likely to be bundled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BundleOrderingStrategy {
+    /// Keep the pool's order, by effective gas price
+    #[default]
+    GasPrice,
+    /// Order by the profit per unit of computation gas, after the DA cost of the operation
+    ProfitPerGas,
+    /// Order by the priority tier of the operation's permissions, highest first, then by
+    /// the pool's order
+    PriorityTier,
+    /// Move operations that have waited at least `max_wait_blocks` blocks to the front,
+    /// oldest first, then the pool's order
+    #[serde(rename_all = "camelCase")]
+    Age {
+        /// Number of blocks an operation can wait before it is moved to the front
+        max_wait_blocks: u64,
+    },
+    /// Order first the set of operations with the most total profit that fits within the
+    /// target bundle gas, then the remaining operations in the pool's order
+    Knapsack,
+}
+
+/// Information about an operation used to order it
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct OrderingInfo {
+    /// Block number the operation was added to the pool at
+    pub(crate) sim_block_number: u64,
+    /// Priority tier of the operation
+    pub(crate) priority_tier: u32,
+    /// Computation gas limit of the operation in a bundle
+    pub(crate) gas_limit: u128,
+    /// Expected profit of bundling the operation, in wei, after gas and DA costs
+    pub(crate) profit: i128,
+}
+
+impl OrderingInfo {
+    fn profit_per_gas(&self) -> i128 {
+        self.profit / self.gas_limit.max(1) as i128
+    }
+}
+
+impl BundleOrderingStrategy {
+    /// True if the strategy keeps the pool's order, in which case only the first
+    /// `max_bundle_size` operations of the pool are candidates for a bundle.
+    pub(crate) fn is_pool_order(&self) -> bool {
+        matches!(self, Self::GasPrice)
+    }
+
+    /// Orders `ops`, which are expected to be in the pool's order.
+    pub(crate) fn order<T>(
+        &self,
+        mut ops: Vec<(T, OrderingInfo)>,
+        block_number: u64,
+        target_gas: u128,
+    ) -> Vec<T> {
+        match self {
+            Self::GasPrice => {}
+            Self::ProfitPerGas => ops.sort_by_key(|(_, info)| Reverse(info.profit_per_gas())),
+            Self::PriorityTier => ops.sort_by_key(|(_, info)| Reverse(info.priority_tier)),
+            Self::Age { max_wait_blocks } => {
+                let (mut waited, rest): (Vec<_>, Vec<_>) =
+                    ops.into_iter().partition(|(_, info)| {
+                        block_number.saturating_sub(info.sim_block_number) >= *max_wait_blocks
+                    });
+                waited.sort_by_key(|(_, info)| info.sim_block_number);
+                waited.extend(rest);
+                ops = waited;
+            }
+            Self::Knapsack => {
+                let selected = knapsack(&ops, target_gas);
+                let (mut chosen, rest): (Vec<_>, Vec<_>) = ops
+                    .into_iter()
+                    .zip(selected)
+                    .partition(|(_, selected)| *selected);
+                chosen.sort_by_key(|((_, info), _)| Reverse(info.profit_per_gas()));
+                ops = chosen.into_iter().chain(rest).map(|(op, _)| op).collect();
+            }
+        }
+
+        ops.into_iter().map(|(op, _)| op).collect()
+    }
+}
+
+// 0/1 knapsack over the operations with positive profit, with gas rounded up to buckets
+// of the target gas. Returns whether each operation is selected.
+fn knapsack<T>(ops: &[(T, OrderingInfo)], target_gas: u128) -> Vec<bool> {
+    let bucket = (target_gas / KNAPSACK_GAS_BUCKETS).max(1);
+    let capacity = (target_gas / bucket) as usize;
+    let weights = ops
+        .iter()
+        .map(|(_, info)| info.gas_limit.div_ceil(bucket) as usize)
+        .collect::<Vec<_>>();
+
+    // best[i][w] is the most profit using the first i operations within w buckets
+    let mut best = vec![vec![0_i128; capacity + 1]; ops.len() + 1];
+    for (i, (_, info)) in ops.iter().enumerate() {
+        for w in 0..=capacity {
+            best[i + 1][w] = best[i][w];
+            if info.profit > 0 && weights[i] <= w {
+                best[i + 1][w] = best[i + 1][w].max(best[i][w - weights[i]] + info.profit);
+            }
+        }
+    }
+
+    let mut selected = vec![false; ops.len()];
+    let mut w = capacity;
+    for i in (0..ops.len()).rev() {
+        if best[i + 1][w] != best[i][w] {
+            selected[i] = true;
+            w -= weights[i];
+        }
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(
+        sim_block_number: u64,
+        priority_tier: u32,
+        gas_limit: u128,
+        profit: i128,
+    ) -> OrderingInfo {
+        OrderingInfo {
+            sim_block_number,
+            priority_tier,
+            gas_limit,
+            profit,
+        }
+    }
+
+    #[test]
+    fn test_gas_price_keeps_order() {
+        let ops = vec![(0, info(0, 0, 100, 1)), (1, info(0, 0, 100, 10))];
+        assert_eq!(
+            BundleOrderingStrategy::GasPrice.order(ops, 0, 1000),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn test_profit_per_gas() {
+        let ops = vec![
+            (0, info(0, 0, 100, 100)),
+            (1, info(0, 0, 10, 100)),
+            (2, info(0, 0, 100, -100)),
+        ];
+        assert_eq!(
+            BundleOrderingStrategy::ProfitPerGas.order(ops, 0, 1000),
+            vec![1, 0, 2]
+        );
+    }
+
+    #[test]
+    fn test_priority_tier() {
+        let ops = vec![
+            (0, info(0, 0, 100, 100)),
+            (1, info(0, 2, 100, 1)),
+            (2, info(0, 1, 100, 1)),
+            (3, info(0, 2, 100, 1)),
+        ];
+        assert_eq!(
+            BundleOrderingStrategy::PriorityTier.order(ops, 0, 1000),
+            vec![1, 3, 2, 0]
+        );
+    }
+
+    #[test]
+    fn test_age() {
+        let ops = vec![
+            (0, info(10, 0, 100, 100)),
+            (1, info(5, 0, 100, 1)),
+            (2, info(9, 0, 100, 1)),
+            (3, info(2, 0, 100, 1)),
+        ];
+        assert_eq!(
+            BundleOrderingStrategy::Age { max_wait_blocks: 2 }.order(ops, 10, 1000),
+            vec![3, 1, 0, 2]
+        );
+    }
+
+    #[test]
+    fn test_knapsack() {
+        // the two smaller ops are worth more together than the large op
+        let ops = vec![
+            (0, info(0, 0, 600, 100)),
+            (1, info(0, 0, 500, 70)),
+            (2, info(0, 0, 500, 60)),
+            (3, info(0, 0, 100, -10)),
+        ];
+        assert_eq!(
+            BundleOrderingStrategy::Knapsack.order(ops, 0, 1000),
+            vec![1, 2, 0, 3]
+        );
+    }
+
+    #[test]
+    fn test_is_pool_order() {
+        assert!(BundleOrderingStrategy::GasPrice.is_pool_order());
+        assert!(!BundleOrderingStrategy::Knapsack.is_pool_order());
+        assert!(!BundleOrderingStrategy::Age { max_wait_blocks: 1 }.is_pool_order());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let strategy: BundleOrderingStrategy =
+            serde_json::from_str(r#"{"type":"age","maxWaitBlocks":5}"#).unwrap();
+        assert_eq!(strategy, BundleOrderingStrategy::Age { max_wait_blocks: 5 });
+        let strategy: BundleOrderingStrategy =
+            serde_json::from_str(r#"{"type":"profitPerGas"}"#).unwrap();
+        assert_eq!(strategy, BundleOrderingStrategy::ProfitPerGas);
+    }
+}
@@ -0,0 +1,292 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use alloy_primitives::Address;
+use async_trait::async_trait;
+use rundler_provider::{DAGasProvider, EntryPoint, EvmProvider, ProvidersWithEntryPointT};
+use rundler_types::{
+    builder::{BundlePreview, BundlePreviewExcludedOp, BundlePreviewOp},
+    chain::ChainSpec,
+    pool::Pool,
+    UserOperation,
+};
+use rundler_utils::emit::WithEntryPoint;
+use tokio::sync::{
+    broadcast::{self, error::TryRecvError},
+    watch,
+};
+
+use crate::{
+    bundle_ordering::BundleOrderingStrategy,
+    bundle_proposer::{BundleProposer, BundleProposerError},
+    emit::{BuilderEvent, BuilderEventKind},
+};
+
+/// Builds bundles on demand without sending them, used for debugging.
+///
+/// Each previewer mirrors one bundle builder: it shares the builder's sender
+/// address and filter id but uses its own proposer so that previews never
+/// affect the state of the running builder.
+#[async_trait]
+pub trait BundlePreviewer: Send + Sync {
+    /// Entry point this previewer builds bundles for
+    fn entry_point(&self) -> Address;
+
+    /// Filter id of the builder this previewer mirrors
+    fn filter_id(&self) -> Option<&str>;
+
+    /// Build the bundle the builder would propose at the latest block
+    async fn preview_bundle(&mut self) -> anyhow::Result<BundlePreview>;
+}
+
+pub(crate) struct Settings {
+    pub(crate) chain_spec: ChainSpec,
+    // Updated by the builder when its signer is replaced
+    pub(crate) sender_eoa: watch::Receiver<Address>,
+    pub(crate) filter_id: Option<String>,
+    pub(crate) max_bundle_size: u64,
+    pub(crate) max_pool_ops_per_request: u64,
+    pub(crate) ordering: BundleOrderingStrategy,
+}
+
+pub(crate) struct BundlePreviewerImpl<P, EP, PL> {
+    proposer: P,
+    ep_providers: EP,
+    pool: PL,
+    settings: Settings,
+    // Receives the events of `proposer` only, used to capture the reasons
+    // operations were skipped or rejected.
+    event_receiver: broadcast::Receiver<WithEntryPoint<BuilderEvent>>,
+}
+
+impl<P, EP, PL> BundlePreviewerImpl<P, EP, PL> {
+    pub(crate) fn new(
+        proposer: P,
+        ep_providers: EP,
+        pool: PL,
+        settings: Settings,
+        event_receiver: broadcast::Receiver<WithEntryPoint<BuilderEvent>>,
+    ) -> Self {
+        Self {
+            proposer,
+            ep_providers,
+            pool,
+            settings,
+            event_receiver,
+        }
+    }
+}
+
+#[async_trait]
+impl<P, EP, PL> BundlePreviewer for BundlePreviewerImpl<P, EP, PL>
+where
+    P: BundleProposer<UO = EP::UO>,
+    EP: ProvidersWithEntryPointT,
+    PL: Pool,
+{
+    fn entry_point(&self) -> Address {
+        *self.ep_providers.entry_point().address()
+    }
+
+    fn filter_id(&self) -> Option<&str> {
+        self.settings.filter_id.as_deref()
+    }
+
+    async fn preview_bundle(&mut self) -> anyhow::Result<BundlePreview> {
+        let entry_point = self.entry_point();
+
+        // drop anything left over from a previous preview that errored
+        self.take_excluded_ops();
+
+        // the builder's signer may have been replaced since the last preview
+        let sender_eoa = *self.settings.sender_eoa.borrow();
+        self.proposer.set_sender_eoa(sender_eoa);
+
+        let (block_hash, block_number) = self
+            .ep_providers
+            .evm()
+            .get_latest_block_hash_and_number()
+            .await?;
+
+        let mut preview = BundlePreview {
+            entry_point,
+            builder_address: sender_eoa,
+            block_hash,
+            ..Default::default()
+        };
+
+        // mirror the builder's assignment, the proposer truncates reordered candidates
+        let max_ops = if self.settings.ordering.is_pool_order() {
+            self.settings.max_bundle_size
+        } else {
+            self.settings.max_pool_ops_per_request
+        };
+        let ops = self
+            .pool
+            .get_ops(entry_point, max_ops, self.settings.filter_id.clone())
+            .await?;
+        if ops.is_empty() {
+            return Ok(preview);
+        }
+
+        let (gas_fees, base_fee) = self.proposer.estimate_gas_fees(block_hash, None).await?;
+        preview.gas_fees = gas_fees;
+
+        let balance = self
+            .ep_providers
+            .evm()
+            .get_balance(sender_eoa, Some(block_hash.into()))
+            .await?;
+
+        let bundle = match self
+            .proposer
+            .make_bundle(ops, block_hash, block_number, balance, None, false)
+            .await
+        {
+            Ok(bundle) => bundle,
+            Err(BundleProposerError::NoOperationsAfterFeeFilter) => {
+                (preview.rejected_ops, preview.skipped_ops) = self.take_excluded_ops();
+                return Ok(preview);
+            }
+            Err(e) => return Err(anyhow::anyhow!("failed to make bundle: {e:?}")),
+        };
+        (preview.rejected_ops, preview.skipped_ops) = self.take_excluded_ops();
+
+        let gas_price = bundle.gas_fees.gas_price(base_fee);
+        let bundle_size = bundle.len();
+        for ops in &bundle.ops_per_aggregator {
+            let aggregator = (!ops.aggregator.is_zero()).then_some(ops.aggregator);
+            for op in &ops.user_ops {
+                let da_gas = if self.settings.chain_spec.da_pre_verification_gas {
+                    self.ep_providers
+                        .entry_point()
+                        .calc_da_gas(op.clone(), block_hash.into(), gas_price, bundle_size)
+                        .await?
+                        .0
+                } else {
+                    0
+                };
+                preview.da_gas += da_gas;
+                preview.ops.push(BundlePreviewOp {
+                    hash: op.hash(),
+                    sender: op.sender(),
+                    nonce: op.nonce(),
+                    aggregator,
+                    da_gas,
+                });
+            }
+        }
+
+        preview.gas_estimate = bundle.gas_estimate;
+        preview.gas_fees = bundle.gas_fees;
+        preview.expected_storage = bundle.expected_storage;
+
+        Ok(preview)
+    }
+}
+
+impl<P, EP, PL> BundlePreviewerImpl<P, EP, PL> {
+    // Drains the proposer events, returning the (rejected, skipped) operations
+    fn take_excluded_ops(
+        &mut self,
+    ) -> (Vec<BundlePreviewExcludedOp>, Vec<BundlePreviewExcludedOp>) {
+        let mut rejected = vec![];
+        let mut skipped = vec![];
+        loop {
+            let event = match self.event_receiver.try_recv() {
+                Ok(event) => event.event,
+                Err(TryRecvError::Lagged(n)) => {
+                    tracing::warn!("bundle previewer missed {n} proposer events");
+                    continue;
+                }
+                Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+            };
+            match event.kind {
+                BuilderEventKind::RejectedOp { op_hash, reason } => {
+                    rejected.push(BundlePreviewExcludedOp {
+                        hash: op_hash,
+                        reason: format!("{reason:?}"),
+                    })
+                }
+                BuilderEventKind::SkippedOp { op_hash, reason } => {
+                    skipped.push(BundlePreviewExcludedOp {
+                        hash: op_hash,
+                        reason: format!("{reason:?}"),
+                    })
+                }
+                _ => {}
+            }
+        }
+        (rejected, skipped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::B256;
+    use rundler_types::GasFees;
+
+    use super::*;
+    use crate::emit::{OpRejectionReason, SkipReason};
+
+    #[tokio::test]
+    async fn test_take_excluded_ops() {
+        let (tx, rx) = broadcast::channel(8);
+        let mut previewer = BundlePreviewerImpl::new((), (), (), test_settings(), rx);
+
+        let entry_point = Address::random();
+        let skipped = B256::random();
+        let rejected = B256::random();
+        for event in [
+            BuilderEvent::skipped_op("tag".to_string(), skipped, SkipReason::TargetGasLimit),
+            BuilderEvent::rejected_op(
+                "tag".to_string(),
+                rejected,
+                OpRejectionReason::FailedInBundle {
+                    message: "reverted".to_string().into(),
+                },
+            ),
+            BuilderEvent::formed_bundle("tag".to_string(), None, 0, 0, Some(GasFees::default())),
+        ] {
+            tx.send(WithEntryPoint { entry_point, event }).unwrap();
+        }
+
+        let (rejected_ops, skipped_ops) = previewer.take_excluded_ops();
+        assert_eq!(
+            skipped_ops,
+            vec![BundlePreviewExcludedOp {
+                hash: skipped,
+                reason: "TargetGasLimit".to_string(),
+            }]
+        );
+        assert_eq!(rejected_ops.len(), 1);
+        assert_eq!(rejected_ops[0].hash, rejected);
+        assert!(rejected_ops[0].reason.contains("reverted"));
+
+        // drained
+        let (rejected_ops, skipped_ops) = previewer.take_excluded_ops();
+        assert!(rejected_ops.is_empty());
+        assert!(skipped_ops.is_empty());
+    }
+
+    fn test_settings() -> Settings {
+        Settings {
+            chain_spec: ChainSpec::default(),
+            sender_eoa: watch::channel(Address::ZERO).1,
+            filter_id: None,
+            max_bundle_size: 10,
+            max_pool_ops_per_request: 100,
+            ordering: BundleOrderingStrategy::default(),
+        }
+    }
+}
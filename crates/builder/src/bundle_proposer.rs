@@ -20,7 +20,7 @@ use std::{
     time::Instant,
 };
 
-use alloy_primitives::{Address, Bytes, B256, U256};
+use alloy_primitives::{Address, Bytes, LogData, B256, U256};
 use anyhow::Context;
 use async_trait::async_trait;
 use futures::future;
@@ -29,9 +29,15 @@ use metrics::{Counter, Histogram};
 use metrics_derive::Metrics;
 #[cfg(test)]
 use mockall::automock;
+use rundler_contracts::{
+    v0_6::IEntryPoint::UserOperationEvent as UserOperationEventV06,
+    v0_7::IEntryPoint::UserOperationEvent as UserOperationEventV07,
+};
 use rundler_provider::{
     BundleHandler, DAGasOracleSync, DAGasProvider, EntryPoint, EvmProvider, FeeEstimator,
-    HandleOpsOut, ProvidersWithEntryPointT,
+    GethDebugBuiltInTracerType, GethDebugTracerCallConfig, GethDebugTracerCallFrame,
+    GethDebugTracerType, GethDebugTracingCallOptions, GethDebugTracingOptions, HandleOpsOut, Log,
+    ProvidersWithEntryPointT,
 };
 use rundler_sim::{SimulationError, SimulationResult, Simulator, ViolationError};
 use rundler_types::{
@@ -49,7 +55,10 @@ use rundler_utils::{emit::WithEntryPoint, guard_timer::CustomTimerGuard, math};
 use tokio::sync::broadcast;
 use tracing::{debug, error, info, warn};
 
-use crate::emit::{BuilderEvent, ConditionNotMetReason, OpRejectionReason, SkipReason};
+use crate::{
+    bundle_ordering::{BundleOrderingStrategy, OrderingInfo},
+    emit::{BuilderEvent, BundleProfit, ConditionNotMetReason, OpRejectionReason, SkipReason},
+};
 
 /// Extra buffer percent to add on the bundle transaction gas estimate to be sure it will be enough
 const BUNDLE_TRANSACTION_GAS_OVERHEAD_PERCENT: u32 = 5;
@@ -62,6 +71,7 @@ pub(crate) struct Bundle<UO: UserOperation> {
     pub(crate) expected_storage: ExpectedStorage,
     pub(crate) rejected_ops: Vec<UO>,
     pub(crate) entity_updates: Vec<EntityUpdate>,
+    pub(crate) profit: BundleProfit,
 }
 
 impl<UO: UserOperation> Default for Bundle<UO> {
@@ -73,6 +83,7 @@ impl<UO: UserOperation> Default for Bundle<UO> {
             expected_storage: ExpectedStorage::default(),
             rejected_ops: Vec::new(),
             entity_updates: Vec::new(),
+            profit: BundleProfit::default(),
         }
     }
 }
@@ -107,6 +118,7 @@ pub(crate) trait BundleProposer: Send + Sync {
         &mut self,
         ops: Vec<PoolOperation>,
         block_hash: B256,
+        block_number: u64,
         max_bundle_fee: U256,
         min_gas_fees: Option<GasFees>,
         is_replacement: bool,
@@ -123,6 +135,9 @@ pub(crate) trait BundleProposer: Send + Sync {
 
     /// Notifies the proposer that a condition was not met during the last bundle proposal
     fn notify_condition_not_met(&mut self);
+
+    /// Sets the address bundles are sent from, after the builder's signer was replaced
+    fn set_sender_eoa(&mut self, sender_eoa: Address);
 }
 
 pub(crate) type BundleProposerResult<T> = std::result::Result<T, BundleProposerError>;
@@ -148,16 +163,19 @@ pub(crate) struct BundleProposerImpl<EP, BP> {
     metrics: BuilderProposerMetrics,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) struct Settings {
     pub(crate) chain_spec: ChainSpec,
     pub(crate) target_bundle_gas: u128,
     pub(crate) max_bundle_gas: u128,
+    pub(crate) max_bundle_size: u64,
     pub(crate) sender_eoa: Address,
     pub(crate) da_gas_tracking_enabled: bool,
     pub(crate) max_expected_storage_slots: usize,
     pub(crate) verification_gas_limit_efficiency_reject_threshold: f64,
     pub(crate) submission_proxy: Option<Arc<dyn SubmissionProxy>>,
+    pub(crate) ordering: BundleOrderingStrategy,
+    pub(crate) min_profit_margin_pct: Option<i32>,
 }
 
 #[async_trait]
@@ -184,10 +202,15 @@ where
         self.condition_not_met_notified = true;
     }
 
+    fn set_sender_eoa(&mut self, sender_eoa: Address) {
+        self.settings.sender_eoa = sender_eoa;
+    }
+
     async fn make_bundle(
         &mut self,
         ops: Vec<PoolOperation>,
         block_hash: B256,
+        block_number: u64,
         max_bundle_fee: U256,
         min_gas_fees: Option<GasFees>,
         is_replacement: bool,
@@ -250,7 +273,24 @@ where
             return Err(BundleProposerError::NoOperationsAfterFeeFilter);
         }
 
-        // (2) Limit the amount of operations for simulation
+        // (2) Order ops by the builder's strategy, then truncate to the max bundle size
+        let bundle_gas_price = bundle_fees.gas_price(base_fee);
+        let ops = ops
+            .into_iter()
+            .map(|op| {
+                let info = self.ordering_info(&op, base_fee, bundle_gas_price);
+                (op, info)
+            })
+            .collect::<Vec<_>>();
+        let ops = self
+            .settings
+            .ordering
+            .order(ops, block_number, self.settings.target_bundle_gas)
+            .into_iter()
+            .take(self.settings.max_bundle_size as usize)
+            .collect::<Vec<_>>();
+
+        // (3) Limit the amount of operations for simulation
         let (ops, gas_limit) = self.limit_user_operations_for_simulation(ops);
 
         debug!(
@@ -259,7 +299,7 @@ where
             gas_limit
         );
 
-        // (3) simulate ops
+        // (4) simulate ops
         let simulation_futures = ops
             .into_iter()
             .map(|op| self.simulate_op(op, block_hash))
@@ -285,6 +325,8 @@ where
                 balances_by_paymaster,
             )
             .await;
+        // charged once per bundle transaction rather than to each op's DA gas
+        context.bundle_fee = da_block_data.as_ref().map_or(0, DAGasBlockData::bundle_fee);
         while !context.is_empty() {
             let gas_estimate = self
                 .estimate_gas_rejecting_failed_ops(&mut context, bundle_fees)
@@ -306,6 +348,19 @@ where
                     }
                 }
 
+                context.simulated_gas = self
+                    .simulate_bundle_gas(&context, gas_estimate, bundle_fees, block_hash)
+                    .await;
+
+                // Drop the least profitable ops until the bundle meets the minimum margin,
+                // then estimate again without them
+                if self
+                    .drop_unprofitable_ops(&mut context, base_fee, bundle_gas_price)
+                    .await
+                {
+                    continue;
+                }
+
                 // bundle built, record time
                 self.metrics
                     .bundle_build_ms
@@ -317,6 +372,11 @@ where
                     expected_storage: context.bundle_expected_storage.inner,
                     rejected_ops: context.rejected_ops.iter().map(|po| po.0.clone()).collect(),
                     entity_updates: context.entity_updates.into_values().collect(),
+                    profit: context.get_bundle_profit(
+                        &self.settings.chain_spec,
+                        base_fee,
+                        bundle_gas_price,
+                    ),
                 });
             }
 
@@ -344,6 +404,8 @@ struct BuilderProposerMetrics {
     bundle_simulation_failures: Counter,
     #[metric(describe = "the distribution of bundle simulation time.")]
     bundle_simulation_ms: Histogram,
+    #[metric(describe = "the number of ops skipped to meet the minimum bundle profit margin.")]
+    unprofitable_ops_skipped: Counter,
 }
 
 impl<EP, BP> BundleProposerImpl<EP, BP>
@@ -360,13 +422,16 @@ where
         event_sender: broadcast::Sender<WithEntryPoint<BuilderEvent>>,
     ) -> Self {
         Self {
+            metrics: BuilderProposerMetrics::new_with_labels(&[(
+                "builder_tag",
+                builder_tag.clone(),
+            )]),
             builder_tag,
             ep_providers,
             bundle_providers,
             settings,
             event_sender,
             condition_not_met_notified: false,
-            metrics: BuilderProposerMetrics::default(),
         }
     }
 
@@ -420,6 +485,7 @@ where
             return Some(PoolOperationWithSponsoredDAGas {
                 op,
                 sponsored_da_gas: 0,
+                required_da_gas: 0,
             });
         }
 
@@ -535,9 +601,37 @@ where
         Some(PoolOperationWithSponsoredDAGas {
             op,
             sponsored_da_gas,
+            required_da_gas,
         })
     }
 
+    // Information used to order an op, estimating its profit as the fees it pays for its
+    // gas and pre-verification gas minus the cost of its computation and DA gas in the bundle
+    fn ordering_info(
+        &self,
+        op: &PoolOperationWithSponsoredDAGas,
+        base_fee: u128,
+        bundle_gas_price: u128,
+    ) -> OrderingInfo {
+        let uo = &op.op.uo;
+        let gas_limit = uo.bundle_computation_gas_limit(&self.settings.chain_spec, None);
+        let revenue = if op.op.perms.bundler_sponsorship.is_some() {
+            0
+        } else {
+            expected_op_revenue(uo, &self.settings.chain_spec, base_fee)
+        };
+        let cost = bundle_gas_price.saturating_mul(gas_limit + op.required_da_gas);
+
+        OrderingInfo {
+            sim_block_number: op.op.sim_block_number,
+            priority_tier: op.op.perms.priority_tier.unwrap_or_default(),
+            gas_limit,
+            profit: i128::try_from(revenue)
+                .unwrap_or(i128::MAX)
+                .saturating_sub(i128::try_from(cost).unwrap_or(i128::MAX)),
+        }
+    }
+
     // Simulate a single op. Returns None if the op should be skipped.
     //
     // Filters on any errors
@@ -695,6 +789,8 @@ where
                     op: op.clone().into(),
                     simulation: simulation.clone(),
                     sponsored_da_gas: po.sponsored_da_gas,
+                    required_da_gas: po.required_da_gas,
+                    bundler_sponsored: po.op.perms.bundler_sponsorship.is_some(),
                 });
 
             // Limit by max bundle computation gas (excluding DA gas)
@@ -803,6 +899,8 @@ where
                     op: op.into(),
                     simulation,
                     sponsored_da_gas: po.sponsored_da_gas,
+                    required_da_gas: po.required_da_gas,
+                    bundler_sponsored: po.op.perms.bundler_sponsorship.is_some(),
                 });
         }
 
@@ -1063,6 +1161,108 @@ where
         }
     }
 
+    // Traces the bundle's `handleOps` call to measure the gas the bundle uses and the gas fees each
+    // op pays the beneficiary. Returns None if the trace fails, the bundle's profit is then estimated
+    // from gas limits.
+    async fn simulate_bundle_gas(
+        &self,
+        context: &ProposalContext<<Self as BundleProposer>::UO>,
+        gas_limit: u64,
+        bundle_fees: GasFees,
+        block_hash: B256,
+    ) -> Option<SimulatedBundleGas> {
+        let entry_point = self.ep_providers.entry_point();
+        let tx = entry_point.get_send_bundle_transaction(
+            context.to_ops_per_aggregator(),
+            self.settings.sender_eoa,
+            gas_limit,
+            bundle_fees,
+            self.settings.submission_proxy.as_ref().map(|p| p.address()),
+        );
+        let trace_options = GethDebugTracingCallOptions {
+            tracing_options: GethDebugTracingOptions::new_tracer(
+                GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::CallTracer),
+            )
+            .with_call_config(GethDebugTracerCallConfig::default().with_log()),
+            state_overrides: None,
+            block_overrides: None,
+        };
+
+        let frame = match self
+            .ep_providers
+            .evm()
+            .debug_trace_call(tx, Some(block_hash.into()), trace_options)
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|trace| {
+                trace
+                    .try_into_call_frame()
+                    .context("trace is not a call tracer")
+            }) {
+            Ok(frame) => frame,
+            Err(e) => {
+                warn!("Failed to trace bundle gas, estimating profit from gas limits: {e:?}");
+                return None;
+            }
+        };
+        if let Some(error) = &frame.error {
+            warn!("Traced bundle failed with {error}, estimating profit from gas limits");
+            return None;
+        }
+
+        let pre_verification_gas = context
+            .iter_ops()
+            .map(|op| (op.hash(), op.pre_verification_gas()))
+            .collect();
+        Some(SimulatedBundleGas::from_trace(
+            &frame,
+            *entry_point.address(),
+            entry_point.version(),
+            &pre_verification_gas,
+        ))
+    }
+
+    // Skips the least profitable ops, leaving them in the pool, until the bundle meets the builder's
+    // minimum profit margin. Ops sponsored by the bundler are never skipped. Returns true if any op
+    // was skipped.
+    async fn drop_unprofitable_ops(
+        &self,
+        context: &mut ProposalContext<<Self as BundleProposer>::UO>,
+        base_fee: u128,
+        gas_price: u128,
+    ) -> bool {
+        let Some(min_margin_pct) = self.settings.min_profit_margin_pct else {
+            return false;
+        };
+
+        let mut skipped = false;
+        while !context.is_empty() {
+            let profit = context.get_bundle_profit(&self.settings.chain_spec, base_fee, gas_price);
+            if profit.meets_margin(min_margin_pct) {
+                break;
+            }
+            let Some((index, op_hash)) =
+                context.get_least_profitable_op(&self.settings.chain_spec, base_fee, gas_price)
+            else {
+                warn!("Bundle does not meet the minimum profit margin of {min_margin_pct}% but only contains sponsored ops: {profit:?}");
+                break;
+            };
+
+            info!("Skipping op {op_hash:?} because the bundle does not meet the minimum profit margin of {min_margin_pct}%: {profit:?}");
+            self.emit(BuilderEvent::skipped_op(
+                self.builder_tag.clone(),
+                op_hash,
+                SkipReason::BelowMinProfitMargin { profit },
+            ));
+            self.metrics.unprofitable_ops_skipped.increment(1);
+            let changed_aggregator = context.skip_index(index);
+            self.compute_aggregator_signatures(context, &changed_aggregator)
+                .await;
+            skipped = true;
+        }
+        skipped
+    }
+
     async fn get_balances_by_paymaster(
         &self,
         addresses: impl IntoIterator<Item = Address>,
@@ -1375,6 +1575,15 @@ where
     }
 }
 
+// Upper bound of the gas fees paid to the beneficiary by an op, its gas price for its computation gas
+// limit and pre-verification gas, without counting the static overhead included in both twice. Used
+// before the bundle is traced.
+fn expected_op_revenue<UO: UserOperation>(uo: &UO, chain_spec: &ChainSpec, base_fee: u128) -> u128 {
+    let paid_gas = (uo.bundle_computation_gas_limit(chain_spec, None) + uo.pre_verification_gas())
+        .saturating_sub(uo.static_pre_verification_gas(chain_spec));
+    uo.gas_price(base_fee).saturating_mul(paid_gas)
+}
+
 // Type erasure for the bundle proposer providers
 pub(crate) trait BundleProposerProvidersT: Send + Sync {
     type UO: UserOperation + From<UserOperationVariant>;
@@ -1410,6 +1619,7 @@ where
 struct PoolOperationWithSponsoredDAGas {
     op: PoolOperation,
     sponsored_da_gas: u128,
+    required_da_gas: u128,
 }
 
 #[derive(Debug, Clone)]
@@ -1417,6 +1627,94 @@ struct OpWithSimulation<UO> {
     op: UO,
     simulation: SimulationResult,
     sponsored_da_gas: u128,
+    required_da_gas: u128,
+    bundler_sponsored: bool,
+}
+
+/// Gas used by a bundle and its ops in a traced `handleOps` call
+#[derive(Debug, Clone, Default)]
+struct SimulatedBundleGas {
+    // Gas used by the bundle transaction outside of its ops
+    shared_gas: u128,
+    ops: HashMap<B256, SimulatedOpGas>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SimulatedOpGas {
+    // `actualGasCost` of the op's `UserOperationEvent`, including the v0.7 penalty for unused
+    // execution gas
+    actual_gas_cost: U256,
+    // `actualGasUsed` of the op's `UserOperationEvent` without its pre-verification gas
+    gas_used: u128,
+}
+
+impl SimulatedBundleGas {
+    fn from_trace(
+        frame: &GethDebugTracerCallFrame,
+        entry_point: Address,
+        version: EntryPointVersion,
+        pre_verification_gas: &HashMap<B256, u128>,
+    ) -> Self {
+        let mut ops = HashMap::new();
+        let mut frames = vec![frame];
+        while let Some(frame) = frames.pop() {
+            for log in frame.logs.iter().filter(|l| l.address == Some(entry_point)) {
+                let log = Log {
+                    inner: alloy_primitives::Log {
+                        address: entry_point,
+                        data: LogData::new_unchecked(
+                            log.topics.clone().unwrap_or_default(),
+                            log.data.clone().unwrap_or_default(),
+                        ),
+                    },
+                    ..Default::default()
+                };
+                let Some((hash, actual_gas_cost, actual_gas_used)) =
+                    decode_user_operation_event(version, &log)
+                else {
+                    continue;
+                };
+                let pvg = pre_verification_gas.get(&hash).copied().unwrap_or_default();
+                ops.insert(
+                    hash,
+                    SimulatedOpGas {
+                        actual_gas_cost,
+                        gas_used: actual_gas_used.saturating_to::<u128>().saturating_sub(pvg),
+                    },
+                );
+            }
+            frames.extend(&frame.calls);
+        }
+
+        let ops_gas = ops.values().map(|op| op.gas_used).sum::<u128>();
+        Self {
+            shared_gas: frame
+                .gas_used
+                .saturating_to::<u128>()
+                .saturating_sub(ops_gas),
+            ops,
+        }
+    }
+}
+
+// Returns the hash, `actualGasCost` and `actualGasUsed` of a `UserOperationEvent` log
+pub(crate) fn decode_user_operation_event(
+    version: EntryPointVersion,
+    log: &Log,
+) -> Option<(B256, U256, U256)> {
+    match version {
+        EntryPointVersion::V0_6 => log.log_decode::<UserOperationEventV06>().ok().map(|l| {
+            let event = l.inner.data;
+            (event.userOpHash, event.actualGasCost, event.actualGasUsed)
+        }),
+        EntryPointVersion::V0_7 | EntryPointVersion::V0_8 => {
+            log.log_decode::<UserOperationEventV07>().ok().map(|l| {
+                let event = l.inner.data;
+                (event.userOpHash, event.actualGasCost, event.actualGasUsed)
+            })
+        }
+        EntryPointVersion::Unspecified => None,
+    }
 }
 
 /// A struct used internally to represent the current state of a proposed bundle
@@ -1430,6 +1728,10 @@ struct ProposalContext<UO> {
     // This is a BTreeMap so that the conversion to a Vec<EntityUpdate> is deterministic, mainly for tests
     entity_updates: BTreeMap<Address, EntityUpdate>,
     bundle_expected_storage: BundleExpectedStorage,
+    // Fixed fee of the bundle transaction in wei, i.e. the OP stack operator fee constant
+    bundle_fee: u128,
+    // Gas measured by tracing the bundle, None until it is traced
+    simulated_gas: Option<SimulatedBundleGas>,
 }
 
 #[derive(Debug, Clone)]
@@ -1454,6 +1756,8 @@ impl<UO: UserOperation> ProposalContext<UO> {
             rejected_ops: Vec::<(UO, EntityInfos)>::new(),
             entity_updates: BTreeMap::new(),
             bundle_expected_storage: BundleExpectedStorage::default(),
+            bundle_fee: 0,
+            simulated_gas: None,
         }
     }
 
@@ -1501,18 +1805,36 @@ impl<UO: UserOperation> ProposalContext<UO> {
     /// may need to be recomputed.
     #[must_use = "rejected op but did not update aggregator signatures"]
     fn reject_index(&mut self, i: usize, paymaster_amendment: bool) -> Option<Address> {
+        let (rejected, changed_aggregator) = self.remove_index(i)?;
+        self.reject_op(rejected, paymaster_amendment);
+        changed_aggregator
+    }
+
+    /// Removes the op at the index from the bundle without rejecting it from the pool.
+    ///
+    /// Returns the address of the op's aggregator if the aggregator's signature
+    /// may need to be recomputed.
+    #[must_use = "skipped op but did not update aggregator signatures"]
+    fn skip_index(&mut self, i: usize) -> Option<Address> {
+        let (skipped, changed_aggregator) = self.remove_index(i)?;
+        self.bundle_expected_storage
+            .remove(&skipped.simulation.expected_storage);
+        changed_aggregator
+    }
+
+    // Removes the op at the index, returning it along with the address of its aggregator if
+    // the aggregator's signature may need to be recomputed.
+    fn remove_index(&mut self, i: usize) -> Option<(OpWithSimulation<UO>, Option<Address>)> {
         let mut remaining_i = i;
-        let mut found_aggregator: Option<Address> = None;
+        let mut found: Option<(Address, OpWithSimulation<UO>)> = None;
         for (&aggregator, group) in &mut self.groups_by_aggregator {
             if remaining_i < group.ops_with_simulations.len() {
-                let rejected = group.ops_with_simulations.remove(remaining_i);
-                self.reject_op(rejected, paymaster_amendment);
-                found_aggregator = Some(aggregator);
+                found = Some((aggregator, group.ops_with_simulations.remove(remaining_i)));
                 break;
             }
             remaining_i -= group.ops_with_simulations.len();
         }
-        let Some(found_aggregator) = found_aggregator else {
+        let Some((found_aggregator, removed)) = found else {
             error!("The entry point indicated a failed op at index {i}, but the bundle size is only {}", i - remaining_i);
             return None;
         };
@@ -1523,9 +1845,9 @@ impl<UO: UserOperation> ProposalContext<UO> {
             .is_empty()
         {
             self.groups_by_aggregator.remove(&found_aggregator);
-            None
+            Some((removed, None))
         } else {
-            Some(found_aggregator)
+            Some((removed, Some(found_aggregator)))
         }
     }
 
@@ -1664,6 +1986,103 @@ impl<UO: UserOperation> ProposalContext<UO> {
         U256::from(self.get_bundle_gas_limit_inner(chain_spec, true)) * U256::from(gas_price)
     }
 
+    // Get the expected revenue and cost of the bundle at the given base fee and bundle gas price.
+    // Once the bundle is traced, the cost is for the gas it used and the revenue is the gas fees its
+    // ops paid, otherwise both are estimated from gas limits. The cost includes the DA gas of every op,
+    // whether or not it is included in the gas limit, and the fixed fee of the bundle transaction.
+    fn get_bundle_profit(
+        &self,
+        chain_spec: &ChainSpec,
+        base_fee: u128,
+        gas_price: u128,
+    ) -> BundleProfit {
+        let da_gas = self
+            .iter_ops_with_simulations()
+            .map(|sim_op| sim_op.required_da_gas)
+            .sum::<u128>();
+        let gas = match &self.simulated_gas {
+            Some(simulated_gas) => {
+                simulated_gas.shared_gas
+                    + self
+                        .iter_ops_with_simulations()
+                        .map(|sim_op| self.op_gas(sim_op, chain_spec))
+                        .sum::<u128>()
+            }
+            None => self.get_bundle_computation_gas_limit(chain_spec),
+        };
+        let mut profit = BundleProfit {
+            cost: U256::from(gas + da_gas) * U256::from(gas_price) + U256::from(self.bundle_fee),
+            ..Default::default()
+        };
+
+        for sim_op in self.iter_ops_with_simulations() {
+            if sim_op.bundler_sponsored {
+                profit.sponsored_cost += U256::from(self.op_cost(sim_op, chain_spec, gas_price));
+            } else {
+                profit.revenue += self.op_revenue(sim_op, chain_spec, base_fee);
+            }
+        }
+
+        profit
+    }
+
+    // Get the index and hash of the op with the lowest expected profit, excluding ops sponsored by the bundler
+    fn get_least_profitable_op(
+        &self,
+        chain_spec: &ChainSpec,
+        base_fee: u128,
+        gas_price: u128,
+    ) -> Option<(usize, B256)> {
+        self.iter_ops_with_simulations()
+            .enumerate()
+            .filter(|(_, sim_op)| !sim_op.bundler_sponsored)
+            .min_by_key(|(_, sim_op)| {
+                let revenue = self
+                    .op_revenue(sim_op, chain_spec, base_fee)
+                    .saturating_to::<u128>();
+                let cost = self.op_cost(sim_op, chain_spec, gas_price);
+                i128::try_from(revenue)
+                    .unwrap_or(i128::MAX)
+                    .saturating_sub(i128::try_from(cost).unwrap_or(i128::MAX))
+            })
+            .map(|(i, sim_op)| (i, sim_op.op.hash()))
+    }
+
+    // Get the cost of an op's computation and DA gas in the bundle, excluding shared gas
+    fn op_cost(
+        &self,
+        sim_op: &OpWithSimulation<UO>,
+        chain_spec: &ChainSpec,
+        gas_price: u128,
+    ) -> u128 {
+        gas_price.saturating_mul(self.op_gas(sim_op, chain_spec) + sim_op.required_da_gas)
+    }
+
+    // Get the computation gas used by an op in the traced bundle, or its gas limit if not traced
+    fn op_gas(&self, sim_op: &OpWithSimulation<UO>, chain_spec: &ChainSpec) -> u128 {
+        match self.simulated_op_gas(sim_op) {
+            Some(op_gas) => op_gas.gas_used,
+            None => sim_op.op.bundle_computation_gas_limit(chain_spec, None),
+        }
+    }
+
+    // Get the gas fees an op paid the beneficiary in the traced bundle, or an upper bound if not traced
+    fn op_revenue(
+        &self,
+        sim_op: &OpWithSimulation<UO>,
+        chain_spec: &ChainSpec,
+        base_fee: u128,
+    ) -> U256 {
+        match self.simulated_op_gas(sim_op) {
+            Some(op_gas) => op_gas.actual_gas_cost,
+            None => U256::from(expected_op_revenue(&sim_op.op, chain_spec, base_fee)),
+        }
+    }
+
+    fn simulated_op_gas(&self, sim_op: &OpWithSimulation<UO>) -> Option<&SimulatedOpGas> {
+        self.simulated_gas.as_ref()?.ops.get(&sim_op.op.hash())
+    }
+
     // Get the bundle gas limit
     fn get_bundle_gas_limit(&self, chain_spec: &ChainSpec) -> u128 {
         self.get_bundle_gas_limit_inner(chain_spec, chain_spec.include_da_gas_in_gas_limit)
@@ -1970,10 +2389,11 @@ mod tests {
     use std::time::Duration;
 
     use alloy_primitives::{utils::parse_units, Address, B256};
+    use alloy_sol_types::SolEvent;
     use anyhow::anyhow;
     use rundler_provider::{
         MockDAGasOracleSync, MockEntryPointV0_6, MockEvmProvider, MockFeeEstimator,
-        ProvidersWithEntryPoint,
+        ProvidersWithEntryPoint, TransactionRequest,
     };
     use rundler_sim::MockSimulator;
     use rundler_types::{
@@ -2936,6 +3356,8 @@ mod tests {
                             ..Default::default()
                         },
                         sponsored_da_gas: 100_000,
+                        required_da_gas: 100_000,
+                        bundler_sponsored: false,
                     },
                     OpWithSimulation {
                         op: op2.clone(),
@@ -2944,6 +3366,8 @@ mod tests {
                             ..Default::default()
                         },
                         sponsored_da_gas: 0,
+                        required_da_gas: 0,
+                        bundler_sponsored: false,
                     },
                 ],
                 signature: Default::default(),
@@ -2954,6 +3378,8 @@ mod tests {
             rejected_ops: vec![],
             entity_updates: BTreeMap::new(),
             bundle_expected_storage: BundleExpectedStorage::default(),
+            bundle_fee: 0,
+            simulated_gas: None,
         };
 
         // DA gas is not included in the gas limit
@@ -2984,6 +3410,8 @@ mod tests {
                             ..Default::default()
                         },
                         sponsored_da_gas: 0,
+                        required_da_gas: 0,
+                        bundler_sponsored: false,
                     },
                     OpWithSimulation {
                         op: op2.clone(),
@@ -2992,6 +3420,8 @@ mod tests {
                             ..Default::default()
                         },
                         sponsored_da_gas: 0,
+                        required_da_gas: 0,
+                        bundler_sponsored: false,
                     },
                 ],
                 signature: Default::default(),
@@ -3002,6 +3432,8 @@ mod tests {
             rejected_ops: vec![],
             entity_updates: BTreeMap::new(),
             bundle_expected_storage: BundleExpectedStorage::default(),
+            bundle_fee: 0,
+            simulated_gas: None,
         };
         let gas_limit = context.get_bundle_gas_limit(&cs);
 
@@ -3012,6 +3444,115 @@ mod tests {
         assert_eq!(gas_limit, expected_gas_limit);
     }
 
+    #[test]
+    fn test_bundle_profit_includes_bundle_fee() {
+        let cs = ChainSpec::default();
+        let mut context = ProposalContext::<UserOperation>::new();
+        let without_fee = context.get_bundle_profit(&cs, 1, 10);
+
+        // the fee is charged once, not per op
+        context.bundle_fee = 1_000;
+        let with_fee = context.get_bundle_profit(&cs, 1, 10);
+        assert_eq!(with_fee.cost, without_fee.cost + U256::from(1_000));
+    }
+
+    #[test]
+    fn test_simulated_bundle_gas_from_trace() {
+        let entry_point = address(1);
+        let op_hash = hash(2);
+        let log = UserOperationEventV07 {
+            userOpHash: op_hash,
+            sender: address(3),
+            paymaster: Address::ZERO,
+            nonce: U256::ZERO,
+            success: true,
+            actualGasCost: U256::from(600_000),
+            actualGasUsed: U256::from(60_000),
+        }
+        .encode_log_data();
+
+        // the entry point is called through a proxy
+        let frame: GethDebugTracerCallFrame = serde_json::from_value(serde_json::json!({
+            "type": "CALL",
+            "from": address(4),
+            "to": address(5),
+            "input": "0x",
+            "gas": "0x100000",
+            "gasUsed": "0x186a0",
+            "calls": [{
+                "type": "CALL",
+                "from": address(5),
+                "to": entry_point,
+                "input": "0x",
+                "gas": "0x100000",
+                "gasUsed": "0x15f90",
+                "logs": [{
+                    "address": entry_point,
+                    "topics": log.topics(),
+                    "data": log.data,
+                }],
+            }],
+        }))
+        .unwrap();
+
+        let simulated = SimulatedBundleGas::from_trace(
+            &frame,
+            entry_point,
+            EntryPointVersion::V0_7,
+            &HashMap::from([(op_hash, 10_000)]),
+        );
+        let op_gas = simulated.ops[&op_hash];
+        assert_eq!(op_gas.actual_gas_cost, U256::from(600_000));
+        assert_eq!(op_gas.gas_used, 50_000);
+        assert_eq!(simulated.shared_gas, 50_000);
+    }
+
+    #[test]
+    fn test_bundle_profit_uses_simulated_gas() {
+        let cs = ChainSpec::default();
+        let op = op_from_required(UserOperationRequiredFields {
+            sender: address(1),
+            call_gas_limit: 1_000_000,
+            verification_gas_limit: 100_000,
+            pre_verification_gas: DEFAULT_PVG,
+            max_fee_per_gas: 100,
+            max_priority_fee_per_gas: 100,
+            ..Default::default()
+        });
+        let mut context = ProposalContext::<UserOperation>::new();
+        context.groups_by_aggregator.insert(
+            Address::ZERO,
+            AggregatorGroup {
+                ops_with_simulations: vec![OpWithSimulation {
+                    op: op.clone(),
+                    simulation: SimulationResult::default(),
+                    sponsored_da_gas: 0,
+                    required_da_gas: 0,
+                    bundler_sponsored: false,
+                }],
+                signature: Default::default(),
+            },
+        );
+        let estimated = context.get_bundle_profit(&cs, 1, 10);
+
+        context.simulated_gas = Some(SimulatedBundleGas {
+            shared_gas: 50_000,
+            ops: HashMap::from([(
+                op.hash(),
+                SimulatedOpGas {
+                    actual_gas_cost: U256::from(600_000),
+                    gas_used: 10_000,
+                },
+            )]),
+        });
+        let simulated = context.get_bundle_profit(&cs, 1, 10);
+        assert_eq!(simulated.revenue, U256::from(600_000));
+        assert_eq!(simulated.cost, U256::from(600_000));
+        // gas limits overstate both
+        assert!(estimated.revenue > simulated.revenue);
+        assert!(estimated.cost > simulated.cost);
+    }
+
     #[tokio::test]
     async fn test_post_op_revert() {
         let op1 = op_with_sender(address(1));
@@ -3717,6 +4258,7 @@ mod tests {
             vec![],
             None,
             U256::MAX,
+            None,
         )
         .await
         .expect_err("should fail to bundle");
@@ -3755,6 +4297,7 @@ mod tests {
             vec![],
             None,
             U256::MAX,
+            None,
         )
         .await
         .expect_err("should fail to bundle");
@@ -3829,6 +4372,87 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_min_profit_margin() {
+        let base_fee = 1_000;
+        let max_priority_fee_per_gas = 100;
+        // pays 10x the bundle gas price
+        let op1 = op_from_required(UserOperationRequiredFields {
+            sender: address(1),
+            call_gas_limit: 200_000,
+            max_fee_per_gas: 11_000,
+            max_priority_fee_per_gas: 10_000,
+            ..Default::default()
+        });
+        // pays the bundle gas price, but not for its share of the bundle's gas
+        let op2 = op_from_required(UserOperationRequiredFields {
+            sender: address(2),
+            call_gas_limit: 2_000_000,
+            max_fee_per_gas: base_fee + max_priority_fee_per_gas,
+            max_priority_fee_per_gas,
+            ..Default::default()
+        });
+
+        let bundle = mock_make_bundle_allow_error(
+            vec![
+                MockOp {
+                    op: op1.clone(),
+                    simulation_result: Box::new(|| Ok(SimulationResult::default())),
+                    perms: UserOperationPermissions::default(),
+                },
+                MockOp {
+                    op: op2.clone(),
+                    simulation_result: Box::new(|| Ok(SimulationResult::default())),
+                    perms: UserOperationPermissions::default(),
+                },
+            ],
+            vec![],
+            vec![HandleOpsOut::Success, HandleOpsOut::Success],
+            vec![],
+            base_fee,
+            max_priority_fee_per_gas,
+            false,
+            ExpectedStorage::default(),
+            false,
+            vec![],
+            None,
+            U256::MAX,
+            Some(100),
+        )
+        .await
+        .expect("should make a bundle");
+
+        // op2 is skipped, but not rejected
+        assert_eq!(bundle.rejected_ops, vec![]);
+        assert_eq!(
+            bundle.ops_per_aggregator,
+            vec![UserOpsPerAggregator {
+                user_ops: vec![op1.clone()],
+                ..Default::default()
+            }]
+        );
+        assert_eq!(
+            bundle.profit.revenue,
+            U256::from(expected_op_revenue(&op1, &ChainSpec::default(), base_fee))
+        );
+        assert!(bundle.profit.meets_margin(100));
+        assert!(!bundle.profit.meets_margin(1_000));
+    }
+
+    #[test]
+    fn test_bundle_profit_meets_margin() {
+        let profit = BundleProfit {
+            revenue: U256::from(90),
+            cost: U256::from(120),
+            sponsored_cost: U256::from(20),
+        };
+        // sponsored cost is excluded from the margin
+        assert!(!profit.meets_margin(0));
+        assert!(profit.meets_margin(-10));
+        assert!(!profit.meets_margin(-9));
+        assert!(profit.meets_margin(-200));
+    }
+
     struct MockOp {
         op: UserOperation,
         simulation_result: Box<dyn Fn() -> Result<SimulationResult, SimulationError> + Send + Sync>,
@@ -3888,6 +4512,7 @@ mod tests {
             aggregators,
             proxy,
             max_bundle_fee,
+            None,
         )
         .await
         .expect("should make a bundle")
@@ -3907,6 +4532,7 @@ mod tests {
         aggregators: Vec<MockSignatureAggregator>,
         proxy: Option<MockSubmissionProxy>,
         max_bundle_fee: U256,
+        min_profit_margin_pct: Option<i32>,
     ) -> BundleProposerResult<Bundle<UserOperation>> {
         let mut chain_spec = ChainSpec {
             da_pre_verification_gas: da_gas_tracking_enabled,
@@ -3973,6 +4599,9 @@ mod tests {
                 .times(..=1)
                 .return_once(move |_, _| Ok(deposit));
         }
+        entry_point
+            .expect_get_send_bundle_transaction()
+            .returning(|_, _, _, _, _| TransactionRequest::default());
 
         let signatures_by_aggregator: HashMap<_, _> = mock_aggregators
             .into_iter()
@@ -3983,6 +4612,10 @@ mod tests {
         provider
             .expect_get_latest_block_hash_and_number()
             .returning(move || Ok((current_block_hash, 0)));
+        // profit is estimated from gas limits
+        provider
+            .expect_debug_trace_call()
+            .returning(|_, _, _| Err(anyhow!("tracing not supported").into()));
 
         let mut fee_estimator = MockFeeEstimator::new();
         fee_estimator
@@ -4025,6 +4658,8 @@ mod tests {
             l1_base_fee: 1,
             blob_base_fee: 1,
             blob_base_fee_scalar: 1,
+            is_fjord: true,
+            operator_fee_constant: 0,
         });
         let bd_cloned = block_data.clone();
         da_oracle
@@ -4059,11 +4694,14 @@ mod tests {
                 chain_spec,
                 target_bundle_gas: 10_000_000,
                 max_bundle_gas: 25_000_000,
+                max_bundle_size: 128,
                 sender_eoa,
                 da_gas_tracking_enabled,
                 max_expected_storage_slots: MAX_EXPECTED_STORAGE_SLOTS,
                 verification_gas_limit_efficiency_reject_threshold: 0.5,
                 submission_proxy,
+                ordering: BundleOrderingStrategy::default(),
+                min_profit_margin_pct,
             },
             event_sender,
         );
@@ -4073,7 +4711,7 @@ mod tests {
         }
 
         proposer
-            .make_bundle(ops, current_block_hash, max_bundle_fee, None, false)
+            .make_bundle(ops, current_block_hash, 0, max_bundle_fee, None, false)
             .await
     }
 
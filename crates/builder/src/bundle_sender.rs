@@ -11,14 +11,20 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::{pin::Pin, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashSet},
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
 
-use alloy_primitives::{Address, B256};
+use alloy_primitives::{utils::format_units, Address, B256, U256};
 use anyhow::{bail, Context};
 use async_trait::async_trait;
 use futures::Stream;
 use futures_util::StreamExt;
-use metrics::Counter;
+use metrics::{Counter, Histogram};
 use metrics_derive::Metrics;
 #[cfg(test)]
 use mockall::automock;
@@ -27,35 +33,45 @@ use rundler_provider::{
     GethDebugTracerType, GethDebugTracingOptions, HandleOpsOut, ProvidersWithEntryPointT,
     TransactionRequest,
 };
+use rundler_signer::SignerManager;
 use rundler_task::TaskSpawner;
 use rundler_types::{
     builder::BundlingMode,
     chain::ChainSpec,
+    da::DAGasOracleType,
     pool::{AddressUpdate, NewHead, Pool, PoolOperation},
     proxy::SubmissionProxy,
     EntityUpdate, ExpectedStorage, UserOperation,
 };
 use rundler_utils::emit::WithEntryPoint;
+use serde::Deserialize;
 use tokio::{
     join,
     sync::{
         broadcast, mpsc,
         mpsc::{UnboundedReceiver, UnboundedSender},
-        oneshot,
+        oneshot, watch,
     },
+    time::Instant,
 };
 use tracing::{debug, error, info, instrument, warn};
 
 use crate::{
     assigner::Assigner,
-    bundle_proposer::{Bundle, BundleProposer, BundleProposerError},
-    emit::{BuilderEvent, BundleTxDetails},
+    bundle_proposer::{self, Bundle, BundleProposer, BundleProposerError},
+    emit::{BuilderEvent, BundleProfit, BundleTxDetails},
+    signer_health::{SignerHealth, SignerHealthEvent},
     transaction_tracker::{
         TrackerState, TrackerUpdate, TransactionTracker, TransactionTrackerError,
     },
     BuilderSettings,
 };
 
+// Interval to check for a spare signer when replacing a quarantined signer
+const SPARE_SIGNER_POLL_INTERVAL: Duration = Duration::from_secs(10);
+// Interval to renew the lease of the builder's senders while it waits
+const LEASE_RENEW_INTERVAL: Duration = Duration::from_secs(10);
+
 #[async_trait]
 pub(crate) trait BundleSender: Send + Sync {
     async fn send_bundles_in_loop<T: TaskSpawner>(self, task_spawner: T);
@@ -66,6 +82,10 @@ pub(crate) struct Settings {
     pub(crate) max_replacement_underpriced_blocks: u64,
     pub(crate) max_cancellation_fee_increases: u64,
     pub(crate) max_blocks_to_wait_for_mine: u64,
+    pub(crate) max_pending_bundles: u64,
+    // Number of consecutive signer failures of the same kind before the signer is
+    // quarantined and replaced. If none, signers are never quarantined.
+    pub(crate) signer_quarantine_threshold: Option<u64>,
 }
 
 pub(crate) struct BundleSenderImpl<P, EP, T, C> {
@@ -74,12 +94,20 @@ pub(crate) struct BundleSenderImpl<P, EP, T, C> {
     bundle_action_receiver: Option<mpsc::Receiver<BundleSenderAction>>,
     chain_spec: ChainSpec,
     sender_eoa: Address,
+    // Publishes the sender address to the builder's previewer when the signer is replaced
+    sender_eoa_tx: watch::Sender<Address>,
+    signer_manager: Arc<dyn SignerManager>,
+    signer_health: SignerHealth,
+    // Set once the signer is unhealthy, the signer is replaced before the next step
+    quarantine_reason: Option<String>,
     // Optional submission proxy - bundles are sent through this contract
     submission_proxy: Option<Arc<dyn SubmissionProxy>>,
     proposer: P,
     ep_providers: EP,
     transaction_tracker: Option<T>,
     assigner: Arc<Assigner>,
+    // Last time the lease of the builder's senders was renewed while waiting
+    lease_renewed_at: Instant,
     pool: C,
     settings: Settings,
     event_sender: broadcast::Sender<WithEntryPoint<BuilderEvent>>,
@@ -171,12 +199,16 @@ where
             SenderMachineState::new(sender_trigger, self.transaction_tracker.take().unwrap());
 
         loop {
+            if let Some(reason) = self.quarantine_reason.take() {
+                self.replace_signer(&task_spawner, &mut state, reason).await;
+            }
+
             if let Err(e) = self.step_state(&mut state).await {
                 error!("Error in bundle sender loop: {e:#?}");
                 self.metrics.state_machine_errors.increment(1);
                 // release all operations, this may orphan an outstanding transaction and
                 // cause an onchain collision. Errors should be rare.
-                self.assigner.release_all(self.sender_eoa);
+                self.assigner.release_all(self.sender_eoa).await;
                 state.reset();
             }
         }
@@ -196,6 +228,8 @@ where
         bundle_action_receiver: mpsc::Receiver<BundleSenderAction>,
         chain_spec: ChainSpec,
         sender_eoa: Address,
+        sender_eoa_tx: watch::Sender<Address>,
+        signer_manager: Arc<dyn SignerManager>,
         submission_proxy: Option<Arc<dyn SubmissionProxy>>,
         proposer: P,
         ep_providers: EP,
@@ -219,10 +253,15 @@ where
             bundle_action_receiver: Some(bundle_action_receiver),
             chain_spec,
             sender_eoa,
+            sender_eoa_tx,
+            signer_manager,
+            signer_health: SignerHealth::new(settings.signer_quarantine_threshold),
+            quarantine_reason: None,
             submission_proxy,
             proposer,
             transaction_tracker: Some(transaction_tracker),
             assigner,
+            lease_renewed_at: Instant::now(),
             pool,
             settings,
             event_sender,
@@ -231,15 +270,163 @@ where
         }
     }
 
+    // Quarantines the builder's signer and continues with a spare signer.
+    //
+    // Once the transactions of the quarantined signer have settled, its senders are released so
+    // that other builders can bundle their operations. Waits until a spare signer is available.
+    async fn replace_signer<TS: TaskSpawner>(
+        &mut self,
+        task_spawner: &TS,
+        state: &mut SenderMachineState<T, BundleSenderTrigger>,
+        reason: String,
+    ) {
+        let old_eoa = self.sender_eoa;
+        error!("Quarantining bundle signer {old_eoa:?}: {reason}");
+        if let Err(e) = self.signer_manager.quarantine_signer(&old_eoa, reason) {
+            error!("Failed to quarantine signer {old_eoa:?}: {e:?}");
+        }
+        self.metrics.signers_quarantined.increment(1);
+        // a pending bundle can still mine, so its senders can't be bundled by another builder yet
+        self.settle_pending_transactions(state).await;
+        self.assigner.release_all(old_eoa).await;
+
+        let lease = loop {
+            if let Some(lease) = self.signer_manager.lease_signer() {
+                break lease;
+            }
+            warn!("No spare signer available to replace quarantined signer {old_eoa:?}, waiting");
+            tokio::time::sleep(SPARE_SIGNER_POLL_INTERVAL).await;
+        };
+        let new_eoa = lease.address();
+
+        // the lease stays quarantined when returned
+        let old_lease = state.transaction_tracker.replace_signer(lease).await;
+        self.signer_manager.return_lease(old_lease);
+
+        self.sender_eoa = new_eoa;
+        self.proposer.set_sender_eoa(new_eoa);
+        let _ = self.sender_eoa_tx.send(new_eoa);
+        state
+            .trigger
+            .resubscribe(task_spawner, &self.pool, new_eoa)
+            .await
+            .expect("Failed to resubscribe bundle sender trigger");
+
+        self.signer_health.reset();
+        state.reset();
+        info!("Replaced quarantined signer {old_eoa:?} with {new_eoa:?}");
+    }
+
+    // Waits for the pending transactions of the signer to mine.
+    //
+    // Transactions that don't mine within `max_blocks_to_wait_for_mine` blocks are cancelled,
+    // lowest nonce first. Gives up after `max_cancellation_fee_increases` cancellation attempts.
+    async fn settle_pending_transactions<TRIG: Trigger>(
+        &mut self,
+        state: &mut SenderMachineState<T, TRIG>,
+    ) {
+        let address = state.transaction_tracker.address();
+        let mut until = state.block_number() + self.settings.max_blocks_to_wait_for_mine;
+        let mut cancel_attempts = 0;
+
+        while state.transaction_tracker.num_pending_transactions() > 0 {
+            info!(
+                "Waiting for {} pending transactions of signer {address:?} to settle",
+                state.transaction_tracker.num_pending_transactions()
+            );
+            let block = match self.renewing_lease(state.trigger.wait_for_block()).await {
+                Ok(block) => block,
+                Err(e) => {
+                    error!("Failed to wait for pending transactions of signer {address:?}: {e:?}");
+                    return;
+                }
+            };
+            if let Some(update) = block.address_updates.iter().find(|u| u.address == address) {
+                match state.transaction_tracker.process_update(update).await {
+                    Ok(Some(update)) => info!("Tracker update for signer {address:?}: {update:?}"),
+                    Ok(None) => {}
+                    Err(e) => {
+                        error!("Failed to process update for signer {address:?}: {e:?}");
+                        return;
+                    }
+                }
+                for update in state.transaction_tracker.take_in_flight_updates() {
+                    info!("In flight tracker update for signer {address:?}: {update:?}");
+                }
+            }
+            if block.block_number < until
+                || state.transaction_tracker.num_pending_transactions() == 0
+            {
+                continue;
+            }
+
+            if cancel_attempts >= self.settings.max_cancellation_fee_increases {
+                warn!("Pending transactions of signer {address:?} not settled after {cancel_attempts} cancellation attempts, giving up");
+                self.metrics.cancellations_abandoned.increment(1);
+                return;
+            }
+            cancel_attempts += 1;
+
+            state.transaction_tracker.rewind_nonce();
+            let (estimated_fees, _) = self
+                .proposer
+                .estimate_gas_fees(block.block_hash, None)
+                .await
+                .unwrap_or_default();
+            match state
+                .transaction_tracker
+                .cancel_transaction(estimated_fees)
+                .await
+            {
+                Ok(Some(tx_hash)) => {
+                    info!("Sent cancellation {tx_hash:?} for pending transaction of signer {address:?}");
+                    self.metrics.cancellation_txns_sent.increment(1);
+                }
+                Ok(None) => {
+                    info!("Soft cancelled pending transaction of signer {address:?}");
+                    self.metrics.soft_cancellations.increment(1);
+                }
+                Err(e) => {
+                    warn!("Failed to cancel pending transaction of signer {address:?}: {e:?}");
+                    self.metrics.cancellation_txns_failed.increment(1);
+                }
+            }
+            until = block.block_number + self.settings.max_blocks_to_wait_for_mine;
+        }
+    }
+
+    // Waits for `wait` to complete, renewing the lease of the builder's senders every
+    // `LEASE_RENEW_INTERVAL` so that a builder waiting for its bundle keeps them
+    async fn renewing_lease<F: Future>(&mut self, wait: F) -> F::Output {
+        tokio::pin!(wait);
+        loop {
+            let renew_at = self.lease_renewed_at + LEASE_RENEW_INTERVAL;
+            tokio::select! {
+                output = &mut wait => return output,
+                _ = tokio::time::sleep_until(renew_at) => {
+                    self.assigner.renew_lease(self.sender_eoa).await;
+                    self.lease_renewed_at = Instant::now();
+                }
+            }
+        }
+    }
+
     #[instrument(skip_all, fields(entry_point = self.ep_address.to_string(), tag = self.builder_tag))]
     async fn step_state<TRIG: Trigger>(
         &mut self,
         state: &mut SenderMachineState<T, TRIG>,
     ) -> anyhow::Result<()> {
-        let tracker_update = state.wait_for_trigger().await?;
-        if tracker_update.is_some() {
+        let tracker_update = self.renewing_lease(state.wait_for_trigger()).await?;
+        if self.pipelining() {
+            self.process_in_flight_updates(state, tracker_update.as_ref())
+                .await;
+            if state.requires_reset {
+                return Ok(());
+            }
+            self.rewind_if_stalled(state);
+        } else if tracker_update.is_some() {
             // release all operations on any tracker update as all tracker updates mean that there are no longer any valid pending transactions
-            self.assigner.release_all(self.sender_eoa);
+            self.assigner.release_all(self.sender_eoa).await;
         }
 
         match state.inner {
@@ -277,10 +464,15 @@ where
         match result {
             Ok(SendBundleAttemptResult::Success(_)) => {
                 // sent the bundle
-                info!("Bundle sent successfully");
-                state.update(InnerState::Pending(inner.to_pending(
-                    block_number + self.settings.max_blocks_to_wait_for_mine,
-                )));
+                if self.can_pipeline(state) {
+                    info!("Bundle sent successfully, building next bundle with the next nonce");
+                    state.pipeline_next();
+                } else {
+                    info!("Bundle sent successfully");
+                    state.update(InnerState::Pending(inner.to_pending(
+                        block_number + self.settings.max_blocks_to_wait_for_mine,
+                    )));
+                }
             }
             Ok(SendBundleAttemptResult::NoOperationsInitially) => {
                 debug!("No operations available initially");
@@ -333,6 +525,7 @@ where
             Ok(SendBundleAttemptResult::NonceTooLow) => {
                 // reset the transaction tracker and try again
                 info!("Nonce too low, starting new bundle attempt");
+                self.record_signer_health(SignerHealthEvent::NonceTooLow);
                 state.reset();
             }
             Ok(SendBundleAttemptResult::Underpriced) => {
@@ -356,6 +549,7 @@ where
             Ok(SendBundleAttemptResult::InsufficientFunds) => {
                 // Insufficient funds
                 info!("Insufficient funds sending bundle, resetting state and starting new bundle attempt");
+                self.record_signer_health(SignerHealthEvent::InsufficientFunds);
                 state.reset();
             }
             Ok(SendBundleAttemptResult::Rejected) => {
@@ -372,6 +566,7 @@ where
                 self.metrics.bundle_txns_failed.increment(1);
                 state.bundle_error(error);
                 state.transaction_tracker.reset().await;
+                state.clear_bundles();
             }
         }
 
@@ -397,22 +592,17 @@ where
                     ..
                 } => {
                     info!("Bundle transaction mined: block number {block_number}, attempt number {attempt_number}, gas limit {gas_limit:?}, gas used {gas_used:?}, tx hash {tx_hash}, nonce {nonce}, success {is_success}");
-
-                    self.metrics
-                        .process_bundle_txn_mined(gas_limit, gas_used, is_success);
-
-                    if !is_success {
-                        if let Err(e) = self.process_revert(tx_hash).await {
-                            warn!("Failed to process revert for bundle transaction {tx_hash:?}: {e:#?}");
-                        }
-                    }
-
-                    self.emit(BuilderEvent::transaction_mined(
-                        self.builder_tag.clone(),
+                    self.process_mined(
                         tx_hash,
                         nonce,
                         block_number,
-                    ));
+                        gas_limit,
+                        gas_used,
+                        is_success,
+                        state.bundle_profits.remove(&nonce),
+                    )
+                    .await;
+                    self.signer_health.reset();
                     state.bundle_mined(block_number, attempt_number, tx_hash);
                 }
                 TrackerUpdate::LatestTxDropped { nonce } => {
@@ -432,6 +622,7 @@ where
                         nonce,
                     ));
                     self.metrics.bundle_txns_nonce_used.increment(1);
+                    self.record_signer_health(SignerHealthEvent::UnexpectedNonceChange);
                     state.reset();
                 }
             }
@@ -446,11 +637,206 @@ where
             );
             self.metrics.bundle_txn_fee_increases.increment(1);
             state.update(InnerState::Building(inner.to_building()))
+        } else if self.can_pipeline(state) {
+            // a bundle in flight has mined, send the next bundle without waiting for this one
+            info!("Building next bundle with the next nonce");
+            state.pipeline_next();
         }
 
         Ok(())
     }
 
+    // Records a mined bundle transaction, removing the ops of a reverted bundle from the pool
+    #[allow(clippy::too_many_arguments)]
+    async fn process_mined(
+        &self,
+        tx_hash: B256,
+        nonce: u64,
+        block_number: u64,
+        gas_limit: Option<u64>,
+        gas_used: Option<u128>,
+        is_success: bool,
+        expected_profit: Option<BundleProfit>,
+    ) {
+        self.metrics
+            .process_bundle_txn_mined(gas_limit, gas_used, is_success);
+
+        if !is_success {
+            if let Err(e) = self.process_revert(tx_hash).await {
+                warn!("Failed to process revert for bundle transaction {tx_hash:?}: {e:#?}");
+            }
+        }
+
+        if let Err(e) = self.process_realized_profit(tx_hash, expected_profit).await {
+            warn!("Failed to process realized profit for bundle transaction {tx_hash:?}: {e:#?}");
+        }
+
+        self.emit(BuilderEvent::transaction_mined(
+            self.builder_tag.clone(),
+            tx_hash,
+            nonce,
+            block_number,
+        ));
+    }
+
+    // Reconciles a mined bundle transaction's expected profit against the gas fees the entry point
+    // paid the beneficiary, from the `actualGasCost` of its `UserOperationEvent` logs, and the
+    // transaction's gas cost and L1 fee from its receipt
+    async fn process_realized_profit(
+        &self,
+        tx_hash: B256,
+        expected_profit: Option<BundleProfit>,
+    ) -> anyhow::Result<()> {
+        let receipt = self
+            .ep_providers
+            .evm()
+            .get_transaction_receipt(tx_hash)
+            .await
+            .context("should have fetched receipt for mined bundle transaction")?
+            .context("receipt for mined bundle transaction not found")?;
+
+        let revenue = receipt
+            .inner
+            .logs()
+            .iter()
+            .filter(|log| log.address() == self.ep_address)
+            .filter_map(|log| {
+                bundle_proposer::decode_user_operation_event(
+                    self.ep_providers.entry_point().version(),
+                    log,
+                )
+            })
+            .fold(U256::ZERO, |sum, (_, actual_gas_cost, _)| {
+                sum + actual_gas_cost
+            });
+        let cost = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price)
+            + self.receipt_l1_fee(tx_hash).await?;
+
+        info!("Bundle transaction {tx_hash:?} realized revenue {revenue}, cost {cost}, expected {expected_profit:?}");
+        self.metrics
+            .process_bundle_txn_profit(revenue, cost, expected_profit);
+        Ok(())
+    }
+
+    // The L1 data fee charged to a transaction on OP stack chains, which isn't part of its gas cost.
+    // Only OP stack receipts carry it, so it is read from the raw receipt.
+    async fn receipt_l1_fee(&self, tx_hash: B256) -> anyhow::Result<U256> {
+        if !matches!(
+            self.chain_spec.da_gas_oracle_type,
+            DAGasOracleType::OptimismBedrock | DAGasOracleType::LocalBedrock
+        ) {
+            return Ok(U256::ZERO);
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct L1FeeReceipt {
+            l1_fee: Option<U256>,
+        }
+        let receipt: Option<L1FeeReceipt> = self
+            .ep_providers
+            .evm()
+            .request("eth_getTransactionReceipt", (tx_hash,))
+            .await
+            .context("should have fetched L1 fee of mined bundle transaction")?;
+        Ok(receipt.and_then(|r| r.l1_fee).unwrap_or_default())
+    }
+
+    fn pipelining(&self) -> bool {
+        self.settings.max_pending_bundles > 1
+    }
+
+    // Whether the next bundle can be sent before the bundle with the current nonce mines
+    fn can_pipeline<TRIG: Trigger>(&self, state: &SenderMachineState<T, TRIG>) -> bool {
+        self.pipelining()
+            && !state.trigger.builder_must_wait_for_trigger()
+            && (state.transaction_tracker.num_in_flight_transactions() as u64) + 1
+                < self.settings.max_pending_bundles
+    }
+
+    // Handles the updates for bundles in flight below the current nonce, then releases
+    // the senders of all bundles that are no longer pending.
+    async fn process_in_flight_updates<TRIG: Trigger>(
+        &mut self,
+        state: &mut SenderMachineState<T, TRIG>,
+        tracker_update: Option<&TrackerUpdate>,
+    ) {
+        let mut used_nonce = tracker_update.map(TrackerUpdate::nonce);
+        for update in state.transaction_tracker.take_in_flight_updates() {
+            used_nonce = used_nonce.max(Some(update.nonce()));
+            match update {
+                TrackerUpdate::Mined {
+                    tx_hash,
+                    nonce,
+                    block_number,
+                    gas_limit,
+                    gas_used,
+                    is_success,
+                    ..
+                } => {
+                    info!("In flight bundle transaction mined: block number {block_number}, gas limit {gas_limit:?}, gas used {gas_used:?}, tx hash {tx_hash}, nonce {nonce}, success {is_success}");
+                    self.process_mined(
+                        tx_hash,
+                        nonce,
+                        block_number,
+                        gas_limit,
+                        gas_used,
+                        is_success,
+                        state.bundle_profits.remove(&nonce),
+                    )
+                    .await;
+                    self.signer_health.reset();
+                }
+                TrackerUpdate::LatestTxDropped { nonce }
+                | TrackerUpdate::NonceUsedForOtherTx { nonce } => {
+                    info!("Nonce {nonce} of in flight bundle used externally, starting new bundle attempt");
+                    self.emit(BuilderEvent::nonce_used_for_other_transaction(
+                        self.builder_tag.clone(),
+                        nonce,
+                    ));
+                    self.metrics.bundle_txns_nonce_used.increment(1);
+                    if matches!(update, TrackerUpdate::NonceUsedForOtherTx { .. }) {
+                        self.record_signer_health(SignerHealthEvent::UnexpectedNonceChange);
+                    }
+                    state.reset();
+                }
+            }
+        }
+
+        if let Some(used_nonce) = used_nonce {
+            state.nonces_used(used_nonce);
+            self.assigner
+                .release_all_except(self.sender_eoa, state.pending_senders())
+                .await;
+        }
+    }
+
+    // Replaces the bundle with the lowest nonce in flight if it has not mined in time
+    fn rewind_if_stalled<TRIG: Trigger>(&self, state: &mut SenderMachineState<T, TRIG>) {
+        if !matches!(
+            state.inner,
+            InnerState::Building(_) | InnerState::Pending(_)
+        ) {
+            return;
+        }
+        let Some(sent_at_block) = state.transaction_tracker.oldest_in_flight_block() else {
+            return;
+        };
+        if state.block_number() < sent_at_block + self.settings.max_blocks_to_wait_for_mine {
+            return;
+        }
+        let Some(nonce) = state.transaction_tracker.rewind_nonce() else {
+            return;
+        };
+
+        info!(
+            "In flight bundle with nonce {nonce} not mined after {} blocks, increasing fees",
+            self.settings.max_blocks_to_wait_for_mine
+        );
+        self.metrics.bundle_txn_fee_increases.increment(1);
+        state.rewind(nonce);
+    }
+
     async fn handle_cancelling_state<TRIG: Trigger>(
         &mut self,
         state: &mut SenderMachineState<T, TRIG>,
@@ -484,7 +870,7 @@ where
             Ok(None) => {
                 info!("Soft cancellation or no transaction to cancel, starting new bundle attempt");
                 // release all operations after the soft cancellation
-                self.assigner.release_all(self.sender_eoa);
+                self.assigner.release_all(self.sender_eoa).await;
                 self.metrics.soft_cancellations.increment(1);
                 state.reset();
             }
@@ -496,6 +882,7 @@ where
                     // abandon the cancellation
                     warn!("Abandoning cancellation after max fee increases {}, starting new bundle attempt", inner.fee_increase_count);
                     self.metrics.cancellations_abandoned.increment(1);
+                    self.record_signer_health(SignerHealthEvent::StalledCancellation);
                     state.reset();
                 } else {
                     // Increase fees again
@@ -509,11 +896,13 @@ where
             Err(TransactionTrackerError::NonceTooLow) => {
                 // reset the transaction tracker and try again
                 info!("Nonce too low during cancellation, starting new bundle attempt");
+                self.record_signer_health(SignerHealthEvent::NonceTooLow);
                 state.reset();
             }
             Err(TransactionTrackerError::InsufficientFunds) => {
                 error!("Insufficient funds during cancellation, starting new bundle attempt");
                 self.metrics.cancellation_txns_failed.increment(1);
+                self.record_signer_health(SignerHealthEvent::InsufficientFunds);
                 state.reset();
             }
             Err(TransactionTrackerError::ConditionNotMet) => {
@@ -555,6 +944,7 @@ where
                             .cancellation_txns_total_fee
                             .increment(fee as u64);
                     };
+                    self.signer_health.reset();
                 }
                 TrackerUpdate::LatestTxDropped { .. } => {
                     // If a cancellation gets dropped, move to bundling state as there is no
@@ -565,6 +955,7 @@ where
                     // If a nonce is used externally, move to bundling state as there is no longer
                     // a pending transaction
                     info!("Nonce used externally while cancelling, starting new bundle attempt");
+                    self.record_signer_health(SignerHealthEvent::UnexpectedNonceChange);
                 }
             }
             state.reset();
@@ -572,9 +963,10 @@ where
             if inner.fee_increase_count >= self.settings.max_cancellation_fee_increases {
                 // abandon the cancellation
                 // release all operations after the cancellation abandonment
-                self.assigner.release_all(self.sender_eoa);
+                self.assigner.release_all(self.sender_eoa).await;
                 warn!("Abandoning cancellation after max fee increases {}, starting new bundle attempt", inner.fee_increase_count);
                 self.metrics.cancellations_abandoned.increment(1);
+                self.record_signer_health(SignerHealthEvent::StalledCancellation);
                 state.reset();
             } else {
                 // start replacement, don't wait for trigger
@@ -595,17 +987,25 @@ where
         state: &mut SenderMachineState<T, TRIG>,
         fee_increase_count: u64,
     ) -> anyhow::Result<SendBundleAttemptResult> {
-        let ops = self
+        let mut ops = self
             .assigner
             .assign_operations(
                 self.sender_eoa,
                 self.ep_address,
                 self.builder_settings.filter_id.clone(),
+                self.builder_settings.ordering,
             )
             .await?;
+        if !state.in_flight_bundles.is_empty() {
+            // ops from senders with a bundle in flight can't be included until it mines
+            let in_flight_senders: HashSet<Address> = state.in_flight_senders().copied().collect();
+            ops.retain(|op| !in_flight_senders.contains(&op.uo.sender()));
+        }
         if ops.is_empty() {
             // there are no UOs for this sender, so we can release all from the assigner
-            self.assigner.release_all(self.sender_eoa);
+            self.assigner
+                .release_all_except(self.sender_eoa, state.in_flight_senders())
+                .await;
             return Ok(SendBundleAttemptResult::NoOperationsInitially);
         }
 
@@ -614,23 +1014,31 @@ where
         match &result {
             Ok(SendBundleAttemptResult::Success(ops)) => {
                 self.assigner
-                    .confirm_senders_drop_unused(self.sender_eoa, ops.iter().map(|op| &op.0));
+                    .confirm_senders_drop_unused(self.sender_eoa, ops.iter().map(|op| &op.0))
+                    .await;
             }
             Ok(SendBundleAttemptResult::NonceTooLow) => {
-                self.assigner.release_all(self.sender_eoa);
+                self.assigner
+                    .release_all_except(self.sender_eoa, state.in_flight_senders())
+                    .await;
             }
             Ok(SendBundleAttemptResult::NoOperationsAfterSimulation) => {
                 // all UOs for this sender are invalid, so we can release all from the assigner
-                self.assigner.release_all(self.sender_eoa);
+                self.assigner
+                    .release_all_except(self.sender_eoa, state.in_flight_senders())
+                    .await;
             }
             _ => {
                 // If there are no pending transactions, release all operations
                 // Otherwise, drop all unconfirmed
                 if state.transaction_tracker.num_pending_transactions() == 0 {
-                    self.assigner.release_all(self.sender_eoa);
+                    self.assigner
+                        .release_all_except(self.sender_eoa, state.in_flight_senders())
+                        .await;
                 } else {
                     self.assigner
-                        .confirm_senders_drop_unused(self.sender_eoa, &[]);
+                        .confirm_senders_drop_unused(self.sender_eoa, &[])
+                        .await;
                 }
             }
         }
@@ -661,6 +1069,7 @@ where
             .make_bundle(
                 ops,
                 state.block_hash(),
+                state.block_number(),
                 balance,
                 required_fees,
                 fee_increase_count > 0,
@@ -673,6 +1082,7 @@ where
             }
             Err(e) => bail!("Failed to make bundle: {e:?}"),
         };
+        let profit = bundle.profit;
 
         let Some(bundle_tx) = self.get_bundle_tx(nonce, bundle).await? else {
             self.emit(BuilderEvent::formed_bundle(
@@ -681,6 +1091,7 @@ where
                 nonce,
                 fee_increase_count,
                 required_fees,
+                None,
             ));
             return Ok(SendBundleAttemptResult::NoOperationsAfterSimulation);
         };
@@ -700,6 +1111,11 @@ where
         match send_result {
             Ok(tx_hash) => {
                 let ops = Arc::new(ops);
+                if self.pipelining() {
+                    state.bundle_sent(nonce, ops.clone());
+                }
+                // a replacement overwrites the expected profit of the bundle it replaces
+                state.bundle_profits.insert(nonce, profit);
                 self.emit(BuilderEvent::formed_bundle(
                     self.builder_tag.clone(),
                     Some(BundleTxDetails {
@@ -710,6 +1126,7 @@ where
                     nonce,
                     fee_increase_count,
                     required_fees,
+                    Some(profit),
                 ));
 
                 Ok(SendBundleAttemptResult::Success(ops))
@@ -924,6 +1341,13 @@ where
         self.remove_ops_from_pool_by_hash(to_remove).await
     }
 
+    // Records a failure of the signer, marking it for quarantine if it is unhealthy
+    fn record_signer_health(&mut self, event: SignerHealthEvent) {
+        if let Some(reason) = self.signer_health.record(event) {
+            self.quarantine_reason = Some(reason);
+        }
+    }
+
     fn emit(&self, event: BuilderEvent) {
         let _ = self.event_sender.send(WithEntryPoint {
             entry_point: self.ep_address,
@@ -938,6 +1362,13 @@ struct SenderMachineState<T, TRIG> {
     send_bundle_response: Option<oneshot::Sender<SendBundleResult>>,
     inner: InnerState,
     requires_reset: bool,
+    // (sender, op_hash) pairs of the bundles sent with nonces other than the current one that
+    // may still mine, only tracked when pipelining
+    in_flight_bundles: BTreeMap<u64, Arc<Vec<(Address, B256)>>>,
+    // The last bundle sent with the current nonce, only tracked when pipelining
+    current_bundle: Option<(u64, Arc<Vec<(Address, B256)>>)>,
+    // Expected profit of the last bundle sent with each nonce that may still mine
+    bundle_profits: BTreeMap<u64, BundleProfit>,
 }
 
 impl<T: TransactionTracker, TRIG: Trigger> SenderMachineState<T, TRIG> {
@@ -948,6 +1379,9 @@ impl<T: TransactionTracker, TRIG: Trigger> SenderMachineState<T, TRIG> {
             send_bundle_response: None,
             inner: InnerState::new(),
             requires_reset: false,
+            in_flight_bundles: BTreeMap::new(),
+            current_bundle: None,
+            bundle_profits: BTreeMap::new(),
         }
     }
 
@@ -969,6 +1403,7 @@ impl<T: TransactionTracker, TRIG: Trigger> SenderMachineState<T, TRIG> {
     // Resets the state and transaction tracker, doesn't wait for next trigger
     fn reset(&mut self) {
         self.requires_reset = true;
+        self.clear_bundles();
         let building_state = BuildingState {
             wait_for_trigger: false,
             fee_increase_count: 0,
@@ -1021,10 +1456,77 @@ impl<T: TransactionTracker, TRIG: Trigger> SenderMachineState<T, TRIG> {
         }
     }
 
+    /*
+     * Pipelining moves
+     */
+
+    // Moves the bundle with the current nonce in flight and starts building the next bundle,
+    // waiting for the next trigger.
+    fn pipeline_next(&mut self) {
+        if let Some((nonce, ops)) = self.current_bundle.take() {
+            self.in_flight_bundles.insert(nonce, ops);
+            // a bundle sent with the next nonce before a rewind is replaced next
+            self.current_bundle = self.in_flight_bundles.remove_entry(&(nonce + 1));
+        }
+        self.transaction_tracker.advance_nonce();
+        self.update(InnerState::new());
+    }
+
+    // Moves back to the in flight bundle with `nonce` to replace it, doesn't wait for next trigger
+    fn rewind(&mut self, nonce: u64) {
+        if let Some((current_nonce, ops)) = self.current_bundle.take() {
+            self.in_flight_bundles.insert(current_nonce, ops);
+        }
+        self.current_bundle = self.in_flight_bundles.remove_entry(&nonce);
+        self.update(InnerState::Building(BuildingState {
+            wait_for_trigger: false,
+            fee_increase_count: 1,
+            underpriced_info: None,
+        }));
+    }
+
     /*
      * Helpers
      */
 
+    fn clear_bundles(&mut self) {
+        self.in_flight_bundles.clear();
+        self.current_bundle = None;
+        self.bundle_profits.clear();
+    }
+
+    fn bundle_sent(&mut self, nonce: u64, ops: Arc<Vec<(Address, B256)>>) {
+        self.current_bundle = Some((nonce, ops));
+    }
+
+    // Forgets the bundles sent with nonces up to and including `nonce`
+    fn nonces_used(&mut self, nonce: u64) {
+        self.in_flight_bundles.retain(|n, _| *n > nonce);
+        self.bundle_profits.retain(|n, _| *n > nonce);
+        if self
+            .current_bundle
+            .as_ref()
+            .is_some_and(|(n, _)| *n <= nonce)
+        {
+            self.current_bundle = None;
+        }
+    }
+
+    fn in_flight_senders(&self) -> impl Iterator<Item = &Address> {
+        self.in_flight_bundles
+            .values()
+            .flat_map(|ops| ops.iter().map(|(sender, _)| sender))
+    }
+
+    // Senders of all bundles that may still mine
+    fn pending_senders(&self) -> impl Iterator<Item = &Address> {
+        self.in_flight_senders().chain(
+            self.current_bundle
+                .iter()
+                .flat_map(|(_, ops)| ops.iter().map(|(sender, _)| sender)),
+        )
+    }
+
     async fn wait_for_trigger(&mut self) -> anyhow::Result<Option<TrackerUpdate>> {
         if self.requires_reset {
             self.transaction_tracker.reset().await;
@@ -1049,16 +1551,23 @@ impl<T: TransactionTracker, TRIG: Trigger> SenderMachineState<T, TRIG> {
                     .map_err(|e| anyhow::anyhow!("transaction tracker update error {e:?}"))
             }
             InnerState::Pending(..) | InnerState::CancelPending(..) => {
-                self.trigger.wait_for_block().await?;
-
-                let Some(update) = self.find_address_update() else {
-                    return Ok(None);
+                let block = self.trigger.wait_for_block().await?;
+
+                let update = match self.find_address_update() {
+                    Some(update) => self
+                        .transaction_tracker
+                        .process_update(&update)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("transaction tracker update error {e:?}"))?,
+                    None => None,
                 };
 
-                self.transaction_tracker
-                    .process_update(&update)
-                    .await
-                    .map_err(|e| anyhow::anyhow!("transaction tracker update error {e:?}"))
+                // senders that limit inclusion to a range of blocks drop the transaction after
+                // the range has passed, even if the nonce hasn't changed
+                Ok(update.or_else(|| {
+                    self.transaction_tracker
+                        .check_inclusion_deadline(block.block_number)
+                }))
             }
             InnerState::Cancelling(..) => Ok(None),
         }
@@ -1358,6 +1867,35 @@ impl BundleSenderTrigger {
         timer_interval: Duration,
         sender_eoa: Address,
     ) -> anyhow::Result<Self> {
+        Ok(Self {
+            bundling_mode: BundlingMode::Auto,
+            block_rx: Self::subscribe(task_spawner, pool_client, sender_eoa).await?,
+            bundle_action_receiver,
+            timer: tokio::time::interval(timer_interval),
+            last_block: NewHead {
+                block_hash: B256::ZERO,
+                block_number: 0,
+                address_updates: vec![],
+            },
+        })
+    }
+
+    // Replaces the block stream with one with the updates of `sender_eoa`
+    async fn resubscribe<P: Pool, T: TaskSpawner>(
+        &mut self,
+        task_spawner: &T,
+        pool_client: &P,
+        sender_eoa: Address,
+    ) -> anyhow::Result<()> {
+        self.block_rx = Self::subscribe(task_spawner, pool_client, sender_eoa).await?;
+        Ok(())
+    }
+
+    async fn subscribe<P: Pool, T: TaskSpawner>(
+        task_spawner: &T,
+        pool_client: &P,
+        sender_eoa: Address,
+    ) -> anyhow::Result<UnboundedReceiver<NewHead>> {
         let Ok(new_heads) = pool_client.subscribe_new_heads(vec![sender_eoa]).await else {
             error!("Failed to subscribe to new blocks");
             bail!("failed to subscribe to new blocks");
@@ -1369,17 +1907,7 @@ impl BundleSenderTrigger {
             Box::pin(Self::block_stream_task(new_heads, block_tx)),
         );
 
-        Ok(Self {
-            bundling_mode: BundlingMode::Auto,
-            block_rx,
-            bundle_action_receiver,
-            timer: tokio::time::interval(timer_interval),
-            last_block: NewHead {
-                block_hash: B256::ZERO,
-                block_number: 0,
-                address_updates: vec![],
-            },
-        })
+        Ok(block_rx)
     }
 
     async fn block_stream_task(
@@ -1390,7 +1918,8 @@ impl BundleSenderTrigger {
             match new_heads.next().await {
                 Some(b) => {
                     if block_tx.send(b).is_err() {
-                        error!("Failed to buffer new block for bundle sender");
+                        // the stream was replaced after the signer was quarantined
+                        info!("Bundle sender no longer receiving blocks, ending block stream");
                         return;
                     }
                 }
@@ -1470,6 +1999,18 @@ struct BuilderMetric {
     cancellation_txns_failed: Counter,
     #[metric(describe = "the count of state machine errors.")]
     state_machine_errors: Counter,
+    #[metric(describe = "the count of signers quarantined and replaced.")]
+    signers_quarantined: Counter,
+    #[metric(describe = "the distribution of beneficiary revenue of mined bundles in gwei.")]
+    bundle_realized_revenue_gwei: Histogram,
+    #[metric(describe = "the distribution of gas cost of mined bundles in gwei.")]
+    bundle_realized_cost_gwei: Histogram,
+    #[metric(describe = "the distribution of realized profit of mined bundles in gwei.")]
+    bundle_realized_profit_gwei: Histogram,
+    #[metric(describe = "the distribution of realized minus expected profit of bundles in gwei.")]
+    bundle_profit_error_gwei: Histogram,
+    #[metric(describe = "the count of mined bundles that realized a loss.")]
+    bundle_txns_loss: Counter,
 }
 
 impl BuilderMetric {
@@ -1493,6 +2034,34 @@ impl BuilderMetric {
                 .increment(used.try_into().unwrap_or(u64::MAX));
         }
     }
+
+    fn process_bundle_txn_profit(
+        &self,
+        revenue: U256,
+        cost: U256,
+        expected_profit: Option<BundleProfit>,
+    ) {
+        let realized_profit = to_gwei(revenue) - to_gwei(cost);
+        self.bundle_realized_revenue_gwei.record(to_gwei(revenue));
+        self.bundle_realized_cost_gwei.record(to_gwei(cost));
+        self.bundle_realized_profit_gwei.record(realized_profit);
+        if cost > revenue {
+            self.bundle_txns_loss.increment(1);
+        }
+
+        if let Some(expected) = expected_profit {
+            let expected_profit = to_gwei(expected.revenue) - to_gwei(expected.cost);
+            self.bundle_profit_error_gwei
+                .record(realized_profit - expected_profit);
+        }
+    }
+}
+
+fn to_gwei(amount: U256) -> f64 {
+    match format_units(amount, "gwei") {
+        Ok(s) => s.parse::<f64>().unwrap_or_default(),
+        Err(_) => 0.0,
+    }
 }
 
 #[cfg(test)]
@@ -1503,6 +2072,7 @@ mod tests {
         GethDebugTracerCallFrame, MockDAGasOracleSync, MockEntryPointV0_6, MockEvmProvider,
         MockFeeEstimator, ProvidersWithEntryPoint,
     };
+    use rundler_signer::MockSignerManager;
     use rundler_types::{
         chain::ChainSpec,
         pool::{AddressUpdate, MockPool, PoolOperationSummary},
@@ -1514,6 +2084,8 @@ mod tests {
 
     use super::*;
     use crate::{
+        assigner::AssignmentBackend,
+        bundle_ordering::BundleOrderingStrategy,
         bundle_proposer::{Bundle, MockBundleProposer},
         bundle_sender::{BundleSenderImpl, MockTrigger},
         transaction_tracker::MockTransactionTracker,
@@ -1571,7 +2143,9 @@ mod tests {
         mock_proposer
             .expect_make_bundle()
             .times(1)
-            .returning(|_, _, _, _, _| Box::pin(async { Ok(Bundle::<UserOperation>::default()) }));
+            .returning(|_, _, _, _, _, _| {
+                Box::pin(async { Ok(Bundle::<UserOperation>::default()) })
+            });
 
         let mut sender = new_sender(mock_proposer, mock_entry_point, mock_evm, mock_pool);
 
@@ -1637,7 +2211,7 @@ mod tests {
         mock_proposer
             .expect_make_bundle()
             .times(1)
-            .returning(|_, _, _, _, _| Box::pin(async { Ok(bundle()) }));
+            .returning(|_, _, _, _, _, _| Box::pin(async { Ok(bundle()) }));
 
         // should create the bundle txn
         mock_entry_point
@@ -1673,7 +2247,7 @@ mod tests {
             mock_entry_point,
             mut mock_tracker,
             mut mock_trigger,
-            mock_evm,
+            mut mock_evm,
             mock_pool,
         } = new_mocks();
 
@@ -1725,6 +2299,12 @@ mod tests {
             })
         });
 
+        // the realized profit of the mined bundle is recorded from its receipt
+        mock_evm
+            .expect_get_transaction_receipt()
+            .once()
+            .returning(|_| Ok(None));
+
         let mut sender = new_sender(mock_proposer, mock_entry_point, mock_evm, mock_pool);
 
         // start in pending state
@@ -1737,6 +2317,9 @@ mod tests {
                 fee_increase_count: 0,
             }),
             requires_reset: false,
+            in_flight_bundles: BTreeMap::new(),
+            current_bundle: None,
+            bundle_profits: BTreeMap::new(),
         };
 
         // first step has no update
@@ -1786,6 +2369,9 @@ mod tests {
                 fee_increase_count: 0,
             }),
             requires_reset: false,
+            in_flight_bundles: BTreeMap::new(),
+            current_bundle: None,
+            bundle_profits: BTreeMap::new(),
         };
 
         // first and second step has no update
@@ -1809,6 +2395,172 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_send_pipelined() {
+        let Mocks {
+            mut mock_proposer,
+            mut mock_entry_point,
+            mut mock_tracker,
+            mut mock_trigger,
+            mut mock_evm,
+            mut mock_pool,
+        } = new_mocks();
+
+        // block 0
+        add_trigger_no_update_last_block(&mut mock_trigger, &mut Sequence::new(), 0);
+        mock_trigger
+            .expect_builder_must_wait_for_trigger()
+            .return_const(false);
+
+        // zero nonce
+        mock_tracker.expect_get_state().returning(|| {
+            Ok(TrackerState {
+                nonce: 0,
+                balance: U256::ZERO,
+                required_fees: None,
+            })
+        });
+        mock_tracker.expect_address().return_const(Address::ZERO);
+        mock_tracker
+            .expect_take_in_flight_updates()
+            .returning(Vec::new);
+        mock_tracker
+            .expect_oldest_in_flight_block()
+            .return_const(None::<u64>);
+        mock_tracker
+            .expect_num_in_flight_transactions()
+            .return_const(0_usize);
+        // should move on to the next nonce
+        mock_tracker.expect_advance_nonce().once().return_const(());
+
+        mock_evm
+            .expect_get_balance()
+            .returning(|_, _| Ok(U256::MAX));
+
+        mock_pool
+            .expect_get_ops_summaries()
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(vec![PoolOperationSummary {
+                    hash: B256::ZERO,
+                    sender: Address::ZERO,
+                    entry_point: ENTRY_POINT_ADDRESS_V0_6,
+                }])
+            });
+        mock_pool
+            .expect_get_ops_by_hashes()
+            .times(1)
+            .returning(|_, _| Ok(vec![demo_pool_op()]));
+
+        mock_proposer
+            .expect_make_bundle()
+            .times(1)
+            .returning(|_, _, _, _, _, _| Box::pin(async { Ok(bundle()) }));
+        mock_entry_point
+            .expect_get_send_bundle_transaction()
+            .returning(|_, _, _, _, _| TransactionRequest::default());
+        mock_tracker
+            .expect_send_transaction()
+            .returning(|_, _, _| Box::pin(async { Ok(B256::ZERO) }));
+
+        let mut sender = new_sender(mock_proposer, mock_entry_point, mock_evm, mock_pool);
+        sender.settings.max_pending_bundles = 2;
+
+        // start in building state
+        let mut state = SenderMachineState::new(mock_trigger, mock_tracker);
+
+        sender.step_state(&mut state).await.unwrap();
+
+        // back in the building state with the bundle in flight
+        assert!(matches!(
+            state.inner,
+            InnerState::Building(BuildingState {
+                wait_for_trigger: true,
+                fee_increase_count: 0,
+                underpriced_info: None,
+            })
+        ));
+        assert!(state.in_flight_bundles.contains_key(&0));
+        assert!(state.current_bundle.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_in_flight_mined() {
+        let Mocks {
+            mock_proposer,
+            mock_entry_point,
+            mut mock_tracker,
+            mut mock_trigger,
+            mut mock_evm,
+            mock_pool,
+        } = new_mocks();
+
+        add_trigger_wait_for_block_last_block(&mut mock_trigger, &mut Sequence::new(), 1);
+
+        mock_tracker.expect_address().return_const(Address::ZERO);
+        mock_tracker
+            .expect_take_in_flight_updates()
+            .once()
+            .returning(|| {
+                vec![TrackerUpdate::Mined {
+                    block_number: 1,
+                    nonce: 0,
+                    gas_limit: None,
+                    gas_used: None,
+                    gas_price: None,
+                    tx_hash: B256::ZERO,
+                    attempt_number: 0,
+                    is_success: true,
+                }]
+            });
+        mock_tracker
+            .expect_oldest_in_flight_block()
+            .return_const(None::<u64>);
+        mock_tracker
+            .expect_num_in_flight_transactions()
+            .return_const(0_usize);
+        mock_tracker.expect_advance_nonce().once().return_const(());
+
+        // the realized profit of the mined bundle is recorded from its receipt
+        mock_evm
+            .expect_get_transaction_receipt()
+            .once()
+            .returning(|_| Ok(None));
+
+        let mut sender = new_sender(mock_proposer, mock_entry_point, mock_evm, mock_pool);
+        sender.settings.max_pending_bundles = 2;
+
+        // pending at nonce 1 with the bundle at nonce 0 in flight
+        let mut state = SenderMachineState {
+            trigger: mock_trigger,
+            transaction_tracker: mock_tracker,
+            send_bundle_response: None,
+            inner: InnerState::Pending(PendingState {
+                until: 3,
+                fee_increase_count: 0,
+            }),
+            requires_reset: false,
+            in_flight_bundles: BTreeMap::from([(0, Arc::new(vec![(Address::ZERO, B256::ZERO)]))]),
+            current_bundle: Some((1, Arc::new(vec![(Address::random(), B256::random())]))),
+            bundle_profits: BTreeMap::new(),
+        };
+
+        sender.step_state(&mut state).await.unwrap();
+
+        // the next bundle is built without waiting for the current one to mine
+        assert!(matches!(
+            state.inner,
+            InnerState::Building(BuildingState {
+                wait_for_trigger: true,
+                ..
+            })
+        ));
+        assert_eq!(
+            state.in_flight_bundles.keys().copied().collect::<Vec<_>>(),
+            vec![1]
+        );
+    }
+
     #[tokio::test]
     async fn test_transition_to_cancel() {
         let Mocks {
@@ -1859,7 +2611,7 @@ mod tests {
         mock_proposer
             .expect_make_bundle()
             .times(1)
-            .returning(|_, _, _, _, _| {
+            .returning(|_, _, _, _, _, _| {
                 Box::pin(async { Err(BundleProposerError::NoOperationsAfterFeeFilter) })
             });
 
@@ -1879,6 +2631,9 @@ mod tests {
                 }),
             }),
             requires_reset: false,
+            in_flight_bundles: BTreeMap::new(),
+            current_bundle: None,
+            bundle_profits: BTreeMap::new(),
         };
 
         // step state, block number should trigger move to cancellation
@@ -1926,6 +2681,9 @@ mod tests {
                 fee_increase_count: 0,
             }),
             requires_reset: false,
+            in_flight_bundles: BTreeMap::new(),
+            current_bundle: None,
+            bundle_profits: BTreeMap::new(),
         };
 
         let mut sender = new_sender(mock_proposer, mock_entry_point, mock_evm, mock_pool);
@@ -1940,6 +2698,151 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_quarantine_after_repeated_nonce_too_low() {
+        let Mocks {
+            mut mock_proposer,
+            mock_entry_point,
+            mut mock_tracker,
+            mut mock_trigger,
+            mock_evm,
+            mock_pool,
+        } = new_mocks();
+
+        mock_proposer
+            .expect_estimate_gas_fees()
+            .times(2)
+            .returning(|_, _| Box::pin(async { Ok((GasFees::default(), 0)) }));
+
+        mock_tracker
+            .expect_cancel_transaction()
+            .times(2)
+            .returning(|_| Box::pin(async { Err(TransactionTrackerError::NonceTooLow) }));
+
+        mock_trigger.expect_last_block().return_const(NewHead {
+            block_number: 0,
+            block_hash: B256::ZERO,
+            address_updates: vec![],
+        });
+
+        let mut state = SenderMachineState::new(mock_trigger, mock_tracker);
+
+        let mut sender = new_sender(mock_proposer, mock_entry_point, mock_evm, mock_pool);
+        sender.signer_health = SignerHealth::new(Some(2));
+
+        for _ in 0..2 {
+            assert!(sender.quarantine_reason.is_none());
+            state.update(InnerState::Cancelling(CancellingState {
+                fee_increase_count: 0,
+            }));
+            sender.step_state(&mut state).await.unwrap();
+            assert!(state.requires_reset);
+            state.requires_reset = false;
+        }
+        assert_eq!(
+            sender.quarantine_reason.as_deref(),
+            Some("nonce too low 2 consecutive times")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_settle_pending_transactions_cancels_stalled_tx() {
+        let Mocks {
+            mut mock_proposer,
+            mock_entry_point,
+            mut mock_tracker,
+            mut mock_trigger,
+            mock_evm,
+            mock_pool,
+        } = new_mocks();
+
+        let mined = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        mock_trigger.expect_last_block().return_const(NewHead {
+            block_number: 0,
+            block_hash: B256::ZERO,
+            address_updates: vec![],
+        });
+        let mut seq = Sequence::new();
+        for block_number in 1..=4 {
+            mock_trigger
+                .expect_wait_for_block()
+                .once()
+                .in_sequence(&mut seq)
+                .returning(move || {
+                    // the cancellation mines in the block after it is sent
+                    let address_updates = if block_number == 4 {
+                        vec![AddressUpdate {
+                            address: Address::ZERO,
+                            nonce: Some(0),
+                            balance: U256::ZERO,
+                            mined_tx_hashes: vec![B256::ZERO],
+                        }]
+                    } else {
+                        vec![]
+                    };
+                    Box::pin(async move {
+                        Ok(NewHead {
+                            block_number,
+                            block_hash: B256::ZERO,
+                            address_updates,
+                        })
+                    })
+                });
+        }
+
+        mock_tracker.expect_address().return_const(Address::ZERO);
+        let mined_clone = mined.clone();
+        mock_tracker
+            .expect_num_pending_transactions()
+            .returning(move || {
+                if mined_clone.load(std::sync::atomic::Ordering::Relaxed) {
+                    0
+                } else {
+                    1
+                }
+            });
+        // the transaction doesn't mine within 3 blocks so it is cancelled
+        mock_tracker.expect_rewind_nonce().once().return_const(None);
+        mock_proposer
+            .expect_estimate_gas_fees()
+            .once()
+            .returning(|_, _| Box::pin(async { Ok((GasFees::default(), 0)) }));
+        mock_tracker
+            .expect_cancel_transaction()
+            .once()
+            .returning(|_| Box::pin(async { Ok(Some(B256::ZERO)) }));
+        let mined_clone = mined.clone();
+        mock_tracker
+            .expect_process_update()
+            .once()
+            .returning(move |_| {
+                mined_clone.store(true, std::sync::atomic::Ordering::Relaxed);
+                Box::pin(async {
+                    Ok(Some(TrackerUpdate::Mined {
+                        tx_hash: B256::ZERO,
+                        nonce: 0,
+                        block_number: 4,
+                        attempt_number: 0,
+                        gas_limit: None,
+                        gas_used: None,
+                        gas_price: None,
+                        is_success: true,
+                    }))
+                })
+            });
+        mock_tracker
+            .expect_take_in_flight_updates()
+            .once()
+            .returning(Vec::new);
+
+        let mut state = SenderMachineState::new(mock_trigger, mock_tracker);
+        let mut sender = new_sender(mock_proposer, mock_entry_point, mock_evm, mock_pool);
+
+        sender.settle_pending_transactions(&mut state).await;
+        assert!(mined.load(std::sync::atomic::Ordering::Relaxed));
+    }
+
     #[tokio::test]
     async fn test_resubmit_cancel() {
         let Mocks {
@@ -1965,6 +2868,9 @@ mod tests {
                 fee_increase_count: 0,
             }),
             requires_reset: false,
+            in_flight_bundles: BTreeMap::new(),
+            current_bundle: None,
+            bundle_profits: BTreeMap::new(),
         };
 
         let mut sender = new_sender(mock_proposer, mock_entry_point, mock_evm, mock_pool);
@@ -2035,7 +2941,7 @@ mod tests {
         mock_proposer
             .expect_make_bundle()
             .times(1)
-            .returning(|_, _, _, _, _| Box::pin(async { Ok(bundle()) }));
+            .returning(|_, _, _, _, _, _| Box::pin(async { Ok(bundle()) }));
 
         // should get balance of sender
         mock_evm
@@ -2068,6 +2974,9 @@ mod tests {
                 underpriced_info: None,
             }),
             requires_reset: false,
+            in_flight_bundles: BTreeMap::new(),
+            current_bundle: None,
+            bundle_profits: BTreeMap::new(),
         };
 
         let mut sender = new_sender(mock_proposer, mock_entry_point, mock_evm, mock_pool);
@@ -2183,6 +3092,12 @@ mod tests {
             .withf(move |_, hashes| hashes.len() == 1 && hashes[0] == op_hash)
             .returning(|_, _| Ok(()));
 
+        // the realized profit of the mined bundle is recorded from its receipt
+        mock_evm
+            .expect_get_transaction_receipt()
+            .once()
+            .returning(|_| Ok(None));
+
         let mut sender = new_sender(mock_proposer, mock_entry_point, mock_evm, mock_pool);
 
         // start in pending state
@@ -2195,6 +3110,9 @@ mod tests {
                 fee_increase_count: 0,
             }),
             requires_reset: false,
+            in_flight_bundles: BTreeMap::new(),
+            current_bundle: None,
+            bundle_profits: BTreeMap::new(),
         };
 
         // first step has no update
@@ -2231,10 +3149,15 @@ mod tests {
             .expect_address()
             .return_const(Address::default());
 
+        let mut mock_tracker = MockTransactionTracker::new();
+        mock_tracker
+            .expect_check_inclusion_deadline()
+            .returning(|_| None);
+
         Mocks {
             mock_proposer: MockBundleProposer::new(),
             mock_entry_point,
-            mock_tracker: MockTransactionTracker::new(),
+            mock_tracker,
             mock_trigger: MockTrigger::new(),
             mock_evm: MockEvmProvider::new(),
             mock_pool: MockPool::new(),
@@ -2264,10 +3187,14 @@ mod tests {
             BuilderSettings {
                 submission_proxy: None,
                 filter_id: None,
+                ordering: BundleOrderingStrategy::default(),
+                min_profit_margin_pct: None,
             },
             mpsc::channel(1000).1,
             ChainSpec::default(),
             Address::default(),
+            watch::channel(Address::default()).0,
+            Arc::new(MockSignerManager::new()),
             None,
             mock_proposer,
             ProvidersWithEntryPoint::new(
@@ -2277,12 +3204,19 @@ mod tests {
                 Arc::new(MockFeeEstimator::new()),
             ),
             MockTransactionTracker::new(),
-            Arc::new(Assigner::new(Box::new(pool.clone()), 1024, 1024)),
+            Arc::new(Assigner::new(
+                Box::new(pool.clone()),
+                AssignmentBackend::Local,
+                1024,
+                1024,
+            )),
             pool,
             Settings {
                 max_cancellation_fee_increases: 3,
                 max_blocks_to_wait_for_mine: 3,
                 max_replacement_underpriced_blocks: 3,
+                max_pending_bundles: 1,
+                signer_quarantine_threshold: None,
             },
             broadcast::channel(1000).0,
         )
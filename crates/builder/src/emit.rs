@@ -39,6 +39,7 @@ impl BuilderEvent {
         nonce: u64,
         fee_increase_count: u64,
         required_fees: Option<GasFees>,
+        profit: Option<BundleProfit>,
     ) -> Self {
         Self::new(
             tag,
@@ -47,6 +48,7 @@ impl BuilderEvent {
                 nonce,
                 fee_increase_count,
                 required_fees,
+                profit,
             },
         )
     }
@@ -103,6 +105,9 @@ pub enum BuilderEventKind {
         fee_increase_count: u64,
         /// Required fees for the transaction that was sent
         required_fees: Option<GasFees>,
+        /// Expected profit of the bundle
+        /// If `None`, means that the bundle contained no operations.
+        profit: Option<BundleProfit>,
     },
     /// A bundle transaction was mined
     TransactionMined {
@@ -150,6 +155,28 @@ pub struct BundleTxDetails {
     pub ops: Arc<Vec<(Address, B256)>>,
 }
 
+/// Expected revenue and cost of a bundle transaction, in wei
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BundleProfit {
+    /// Gas fees the entry point is expected to pay the beneficiary for the bundle's operations
+    pub revenue: U256,
+    /// Cost of the bundle transaction's gas, including DA gas, and of its fixed fees
+    pub cost: U256,
+    /// Portion of `cost` due to operations sponsored by the bundler
+    pub sponsored_cost: U256,
+}
+
+impl BundleProfit {
+    /// Returns true if the revenue covers the cost not sponsored by the bundler plus
+    /// `min_margin_pct` percent of it. A negative margin accepts a bounded loss.
+    pub fn meets_margin(&self, min_margin_pct: i32) -> bool {
+        let unsponsored_cost = self.cost.saturating_sub(self.sponsored_cost);
+        let margin = U256::from(100_i32.saturating_add(min_margin_pct).max(0) as u32);
+        let required = unsponsored_cost * margin / U256::from(100);
+        self.revenue >= required
+    }
+}
+
 /// Reason for skipping an operation in a bundle
 #[derive(Clone, Debug)]
 pub enum SkipReason {
@@ -185,6 +212,8 @@ pub enum SkipReason {
     TransactionSizeLimit,
     /// UO uses an unsupported aggregator
     UnsupportedAggregator(Address),
+    /// Bundle did not meet the builder's minimum profit margin, and this was its least profitable operation
+    BelowMinProfitMargin { profit: BundleProfit },
     /// Other reason, typically internal errors
     Other { reason: Arc<String> },
 }
@@ -219,6 +248,7 @@ impl Display for BuilderEvent {
                 nonce,
                 fee_increase_count,
                 required_fees,
+                profit,
             } => {
                 let required_max_fee_per_gas =
                     strs::to_string_or(required_fees.map(|fees| fees.max_fee_per_gas), "(default)");
@@ -228,6 +258,7 @@ impl Display for BuilderEvent {
                 );
                 match tx_details {
                     Some(tx_details) => {
+                        let profit = profit.unwrap_or_default();
                         let op_hashes = tx_details
                             .ops
                             .iter()
@@ -244,6 +275,9 @@ impl Display for BuilderEvent {
                                 "    Fee increases: {}",
                                 "    Required maxFeePerGas: {}",
                                 "    Required maxPriorityFeePerGas: {}",
+                                "    Expected revenue: {}",
+                                "    Expected cost: {}",
+                                "    Expected sponsored cost: {}",
                                 "    Ops: {}",
                             ),
                             self.tag,
@@ -252,6 +286,9 @@ impl Display for BuilderEvent {
                             fee_increase_count,
                             required_max_fee_per_gas,
                             required_max_priority_fee_per_gas,
+                            profit.revenue,
+                            profit.cost,
+                            profit.sponsored_cost,
                             op_hashes,
                         )
                     }
@@ -20,6 +20,12 @@
 //! Bundle builder implementation for the Rundler.
 
 mod assigner;
+pub use assigner::AssignmentBackend;
+
+mod bundle_ordering;
+pub use bundle_ordering::BundleOrderingStrategy;
+
+mod bundle_previewer;
 mod bundle_proposer;
 mod bundle_sender;
 
@@ -28,13 +34,15 @@ pub use emit::{BuilderEvent, BuilderEventKind};
 
 mod sender;
 pub use sender::{
-    BloxrouteSenderArgs, FlashbotsSenderArgs, RawSenderArgs, TransactionSenderArgs,
-    TransactionSenderKind,
+    BlockBuilderEndpoint, BloxrouteSenderArgs, BundleSenderArgs, BundleSubmitMethod,
+    FlashbotsSenderArgs, RawSenderArgs, TransactionSenderArgs, TransactionSenderKind,
 };
 
 mod server;
 pub use server::{LocalBuilderBuilder, LocalBuilderHandle, RemoteBuilderClient};
 
+mod signer_health;
+
 mod task;
 pub use task::{Args as BuilderTaskArgs, BuilderSettings, BuilderTask, EntryPointBuilderSettings};
 
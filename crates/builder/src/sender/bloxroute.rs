@@ -41,6 +41,7 @@ where
         tx: TransactionRequest,
         _expected_storage: &ExpectedStorage,
         signer: &SignerLease,
+        _block_number: u64,
     ) -> Result<B256> {
         let raw_tx = signer
             .sign_tx_raw(tx)
@@ -0,0 +1,729 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::time::Duration;
+
+use alloy_primitives::{utils, Address, Bytes, B256, U64};
+use alloy_signer_local::PrivateKeySigner;
+use anyhow::{anyhow, bail, Context};
+use futures::future;
+use reqwest::{
+    header::{HeaderName, HeaderValue, CONTENT_TYPE},
+    Client,
+};
+use rundler_provider::TransactionRequest;
+use rundler_signer::SignerLease;
+use rundler_types::{ExpectedStorage, GasFees};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+
+use super::{
+    flashbots::flashbots_signature, BlockBuilderEndpoint, BundleSenderArgs, BundleSubmitMethod,
+    CancelTxInfo, Result, TransactionSender, TxSenderError,
+};
+
+/// Sends transactions privately to a set of block builders, either as single transaction
+/// bundles via `eth_sendBundle` or via `eth_sendPrivateTransaction`.
+///
+/// Each transaction targets the `target_blocks` blocks following the block it was sent at,
+/// after which the builders drop it.
+#[derive(Debug)]
+pub(crate) struct BundleTransactionSender {
+    http_client: Client,
+    builders: Vec<BlockBuilder>,
+    target_blocks: u64,
+    refund_percent: Option<u8>,
+    refund_recipient: Option<Address>,
+    allow_revert: bool,
+}
+
+#[derive(Debug)]
+struct BlockBuilder {
+    name: String,
+    url: String,
+    method: BundleSubmitMethod,
+    signer: Option<PrivateKeySigner>,
+    auth_header: Option<(HeaderName, HeaderValue)>,
+}
+
+#[async_trait::async_trait]
+impl TransactionSender for BundleTransactionSender {
+    async fn send_transaction(
+        &self,
+        tx: TransactionRequest,
+        _expected_storage: &ExpectedStorage,
+        signer: &SignerLease,
+        block_number: u64,
+    ) -> Result<B256> {
+        let raw_tx = signer
+            .sign_tx_raw(tx)
+            .await
+            .context("failed to sign transaction")?;
+        let tx_hash = utils::keccak256(&raw_tx);
+
+        let results =
+            future::join_all(self.builders.iter().map(|builder| {
+                self.submit(builder, &raw_tx, tx_hash, signer.address(), block_number)
+            }))
+            .await;
+
+        let mut accepted = 0;
+        for (builder, result) in self.builders.iter().zip(results) {
+            match result {
+                Ok(()) => {
+                    debug!("Builder {} accepted transaction {tx_hash:?}", builder.name);
+                    accepted += 1;
+                }
+                Err(e) => warn!(
+                    "Builder {} failed to accept transaction {tx_hash:?}: {e:?}",
+                    builder.name
+                ),
+            }
+        }
+
+        if accepted == 0 {
+            return Err(TxSenderError::Rejected);
+        }
+
+        Ok(tx_hash)
+    }
+
+    async fn cancel_transaction(
+        &self,
+        tx_hash: B256,
+        _nonce: u64,
+        _gas_fees: GasFees,
+        _signer: &SignerLease,
+    ) -> Result<CancelTxInfo> {
+        // Bundles can't be cancelled by transaction hash, but are dropped by the builders
+        // once their target blocks pass. Private transactions are cancelled where possible.
+        let results = future::join_all(
+            self.builders
+                .iter()
+                .filter(|builder| builder.method == BundleSubmitMethod::SendPrivateTransaction)
+                .map(|builder| async move {
+                    let body = json!({
+                        "jsonrpc": "2.0",
+                        "method": "eth_cancelPrivateTransaction",
+                        "params": [{ "txHash": tx_hash }],
+                        "id": 1
+                    });
+                    (builder, self.post(builder, body).await)
+                }),
+        )
+        .await;
+
+        for (builder, result) in results {
+            if let Err(e) = result {
+                warn!(
+                    "Builder {} failed to cancel transaction {tx_hash:?}: {e:?}",
+                    builder.name
+                );
+            }
+        }
+
+        Ok(CancelTxInfo {
+            tx_hash: B256::ZERO,
+            soft_cancelled: true,
+        })
+    }
+}
+
+impl BundleTransactionSender {
+    pub(crate) fn new(args: BundleSenderArgs, timeout_seconds: u64) -> Result<Self> {
+        if args.builders.is_empty() {
+            return Err(anyhow!("bundle sender requires at least one block builder").into());
+        }
+        if args.target_blocks == 0 {
+            return Err(anyhow!("bundle sender target blocks must be greater than zero").into());
+        }
+
+        let builders = args
+            .builders
+            .into_iter()
+            .map(BlockBuilder::try_from)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(timeout_seconds))
+            .build()
+            .context("failed to build http client")?;
+
+        Ok(Self {
+            http_client,
+            builders,
+            target_blocks: args.target_blocks,
+            refund_percent: args.refund_percent,
+            refund_recipient: args.refund_recipient,
+            allow_revert: args.allow_revert,
+        })
+    }
+
+    async fn submit(
+        &self,
+        builder: &BlockBuilder,
+        raw_tx: &Bytes,
+        tx_hash: B256,
+        sender: Address,
+        block_number: u64,
+    ) -> anyhow::Result<()> {
+        match builder.method {
+            BundleSubmitMethod::SendBundle => {
+                // A bundle targets a single block, send one for each of the target blocks
+                let requests =
+                    (block_number + 1..=block_number + self.target_blocks).map(|target_block| {
+                        let bundle = SendBundleRequest {
+                            txs: vec![raw_tx.clone()],
+                            block_number: U64::from(target_block),
+                            reverting_tx_hashes: if self.allow_revert {
+                                vec![tx_hash]
+                            } else {
+                                vec![]
+                            },
+                            refund_percent: self.refund_percent,
+                            refund_recipient: self
+                                .refund_percent
+                                .map(|_| self.refund_recipient.unwrap_or(sender)),
+                        };
+                        self.post(
+                            builder,
+                            json!({
+                                "jsonrpc": "2.0",
+                                "method": "eth_sendBundle",
+                                "params": [bundle],
+                                "id": 1
+                            }),
+                        )
+                    });
+                future::try_join_all(requests).await?;
+            }
+            BundleSubmitMethod::SendPrivateTransaction => {
+                let refund = self.refund_percent.map(|percent| {
+                    vec![Refund {
+                        address: self.refund_recipient.unwrap_or(sender),
+                        percent,
+                    }]
+                });
+                let request = SendPrivateTransactionRequest {
+                    tx: raw_tx.clone(),
+                    max_block_number: U64::from(block_number + self.target_blocks),
+                    preferences: Preferences {
+                        fast: false,
+                        validity: refund.map(|refund| Validity { refund }),
+                    },
+                };
+                self.post(
+                    builder,
+                    json!({
+                        "jsonrpc": "2.0",
+                        "method": "eth_sendPrivateTransaction",
+                        "params": [request],
+                        "id": 1
+                    }),
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn post(&self, builder: &BlockBuilder, body: Value) -> anyhow::Result<()> {
+        let body = body.to_string();
+
+        let mut request = self
+            .http_client
+            .post(&builder.url)
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(signer) = &builder.signer {
+            request = request.header("x-flashbots-signature", flashbots_signature(signer, &body));
+        }
+        if let Some((name, value)) = &builder.auth_header {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .context("failed to send request to builder")?
+            .error_for_status()
+            .context("builder request failed")?
+            .json::<JsonRpcResponse>()
+            .await
+            .context("failed to deserialize builder response")?;
+
+        if let Some(error) = response.error {
+            bail!("builder returned error {}: {}", error.code, error.message);
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<BlockBuilderEndpoint> for BlockBuilder {
+    type Error = anyhow::Error;
+
+    fn try_from(endpoint: BlockBuilderEndpoint) -> anyhow::Result<Self> {
+        let signer = endpoint
+            .signing_key
+            .map(|key| key.parse::<PrivateKeySigner>())
+            .transpose()
+            .with_context(|| format!("invalid signing key for builder {}", endpoint.name))?;
+
+        let auth_header = endpoint
+            .auth_header
+            .map(|header| -> anyhow::Result<_> {
+                let (name, value) = header
+                    .split_once(':')
+                    .context("auth header should be formatted as `name: value`")?;
+                Ok((
+                    HeaderName::from_bytes(name.trim().as_bytes())?,
+                    HeaderValue::from_str(value.trim())?,
+                ))
+            })
+            .transpose()
+            .with_context(|| format!("invalid auth header for builder {}", endpoint.name))?;
+
+        Ok(Self {
+            name: endpoint.name,
+            url: endpoint.url,
+            method: endpoint.method,
+            signer,
+            auth_header,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SendBundleRequest {
+    txs: Vec<Bytes>,
+    block_number: U64,
+    reverting_tx_hashes: Vec<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refund_percent: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refund_recipient: Option<Address>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SendPrivateTransactionRequest {
+    tx: Bytes,
+    max_block_number: U64,
+    preferences: Preferences,
+}
+
+#[derive(Serialize, Debug)]
+struct Preferences {
+    fast: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    validity: Option<Validity>,
+}
+
+#[derive(Serialize, Debug)]
+struct Validity {
+    refund: Vec<Refund>,
+}
+
+#[derive(Serialize, Debug)]
+struct Refund {
+    address: Address,
+    percent: u8,
+}
+
+#[derive(Deserialize, Debug)]
+struct JsonRpcResponse {
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Deserialize, Debug)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use alloy_primitives::{address, hex};
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::*;
+
+    const OK: &str = r#"{"jsonrpc":"2.0","id":1,"result":null}"#;
+    const ERROR: &str = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"rejected"}}"#;
+
+    #[derive(Debug)]
+    struct Request {
+        headers: HashMap<String, String>,
+        body: Value,
+    }
+
+    /// Block builder served over HTTP that records its requests and replies with `response`
+    struct MockBuilder {
+        url: String,
+        requests: Arc<Mutex<Vec<Request>>>,
+    }
+
+    impl MockBuilder {
+        async fn start(response: &'static str) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(vec![]));
+
+            let recorded = requests.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(Self::serve(stream, recorded.clone(), response));
+                }
+            });
+
+            Self { url, requests }
+        }
+
+        async fn serve(
+            stream: TcpStream,
+            requests: Arc<Mutex<Vec<Request>>>,
+            response: &'static str,
+        ) {
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            // one request per iteration, the client may reuse the connection
+            while reader.read_line(&mut line).await.unwrap_or(0) > 0 {
+                let mut headers = HashMap::new();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).await.unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(':') else {
+                        break;
+                    };
+                    headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+                }
+
+                let length = headers["content-length"].parse().unwrap();
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).await.unwrap();
+                requests.lock().unwrap().push(Request {
+                    headers,
+                    body: serde_json::from_slice(&body).unwrap(),
+                });
+
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{response}",
+                    response.len()
+                );
+                reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+                line.clear();
+            }
+        }
+
+        fn endpoint(&self, method: BundleSubmitMethod) -> BlockBuilderEndpoint {
+            BlockBuilderEndpoint {
+                name: self.url.clone(),
+                url: self.url.clone(),
+                method,
+                signing_key: None,
+                auth_header: None,
+            }
+        }
+
+        fn requests(&self) -> Vec<Request> {
+            std::mem::take(&mut *self.requests.lock().unwrap())
+        }
+    }
+
+    fn args(builders: Vec<BlockBuilderEndpoint>) -> BundleSenderArgs {
+        BundleSenderArgs {
+            builders,
+            target_blocks: 3,
+            refund_percent: None,
+            refund_recipient: None,
+            allow_revert: false,
+        }
+    }
+
+    fn lease() -> SignerLease {
+        SignerLease::new(Arc::new(PrivateKeySigner::random()), 1)
+    }
+
+    fn tx() -> TransactionRequest {
+        TransactionRequest::default()
+            .to(Address::ZERO)
+            .nonce(0)
+            .gas_limit(100_000)
+            .max_fee_per_gas(10)
+            .max_priority_fee_per_gas(1)
+    }
+
+    async fn send(sender: &BundleTransactionSender, lease: &SignerLease) -> Result<B256> {
+        sender
+            .send_transaction(tx(), &ExpectedStorage::default(), lease, 10)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_send_bundle_per_target_block() {
+        let builder = MockBuilder::start(OK).await;
+        let sender = BundleTransactionSender::new(
+            args(vec![builder.endpoint(BundleSubmitMethod::SendBundle)]),
+            5,
+        )
+        .unwrap();
+        let lease = lease();
+        let raw_tx = lease.sign_tx_raw(tx()).await.unwrap();
+
+        let tx_hash = send(&sender, &lease).await.unwrap();
+        assert_eq!(tx_hash, utils::keccak256(&raw_tx));
+
+        let mut requests = builder.requests();
+        requests.sort_by_key(|r| {
+            r.body["params"][0]["blockNumber"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        });
+        assert_eq!(requests.len(), 3);
+        for (request, target_block) in requests.iter().zip(11_u64..=13) {
+            assert_eq!(request.headers["content-type"], "application/json");
+            assert_eq!(
+                request.body,
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "eth_sendBundle",
+                    "params": [{
+                        "txs": [raw_tx],
+                        "blockNumber": U64::from(target_block),
+                        "revertingTxHashes": [],
+                    }],
+                    "id": 1
+                })
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_bundle_refund_and_revert() {
+        let builder = MockBuilder::start(OK).await;
+        let sender = BundleTransactionSender::new(
+            BundleSenderArgs {
+                target_blocks: 1,
+                refund_percent: Some(90),
+                allow_revert: true,
+                ..args(vec![builder.endpoint(BundleSubmitMethod::SendBundle)])
+            },
+            5,
+        )
+        .unwrap();
+        let lease = lease();
+
+        let tx_hash = send(&sender, &lease).await.unwrap();
+
+        let requests = builder.requests();
+        assert_eq!(requests.len(), 1);
+        let bundle = &requests[0].body["params"][0];
+        assert_eq!(bundle["revertingTxHashes"], json!([tx_hash]));
+        assert_eq!(bundle["refundPercent"], json!(90));
+        // the refund recipient defaults to the sender
+        assert_eq!(bundle["refundRecipient"], json!(lease.address()));
+    }
+
+    #[tokio::test]
+    async fn test_send_private_transaction_refund() {
+        let recipient = address!("0000000000000000000000000000000000000123");
+        let builder = MockBuilder::start(OK).await;
+        let sender = BundleTransactionSender::new(
+            BundleSenderArgs {
+                refund_percent: Some(50),
+                refund_recipient: Some(recipient),
+                ..args(vec![
+                    builder.endpoint(BundleSubmitMethod::SendPrivateTransaction)
+                ])
+            },
+            5,
+        )
+        .unwrap();
+        let lease = lease();
+        let raw_tx = lease.sign_tx_raw(tx()).await.unwrap();
+
+        send(&sender, &lease).await.unwrap();
+
+        let requests = builder.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].body,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "eth_sendPrivateTransaction",
+                "params": [{
+                    "tx": raw_tx,
+                    "maxBlockNumber": U64::from(13),
+                    "preferences": {
+                        "fast": false,
+                        "validity": {
+                            "refund": [{ "address": recipient, "percent": 50 }]
+                        }
+                    }
+                }],
+                "id": 1
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auth_and_signature_headers() {
+        let signer = PrivateKeySigner::random();
+        let builder = MockBuilder::start(OK).await;
+        let sender = BundleTransactionSender::new(
+            BundleSenderArgs {
+                target_blocks: 1,
+                ..args(vec![BlockBuilderEndpoint {
+                    signing_key: Some(hex::encode(signer.to_bytes())),
+                    auth_header: Some("X-Api-Key:  secret ".to_string()),
+                    ..builder.endpoint(BundleSubmitMethod::SendBundle)
+                }])
+            },
+            5,
+        )
+        .unwrap();
+
+        send(&sender, &lease()).await.unwrap();
+
+        let requests = builder.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].headers["x-api-key"], "secret");
+        assert_eq!(
+            requests[0].headers["x-flashbots-signature"],
+            flashbots_signature(&signer, &requests[0].body.to_string())
+                .to_str()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_builder_config() {
+        let endpoint = BlockBuilderEndpoint {
+            name: "builder".to_string(),
+            url: "http://localhost".to_string(),
+            method: BundleSubmitMethod::SendBundle,
+            signing_key: None,
+            auth_header: None,
+        };
+
+        for auth_header in [
+            "no-separator",
+            "invalid name: value",
+            "name: invalid\nvalue",
+        ] {
+            assert!(BlockBuilder::try_from(BlockBuilderEndpoint {
+                auth_header: Some(auth_header.to_string()),
+                ..endpoint.clone()
+            })
+            .is_err());
+        }
+        assert!(BlockBuilder::try_from(BlockBuilderEndpoint {
+            signing_key: Some("not a key".to_string()),
+            ..endpoint.clone()
+        })
+        .is_err());
+        assert!(BundleTransactionSender::new(args(vec![]), 5).is_err());
+        assert!(BundleTransactionSender::new(
+            BundleSenderArgs {
+                target_blocks: 0,
+                ..args(vec![endpoint])
+            },
+            5
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_to_all_builders() {
+        let accepting = MockBuilder::start(OK).await;
+        let rejecting = MockBuilder::start(ERROR).await;
+        let sender = BundleTransactionSender::new(
+            BundleSenderArgs {
+                target_blocks: 1,
+                ..args(vec![
+                    accepting.endpoint(BundleSubmitMethod::SendBundle),
+                    rejecting.endpoint(BundleSubmitMethod::SendPrivateTransaction),
+                ])
+            },
+            5,
+        )
+        .unwrap();
+        let lease = lease();
+
+        // accepted by one of the builders
+        send(&sender, &lease).await.unwrap();
+        assert_eq!(accepting.requests().len(), 1);
+        assert_eq!(rejecting.requests().len(), 1);
+
+        let rejecting_only = BundleTransactionSender::new(
+            BundleSenderArgs {
+                target_blocks: 1,
+                ..args(vec![rejecting.endpoint(BundleSubmitMethod::SendBundle)])
+            },
+            5,
+        )
+        .unwrap();
+        assert!(matches!(
+            send(&rejecting_only, &lease).await,
+            Err(TxSenderError::Rejected)
+        ));
+        assert_eq!(rejecting.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_private_transactions() {
+        let bundle = MockBuilder::start(OK).await;
+        let private = MockBuilder::start(OK).await;
+        let sender = BundleTransactionSender::new(
+            args(vec![
+                bundle.endpoint(BundleSubmitMethod::SendBundle),
+                private.endpoint(BundleSubmitMethod::SendPrivateTransaction),
+            ]),
+            5,
+        )
+        .unwrap();
+        let tx_hash = B256::repeat_byte(1);
+
+        let info = sender
+            .cancel_transaction(tx_hash, 0, GasFees::default(), &lease())
+            .await
+            .unwrap();
+        assert!(info.soft_cancelled);
+        assert_eq!(info.tx_hash, B256::ZERO);
+
+        assert!(bundle.requests().is_empty());
+        let requests = private.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].body,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "eth_cancelPrivateTransaction",
+                "params": [{ "txHash": tx_hash }],
+                "id": 1
+            })
+        );
+    }
+}
@@ -44,6 +44,7 @@ impl TransactionSender for FlashbotsTransactionSender {
         tx: TransactionRequest,
         _expected_storage: &ExpectedStorage,
         signer: &SignerLease,
+        _block_number: u64,
     ) -> Result<B256> {
         let raw_tx = signer
             .sign_tx_raw(tx)
@@ -262,22 +263,12 @@ impl FlashbotsClient {
     }
 
     async fn sign_send_request(&self, body: Value) -> anyhow::Result<Response> {
-        let to_sign = format!("0x{:x}", utils::keccak256(body.to_string()));
-
-        let signature = self
-            .signer
-            .sign_message_sync(to_sign.as_bytes())
-            .expect("Signature failed");
-        let header_val = HeaderValue::from_str(&format!(
-            "{:?}:0x{}",
-            self.signer.address(),
-            hex::encode(signature.as_bytes())
-        ))
-        .expect("Header contains invalid characters");
-
         let mut headers = HeaderMap::new();
         headers.insert(CONTENT_TYPE, "application/json".parse().unwrap());
-        headers.insert("x-flashbots-signature", header_val);
+        headers.insert(
+            "x-flashbots-signature",
+            flashbots_signature(&self.signer, &body.to_string()),
+        );
 
         // Send the request
         let response = self
@@ -295,6 +286,21 @@ impl FlashbotsClient {
     }
 }
 
+/// Signs a request body for the `x-flashbots-signature` header
+pub(super) fn flashbots_signature(signer: &PrivateKeySigner, body: &str) -> HeaderValue {
+    let to_sign = format!("0x{:x}", utils::keccak256(body));
+
+    let signature = signer
+        .sign_message_sync(to_sign.as_bytes())
+        .expect("Signature failed");
+    HeaderValue::from_str(&format!(
+        "{:?}:0x{}",
+        signer.address(),
+        hex::encode(signature.as_bytes())
+    ))
+    .expect("Header contains invalid characters")
+}
+
 fn deserialize_u64<'de, D>(deserializer: D) -> std::result::Result<U64, D::Error>
 where
     D: de::Deserializer<'de>,
@@ -12,11 +12,13 @@
 // If not, see https://www.gnu.org/licenses/.
 
 mod bloxroute;
+mod bundle;
 mod flashbots;
 mod raw;
 
 use alloy_primitives::{Address, B256};
 pub(crate) use bloxroute::PolygonBloxrouteTransactionSender;
+pub(crate) use bundle::BundleTransactionSender;
 use enum_dispatch::enum_dispatch;
 pub(crate) use flashbots::FlashbotsTransactionSender;
 #[cfg(test)]
@@ -25,6 +27,7 @@ pub(crate) use raw::RawTransactionSender;
 use rundler_provider::{EvmProvider, ProviderError, TransactionRequest};
 use rundler_signer::SignerLease;
 use rundler_types::{ExpectedStorage, GasFees};
+use serde::Deserialize;
 
 #[derive(Debug)]
 pub(crate) struct CancelTxInfo {
@@ -77,6 +80,7 @@ pub(crate) trait TransactionSender: Send + Sync {
         tx: TransactionRequest,
         expected_storage: &ExpectedStorage,
         signer: &SignerLease,
+        block_number: u64,
     ) -> Result<B256>;
 
     async fn cancel_transaction(
@@ -94,6 +98,7 @@ pub(crate) enum TransactionSenderEnum<P: EvmProvider> {
     Raw(RawTransactionSender<P>),
     Flashbots(FlashbotsTransactionSender),
     PolygonBloxroute(PolygonBloxrouteTransactionSender<P>),
+    Bundle(BundleTransactionSender),
 }
 
 /// Transaction sender types
@@ -106,6 +111,8 @@ pub enum TransactionSenderKind {
     Flashbots,
     /// Bloxroute transaction sender
     Bloxroute,
+    /// Block builder bundle sender
+    Bundle,
 }
 
 /// Transaction sender types
@@ -117,6 +124,8 @@ pub enum TransactionSenderArgs {
     Flashbots(FlashbotsSenderArgs),
     /// Bloxroute transaction sender
     Bloxroute(BloxrouteSenderArgs),
+    /// Block builder bundle sender
+    Bundle(BundleSenderArgs),
 }
 
 /// Raw sender arguments
@@ -146,7 +155,60 @@ pub struct FlashbotsSenderArgs {
     pub auth_key: String,
 }
 
+/// Bundle sender arguments
+#[derive(Debug, Clone)]
+pub struct BundleSenderArgs {
+    /// Block builders to send to
+    pub builders: Vec<BlockBuilderEndpoint>,
+    /// Number of blocks following the current block that each transaction targets
+    pub target_blocks: u64,
+    /// Percent of the bundle's MEV to refund to `refund_recipient`, if supported by the builder
+    pub refund_percent: Option<u8>,
+    /// Recipient of refunds, defaults to the transaction sender
+    pub refund_recipient: Option<Address>,
+    /// If the builders may include the transaction when it reverts
+    pub allow_revert: bool,
+}
+
+/// A block builder endpoint to send bundles to
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockBuilderEndpoint {
+    /// Name of the builder, used for logging
+    pub name: String,
+    /// RPC URL of the builder
+    pub url: String,
+    /// RPC method used to submit to the builder
+    #[serde(default)]
+    pub method: BundleSubmitMethod,
+    /// Private key used to sign requests in the `x-flashbots-signature` header
+    pub signing_key: Option<String>,
+    /// Header added to requests, formatted as `name: value`
+    pub auth_header: Option<String>,
+}
+
+/// RPC method used to submit to a block builder
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum BundleSubmitMethod {
+    /// Send a single transaction bundle for each target block
+    #[default]
+    #[serde(rename = "eth_sendBundle")]
+    SendBundle,
+    /// Send a private transaction valid until the last target block
+    #[serde(rename = "eth_sendPrivateTransaction")]
+    SendPrivateTransaction,
+}
+
 impl TransactionSenderArgs {
+    /// The number of blocks after being sent that a transaction can be included in,
+    /// if the sender limits inclusion.
+    pub(crate) fn max_inclusion_blocks(&self) -> Option<u64> {
+        match self {
+            Self::Bundle(args) => Some(args.target_blocks),
+            _ => None,
+        }
+    }
+
     pub(crate) fn into_sender(
         self,
         rpc_url: &str,
@@ -172,6 +234,10 @@ impl TransactionSenderArgs {
             Self::Bloxroute(args) => TransactionSenderEnum::PolygonBloxroute(
                 PolygonBloxrouteTransactionSender::new(provider, &args.header)?,
             ),
+            Self::Bundle(args) => TransactionSenderEnum::Bundle(BundleTransactionSender::new(
+                args,
+                provider_client_timeout_seconds,
+            )?),
         };
         Ok(sender)
     }
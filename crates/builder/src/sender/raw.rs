@@ -38,6 +38,7 @@ where
         tx: TransactionRequest,
         expected_storage: &ExpectedStorage,
         signer: &SignerLease,
+        _block_number: u64,
     ) -> Result<B256> {
         let raw_tx = signer
             .sign_tx_raw(tx)
@@ -23,12 +23,17 @@ use rundler_task::{
     GracefulShutdown,
 };
 use rundler_types::{
-    builder::{Builder, BuilderError, BuilderResult, BundlingMode},
+    builder::{
+        Builder, BuilderError, BuilderResult, BundlePreview, BundlingMode, QuarantinedSigner,
+    },
     pool::Pool,
 };
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, Mutex};
 
-use crate::bundle_sender::{BundleSenderAction, SendBundleRequest, SendBundleResult};
+use crate::{
+    bundle_previewer::BundlePreviewer,
+    bundle_sender::{BundleSenderAction, SendBundleRequest, SendBundleResult},
+};
 
 /// Local builder server builder
 pub struct LocalBuilderBuilder {
@@ -65,12 +70,14 @@ impl LocalBuilderBuilder {
     pub fn run(
         self,
         bundle_sender_actions: Vec<mpsc::Sender<BundleSenderAction>>,
+        bundle_previewers: Vec<Box<dyn BundlePreviewer>>,
         entry_points: Vec<Address>,
         shutdown: GracefulShutdown,
     ) -> BoxFuture<'static, ()> {
         let runner = LocalBuilderServerRunner::new(
             self.req_receiver,
             bundle_sender_actions,
+            bundle_previewers,
             entry_points,
             self.signer_manager,
             self.pool,
@@ -88,6 +95,7 @@ pub struct LocalBuilderHandle {
 struct LocalBuilderServerRunner {
     req_receiver: mpsc::Receiver<ServerRequest>,
     bundle_sender_actions: Vec<mpsc::Sender<BundleSenderAction>>,
+    bundle_previewers: Vec<PreviewerHandle>,
     entry_points: Vec<Address>,
     signer_manager: Arc<dyn SignerManager>,
     pool: Arc<dyn Pool>,
@@ -138,6 +146,40 @@ impl Builder for LocalBuilderHandle {
             _ => Err(BuilderError::UnexpectedResponse),
         }
     }
+
+    async fn debug_preview_bundle(
+        &self,
+        entry_point: Address,
+        filter_id: Option<String>,
+    ) -> BuilderResult<BundlePreview> {
+        let req = ServerRequestKind::DebugPreviewBundle {
+            entry_point,
+            filter_id,
+        };
+        let resp = self.send(req).await?;
+        match resp {
+            ServerResponse::DebugPreviewBundle { preview } => Ok(preview),
+            _ => Err(BuilderError::UnexpectedResponse),
+        }
+    }
+
+    async fn admin_get_quarantined_signers(&self) -> BuilderResult<Vec<QuarantinedSigner>> {
+        let req = ServerRequestKind::AdminGetQuarantinedSigners;
+        let resp = self.send(req).await?;
+        match resp {
+            ServerResponse::AdminGetQuarantinedSigners { signers } => Ok(signers),
+            _ => Err(BuilderError::UnexpectedResponse),
+        }
+    }
+
+    async fn admin_unquarantine_signer(&self, address: Address) -> BuilderResult<()> {
+        let req = ServerRequestKind::AdminUnquarantineSigner { address };
+        let resp = self.send(req).await?;
+        match resp {
+            ServerResponse::AdminUnquarantineSigner => Ok(()),
+            _ => Err(BuilderError::UnexpectedResponse),
+        }
+    }
 }
 
 #[async_trait]
@@ -159,6 +201,7 @@ impl LocalBuilderServerRunner {
     fn new(
         req_receiver: mpsc::Receiver<ServerRequest>,
         bundle_sender_actions: Vec<mpsc::Sender<BundleSenderAction>>,
+        bundle_previewers: Vec<Box<dyn BundlePreviewer>>,
         entry_points: Vec<Address>,
         signer_manager: Arc<dyn SignerManager>,
         pool: Arc<dyn Pool>,
@@ -166,6 +209,14 @@ impl LocalBuilderServerRunner {
         Self {
             req_receiver,
             bundle_sender_actions,
+            bundle_previewers: bundle_previewers
+                .into_iter()
+                .map(|previewer| PreviewerHandle {
+                    entry_point: previewer.entry_point(),
+                    filter_id: previewer.filter_id().map(str::to_string),
+                    previewer: Arc::new(Mutex::new(previewer)),
+                })
+                .collect(),
             entry_points,
             signer_manager,
             pool,
@@ -242,6 +293,49 @@ impl LocalBuilderServerRunner {
 
                                 Ok(ServerResponse::DebugSetBundlingMode)
                             },
+                            ServerRequestKind::DebugPreviewBundle { entry_point, filter_id } => {
+                                let mut matching = self
+                                    .bundle_previewers
+                                    .iter()
+                                    .filter(|p| p.entry_point == entry_point && p.filter_id == filter_id)
+                                    .map(|p| p.previewer.clone())
+                                    .collect::<Vec<_>>();
+                                let previewer = match matching.len() {
+                                    0 => break 'a Err(anyhow::anyhow!("no builder for entry point {entry_point:?} and filter id {filter_id:?}").into()),
+                                    1 => matching.pop().unwrap(),
+                                    n => break 'a Err(anyhow::anyhow!("{n} builders for entry point {entry_point:?} and filter id {filter_id:?}, preview is ambiguous").into()),
+                                };
+
+                                // Building a bundle takes a while, don't block other requests on it
+                                tokio::spawn(async move {
+                                    let resp: BuilderResult<ServerResponse> = match previewer.lock().await.preview_bundle().await {
+                                        Ok(preview) => Ok(ServerResponse::DebugPreviewBundle { preview }),
+                                        Err(e) => Err(anyhow::anyhow!("failed to preview bundle: {e:?}").into()),
+                                    };
+                                    if let Err(e) = req.response.send(resp) {
+                                        tracing::error!("failed to send response: {:?}", e);
+                                    }
+                                });
+                                continue;
+                            },
+                            ServerRequestKind::AdminGetQuarantinedSigners => {
+                                let signers = self
+                                    .signer_manager
+                                    .quarantined_signers()
+                                    .into_iter()
+                                    .map(|s| QuarantinedSigner {
+                                        address: s.address,
+                                        reason: s.reason,
+                                    })
+                                    .collect();
+                                Ok(ServerResponse::AdminGetQuarantinedSigners { signers })
+                            },
+                            ServerRequestKind::AdminUnquarantineSigner { address } => {
+                                match self.signer_manager.unquarantine_signer(&address) {
+                                    Ok(()) => Ok(ServerResponse::AdminUnquarantineSigner),
+                                    Err(e) => Err(anyhow::anyhow!("failed to unquarantine signer: {e}").into()),
+                                }
+                            },
                         }
                     };
 
@@ -254,11 +348,28 @@ impl LocalBuilderServerRunner {
     }
 }
 
+// Previews run in their own tasks, the lock serializes previews of the same builder
+struct PreviewerHandle {
+    entry_point: Address,
+    filter_id: Option<String>,
+    previewer: Arc<Mutex<Box<dyn BundlePreviewer>>>,
+}
+
 #[derive(Clone, Debug)]
 enum ServerRequestKind {
     GetSupportedEntryPoints,
     DebugSendBundleNow,
-    DebugSetBundlingMode { mode: BundlingMode },
+    DebugSetBundlingMode {
+        mode: BundlingMode,
+    },
+    DebugPreviewBundle {
+        entry_point: Address,
+        filter_id: Option<String>,
+    },
+    AdminGetQuarantinedSigners,
+    AdminUnquarantineSigner {
+        address: Address,
+    },
 }
 
 #[derive(Debug)]
@@ -272,4 +383,7 @@ enum ServerResponse {
     GetSupportedEntryPoints { entry_points: Vec<Address> },
     DebugSendBundleNow { hash: B256, block_number: u64 },
     DebugSetBundlingMode,
+    DebugPreviewBundle { preview: BundlePreview },
+    AdminGetQuarantinedSigners { signers: Vec<QuarantinedSigner> },
+    AdminUnquarantineSigner,
 }
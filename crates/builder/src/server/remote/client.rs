@@ -14,12 +14,15 @@
 use std::str::FromStr;
 
 use alloy_primitives::{Address, B256};
+use anyhow::Context;
 use async_trait::async_trait;
 use rundler_task::{
     grpc::protos::{from_bytes, ConversionError},
     server::{HealthCheck, ServerStatus},
 };
-use rundler_types::builder::{Builder, BuilderError, BuilderResult, BundlingMode};
+use rundler_types::builder::{
+    Builder, BuilderError, BuilderResult, BundlePreview, BundlingMode, QuarantinedSigner,
+};
 use tonic::transport::{Channel, Uri};
 use tonic_health::{
     pb::{health_client::HealthClient, HealthCheckRequest},
@@ -27,9 +30,11 @@ use tonic_health::{
 };
 
 use super::protos::{
-    builder_client::BuilderClient, debug_send_bundle_now_response,
-    debug_set_bundling_mode_response, BundlingMode as ProtoBundlingMode, DebugSendBundleNowRequest,
-    DebugSetBundlingModeRequest, GetSupportedEntryPointsRequest,
+    admin_get_quarantined_signers_response, admin_unquarantine_signer_response,
+    builder_client::BuilderClient, debug_preview_bundle_response, debug_send_bundle_now_response,
+    debug_set_bundling_mode_response, AdminGetQuarantinedSignersRequest,
+    AdminUnquarantineSignerRequest, BundlingMode as ProtoBundlingMode, DebugPreviewBundleRequest,
+    DebugSendBundleNowRequest, DebugSetBundlingModeRequest, GetSupportedEntryPointsRequest,
 };
 
 /// Remote builder client, used for communicating with a remote builder server
@@ -110,6 +115,81 @@ impl Builder for RemoteBuilderClient {
             )))?,
         }
     }
+
+    async fn debug_preview_bundle(
+        &self,
+        entry_point: Address,
+        filter_id: Option<String>,
+    ) -> BuilderResult<BundlePreview> {
+        let res = self
+            .grpc_client
+            .clone()
+            .debug_preview_bundle(DebugPreviewBundleRequest {
+                entry_point: entry_point.to_vec(),
+                filter_id: filter_id.unwrap_or_default(),
+            })
+            .await
+            .map_err(anyhow::Error::from)?
+            .into_inner()
+            .result;
+
+        match res {
+            Some(debug_preview_bundle_response::Result::Success(s)) => Ok(s
+                .preview
+                .context("should have received preview from builder")?
+                .try_into()
+                .map_err(anyhow::Error::from)?),
+            Some(debug_preview_bundle_response::Result::Failure(f)) => Err(f.try_into()?),
+            None => Err(BuilderError::Other(anyhow::anyhow!(
+                "should have received result from builder"
+            )))?,
+        }
+    }
+
+    async fn admin_get_quarantined_signers(&self) -> BuilderResult<Vec<QuarantinedSigner>> {
+        let res = self
+            .grpc_client
+            .clone()
+            .admin_get_quarantined_signers(AdminGetQuarantinedSignersRequest {})
+            .await
+            .map_err(anyhow::Error::from)?
+            .into_inner()
+            .result;
+
+        match res {
+            Some(admin_get_quarantined_signers_response::Result::Success(s)) => Ok(s
+                .signers
+                .into_iter()
+                .map(QuarantinedSigner::try_from)
+                .collect::<Result<_, ConversionError>>()
+                .map_err(anyhow::Error::from)?),
+            Some(admin_get_quarantined_signers_response::Result::Failure(f)) => Err(f.try_into()?),
+            None => Err(BuilderError::Other(anyhow::anyhow!(
+                "should have received result from builder"
+            )))?,
+        }
+    }
+
+    async fn admin_unquarantine_signer(&self, address: Address) -> BuilderResult<()> {
+        let res = self
+            .grpc_client
+            .clone()
+            .admin_unquarantine_signer(AdminUnquarantineSignerRequest {
+                address: address.to_vec(),
+            })
+            .await
+            .map_err(anyhow::Error::from)?
+            .into_inner()
+            .result;
+
+        match res {
+            Some(admin_unquarantine_signer_response::Result::Success(_)) => Ok(()),
+            Some(admin_unquarantine_signer_response::Result::Failure(f)) => Err(f.try_into()?),
+            None => Err(BuilderError::Other(anyhow::anyhow!(
+                "should have received result from builder"
+            )))?,
+        }
+    }
 }
 
 #[async_trait]
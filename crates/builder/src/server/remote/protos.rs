@@ -11,8 +11,16 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use rundler_task::grpc::protos::ConversionError;
-use rundler_types::builder::BundlingMode as RpcBundlingMode;
+use alloy_primitives::Address;
+use rundler_task::grpc::protos::{from_bytes, ConversionError, ToProtoBytes};
+use rundler_types::{
+    builder::{
+        BundlePreview as RpcBundlePreview, BundlePreviewExcludedOp as RpcBundlePreviewExcludedOp,
+        BundlePreviewOp as RpcBundlePreviewOp, BundlingMode as RpcBundlingMode,
+        QuarantinedSigner as RpcQuarantinedSigner,
+    },
+    ExpectedStorage, GasFees,
+};
 
 tonic::include_proto!("builder");
 
@@ -39,3 +47,217 @@ impl TryFrom<BundlingMode> for RpcBundlingMode {
         }
     }
 }
+
+impl From<&RpcBundlePreview> for BundlePreview {
+    fn from(preview: &RpcBundlePreview) -> Self {
+        Self {
+            entry_point: preview.entry_point.to_proto_bytes(),
+            builder_address: preview.builder_address.to_proto_bytes(),
+            block_hash: preview.block_hash.to_proto_bytes(),
+            ops: preview.ops.iter().map(BundlePreviewOp::from).collect(),
+            rejected_ops: preview
+                .rejected_ops
+                .iter()
+                .map(BundlePreviewExcludedOp::from)
+                .collect(),
+            skipped_ops: preview
+                .skipped_ops
+                .iter()
+                .map(BundlePreviewExcludedOp::from)
+                .collect(),
+            gas_estimate: preview.gas_estimate,
+            max_fee_per_gas: preview.gas_fees.max_fee_per_gas.to_proto_bytes(),
+            max_priority_fee_per_gas: preview.gas_fees.max_priority_fee_per_gas.to_proto_bytes(),
+            expected_storage: preview
+                .expected_storage
+                .0
+                .iter()
+                .flat_map(|(address, slots)| {
+                    slots.iter().map(|(slot, value)| ExpectedStorageSlot {
+                        address: address.to_proto_bytes(),
+                        slot: slot.to_proto_bytes(),
+                        value: value.to_proto_bytes(),
+                    })
+                })
+                .collect(),
+            da_gas: preview.da_gas.to_proto_bytes(),
+        }
+    }
+}
+
+impl TryFrom<BundlePreview> for RpcBundlePreview {
+    type Error = ConversionError;
+
+    fn try_from(preview: BundlePreview) -> Result<Self, Self::Error> {
+        let mut expected_storage = ExpectedStorage::default();
+        for slot in preview.expected_storage {
+            expected_storage
+                .0
+                .entry(from_bytes(&slot.address)?)
+                .or_default()
+                .insert(from_bytes(&slot.slot)?, from_bytes(&slot.value)?);
+        }
+
+        Ok(Self {
+            entry_point: from_bytes(&preview.entry_point)?,
+            builder_address: from_bytes(&preview.builder_address)?,
+            block_hash: from_bytes(&preview.block_hash)?,
+            ops: preview
+                .ops
+                .into_iter()
+                .map(RpcBundlePreviewOp::try_from)
+                .collect::<Result<_, _>>()?,
+            rejected_ops: preview
+                .rejected_ops
+                .into_iter()
+                .map(RpcBundlePreviewExcludedOp::try_from)
+                .collect::<Result<_, _>>()?,
+            skipped_ops: preview
+                .skipped_ops
+                .into_iter()
+                .map(RpcBundlePreviewExcludedOp::try_from)
+                .collect::<Result<_, _>>()?,
+            gas_estimate: preview.gas_estimate,
+            gas_fees: GasFees {
+                max_fee_per_gas: from_bytes(&preview.max_fee_per_gas)?,
+                max_priority_fee_per_gas: from_bytes(&preview.max_priority_fee_per_gas)?,
+            },
+            expected_storage,
+            da_gas: from_bytes(&preview.da_gas)?,
+        })
+    }
+}
+
+impl From<&RpcBundlePreviewOp> for BundlePreviewOp {
+    fn from(op: &RpcBundlePreviewOp) -> Self {
+        Self {
+            hash: op.hash.to_proto_bytes(),
+            sender: op.sender.to_proto_bytes(),
+            nonce: op.nonce.to_proto_bytes(),
+            aggregator: op
+                .aggregator
+                .map(|a| a.to_proto_bytes())
+                .unwrap_or_default(),
+            da_gas: op.da_gas.to_proto_bytes(),
+        }
+    }
+}
+
+impl TryFrom<BundlePreviewOp> for RpcBundlePreviewOp {
+    type Error = ConversionError;
+
+    fn try_from(op: BundlePreviewOp) -> Result<Self, Self::Error> {
+        let aggregator = if op.aggregator.is_empty() {
+            None
+        } else {
+            Some(from_bytes::<Address>(&op.aggregator)?)
+        };
+
+        Ok(Self {
+            hash: from_bytes(&op.hash)?,
+            sender: from_bytes(&op.sender)?,
+            nonce: from_bytes(&op.nonce)?,
+            aggregator,
+            da_gas: from_bytes(&op.da_gas)?,
+        })
+    }
+}
+
+impl From<&RpcBundlePreviewExcludedOp> for BundlePreviewExcludedOp {
+    fn from(op: &RpcBundlePreviewExcludedOp) -> Self {
+        Self {
+            hash: op.hash.to_proto_bytes(),
+            reason: op.reason.clone(),
+        }
+    }
+}
+
+impl TryFrom<BundlePreviewExcludedOp> for RpcBundlePreviewExcludedOp {
+    type Error = ConversionError;
+
+    fn try_from(op: BundlePreviewExcludedOp) -> Result<Self, Self::Error> {
+        Ok(Self {
+            hash: from_bytes(&op.hash)?,
+            reason: op.reason,
+        })
+    }
+}
+
+impl From<&RpcQuarantinedSigner> for QuarantinedSigner {
+    fn from(signer: &RpcQuarantinedSigner) -> Self {
+        Self {
+            address: signer.address.to_proto_bytes(),
+            reason: signer.reason.clone(),
+        }
+    }
+}
+
+impl TryFrom<QuarantinedSigner> for RpcQuarantinedSigner {
+    type Error = ConversionError;
+
+    fn try_from(signer: QuarantinedSigner) -> Result<Self, Self::Error> {
+        Ok(Self {
+            address: from_bytes(&signer.address)?,
+            reason: signer.reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{B256, U256};
+
+    use super::*;
+
+    #[test]
+    fn test_bundle_preview_roundtrip() {
+        let mut expected_storage = ExpectedStorage::default();
+        expected_storage.insert(Address::random(), U256::from(1), U256::from(2));
+
+        let preview = RpcBundlePreview {
+            entry_point: Address::random(),
+            builder_address: Address::random(),
+            block_hash: B256::random(),
+            ops: vec![
+                RpcBundlePreviewOp {
+                    hash: B256::random(),
+                    sender: Address::random(),
+                    nonce: U256::from(7),
+                    aggregator: None,
+                    da_gas: 100,
+                },
+                RpcBundlePreviewOp {
+                    hash: B256::random(),
+                    sender: Address::random(),
+                    nonce: U256::ZERO,
+                    aggregator: Some(Address::random()),
+                    da_gas: 0,
+                },
+            ],
+            rejected_ops: vec![RpcBundlePreviewExcludedOp {
+                hash: B256::random(),
+                reason: "rejected".to_string(),
+            }],
+            skipped_ops: vec![],
+            gas_estimate: 1_000_000,
+            gas_fees: GasFees {
+                max_fee_per_gas: 10,
+                max_priority_fee_per_gas: 1,
+            },
+            expected_storage,
+            da_gas: 100,
+        };
+
+        let decoded = RpcBundlePreview::try_from(BundlePreview::from(&preview)).unwrap();
+        assert_eq!(decoded.entry_point, preview.entry_point);
+        assert_eq!(decoded.builder_address, preview.builder_address);
+        assert_eq!(decoded.block_hash, preview.block_hash);
+        assert_eq!(decoded.ops, preview.ops);
+        assert_eq!(decoded.rejected_ops, preview.rejected_ops);
+        assert_eq!(decoded.skipped_ops, preview.skipped_ops);
+        assert_eq!(decoded.gas_estimate, preview.gas_estimate);
+        assert_eq!(decoded.gas_fees, preview.gas_fees);
+        assert_eq!(decoded.expected_storage.0, preview.expected_storage.0);
+        assert_eq!(decoded.da_gas, preview.da_gas);
+    }
+}
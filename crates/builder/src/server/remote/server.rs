@@ -13,16 +13,22 @@
 
 use std::net::SocketAddr;
 
-use rundler_task::GracefulShutdown;
+use rundler_task::{grpc::protos::from_bytes, GracefulShutdown};
 use rundler_types::builder::Builder;
 use tonic::{async_trait, transport::Server, Request, Response, Status};
 
 use super::protos::{
+    admin_get_quarantined_signers_response, admin_unquarantine_signer_response,
     builder_server::{Builder as GrpcBuilder, BuilderServer as GrpcBuilderServer},
-    debug_send_bundle_now_response, debug_set_bundling_mode_response, BundlingMode,
-    DebugSendBundleNowRequest, DebugSendBundleNowResponse, DebugSetBundlingModeRequest,
-    DebugSetBundlingModeResponse, DebugSetBundlingModeSuccess, GetSupportedEntryPointsRequest,
-    GetSupportedEntryPointsResponse, BUILDER_FILE_DESCRIPTOR_SET,
+    debug_preview_bundle_response, debug_send_bundle_now_response,
+    debug_set_bundling_mode_response, AdminGetQuarantinedSignersRequest,
+    AdminGetQuarantinedSignersResponse, AdminGetQuarantinedSignersSuccess,
+    AdminUnquarantineSignerRequest, AdminUnquarantineSignerResponse,
+    AdminUnquarantineSignerSuccess, BundlingMode, DebugPreviewBundleRequest,
+    DebugPreviewBundleResponse, DebugPreviewBundleSuccess, DebugSendBundleNowRequest,
+    DebugSendBundleNowResponse, DebugSetBundlingModeRequest, DebugSetBundlingModeResponse,
+    DebugSetBundlingModeSuccess, GetSupportedEntryPointsRequest, GetSupportedEntryPointsResponse,
+    BUILDER_FILE_DESCRIPTOR_SET,
 };
 use crate::server::{local::LocalBuilderHandle, remote::protos::DebugSendBundleNowSuccess};
 
@@ -142,4 +148,82 @@ impl GrpcBuilder for GrpcBuilderServerImpl {
 
         Ok(Response::new(resp))
     }
+
+    async fn debug_preview_bundle(
+        &self,
+        request: Request<DebugPreviewBundleRequest>,
+    ) -> tonic::Result<Response<DebugPreviewBundleResponse>> {
+        let req = request.into_inner();
+        let entry_point = from_bytes(&req.entry_point)
+            .map_err(|e| Status::invalid_argument(format!("Invalid entry point: {e}")))?;
+        let filter_id = if req.filter_id.is_empty() {
+            None
+        } else {
+            Some(req.filter_id)
+        };
+
+        let resp = match self
+            .local_builder
+            .debug_preview_bundle(entry_point, filter_id)
+            .await
+        {
+            Ok(preview) => DebugPreviewBundleResponse {
+                result: Some(debug_preview_bundle_response::Result::Success(
+                    DebugPreviewBundleSuccess {
+                        preview: Some((&preview).into()),
+                    },
+                )),
+            },
+            Err(e) => {
+                return Err(Status::internal(format!("Failed to preview bundle: {e}")));
+            }
+        };
+
+        Ok(Response::new(resp))
+    }
+
+    async fn admin_get_quarantined_signers(
+        &self,
+        _request: Request<AdminGetQuarantinedSignersRequest>,
+    ) -> tonic::Result<Response<AdminGetQuarantinedSignersResponse>> {
+        let resp = match self.local_builder.admin_get_quarantined_signers().await {
+            Ok(signers) => AdminGetQuarantinedSignersResponse {
+                result: Some(admin_get_quarantined_signers_response::Result::Success(
+                    AdminGetQuarantinedSignersSuccess {
+                        signers: signers.iter().map(|s| s.into()).collect(),
+                    },
+                )),
+            },
+            Err(e) => {
+                return Err(Status::internal(format!(
+                    "Failed to get quarantined signers: {e}"
+                )));
+            }
+        };
+
+        Ok(Response::new(resp))
+    }
+
+    async fn admin_unquarantine_signer(
+        &self,
+        request: Request<AdminUnquarantineSignerRequest>,
+    ) -> tonic::Result<Response<AdminUnquarantineSignerResponse>> {
+        let address = from_bytes(&request.into_inner().address)
+            .map_err(|e| Status::invalid_argument(format!("Invalid address: {e}")))?;
+
+        let resp = match self.local_builder.admin_unquarantine_signer(address).await {
+            Ok(()) => AdminUnquarantineSignerResponse {
+                result: Some(admin_unquarantine_signer_response::Result::Success(
+                    AdminUnquarantineSignerSuccess {},
+                )),
+            },
+            Err(e) => {
+                return Err(Status::internal(format!(
+                    "Failed to unquarantine signer: {e}"
+                )));
+            }
+        };
+
+        Ok(Response::new(resp))
+    }
 }
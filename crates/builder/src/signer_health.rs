@@ -0,0 +1,121 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::{collections::HashMap, fmt};
+
+/// Failure seen while sending with a signer that may indicate the signer is stuck
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum SignerHealthEvent {
+    /// A transaction was rejected with a nonce below the account's nonce
+    NonceTooLow,
+    /// A transaction was rejected because the account can't pay for it
+    InsufficientFunds,
+    /// A cancellation was abandoned after the maximum number of fee increases
+    StalledCancellation,
+    /// The account's nonce was used by a transaction that wasn't sent by the builder
+    UnexpectedNonceChange,
+}
+
+impl fmt::Display for SignerHealthEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerHealthEvent::NonceTooLow => write!(f, "nonce too low"),
+            SignerHealthEvent::InsufficientFunds => write!(f, "insufficient funds"),
+            SignerHealthEvent::StalledCancellation => {
+                write!(f, "cancellation stalled at max fee increases")
+            }
+            SignerHealthEvent::UnexpectedNonceChange => write!(f, "unexpected nonce change"),
+        }
+    }
+}
+
+/// Counts the consecutive failures of each kind for a builder's signer.
+///
+/// Any transaction of the signer mining resets the counts, so only a signer that fails
+/// repeatedly without making progress reaches the threshold.
+#[derive(Debug)]
+pub(crate) struct SignerHealth {
+    threshold: Option<u64>,
+    counts: HashMap<SignerHealthEvent, u64>,
+}
+
+impl SignerHealth {
+    /// Create a tracker that reports a signer as unhealthy after `threshold` consecutive
+    /// events of the same kind. If `None`, signers are never reported as unhealthy.
+    pub(crate) fn new(threshold: Option<u64>) -> Self {
+        Self {
+            threshold,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Records an event, returning the reason to quarantine the signer if the event
+    /// reached the threshold.
+    pub(crate) fn record(&mut self, event: SignerHealthEvent) -> Option<String> {
+        let threshold = self.threshold?;
+        let count = self.counts.entry(event).or_default();
+        *count += 1;
+
+        (*count >= threshold).then(|| format!("{event} {count} consecutive times"))
+    }
+
+    /// Clears all counts after the signer made progress
+    pub(crate) fn reset(&mut self) {
+        self.counts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold() {
+        let mut health = SignerHealth::new(Some(3));
+
+        assert_eq!(health.record(SignerHealthEvent::NonceTooLow), None);
+        assert_eq!(health.record(SignerHealthEvent::InsufficientFunds), None);
+        assert_eq!(health.record(SignerHealthEvent::NonceTooLow), None);
+        assert_eq!(
+            health.record(SignerHealthEvent::NonceTooLow),
+            Some("nonce too low 3 consecutive times".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut health = SignerHealth::new(Some(2));
+
+        assert_eq!(
+            health.record(SignerHealthEvent::UnexpectedNonceChange),
+            None
+        );
+        health.reset();
+        assert_eq!(
+            health.record(SignerHealthEvent::UnexpectedNonceChange),
+            None
+        );
+        assert!(health
+            .record(SignerHealthEvent::UnexpectedNonceChange)
+            .is_some());
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut health = SignerHealth::new(None);
+
+        for _ in 0..100 {
+            assert_eq!(health.record(SignerHealthEvent::StalledCancellation), None);
+        }
+    }
+}
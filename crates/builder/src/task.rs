@@ -31,11 +31,13 @@ use rundler_types::{
     chain::ChainSpec, pool::Pool as PoolT, EntryPointVersion, UserOperation, UserOperationVariant,
 };
 use rundler_utils::emit::WithEntryPoint;
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::{broadcast, mpsc, watch};
 use tracing::info;
 
 use crate::{
-    assigner::Assigner,
+    assigner::{Assigner, AssignmentBackend},
+    bundle_ordering::BundleOrderingStrategy,
+    bundle_previewer::{self, BundlePreviewer, BundlePreviewerImpl},
     bundle_proposer::{self, BundleProposerImpl, BundleProposerProviders},
     bundle_sender::{self, BundleSender, BundleSenderAction, BundleSenderImpl},
     emit::BuilderEvent,
@@ -45,6 +47,7 @@ use crate::{
 };
 
 const MAX_POOL_OPS_PER_REQUEST: u64 = 1024;
+const PREVIEW_EVENT_CAPACITY: usize = 1024;
 
 /// Builder task arguments
 #[derive(Debug)]
@@ -77,6 +80,15 @@ pub struct Args {
     pub max_cancellation_fee_increases: u64,
     /// Maximum amount of blocks to spend in a replacement underpriced state before moving to cancel
     pub max_replacement_underpriced_blocks: u64,
+    /// Maximum number of bundle transactions a sender can have pending at once, each with its
+    /// own nonce. Values above 1 enable pipelined submission in auto bundling mode.
+    pub max_pending_bundles: u64,
+    /// Number of consecutive failures of the same kind, i.e. nonce too low or insufficient
+    /// funds, after which a builder's signer is quarantined and replaced with a spare signer.
+    /// If none, signers are never quarantined.
+    pub signer_quarantine_threshold: Option<u64>,
+    /// Where the assignments of senders to builders are kept
+    pub assignment_backend: AssignmentBackend,
     /// Address to bind the remote builder server to, if any. If none, no server is starter.
     pub remote_address: Option<SocketAddr>,
     /// Entry points to start builders for
@@ -98,6 +110,10 @@ pub struct BuilderSettings {
     pub submission_proxy: Option<Address>,
     /// Optional filter id to apply to this builder
     pub filter_id: Option<String>,
+    /// Strategy used to order user operations in this builder's bundles
+    pub ordering: BundleOrderingStrategy,
+    /// Optional minimum expected profit margin, in percent of cost, for this builder's bundles
+    pub min_profit_margin_pct: Option<i32>,
 }
 
 impl BuilderSettings {
@@ -168,6 +184,7 @@ where
         T: TaskSpawnerExt,
     {
         let mut bundle_sender_actions = vec![];
+        let mut bundle_previewers = vec![];
 
         let num_required_signers: usize = self
             .args
@@ -197,6 +214,7 @@ where
 
         let assigner = Arc::new(Assigner::new(
             Box::new(self.pool.clone()),
+            self.args.assignment_backend,
             MAX_POOL_OPS_PER_REQUEST,
             self.args.max_bundle_size,
         ));
@@ -213,7 +231,10 @@ where
                             assigner.clone(),
                         )
                         .await?;
-                    bundle_sender_actions.extend(actions);
+                    for (action, previewer) in actions {
+                        bundle_sender_actions.push(action);
+                        bundle_previewers.push(previewer);
+                    }
                     supported_entry_points.insert(self.args.chain_spec.entry_point_address_v0_6);
                 }
                 EntryPointVersion::V0_7 => {
@@ -225,9 +246,27 @@ where
                             assigner.clone(),
                         )
                         .await?;
-                    bundle_sender_actions.extend(actions);
+                    for (action, previewer) in actions {
+                        bundle_sender_actions.push(action);
+                        bundle_previewers.push(previewer);
+                    }
                     supported_entry_points.insert(self.args.chain_spec.entry_point_address_v0_7);
                 }
+                EntryPointVersion::V0_8 => {
+                    let actions = self
+                        .create_builders_v0_8(
+                            &task_spawner,
+                            ep,
+                            &self.signer_manager,
+                            assigner.clone(),
+                        )
+                        .await?;
+                    for (action, previewer) in actions {
+                        bundle_sender_actions.push(action);
+                        bundle_previewers.push(previewer);
+                    }
+                    supported_entry_points.insert(self.args.chain_spec.entry_point_address_v0_8);
+                }
                 EntryPointVersion::Unspecified => {
                     panic!("Unspecified entry point version")
                 }
@@ -241,6 +280,7 @@ where
             |shutdown| {
                 self.builder_builder.run(
                     bundle_sender_actions,
+                    bundle_previewers,
                     supported_entry_points.into_iter().collect(),
                     shutdown,
                 )
@@ -271,7 +311,7 @@ where
         ep: &EntryPointBuilderSettings,
         signer_manager: &Arc<dyn SignerManager>,
         assigner: Arc<Assigner>,
-    ) -> anyhow::Result<Vec<mpsc::Sender<BundleSenderAction>>>
+    ) -> anyhow::Result<Vec<(mpsc::Sender<BundleSenderAction>, Box<dyn BundlePreviewer>)>>
     where
         T: TaskSpawnerExt,
     {
@@ -281,9 +321,9 @@ where
             .ep_v0_6_providers()
             .clone()
             .context("entry point v0.6 not supplied")?;
-        let mut bundle_sender_actions = vec![];
+        let mut builders = vec![];
         for settings in &ep.builders {
-            let bundle_sender_action = if self.args.unsafe_mode {
+            let builder = if self.args.unsafe_mode {
                 self.create_bundle_builder(
                     task_spawner,
                     settings,
@@ -312,9 +352,9 @@ where
                 )
                 .await?
             };
-            bundle_sender_actions.push(bundle_sender_action);
+            builders.push(builder);
         }
-        Ok(bundle_sender_actions)
+        Ok(builders)
     }
 
     async fn create_builders_v0_7<T>(
@@ -323,7 +363,7 @@ where
         ep: &EntryPointBuilderSettings,
         signer_manager: &Arc<dyn SignerManager>,
         assigner: Arc<Assigner>,
-    ) -> anyhow::Result<Vec<mpsc::Sender<BundleSenderAction>>>
+    ) -> anyhow::Result<Vec<(mpsc::Sender<BundleSenderAction>, Box<dyn BundlePreviewer>)>>
     where
         T: TaskSpawnerExt,
     {
@@ -333,9 +373,9 @@ where
             .ep_v0_7_providers()
             .clone()
             .context("entry point v0.7 not supplied")?;
-        let mut bundle_sender_actions = vec![];
+        let mut builders = vec![];
         for settings in &ep.builders {
-            let bundle_sender_action = if self.args.unsafe_mode {
+            let builder = if self.args.unsafe_mode {
                 self.create_bundle_builder(
                     task_spawner,
                     settings,
@@ -364,9 +404,61 @@ where
                 )
                 .await?
             };
-            bundle_sender_actions.push(bundle_sender_action);
+            builders.push(builder);
         }
-        Ok(bundle_sender_actions)
+        Ok(builders)
+    }
+
+    async fn create_builders_v0_8<T>(
+        &self,
+        task_spawner: &T,
+        ep: &EntryPointBuilderSettings,
+        signer_manager: &Arc<dyn SignerManager>,
+        assigner: Arc<Assigner>,
+    ) -> anyhow::Result<Vec<(mpsc::Sender<BundleSenderAction>, Box<dyn BundlePreviewer>)>>
+    where
+        T: TaskSpawnerExt,
+    {
+        info!("Mempool config for ep v0.8: {:?}", ep.mempool_configs);
+        let ep_providers = self
+            .providers
+            .ep_v0_8_providers()
+            .clone()
+            .context("entry point v0.8 not supplied")?;
+        let mut builders = vec![];
+        for settings in &ep.builders {
+            let builder = if self.args.unsafe_mode {
+                self.create_bundle_builder(
+                    task_spawner,
+                    settings,
+                    ep_providers.clone(),
+                    UnsafeSimulator::new(
+                        ep_providers.entry_point().clone(),
+                        self.args.sim_settings.clone(),
+                    ),
+                    signer_manager,
+                    assigner.clone(),
+                )
+                .await?
+            } else {
+                self.create_bundle_builder(
+                    task_spawner,
+                    settings,
+                    ep_providers.clone(),
+                    simulation::new_v0_8_simulator(
+                        ep_providers.evm().clone(),
+                        ep_providers.entry_point().clone(),
+                        self.args.sim_settings.clone(),
+                        ep.mempool_configs.clone(),
+                    ),
+                    signer_manager,
+                    assigner.clone(),
+                )
+                .await?
+            };
+            builders.push(builder);
+        }
+        Ok(builders)
     }
 
     async fn create_bundle_builder<T, UO, EP, S>(
@@ -377,7 +469,7 @@ where
         simulator: S,
         signer_manager: &Arc<dyn SignerManager>,
         assigner: Arc<Assigner>,
-    ) -> anyhow::Result<mpsc::Sender<BundleSenderAction>>
+    ) -> anyhow::Result<(mpsc::Sender<BundleSenderAction>, Box<dyn BundlePreviewer>)>
     where
         T: TaskSpawnerExt,
         UO: UserOperation + From<UserOperationVariant>,
@@ -408,6 +500,7 @@ where
             chain_spec: self.args.chain_spec.clone(),
             target_bundle_gas: self.args.target_bundle_gas,
             max_bundle_gas: self.args.max_bundle_gas,
+            max_bundle_size: self.args.max_bundle_size,
             sender_eoa,
             da_gas_tracking_enabled: self.args.da_gas_tracking_enabled,
             max_expected_storage_slots: self.args.max_expected_storage_slots,
@@ -415,6 +508,8 @@ where
                 .args
                 .verification_gas_limit_efficiency_reject_threshold,
             submission_proxy: submission_proxy.cloned(),
+            ordering: builder_settings.ordering,
+            min_profit_margin_pct: builder_settings.min_profit_margin_pct,
         };
 
         let transaction_sender = self.args.sender_args.clone().into_sender(
@@ -424,6 +519,7 @@ where
 
         let tracker_settings = transaction_tracker::Settings {
             replacement_fee_percent_increase: self.args.replacement_fee_percent_increase,
+            max_inclusion_blocks: self.args.sender_args.max_inclusion_blocks(),
         };
 
         let transaction_tracker = TransactionTrackerImpl::new(
@@ -439,10 +535,42 @@ where
             max_replacement_underpriced_blocks: self.args.max_replacement_underpriced_blocks,
             max_cancellation_fee_increases: self.args.max_cancellation_fee_increases,
             max_blocks_to_wait_for_mine: self.args.max_blocks_to_wait_for_mine,
+            max_pending_bundles: self.args.max_pending_bundles,
+            signer_quarantine_threshold: self.args.signer_quarantine_threshold,
         };
 
+        let (sender_eoa_tx, sender_eoa_rx) = watch::channel(sender_eoa);
+        let simulator = Arc::new(simulator);
+        let builder_tag = builder_settings.tag(ep_providers.entry_point().address(), &sender_eoa);
+
+        // The previewer gets its own proposer and event channel so that previews
+        // don't touch the builder's proposer state or emit to the global event stream.
+        let (preview_event_sender, preview_event_receiver) =
+            broadcast::channel(PREVIEW_EVENT_CAPACITY);
+        let preview_proposer = BundleProposerImpl::new(
+            format!("{builder_tag}:preview"),
+            ep_providers.clone(),
+            BundleProposerProviders::new(simulator.clone()),
+            proposer_settings.clone(),
+            preview_event_sender,
+        );
+        let previewer = BundlePreviewerImpl::new(
+            preview_proposer,
+            ep_providers.clone(),
+            self.pool.clone(),
+            bundle_previewer::Settings {
+                chain_spec: self.args.chain_spec.clone(),
+                sender_eoa: sender_eoa_rx,
+                filter_id: builder_settings.filter_id.clone(),
+                max_bundle_size: self.args.max_bundle_size,
+                max_pool_ops_per_request: MAX_POOL_OPS_PER_REQUEST,
+                ordering: builder_settings.ordering,
+            },
+            preview_event_receiver,
+        );
+
         let proposer = BundleProposerImpl::new(
-            builder_settings.tag(ep_providers.entry_point().address(), &sender_eoa),
+            builder_tag,
             ep_providers.clone(),
             BundleProposerProviders::new(simulator),
             proposer_settings,
@@ -454,6 +582,8 @@ where
             send_bundle_rx,
             self.args.chain_spec.clone(),
             sender_eoa,
+            sender_eoa_tx,
+            signer_manager.clone(),
             submission_proxy.cloned(),
             proposer,
             ep_providers.clone(),
@@ -468,6 +598,6 @@ where
         let ts = task_spawner.clone();
         task_spawner.spawn_critical("bundle sender", builder.send_bundles_in_loop(ts));
 
-        Ok(send_bundle_tx)
+        Ok((send_bundle_tx, Box::new(previewer)))
     }
 }
@@ -11,6 +11,8 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
+use std::collections::BTreeMap;
+
 use alloy_consensus::Transaction;
 use alloy_primitives::{Address, B256, I256, U256};
 use anyhow::bail;
@@ -36,6 +38,11 @@ use crate::sender::{TransactionSender, TxSenderError};
 /// until it returns a `TrackerUpdate` to indicate whether a transaction has
 /// succeeded (potentially not the most recent one) or whether circumstances
 /// have changed so that it is worth making another attempt.
+///
+/// When pipelining, `advance_nonce` moves the current transaction in flight so
+/// that the next transaction can be sent with the next nonce before it mines.
+/// Updates for in flight transactions are collected by `process_update` and
+/// returned by `take_in_flight_updates`.
 #[async_trait]
 #[cfg_attr(test, automock)]
 pub(crate) trait TransactionTracker: Send + Sync {
@@ -80,6 +87,11 @@ pub(crate) trait TransactionTracker: Send + Sync {
     /// Resets the tracker to its initial state
     async fn reset(&mut self);
 
+    /// Starts tracking the account of a new signer, returning the previous signer.
+    ///
+    /// All transactions of the previous signer are forgotten and the tracker is reset.
+    async fn replace_signer(&mut self, signer: SignerLease) -> SignerLease;
+
     /// Abandons the current transaction.
     /// The current transaction will still be tracked, but will no longer be considered during fee estimation
     fn abandon(&mut self);
@@ -89,6 +101,32 @@ pub(crate) trait TransactionTracker: Send + Sync {
 
     /// Returns the address of the account being tracked
     fn address(&self) -> Address;
+
+    /// Returns the number of transactions in flight below the current nonce
+    fn num_in_flight_transactions(&self) -> usize;
+
+    /// Returns the block the transaction with the lowest in flight nonce was last sent at
+    fn oldest_in_flight_block(&self) -> Option<u64>;
+
+    /// Moves the current transaction in flight and starts tracking the next nonce.
+    ///
+    /// The in flight transaction is still checked for on each update.
+    fn advance_nonce(&mut self);
+
+    /// Moves back to the lowest in flight nonce so that its transaction can be replaced.
+    ///
+    /// Transactions sent with higher nonces are kept so that their replacements are priced
+    /// correctly. Returns the new current nonce, or `None` if no transactions are in flight.
+    fn rewind_nonce(&mut self) -> Option<u64>;
+
+    /// Returns the updates for in flight transactions collected since the last call
+    fn take_in_flight_updates(&mut self) -> Vec<TrackerUpdate>;
+
+    /// Checks if the latest transaction can no longer be included at `block_number`.
+    ///
+    /// Returns `LatestTxDropped` once the last block the sender allowed the transaction to
+    /// be included in has passed.
+    fn check_inclusion_deadline(&mut self, block_number: u64) -> Option<TrackerUpdate>;
 }
 
 /// Errors that can occur while using a `TransactionTracker`.
@@ -113,7 +151,7 @@ pub(crate) enum TransactionTrackerError {
 
 pub(crate) type TransactionTrackerResult<T> = std::result::Result<T, TransactionTrackerError>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub(crate) enum TrackerUpdate {
     Mined {
@@ -134,6 +172,16 @@ pub(crate) enum TrackerUpdate {
     },
 }
 
+impl TrackerUpdate {
+    pub(crate) fn nonce(&self) -> u64 {
+        match self {
+            TrackerUpdate::Mined { nonce, .. }
+            | TrackerUpdate::LatestTxDropped { nonce }
+            | TrackerUpdate::NonceUsedForOtherTx { nonce } => *nonce,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct TransactionTrackerImpl<P, T> {
     provider: P,
@@ -143,6 +191,10 @@ pub(crate) struct TransactionTrackerImpl<P, T> {
     nonce: u64,
     balance: U256,
     transactions: Vec<PendingTransaction>,
+    // Transactions sent with nonces other than the current one that may still mine.
+    // Lower nonces are in flight, higher nonces were sent before a rewind.
+    in_flight: BTreeMap<u64, Vec<PendingTransaction>>,
+    in_flight_updates: Vec<TrackerUpdate>,
     has_abandoned: bool,
     attempt_count: u64,
     metrics: TransactionTrackerMetrics,
@@ -151,6 +203,9 @@ pub(crate) struct TransactionTrackerImpl<P, T> {
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct Settings {
     pub(crate) replacement_fee_percent_increase: u32,
+    // Number of blocks after being sent that a transaction can be included in, if
+    // limited by the sender
+    pub(crate) max_inclusion_blocks: Option<u64>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -162,6 +217,8 @@ struct PendingTransaction {
     attempt_number: u64,
     sent_at_block: Option<u64>,
     sent_at_time: Option<Instant>,
+    // Last block the transaction can be included in, if limited by the sender
+    max_inclusion_block: Option<u64>,
 }
 
 impl<P, T> TransactionTrackerImpl<P, T>
@@ -184,6 +241,8 @@ where
             nonce: 0,
             balance: U256::ZERO,
             transactions: vec![],
+            in_flight: BTreeMap::new(),
+            in_flight_updates: vec![],
             has_abandoned: false,
             attempt_count: 0,
             metrics: TransactionTrackerMetrics::new_with_labels(&[("builder_tag", builder_tag)]),
@@ -196,12 +255,69 @@ where
 
     fn set_nonce_and_clear_state(&mut self, nonce: u64) {
         self.nonce = nonce;
-        self.transactions.clear();
-        self.attempt_count = 0;
+        // pick up any transactions sent with this nonce before a rewind
+        self.transactions = self.in_flight.remove(&nonce).unwrap_or_default();
+        self.attempt_count = self
+            .transactions
+            .last()
+            .map_or(0, |tx| tx.attempt_number + 1);
         self.has_abandoned = false;
         self.update_metrics();
     }
 
+    // Moves the current transactions in flight and makes `nonce` the current nonce
+    fn move_to_nonce(&mut self, nonce: u64) {
+        let transactions = std::mem::take(&mut self.transactions);
+        if transactions.iter().any(|tx| tx.tx_hash.is_some()) {
+            self.in_flight.insert(self.nonce, transactions);
+        }
+        self.set_nonce_and_clear_state(nonce);
+    }
+
+    // Finds which of the transactions sent with `nonce` mined, if any
+    async fn find_mined_transaction(
+        &self,
+        nonce: u64,
+        transactions: &[PendingTransaction],
+        update: &AddressUpdate,
+    ) -> TransactionTrackerResult<TrackerUpdate> {
+        let mut out = TrackerUpdate::NonceUsedForOtherTx { nonce };
+        for tx in transactions.iter().rev() {
+            let Some(pending_tx_hash) = tx.tx_hash else {
+                continue;
+            };
+
+            if update.mined_tx_hashes.contains(&pending_tx_hash) {
+                let Some(mined_tx_info) = self.get_mined_tx_info(pending_tx_hash).await? else {
+                    continue;
+                };
+                out = TrackerUpdate::Mined {
+                    tx_hash: pending_tx_hash,
+                    nonce,
+                    block_number: mined_tx_info.block_number,
+                    attempt_number: tx.attempt_number,
+                    gas_limit: mined_tx_info.gas_limit,
+                    gas_used: mined_tx_info.gas_used,
+                    gas_price: mined_tx_info.gas_price,
+                    is_success: mined_tx_info.is_success,
+                };
+
+                if let Some(sent_at_time) = tx.sent_at_time {
+                    let elapsed = Instant::now().duration_since(sent_at_time);
+                    self.metrics
+                        .txn_time_to_mine_ms
+                        .record(elapsed.as_millis() as f64);
+                }
+                if let Some(sent_at_block) = tx.sent_at_block {
+                    self.metrics
+                        .txn_blocks_to_mine
+                        .record((mined_tx_info.block_number.saturating_sub(sent_at_block)) as f64);
+                }
+            }
+        }
+        Ok(out)
+    }
+
     fn validate_transaction(&self, tx: &TransactionRequest) -> anyhow::Result<()> {
         let Some(nonce) = tx.nonce else {
             bail!("transaction given to tracker should have nonce set");
@@ -232,6 +348,9 @@ where
         self.metrics
             .num_pending_transactions
             .set(self.transactions.len() as f64);
+        self.metrics
+            .num_in_flight_transactions
+            .set(self.in_flight.range(..self.nonce).count() as f64);
         self.metrics.nonce.set(self.nonce as f64);
         self.metrics.attempt_count.set(self.attempt_count as f64);
 
@@ -323,6 +442,7 @@ where
     fn num_pending_transactions(&self) -> usize {
         self.transactions
             .iter()
+            .chain(self.in_flight.values().flatten())
             .filter(|t| t.tx_hash.is_some())
             .count()
     }
@@ -354,7 +474,7 @@ where
         let sent_at_time = Instant::now();
         let tx_hash = self
             .sender
-            .send_transaction(tx, expected_storage, &self.signer)
+            .send_transaction(tx, expected_storage, &self.signer, block_number)
             .await;
 
         self.update_metrics();
@@ -368,6 +488,10 @@ where
                     attempt_number: self.attempt_count,
                     sent_at_block: Some(block_number),
                     sent_at_time: Some(sent_at_time),
+                    max_inclusion_block: self
+                        .settings
+                        .max_inclusion_blocks
+                        .map(|blocks| block_number + blocks),
                 });
                 self.has_abandoned = false;
                 self.attempt_count += 1;
@@ -397,6 +521,7 @@ where
                         attempt_number: self.attempt_count,
                         sent_at_block: None,
                         sent_at_time: None,
+                        max_inclusion_block: None,
                     });
                 };
 
@@ -457,6 +582,7 @@ where
                     attempt_number: self.attempt_count,
                     sent_at_block: None,
                     sent_at_time: None,
+                    max_inclusion_block: None,
                 });
 
                 self.attempt_count += 1;
@@ -481,6 +607,7 @@ where
                         attempt_number: self.attempt_count,
                         sent_at_block: None,
                         sent_at_time: None,
+                        max_inclusion_block: None,
                     });
                 };
 
@@ -504,7 +631,25 @@ where
         let Some(update_nonce) = update.nonce else {
             return Ok(None);
         };
+
+        // Check the other transactions with nonces that have been used
+        let used_nonces: Vec<u64> = self
+            .in_flight
+            .range(..=update_nonce)
+            .map(|(nonce, _)| *nonce)
+            .filter(|nonce| *nonce != self.nonce)
+            .collect();
+        for nonce in used_nonces {
+            let transactions = self.in_flight.remove(&nonce).unwrap_or_default();
+            let out = self
+                .find_mined_transaction(nonce, &transactions, update)
+                .await?;
+            info!("Tracker update: nonce {nonce} of in flight transaction has been used: {out:?}");
+            self.in_flight_updates.push(out);
+        }
+
         if self.nonce > update_nonce {
+            self.update_metrics();
             return Ok(None);
         }
         let new_nonce = update_nonce + 1;
@@ -519,40 +664,9 @@ where
             update.mined_tx_hashes
         );
 
-        let mut out = TrackerUpdate::NonceUsedForOtherTx { nonce: self.nonce };
-        for tx in self.transactions.iter().rev() {
-            let Some(pending_tx_hash) = tx.tx_hash else {
-                continue;
-            };
-
-            if update.mined_tx_hashes.contains(&pending_tx_hash) {
-                let Some(mined_tx_info) = self.get_mined_tx_info(pending_tx_hash).await? else {
-                    continue;
-                };
-                out = TrackerUpdate::Mined {
-                    tx_hash: pending_tx_hash,
-                    nonce: self.nonce,
-                    block_number: mined_tx_info.block_number,
-                    attempt_number: tx.attempt_number,
-                    gas_limit: mined_tx_info.gas_limit,
-                    gas_used: mined_tx_info.gas_used,
-                    gas_price: mined_tx_info.gas_price,
-                    is_success: mined_tx_info.is_success,
-                };
-
-                if let Some(sent_at_time) = tx.sent_at_time {
-                    let elapsed = Instant::now().duration_since(sent_at_time);
-                    self.metrics
-                        .txn_time_to_mine_ms
-                        .record(elapsed.as_millis() as f64);
-                }
-                if let Some(sent_at_block) = tx.sent_at_block {
-                    self.metrics
-                        .txn_blocks_to_mine
-                        .record((mined_tx_info.block_number.saturating_sub(sent_at_block)) as f64);
-                }
-            }
-        }
+        let out = self
+            .find_mined_transaction(self.nonce, &self.transactions, update)
+            .await?;
         self.set_nonce_and_clear_state(new_nonce);
         return Ok(Some(out));
     }
@@ -563,15 +677,24 @@ where
         let (nonce, balance) =
             tokio::try_join!(nonce_fut, balance_fut).unwrap_or((self.nonce, self.balance));
 
+        self.in_flight.clear();
+        self.in_flight_updates.clear();
         self.set_nonce_and_clear_state(nonce);
         self.balance = balance;
 
         // reset metrics when tracker reset.
         self.metrics.num_pending_transactions.set(0);
+        self.metrics.num_in_flight_transactions.set(0);
         self.metrics.current_max_fee_per_gas.set(0);
         self.metrics.max_priority_fee_per_gas.set(0);
     }
 
+    async fn replace_signer(&mut self, signer: SignerLease) -> SignerLease {
+        let old = std::mem::replace(&mut self.signer, signer);
+        self.reset().await;
+        old
+    }
+
     fn abandon(&mut self) {
         self.has_abandoned = true;
         self.attempt_count = 0;
@@ -584,6 +707,49 @@ where
     fn unabandon(&mut self) {
         self.has_abandoned = false;
     }
+
+    fn num_in_flight_transactions(&self) -> usize {
+        self.in_flight.range(..self.nonce).count()
+    }
+
+    fn oldest_in_flight_block(&self) -> Option<u64> {
+        let (_, transactions) = self.in_flight.range(..self.nonce).next()?;
+        transactions.iter().rev().find_map(|tx| tx.sent_at_block)
+    }
+
+    fn advance_nonce(&mut self) {
+        self.move_to_nonce(self.nonce + 1);
+    }
+
+    fn rewind_nonce(&mut self) -> Option<u64> {
+        let (&nonce, _) = self.in_flight.range(..self.nonce).next()?;
+        info!(
+            "Rewinding tracker from nonce {:?} to in flight nonce {:?}",
+            self.nonce, nonce
+        );
+        self.move_to_nonce(nonce);
+        Some(nonce)
+    }
+
+    fn take_in_flight_updates(&mut self) -> Vec<TrackerUpdate> {
+        std::mem::take(&mut self.in_flight_updates)
+    }
+
+    fn check_inclusion_deadline(&mut self, block_number: u64) -> Option<TrackerUpdate> {
+        let tx = self.transactions.last_mut()?;
+        let max_inclusion_block = tx.max_inclusion_block?;
+        if tx.tx_hash.is_none() || block_number <= max_inclusion_block {
+            return None;
+        }
+
+        info!(
+            "Tracker update: transaction {:?} with nonce {:?} was not included by block {max_inclusion_block}",
+            tx.tx_hash, self.nonce
+        );
+        // only report the deadline once
+        tx.max_inclusion_block = None;
+        Some(TrackerUpdate::LatestTxDropped { nonce: self.nonce })
+    }
 }
 
 impl From<TxSenderError> for TransactionTrackerError {
@@ -610,6 +776,8 @@ impl From<TxSenderError> for TransactionTrackerError {
 struct TransactionTrackerMetrics {
     #[metric(describe = "the number of pending transactions.")]
     num_pending_transactions: Gauge,
+    #[metric(describe = "the number of transactions in flight below the current nonce.")]
+    num_in_flight_transactions: Gauge,
     #[metric(describe = "the current account‘s nonce.")]
     nonce: Gauge,
     #[metric(describe = "the number of pending transactions.")]
@@ -679,6 +847,7 @@ mod tests {
     ) -> TransactionTrackerImpl<MockEvmProvider, MockTransactionSender> {
         let settings = Settings {
             replacement_fee_percent_increase: 5,
+            max_inclusion_blocks: None,
         };
 
         let lease = SignerLease::new(Arc::new(signer), 1);
@@ -696,7 +865,7 @@ mod tests {
         let (mut sender, provider, signer) = create_base_config(0);
         sender
             .expect_send_transaction()
-            .returning(move |_a, _b, _c| Box::pin(async { Ok(B256::ZERO) }));
+            .returning(move |_a, _b, _c, _d| Box::pin(async { Ok(B256::ZERO) }));
 
         let mut tracker = create_tracker(sender, provider, signer).await;
 
@@ -728,7 +897,7 @@ mod tests {
         let (mut sender, provider, signer) = create_base_config(0);
         sender
             .expect_send_transaction()
-            .returning(move |_a, _b, _c| Box::pin(async { Ok(B256::ZERO) }));
+            .returning(move |_a, _b, _c, _d| Box::pin(async { Ok(B256::ZERO) }));
 
         let mut tracker = create_tracker(sender, provider, signer).await;
 
@@ -755,12 +924,46 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_replace_signer() {
+        let (mut sender, provider, signer) = create_base_config(0);
+        sender
+            .expect_send_transaction()
+            .returning(move |_a, _b, _c, _d| Box::pin(async { Ok(B256::ZERO) }));
+
+        let mut tracker = create_tracker(sender, provider, signer).await;
+
+        let tx = TransactionRequest::default()
+            .nonce(0)
+            .gas_limit(10000)
+            .max_fee_per_gas(10000);
+        let exp = ExpectedStorage::default();
+        let _sent = tracker.send_transaction(tx, &exp, 0).await;
+        assert_eq!(tracker.num_pending_transactions(), 1);
+
+        let old = tracker
+            .replace_signer(SignerLease::new(Arc::new(MockTxSigner {}), 1))
+            .await;
+        assert_eq!(old.address(), Address::ZERO);
+
+        // the transactions of the previous signer are forgotten
+        assert_eq!(tracker.num_pending_transactions(), 0);
+        assert_eq!(
+            TrackerState {
+                nonce: 0,
+                balance: U256::ZERO,
+                required_fees: None,
+            },
+            tracker.get_state().unwrap()
+        );
+    }
+
     #[tokio::test]
     async fn test_send_transaction_without_nonce() {
         let (mut sender, provider, signer) = create_base_config(2);
         sender
             .expect_send_transaction()
-            .returning(move |_a, _b, _c| Box::pin(async { Ok(B256::ZERO) }));
+            .returning(move |_a, _b, _c, _d| Box::pin(async { Ok(B256::ZERO) }));
 
         let mut tracker = create_tracker(sender, provider, signer).await;
 
@@ -777,7 +980,7 @@ mod tests {
 
         sender
             .expect_send_transaction()
-            .returning(move |_a, _b, _c| Box::pin(async { Ok(B256::ZERO) }));
+            .returning(move |_a, _b, _c, _d| Box::pin(async { Ok(B256::ZERO) }));
 
         let mut tracker = create_tracker(sender, provider, signer).await;
 
@@ -793,7 +996,7 @@ mod tests {
         let (mut sender, provider, signer) = create_base_config(0);
         sender
             .expect_send_transaction()
-            .returning(move |_a, _b, _c| Box::pin(async { Ok(B256::ZERO) }));
+            .returning(move |_a, _b, _c, _d| Box::pin(async { Ok(B256::ZERO) }));
 
         let mut tracker = create_tracker(sender, provider, signer).await;
 
@@ -827,7 +1030,7 @@ mod tests {
         let (mut sender, provider, signer) = create_base_config(0);
         sender
             .expect_send_transaction()
-            .returning(move |_a, _b, _c| Box::pin(async { Err(TxSenderError::Underpriced) }));
+            .returning(move |_a, _b, _c, _d| Box::pin(async { Err(TxSenderError::Underpriced) }));
 
         let mut tracker = create_tracker(sender, provider, signer).await;
 
@@ -856,7 +1059,7 @@ mod tests {
         let (mut sender, provider, signer) = create_base_config(0);
         sender
             .expect_send_transaction()
-            .returning(move |_a, _b, _c| {
+            .returning(move |_a, _b, _c, _d| {
                 Box::pin(async { Err(TxSenderError::ReplacementUnderpriced) })
             });
 
@@ -903,16 +1106,8 @@ mod tests {
             from: Address::default(),
         }
     }
-    #[tokio::test]
-    async fn test_process_update_mined() {
-        let (mut sender, mut provider, signer) = create_base_config(0);
-
-        let tx_hash = B256::random();
-
-        sender
-            .expect_send_transaction()
-            .returning(move |_a, _b, _c| Box::pin(async move { Ok(tx_hash) }));
 
+    fn expect_mined_transactions(provider: &mut MockEvmProvider) {
         provider
             .expect_get_transaction_by_hash()
             .returning(|hash: B256| Ok(Some(sign_transaction(hash))));
@@ -939,6 +1134,19 @@ mod tests {
                     authorization_list: None,
                 }))
             });
+    }
+
+    #[tokio::test]
+    async fn test_process_update_mined() {
+        let (mut sender, mut provider, signer) = create_base_config(0);
+
+        let tx_hash = B256::random();
+
+        sender
+            .expect_send_transaction()
+            .returning(move |_a, _b, _c, _d| Box::pin(async move { Ok(tx_hash) }));
+
+        expect_mined_transactions(&mut provider);
 
         let mut tracker = create_tracker(sender, provider, signer).await;
 
@@ -968,4 +1176,144 @@ mod tests {
             }
         );
     }
+
+    #[tokio::test]
+    async fn test_advance_nonce_in_flight_mined() {
+        let (mut sender, mut provider, signer) = create_base_config(0);
+
+        let first_hash = B256::random();
+        let second_hash = B256::random();
+        sender
+            .expect_send_transaction()
+            .times(1)
+            .returning(move |_a, _b, _c, _d| Box::pin(async move { Ok(first_hash) }));
+        sender
+            .expect_send_transaction()
+            .times(1)
+            .returning(move |_a, _b, _c, _d| Box::pin(async move { Ok(second_hash) }));
+        expect_mined_transactions(&mut provider);
+
+        let mut tracker = create_tracker(sender, provider, signer).await;
+        let exp = ExpectedStorage::default();
+
+        let tx = TransactionRequest::default()
+            .nonce(0)
+            .max_fee_per_gas(10000);
+        tracker.send_transaction(tx, &exp, 5).await.unwrap();
+        tracker.advance_nonce();
+
+        // the next transaction doesn't replace the in flight one
+        let state = tracker.get_state().unwrap();
+        assert_eq!(state.nonce, 1);
+        assert_eq!(state.required_fees, None);
+        assert_eq!(tracker.num_in_flight_transactions(), 1);
+        assert_eq!(tracker.oldest_in_flight_block(), Some(5));
+
+        let tx = TransactionRequest::default()
+            .nonce(1)
+            .max_fee_per_gas(10000);
+        tracker.send_transaction(tx, &exp, 6).await.unwrap();
+        assert_eq!(tracker.num_pending_transactions(), 2);
+
+        // only the in flight transaction mined
+        let update = AddressUpdate {
+            address: Address::ZERO,
+            nonce: Some(0),
+            mined_tx_hashes: vec![first_hash],
+            balance: U256::ZERO,
+        };
+        assert!(tracker.process_update(&update).await.unwrap().is_none());
+
+        let in_flight_updates = tracker.take_in_flight_updates();
+        assert_eq!(in_flight_updates.len(), 1);
+        assert!(matches!(
+            in_flight_updates[0],
+            TrackerUpdate::Mined { tx_hash, nonce: 0, .. } if tx_hash == first_hash
+        ));
+        assert!(tracker.take_in_flight_updates().is_empty());
+        assert_eq!(tracker.num_in_flight_transactions(), 0);
+        assert_eq!(tracker.num_pending_transactions(), 1);
+        assert_eq!(tracker.get_state().unwrap().nonce, 1);
+    }
+
+    #[tokio::test]
+    async fn test_inclusion_deadline() {
+        let (mut sender, provider, signer) = create_base_config(0);
+        sender
+            .expect_send_transaction()
+            .returning(move |_a, _b, _c, _d| Box::pin(async { Ok(B256::random()) }));
+
+        let settings = Settings {
+            replacement_fee_percent_increase: 5,
+            max_inclusion_blocks: Some(2),
+        };
+        let lease = SignerLease::new(Arc::new(signer), 1);
+        let mut tracker =
+            TransactionTrackerImpl::new(provider, sender, lease, settings, "test".to_string())
+                .await
+                .unwrap();
+
+        let tx = TransactionRequest::default().nonce(0);
+        let exp = ExpectedStorage::default();
+        tracker.send_transaction(tx, &exp, 10).await.unwrap();
+
+        assert!(tracker.check_inclusion_deadline(11).is_none());
+        assert!(tracker.check_inclusion_deadline(12).is_none());
+        assert!(matches!(
+            tracker.check_inclusion_deadline(13),
+            Some(TrackerUpdate::LatestTxDropped { nonce: 0 })
+        ));
+        // only reported once
+        assert!(tracker.check_inclusion_deadline(14).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rewind_nonce() {
+        let (mut sender, provider, signer) = create_base_config(0);
+        sender
+            .expect_send_transaction()
+            .returning(move |_a, _b, _c, _d| Box::pin(async { Ok(B256::random()) }));
+
+        let mut tracker = create_tracker(sender, provider, signer).await;
+        let exp = ExpectedStorage::default();
+
+        // nothing to rewind to
+        assert_eq!(tracker.rewind_nonce(), None);
+
+        let tx = TransactionRequest::default()
+            .nonce(0)
+            .max_fee_per_gas(10000);
+        tracker.send_transaction(tx, &exp, 0).await.unwrap();
+        tracker.advance_nonce();
+        let tx = TransactionRequest::default()
+            .nonce(1)
+            .max_fee_per_gas(20000);
+        tracker.send_transaction(tx, &exp, 1).await.unwrap();
+
+        // the replacement for the rewound nonce must be priced above the in flight transaction
+        assert_eq!(tracker.rewind_nonce(), Some(0));
+        let state = tracker.get_state().unwrap();
+        assert_eq!(state.nonce, 0);
+        assert_eq!(
+            state.required_fees,
+            Some(GasFees {
+                max_fee_per_gas: 10500,
+                max_priority_fee_per_gas: 0,
+            })
+        );
+        assert_eq!(tracker.num_in_flight_transactions(), 0);
+        assert_eq!(tracker.num_pending_transactions(), 2);
+
+        // and the transaction sent after it is replaced when advancing again
+        tracker.advance_nonce();
+        let state = tracker.get_state().unwrap();
+        assert_eq!(state.nonce, 1);
+        assert_eq!(
+            state.required_fees,
+            Some(GasFees {
+                max_fee_per_gas: 21000,
+                max_priority_fee_per_gas: 0,
+            })
+        );
+    }
 }
@@ -16,9 +16,11 @@ use std::{error, fs, io::ErrorKind, process::Command};
 use serde_json::Value;
 
 macro_rules! write_deployed_bytecode {
-    ($contract_name:ident) => {
+    ($version:literal, $contract_name:ident) => {
         let json_file = fs::File::open(concat!(
-            "contracts/out/v0_7/",
+            "contracts/out/",
+            $version,
+            "/",
             stringify!($contract_name),
             ".sol/",
             stringify!($contract_name),
@@ -34,7 +36,9 @@ macro_rules! write_deployed_bytecode {
             .unwrap();
         fs::write(
             concat!(
-                "contracts/out/v0_7/",
+                "contracts/out/",
+                $version,
+                "/",
                 stringify!($contract_name),
                 ".sol/",
                 stringify!($contract_name),
@@ -52,6 +56,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     println!("cargo:rerun-if-changed=contracts/foundry.toml");
     generate_v0_6_bindings()?;
     generate_v0_7_bindings()?;
+    generate_v0_8_bindings()?;
     generate_utils_bindings()?;
     Ok(())
 }
@@ -77,8 +82,27 @@ fn generate_v0_7_bindings() -> Result<(), Box<dyn error::Error>> {
         "generate ABIs",
     )?;
 
-    write_deployed_bytecode!(CallGasEstimationProxy);
-    write_deployed_bytecode!(EntryPointSimulations);
+    write_deployed_bytecode!("v0_7", CallGasEstimationProxy);
+    write_deployed_bytecode!("v0_7", EntryPointSimulations);
+
+    Ok(())
+}
+
+fn generate_v0_8_bindings() -> Result<(), Box<dyn error::Error>> {
+    // v0.8 requires a newer compiler and transient storage support
+    run_command(
+        forge_build("v0_8")
+            .arg("--use")
+            .arg("0.8.28")
+            .arg("--evm-version")
+            .arg("cancun")
+            .arg("--remappings")
+            .arg("@openzeppelin/=lib/openzeppelin-contracts-versions/v5_1"),
+        "https://getfoundry.sh/",
+        "generate ABIs",
+    )?;
+
+    write_deployed_bytecode!("v0_8", EntryPointSimulations);
 
     Ok(())
 }
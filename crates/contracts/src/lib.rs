@@ -15,3 +15,4 @@ pub mod multicall3;
 pub mod utils;
 pub mod v0_6;
 pub mod v0_7;
+pub mod v0_8;
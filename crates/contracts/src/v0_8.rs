@@ -0,0 +1,41 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+// Contracts from https://github.com/eth-infinitism/account-abstraction/tree/releases/v0.8/contracts
+
+use alloy_primitives::Bytes;
+
+// The v0.8 entry point keeps the v0.7 ABI for the packed user operation and for all of the
+// methods, events, and errors used here, so the v0.7 bindings are shared.
+pub use super::v0_7::{
+    AggregatorStakeInfo, CallGasEstimationProxy, DepositInfo, GetBalances, IAggregator,
+    IEntryPoint, IEntryPointSimulations, PackedUserOperation, ReturnInfo, StakeInfo,
+    UserOpsPerAggregator, ValidationResult, CALL_GAS_ESTIMATION_PROXY_V0_7_DEPLOYED_BYTECODE,
+};
+
+// EntryPointSimulations deployed bytecode
+const __ENTRY_POINT_SIMULATIONS_V0_8_DEPLOYED_BYTECODE_HEX: &[u8] = include_bytes!(
+    "../contracts/out/v0_8/EntryPointSimulations.sol/EntryPointSimulations_deployedBytecode.txt"
+);
+
+// Hex is prefixed with 0x
+static __ENTRY_POINT_SIMULATIONS_V0_8_DEPLOYED_BYTECODE: [u8;
+    (__ENTRY_POINT_SIMULATIONS_V0_8_DEPLOYED_BYTECODE_HEX.len() - 2) / 2] = {
+    match const_hex::const_decode_to_array(__ENTRY_POINT_SIMULATIONS_V0_8_DEPLOYED_BYTECODE_HEX) {
+        Ok(a) => a,
+        Err(_) => panic!("Failed to decode entry point simulations hex"),
+    }
+};
+
+pub static ENTRY_POINT_SIMULATIONS_V0_8_DEPLOYED_BYTECODE: Bytes =
+    Bytes::from_static(&__ENTRY_POINT_SIMULATIONS_V0_8_DEPLOYED_BYTECODE);
@@ -174,8 +174,9 @@ impl<P: EvmProvider> Chain<P> {
         if settings
             .entry_point_addresses
             .values()
-            .any(|v| *v == EntryPointVersion::V0_7)
+            .any(|v| *v == EntryPointVersion::V0_7 || *v == EntryPointVersion::V0_8)
         {
+            // v0.8 emits the same events as v0.7
             events.push(UserOperationEventV07::SIGNATURE_HASH);
             events.push(DepositedV07::SIGNATURE_HASH);
             events.push(WithdrawnV07::SIGNATURE_HASH);
@@ -659,7 +660,7 @@ impl<P: EvmProvider> Chain<P> {
                 Some(EntryPointVersion::V0_6) => {
                     Self::load_v0_6(log, &mut mined_ops, &mut entity_balance_updates)
                 }
-                Some(EntryPointVersion::V0_7) => {
+                Some(EntryPointVersion::V0_7 | EntryPointVersion::V0_8) => {
                     Self::load_v0_7(log, &mut mined_ops, &mut entity_balance_updates)
                 }
                 Some(EntryPointVersion::Unspecified) | None => {
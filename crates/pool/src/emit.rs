@@ -141,6 +141,11 @@ pub enum OpRemovalReason {
         valid_until: Timestamp,
     },
     PoolSizeExceeded,
+    /// Op was replaced by another op with the same sender and nonce
+    Replaced {
+        /// Hash of the replacing op
+        replaced_by: B256,
+    },
 }
 
 impl EntitySummary {
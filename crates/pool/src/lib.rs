@@ -22,13 +22,19 @@
 mod chain;
 
 mod emit;
-pub use emit::OpPoolEvent as PoolEvent;
+pub use emit::{OpPoolEvent as PoolEvent, OpRemovalReason};
 
 mod mempool;
-pub use mempool::PoolConfig;
+pub use mempool::{PoolConfig, RateLimit, RateLimitConfig};
+
+mod p2p;
+pub use p2p::Settings as P2pSettings;
 
 mod server;
 pub use server::{LocalPoolBuilder, LocalPoolHandle, RemotePoolClient};
 
+mod store;
+pub use store::Settings as PoolStoreSettings;
+
 mod task;
 pub use task::{Args as PoolTaskArgs, PoolTask};
@@ -22,6 +22,10 @@ mod size;
 mod paymaster;
 pub(crate) use paymaster::{PaymasterConfig, PaymasterTracker};
 
+mod rate_limiter;
+pub(crate) use rate_limiter::RateLimiter;
+pub use rate_limiter::{RateLimit, RateLimitConfig};
+
 mod uo_pool;
 use std::{
     collections::{HashMap, HashSet},
@@ -108,6 +112,9 @@ pub(crate) trait Mempool: Send + Sync {
     /// Dumps the mempool's paymaster balance cache
     fn dump_paymaster_balances(&self) -> Vec<PaymasterMetadata>;
 
+    /// Seeds the mempool's paymaster balance cache with previously saved confirmed balances
+    fn restore_paymaster_balances(&self, balances: &[PaymasterMetadata]);
+
     /// Dumps the mempool's reputation tracking
     fn get_reputation_status(&self, address: Address) -> ReputationStatus;
 
@@ -178,11 +185,12 @@ pub struct PoolConfig {
     pub max_expected_storage_slots: usize,
     /// Whether to enable UO with 7702 auth
     pub support_7702: bool,
+    /// Rate limits on operation admissions
+    pub rate_limits: RateLimitConfig,
 }
 
 /// Origin of an operation.
 #[derive(Debug, Clone, Copy)]
-#[allow(dead_code)] // TODO(danc): remove once implemented
 pub enum OperationOrigin {
     /// The operation was submitted via a local RPC call.
     Local,
@@ -191,6 +199,8 @@ pub enum OperationOrigin {
     /// The operation was returned to the pool when the block it was in was
     /// reorged away.
     ReturnedAfterReorg,
+    /// The operation was reloaded from the pool store on startup.
+    Restored,
 }
 
 #[cfg(test)]
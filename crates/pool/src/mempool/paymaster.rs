@@ -156,6 +156,15 @@ where
         self.state.read().dump_paymaster_metadata()
     }
 
+    pub(crate) fn restore_confirmed_balances(&self, balances: &[PaymasterMetadata]) {
+        let mut state = self.state.write();
+        for balance in balances {
+            if !state.paymaster_exists(balance.address) {
+                state.add_new_paymaster(balance.address, balance.confirmed_balance, U256::ZERO);
+            }
+        }
+    }
+
     pub(crate) fn set_tracking(&self, tracking_enabled: bool) {
         self.state.write().set_tracking(tracking_enabled);
     }
@@ -0,0 +1,324 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::{collections::HashMap, time::Instant};
+
+use metrics::{Counter, Gauge};
+use metrics_derive::Metrics;
+use parking_lot::Mutex;
+use rundler_types::{
+    pool::{MempoolError, RateLimitKey},
+    UserOperation, UserOperationPermissions, UserOperationVariant,
+};
+use rundler_utils::token_bucket::TokenBucket;
+
+use super::MempoolResult;
+
+/// Token bucket rate limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Number of operations admitted per second once the burst is used
+    pub per_second: f64,
+    /// Maximum number of operations admitted at once
+    pub burst: u32,
+}
+
+/// Rate limits applied to operation admissions to the mempool, by key.
+///
+/// Keys without a limit are not rate limited.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitConfig {
+    /// Limit per operation sender
+    pub sender: Option<RateLimit>,
+    /// Limit per operation paymaster
+    pub paymaster: Option<RateLimit>,
+    /// Limit per operation factory
+    pub factory: Option<RateLimit>,
+    /// Limit per caller identity, from the operation permissions
+    pub caller: Option<RateLimit>,
+}
+
+impl RateLimitConfig {
+    fn limit(&self, key: &RateLimitKey) -> Option<RateLimit> {
+        match key {
+            RateLimitKey::Sender(_) => self.sender,
+            RateLimitKey::Paymaster(_) => self.paymaster,
+            RateLimitKey::Factory(_) => self.factory,
+            RateLimitKey::Caller(_) => self.caller,
+        }
+    }
+}
+
+/// Rate limits operation admissions to the mempool with a token bucket per key
+pub(crate) struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<RateLimitKey, TokenBucket>>,
+    metrics: RateLimiterMetrics,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(config: RateLimitConfig, entry_point: String) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+            metrics: RateLimiterMetrics::new_with_labels(&[("entry_point", entry_point)]),
+        }
+    }
+
+    /// Admit an operation before validation, taking a token from the buckets of its sender
+    /// and caller.
+    ///
+    /// The paymaster and factory are named by the operation but not yet validated, so their
+    /// buckets are only checked here and charged by [`Self::charge_entities`] once the
+    /// operation is validated. Otherwise anyone could drain an entity's bucket with invalid
+    /// operations naming it.
+    ///
+    /// No tokens are taken if any of the buckets is empty.
+    pub(crate) fn check_operation(
+        &self,
+        op: &UserOperationVariant,
+        perms: &UserOperationPermissions,
+    ) -> MempoolResult<()> {
+        let charged = self.limited([
+            Some(RateLimitKey::Sender(op.sender())),
+            perms.caller_id.clone().map(RateLimitKey::Caller),
+        ]);
+        self.check_at(&charged, &self.entity_keys(op), Instant::now())
+    }
+
+    /// Charge the paymaster and factory of a validated operation, rejecting it if either
+    /// bucket is empty
+    pub(crate) fn charge_entities(&self, op: &UserOperationVariant) -> MempoolResult<()> {
+        self.check_at(&self.entity_keys(op), &[], Instant::now())
+    }
+
+    /// Removes the buckets that have refilled, as they are equivalent to a new bucket
+    pub(crate) fn prune(&self) {
+        self.prune_at(Instant::now());
+    }
+
+    fn entity_keys(&self, op: &UserOperationVariant) -> Vec<RateLimitKey> {
+        self.limited([
+            op.paymaster().map(RateLimitKey::Paymaster),
+            op.factory().map(RateLimitKey::Factory),
+        ])
+    }
+
+    fn limited<const N: usize>(&self, keys: [Option<RateLimitKey>; N]) -> Vec<RateLimitKey> {
+        keys.into_iter()
+            .flatten()
+            .filter(|key| self.config.limit(key).is_some())
+            .collect()
+    }
+
+    /// Takes a token from the buckets of `charged` keys if they and the buckets of `checked`
+    /// keys all have a token
+    fn check_at(
+        &self,
+        charged: &[RateLimitKey],
+        checked: &[RateLimitKey],
+        now: Instant,
+    ) -> MempoolResult<()> {
+        if charged.is_empty() && checked.is_empty() {
+            return Ok(());
+        }
+
+        let mut buckets = self.buckets.lock();
+        for key in charged.iter().chain(checked) {
+            let limit = self.config.limit(key).expect("keys should have limits");
+            let bucket = buckets
+                .entry(key.clone())
+                .or_insert_with(|| TokenBucket::new(limit.burst as f64, now));
+            bucket.refill(limit.per_second, limit.burst as f64, now);
+            if !bucket.has_token() {
+                self.metrics.increment_rate_limited(key);
+                return Err(MempoolError::RateLimited(key.clone()));
+            }
+        }
+
+        for key in charged {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.take();
+            }
+        }
+        self.metrics.tracked_keys.set(buckets.len() as f64);
+
+        Ok(())
+    }
+
+    fn prune_at(&self, now: Instant) {
+        let mut buckets = self.buckets.lock();
+        buckets.retain(|key, bucket| {
+            let Some(limit) = self.config.limit(key) else {
+                return false;
+            };
+            bucket.refill(limit.per_second, limit.burst as f64, now);
+            !bucket.is_full(limit.burst as f64)
+        });
+        self.metrics.tracked_keys.set(buckets.len() as f64);
+    }
+}
+
+#[derive(Metrics)]
+#[metrics(scope = "op_pool_rate_limiter")]
+struct RateLimiterMetrics {
+    #[metric(describe = "the count of ops rate limited by sender.")]
+    sender_rate_limited: Counter,
+    #[metric(describe = "the count of ops rate limited by paymaster.")]
+    paymaster_rate_limited: Counter,
+    #[metric(describe = "the count of ops rate limited by factory.")]
+    factory_rate_limited: Counter,
+    #[metric(describe = "the count of ops rate limited by caller.")]
+    caller_rate_limited: Counter,
+    #[metric(describe = "the number of keys with a partially used bucket.")]
+    tracked_keys: Gauge,
+}
+
+impl RateLimiterMetrics {
+    fn increment_rate_limited(&self, key: &RateLimitKey) {
+        match key {
+            RateLimitKey::Sender(_) => self.sender_rate_limited.increment(1),
+            RateLimitKey::Paymaster(_) => self.paymaster_rate_limited.increment(1),
+            RateLimitKey::Factory(_) => self.factory_rate_limited.increment(1),
+            RateLimitKey::Caller(_) => self.caller_rate_limited.increment(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use alloy_primitives::Address;
+
+    use super::*;
+
+    fn limiter(config: RateLimitConfig) -> RateLimiter {
+        RateLimiter::new(config, "test".to_string())
+    }
+
+    const LIMIT: RateLimit = RateLimit {
+        per_second: 1.0,
+        burst: 2,
+    };
+
+    #[test]
+    fn test_burst_then_refill() {
+        let limiter = limiter(RateLimitConfig {
+            sender: Some(LIMIT),
+            ..Default::default()
+        });
+        let keys = [RateLimitKey::Sender(Address::random())];
+        let now = Instant::now();
+
+        limiter.check_at(&keys, &[], now).unwrap();
+        limiter.check_at(&keys, &[], now).unwrap();
+        let err = limiter.check_at(&keys, &[], now).unwrap_err();
+        assert!(matches!(
+            err,
+            MempoolError::RateLimited(RateLimitKey::Sender(_))
+        ));
+
+        limiter
+            .check_at(&keys, &[], now + Duration::from_secs(1))
+            .unwrap();
+        assert!(limiter
+            .check_at(&keys, &[], now + Duration::from_secs(1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_no_tokens_taken_when_limited() {
+        let limiter = limiter(RateLimitConfig {
+            sender: Some(LIMIT),
+            caller: Some(RateLimit {
+                per_second: 1.0,
+                burst: 1,
+            }),
+            ..Default::default()
+        });
+        let caller = RateLimitKey::Caller("key".to_string());
+        let sender = RateLimitKey::Sender(Address::random());
+        let now = Instant::now();
+
+        limiter.check_at(&[caller.clone()], &[], now).unwrap();
+        let err = limiter
+            .check_at(&[sender.clone(), caller.clone()], &[], now)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            MempoolError::RateLimited(RateLimitKey::Caller(_))
+        ));
+
+        // the sender's bucket is untouched by the rejected admission
+        limiter.check_at(&[sender.clone()], &[], now).unwrap();
+        limiter.check_at(&[sender], &[], now).unwrap();
+    }
+
+    #[test]
+    fn test_checked_keys_not_charged() {
+        let limiter = limiter(RateLimitConfig {
+            paymaster: Some(LIMIT),
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let paymaster = [RateLimitKey::Paymaster(Address::random())];
+
+        // checking before validation takes no tokens
+        for _ in 0..10 {
+            limiter.check_at(&[], &paymaster, now).unwrap();
+        }
+
+        limiter.check_at(&paymaster, &[], now).unwrap();
+        limiter.check_at(&paymaster, &[], now).unwrap();
+        assert!(matches!(
+            limiter.check_at(&[], &paymaster, now),
+            Err(MempoolError::RateLimited(RateLimitKey::Paymaster(_)))
+        ));
+    }
+
+    #[test]
+    fn test_keys_are_independent() {
+        let limiter = limiter(RateLimitConfig {
+            paymaster: Some(LIMIT),
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let paymaster = [RateLimitKey::Paymaster(Address::random())];
+
+        limiter.check_at(&paymaster, &[], now).unwrap();
+        limiter.check_at(&paymaster, &[], now).unwrap();
+        assert!(limiter.check_at(&paymaster, &[], now).is_err());
+        limiter
+            .check_at(&[RateLimitKey::Paymaster(Address::random())], &[], now)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_prune() {
+        let limiter = limiter(RateLimitConfig {
+            factory: Some(LIMIT),
+            ..Default::default()
+        });
+        let now = Instant::now();
+        limiter
+            .check_at(&[RateLimitKey::Factory(Address::random())], &[], now)
+            .unwrap();
+
+        limiter.prune_at(now);
+        assert_eq!(limiter.buckets.lock().len(), 1);
+
+        limiter.prune_at(now + Duration::from_secs(1));
+        assert!(limiter.buckets.lock().is_empty());
+    }
+}
@@ -29,17 +29,17 @@ use rundler_types::{
     pool::{
         MempoolError, PaymasterMetadata, PoolOperation, Reputation, ReputationStatus, StakeStatus,
     },
-    Entity, EntityUpdate, EntityUpdateType, EntryPointVersion, GasFees, UserOperation,
+    v0_8, Entity, EntityUpdate, EntityUpdateType, EntryPointVersion, GasFees, UserOperation,
     UserOperationId, UserOperationPermissions, UserOperationVariant,
 };
-use rundler_utils::{emit::WithEntryPoint, guard_timer::CustomTimerGuard};
+use rundler_utils::{authorization_utils, emit::WithEntryPoint, guard_timer::CustomTimerGuard};
 use tokio::sync::broadcast;
 use tonic::async_trait;
 use tracing::{info, instrument};
 
 use super::{
     paymaster::PaymasterTracker, pool::PoolInner, reputation::AddressReputation, Mempool,
-    MempoolResult, OperationOrigin, PoolConfig,
+    MempoolResult, OperationOrigin, PoolConfig, RateLimiter,
 };
 use crate::{
     chain::ChainUpdate,
@@ -58,6 +58,7 @@ pub(crate) struct UoPool<UP: UoPoolProvidersT, EP: ProvidersWithEntryPointT> {
     state: RwLock<UoPoolState<EP::DAGasOracleSync>>,
     paymaster: PaymasterTracker<EP::EntryPoint>,
     reputation: Arc<AddressReputation>,
+    rate_limiter: RateLimiter,
     event_sender: broadcast::Sender<WithEntryPoint<OpPoolEvent>>,
     ep_specific_metrics: UoPoolMetricsEPSpecific,
     metrics: UoPoolMetrics,
@@ -106,6 +107,7 @@ where
             reputation,
             paymaster,
             event_sender,
+            rate_limiter: RateLimiter::new(config.rate_limits.clone(), ep.clone()),
             config,
             ep_specific_metrics: UoPoolMetricsEPSpecific::new_with_labels(&[("entry_point", ep)]),
             metrics: UoPoolMetrics::default(),
@@ -181,6 +183,7 @@ where
                 // whether or not the UO is using a post op. Can cause the efficiency check to fail.
                 UserOperationVariant::V0_6(op) => op.call_gas_limit(),
                 UserOperationVariant::V0_7(op) => op.execution_gas_limit(),
+                UserOperationVariant::V0_8(op) => op.execution_gas_limit(),
             };
             if execution_gas_limit == 0 {
                 return Ok(()); // No call gas limit, not useful, but not a failure here.
@@ -230,6 +233,38 @@ where
 
         Ok(())
     }
+
+    // The v0.8 entry point hashes an EIP-7702 marker `initCode` with the sender's delegate.
+    // Without an authorization tuple the delegate is the one currently set in the sender's
+    // code. If the sender isn't delegated the operation is left as is and fails validation.
+    async fn resolve_eip7702_delegate(
+        &self,
+        op: UserOperationVariant,
+    ) -> MempoolResult<UserOperationVariant> {
+        let UserOperationVariant::V0_8(uo) = op else {
+            return Ok(op);
+        };
+        if !uo.requires_eip7702_delegate() {
+            return Ok(uo.into());
+        }
+
+        let code = self
+            .ep_providers
+            .evm()
+            .get_code(uo.sender(), None)
+            .await
+            .map_err(anyhow::Error::from)?;
+        let Some(delegate) = authorization_utils::eip7702_delegate(&code) else {
+            return Ok(uo.into());
+        };
+
+        Ok(
+            v0_8::UserOperationBuilder::from_uo(uo, &self.config.chain_spec)
+                .eip7702_delegate(delegate)
+                .build()
+                .into(),
+        )
+    }
 }
 
 #[async_trait]
@@ -361,6 +396,9 @@ where
             .unmined_operations
             .increment(unmined_op_count);
 
+        // Drop rate limit buckets that have refilled
+        self.rate_limiter.prune();
+
         // update required bundle fees and update metrics
         match self
             .ep_providers
@@ -488,6 +526,8 @@ where
         mut op: UserOperationVariant,
         perms: UserOperationPermissions,
     ) -> MempoolResult<B256> {
+        op = self.resolve_eip7702_delegate(op).await?;
+
         // Initial state checks
         let to_replace = {
             let state = self.state.read();
@@ -539,6 +579,13 @@ where
             );
         }
 
+        // Rate limit new admissions before any expensive validation. Operations returning to
+        // the pool after a reorg or restored from the store are not new admissions.
+        let new_admission = matches!(origin, OperationOrigin::Local | OperationOrigin::External);
+        if new_admission {
+            self.rate_limiter.check_operation(&op, &perms)?;
+        }
+
         // NOTE: We get the latest block from the provider here to avoid a race condition
         // where the pool is still processing the previous block, but the user may have been
         // notified of a new block.
@@ -634,6 +681,11 @@ where
             ));
         }
 
+        // The paymaster and factory validated the operation, charge their rate limits
+        if new_admission {
+            self.rate_limiter.charge_entities(&op)?;
+        }
+
         let filter_id = self.mempool_config.match_filter(&op);
         let valid_time_range = sim_result.valid_time_range;
         let pool_op = PoolOperation {
@@ -712,7 +764,7 @@ where
         self.paymaster.add_or_update_balance(&pool_op).await?;
 
         // Update reputation, handling replacement if needed
-        if let Some(to_replace) = to_replace {
+        if let Some(to_replace) = &to_replace {
             to_replace.entities().unique().for_each(|e| {
                 self.reputation.dec_seen(e.address);
             });
@@ -737,6 +789,14 @@ where
             valid_until: pool_op.valid_time_range.valid_until,
             entities: entity_summary,
         });
+        if let Some(to_replace) = to_replace {
+            self.emit(OpPoolEvent::RemovedOp {
+                op_hash: to_replace.uo.hash(),
+                reason: OpRemovalReason::Replaced {
+                    replaced_by: op_hash,
+                },
+            });
+        }
 
         Ok(hash)
     }
@@ -881,6 +941,10 @@ where
         self.paymaster.dump_paymaster_metadata()
     }
 
+    fn restore_paymaster_balances(&self, balances: &[PaymasterMetadata]) {
+        self.paymaster.restore_confirmed_balances(balances);
+    }
+
     fn get_reputation_status(&self, address: Address) -> ReputationStatus {
         self.reputation.status(address)
     }
@@ -999,7 +1063,7 @@ mod tests {
         authorization::Eip7702Auth,
         chain::{ChainSpec, ContractRegistry},
         da::DAGasData,
-        pool::{PrecheckViolation, SimulationViolation},
+        pool::{PrecheckViolation, RateLimitKey, SimulationViolation},
         v0_6::{UserOperationBuilder, UserOperationRequiredFields},
         EntityInfo, EntityInfos, EntityType, EntryPointVersion,
         UserOperation as UserOperationTrait, ValidTimeRange,
@@ -1008,7 +1072,7 @@ mod tests {
     use super::*;
     use crate::{
         chain::{BalanceUpdate, MinedOp},
-        mempool::{PaymasterConfig, ReputationParams},
+        mempool::{PaymasterConfig, RateLimit, RateLimitConfig, ReputationParams},
     };
     const THROTTLE_SLACK: u64 = 5;
     const BAN_SLACK: u64 = 10;
@@ -2267,6 +2331,136 @@ mod tests {
         assert!(matches!(err, MempoolError::MaxOperationsReached(2, _)));
     }
 
+    #[tokio::test]
+    async fn test_rate_limited_caller() {
+        let mut config = default_config();
+        config.rate_limits = RateLimitConfig {
+            caller: Some(RateLimit {
+                per_second: 0.0,
+                burst: 1,
+            }),
+            ..Default::default()
+        };
+        let perms = UserOperationPermissions {
+            caller_id: Some("key".to_string()),
+            ..Default::default()
+        };
+
+        let op1 = create_op(Address::random(), 0, 100, None);
+        let op2 = create_op(Address::random(), 0, 100, None);
+        let op3 = create_op(Address::random(), 0, 100, None);
+
+        let pool = create_pool_with_config(config, vec![op1.clone(), op2.clone(), op3.clone()]);
+        pool.add_operation(OperationOrigin::Local, op1.op, perms.clone())
+            .await
+            .unwrap();
+        let err = pool
+            .add_operation(OperationOrigin::Local, op2.op, perms)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            MempoolError::RateLimited(RateLimitKey::Caller(_))
+        ));
+
+        // other callers are not limited
+        pool.add_operation(OperationOrigin::Local, op3.op, default_perms())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_sender_not_applied_to_restored() {
+        let mut config = default_config();
+        config.rate_limits = RateLimitConfig {
+            sender: Some(RateLimit {
+                per_second: 0.0,
+                burst: 1,
+            }),
+            ..Default::default()
+        };
+        let sender = Address::random();
+        let op1 = create_op(sender, 0, 100, None);
+        let op2 = create_op(sender, 1, 100, None);
+        let op3 = create_op(sender, 2, 100, None);
+
+        let pool = create_pool_with_config(config, vec![op1.clone(), op2.clone(), op3.clone()]);
+        pool.add_operation(OperationOrigin::Local, op1.op, default_perms())
+            .await
+            .unwrap();
+        pool.add_operation(OperationOrigin::Restored, op2.op, default_perms())
+            .await
+            .unwrap();
+        let err = pool
+            .add_operation(OperationOrigin::External, op3.op, default_perms())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            MempoolError::RateLimited(RateLimitKey::Sender(s)) if s == sender
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_paymaster_not_charged_for_invalid_ops() {
+        let mut config = default_config();
+        config.rate_limits = RateLimitConfig {
+            paymaster: Some(RateLimit {
+                per_second: 0.0,
+                burst: 1,
+            }),
+            ..Default::default()
+        };
+        let paymaster = Address::random();
+        let mut invalid = create_op(Address::random(), 0, 100, Some(paymaster));
+        invalid.simulation_error = Some(SimulationViolation::DidNotRevert);
+        // the mock simulator matches the valid operations by their trusted permission
+        let mut op1 = create_op(Address::random(), 0, 100, Some(paymaster));
+        op1.trusted = true;
+        let mut op2 = create_op(Address::random(), 0, 100, Some(paymaster));
+        op2.trusted = true;
+        let trusted = UserOperationPermissions {
+            trusted: true,
+            ..default_perms()
+        };
+
+        let mut entrypoint = MockEntryPointV0_6::new();
+        entrypoint
+            .expect_balance_of()
+            .returning(|_, _| Ok(U256::from(1000)));
+        let pool = create_pool_with_entry_point_config(
+            config,
+            vec![invalid.clone(), op1.clone(), op2.clone()],
+            entrypoint,
+            MempoolConfig::default(),
+        );
+
+        // invalid operations naming the paymaster don't use up its bucket
+        for _ in 0..3 {
+            let err = pool
+                .add_operation(
+                    OperationOrigin::External,
+                    invalid.op.clone(),
+                    default_perms(),
+                )
+                .await
+                .unwrap_err();
+            assert!(matches!(err, MempoolError::SimulationViolation(_)));
+        }
+
+        pool.add_operation(OperationOrigin::External, op1.op, trusted.clone())
+            .await
+            .unwrap();
+        let err = pool
+            .add_operation(OperationOrigin::External, op2.op, trusted)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            MempoolError::RateLimited(RateLimitKey::Paymaster(p)) if p == paymaster
+        ));
+    }
+
     #[derive(Clone, Debug)]
     struct OpWithErrors {
         op: UserOperationVariant,
@@ -2302,6 +2496,7 @@ mod tests {
             max_time_in_pool: None,
             max_expected_storage_slots: usize::MAX,
             support_7702: false,
+            rate_limits: RateLimitConfig::default(),
         }
     }
 
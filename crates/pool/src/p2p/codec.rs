@@ -0,0 +1,638 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+//! SSZ + snappy encoding of the gossiped `UserOperationsWithEntryPoint` message.
+//!
+//! ```text
+//! UserOperationsWithEntryPoint(Container):
+//!     entry_point_contract: Address
+//!     verified_at_block_hash: Bytes32
+//!     chain_id: uint256
+//!     user_operations: List[UserOp, MAX_OPS_PER_MESSAGE]
+//! ```
+//!
+//! `UserOp` is the v0.6 `UserOperation` container for v0.6 entry points and the
+//! `PackedUserOperation` container for v0.7 and v0.8 entry points. Integers are
+//! encoded as little-endian `uint256` and dynamic byte strings as `List[byte, N]`.
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use rundler_contracts::{
+    v0_6::UserOperation as ContractUserOperationV0_6, v0_7::PackedUserOperation,
+};
+use rundler_types::{chain::ChainSpec, v0_6, v0_7, v0_8, EntryPointVersion, UserOperationVariant};
+use sha2::{Digest, Sha256};
+
+/// Maximum size of an uncompressed gossip message
+pub(crate) const MAX_MESSAGE_SIZE: usize = 1 << 20;
+/// Maximum number of user operations in a single gossip message
+pub(crate) const MAX_OPS_PER_MESSAGE: usize = 256;
+
+const OFFSET_LEN: usize = 4;
+
+/// Message ID domain of messages that decompress with snappy
+const MESSAGE_DOMAIN_VALID_SNAPPY: [u8; 4] = [0x01, 0x00, 0x00, 0x00];
+/// Message ID domain of messages that don't decompress with snappy
+const MESSAGE_DOMAIN_INVALID_SNAPPY: [u8; 4] = [0x00, 0x00, 0x00, 0x00];
+/// Length of a gossip message ID
+const MESSAGE_ID_LEN: usize = 20;
+
+/// Computes the gossipsub message ID of a message's data
+///
+/// `SHA256(MESSAGE_DOMAIN_VALID_SNAPPY + snappy_decompress(data))[:20]`, or
+/// `SHA256(MESSAGE_DOMAIN_INVALID_SNAPPY + data)[:20]` if the data can't be decompressed.
+pub(crate) fn message_id(data: &[u8]) -> Vec<u8> {
+    let decompressed = snap::raw::decompress_len(data)
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_SIZE)
+        .and_then(|_| snap::raw::Decoder::new().decompress_vec(data).ok());
+
+    let mut hasher = Sha256::new();
+    match decompressed {
+        Some(decompressed) => {
+            hasher.update(MESSAGE_DOMAIN_VALID_SNAPPY);
+            hasher.update(decompressed);
+        }
+        None => {
+            hasher.update(MESSAGE_DOMAIN_INVALID_SNAPPY);
+            hasher.update(data);
+        }
+    }
+    hasher.finalize()[..MESSAGE_ID_LEN].to_vec()
+}
+
+/// Errors that can occur while decoding a gossip message
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum DecodeError {
+    /// Snappy decompression failed
+    #[error("snappy decompression failed: {0}")]
+    Snappy(#[from] snap::Error),
+    /// The message exceeded the maximum size
+    #[error("message too large: {0} bytes")]
+    TooLarge(usize),
+    /// The SSZ encoding is malformed
+    #[error("invalid ssz encoding: {0}")]
+    InvalidSsz(&'static str),
+    /// A user operation field is out of range or malformed
+    #[error("invalid user operation: {0}")]
+    InvalidUserOperation(String),
+    /// The entry point version cannot be gossiped
+    #[error("unsupported entry point version {0:?}")]
+    UnsupportedVersion(EntryPointVersion),
+}
+
+/// A batch of user operations for a single entry point, as gossiped between peers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UserOperationsWithEntryPoint {
+    /// Entry point the operations target
+    pub(crate) entry_point: Address,
+    /// Hash of the block the sender validated the operations against
+    pub(crate) verified_at_block_hash: B256,
+    /// Chain id the operations are valid on
+    pub(crate) chain_id: u64,
+    /// The operations
+    pub(crate) user_operations: Vec<UserOperationVariant>,
+}
+
+impl UserOperationsWithEntryPoint {
+    /// Encodes the message with SSZ and compresses it with snappy
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let ops = self
+            .user_operations
+            .iter()
+            .map(encode_user_operation)
+            .collect::<Vec<_>>();
+
+        let ssz = encode_container(vec![
+            Field::Fixed(self.entry_point.to_vec()),
+            Field::Fixed(self.verified_at_block_hash.to_vec()),
+            Field::Fixed(encode_uint(U256::from(self.chain_id))),
+            Field::Variable(encode_list(ops)),
+        ]);
+
+        snap::raw::Encoder::new()
+            .compress_vec(&ssz)
+            .expect("snappy compression should not fail")
+    }
+
+    /// Decompresses and decodes a message whose operations target an entry point of `version`
+    pub(crate) fn decode(
+        data: &[u8],
+        version: EntryPointVersion,
+        chain_spec: &ChainSpec,
+    ) -> Result<Self, DecodeError> {
+        let len = snap::raw::decompress_len(data)?;
+        if len > MAX_MESSAGE_SIZE {
+            return Err(DecodeError::TooLarge(len));
+        }
+        let ssz = snap::raw::Decoder::new().decompress_vec(data)?;
+
+        let fields = decode_container(
+            &ssz,
+            &[
+                FieldKind::Fixed(20),
+                FieldKind::Fixed(32),
+                FieldKind::Fixed(32),
+                FieldKind::Variable,
+            ],
+        )?;
+
+        let chain_id = decode_uint(fields[2])?
+            .try_into()
+            .map_err(|_| DecodeError::InvalidSsz("chain id out of range"))?;
+
+        let user_operations = decode_list(fields[3])?
+            .into_iter()
+            .map(|op| decode_user_operation(op, version, chain_spec))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            entry_point: Address::from_slice(fields[0]),
+            verified_at_block_hash: B256::from_slice(fields[1]),
+            chain_id,
+            user_operations,
+        })
+    }
+}
+
+fn encode_user_operation(op: &UserOperationVariant) -> Vec<u8> {
+    match op {
+        UserOperationVariant::V0_6(op) => {
+            let op: ContractUserOperationV0_6 = op.clone().into();
+            encode_container(vec![
+                Field::Fixed(op.sender.to_vec()),
+                Field::Fixed(encode_uint(op.nonce)),
+                Field::Variable(op.initCode.to_vec()),
+                Field::Variable(op.callData.to_vec()),
+                Field::Fixed(encode_uint(op.callGasLimit)),
+                Field::Fixed(encode_uint(op.verificationGasLimit)),
+                Field::Fixed(encode_uint(op.preVerificationGas)),
+                Field::Fixed(encode_uint(op.maxFeePerGas)),
+                Field::Fixed(encode_uint(op.maxPriorityFeePerGas)),
+                Field::Variable(op.paymasterAndData.to_vec()),
+                Field::Variable(op.signature.to_vec()),
+            ])
+        }
+        UserOperationVariant::V0_7(op) => encode_packed(op.packed()),
+        UserOperationVariant::V0_8(op) => encode_packed(op.packed()),
+    }
+}
+
+fn encode_packed(op: &PackedUserOperation) -> Vec<u8> {
+    encode_container(vec![
+        Field::Fixed(op.sender.to_vec()),
+        Field::Fixed(encode_uint(op.nonce)),
+        Field::Variable(op.initCode.to_vec()),
+        Field::Variable(op.callData.to_vec()),
+        Field::Fixed(op.accountGasLimits.to_vec()),
+        Field::Fixed(encode_uint(op.preVerificationGas)),
+        Field::Fixed(op.gasFees.to_vec()),
+        Field::Variable(op.paymasterAndData.to_vec()),
+        Field::Variable(op.signature.to_vec()),
+    ])
+}
+
+fn decode_user_operation(
+    data: &[u8],
+    version: EntryPointVersion,
+    chain_spec: &ChainSpec,
+) -> Result<UserOperationVariant, DecodeError> {
+    match version {
+        EntryPointVersion::V0_6 => {
+            let f = decode_container(
+                data,
+                &[
+                    FieldKind::Fixed(20),
+                    FieldKind::Fixed(32),
+                    FieldKind::Variable,
+                    FieldKind::Variable,
+                    FieldKind::Fixed(32),
+                    FieldKind::Fixed(32),
+                    FieldKind::Fixed(32),
+                    FieldKind::Fixed(32),
+                    FieldKind::Fixed(32),
+                    FieldKind::Variable,
+                    FieldKind::Variable,
+                ],
+            )?;
+            let contract_uo = ContractUserOperationV0_6 {
+                sender: Address::from_slice(f[0]),
+                nonce: decode_uint(f[1])?,
+                initCode: Bytes::copy_from_slice(f[2]),
+                callData: Bytes::copy_from_slice(f[3]),
+                callGasLimit: decode_uint(f[4])?,
+                verificationGasLimit: decode_uint(f[5])?,
+                preVerificationGas: decode_uint(f[6])?,
+                maxFeePerGas: decode_uint(f[7])?,
+                maxPriorityFeePerGas: decode_uint(f[8])?,
+                paymasterAndData: Bytes::copy_from_slice(f[9]),
+                signature: Bytes::copy_from_slice(f[10]),
+            };
+            let uo = v0_6::UserOperationBuilder::from_contract(chain_spec, contract_uo)
+                .map_err(|e| DecodeError::InvalidUserOperation(e.to_string()))?
+                .build();
+            Ok(uo.into())
+        }
+        EntryPointVersion::V0_7 => {
+            let puo = decode_packed(data)?;
+            validate_packed(&puo, false)?;
+            let uo = v0_7::UserOperationBuilder::from_packed(puo, chain_spec)
+                .map_err(|e| DecodeError::InvalidUserOperation(e.to_string()))?
+                .build();
+            Ok(uo.into())
+        }
+        EntryPointVersion::V0_8 => {
+            let puo = decode_packed(data)?;
+            validate_packed(&puo, true)?;
+            let uo = v0_8::UserOperationBuilder::from_packed(puo, chain_spec)
+                .map_err(|e| DecodeError::InvalidUserOperation(e.to_string()))?
+                .build();
+            Ok(uo.into())
+        }
+        EntryPointVersion::Unspecified => Err(DecodeError::UnsupportedVersion(version)),
+    }
+}
+
+fn decode_packed(data: &[u8]) -> Result<PackedUserOperation, DecodeError> {
+    let f = decode_container(
+        data,
+        &[
+            FieldKind::Fixed(20),
+            FieldKind::Fixed(32),
+            FieldKind::Variable,
+            FieldKind::Variable,
+            FieldKind::Fixed(32),
+            FieldKind::Fixed(32),
+            FieldKind::Fixed(32),
+            FieldKind::Variable,
+            FieldKind::Variable,
+        ],
+    )?;
+    Ok(PackedUserOperation {
+        sender: Address::from_slice(f[0]),
+        nonce: decode_uint(f[1])?,
+        initCode: Bytes::copy_from_slice(f[2]),
+        callData: Bytes::copy_from_slice(f[3]),
+        accountGasLimits: B256::from_slice(f[4]),
+        preVerificationGas: decode_uint(f[5])?,
+        gasFees: B256::from_slice(f[6]),
+        paymasterAndData: Bytes::copy_from_slice(f[7]),
+        signature: Bytes::copy_from_slice(f[8]),
+    })
+}
+
+// The packed builders slice into these fields, reject lengths they can't represent
+fn validate_packed(
+    puo: &PackedUserOperation,
+    allow_eip7702_marker: bool,
+) -> Result<(), DecodeError> {
+    if !puo.initCode.is_empty()
+        && puo.initCode.len() < 20
+        && !(allow_eip7702_marker && v0_8::is_eip7702_init_code(&puo.initCode))
+    {
+        return Err(DecodeError::InvalidUserOperation(
+            "init code shorter than an address".to_string(),
+        ));
+    }
+    if !puo.paymasterAndData.is_empty() && puo.paymasterAndData.len() < 52 {
+        return Err(DecodeError::InvalidUserOperation(
+            "paymaster and data shorter than 52 bytes".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+enum Field {
+    Fixed(Vec<u8>),
+    Variable(Vec<u8>),
+}
+
+enum FieldKind {
+    Fixed(usize),
+    Variable,
+}
+
+fn encode_uint(value: U256) -> Vec<u8> {
+    value.to_le_bytes::<32>().to_vec()
+}
+
+fn decode_uint(data: &[u8]) -> Result<U256, DecodeError> {
+    U256::try_from_le_slice(data).ok_or(DecodeError::InvalidSsz("invalid uint256"))
+}
+
+fn encode_offset(out: &mut Vec<u8>, offset: usize) {
+    out.extend_from_slice(&(offset as u32).to_le_bytes());
+}
+
+fn decode_offset(data: &[u8], at: usize) -> Result<usize, DecodeError> {
+    let bytes = data
+        .get(at..at + OFFSET_LEN)
+        .ok_or(DecodeError::InvalidSsz("truncated offset"))?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+}
+
+fn encode_container(fields: Vec<Field>) -> Vec<u8> {
+    let fixed_len: usize = fields
+        .iter()
+        .map(|f| match f {
+            Field::Fixed(b) => b.len(),
+            Field::Variable(_) => OFFSET_LEN,
+        })
+        .sum();
+
+    let mut out = Vec::with_capacity(fixed_len);
+    let mut variable = vec![];
+    for field in fields {
+        match field {
+            Field::Fixed(b) => out.extend_from_slice(&b),
+            Field::Variable(b) => {
+                encode_offset(&mut out, fixed_len + variable.len());
+                variable.extend_from_slice(&b);
+            }
+        }
+    }
+    out.extend_from_slice(&variable);
+    out
+}
+
+fn decode_container<'a>(data: &'a [u8], kinds: &[FieldKind]) -> Result<Vec<&'a [u8]>, DecodeError> {
+    let fixed_len: usize = kinds
+        .iter()
+        .map(|k| match k {
+            FieldKind::Fixed(len) => *len,
+            FieldKind::Variable => OFFSET_LEN,
+        })
+        .sum();
+    if data.len() < fixed_len {
+        return Err(DecodeError::InvalidSsz("container too short"));
+    }
+
+    // First pass, slice fixed fields and collect variable offsets
+    let mut pos = 0;
+    let mut fields = Vec::with_capacity(kinds.len());
+    let mut offsets = vec![];
+    for kind in kinds {
+        match kind {
+            FieldKind::Fixed(len) => {
+                fields.push(Some(&data[pos..pos + len]));
+                pos += len;
+            }
+            FieldKind::Variable => {
+                offsets.push((fields.len(), decode_offset(data, pos)?));
+                fields.push(None);
+                pos += OFFSET_LEN;
+            }
+        }
+    }
+
+    // Second pass, variable fields span from their offset to the next offset
+    if let Some((_, first)) = offsets.first() {
+        if *first != fixed_len {
+            return Err(DecodeError::InvalidSsz("invalid first offset"));
+        }
+    } else if data.len() != fixed_len {
+        return Err(DecodeError::InvalidSsz("trailing bytes"));
+    }
+    for (i, (idx, start)) in offsets.iter().enumerate() {
+        let end = offsets.get(i + 1).map_or(data.len(), |(_, o)| *o);
+        if start > &end || end > data.len() {
+            return Err(DecodeError::InvalidSsz("offsets out of order"));
+        }
+        fields[*idx] = Some(&data[*start..end]);
+    }
+
+    Ok(fields.into_iter().map(|f| f.unwrap()).collect())
+}
+
+fn encode_list(elements: Vec<Vec<u8>>) -> Vec<u8> {
+    let mut out = vec![];
+    let mut offset = elements.len() * OFFSET_LEN;
+    for e in &elements {
+        encode_offset(&mut out, offset);
+        offset += e.len();
+    }
+    for e in elements {
+        out.extend_from_slice(&e);
+    }
+    out
+}
+
+fn decode_list(data: &[u8]) -> Result<Vec<&[u8]>, DecodeError> {
+    if data.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let first = decode_offset(data, 0)?;
+    if first == 0 || first % OFFSET_LEN != 0 || first > data.len() {
+        return Err(DecodeError::InvalidSsz("invalid first list offset"));
+    }
+    let count = first / OFFSET_LEN;
+    if count > MAX_OPS_PER_MESSAGE {
+        return Err(DecodeError::InvalidSsz("too many list elements"));
+    }
+
+    let mut offsets = Vec::with_capacity(count);
+    for i in 0..count {
+        offsets.push(decode_offset(data, i * OFFSET_LEN)?);
+    }
+
+    let mut elements = Vec::with_capacity(count);
+    for (i, start) in offsets.iter().enumerate() {
+        let end = offsets.get(i + 1).copied().unwrap_or(data.len());
+        if *start > end || end > data.len() {
+            return Err(DecodeError::InvalidSsz("list offsets out of order"));
+        }
+        elements.push(&data[*start..end]);
+    }
+
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::bytes;
+    use rundler_types::{v0_6::UserOperationRequiredFields, UserOperation};
+
+    use super::*;
+
+    fn v0_6_op(cs: &ChainSpec, nonce: u64) -> UserOperationVariant {
+        v0_6::UserOperationBuilder::new(
+            cs,
+            UserOperationRequiredFields {
+                sender: Address::random(),
+                nonce: U256::from(nonce),
+                init_code: bytes!("1234"),
+                call_data: bytes!("deadbeef"),
+                call_gas_limit: 100_000,
+                verification_gas_limit: 200_000,
+                pre_verification_gas: 50_000,
+                max_fee_per_gas: 10,
+                max_priority_fee_per_gas: 1,
+                paymaster_and_data: Bytes::new(),
+                signature: bytes!("abcdef"),
+            },
+        )
+        .build()
+        .into()
+    }
+
+    fn v0_7_op(cs: &ChainSpec) -> v0_7::UserOperation {
+        v0_7::UserOperationBuilder::new(
+            cs,
+            v0_7::UserOperationRequiredFields {
+                sender: Address::random(),
+                nonce: U256::from(7),
+                call_data: bytes!("deadbeef"),
+                call_gas_limit: 100_000,
+                verification_gas_limit: 200_000,
+                pre_verification_gas: 50_000,
+                max_fee_per_gas: 10,
+                max_priority_fee_per_gas: 1,
+                signature: bytes!("abcdef"),
+            },
+        )
+        .factory(Address::random(), bytes!("0102"))
+        .paymaster(Address::random(), 30_000, 40_000, bytes!("05"))
+        .build()
+    }
+
+    #[test]
+    fn test_roundtrip_v0_6() {
+        let cs = ChainSpec::default();
+        let msg = UserOperationsWithEntryPoint {
+            entry_point: cs.entry_point_address_v0_6,
+            verified_at_block_hash: B256::random(),
+            chain_id: cs.id,
+            user_operations: vec![v0_6_op(&cs, 1), v0_6_op(&cs, 2)],
+        };
+
+        let decoded =
+            UserOperationsWithEntryPoint::decode(&msg.encode(), EntryPointVersion::V0_6, &cs)
+                .unwrap();
+        assert_eq!(decoded, msg);
+    }
+
+    #[test]
+    fn test_roundtrip_v0_7() {
+        let cs = ChainSpec::default();
+        let msg = UserOperationsWithEntryPoint {
+            entry_point: cs.entry_point_address_v0_7,
+            verified_at_block_hash: B256::random(),
+            chain_id: cs.id,
+            user_operations: vec![v0_7_op(&cs).into()],
+        };
+
+        let decoded =
+            UserOperationsWithEntryPoint::decode(&msg.encode(), EntryPointVersion::V0_7, &cs)
+                .unwrap();
+        assert_eq!(
+            decoded.user_operations[0].hash(),
+            msg.user_operations[0].hash()
+        );
+        assert_eq!(decoded, msg);
+    }
+
+    #[test]
+    fn test_roundtrip_empty() {
+        let cs = ChainSpec::default();
+        let msg = UserOperationsWithEntryPoint {
+            entry_point: cs.entry_point_address_v0_7,
+            verified_at_block_hash: B256::ZERO,
+            chain_id: cs.id,
+            user_operations: vec![],
+        };
+
+        let decoded =
+            UserOperationsWithEntryPoint::decode(&msg.encode(), EntryPointVersion::V0_7, &cs)
+                .unwrap();
+        assert_eq!(decoded, msg);
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        let cs = ChainSpec::default();
+        let msg = UserOperationsWithEntryPoint {
+            entry_point: cs.entry_point_address_v0_6,
+            verified_at_block_hash: B256::random(),
+            chain_id: cs.id,
+            user_operations: vec![v0_6_op(&cs, 1)],
+        };
+        let ssz = snap::raw::Decoder::new()
+            .decompress_vec(&msg.encode())
+            .unwrap();
+
+        for len in [0, 50, ssz.len() - 1] {
+            let truncated = snap::raw::Encoder::new().compress_vec(&ssz[..len]).unwrap();
+            assert!(
+                UserOperationsWithEntryPoint::decode(&truncated, EntryPointVersion::V0_6, &cs)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_short_paymaster() {
+        let cs = ChainSpec::default();
+        let mut puo = v0_7_op(&cs).pack();
+        puo.paymasterAndData = bytes!("1234");
+        let data = encode_list(vec![encode_packed(&puo)]);
+
+        let op = decode_list(&data).unwrap()[0];
+        assert!(matches!(
+            decode_user_operation(op, EntryPointVersion::V0_7, &cs),
+            Err(DecodeError::InvalidUserOperation(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_short_init_code_v0_8() {
+        let cs = ChainSpec::default();
+        let mut puo = v0_7_op(&cs).pack();
+
+        // a short EIP-7702 marker is accepted
+        puo.initCode = bytes!("7702");
+        let data = encode_list(vec![encode_packed(&puo)]);
+        let op = decode_list(&data).unwrap()[0];
+        assert!(decode_user_operation(op, EntryPointVersion::V0_8, &cs).is_ok());
+
+        // any other short init code is rejected
+        for init_code in [bytes!("12"), bytes!("1234"), bytes!("770100")] {
+            puo.initCode = init_code;
+            let data = encode_list(vec![encode_packed(&puo)]);
+            let op = decode_list(&data).unwrap()[0];
+            assert!(matches!(
+                decode_user_operation(op, EntryPointVersion::V0_8, &cs),
+                Err(DecodeError::InvalidUserOperation(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_message_id() {
+        let ssz = b"user operations".to_vec();
+        let data = snap::raw::Encoder::new().compress_vec(&ssz).unwrap();
+
+        let mut preimage = MESSAGE_DOMAIN_VALID_SNAPPY.to_vec();
+        preimage.extend_from_slice(&ssz);
+        let id = message_id(&data);
+        assert_eq!(id, Sha256::digest(&preimage)[..20].to_vec());
+
+        // Data that isn't valid snappy is hashed as is with the invalid domain
+        let invalid = vec![0xff; 8];
+        let mut preimage = MESSAGE_DOMAIN_INVALID_SNAPPY.to_vec();
+        preimage.extend_from_slice(&invalid);
+        assert_eq!(
+            message_id(&invalid),
+            Sha256::digest(&preimage)[..20].to_vec()
+        );
+    }
+}
@@ -0,0 +1,548 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+//! ERC-4337 shared mempool gossip over libp2p.
+//!
+//! Each mempool configured for an entry point (canonical or alternative) is mapped to
+//! a gossipsub topic keyed by its mempool ID. Operations received from peers are added
+//! to the local pool with an `External` origin and are only forwarded by gossipsub once
+//! the pool has accepted them. Operations submitted locally are published to every topic
+//! of their entry point.
+
+mod codec;
+
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
+
+use alloy_primitives::{Address, B256};
+use anyhow::Context;
+use futures::StreamExt;
+use libp2p::{
+    gossipsub::{
+        self, IdentTopic, MessageAcceptance, MessageAuthenticity, MessageId, PeerScoreParams,
+        PeerScoreThresholds, PublishError, TopicHash, TopicScoreParams, ValidationMode,
+    },
+    identify, identity,
+    multiaddr::Protocol,
+    noise,
+    swarm::{NetworkBehaviour, SwarmEvent},
+    tcp, yamux, Multiaddr, PeerId, Swarm, SwarmBuilder,
+};
+use metrics::{Counter, Gauge};
+use metrics_derive::Metrics;
+use rundler_task::{GracefulShutdown, TaskSpawner};
+use rundler_types::{
+    chain::ChainSpec,
+    pool::{MempoolError, Pool, PoolError},
+    EntryPointVersion, UserOperation,
+};
+use rundler_utils::emit::WithEntryPoint;
+use tokio::sync::{broadcast, mpsc, Semaphore};
+use tracing::{debug, info, warn};
+
+use self::codec::{UserOperationsWithEntryPoint, MAX_MESSAGE_SIZE};
+use crate::{
+    emit::OpPoolEvent,
+    mempool::{OperationOrigin, PoolConfig},
+    server::LocalPoolHandle,
+};
+
+const PROTOCOL_VERSION: &str = "/rundler/erc4337/1.0.0";
+const IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(60);
+/// Maximum number of gossip messages validated concurrently, messages received while
+/// at the limit are ignored
+const MAX_CONCURRENT_VALIDATIONS: usize = 32;
+/// Peer score weight of an invalid message on a mempool topic
+const INVALID_MESSAGE_DELIVERIES_WEIGHT: f64 = -10.0;
+/// Time for the invalid message penalty of a peer to decay to zero
+const INVALID_MESSAGE_DELIVERIES_DECAY: Duration = Duration::from_secs(60 * 60);
+
+/// Settings for the p2p network
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// Address to listen for peer connections on
+    pub listen_address: SocketAddr,
+    /// Multiaddrs of peers to dial on startup
+    pub bootnodes: Vec<String>,
+    /// Secp256k1 private key of the node's identity, randomly generated if not set
+    pub private_key: Option<B256>,
+}
+
+/// Returns the gossipsub topic for a mempool ID
+fn mempool_topic(mempool_id: B256) -> IdentTopic {
+    IdentTopic::new(format!(
+        "/account_abstraction/{mempool_id:x}/user_ops_with_entry_point/ssz_snappy"
+    ))
+}
+
+/// Peer score parameters of a mempool topic
+///
+/// Only invalid messages are penalized. Mempool topics carry too little traffic to
+/// penalize peers for mesh delivery deficits.
+fn mempool_topic_score_params() -> TopicScoreParams {
+    TopicScoreParams {
+        mesh_message_deliveries_weight: 0.0,
+        mesh_failure_penalty_weight: 0.0,
+        invalid_message_deliveries_weight: INVALID_MESSAGE_DELIVERIES_WEIGHT,
+        invalid_message_deliveries_decay: gossipsub::score_parameter_decay(
+            INVALID_MESSAGE_DELIVERIES_DECAY,
+        ),
+        ..Default::default()
+    }
+}
+
+#[derive(NetworkBehaviour)]
+struct Behaviour {
+    gossipsub: gossipsub::Behaviour,
+    identify: identify::Behaviour,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MempoolTopic {
+    entry_point: Address,
+    version: EntryPointVersion,
+}
+
+struct ValidationResult {
+    message_id: MessageId,
+    source: PeerId,
+    acceptance: MessageAcceptance,
+}
+
+/// Gossips user operations between the local pool and its peers
+pub(crate) struct P2pNetwork {
+    chain_spec: ChainSpec,
+    swarm: Swarm<Behaviour>,
+    pool: LocalPoolHandle,
+    pool_events: broadcast::Receiver<WithEntryPoint<OpPoolEvent>>,
+    topics: HashMap<TopicHash, MempoolTopic>,
+    entry_point_topics: HashMap<Address, Vec<IdentTopic>>,
+    validation_sender: mpsc::UnboundedSender<ValidationResult>,
+    validation_receiver: mpsc::UnboundedReceiver<ValidationResult>,
+    validation_permits: Arc<Semaphore>,
+    task_spawner: Box<dyn TaskSpawner>,
+    metrics: P2pMetrics,
+}
+
+impl P2pNetwork {
+    /// Creates the network, subscribing to the topic of every configured mempool
+    pub(crate) fn new(
+        settings: Settings,
+        chain_spec: ChainSpec,
+        pool_configs: &[PoolConfig],
+        pool: LocalPoolHandle,
+        pool_events: broadcast::Receiver<WithEntryPoint<OpPoolEvent>>,
+        task_spawner: Box<dyn TaskSpawner>,
+    ) -> anyhow::Result<Self> {
+        let keypair = match settings.private_key {
+            Some(key) => {
+                let secret = identity::secp256k1::SecretKey::try_from_bytes(key.0)
+                    .context("invalid p2p private key")?;
+                identity::Keypair::from(identity::secp256k1::Keypair::from(secret))
+            }
+            None => identity::Keypair::generate_secp256k1(),
+        };
+
+        let mut swarm = SwarmBuilder::with_existing_identity(keypair)
+            .with_tokio()
+            .with_tcp(
+                tcp::Config::default(),
+                noise::Config::new,
+                yamux::Config::default,
+            )
+            .context("should have built tcp transport")?
+            .with_behaviour(|key| {
+                // Messages are unsigned and identified by their content, per the ERC-4337
+                // p2p spec
+                let config = gossipsub::ConfigBuilder::default()
+                    .max_transmit_size(MAX_MESSAGE_SIZE)
+                    .validation_mode(ValidationMode::Anonymous)
+                    .validate_messages()
+                    .message_id_fn(|m| MessageId::from(codec::message_id(&m.data)))
+                    .build()?;
+                let mut gossipsub =
+                    gossipsub::Behaviour::new(MessageAuthenticity::Anonymous, config)?;
+                gossipsub
+                    .with_peer_score(PeerScoreParams::default(), PeerScoreThresholds::default())?;
+                let identify = identify::Behaviour::new(identify::Config::new(
+                    PROTOCOL_VERSION.to_string(),
+                    key.public(),
+                ));
+                Ok(Behaviour {
+                    gossipsub,
+                    identify,
+                })
+            })
+            .map_err(|e| anyhow::anyhow!("should have built p2p behaviour: {e}"))?
+            .with_swarm_config(|c| c.with_idle_connection_timeout(IDLE_CONNECTION_TIMEOUT))
+            .build();
+
+        let mut topics = HashMap::new();
+        let mut entry_point_topics: HashMap<Address, Vec<IdentTopic>> = HashMap::new();
+        for pool_config in pool_configs {
+            if pool_config.mempool_channel_configs.is_empty() {
+                warn!(
+                    "No mempool configured for entry point {:?}, its operations will not be gossiped",
+                    pool_config.entry_point
+                );
+            }
+
+            for (id, mempool) in &pool_config.mempool_channel_configs {
+                if mempool.entry_point() != pool_config.entry_point {
+                    continue;
+                }
+
+                let topic = mempool_topic(*id);
+                let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+                gossipsub
+                    .subscribe(&topic)
+                    .context("should have subscribed to mempool topic")?;
+                gossipsub
+                    .set_topic_params(topic.clone(), mempool_topic_score_params())
+                    .map_err(|e| anyhow::anyhow!("should have set topic score params: {e}"))?;
+                info!("Subscribed to mempool topic {topic}");
+
+                topics.insert(
+                    topic.hash(),
+                    MempoolTopic {
+                        entry_point: pool_config.entry_point,
+                        version: pool_config.entry_point_version,
+                    },
+                );
+                entry_point_topics
+                    .entry(pool_config.entry_point)
+                    .or_default()
+                    .push(topic);
+            }
+        }
+
+        let listen_address = Multiaddr::from(settings.listen_address.ip())
+            .with(Protocol::Tcp(settings.listen_address.port()));
+        swarm
+            .listen_on(listen_address)
+            .context("should have listened on p2p address")?;
+
+        for bootnode in &settings.bootnodes {
+            let addr: Multiaddr = bootnode
+                .parse()
+                .with_context(|| format!("invalid bootnode multiaddr {bootnode}"))?;
+            if let Err(e) = swarm.dial(addr) {
+                warn!("Failed to dial bootnode {bootnode}: {e}");
+            }
+        }
+
+        info!("Started p2p network with peer id {}", swarm.local_peer_id());
+
+        let (validation_sender, validation_receiver) = mpsc::unbounded_channel();
+
+        Ok(Self {
+            chain_spec,
+            swarm,
+            pool,
+            pool_events,
+            topics,
+            entry_point_topics,
+            validation_sender,
+            validation_receiver,
+            validation_permits: Arc::new(Semaphore::new(MAX_CONCURRENT_VALIDATIONS)),
+            task_spawner,
+            metrics: P2pMetrics::default(),
+        })
+    }
+
+    /// Runs the network until shutdown
+    pub(crate) async fn run(mut self, shutdown: GracefulShutdown) {
+        loop {
+            tokio::select! {
+                _ = shutdown.clone() => {
+                    info!("Shutting down p2p network");
+                    break;
+                }
+                event = self.swarm.select_next_some() => {
+                    self.on_swarm_event(event);
+                }
+                event = self.pool_events.recv() => {
+                    match event {
+                        Ok(event) => self.on_pool_event(event).await,
+                        Err(broadcast::error::RecvError::Lagged(c)) => {
+                            warn!("p2p network lagged {c} pool events");
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("pool event channel closed, stopping p2p network");
+                            break;
+                        }
+                    }
+                }
+                Some(result) = self.validation_receiver.recv() => {
+                    // Accepted messages are forwarded to the rest of the mesh by gossipsub
+                    self.report(result.message_id, result.source, result.acceptance);
+                }
+            }
+        }
+    }
+
+    fn on_swarm_event(&mut self, event: SwarmEvent<BehaviourEvent>) {
+        match event {
+            SwarmEvent::NewListenAddr { address, .. } => {
+                info!("p2p network listening on {address}");
+            }
+            SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                debug!("Connected to peer {peer_id}");
+                self.metrics
+                    .connected_peers
+                    .set(self.swarm.connected_peers().count() as f64);
+            }
+            SwarmEvent::ConnectionClosed { peer_id, .. } => {
+                debug!("Disconnected from peer {peer_id}");
+                self.metrics
+                    .connected_peers
+                    .set(self.swarm.connected_peers().count() as f64);
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(gossipsub::Event::Message {
+                propagation_source,
+                message_id,
+                message,
+            })) => {
+                self.on_gossip_message(propagation_source, message_id, message);
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::Identify(identify::Event::Received {
+                peer_id,
+                info,
+                ..
+            })) => {
+                debug!(
+                    "Identified peer {peer_id} running {} {}",
+                    info.protocol_version, info.agent_version
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn on_gossip_message(
+        &mut self,
+        source: PeerId,
+        message_id: MessageId,
+        message: gossipsub::Message,
+    ) {
+        self.metrics.messages_received.increment(1);
+
+        let Some(topic) = self.topics.get(&message.topic).copied() else {
+            self.report(message_id, source, MessageAcceptance::Ignore);
+            return;
+        };
+
+        let decoded = match UserOperationsWithEntryPoint::decode(
+            &message.data,
+            topic.version,
+            &self.chain_spec,
+        ) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                debug!("Rejecting malformed gossip message from {source}: {e}");
+                self.metrics.messages_rejected.increment(1);
+                self.report(message_id, source, MessageAcceptance::Reject);
+                return;
+            }
+        };
+
+        if decoded.entry_point != topic.entry_point || decoded.chain_id != self.chain_spec.id {
+            debug!(
+                "Rejecting gossip message from {source} for entry point {:?} on chain {}",
+                decoded.entry_point, decoded.chain_id
+            );
+            self.metrics.messages_rejected.increment(1);
+            self.report(message_id, source, MessageAcceptance::Reject);
+            return;
+        }
+
+        // Simulating operations is expensive, bound the number of messages validated at
+        // once so that peers can't flood the node.
+        let Ok(permit) = self.validation_permits.clone().try_acquire_owned() else {
+            debug!("Ignoring gossip message from {source}, too many messages in validation");
+            self.metrics.messages_dropped.increment(1);
+            self.report(message_id, source, MessageAcceptance::Ignore);
+            return;
+        };
+
+        let pool = self.pool.clone();
+        let validation_sender = self.validation_sender.clone();
+        let ops_added = self.metrics.ops_added.clone();
+        let ops_rejected = self.metrics.ops_rejected.clone();
+        self.task_spawner.spawn(Box::pin(async move {
+            let mut accepted = false;
+            let mut invalid = false;
+            for op in decoded.user_operations {
+                match pool.add_external_op(op).await {
+                    Ok(hash) => {
+                        debug!("Added op {hash:?} from peer {source}");
+                        ops_added.increment(1);
+                        accepted = true;
+                    }
+                    Err(PoolError::MempoolError(
+                        MempoolError::OperationAlreadyKnown
+                        | MempoolError::ReplacementUnderpriced(_, _),
+                    )) => {}
+                    Err(e) if is_invalid_op_error(&e) => {
+                        debug!("Rejecting invalid op from peer {source}: {e:?}");
+                        ops_rejected.increment(1);
+                        invalid = true;
+                    }
+                    Err(e) => {
+                        debug!("Failed to add op from peer {source}: {e:?}");
+                    }
+                }
+            }
+            drop(permit);
+
+            // Rejecting the message penalizes the peer's score
+            let acceptance = if invalid {
+                MessageAcceptance::Reject
+            } else if accepted {
+                MessageAcceptance::Accept
+            } else {
+                MessageAcceptance::Ignore
+            };
+            let _ = validation_sender.send(ValidationResult {
+                message_id,
+                source,
+                acceptance,
+            });
+        }));
+    }
+
+    async fn on_pool_event(&mut self, event: WithEntryPoint<OpPoolEvent>) {
+        // Only locally submitted ops are published, ops from peers are forwarded by gossipsub
+        // after validation and reorged ops are returned to every peer's pool.
+        let OpPoolEvent::ReceivedOp {
+            op_hash,
+            op,
+            origin: OperationOrigin::Local,
+            ..
+        } = event.event
+        else {
+            return;
+        };
+        let Some(topics) = self.entry_point_topics.get(&event.entry_point) else {
+            return;
+        };
+        if op.authorization_tuple().is_some() {
+            debug!("Not gossiping op {op_hash:?} with an EIP-7702 authorization");
+            return;
+        }
+
+        let verified_at_block_hash = match self.pool.get_op_by_hash(op_hash).await {
+            Ok(Some(pool_op)) => pool_op.sim_block_hash,
+            // The op has already left the pool
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to get op {op_hash:?} for gossip: {e:?}");
+                return;
+            }
+        };
+
+        let data = UserOperationsWithEntryPoint {
+            entry_point: event.entry_point,
+            verified_at_block_hash,
+            chain_id: self.chain_spec.id,
+            user_operations: vec![op],
+        }
+        .encode();
+
+        for topic in topics {
+            match self
+                .swarm
+                .behaviour_mut()
+                .gossipsub
+                .publish(topic.clone(), data.clone())
+            {
+                Ok(_) => self.metrics.ops_published.increment(1),
+                Err(PublishError::InsufficientPeers | PublishError::Duplicate) => {}
+                Err(e) => warn!("Failed to publish op {op_hash:?} to {topic}: {e}"),
+            }
+        }
+    }
+
+    fn report(&mut self, message_id: MessageId, source: PeerId, acceptance: MessageAcceptance) {
+        let _ = self
+            .swarm
+            .behaviour_mut()
+            .gossipsub
+            .report_message_validation_result(&message_id, &source, acceptance);
+    }
+}
+
+/// True if an operation was rejected for failing prechecks or simulation
+///
+/// Other errors, i.e. throttling, rate limits or provider errors, aren't caused by the
+/// peer and don't penalize it.
+fn is_invalid_op_error(error: &PoolError) -> bool {
+    matches!(
+        error,
+        PoolError::MempoolError(
+            MempoolError::PrecheckViolation(_) | MempoolError::SimulationViolation(_)
+        )
+    )
+}
+
+#[derive(Metrics)]
+#[metrics(scope = "op_pool_p2p")]
+struct P2pMetrics {
+    #[metric(describe = "the number of connected peers.")]
+    connected_peers: Gauge,
+    #[metric(describe = "the count of gossip messages received.")]
+    messages_received: Counter,
+    #[metric(describe = "the count of malformed gossip messages rejected.")]
+    messages_rejected: Counter,
+    #[metric(describe = "the count of gossip messages ignored while at the validation limit.")]
+    messages_dropped: Counter,
+    #[metric(describe = "the count of ops added to the pool from peers.")]
+    ops_added: Counter,
+    #[metric(describe = "the count of invalid ops from peers.")]
+    ops_rejected: Counter,
+    #[metric(describe = "the count of ops published to peers.")]
+    ops_published: Counter,
+}
+
+#[cfg(test)]
+mod tests {
+    use rundler_types::{pool::PrecheckViolation, Entity};
+
+    use super::*;
+
+    #[test]
+    fn test_invalid_op_error() {
+        assert!(is_invalid_op_error(&PoolError::MempoolError(
+            MempoolError::PrecheckViolation(PrecheckViolation::SenderIsNotContractAndNoInitCode(
+                Address::ZERO
+            ))
+        )));
+        assert!(!is_invalid_op_error(&PoolError::MempoolError(
+            MempoolError::EntityThrottled(Entity::account(Address::ZERO))
+        )));
+        assert!(!is_invalid_op_error(&PoolError::MempoolError(
+            MempoolError::Other(anyhow::anyhow!("provider error"))
+        )));
+    }
+
+    #[test]
+    fn test_mempool_topic() {
+        let id = B256::repeat_byte(0xab);
+        assert_eq!(
+            mempool_topic(id).to_string(),
+            format!(
+                "/account_abstraction/{}/user_ops_with_entry_point/ssz_snappy",
+                "ab".repeat(32)
+            )
+        );
+    }
+}
@@ -11,7 +11,7 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc, time::Duration};
 
 use alloy_primitives::{Address, B256};
 use async_stream::stream;
@@ -25,7 +25,8 @@ use rundler_task::{
 use rundler_types::{
     pool::{
         MempoolError, NewHead, PaymasterMetadata, Pool, PoolError, PoolOperation,
-        PoolOperationSummary, PoolResult, Reputation, ReputationStatus, StakeStatus,
+        PoolOperationSummary, PoolResult, Reputation, ReputationStatus, SenderAssignments,
+        StakeStatus,
     },
     EntityUpdate, EntryPointVersion, UserOperation, UserOperationId, UserOperationPermissions,
     UserOperationVariant,
@@ -71,6 +72,7 @@ impl LocalPoolBuilder {
         task_spawner: Box<dyn TaskSpawner>,
         mempools: HashMap<Address, Arc<dyn Mempool>>,
         chain_subscriber: ChainSubscriber,
+        sender_lease_duration: Duration,
         shutdown: GracefulShutdown,
     ) -> BoxFuture<'static, ()> {
         let runner = LocalPoolServerRunner::new(
@@ -79,6 +81,7 @@ impl LocalPoolBuilder {
             mempools,
             chain_subscriber,
             task_spawner,
+            sender_lease_duration,
         );
         Box::pin(runner.run(shutdown))
     }
@@ -98,6 +101,7 @@ struct LocalPoolServerRunner {
     mempools: HashMap<Address, Arc<dyn Mempool>>,
     chain_subscriber: ChainSubscriber,
     task_spawner: Box<dyn TaskSpawner>,
+    sender_assignments: SenderAssignments,
 }
 
 impl LocalPoolHandle {
@@ -118,6 +122,21 @@ impl LocalPoolHandle {
             PoolError::UnexpectedResponse
         })?
     }
+
+    /// Adds an operation received from a p2p peer to the pool
+    pub(crate) async fn add_external_op(&self, op: UserOperationVariant) -> PoolResult<B256> {
+        let req = ServerRequestKind::AddOp {
+            entry_point: op.entry_point(),
+            op,
+            perms: UserOperationPermissions::default(),
+            origin: OperationOrigin::External,
+        };
+        let resp = self.send(req).await?;
+        match resp {
+            ServerResponse::AddOp { hash } => Ok(hash),
+            _ => Err(PoolError::UnexpectedResponse),
+        }
+    }
 }
 
 #[async_trait]
@@ -284,6 +303,51 @@ impl Pool for LocalPoolHandle {
         }
     }
 
+    async fn assign_senders(
+        &self,
+        builder: Address,
+        senders: Vec<Address>,
+        max_ops: u64,
+    ) -> PoolResult<Vec<Address>> {
+        let req = ServerRequestKind::AssignSenders {
+            builder,
+            senders,
+            max_ops,
+        };
+        let resp = self.send(req).await?;
+        match resp {
+            ServerResponse::AssignSenders { assigned } => Ok(assigned),
+            _ => Err(PoolError::UnexpectedResponse),
+        }
+    }
+
+    async fn confirm_senders(&self, builder: Address, confirmed: Vec<Address>) -> PoolResult<()> {
+        let req = ServerRequestKind::ConfirmSenders { builder, confirmed };
+        let resp = self.send(req).await?;
+        match resp {
+            ServerResponse::ConfirmSenders => Ok(()),
+            _ => Err(PoolError::UnexpectedResponse),
+        }
+    }
+
+    async fn release_senders(&self, builder: Address, kept: Vec<Address>) -> PoolResult<()> {
+        let req = ServerRequestKind::ReleaseSenders { builder, kept };
+        let resp = self.send(req).await?;
+        match resp {
+            ServerResponse::ReleaseSenders => Ok(()),
+            _ => Err(PoolError::UnexpectedResponse),
+        }
+    }
+
+    async fn renew_senders(&self, builder: Address) -> PoolResult<()> {
+        let req = ServerRequestKind::RenewSenders { builder };
+        let resp = self.send(req).await?;
+        match resp {
+            ServerResponse::RenewSenders => Ok(()),
+            _ => Err(PoolError::UnexpectedResponse),
+        }
+    }
+
     async fn debug_dump_mempool(&self, entry_point: Address) -> PoolResult<Vec<PoolOperation>> {
         let req = ServerRequestKind::DebugDumpMempool { entry_point };
         let resp = self.send(req).await?;
@@ -410,6 +474,7 @@ impl LocalPoolServerRunner {
         mempools: HashMap<Address, Arc<dyn Mempool>>,
         chain_subscriber: ChainSubscriber,
         task_spawner: Box<dyn TaskSpawner>,
+        sender_lease_duration: Duration,
     ) -> Self {
         Self {
             req_receiver,
@@ -417,6 +482,7 @@ impl LocalPoolServerRunner {
             mempools,
             chain_subscriber,
             task_spawner,
+            sender_assignments: SenderAssignments::new(Some(sender_lease_duration)),
         }
     }
 
@@ -644,6 +710,11 @@ impl LocalPoolServerRunner {
                                                 break 'resp Err(anyhow::anyhow!("Invalid user operation version for mempool v0.7 {:?}", op.uo_type()).into());
                                             }
                                         }
+                                        EntryPointVersion::V0_8 => {
+                                            if !matches!(&op, UserOperationVariant::V0_8(_)){
+                                                break 'resp Err(anyhow::anyhow!("Invalid user operation version for mempool v0.8 {:?}", op.uo_type()).into());
+                                            }
+                                        }
                                         EntryPointVersion::Unspecified => {
                                             panic!("Found mempool with unspecified entry point version")
                                         }
@@ -772,6 +843,24 @@ impl LocalPoolServerRunner {
                             self.chain_subscriber.track_addresses(to_track);
                             Ok(ServerResponse::SubscribeNewHeads { new_heads: self.block_sender.subscribe() } )
                         }
+                        ServerRequestKind::AssignSenders { builder, senders, max_ops } => {
+                            let assigned = self.sender_assignments.assign(builder, senders, max_ops as usize);
+                            Ok(ServerResponse::AssignSenders { assigned })
+                        },
+                        ServerRequestKind::ConfirmSenders { builder, confirmed } => {
+                            match self.sender_assignments.confirm(builder, confirmed) {
+                                Ok(_) => Ok(ServerResponse::ConfirmSenders),
+                                Err(e) => Err(anyhow::anyhow!(e).into()),
+                            }
+                        },
+                        ServerRequestKind::ReleaseSenders { builder, kept } => {
+                            self.sender_assignments.release_all_except(builder, kept);
+                            Ok(ServerResponse::ReleaseSenders)
+                        },
+                        ServerRequestKind::RenewSenders { builder } => {
+                            self.sender_assignments.renew_lease(builder);
+                            Ok(ServerResponse::RenewSenders)
+                        },
                     };
                     if let Err(e) = req.response.send(resp) {
                         tracing::error!("Failed to send response: {:?}", e);
@@ -861,6 +950,22 @@ enum ServerRequestKind {
     SubscribeNewHeads {
         to_track: Vec<Address>,
     },
+    AssignSenders {
+        builder: Address,
+        senders: Vec<Address>,
+        max_ops: u64,
+    },
+    ConfirmSenders {
+        builder: Address,
+        confirmed: Vec<Address>,
+    },
+    ReleaseSenders {
+        builder: Address,
+        kept: Vec<Address>,
+    },
+    RenewSenders {
+        builder: Address,
+    },
 }
 
 #[derive(Debug)]
@@ -910,6 +1015,12 @@ enum ServerResponse {
     SubscribeNewHeads {
         new_heads: broadcast::Receiver<NewHead>,
     },
+    AssignSenders {
+        assigned: Vec<Address>,
+    },
+    ConfirmSenders,
+    ReleaseSenders,
+    RenewSenders,
 }
 
 #[cfg(test)]
@@ -1044,6 +1155,49 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_sender_assignment() {
+        let state = setup(HashMap::new());
+        let (builder0, builder1) = (Address::random(), Address::random());
+        let (sender0, sender1) = (Address::random(), Address::random());
+
+        let assigned = state
+            .handle
+            .assign_senders(builder0, vec![sender0, sender1], 10)
+            .await
+            .unwrap();
+        assert_eq!(assigned, vec![sender0, sender1]);
+        state
+            .handle
+            .confirm_senders(builder0, vec![sender0])
+            .await
+            .unwrap();
+
+        let assigned = state
+            .handle
+            .assign_senders(builder1, vec![sender0, sender1], 10)
+            .await
+            .unwrap();
+        assert_eq!(assigned, vec![sender1]);
+        assert!(state
+            .handle
+            .confirm_senders(builder1, vec![sender0])
+            .await
+            .is_err());
+
+        state
+            .handle
+            .release_senders(builder0, vec![])
+            .await
+            .unwrap();
+        let assigned = state
+            .handle
+            .assign_senders(builder1, vec![sender0], 10)
+            .await
+            .unwrap();
+        assert_eq!(assigned, vec![sender0]);
+    }
+
     struct State {
         handle: LocalPoolHandle,
         chain_update_tx: Arc<broadcast::Sender<Arc<ChainUpdate>>>,
@@ -1064,7 +1218,13 @@ mod tests {
         };
 
         ts.spawn_critical_with_graceful_shutdown_signal("test pool", |shutdown| {
-            builder.run(ts_box, pools, chain_subscriber, shutdown)
+            builder.run(
+                ts_box,
+                pools,
+                chain_subscriber,
+                Duration::from_secs(60),
+                shutdown,
+            )
         });
 
         State {
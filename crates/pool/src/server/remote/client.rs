@@ -42,17 +42,19 @@ use tonic_health::{
 };
 
 use super::protos::{
-    self, add_op_response, admin_set_tracking_response, debug_clear_state_response,
-    debug_dump_mempool_response, debug_dump_paymaster_balances_response,
-    debug_dump_reputation_response, debug_set_reputation_response, get_op_by_hash_response,
-    get_ops_by_hashes_response, get_ops_response, get_ops_summaries_response,
-    get_reputation_status_response, get_stake_status_response, op_pool_client::OpPoolClient,
-    remove_op_by_id_response, remove_ops_response, update_entities_response, AddOpRequest,
-    AdminSetTrackingRequest, DebugClearStateRequest, DebugDumpMempoolRequest,
+    self, add_op_response, admin_set_tracking_response, assign_senders_response,
+    confirm_senders_response, debug_clear_state_response, debug_dump_mempool_response,
+    debug_dump_paymaster_balances_response, debug_dump_reputation_response,
+    debug_set_reputation_response, get_op_by_hash_response, get_ops_by_hashes_response,
+    get_ops_response, get_ops_summaries_response, get_reputation_status_response,
+    get_stake_status_response, op_pool_client::OpPoolClient, release_senders_response,
+    remove_op_by_id_response, remove_ops_response, renew_senders_response,
+    update_entities_response, AddOpRequest, AdminSetTrackingRequest, AssignSendersRequest,
+    ConfirmSendersRequest, DebugClearStateRequest, DebugDumpMempoolRequest,
     DebugDumpPaymasterBalancesRequest, DebugDumpReputationRequest, DebugSetReputationRequest,
-    GetOpsRequest, GetReputationStatusRequest, GetStakeStatusRequest, RemoveOpsRequest,
-    ReputationStatus as ProtoReputationStatus, SubscribeNewHeadsRequest, SubscribeNewHeadsResponse,
-    TryUoFromProto, UpdateEntitiesRequest,
+    GetOpsRequest, GetReputationStatusRequest, GetStakeStatusRequest, ReleaseSendersRequest,
+    RemoveOpsRequest, RenewSendersRequest, ReputationStatus as ProtoReputationStatus,
+    SubscribeNewHeadsRequest, SubscribeNewHeadsResponse, TryUoFromProto, UpdateEntitiesRequest,
 };
 
 /// Remote pool client
@@ -464,6 +466,104 @@ impl Pool for RemotePoolClient {
         }
     }
 
+    async fn assign_senders(
+        &self,
+        builder: Address,
+        senders: Vec<Address>,
+        max_ops: u64,
+    ) -> PoolResult<Vec<Address>> {
+        let res = self
+            .op_pool_client
+            .clone()
+            .assign_senders(AssignSendersRequest {
+                builder: builder.to_vec(),
+                senders: senders.iter().map(|s| s.to_vec()).collect(),
+                max_ops,
+            })
+            .await
+            .map_err(anyhow::Error::from)?
+            .into_inner()
+            .result;
+
+        match res {
+            Some(assign_senders_response::Result::Success(s)) => Ok(s
+                .senders
+                .into_iter()
+                .map(|s| from_bytes(s.as_slice()))
+                .collect::<Result<_, ConversionError>>()
+                .map_err(anyhow::Error::from)?),
+            Some(assign_senders_response::Result::Failure(f)) => Err(f.try_into()?),
+            None => Err(PoolError::Other(anyhow::anyhow!(
+                "should have received result from op pool"
+            )))?,
+        }
+    }
+
+    async fn confirm_senders(&self, builder: Address, confirmed: Vec<Address>) -> PoolResult<()> {
+        let res = self
+            .op_pool_client
+            .clone()
+            .confirm_senders(ConfirmSendersRequest {
+                builder: builder.to_vec(),
+                confirmed: confirmed.iter().map(|s| s.to_vec()).collect(),
+            })
+            .await
+            .map_err(anyhow::Error::from)?
+            .into_inner()
+            .result;
+
+        match res {
+            Some(confirm_senders_response::Result::Success(_)) => Ok(()),
+            Some(confirm_senders_response::Result::Failure(f)) => Err(f.try_into()?),
+            None => Err(PoolError::Other(anyhow::anyhow!(
+                "should have received result from op pool"
+            )))?,
+        }
+    }
+
+    async fn release_senders(&self, builder: Address, kept: Vec<Address>) -> PoolResult<()> {
+        let res = self
+            .op_pool_client
+            .clone()
+            .release_senders(ReleaseSendersRequest {
+                builder: builder.to_vec(),
+                kept: kept.iter().map(|s| s.to_vec()).collect(),
+            })
+            .await
+            .map_err(anyhow::Error::from)?
+            .into_inner()
+            .result;
+
+        match res {
+            Some(release_senders_response::Result::Success(_)) => Ok(()),
+            Some(release_senders_response::Result::Failure(f)) => Err(f.try_into()?),
+            None => Err(PoolError::Other(anyhow::anyhow!(
+                "should have received result from op pool"
+            )))?,
+        }
+    }
+
+    async fn renew_senders(&self, builder: Address) -> PoolResult<()> {
+        let res = self
+            .op_pool_client
+            .clone()
+            .renew_senders(RenewSendersRequest {
+                builder: builder.to_vec(),
+            })
+            .await
+            .map_err(anyhow::Error::from)?
+            .into_inner()
+            .result;
+
+        match res {
+            Some(renew_senders_response::Result::Success(_)) => Ok(()),
+            Some(renew_senders_response::Result::Failure(f)) => Err(f.try_into()?),
+            None => Err(PoolError::Other(anyhow::anyhow!(
+                "should have received result from op pool"
+            )))?,
+        }
+    }
+
     async fn debug_dump_mempool(&self, entry_point: Address) -> PoolResult<Vec<PoolOperation>> {
         let res = self
             .op_pool_client
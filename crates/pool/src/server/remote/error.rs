@@ -16,25 +16,27 @@ use anyhow::{bail, Context};
 use rundler_task::grpc::protos::{from_bytes, ToProtoBytes};
 use rundler_types::{
     pool::{
-        MempoolError, NeedsStakeInformation, PoolError, PrecheckViolation, SimulationViolation,
+        MempoolError, NeedsStakeInformation, PoolError, PrecheckViolation, RateLimitKey,
+        SimulationViolation,
     },
     Opcode, StorageSlot, Timestamp, ValidationRevert, ViolationOpCode,
 };
 
 use super::protos::{
-    mempool_error, precheck_violation_error, simulation_violation_error, validation_revert,
-    AccessedUndeployedContract, AccessedUnsupportedContractType, AggregatorError,
-    AggregatorMismatch, AssociatedStorageDuringDeploy, AssociatedStorageIsAlternateSender,
-    CallGasLimitTooLow, CallHadValue, CalledBannedEntryPointMethod, CodeHashChanged, DidNotRevert,
-    DiscardedOnInsertError, Entity, EntityThrottledError, EntityType, EntryPointRevert,
-    ExecutionGasLimitEfficiencyTooLow, ExistingSenderWithInitCode, FactoryCalledCreate2Twice,
-    FactoryIsNotContract, FactoryMustBeEmpty, InvalidAccountSignature, InvalidPaymasterSignature,
-    InvalidSignature, InvalidStorageAccess, InvalidTimeRange, MaxFeePerGasTooLow,
-    MaxOperationsReachedError, MaxPriorityFeePerGasTooLow, MempoolError as ProtoMempoolError,
-    MultipleRolesViolation, NotStaked, OperationAlreadyKnownError, OperationDropTooSoon,
-    OperationRevert, OutOfGas, OverMaxCost, PanicRevert, PaymasterBalanceTooLow,
-    PaymasterDepositTooLow, PaymasterIsNotContract, PreOpGasLimitEfficiencyTooLow,
-    PreVerificationGasTooLow, PrecheckViolationError as ProtoPrecheckViolationError,
+    mempool_error, precheck_violation_error, rate_limited_error, simulation_violation_error,
+    validation_revert, AccessedUndeployedContract, AccessedUnsupportedContractType,
+    AggregatorError, AggregatorMismatch, AssociatedStorageDuringDeploy,
+    AssociatedStorageIsAlternateSender, CallGasLimitTooLow, CallHadValue,
+    CalledBannedEntryPointMethod, CodeHashChanged, DidNotRevert, DiscardedOnInsertError, Entity,
+    EntityThrottledError, EntityType, EntryPointRevert, ExecutionGasLimitEfficiencyTooLow,
+    ExistingSenderWithInitCode, FactoryCalledCreate2Twice, FactoryIsNotContract,
+    FactoryMustBeEmpty, InvalidAccountSignature, InvalidPaymasterSignature, InvalidSignature,
+    InvalidStorageAccess, InvalidTimeRange, MaxFeePerGasTooLow, MaxOperationsReachedError,
+    MaxPriorityFeePerGasTooLow, MempoolError as ProtoMempoolError, MultipleRolesViolation,
+    NotStaked, OperationAlreadyKnownError, OperationDropTooSoon, OperationRevert, OutOfGas,
+    OverMaxCost, PanicRevert, PaymasterBalanceTooLow, PaymasterDepositTooLow,
+    PaymasterIsNotContract, PreOpGasLimitEfficiencyTooLow, PreVerificationGasTooLow,
+    PrecheckViolationError as ProtoPrecheckViolationError, RateLimitedError,
     ReplacementUnderpricedError, SenderAddressUsedAsAlternateEntity, SenderFundsTooLow,
     SenderIsNotContractAndNoInitCode, SimulationViolationError as ProtoSimulationViolationError,
     TooManyExpectedStorageSlots, TotalGasLimitTooHigh, UnintendedRevert,
@@ -141,6 +143,15 @@ impl TryFrom<ProtoMempoolError> for MempoolError {
             Some(mempool_error::Error::UseUnsupportedEip(e)) => {
                 MempoolError::EIPNotSupported(e.eip_name)
             }
+            Some(mempool_error::Error::RateLimited(e)) => MempoolError::RateLimited(match e.key {
+                Some(rate_limited_error::Key::Sender(a)) => RateLimitKey::Sender(from_bytes(&a)?),
+                Some(rate_limited_error::Key::Paymaster(a)) => {
+                    RateLimitKey::Paymaster(from_bytes(&a)?)
+                }
+                Some(rate_limited_error::Key::Factory(a)) => RateLimitKey::Factory(from_bytes(&a)?),
+                Some(rate_limited_error::Key::Caller(c)) => RateLimitKey::Caller(c),
+                None => bail!("unknown proto rate limit key"),
+            }),
             None => bail!("unknown proto mempool error"),
         })
     }
@@ -268,6 +279,22 @@ impl From<MempoolError> for ProtoMempoolError {
                     eip_name: msg,
                 })),
             },
+            MempoolError::RateLimited(key) => ProtoMempoolError {
+                error: Some(mempool_error::Error::RateLimited(RateLimitedError {
+                    key: Some(match key {
+                        RateLimitKey::Sender(a) => {
+                            rate_limited_error::Key::Sender(a.to_proto_bytes())
+                        }
+                        RateLimitKey::Paymaster(a) => {
+                            rate_limited_error::Key::Paymaster(a.to_proto_bytes())
+                        }
+                        RateLimitKey::Factory(a) => {
+                            rate_limited_error::Key::Factory(a.to_proto_bytes())
+                        }
+                        RateLimitKey::Caller(c) => rate_limited_error::Key::Caller(c),
+                    }),
+                })),
+            },
         }
     }
 }
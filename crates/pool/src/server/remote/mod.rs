@@ -18,4 +18,5 @@ mod protos;
 mod server;
 
 pub use client::*;
+pub(crate) use protos::{MempoolSnapshot, TryUoFromProto};
 pub(crate) use server::remote_mempool_server_task;
@@ -27,13 +27,15 @@ use rundler_types::{
         PoolOperationSummary as RundlerPoolOperationSummary, Reputation as PoolReputation,
         ReputationStatus as PoolReputationStatus, StakeStatus as RundlerStakeStatus,
     },
-    v0_6, v0_7, BundlerSponsorship as RundlerBundlerSponsorship, Entity as RundlerEntity,
+    v0_6, v0_7, v0_8, BundlerSponsorship as RundlerBundlerSponsorship, Entity as RundlerEntity,
     EntityInfos, EntityType as RundlerEntityType, EntityUpdate as RundlerEntityUpdate,
     EntityUpdateType as RundlerEntityUpdateType, StakeInfo as RundlerStakeInfo, UserOperation as _,
     UserOperationPermissions as RundlerUserOperationPermissions, UserOperationVariant,
     ValidTimeRange,
 };
 
+use crate::store::Snapshot;
+
 tonic::include_proto!("op_pool");
 
 pub const OP_POOL_FILE_DESCRIPTOR_SET: &[u8] =
@@ -44,6 +46,7 @@ impl From<&UserOperationVariant> for UserOperation {
         match op {
             UserOperationVariant::V0_6(op) => op.into(),
             UserOperationVariant::V0_7(op) => op.into(),
+            UserOperationVariant::V0_8(op) => op.into(),
         }
     }
 }
@@ -142,7 +145,28 @@ impl TryUoFromProto<UserOperationV06> for v0_6::UserOperation {
 
 impl From<&v0_7::UserOperation> for UserOperation {
     fn from(op: &v0_7::UserOperation) -> Self {
-        let op = UserOperationV07 {
+        UserOperation {
+            uo: Some(user_operation::Uo::V07(op.into())),
+        }
+    }
+}
+
+impl From<&v0_8::UserOperation> for UserOperation {
+    fn from(op: &v0_8::UserOperation) -> Self {
+        let mut uo: UserOperationV07 = (&op.clone().into_v0_7()).into();
+        uo.eip7702_delegate = op
+            .eip7702_delegate()
+            .map(|d| d.to_proto_bytes())
+            .unwrap_or_default();
+        UserOperation {
+            uo: Some(user_operation::Uo::V08(uo)),
+        }
+    }
+}
+
+impl From<&v0_7::UserOperation> for UserOperationV07 {
+    fn from(op: &v0_7::UserOperation) -> Self {
+        UserOperationV07 {
             sender: op.sender().to_proto_bytes(),
             nonce: op.nonce().to_proto_bytes(),
             call_data: op.call_data().to_proto_bytes(),
@@ -172,9 +196,7 @@ impl From<&v0_7::UserOperation> for UserOperation {
                 .aggregator()
                 .map(|a| a.to_proto_bytes())
                 .unwrap_or_default(),
-        };
-        UserOperation {
-            uo: Some(user_operation::Uo::V07(op)),
+            eip7702_delegate: vec![],
         }
     }
 }
@@ -242,6 +264,21 @@ impl TryUoFromProto<UserOperation> for UserOperationVariant {
             user_operation::Uo::V07(op) => Ok(UserOperationVariant::V0_7(
                 v0_7::UserOperation::try_uo_from_proto(op, chain_spec)?,
             )),
+            user_operation::Uo::V08(op) => {
+                let eip7702_delegate = if op.eip7702_delegate.is_empty() {
+                    None
+                } else {
+                    Some(from_bytes(&op.eip7702_delegate)?)
+                };
+                let mut builder = v0_8::UserOperationBuilder::from_v0_7(
+                    v0_7::UserOperation::try_uo_from_proto(op, chain_spec)?,
+                    chain_spec,
+                );
+                if let Some(delegate) = eip7702_delegate {
+                    builder = builder.eip7702_delegate(delegate);
+                }
+                Ok(UserOperationVariant::V0_8(builder.build()))
+            }
         }
     }
 }
@@ -618,6 +655,51 @@ impl From<PoolPaymasterMetadata> for PaymasterBalance {
     }
 }
 
+impl From<&Snapshot> for MempoolSnapshot {
+    fn from(snapshot: &Snapshot) -> Self {
+        Self {
+            ops: snapshot.ops.iter().map(MempoolOp::from).collect(),
+            reputations: snapshot
+                .reputations
+                .iter()
+                .cloned()
+                .map(Reputation::from)
+                .collect(),
+            paymaster_balances: snapshot
+                .paymaster_balances
+                .iter()
+                .copied()
+                .map(PaymasterBalance::from)
+                .collect(),
+        }
+    }
+}
+
+impl TryUoFromProto<MempoolSnapshot> for Snapshot {
+    fn try_uo_from_proto(
+        snapshot: MempoolSnapshot,
+        chain_spec: &ChainSpec,
+    ) -> Result<Self, ConversionError> {
+        Ok(Self {
+            ops: snapshot
+                .ops
+                .into_iter()
+                .map(|op| PoolOperation::try_uo_from_proto(op, chain_spec))
+                .collect::<Result<_, _>>()?,
+            reputations: snapshot
+                .reputations
+                .into_iter()
+                .map(PoolReputation::try_from)
+                .collect::<Result<_, _>>()?,
+            paymaster_balances: snapshot
+                .paymaster_balances
+                .into_iter()
+                .map(PoolPaymasterMetadata::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
 impl TryFrom<UserOperationPermissions> for RundlerUserOperationPermissions {
     type Error = ConversionError;
 
@@ -633,6 +715,8 @@ impl TryFrom<UserOperationPermissions> for RundlerUserOperationPermissions {
                 .bundler_sponsorship
                 .map(|s| s.try_into())
                 .transpose()?,
+            priority_tier: permissions.priority_tier,
+            caller_id: permissions.caller_id,
         })
     }
 }
@@ -647,6 +731,8 @@ impl From<RundlerUserOperationPermissions> for UserOperationPermissions {
             underpriced_accept_pct: permissions.underpriced_accept_pct,
             underpriced_bundle_pct: permissions.underpriced_bundle_pct,
             bundler_sponsorship: permissions.bundler_sponsorship.map(|s| s.into()),
+            priority_tier: permissions.priority_tier,
+            caller_id: permissions.caller_id,
         }
     }
 }
@@ -38,15 +38,18 @@ use tokio_stream::wrappers::UnboundedReceiverStream;
 use tonic::{transport::Server, Request, Response, Result, Status};
 
 use super::protos::{
-    add_op_response, admin_set_tracking_response, debug_clear_state_response,
-    debug_dump_mempool_response, debug_dump_paymaster_balances_response,
-    debug_dump_reputation_response, debug_set_reputation_response, get_op_by_hash_response,
-    get_ops_by_hashes_response, get_ops_response, get_ops_summaries_response,
-    get_reputation_status_response, get_stake_status_response,
+    add_op_response, admin_set_tracking_response, assign_senders_response,
+    confirm_senders_response, debug_clear_state_response, debug_dump_mempool_response,
+    debug_dump_paymaster_balances_response, debug_dump_reputation_response,
+    debug_set_reputation_response, get_op_by_hash_response, get_ops_by_hashes_response,
+    get_ops_response, get_ops_summaries_response, get_reputation_status_response,
+    get_stake_status_response,
     op_pool_server::{OpPool, OpPoolServer},
-    remove_op_by_id_response, remove_ops_response, update_entities_response, AddOpRequest,
-    AddOpResponse, AddOpSuccess, AdminSetTrackingRequest, AdminSetTrackingResponse,
-    AdminSetTrackingSuccess, DebugClearStateRequest, DebugClearStateResponse,
+    release_senders_response, remove_op_by_id_response, remove_ops_response,
+    renew_senders_response, update_entities_response, AddOpRequest, AddOpResponse, AddOpSuccess,
+    AdminSetTrackingRequest, AdminSetTrackingResponse, AdminSetTrackingSuccess,
+    AssignSendersRequest, AssignSendersResponse, AssignSendersSuccess, ConfirmSendersRequest,
+    ConfirmSendersResponse, ConfirmSendersSuccess, DebugClearStateRequest, DebugClearStateResponse,
     DebugClearStateSuccess, DebugDumpMempoolRequest, DebugDumpMempoolResponse,
     DebugDumpMempoolSuccess, DebugDumpPaymasterBalancesRequest, DebugDumpPaymasterBalancesResponse,
     DebugDumpPaymasterBalancesSuccess, DebugDumpReputationRequest, DebugDumpReputationResponse,
@@ -57,10 +60,12 @@ use super::protos::{
     GetOpsSummariesSuccess, GetReputationStatusRequest, GetReputationStatusResponse,
     GetReputationStatusSuccess, GetStakeStatusRequest, GetStakeStatusResponse,
     GetStakeStatusSuccess, GetSupportedEntryPointsRequest, GetSupportedEntryPointsResponse,
-    MempoolOp, PoolOperationSummary, RemoveOpByIdRequest, RemoveOpByIdResponse,
-    RemoveOpByIdSuccess, RemoveOpsRequest, RemoveOpsResponse, RemoveOpsSuccess, ReputationStatus,
-    SubscribeNewHeadsRequest, SubscribeNewHeadsResponse, TryUoFromProto, UpdateEntitiesRequest,
-    UpdateEntitiesResponse, UpdateEntitiesSuccess, OP_POOL_FILE_DESCRIPTOR_SET,
+    MempoolOp, PoolOperationSummary, ReleaseSendersRequest, ReleaseSendersResponse,
+    ReleaseSendersSuccess, RemoveOpByIdRequest, RemoveOpByIdResponse, RemoveOpByIdSuccess,
+    RemoveOpsRequest, RemoveOpsResponse, RemoveOpsSuccess, RenewSendersRequest,
+    RenewSendersResponse, RenewSendersSuccess, ReputationStatus, SubscribeNewHeadsRequest,
+    SubscribeNewHeadsResponse, TryUoFromProto, UpdateEntitiesRequest, UpdateEntitiesResponse,
+    UpdateEntitiesSuccess, OP_POOL_FILE_DESCRIPTOR_SET,
 };
 use crate::server::local::LocalPoolHandle;
 
@@ -132,6 +137,10 @@ impl OpPoolImpl {
     fn get_address(&self, address: &[u8]) -> Result<Address> {
         from_bytes(address).map_err(|e| Status::invalid_argument(format!("Invalid address: {e}")))
     }
+
+    fn get_addresses(&self, addresses: &[Vec<u8>]) -> Result<Vec<Address>> {
+        addresses.iter().map(|a| self.get_address(a)).collect()
+    }
 }
 
 #[async_trait]
@@ -443,6 +452,95 @@ impl OpPool for OpPoolImpl {
         Ok(Response::new(resp))
     }
 
+    async fn assign_senders(
+        &self,
+        request: Request<AssignSendersRequest>,
+    ) -> Result<Response<AssignSendersResponse>> {
+        let req = request.into_inner();
+        let builder = self.get_address(&req.builder)?;
+        let senders = self.get_addresses(&req.senders)?;
+        let resp = match self
+            .local_pool
+            .assign_senders(builder, senders, req.max_ops)
+            .await
+        {
+            Ok(assigned) => AssignSendersResponse {
+                result: Some(assign_senders_response::Result::Success(
+                    AssignSendersSuccess {
+                        senders: assigned.into_iter().map(|s| s.to_vec()).collect(),
+                    },
+                )),
+            },
+            Err(error) => AssignSendersResponse {
+                result: Some(assign_senders_response::Result::Failure(error.into())),
+            },
+        };
+
+        Ok(Response::new(resp))
+    }
+
+    async fn confirm_senders(
+        &self,
+        request: Request<ConfirmSendersRequest>,
+    ) -> Result<Response<ConfirmSendersResponse>> {
+        let req = request.into_inner();
+        let builder = self.get_address(&req.builder)?;
+        let confirmed = self.get_addresses(&req.confirmed)?;
+        let resp = match self.local_pool.confirm_senders(builder, confirmed).await {
+            Ok(_) => ConfirmSendersResponse {
+                result: Some(confirm_senders_response::Result::Success(
+                    ConfirmSendersSuccess {},
+                )),
+            },
+            Err(error) => ConfirmSendersResponse {
+                result: Some(confirm_senders_response::Result::Failure(error.into())),
+            },
+        };
+
+        Ok(Response::new(resp))
+    }
+
+    async fn release_senders(
+        &self,
+        request: Request<ReleaseSendersRequest>,
+    ) -> Result<Response<ReleaseSendersResponse>> {
+        let req = request.into_inner();
+        let builder = self.get_address(&req.builder)?;
+        let kept = self.get_addresses(&req.kept)?;
+        let resp = match self.local_pool.release_senders(builder, kept).await {
+            Ok(_) => ReleaseSendersResponse {
+                result: Some(release_senders_response::Result::Success(
+                    ReleaseSendersSuccess {},
+                )),
+            },
+            Err(error) => ReleaseSendersResponse {
+                result: Some(release_senders_response::Result::Failure(error.into())),
+            },
+        };
+
+        Ok(Response::new(resp))
+    }
+
+    async fn renew_senders(
+        &self,
+        request: Request<RenewSendersRequest>,
+    ) -> Result<Response<RenewSendersResponse>> {
+        let req = request.into_inner();
+        let builder = self.get_address(&req.builder)?;
+        let resp = match self.local_pool.renew_senders(builder).await {
+            Ok(_) => RenewSendersResponse {
+                result: Some(renew_senders_response::Result::Success(
+                    RenewSendersSuccess {},
+                )),
+            },
+            Err(error) => RenewSendersResponse {
+                result: Some(renew_senders_response::Result::Failure(error.into())),
+            },
+        };
+
+        Ok(Response::new(resp))
+    }
+
     async fn debug_dump_mempool(
         &self,
         request: Request<DebugDumpMempoolRequest>,
@@ -0,0 +1,89 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::path::Path;
+
+use anyhow::Context;
+use redb::{Database, ReadableTable, TableDefinition, TableError};
+
+use super::KvStore;
+
+const POOL_TABLE: TableDefinition<'_, &str, &[u8]> = TableDefinition::new("pool");
+
+/// A key-value store backed by an embedded redb database file
+pub(crate) struct RedbStore {
+    db: Database,
+}
+
+impl RedbStore {
+    /// Opens the database at `path`, creating it if it doesn't exist
+    pub(crate) fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("should have created directory {parent:?}"))?;
+        }
+        let db = Database::create(path)
+            .with_context(|| format!("should have opened pool store at {path:?}"))?;
+        Ok(Self { db })
+    }
+}
+
+impl KvStore for RedbStore {
+    fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let txn = self.db.begin_read()?;
+        let table = match txn.open_table(POOL_TABLE) {
+            Ok(table) => table,
+            // Nothing has been written yet
+            Err(TableError::TableDoesNotExist(_)) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(table.get(key)?.map(|v| v.value().to_vec()))
+    }
+
+    fn put(&self, key: &str, value: Vec<u8>) -> anyhow::Result<()> {
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(POOL_TABLE)?;
+            table.insert(key, value.as_slice())?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_put() {
+        let dir = std::env::temp_dir().join(format!("rundler-pool-store-{}", std::process::id()));
+        let path = dir.join("pool.redb");
+
+        {
+            let store = RedbStore::open(&path).unwrap();
+            assert_eq!(store.get("a").unwrap(), None);
+            store.put("a", vec![1, 2, 3]).unwrap();
+            store.put("a", vec![4, 5]).unwrap();
+            store.put("b", vec![6]).unwrap();
+        }
+
+        // values survive reopening the database
+        let store = RedbStore::open(&path).unwrap();
+        assert_eq!(store.get("a").unwrap(), Some(vec![4, 5]));
+        assert_eq!(store.get("b").unwrap(), Some(vec![6]));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,368 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+//! Persistence of mempool state across restarts.
+//!
+//! Each mempool is periodically snapshotted to a key-value store: its operations,
+//! the reputation of tracked entities and the tracked paymaster balances. On startup
+//! the snapshot is reloaded before the chain watcher starts. Operations are not trusted
+//! from the snapshot, they are re-validated through the normal `add_operation` path and
+//! dropped if they are no longer valid.
+
+mod db;
+pub(crate) use self::db::RedbStore;
+
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
+
+use alloy_primitives::Address;
+use anyhow::Context;
+use prost::Message;
+use rundler_task::GracefulShutdown;
+use rundler_types::{
+    chain::ChainSpec,
+    pool::{PaymasterMetadata, PoolOperation, Reputation},
+    Timestamp, UserOperation,
+};
+use tracing::{info, warn};
+
+use crate::{
+    mempool::{Mempool, OperationOrigin},
+    server::{MempoolSnapshot, TryUoFromProto},
+};
+
+/// Settings for the pool store
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// Path of the database file
+    pub path: PathBuf,
+    /// Interval at which mempool state is snapshotted to the store
+    pub snapshot_interval: Duration,
+}
+
+/// A key-value store used to persist pool state
+pub(crate) trait KvStore: Send + Sync {
+    /// Returns the value stored at `key`, if any
+    fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// Stores `value` at `key`, overwriting any existing value
+    fn put(&self, key: &str, value: Vec<u8>) -> anyhow::Result<()>;
+}
+
+/// The persisted state of a single mempool
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Snapshot {
+    /// Operations in the mempool
+    pub(crate) ops: Vec<PoolOperation>,
+    /// Reputation of tracked entities
+    pub(crate) reputations: Vec<Reputation>,
+    /// Tracked paymaster balances
+    pub(crate) paymaster_balances: Vec<PaymasterMetadata>,
+}
+
+impl Snapshot {
+    fn take(mempool: &dyn Mempool) -> Self {
+        Self {
+            ops: mempool
+                .all_operations(usize::MAX)
+                .iter()
+                .map(|op| (**op).clone())
+                .collect(),
+            reputations: mempool.dump_reputation(),
+            paymaster_balances: mempool.dump_paymaster_balances(),
+        }
+    }
+}
+
+/// Reads and writes mempool snapshots
+#[derive(Clone)]
+pub(crate) struct PoolStore {
+    kv: Arc<dyn KvStore>,
+    chain_spec: ChainSpec,
+}
+
+impl PoolStore {
+    pub(crate) fn new(kv: Arc<dyn KvStore>, chain_spec: ChainSpec) -> Self {
+        Self { kv, chain_spec }
+    }
+
+    fn key(&self, entry_point: Address) -> String {
+        format!("mempool_snapshot/{}/{entry_point}", self.chain_spec.id)
+    }
+
+    /// Loads the snapshot of the mempool for `entry_point`, if one was saved
+    pub(crate) fn load(&self, entry_point: Address) -> anyhow::Result<Option<Snapshot>> {
+        let Some(bytes) = self.kv.get(&self.key(entry_point))? else {
+            return Ok(None);
+        };
+        let proto = MempoolSnapshot::decode(bytes.as_slice())
+            .context("should have decoded mempool snapshot")?;
+        let snapshot = Snapshot::try_uo_from_proto(proto, &self.chain_spec)
+            .context("should have converted mempool snapshot")?;
+        Ok(Some(snapshot))
+    }
+
+    /// Saves the snapshot of the mempool for `entry_point`
+    pub(crate) fn save(&self, entry_point: Address, snapshot: &Snapshot) -> anyhow::Result<()> {
+        let bytes = MempoolSnapshot::from(snapshot).encode_to_vec();
+        self.kv.put(&self.key(entry_point), bytes)
+    }
+
+    /// Reloads the saved state of every mempool.
+    ///
+    /// Must be called before the mempools receive their first chain update.
+    pub(crate) async fn restore(&self, mempools: &HashMap<Address, Arc<dyn Mempool>>) {
+        for (entry_point, mempool) in mempools {
+            match self.load(*entry_point) {
+                Ok(Some(snapshot)) => {
+                    restore_mempool(*entry_point, mempool.as_ref(), snapshot).await
+                }
+                Ok(None) => info!("No saved state for mempool {entry_point:?}"),
+                Err(e) => warn!("Failed to load saved state for mempool {entry_point:?}: {e:?}"),
+            }
+        }
+    }
+
+    /// Snapshots every mempool to the store on an interval, and once more on shutdown
+    pub(crate) async fn run(
+        self,
+        mempools: HashMap<Address, Arc<dyn Mempool>>,
+        snapshot_interval: Duration,
+        shutdown: GracefulShutdown,
+    ) {
+        let mut interval = tokio::time::interval(snapshot_interval);
+        // The first tick completes immediately, skip it as the pool has just been restored
+        interval.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    self.save_all(&mempools).await;
+                }
+                _ = shutdown.clone() => {
+                    self.save_all(&mempools).await;
+                    info!("Saved mempool state on shutdown");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn save_all(&self, mempools: &HashMap<Address, Arc<dyn Mempool>>) {
+        for (entry_point, mempool) in mempools {
+            let snapshot = Snapshot::take(mempool.as_ref());
+            let store = self.clone();
+            let entry_point = *entry_point;
+            let res = tokio::task::spawn_blocking(move || store.save(entry_point, &snapshot)).await;
+            match res {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => warn!("Failed to save state for mempool {entry_point:?}: {e:?}"),
+                Err(e) => warn!("Failed to save state for mempool {entry_point:?}: {e:?}"),
+            }
+        }
+    }
+}
+
+async fn restore_mempool(entry_point: Address, mempool: &dyn Mempool, snapshot: Snapshot) {
+    // Seed the tracked paymasters and refresh their confirmed balances in a single batch,
+    // pending balances are rebuilt as operations are re-added
+    mempool.restore_paymaster_balances(&snapshot.paymaster_balances);
+    if let Err(e) = mempool.reset_confirmed_paymaster_balances().await {
+        warn!("Failed to refresh restored paymaster balances for mempool {entry_point:?}: {e:?}");
+    }
+
+    let now = Timestamp::now();
+    let total = snapshot.ops.len();
+    let mut restored = 0;
+    for op in snapshot.ops {
+        if op.valid_time_range.valid_until < now {
+            continue;
+        }
+        let hash = op.uo.hash();
+        match mempool
+            .add_operation(
+                OperationOrigin::Restored,
+                op.uo.with_original_signature(),
+                op.perms,
+            )
+            .await
+        {
+            Ok(_) => restored += 1,
+            Err(e) => info!("Dropping saved op {hash:?} that failed re-validation: {e:?}"),
+        }
+    }
+
+    // Restored last as re-adding operations counts them as seen
+    for rep in &snapshot.reputations {
+        mempool.set_reputation(rep.address, rep.ops_seen, rep.ops_included);
+    }
+
+    info!(
+        "Restored {restored}/{total} ops and {} reputations for mempool {entry_point:?}",
+        snapshot.reputations.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{B256, U256};
+    use parking_lot::Mutex;
+    use rundler_types::{
+        v0_6::{UserOperationBuilder, UserOperationRequiredFields},
+        EntityInfos, UserOperationPermissions, ValidTimeRange,
+    };
+
+    use super::*;
+    use crate::mempool::MockMempool;
+
+    #[derive(Default)]
+    struct MemoryStore(Mutex<HashMap<String, Vec<u8>>>);
+
+    impl KvStore for MemoryStore {
+        fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(self.0.lock().get(key).cloned())
+        }
+
+        fn put(&self, key: &str, value: Vec<u8>) -> anyhow::Result<()> {
+            self.0.lock().insert(key.to_string(), value);
+            Ok(())
+        }
+    }
+
+    fn pool_op(cs: &ChainSpec, nonce: u64, valid_time_range: ValidTimeRange) -> PoolOperation {
+        PoolOperation {
+            uo: UserOperationBuilder::new(
+                cs,
+                UserOperationRequiredFields {
+                    sender: Address::random(),
+                    nonce: U256::from(nonce),
+                    ..Default::default()
+                },
+            )
+            .build()
+            .into(),
+            entry_point: cs.entry_point_address_v0_6,
+            aggregator: None,
+            valid_time_range,
+            expected_code_hash: B256::random(),
+            sim_block_hash: B256::random(),
+            sim_block_number: 0,
+            account_is_staked: false,
+            entity_infos: EntityInfos::default(),
+            da_gas_data: Default::default(),
+            filter_id: None,
+            perms: UserOperationPermissions::default(),
+        }
+    }
+
+    #[test]
+    fn test_save_load() {
+        let cs = ChainSpec::default();
+        let store = PoolStore::new(Arc::new(MemoryStore::default()), cs.clone());
+        let entry_point = cs.entry_point_address_v0_6;
+
+        assert!(store.load(entry_point).unwrap().is_none());
+
+        let snapshot = Snapshot {
+            ops: vec![pool_op(&cs, 0, ValidTimeRange::all_time())],
+            reputations: vec![Reputation {
+                address: Address::random(),
+                ops_seen: 10,
+                ops_included: 2,
+            }],
+            paymaster_balances: vec![PaymasterMetadata {
+                address: Address::random(),
+                confirmed_balance: U256::from(100),
+                pending_balance: U256::from(50),
+            }],
+        };
+        store.save(entry_point, &snapshot).unwrap();
+
+        let loaded = store.load(entry_point).unwrap().unwrap();
+        assert_eq!(loaded.ops.len(), 1);
+        assert_eq!(loaded.ops[0].uo, snapshot.ops[0].uo);
+        assert_eq!(loaded.reputations.len(), 1);
+        assert_eq!(
+            loaded.reputations[0].address,
+            snapshot.reputations[0].address
+        );
+        assert_eq!(loaded.reputations[0].ops_seen, 10);
+        assert_eq!(loaded.reputations[0].ops_included, 2);
+        assert_eq!(loaded.paymaster_balances, snapshot.paymaster_balances);
+
+        // snapshots are per entry point
+        assert!(store.load(cs.entry_point_address_v0_7).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_restore() {
+        let cs = ChainSpec::default();
+        let store = PoolStore::new(Arc::new(MemoryStore::default()), cs.clone());
+        let entry_point = cs.entry_point_address_v0_6;
+
+        let valid = pool_op(&cs, 0, ValidTimeRange::all_time());
+        let expired = pool_op(
+            &cs,
+            1,
+            ValidTimeRange::new(Timestamp::new(0), Timestamp::new(1)),
+        );
+        let rep_address = Address::random();
+        store
+            .save(
+                entry_point,
+                &Snapshot {
+                    ops: vec![valid.clone(), expired],
+                    reputations: vec![Reputation {
+                        address: rep_address,
+                        ops_seen: 5,
+                        ops_included: 1,
+                    }],
+                    paymaster_balances: vec![],
+                },
+            )
+            .unwrap();
+
+        let mut mempool = MockMempool::new();
+        let mut seq = mockall::Sequence::new();
+        mempool
+            .expect_restore_paymaster_balances()
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(());
+        mempool
+            .expect_reset_confirmed_paymaster_balances()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(()));
+        let valid_uo = valid.uo.clone();
+        mempool
+            .expect_add_operation()
+            .withf(move |origin, op, _| {
+                matches!(origin, OperationOrigin::Restored) && *op == valid_uo
+            })
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, op, _| Ok(op.hash()));
+        mempool
+            .expect_set_reputation()
+            .withf(move |address, seen, included| {
+                *address == rep_address && *seen == 5 && *included == 1
+            })
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(());
+
+        let mempools: HashMap<Address, Arc<dyn Mempool>> =
+            HashMap::from([(entry_point, Arc::new(mempool) as Arc<dyn Mempool>)]);
+        store.restore(&mempools).await;
+    }
+}
@@ -33,7 +33,9 @@ use crate::{
         AddressReputation, Mempool, PaymasterConfig, PaymasterTracker, ReputationParams, UoPool,
         UoPoolProviders,
     },
+    p2p::{P2pNetwork, Settings as P2pSettings},
     server::{self, LocalPoolBuilder},
+    store::{PoolStore, RedbStore, Settings as PoolStoreSettings},
 };
 
 /// Arguments for the pool task.
@@ -56,6 +58,15 @@ pub struct Args {
     pub remote_address: Option<SocketAddr>,
     /// Channel capacity for the chain update channel.
     pub chain_update_channel_capacity: usize,
+    /// Settings for the p2p network, if any.
+    /// If not provided, operations will not be gossiped with peers.
+    pub p2p_settings: Option<P2pSettings>,
+    /// Settings for the pool store, if any.
+    /// If not provided, pool state will not be persisted across restarts.
+    pub store_settings: Option<PoolStoreSettings>,
+    /// Time after which the senders assigned to a builder that stopped renewing its
+    /// lease are released to other builders.
+    pub sender_lease_duration: Duration,
 }
 
 /// Mempool task.
@@ -113,10 +124,6 @@ where
         let chain = Chain::new(self.providers.evm().clone(), chain_settings);
         let chain_subscriber = chain.subscriber();
 
-        task_spawner.spawn_critical_with_graceful_shutdown_signal("chain watcher", |shutdown| {
-            chain.watch(shutdown)
-        });
-
         // create mempools
         let mut mempools = HashMap::new();
         for pool_config in &self.args.pool_configs {
@@ -147,20 +154,72 @@ where
 
                     mempools.insert(pool_config.entry_point, pool);
                 }
+                EntryPointVersion::V0_8 => {
+                    let pool = self
+                        .create_mempool_v0_8(
+                            &task_spawner,
+                            self.args.chain_spec.clone(),
+                            pool_config,
+                            self.args.unsafe_mode,
+                            self.event_sender.clone(),
+                        )
+                        .context("should have created mempool")?;
+
+                    mempools.insert(pool_config.entry_point, pool);
+                }
                 EntryPointVersion::Unspecified => {
                     bail!("Unsupported entry point version");
                 }
             }
         }
 
+        // Reload saved state before the chain watcher starts so that the first chain update
+        // removes any restored operations that were mined while the pool was down
+        if let Some(settings) = &self.args.store_settings {
+            let kv = RedbStore::open(&settings.path).context("should have opened pool store")?;
+            let store = PoolStore::new(Arc::new(kv), self.args.chain_spec.clone());
+            store.restore(&mempools).await;
+
+            let mempools = mempools.clone();
+            let snapshot_interval = settings.snapshot_interval;
+            task_spawner.spawn_critical_with_graceful_shutdown_signal("pool store", |shutdown| {
+                store.run(mempools, snapshot_interval, shutdown)
+            });
+        }
+
+        task_spawner.spawn_critical_with_graceful_shutdown_signal("chain watcher", |shutdown| {
+            chain.watch(shutdown)
+        });
+
         let pool_handle = self.pool_builder.get_handle();
 
+        // Create the p2p network before the pool starts so that no pool events are missed
+        let p2p_network = match &self.args.p2p_settings {
+            Some(settings) => Some(
+                P2pNetwork::new(
+                    settings.clone(),
+                    self.args.chain_spec.clone(),
+                    &self.args.pool_configs,
+                    pool_handle.clone(),
+                    self.event_sender.subscribe(),
+                    Box::new(task_spawner.clone()),
+                )
+                .context("should have created p2p network")?,
+            ),
+            None => None,
+        };
+
         let ts_box = Box::new(task_spawner.clone());
         task_spawner.spawn_critical_with_graceful_shutdown_signal(
             "local pool server",
             |shutdown| {
-                self.pool_builder
-                    .run(ts_box, mempools, chain_subscriber, shutdown)
+                self.pool_builder.run(
+                    ts_box,
+                    mempools,
+                    chain_subscriber,
+                    self.args.sender_lease_duration,
+                    shutdown,
+                )
             },
         );
 
@@ -180,6 +239,12 @@ where
             );
         };
 
+        if let Some(p2p_network) = p2p_network {
+            task_spawner.spawn_critical_with_graceful_shutdown_signal("p2p network", |shutdown| {
+                p2p_network.run(shutdown)
+            });
+        }
+
         tracing::info!("Started op_pool");
 
         Ok(())
@@ -281,6 +346,54 @@ where
         }
     }
 
+    fn create_mempool_v0_8<T>(
+        &self,
+        task_spawner: &T,
+        chain_spec: ChainSpec,
+        pool_config: &PoolConfig,
+        unsafe_mode: bool,
+        event_sender: broadcast::Sender<WithEntryPoint<OpPoolEvent>>,
+    ) -> anyhow::Result<Arc<dyn Mempool + 'static>>
+    where
+        T: TaskSpawnerExt,
+    {
+        let ep_providers = self
+            .providers
+            .ep_v0_8_providers()
+            .clone()
+            .context("entry point v0.8 not supplied")?;
+
+        if unsafe_mode {
+            let simulator = UnsafeSimulator::new(
+                ep_providers.entry_point().clone(),
+                pool_config.sim_settings.clone(),
+            );
+            self.create_mempool(
+                task_spawner,
+                chain_spec,
+                pool_config,
+                event_sender,
+                ep_providers,
+                simulator,
+            )
+        } else {
+            let simulator = simulation::new_v0_8_simulator(
+                self.providers.evm().clone(),
+                ep_providers.entry_point().clone(),
+                pool_config.sim_settings.clone(),
+                pool_config.mempool_channel_configs.clone(),
+            );
+            self.create_mempool(
+                task_spawner,
+                chain_spec,
+                pool_config,
+                event_sender,
+                ep_providers,
+                simulator,
+            )
+        }
+    }
+
     fn create_mempool<T, UO, EP, S>(
         &self,
         task_spawner: &T,
@@ -11,25 +11,29 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use alloy_primitives::{Address, Bytes};
+use alloy_primitives::{Address, Bytes, U256};
 use alloy_provider::network::AnyNetwork;
+use alloy_sol_types::SolCall;
 use alloy_transport::Transport;
 use anyhow::Context;
 use reth_tasks::pool::BlockingTaskPool;
-use rundler_contracts::multicall3::{self, Multicall3::Multicall3Instance};
+use rundler_contracts::multicall3::{
+    self,
+    Multicall3::{Multicall3Instance, Result as MulticallResult},
+};
 use rundler_types::{
     chain::ChainSpec,
     da::{BedrockDAGasBlockData, BedrockDAGasData, DAGasBlockData, DAGasData},
 };
 use rundler_utils::cache::LruMap;
 use tokio::sync::Mutex as TokioMutex;
-use tracing::{error, instrument};
+use tracing::instrument;
 
 use super::DAMetrics;
 use crate::{
     alloy::da::optimism::GasPriceOracle::{
-        baseFeeScalarCall, blobBaseFeeCall, blobBaseFeeScalarCall, l1BaseFeeCall,
-        GasPriceOracleCalls, GasPriceOracleInstance,
+        baseFeeScalarCall, blobBaseFeeCall, blobBaseFeeScalarCall, getOperatorFeeCall, isFjordCall,
+        isIsthmusCall, l1BaseFeeCall, GasPriceOracleCalls, GasPriceOracleInstance,
     },
     AlloyProvider, BlockHashOrNumber, DAGasOracle, DAGasOracleSync, ProviderResult,
 };
@@ -39,9 +43,16 @@ const DECIMAL_SCALAR: u128 = 1_000_000_000_000;
 const COST_INTERCEPT: i128 = -42_585_600;
 const COST_FASTLZ_COEF: i128 = 836_500;
 const MIN_TRANSACTION_SIZE: i128 = 100_000_000;
+// Size of the signature added to the unsigned transaction data by the oracle
+const SIGNATURE_SIZE: u64 = 68;
 
 /// Local Bedrock DA gas oracle
 ///
+/// Supports the Ecotone and Fjord L1 fee formulas. The Isthmus operator fee constant is
+/// charged once per transaction, so it is returned in the block data for the builder to
+/// account for per bundle and is not part of the DA gas of user operations. The operator fee
+/// scalar is charged per unit of execution gas, not DA, and is not included.
+///
 /// Details: https://github.com/ethereum-optimism/specs/blob/main/specs/protocol/fjord/exec-engine.md#fjord-l1-cost-fee-changes-fastlz-estimator
 #[derive(Debug)]
 pub(crate) struct LocalBedrockDAGasOracle<AP, T> {
@@ -101,15 +112,9 @@ where
 {
     #[instrument(skip_all)]
     async fn da_block_data(&self, block: BlockHashOrNumber) -> ProviderResult<DAGasBlockData> {
-        let mut cache = self.block_data_cache.lock().await;
-        match cache.get(&block) {
-            Some(block_data) => Ok(DAGasBlockData::Bedrock(block_data.clone())),
-            None => {
-                let block_data = self.get_block_data(block).await?;
-                cache.insert(block, block_data.clone());
-                Ok(DAGasBlockData::Bedrock(block_data))
-            }
-        }
+        Ok(DAGasBlockData::Bedrock(
+            self.cached_block_data(block).await?,
+        ))
     }
 
     #[instrument(skip_all)]
@@ -117,9 +122,10 @@ where
         &self,
         data: Bytes,
         _to: Address,
-        _block: BlockHashOrNumber,
+        block: BlockHashOrNumber,
     ) -> ProviderResult<DAGasData> {
-        let gas_data = self.get_gas_data(data).await?;
+        let block_data = self.cached_block_data(block).await?;
+        let gas_data = self.get_gas_data(data, block_data.is_fjord).await?;
         Ok(DAGasData::Bedrock(gas_data))
     }
 
@@ -139,14 +145,10 @@ where
             _ => panic!("LocalBedrockDAGasOracle only supports Bedrock data"),
         };
 
-        let fee_scaled = (block_da_data.base_fee_scalar * 16 * block_da_data.l1_base_fee
-            + block_da_data.blob_base_fee_scalar * block_da_data.blob_base_fee)
-            as u128;
-
         let units = gas_data.units + extra_data_to_units(extra_data_len);
-
-        let l1_fee = (units as u128 * fee_scaled) / DECIMAL_SCALAR;
-        l1_fee.checked_div(gas_price).unwrap_or(u128::MAX)
+        l1_fee(units, block_da_data)
+            .checked_div(gas_price)
+            .unwrap_or(u128::MAX)
     }
 }
 
@@ -155,15 +157,19 @@ where
     AP: AlloyProvider<T>,
     T: Transport + Clone,
 {
-    #[instrument(skip_all)]
-    async fn is_fjord(&self) -> bool {
-        self.oracle
-            .isFjord()
-            .call()
-            .await
-            .map(|r| r.isFjord)
-            .map_err(|e| error!("failed to check if fjord: {:?}", e))
-            .unwrap_or(true) // Fail-open. Assume fjord if we can't check, so this can be used downstream in asserts w/o panic on RPC errors.
+    async fn cached_block_data(
+        &self,
+        block: BlockHashOrNumber,
+    ) -> ProviderResult<BedrockDAGasBlockData> {
+        let mut cache = self.block_data_cache.lock().await;
+        match cache.get(&block) {
+            Some(block_data) => Ok(block_data.clone()),
+            None => {
+                let block_data = self.get_block_data(block).await?;
+                cache.insert(block, block_data.clone());
+                Ok(block_data)
+            }
+        }
     }
 
     #[instrument(skip_all)]
@@ -171,25 +177,29 @@ where
         &self,
         block: BlockHashOrNumber,
     ) -> ProviderResult<BedrockDAGasBlockData> {
-        assert!(self.is_fjord().await);
-
+        let oracle = *self.oracle.address();
+        // Fork checks and the operator fee are allowed to fail on oracles deployed before
+        // the forks that added them.
+        let optional_call = |call: GasPriceOracleCalls| multicall3::Multicall3::Call3 {
+            allowFailure: true,
+            ..multicall3::create_call(oracle, call)
+        };
         let calls = vec![
             multicall3::create_call(
-                *self.oracle.address(),
+                oracle,
                 GasPriceOracleCalls::baseFeeScalar(baseFeeScalarCall {}),
             ),
+            multicall3::create_call(oracle, GasPriceOracleCalls::l1BaseFee(l1BaseFeeCall {})),
             multicall3::create_call(
-                *self.oracle.address(),
-                GasPriceOracleCalls::l1BaseFee(l1BaseFeeCall {}),
-            ),
-            multicall3::create_call(
-                *self.oracle.address(),
+                oracle,
                 GasPriceOracleCalls::blobBaseFeeScalar(blobBaseFeeScalarCall {}),
             ),
-            multicall3::create_call(
-                *self.oracle.address(),
-                GasPriceOracleCalls::blobBaseFee(blobBaseFeeCall {}),
-            ),
+            multicall3::create_call(oracle, GasPriceOracleCalls::blobBaseFee(blobBaseFeeCall {})),
+            optional_call(GasPriceOracleCalls::isFjord(isFjordCall {})),
+            optional_call(GasPriceOracleCalls::isIsthmus(isIsthmusCall {})),
+            optional_call(GasPriceOracleCalls::getOperatorFee(getOperatorFeeCall {
+                _gasUsed: U256::ZERO,
+            })),
         ];
 
         let result = self
@@ -199,12 +209,14 @@ where
             .block(block.into())
             .await?;
 
-        if result.returnData.len() != 4 {
+        if result.returnData.len() != 7 {
             Err(anyhow::anyhow!(
                 "multicall returned unexpected number of results"
             ))?;
-        } else if result.returnData.iter().any(|r| !r.success) {
-            Err(anyhow::anyhow!("multicall returned some failed results"))?;
+        } else if result.returnData[..4].iter().any(|r| !r.success) {
+            Err(anyhow::anyhow!(
+                "multicall returned some failed results, the gas price oracle must be at least on Ecotone"
+            ))?;
         }
 
         let base_fee_scalar =
@@ -224,6 +236,21 @@ where
                 ._0
                 .try_into()
                 .context("blob_base_fee too large for u64")?;
+        let is_fjord = optional_result::<isFjordCall>(&result.returnData[4])
+            .map(|r| r.isFjord)
+            .unwrap_or(false);
+        let is_isthmus = optional_result::<isIsthmusCall>(&result.returnData[5])
+            .map(|r| r.isIsthmus)
+            .unwrap_or(false);
+        let operator_fee_constant = if is_isthmus {
+            optional_result::<getOperatorFeeCall>(&result.returnData[6])
+                .context("Isthmus gas price oracle should return the operator fee")?
+                ._0
+                .try_into()
+                .context("operator_fee_constant too large for u64")?
+        } else {
+            0
+        };
 
         self.metrics.l1_base_fee.set(l1_base_fee as f64);
         self.metrics.blob_base_fee.set(blob_base_fee as f64);
@@ -233,11 +260,19 @@ where
             l1_base_fee,
             blob_base_fee_scalar,
             blob_base_fee,
+            is_fjord,
+            operator_fee_constant,
         })
     }
 
     #[instrument(skip_all)]
-    async fn get_gas_data(&self, data: Bytes) -> ProviderResult<BedrockDAGasData> {
+    async fn get_gas_data(&self, data: Bytes, is_fjord: bool) -> ProviderResult<BedrockDAGasData> {
+        if !is_fjord {
+            return Ok(BedrockDAGasData {
+                units: ecotone_units(&data),
+            });
+        }
+
         // Blocking call compressing potentially a lot of data.
         // Generally takes more than 100µs so should be spawned on blocking threadpool.
         // https://ryhl.io/blog/async-what-is-blocking/
@@ -251,19 +286,107 @@ where
             .await
             .map_err(|e| anyhow::anyhow!("failed to compress data: {:?}", e))?;
 
-        let compressed_with_buffer = compressed_len + 68;
-
-        let estimated_size = COST_INTERCEPT + COST_FASTLZ_COEF * compressed_with_buffer as i128;
-        let units = estimated_size.clamp(MIN_TRANSACTION_SIZE, u64::MAX as i128);
-
         Ok(BedrockDAGasData {
-            units: units as u64,
+            units: fjord_units(compressed_len),
         })
     }
 }
 
+fn optional_result<T: SolCall>(result: &MulticallResult) -> Option<T::Return> {
+    if !result.success {
+        return None;
+    }
+    multicall3::decode_result::<T>(&result.returnData).ok()
+}
+
+/// Estimated size of the transaction scaled by 1e6, from the FastLZ compressed length of its data
+fn fjord_units(compressed_len: u64) -> u64 {
+    let compressed_with_buffer = compressed_len + SIGNATURE_SIZE;
+
+    let estimated_size = COST_INTERCEPT + COST_FASTLZ_COEF * compressed_with_buffer as i128;
+    estimated_size.clamp(MIN_TRANSACTION_SIZE, u64::MAX as i128) as u64
+}
+
+/// Calldata gas of the transaction converted to bytes scaled by 1e6, so that the Ecotone fee
+/// `gas * fee_scaled / 16e6` equals the Fjord fee `units * fee_scaled / 1e12`
+fn ecotone_units(data: &[u8]) -> u64 {
+    let zeros = data.iter().filter(|b| **b == 0).count() as u64;
+    let non_zeros = data.len() as u64 - zeros;
+    let gas = zeros * 4 + (non_zeros + SIGNATURE_SIZE) * 16;
+    gas * 1_000_000 / 16
+}
+
+/// L1 fee in wei of a transaction with the given units
+fn l1_fee(units: u64, block_data: &BedrockDAGasBlockData) -> u128 {
+    let fee_scaled = (block_data.base_fee_scalar as u128)
+        .saturating_mul(16)
+        .saturating_mul(block_data.l1_base_fee as u128)
+        .saturating_add(
+            (block_data.blob_base_fee_scalar as u128)
+                .saturating_mul(block_data.blob_base_fee as u128),
+        );
+    (units as u128).saturating_mul(fee_scaled) / DECIMAL_SCALAR
+}
+
 fn extra_data_to_units(extra_data_len: usize) -> u64 {
     // https://github.com/ethereum-optimism/optimism/blob/d39eb247e60584c87b75baec937ddd20701225a5/packages/contracts-bedrock/src/L2/GasPriceOracle.sol#L239
     // bytes are all scaled up by 1e6
     (extra_data_len * 1_000_000) as u64
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    // Gas price oracle responses for the Ecotone, Fjord and Isthmus fee formulas
+    const FIXTURE: &str = include_str!("testdata/bedrock.json");
+
+    fn parse<T: std::str::FromStr>(value: &Value) -> T
+    where
+        T::Err: std::fmt::Debug,
+    {
+        match value {
+            Value::String(s) => s.parse().unwrap(),
+            v => v.to_string().parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_l1_fee_fixture() {
+        let fixture: Value = serde_json::from_str(FIXTURE).unwrap();
+        for case in fixture["cases"].as_array().unwrap() {
+            let name = case["name"].as_str().unwrap();
+            let data: Bytes = parse(&fixture["data"][case["data"].as_str().unwrap()]);
+            let block = &case["blockData"];
+            let block_data = BedrockDAGasBlockData {
+                base_fee_scalar: parse(&block["baseFeeScalar"]),
+                l1_base_fee: parse(&block["l1BaseFee"]),
+                blob_base_fee_scalar: parse(&block["blobBaseFeeScalar"]),
+                blob_base_fee: parse(&block["blobBaseFee"]),
+                is_fjord: parse(&block["isFjord"]),
+                operator_fee_constant: parse(&block["operatorFeeConstant"]),
+            };
+
+            let units = if block_data.is_fjord {
+                let mut buf = vec![0; data.len() * 2];
+                fjord_units(rundler_bindings_fastlz::compress(&data, &mut buf).len() as u64)
+            } else {
+                ecotone_units(&data)
+            };
+            let fee = l1_fee(units, &block_data) + block_data.operator_fee_constant as u128;
+            let expected = parse::<u128>(&case["l1Fee"]) + parse::<u128>(&case["operatorFee"]);
+            assert_eq!(fee, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_extra_data_units() {
+        // extra bytes are priced as non-zero bytes in both formulas
+        assert_eq!(
+            ecotone_units(&[1; 10]) - ecotone_units(&[]),
+            extra_data_to_units(10)
+        );
+    }
+}
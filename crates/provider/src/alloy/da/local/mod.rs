@@ -18,7 +18,7 @@ mod bedrock;
 pub(crate) use bedrock::LocalBedrockDAGasOracle;
 
 mod nitro;
-pub(crate) use nitro::CachedNitroDAGasOracle;
+pub(crate) use nitro::{CachedNitroDAGasOracle, LocalNitroDAGasOracle};
 
 #[derive(Metrics, Clone)]
 #[metrics(scope = "provider_da")]
@@ -11,10 +11,13 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
+use std::io::Write;
+
 use alloy_primitives::{Address, Bytes};
 use alloy_provider::network::AnyNetwork;
 use alloy_transport::Transport;
 use anyhow::Context;
+use reth_tasks::pool::BlockingTaskPool;
 use rundler_types::da::{DAGasBlockData, DAGasData, NitroDAGasBlockData, NitroDAGasData};
 use rundler_utils::cache::LruMap;
 use tokio::sync::Mutex as TokioMutex;
@@ -218,6 +221,133 @@ where
     }
 }
 
+/// Local Arbitrum Nitro DA gas oracle
+///
+/// Only the block data is fetched from the node interface, once per block. The units of the
+/// data are estimated locally from its brotli compressed size, the same way Nitro prices
+/// transactions for gas estimation.
+pub(crate) struct LocalNitroDAGasOracle<AP, T> {
+    cached: CachedNitroDAGasOracle<AP, T>,
+    blocking_task_pool: BlockingTaskPool,
+}
+
+impl<AP, T> LocalNitroDAGasOracle<AP, T>
+where
+    AP: AlloyProvider<T>,
+    T: Transport + Clone,
+{
+    pub(crate) fn new(oracle_address: Address, provider: AP) -> Self {
+        Self {
+            cached: CachedNitroDAGasOracle::new(oracle_address, provider),
+            blocking_task_pool: BlockingTaskPool::build()
+                .expect("failed to build blocking task pool"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<AP, T> DAGasOracle for LocalNitroDAGasOracle<AP, T>
+where
+    AP: AlloyProvider<T>,
+    T: Transport + Clone,
+{
+    #[instrument(skip_all)]
+    async fn estimate_da_gas(
+        &self,
+        data: Bytes,
+        to: Address,
+        block: BlockHashOrNumber,
+        gas_price: u128,
+        extra_bytes_len: usize,
+    ) -> ProviderResult<(u128, DAGasData, DAGasBlockData)> {
+        let block_data = self.da_block_data(block).await?;
+        let gas_data = self.da_gas_data(data, to, block).await?;
+        let da_gas = self.calc_da_gas_sync(&gas_data, &block_data, gas_price, extra_bytes_len);
+        Ok((da_gas, gas_data, block_data))
+    }
+}
+
+#[async_trait::async_trait]
+impl<AP, T> DAGasOracleSync for LocalNitroDAGasOracle<AP, T>
+where
+    AP: AlloyProvider<T>,
+    T: Transport + Clone,
+{
+    #[instrument(skip_all)]
+    async fn da_block_data(&self, block: BlockHashOrNumber) -> ProviderResult<DAGasBlockData> {
+        self.cached.da_block_data(block).await
+    }
+
+    #[instrument(skip_all)]
+    async fn da_gas_data(
+        &self,
+        data: Bytes,
+        _to: Address,
+        _block: BlockHashOrNumber,
+    ) -> ProviderResult<DAGasData> {
+        // Compression is CPU bound, run it on the blocking threadpool
+        let compressed_len = self
+            .blocking_task_pool
+            .spawn(move || brotli_compressed_len(&data))
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to compress data: {:?}", e))?;
+
+        Ok(DAGasData::Nitro(NitroDAGasData {
+            units: brotli_units(compressed_len),
+        }))
+    }
+
+    fn calc_da_gas_sync(
+        &self,
+        data: &DAGasData,
+        block_data: &DAGasBlockData,
+        gas_price: u128,
+        extra_data_len: usize,
+    ) -> u128 {
+        self.cached
+            .calc_da_gas_sync(data, block_data, gas_price, extra_data_len)
+    }
+}
+
+// Brotli settings used by Nitro to price L1 data
+const BROTLI_COMPRESSION_LEVEL: u32 = 1;
+const BROTLI_WINDOW_SIZE: u32 = 22;
+// Approximate size of the unsigned dynamic fee transaction around the data. Its nonce, fee and
+// gas fields are random during estimation so don't compress.
+const TX_ENVELOPE_SIZE: u128 = 74;
+// Nitro's padding of the units of estimated transactions
+const ESTIMATION_PADDING_UNITS: u128 = 16 * TX_NON_ZERO_GAS_EIP_2028;
+const ESTIMATION_PADDING_BIPS: u128 = 100;
+const ONE_IN_BIPS: u128 = 10_000;
+
+fn brotli_compressed_len(data: &[u8]) -> u128 {
+    let mut compressed = Vec::with_capacity(data.len());
+    {
+        let mut writer = brotli::CompressorWriter::new(
+            &mut compressed,
+            4096,
+            BROTLI_COMPRESSION_LEVEL,
+            BROTLI_WINDOW_SIZE,
+        );
+        writer
+            .write_all(data)
+            .expect("writing to a vec should not fail");
+    }
+    compressed.len() as u128
+}
+
+// Scaled units of a transaction with the given compressed data length, see
+// https://github.com/OffchainLabs/nitro/blob/32c3f4b36d5eb0b4bbd37a82afe6c0c707ebe78d/arbos/l1pricing/l1pricing.go#L582
+fn brotli_units(compressed_len: u128) -> u128 {
+    let units = (compressed_len + TX_ENVELOPE_SIZE)
+        .saturating_mul(TX_NON_ZERO_GAS_EIP_2028)
+        .saturating_add(ESTIMATION_PADDING_UNITS);
+    units
+        .saturating_mul(CACHE_UNITS_SCALAR)
+        .saturating_mul(ONE_IN_BIPS + ESTIMATION_PADDING_BIPS)
+        .saturating_div(ONE_IN_BIPS)
+}
+
 // DA Fee to gas units conversion.
 //
 // See https://github.com/OffchainLabs/nitro/blob/32c3f4b36d5eb0b4bbd37a82afe6c0c707ebe78d/execution/nodeInterface/NodeInterface.go#L515
@@ -267,3 +397,49 @@ fn extra_data_to_units(extra_data_len: usize) -> u128 {
         .saturating_mul(101)
         .saturating_div(100)
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    // Node interface responses for the data
+    const FIXTURE: &str = include_str!("testdata/nitro.json");
+
+    #[test]
+    fn test_brotli_estimate_fixture() {
+        let fixture: Value = serde_json::from_str(FIXTURE).unwrap();
+        for case in fixture["cases"].as_array().unwrap() {
+            let name = case["name"].as_str().unwrap();
+            let data: Bytes = fixture["data"][case["data"].as_str().unwrap()]
+                .as_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            let block_data = NitroDAGasBlockData {
+                l1_base_fee: case["l1BaseFeeEstimate"].as_str().unwrap().parse().unwrap(),
+                base_fee: case["baseFee"].as_str().unwrap().parse().unwrap(),
+            };
+            let expected = case["gasEstimateForL1"].as_u64().unwrap() as f64;
+
+            let units = brotli_units(brotli_compressed_len(&data));
+            let ratio = calculate_da_fee(units, &block_data) as f64 / expected;
+            assert!((0.999..=1.001).contains(&ratio), "{name}: {ratio}");
+        }
+    }
+
+    #[test]
+    fn test_units_round_trip() {
+        let block_data = NitroDAGasBlockData {
+            l1_base_fee: 32_000_000_000,
+            base_fee: 10_000_000,
+        };
+        let units = brotli_units(100);
+        let da_fee = calculate_da_fee(units, &block_data);
+        // errors round down
+        let round_trip = calculate_units(da_fee, &block_data);
+        assert!(round_trip <= units);
+        assert!(units - round_trip < CACHE_UNITS_SCALAR);
+    }
+}
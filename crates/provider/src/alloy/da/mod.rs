@@ -24,9 +24,12 @@ use arbitrum::ArbitrumNitroDAGasOracle;
 mod optimism;
 use optimism::OptimismBedrockDAGasOracle;
 mod local;
-use local::{CachedNitroDAGasOracle, LocalBedrockDAGasOracle};
+use local::{CachedNitroDAGasOracle, LocalBedrockDAGasOracle, LocalNitroDAGasOracle};
 
 /// Create a DA gas oracle for the given chain spec
+///
+/// The contract oracle types estimate with an oracle call per operation, and are paired with
+/// a local sync oracle that estimates from cached block data.
 pub fn new_alloy_da_gas_oracle<'a, AP, T>(
     chain_spec: &ChainSpec,
     provider: AP,
@@ -41,17 +44,26 @@ where
     match chain_spec.da_gas_oracle_type {
         DAGasOracleType::ArbitrumNitro => {
             let oracle = Arc::new(ArbitrumNitroDAGasOracle::new(
+                chain_spec.da_gas_oracle_contract_address,
+                provider.clone(),
+            ));
+            let sync_oracle = Arc::new(LocalNitroDAGasOracle::new(
                 chain_spec.da_gas_oracle_contract_address,
                 provider,
             ));
-            (oracle, None)
+            (oracle, Some(sync_oracle))
         }
         DAGasOracleType::OptimismBedrock => {
             let oracle = Arc::new(OptimismBedrockDAGasOracle::new(
+                chain_spec.da_gas_oracle_contract_address,
+                provider.clone(),
+            ));
+            let sync_oracle = Arc::new(LocalBedrockDAGasOracle::new(
                 chain_spec.da_gas_oracle_contract_address,
                 provider,
+                chain_spec,
             ));
-            (oracle, None)
+            (oracle, Some(sync_oracle))
         }
         DAGasOracleType::LocalBedrock => {
             let oracle = Arc::new(LocalBedrockDAGasOracle::new(
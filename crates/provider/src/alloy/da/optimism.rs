@@ -28,6 +28,7 @@ sol! {
     #[sol(rpc)]
     interface GasPriceOracle {
         bool public isFjord;
+        bool public isIsthmus;
 
         function baseFeeScalar() public view returns (uint32);
         function l1BaseFee() public view returns (uint256);
@@ -35,6 +36,7 @@ sol! {
         function blobBaseFee() public view returns (uint256);
 
         function getL1Fee(bytes memory _data) external view returns (uint256);
+        function getOperatorFee(uint256 _gasUsed) external view returns (uint256);
     }
 }
 
@@ -20,6 +20,7 @@ use rundler_types::authorization::Eip7702Auth;
 
 pub(crate) mod v0_6;
 pub(crate) mod v0_7;
+pub(crate) mod v0_8;
 
 fn max_bundle_transaction_data(
     to_address: Address,
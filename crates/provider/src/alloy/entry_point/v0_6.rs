@@ -524,6 +524,40 @@ where
         }
     }
 
+    fn get_simulate_handle_op_transaction(
+        &self,
+        op: Self::UO,
+        target: Address,
+        target_call_data: Bytes,
+        mut state_override: StateOverride,
+    ) -> (TransactionRequest, StateOverride) {
+        let da_gas: u64 = op
+            .pre_verification_da_gas_limit(&self.chain_spec, Some(1))
+            .try_into()
+            .unwrap_or(u64::MAX);
+
+        if let Some(authorization) = op.authorization_tuple() {
+            authorization_utils::apply_7702_overrides(
+                &mut state_override,
+                op.sender(),
+                authorization.address,
+            );
+        }
+
+        let call = self
+            .i_entry_point
+            .simulateHandleOp(op.into(), target, target_call_data)
+            .gas(self.max_simulate_handle_op_gas.saturating_add(da_gas))
+            .into_transaction_request();
+        (call.inner, state_override)
+    }
+
+    fn decode_simulate_handle_op_output(_output: &Bytes) -> ProviderResult<ExecutionResult> {
+        Err(anyhow::anyhow!(
+            "simulateHandleOp succeeded, but should always revert"
+        ))?
+    }
+
     fn decode_simulate_handle_ops_revert(
         payload: &Bytes,
     ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>> {
@@ -714,8 +748,9 @@ impl From<DepositInfoV0_6> for DepositInfo {
 
 #[cfg(test)]
 mod tests {
-    use alloy_provider::RootProvider;
+    use alloy_provider::{Provider, ProviderBuilder, RootProvider};
     use alloy_transport::BoxTransport;
+    use rundler_types::v0_6::UserOperationRequiredFields;
 
     use super::*;
     use crate::ZeroDAGasOracle;
@@ -729,4 +764,66 @@ mod tests {
         >::decode_handle_ops_revert("return data out of bounds", &None);
         assert_eq!(result, Some(HandleOpsOut::PostOpRevert));
     }
+
+    #[test]
+    fn test_handle_aggregated_ops_round_trip() {
+        let chain_spec = ChainSpec::default();
+        let provider = ProviderBuilder::new()
+            .network::<AnyNetwork>()
+            .on_http("http://localhost:8545".parse().unwrap())
+            .boxed();
+        let entry_point = IEntryPoint::new(chain_spec.entry_point_address_v0_6, provider);
+
+        let aggregator = Address::repeat_byte(0xaa);
+        let op = |nonce: u64, aggregator: Option<Address>| {
+            let builder = UserOperationBuilder::new(
+                &chain_spec,
+                UserOperationRequiredFields {
+                    sender: Address::repeat_byte(1),
+                    nonce: U256::from(nonce),
+                    init_code: Bytes::new(),
+                    call_data: Bytes::new(),
+                    call_gas_limit: 0,
+                    verification_gas_limit: 0,
+                    pre_verification_gas: 0,
+                    max_fee_per_gas: 0,
+                    max_priority_fee_per_gas: 0,
+                    paymaster_and_data: Bytes::new(),
+                    signature: Bytes::new(),
+                },
+            );
+            match aggregator {
+                Some(aggregator) => builder.aggregator(aggregator).build(),
+                None => builder.build(),
+            }
+        };
+        let ops_per_aggregator = vec![
+            UserOpsPerAggregator {
+                user_ops: vec![op(0, None)],
+                aggregator: Address::ZERO,
+                signature: Bytes::new(),
+            },
+            UserOpsPerAggregator {
+                user_ops: vec![op(1, Some(aggregator)), op(2, Some(aggregator))],
+                aggregator,
+                signature: Bytes::from_static(&[1, 2, 3]),
+            },
+        ];
+
+        let tx = get_handle_ops_call(
+            &entry_point,
+            ops_per_aggregator.clone(),
+            Address::repeat_byte(2),
+            1_000_000,
+            GasFees::default(),
+            None,
+            chain_spec.id,
+        );
+        let calldata = tx.input.input().unwrap();
+        assert!(calldata.starts_with(&IEntryPoint::handleAggregatedOpsCall::SELECTOR));
+        assert_eq!(
+            decode_ops_from_calldata(&chain_spec, calldata),
+            ops_per_aggregator
+        );
+    }
 }
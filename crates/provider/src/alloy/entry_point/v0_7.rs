@@ -36,16 +36,16 @@ use rundler_contracts::v0_7::{
     IEntryPointSimulations::{
         self, ExecutionResult as ExecutionResultV0_7, IEntryPointSimulationsInstance,
     },
-    UserOpsPerAggregator as UserOpsPerAggregatorV0_7, ValidationResult as ValidationResultV0_7,
-    ENTRY_POINT_SIMULATIONS_V0_7_DEPLOYED_BYTECODE,
+    PackedUserOperation, UserOpsPerAggregator as UserOpsPerAggregatorV0_7,
+    ValidationResult as ValidationResultV0_7, ENTRY_POINT_SIMULATIONS_V0_7_DEPLOYED_BYTECODE,
 };
 use rundler_types::{
     authorization::Eip7702Auth,
     chain::ChainSpec,
     da::{DAGasBlockData, DAGasData},
     v0_7::{UserOperation, UserOperationBuilder, UserOperationRequiredFields},
-    EntryPointVersion, GasFees, UserOperation as _, UserOpsPerAggregator, ValidationOutput,
-    ValidationRevert,
+    EntryPointVersion, GasFees, UserOperation as UserOperationTrait, UserOpsPerAggregator,
+    ValidationOutput, ValidationRevert,
 };
 use rundler_utils::authorization_utils;
 use tracing::instrument;
@@ -66,6 +66,8 @@ pub struct EntryPointProvider<AP, T, D> {
     max_simulate_handle_ops_gas: u64,
     max_aggregation_gas: u64,
     chain_spec: ChainSpec,
+    version: EntryPointVersion,
+    simulations_bytecode: Bytes,
 }
 
 impl<AP, T, D> EntryPointProvider<AP, T, D>
@@ -92,8 +94,23 @@ where
             max_simulate_handle_ops_gas,
             max_aggregation_gas,
             chain_spec,
+            version: EntryPointVersion::V0_7,
+            simulations_bytecode: ENTRY_POINT_SIMULATIONS_V0_7_DEPLOYED_BYTECODE.clone(),
         }
     }
+
+    /// Retarget this provider at a later entry point version that shares the v0.7 ABI
+    pub(super) fn with_entry_point(
+        mut self,
+        address: Address,
+        version: EntryPointVersion,
+        simulations_bytecode: Bytes,
+    ) -> Self {
+        self.i_entry_point.set_address(address);
+        self.version = version;
+        self.simulations_bytecode = simulations_bytecode;
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -104,7 +121,7 @@ where
     D: Send + Sync,
 {
     fn version(&self) -> EntryPointVersion {
-        EntryPointVersion::V0_7
+        self.version
     }
 
     fn address(&self) -> &Address {
@@ -285,7 +302,7 @@ where
                 UserOperationBuilder::new(
                     &self.chain_spec,
                     UserOperationRequiredFields {
-                        sender: self.chain_spec.entry_point_address_v0_7,
+                        sender: *self.i_entry_point.address(),
                         nonce: U256::ZERO,
                         call_data: Bytes::new(),
                         call_gas_limit: 0,
@@ -296,7 +313,7 @@ where
                         signature: Bytes::new(),
                     },
                 )
-                .factory(self.chain_spec.entry_point_address_v0_7, Bytes::new())
+                .factory(*self.i_entry_point.address(), Bytes::new())
                 .build(),
             );
         }
@@ -478,7 +495,7 @@ where
             .unwrap_or(u64::MAX);
 
         let mut override_ep = StateOverride::default();
-        add_simulations_override(&mut override_ep, addr);
+        add_simulations_override(&mut override_ep, addr, &self.simulations_bytecode);
 
         add_authorization_tuple(
             user_op.sender(),
@@ -528,7 +545,11 @@ where
         op: Self::UO,
         mut state_override: StateOverride,
     ) -> EvmCall {
-        add_simulations_override(&mut state_override, *self.i_entry_point.address());
+        add_simulations_override(
+            &mut state_override,
+            *self.i_entry_point.address(),
+            &self.simulations_bytecode,
+        );
 
         let data = IEntryPointSimulations::simulateHandleOpCall {
             op: op.pack(),
@@ -560,7 +581,11 @@ where
             .try_into()
             .unwrap_or(u64::MAX);
 
-        add_simulations_override(&mut state_override, *self.i_entry_point.address());
+        add_simulations_override(
+            &mut state_override,
+            *self.i_entry_point.address(),
+            &self.simulations_bytecode,
+        );
 
         add_authorization_tuple(op.sender(), op.authorization_tuple(), &mut state_override);
 
@@ -585,6 +610,37 @@ where
         }
     }
 
+    fn get_simulate_handle_op_transaction(
+        &self,
+        op: Self::UO,
+        target: Address,
+        target_call_data: Bytes,
+        mut state_override: StateOverride,
+    ) -> (TransactionRequest, StateOverride) {
+        let addr = *self.i_entry_point.address();
+        let da_gas: u64 = op
+            .pre_verification_da_gas_limit(&self.chain_spec, Some(1))
+            .try_into()
+            .unwrap_or(u64::MAX);
+
+        add_simulations_override(&mut state_override, addr, &self.simulations_bytecode);
+        add_authorization_tuple(op.sender(), op.authorization_tuple(), &mut state_override);
+
+        let ep_simulations =
+            IEntryPointSimulationsInstance::new(addr, self.i_entry_point.provider());
+        let call = ep_simulations
+            .simulateHandleOp(op.pack(), target, target_call_data)
+            .gas(self.max_simulate_handle_ops_gas.saturating_add(da_gas))
+            .into_transaction_request();
+        (call.inner, state_override)
+    }
+
+    fn decode_simulate_handle_op_output(output: &Bytes) -> ProviderResult<ExecutionResult> {
+        let ret = IEntryPointSimulations::simulateHandleOpCall::abi_decode_returns(output, false)
+            .context("failed to decode simulateHandleOp output")?;
+        Ok(ret._0.try_into()?)
+    }
+
     fn decode_simulate_handle_ops_revert(
         revert_data: &Bytes,
     ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>> {
@@ -606,7 +662,11 @@ where
 {
 }
 
-fn add_simulations_override(state_override: &mut StateOverride, addr: Address) {
+fn add_simulations_override(
+    state_override: &mut StateOverride,
+    addr: Address,
+    simulations_bytecode: &Bytes,
+) {
     // Do nothing if the caller has already overridden the entry point code.
     // We'll trust they know what they're doing and not replace their code.
     // This is needed for call gas estimation, where the entry point is
@@ -614,7 +674,7 @@ fn add_simulations_override(state_override: &mut StateOverride, addr: Address) {
     state_override
         .entry(addr)
         .or_insert_with(|| AccountOverride {
-            code: Some(ENTRY_POINT_SIMULATIONS_V0_7_DEPLOYED_BYTECODE.clone()),
+            code: Some(simulations_bytecode.clone()),
             ..Default::default()
         });
 }
@@ -710,6 +770,19 @@ pub fn decode_ops_from_calldata(
     chain_spec: &ChainSpec,
     calldata: &Bytes,
 ) -> Vec<UserOpsPerAggregator<UserOperation>> {
+    decode_packed_ops_from_calldata(calldata, |op| {
+        UserOperationBuilder::from_packed(op, chain_spec)
+            .ok()
+            .map(|uo| uo.build())
+    })
+}
+
+/// Decode user ops from calldata of an entry point that shares the v0.7 ABI,
+/// using `from_packed` to convert each packed user operation
+pub(super) fn decode_packed_ops_from_calldata<UO: UserOperationTrait>(
+    calldata: &Bytes,
+    from_packed: impl Fn(PackedUserOperation) -> Option<UO>,
+) -> Vec<UserOpsPerAggregator<UO>> {
     let entry_point_calls = match IEntryPointCalls::abi_decode(calldata, false) {
         Ok(entry_point_calls) => entry_point_calls,
         Err(_) => return vec![],
@@ -720,11 +793,7 @@ pub fn decode_ops_from_calldata(
             let ops = handle_ops_call
                 .ops
                 .into_iter()
-                .filter_map(|op| {
-                    UserOperationBuilder::from_packed(op, chain_spec)
-                        .ok()
-                        .map(|uo| uo.build())
-                })
+                .filter_map(&from_packed)
                 .collect();
 
             vec![UserOpsPerAggregator {
@@ -738,15 +807,7 @@ pub fn decode_ops_from_calldata(
                 .opsPerAggregator
                 .into_iter()
                 .map(|ops| UserOpsPerAggregator {
-                    user_ops: ops
-                        .userOps
-                        .into_iter()
-                        .filter_map(|op| {
-                            UserOperationBuilder::from_packed(op, chain_spec)
-                                .ok()
-                                .map(|uo| uo.build())
-                        })
-                        .collect(),
+                    user_ops: ops.userOps.into_iter().filter_map(&from_packed).collect(),
                     aggregator: ops.aggregator,
                     signature: ops.signature,
                 })
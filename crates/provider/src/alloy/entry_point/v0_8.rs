@@ -0,0 +1,340 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+//! The v0.8 entry point shares its ABI with v0.7, so this provider delegates to
+//! the v0.7 provider pointed at the v0.8 address with the v0.8 simulations code.
+
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_rpc_types_eth::{state::StateOverride, BlockId};
+use alloy_transport::Transport;
+use rundler_contracts::v0_8::ENTRY_POINT_SIMULATIONS_V0_8_DEPLOYED_BYTECODE;
+use rundler_types::{
+    chain::ChainSpec,
+    da::{DAGasBlockData, DAGasData},
+    v0_8::{UserOperation, UserOperationBuilder},
+    EntryPointVersion, GasFees, UserOpsPerAggregator, ValidationOutput, ValidationRevert,
+};
+
+use super::v0_7;
+use crate::{
+    AggregatorOut, AlloyProvider, BlockHashOrNumber, BundleHandler, DAGasOracle, DAGasProvider,
+    DepositInfo, EntryPoint, EntryPointProvider as EntryPointProviderTrait, EvmCall,
+    ExecutionResult, HandleOpsOut, ProviderResult, SignatureAggregator, SimulationProvider,
+    TransactionRequest,
+};
+
+/// Entry point provider for v0.8
+#[derive(Clone)]
+pub struct EntryPointProvider<AP, T, D> {
+    inner: v0_7::EntryPointProvider<AP, T, D>,
+}
+
+impl<AP, T, D> EntryPointProvider<AP, T, D>
+where
+    T: Transport + Clone,
+    AP: AlloyProvider<T> + Clone,
+{
+    /// Create a new `EntryPoint` instance for v0.8
+    pub fn new(
+        chain_spec: ChainSpec,
+        max_verification_gas: u64,
+        max_simulate_handle_ops_gas: u64,
+        max_aggregation_gas: u64,
+        provider: AP,
+        da_gas_oracle: D,
+    ) -> Self {
+        let address = chain_spec.entry_point_address_v0_8;
+        let inner = v0_7::EntryPointProvider::new(
+            chain_spec,
+            max_verification_gas,
+            max_simulate_handle_ops_gas,
+            max_aggregation_gas,
+            provider,
+            da_gas_oracle,
+        )
+        .with_entry_point(
+            address,
+            EntryPointVersion::V0_8,
+            ENTRY_POINT_SIMULATIONS_V0_8_DEPLOYED_BYTECODE.clone(),
+        );
+
+        Self { inner }
+    }
+}
+
+#[async_trait::async_trait]
+impl<AP, T, D> EntryPoint for EntryPointProvider<AP, T, D>
+where
+    T: Transport + Clone,
+    AP: AlloyProvider<T>,
+    D: Send + Sync,
+{
+    fn version(&self) -> EntryPointVersion {
+        EntryPointVersion::V0_8
+    }
+
+    fn address(&self) -> &Address {
+        self.inner.address()
+    }
+
+    async fn balance_of(
+        &self,
+        address: Address,
+        block_id: Option<BlockId>,
+    ) -> ProviderResult<U256> {
+        self.inner.balance_of(address, block_id).await
+    }
+
+    async fn get_deposit_info(&self, address: Address) -> ProviderResult<DepositInfo> {
+        self.inner.get_deposit_info(address).await
+    }
+
+    async fn get_balances(&self, addresses: Vec<Address>) -> ProviderResult<Vec<U256>> {
+        self.inner.get_balances(addresses).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<AP, T, D> SignatureAggregator for EntryPointProvider<AP, T, D>
+where
+    T: Transport + Clone,
+    AP: AlloyProvider<T>,
+    D: Send + Sync,
+{
+    type UO = UserOperation;
+
+    async fn aggregate_signatures(
+        &self,
+        aggregator_address: Address,
+        ops: Vec<Self::UO>,
+    ) -> ProviderResult<Option<Bytes>> {
+        self.inner
+            .aggregate_signatures(
+                aggregator_address,
+                ops.into_iter().map(UserOperation::into_v0_7).collect(),
+            )
+            .await
+    }
+
+    async fn validate_user_op_signature(
+        &self,
+        aggregator_address: Address,
+        user_op: Self::UO,
+    ) -> ProviderResult<AggregatorOut> {
+        self.inner
+            .validate_user_op_signature(aggregator_address, user_op.into_v0_7())
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<AP, T, D> BundleHandler for EntryPointProvider<AP, T, D>
+where
+    T: Transport + Clone,
+    AP: AlloyProvider<T>,
+    D: Send + Sync,
+{
+    type UO = UserOperation;
+
+    async fn call_handle_ops(
+        &self,
+        ops_per_aggregator: Vec<UserOpsPerAggregator<UserOperation>>,
+        sender_eoa: Address,
+        gas_limit: u64,
+        gas_fees: GasFees,
+        proxy: Option<Address>,
+        validation_only: bool,
+    ) -> ProviderResult<HandleOpsOut> {
+        self.inner
+            .call_handle_ops(
+                into_v0_7_ops_per_aggregator(ops_per_aggregator),
+                sender_eoa,
+                gas_limit,
+                gas_fees,
+                proxy,
+                validation_only,
+            )
+            .await
+    }
+
+    fn get_send_bundle_transaction(
+        &self,
+        ops_per_aggregator: Vec<UserOpsPerAggregator<UserOperation>>,
+        sender_eoa: Address,
+        gas_limit: u64,
+        gas_fees: GasFees,
+        proxy: Option<Address>,
+    ) -> TransactionRequest {
+        self.inner.get_send_bundle_transaction(
+            into_v0_7_ops_per_aggregator(ops_per_aggregator),
+            sender_eoa,
+            gas_limit,
+            gas_fees,
+            proxy,
+        )
+    }
+
+    fn decode_handle_ops_revert(
+        message: &str,
+        revert_data: &Option<Bytes>,
+    ) -> Option<HandleOpsOut> {
+        v0_7::EntryPointProvider::<AP, T, D>::decode_handle_ops_revert(message, revert_data)
+    }
+
+    fn decode_ops_from_calldata(
+        chain_spec: &ChainSpec,
+        calldata: &Bytes,
+    ) -> Vec<UserOpsPerAggregator<UserOperation>> {
+        decode_ops_from_calldata(chain_spec, calldata)
+    }
+}
+
+#[async_trait::async_trait]
+impl<AP, T, D> DAGasProvider for EntryPointProvider<AP, T, D>
+where
+    T: Transport + Clone,
+    AP: AlloyProvider<T>,
+    D: DAGasOracle,
+{
+    type UO = UserOperation;
+
+    async fn calc_da_gas(
+        &self,
+        user_op: UserOperation,
+        block: BlockHashOrNumber,
+        gas_price: u128,
+        bundle_size: usize,
+    ) -> ProviderResult<(u128, DAGasData, DAGasBlockData)> {
+        self.inner
+            .calc_da_gas(user_op.into_v0_7(), block, gas_price, bundle_size)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<AP, T, D> SimulationProvider for EntryPointProvider<AP, T, D>
+where
+    T: Transport + Clone,
+    AP: AlloyProvider<T>,
+    D: Send + Sync,
+{
+    type UO = UserOperation;
+
+    fn get_tracer_simulate_validation_call(
+        &self,
+        user_op: Self::UO,
+    ) -> ProviderResult<(TransactionRequest, StateOverride)> {
+        self.inner
+            .get_tracer_simulate_validation_call(user_op.into_v0_7())
+    }
+
+    async fn simulate_validation(
+        &self,
+        user_op: Self::UO,
+        block_id: Option<BlockId>,
+    ) -> ProviderResult<Result<ValidationOutput, ValidationRevert>> {
+        self.inner
+            .simulate_validation(user_op.into_v0_7(), block_id)
+            .await
+    }
+
+    fn get_simulate_handle_op_call(&self, op: Self::UO, state_override: StateOverride) -> EvmCall {
+        self.inner
+            .get_simulate_handle_op_call(op.into_v0_7(), state_override)
+    }
+
+    async fn simulate_handle_op(
+        &self,
+        op: Self::UO,
+        target: Address,
+        target_call_data: Bytes,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>> {
+        self.inner
+            .simulate_handle_op(
+                op.into_v0_7(),
+                target,
+                target_call_data,
+                block_id,
+                state_override,
+            )
+            .await
+    }
+
+    fn get_simulate_handle_op_transaction(
+        &self,
+        op: Self::UO,
+        target: Address,
+        target_call_data: Bytes,
+        state_override: StateOverride,
+    ) -> (TransactionRequest, StateOverride) {
+        self.inner.get_simulate_handle_op_transaction(
+            op.into_v0_7(),
+            target,
+            target_call_data,
+            state_override,
+        )
+    }
+
+    fn decode_simulate_handle_op_output(output: &Bytes) -> ProviderResult<ExecutionResult> {
+        v0_7::EntryPointProvider::<AP, T, D>::decode_simulate_handle_op_output(output)
+    }
+
+    fn decode_simulate_handle_ops_revert(
+        revert_data: &Bytes,
+    ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>> {
+        v0_7::EntryPointProvider::<AP, T, D>::decode_simulate_handle_ops_revert(revert_data)
+    }
+
+    fn simulation_should_revert(&self) -> bool {
+        self.inner.simulation_should_revert()
+    }
+}
+
+impl<AP, T, D> EntryPointProviderTrait<UserOperation> for EntryPointProvider<AP, T, D>
+where
+    T: Transport + Clone,
+    AP: AlloyProvider<T>,
+    D: DAGasOracle,
+{
+}
+
+fn into_v0_7_ops_per_aggregator(
+    ops_per_aggregator: Vec<UserOpsPerAggregator<UserOperation>>,
+) -> Vec<UserOpsPerAggregator<rundler_types::v0_7::UserOperation>> {
+    ops_per_aggregator
+        .into_iter()
+        .map(|uoa| UserOpsPerAggregator {
+            user_ops: uoa
+                .user_ops
+                .into_iter()
+                .map(UserOperation::into_v0_7)
+                .collect(),
+            aggregator: uoa.aggregator,
+            signature: uoa.signature,
+        })
+        .collect()
+}
+
+/// Decode user ops from calldata
+pub fn decode_ops_from_calldata(
+    chain_spec: &ChainSpec,
+    calldata: &Bytes,
+) -> Vec<UserOpsPerAggregator<UserOperation>> {
+    v0_7::decode_packed_ops_from_calldata(calldata, |op| {
+        UserOperationBuilder::from_packed(op, chain_spec)
+            .ok()
+            .map(|uo| uo.build())
+    })
+}
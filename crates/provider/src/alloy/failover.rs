@@ -0,0 +1,761 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use alloy_json_rpc::{RequestPacket, ResponsePacket, ResponsePayload};
+use alloy_transport::{BoxFuture, TransportError, TransportErrorKind};
+use futures_util::{stream::FuturesUnordered, FutureExt, StreamExt};
+use metrics::{Counter, Gauge};
+use metrics_derive::Metrics;
+use tower::{Service, ServiceExt};
+
+// Consecutive failures after which an upstream is considered unhealthy
+const UNHEALTHY_FAILURE_THRESHOLD: u32 = 3;
+// Time an unhealthy upstream is deprioritized before being tried first again
+const UNHEALTHY_COOLDOWN: Duration = Duration::from_secs(10);
+
+const METHOD_NOT_FOUND_CODE: i64 = -32601;
+const LIMIT_EXCEEDED_CODE: i64 = -32005;
+
+/// Configuration of the upstream nodes backing a provider.
+///
+/// Every method is first routed to the nodes preferred for its kind and then
+/// fails over to the primary nodes, in the order given. Nodes that fail
+/// repeatedly are moved to the back of the order until they recover.
+#[derive(Clone, Debug, Default)]
+pub struct NodeConfig {
+    /// Primary node URLs, used for every method
+    pub urls: Vec<String>,
+    /// Node URLs preferred for tracing methods (`debug_*` and `trace_*`)
+    pub trace_urls: Vec<String>,
+    /// Node URLs preferred for transaction submission methods
+    pub submit_urls: Vec<String>,
+    /// Node URLs preferred for read-only methods
+    pub read_urls: Vec<String>,
+    /// Number of nodes that must agree on a block fetched by hash or number.
+    ///
+    /// Values of 0 or 1 disable quorum reads.
+    pub block_quorum: usize,
+    /// Client side timeout for each request to a node
+    pub client_timeout_seconds: u64,
+}
+
+impl NodeConfig {
+    /// Create a config from a comma separated list of primary node URLs
+    pub fn new(rpc_urls: &str, client_timeout_seconds: u64) -> Self {
+        Self {
+            urls: split_urls(rpc_urls),
+            client_timeout_seconds,
+            ..Default::default()
+        }
+    }
+}
+
+/// Split a comma separated list of URLs
+pub fn split_urls(urls: &str) -> Vec<String> {
+    urls.split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MethodKind {
+    Trace,
+    Submit,
+    Read,
+    Other,
+}
+
+impl MethodKind {
+    fn from_request(request: &RequestPacket) -> Self {
+        let RequestPacket::Single(request) = request else {
+            return Self::Other;
+        };
+
+        match request.method() {
+            m if m.starts_with("debug_") || m.starts_with("trace_") => Self::Trace,
+            "eth_sendRawTransaction"
+            | "eth_sendRawTransactionConditional"
+            | "eth_sendBundle"
+            | "eth_sendPrivateTransaction" => Self::Submit,
+            "eth_blockNumber"
+            | "eth_call"
+            | "eth_chainId"
+            | "eth_estimateGas"
+            | "eth_feeHistory"
+            | "eth_gasPrice"
+            | "eth_getBalance"
+            | "eth_getBlockByHash"
+            | "eth_getBlockByNumber"
+            | "eth_getCode"
+            | "eth_getLogs"
+            | "eth_getStorageAt"
+            | "eth_getTransactionByHash"
+            | "eth_getTransactionCount"
+            | "eth_getTransactionReceipt"
+            | "eth_maxPriorityFeePerGas" => Self::Read,
+            _ => Self::Other,
+        }
+    }
+}
+
+// Block reads by hash or explicit number have a single correct answer, and so
+// can be checked for agreement across nodes. Tagged reads (e.g. `latest`) can't.
+fn is_quorum_request(request: &RequestPacket) -> bool {
+    let RequestPacket::Single(request) = request else {
+        return false;
+    };
+
+    match request.method() {
+        "eth_getBlockByHash" => true,
+        "eth_getBlockByNumber" => request
+            .params()
+            .and_then(|params| serde_json::from_str::<Vec<serde_json::Value>>(params.get()).ok())
+            .and_then(|params| params.first()?.as_str().map(|b| b.starts_with("0x")))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+// The value nodes vote on for a quorum read: the block hash, or null if the
+// block wasn't found. Error responses don't vote.
+fn quorum_key(response: &ResponsePacket) -> Option<String> {
+    let ResponsePacket::Single(response) = response else {
+        return None;
+    };
+    let ResponsePayload::Success(result) = &response.payload else {
+        return None;
+    };
+
+    let value = serde_json::from_str::<serde_json::Value>(result.get()).ok()?;
+    match value.get("hash") {
+        Some(hash) => Some(hash.to_string()),
+        None => Some(value.to_string()),
+    }
+}
+
+fn error_code(response: &ResponsePacket) -> Option<i64> {
+    match response {
+        ResponsePacket::Single(response) => match &response.payload {
+            ResponsePayload::Failure(error) => Some(error.code),
+            ResponsePayload::Success(_) => None,
+        },
+        ResponsePacket::Batch(_) => None,
+    }
+}
+
+#[derive(Metrics)]
+#[metrics(scope = "provider_upstream")]
+struct UpstreamMetrics {
+    #[metric(describe = "the number of failed requests to the upstream node.")]
+    failures: Counter,
+    #[metric(describe = "whether the upstream node is considered healthy.")]
+    healthy: Gauge,
+}
+
+#[derive(Default)]
+struct Health {
+    consecutive_failures: u32,
+    unhealthy_until: Option<Instant>,
+}
+
+struct Upstream<S> {
+    name: String,
+    service: S,
+    health: Mutex<Health>,
+    metrics: UpstreamMetrics,
+}
+
+impl<S> Upstream<S> {
+    fn new(name: String, service: S) -> Self {
+        let metrics = UpstreamMetrics::new_with_labels(&[("upstream", name.clone())]);
+        metrics.healthy.set(1);
+        Self {
+            name,
+            service,
+            health: Mutex::new(Health::default()),
+            metrics,
+        }
+    }
+
+    fn is_healthy(&self, now: Instant) -> bool {
+        let health = self.health.lock().unwrap();
+        health.unhealthy_until.is_none_or(|until| until <= now)
+    }
+
+    fn record_success(&self) {
+        let mut health = self.health.lock().unwrap();
+        if health.unhealthy_until.take().is_some() {
+            tracing::info!("upstream node {} recovered", self.name);
+        }
+        health.consecutive_failures = 0;
+        self.metrics.healthy.set(1);
+    }
+
+    fn record_failure(&self) {
+        self.metrics.failures.increment(1);
+        let mut health = self.health.lock().unwrap();
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= UNHEALTHY_FAILURE_THRESHOLD {
+            if health.unhealthy_until.is_none() {
+                tracing::warn!(
+                    "upstream node {} marked unhealthy after {} consecutive failures",
+                    self.name,
+                    health.consecutive_failures
+                );
+            }
+            health.unhealthy_until = Some(Instant::now() + UNHEALTHY_COOLDOWN);
+            self.metrics.healthy.set(0);
+        }
+    }
+}
+
+struct Inner<S> {
+    upstreams: Vec<Upstream<S>>,
+    primary: Vec<usize>,
+    trace: Vec<usize>,
+    submit: Vec<usize>,
+    read: Vec<usize>,
+    block_quorum: usize,
+}
+
+impl<S> Inner<S> {
+    // Upstreams to try for a method, in order. Healthy upstreams come first,
+    // unhealthy ones are kept as a last resort.
+    fn candidates(&self, kind: MethodKind) -> Vec<usize> {
+        let preferred: &[usize] = match kind {
+            MethodKind::Trace => &self.trace,
+            MethodKind::Submit => &self.submit,
+            MethodKind::Read => &self.read,
+            MethodKind::Other => &[],
+        };
+
+        let mut ordered = Vec::with_capacity(self.upstreams.len());
+        for &idx in preferred.iter().chain(self.primary.iter()) {
+            if !ordered.contains(&idx) {
+                ordered.push(idx);
+            }
+        }
+
+        let now = Instant::now();
+        let (mut healthy, unhealthy): (Vec<_>, Vec<_>) = ordered
+            .into_iter()
+            .partition(|&idx| self.upstreams[idx].is_healthy(now));
+        healthy.extend(unhealthy);
+        healthy
+    }
+}
+
+/// Transport that spreads requests over a set of upstream node transports,
+/// with per-method routing, health-tracked failover and quorum block reads.
+pub struct FailoverTransport<S> {
+    inner: Arc<Inner<S>>,
+}
+
+impl<S> Clone for FailoverTransport<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S> FailoverTransport<S> {
+    /// Create a new failover transport, using `make_service` to build the
+    /// transport for each distinct node URL in `config`.
+    pub(crate) fn new(
+        config: &NodeConfig,
+        mut make_service: impl FnMut(&str) -> anyhow::Result<S>,
+    ) -> anyhow::Result<Self> {
+        if config.urls.is_empty() {
+            anyhow::bail!("at least one node url must be provided");
+        }
+
+        let mut upstreams = vec![];
+        let mut urls: Vec<&str> = vec![];
+        let mut index = |url: &str| -> anyhow::Result<usize> {
+            if let Some(idx) = urls.iter().position(|u| *u == url) {
+                return Ok(idx);
+            }
+            upstreams.push(Upstream::new(
+                upstream_name(urls.len(), url),
+                make_service(url)?,
+            ));
+            urls.push(url);
+            Ok(urls.len() - 1)
+        };
+
+        let mut indexes = |urls: &[String]| -> anyhow::Result<Vec<usize>> {
+            urls.iter().map(|url| index(url)).collect()
+        };
+        let primary = indexes(&config.urls)?;
+        let trace = indexes(&config.trace_urls)?;
+        let submit = indexes(&config.submit_urls)?;
+        let read = indexes(&config.read_urls)?;
+
+        let num_read_nodes = read
+            .iter()
+            .chain(primary.iter())
+            .fold(vec![], |mut acc, idx| {
+                if !acc.contains(idx) {
+                    acc.push(*idx);
+                }
+                acc
+            });
+        if config.block_quorum > num_read_nodes.len() {
+            anyhow::bail!(
+                "block quorum {} is larger than the number of read nodes {}",
+                config.block_quorum,
+                num_read_nodes.len()
+            );
+        }
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                upstreams,
+                primary,
+                trace,
+                submit,
+                read,
+                block_quorum: config.block_quorum,
+            }),
+        })
+    }
+}
+
+// Name used for an upstream in logs and metrics, its index with the host and
+// port of its URL. The path is left out as node URLs commonly embed API keys.
+fn upstream_name(index: usize, url: &str) -> String {
+    let host = url::Url::parse(url).ok().and_then(|url| {
+        let host = url.host_str()?.to_string();
+        Some(match url.port_or_known_default() {
+            Some(port) => format!("{host}:{port}"),
+            None => host,
+        })
+    });
+    format!("{index}:{}", host.as_deref().unwrap_or("unknown"))
+}
+
+impl<S> Service<RequestPacket> for FailoverTransport<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError>
+        + Sync
+        + Send
+        + Clone
+        + 'static,
+    S::Future: Send,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // upstream readiness is checked per request
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let inner = self.inner.clone();
+        let kind = MethodKind::from_request(&request);
+        let candidates = inner.candidates(kind);
+
+        if inner.block_quorum > 1 && is_quorum_request(&request) {
+            send_with_quorum(inner, candidates, request).boxed()
+        } else {
+            send_with_failover(inner, candidates, request).boxed()
+        }
+    }
+}
+
+async fn send_with_failover<S>(
+    inner: Arc<Inner<S>>,
+    candidates: Vec<usize>,
+    request: RequestPacket,
+) -> Result<ResponsePacket, TransportError>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError> + Clone,
+{
+    let mut last = None;
+    for idx in candidates {
+        let upstream = &inner.upstreams[idx];
+        match upstream.service.clone().oneshot(request.clone()).await {
+            Ok(response) => match error_code(&response) {
+                // the node doesn't serve this method, try the next one
+                Some(METHOD_NOT_FOUND_CODE) => last = Some(Ok(response)),
+                Some(LIMIT_EXCEEDED_CODE) => {
+                    upstream.record_failure();
+                    last = Some(Ok(response));
+                }
+                _ => {
+                    upstream.record_success();
+                    return Ok(response);
+                }
+            },
+            Err(e) => {
+                tracing::warn!("request to upstream node {} failed: {e:?}", upstream.name);
+                upstream.record_failure();
+                last = Some(Err(e));
+            }
+        }
+    }
+
+    last.unwrap_or_else(|| {
+        Err(TransportErrorKind::custom_str(
+            "no upstream nodes available",
+        ))
+    })
+}
+
+async fn send_with_quorum<S>(
+    inner: Arc<Inner<S>>,
+    candidates: Vec<usize>,
+    request: RequestPacket,
+) -> Result<ResponsePacket, TransportError>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError> + Clone,
+{
+    let mut responses = candidates
+        .into_iter()
+        .map(|idx| {
+            let service = inner.upstreams[idx].service.clone();
+            let request = request.clone();
+            async move { (idx, service.oneshot(request).await) }
+        })
+        .collect::<FuturesUnordered<_>>();
+
+    let mut votes: HashMap<String, (usize, ResponsePacket)> = HashMap::new();
+    while let Some((idx, result)) = responses.next().await {
+        let upstream = &inner.upstreams[idx];
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::warn!("request to upstream node {} failed: {e:?}", upstream.name);
+                upstream.record_failure();
+                continue;
+            }
+        };
+        upstream.record_success();
+
+        let Some(key) = quorum_key(&response) else {
+            continue;
+        };
+        let (count, _) = votes.entry(key.clone()).or_insert((0, response));
+        *count += 1;
+        if *count >= inner.block_quorum {
+            return Ok(votes.remove(&key).unwrap().1);
+        }
+    }
+
+    tracing::warn!(
+        "block quorum of {} not reached, votes: {:?}",
+        inner.block_quorum,
+        votes
+            .iter()
+            .map(|(key, (count, _))| (key, count))
+            .collect::<Vec<_>>()
+    );
+    Err(TransportErrorKind::custom_str("block quorum not reached"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use alloy_json_rpc::{Id, Request, Response, SerializedRequest};
+    use serde_json::value::RawValue;
+
+    use super::*;
+
+    #[derive(Clone)]
+    enum Behavior {
+        Ok(&'static str),
+        RpcError(i64),
+        TransportError,
+    }
+
+    #[derive(Clone)]
+    struct MockNode {
+        behavior: Behavior,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl MockNode {
+        fn new(behavior: Behavior) -> Self {
+            Self {
+                behavior,
+                calls: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::Relaxed)
+        }
+    }
+
+    impl Service<RequestPacket> for MockNode {
+        type Response = ResponsePacket;
+        type Error = TransportError;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: RequestPacket) -> Self::Future {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            let payload = match self.behavior {
+                Behavior::Ok(result) => {
+                    ResponsePayload::Success(RawValue::from_string(result.to_string()).unwrap())
+                }
+                Behavior::RpcError(code) => {
+                    ResponsePayload::Failure(alloy_json_rpc::ErrorPayload {
+                        code,
+                        message: "error".into(),
+                        data: None,
+                    })
+                }
+                Behavior::TransportError => {
+                    return async { Err(TransportErrorKind::custom_str("connection refused")) }
+                        .boxed()
+                }
+            };
+            let response = ResponsePacket::Single(Response {
+                id: Id::Number(1),
+                payload,
+            });
+            async move { Ok(response) }.boxed()
+        }
+    }
+
+    fn request(method: &'static str, params: serde_json::Value) -> RequestPacket {
+        let request: SerializedRequest = Request::new(method, Id::Number(1), params)
+            .try_into()
+            .unwrap();
+        RequestPacket::Single(request)
+    }
+
+    fn new_transport(
+        config: NodeConfig,
+        nodes: &[(&str, MockNode)],
+    ) -> FailoverTransport<MockNode> {
+        FailoverTransport::new(&config, |url| {
+            Ok(nodes.iter().find(|(u, _)| *u == url).unwrap().1.clone())
+        })
+        .unwrap()
+    }
+
+    fn result(response: &ResponsePacket) -> String {
+        let ResponsePacket::Single(response) = response else {
+            panic!("expected single response");
+        };
+        match &response.payload {
+            ResponsePayload::Success(result) => result.get().to_string(),
+            ResponsePayload::Failure(e) => panic!("unexpected error {e:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failover_on_transport_error() {
+        let a = MockNode::new(Behavior::TransportError);
+        let b = MockNode::new(Behavior::Ok("\"0x1\""));
+        let mut transport = new_transport(
+            NodeConfig::new("http://a,http://b", 10),
+            &[("http://a", a.clone()), ("http://b", b.clone())],
+        );
+
+        for _ in 0..UNHEALTHY_FAILURE_THRESHOLD {
+            let response = transport
+                .call(request("eth_blockNumber", serde_json::json!([])))
+                .await
+                .unwrap();
+            assert_eq!(result(&response), "\"0x1\"");
+        }
+        assert_eq!(a.calls(), UNHEALTHY_FAILURE_THRESHOLD as usize);
+
+        // a is now unhealthy and is tried after b
+        transport
+            .call(request("eth_blockNumber", serde_json::json!([])))
+            .await
+            .unwrap();
+        assert_eq!(a.calls(), UNHEALTHY_FAILURE_THRESHOLD as usize);
+        assert_eq!(b.calls(), UNHEALTHY_FAILURE_THRESHOLD as usize + 1);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_errors_are_returned() {
+        let a = MockNode::new(Behavior::RpcError(-32000));
+        let b = MockNode::new(Behavior::Ok("\"0x1\""));
+        let mut transport = new_transport(
+            NodeConfig::new("http://a,http://b", 10),
+            &[("http://a", a.clone()), ("http://b", b.clone())],
+        );
+
+        let response = transport
+            .call(request("eth_call", serde_json::json!([])))
+            .await
+            .unwrap();
+        assert_eq!(error_code(&response), Some(-32000));
+        assert_eq!(b.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_method_routing() {
+        let primary = MockNode::new(Behavior::Ok("\"primary\""));
+        let trace = MockNode::new(Behavior::RpcError(METHOD_NOT_FOUND_CODE));
+        let submit = MockNode::new(Behavior::Ok("\"submit\""));
+        let read = MockNode::new(Behavior::Ok("\"read\""));
+        let mut transport = new_transport(
+            NodeConfig {
+                urls: vec!["http://primary".to_string()],
+                trace_urls: vec!["http://trace".to_string()],
+                submit_urls: vec!["http://submit".to_string()],
+                read_urls: vec!["http://read".to_string()],
+                ..Default::default()
+            },
+            &[
+                ("http://primary", primary.clone()),
+                ("http://trace", trace.clone()),
+                ("http://submit", submit.clone()),
+                ("http://read", read.clone()),
+            ],
+        );
+
+        let send = |method| request(method, serde_json::json!([]));
+        assert_eq!(
+            result(
+                &transport
+                    .call(send("eth_sendRawTransaction"))
+                    .await
+                    .unwrap()
+            ),
+            "\"submit\""
+        );
+        assert_eq!(
+            result(&transport.call(send("eth_getCode")).await.unwrap()),
+            "\"read\""
+        );
+        assert_eq!(
+            result(
+                &transport
+                    .call(send("eth_getUserOperationByHash"))
+                    .await
+                    .unwrap()
+            ),
+            "\"primary\""
+        );
+        // trace node doesn't support the method, falls back to primary
+        assert_eq!(
+            result(&transport.call(send("debug_traceCall")).await.unwrap()),
+            "\"primary\""
+        );
+        assert_eq!(trace.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_block_quorum() {
+        let a = MockNode::new(Behavior::Ok(r#"{"hash":"0x01","number":"0x1"}"#));
+        let b = MockNode::new(Behavior::Ok(r#"{"hash":"0x02","number":"0x1"}"#));
+        let c = MockNode::new(Behavior::Ok(r#"{"hash":"0x01","number":"0x1"}"#));
+        let nodes = [
+            ("http://a", a.clone()),
+            ("http://b", b.clone()),
+            ("http://c", c.clone()),
+        ];
+        let mut transport = new_transport(
+            NodeConfig {
+                block_quorum: 2,
+                ..NodeConfig::new("http://a,http://b,http://c", 10)
+            },
+            &nodes,
+        );
+
+        let response = transport
+            .call(request(
+                "eth_getBlockByNumber",
+                serde_json::json!(["0x1", false]),
+            ))
+            .await
+            .unwrap();
+        assert!(result(&response).contains("0x01"));
+        assert_eq!(b.calls(), 1);
+
+        // tagged reads are not checked for quorum
+        transport
+            .call(request(
+                "eth_getBlockByNumber",
+                serde_json::json!(["latest", false]),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(b.calls(), 1);
+        assert_eq!(a.calls(), 2);
+
+        let mut transport = new_transport(
+            NodeConfig {
+                block_quorum: 3,
+                ..NodeConfig::new("http://a,http://b,http://c", 10)
+            },
+            &nodes,
+        );
+        assert!(transport
+            .call(request(
+                "eth_getBlockByHash",
+                serde_json::json!(["0x01", false]),
+            ))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let node = MockNode::new(Behavior::Ok("null"));
+        assert!(FailoverTransport::new(&NodeConfig::default(), |_| Ok(node.clone())).is_err());
+        assert!(FailoverTransport::new(
+            &NodeConfig {
+                block_quorum: 2,
+                ..NodeConfig::new("http://a", 10)
+            },
+            |_| Ok(node.clone())
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_upstream_name() {
+        assert_eq!(
+            upstream_name(0, "https://node.example/v2/key"),
+            "0:node.example:443"
+        );
+        assert_eq!(
+            upstream_name(1, "http://node.example:8545/v2/other"),
+            "1:node.example:8545"
+        );
+        assert_eq!(upstream_name(2, "not a url"), "2:unknown");
+    }
+
+    #[test]
+    fn test_split_urls() {
+        assert_eq!(
+            split_urls("http://a, http://b,,"),
+            vec!["http://a".to_string(), "http://b".to_string()]
+        );
+    }
+}
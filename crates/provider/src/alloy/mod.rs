@@ -15,13 +15,15 @@ use std::time::Duration;
 
 use alloy_provider::{network::AnyNetwork, Provider as AlloyProvider, ProviderBuilder};
 use alloy_rpc_client::ClientBuilder;
-use alloy_transport::layers::RetryBackoffService;
+use alloy_transport::{layers::RetryBackoffService, utils::guess_local_url};
 use alloy_transport_http::Http;
 use anyhow::Context;
 use evm::AlloyEvmProvider;
+use failover::FailoverTransport;
 use metrics::{AlloyMetricLayer, AlloyMetricMiddleware};
 use provider_timeout::{ProviderTimeout, ProviderTimeoutLayer};
 use reqwest::Client;
+use tower::Layer;
 use url::Url;
 
 use crate::EvmProvider;
@@ -30,10 +32,12 @@ mod da;
 pub use da::new_alloy_da_gas_oracle;
 pub(crate) mod entry_point;
 pub(crate) mod evm;
+mod failover;
+pub use failover::{split_urls, NodeConfig};
 pub(crate) mod metrics;
 mod provider_timeout;
 
-/// Create a new alloy evm provider from a given RPC URL
+/// Create a new alloy evm provider from a given RPC URL, or comma separated list of URLs
 pub fn new_alloy_evm_provider(
     rpc_url: &str,
     provider_client_timeout_seconds: u64,
@@ -42,28 +46,47 @@ pub fn new_alloy_evm_provider(
     Ok(AlloyEvmProvider::new(provider))
 }
 
-/// Create a new alloy provider from a given RPC URL
+/// Create a new alloy provider from a given RPC URL, or comma separated list of URLs
 pub fn new_alloy_provider(
     rpc_url: &str,
     provider_client_timeout_seconds: u64,
 ) -> anyhow::Result<
     impl AlloyProvider<
-            RetryBackoffService<AlloyMetricMiddleware<ProviderTimeout<Http<Client>>>>,
+            RetryBackoffService<
+                AlloyMetricMiddleware<FailoverTransport<ProviderTimeout<Http<Client>>>>,
+            >,
             AnyNetwork,
         > + Clone,
 > {
-    let url = Url::parse(rpc_url).context("invalid rpc url")?;
+    new_alloy_provider_with_config(&NodeConfig::new(rpc_url, provider_client_timeout_seconds))
+}
+
+/// Create a new alloy provider backed by the upstream nodes in `config`
+pub fn new_alloy_provider_with_config(
+    config: &NodeConfig,
+) -> anyhow::Result<
+    impl AlloyProvider<
+            RetryBackoffService<
+                AlloyMetricMiddleware<FailoverTransport<ProviderTimeout<Http<Client>>>>,
+            >,
+            AnyNetwork,
+        > + Clone,
+> {
+    let timeout = Duration::from_secs(config.client_timeout_seconds);
+    let transport = FailoverTransport::new(config, |rpc_url| {
+        let url = Url::parse(rpc_url).context("invalid rpc url")?;
+        // add a timeout layer here.
+        Ok(ProviderTimeoutLayer::new(timeout).layer(Http::new(url)))
+    })?;
+
     let metric_layer = AlloyMetricLayer::default();
     // TODO: make this configurable: use a large number for CUPS for now
     let retry_layer = alloy_transport::layers::RetryBackoffLayer::new(10, 500, 1_000_000);
-    // add a timeout layer here.
-    let timeout_layer =
-        ProviderTimeoutLayer::new(Duration::from_secs(provider_client_timeout_seconds));
+    let is_local = config.urls.iter().all(guess_local_url);
     let client = ClientBuilder::default()
         .layer(retry_layer)
         .layer(metric_layer)
-        .layer(timeout_layer)
-        .http(url);
+        .transport(transport, is_local);
     let provider = ProviderBuilder::new()
         .network::<AnyNetwork>()
         .on_client(client);
@@ -33,9 +33,14 @@ pub use alloy::{
             decode_validation_revert as decode_v0_7_validation_revert,
             EntryPointProvider as AlloyEntryPointV0_7,
         },
+        v0_8::{
+            decode_ops_from_calldata as decode_v0_8_ops_from_calldata,
+            EntryPointProvider as AlloyEntryPointV0_8,
+        },
     },
     evm::AlloyEvmProvider,
     new_alloy_da_gas_oracle, new_alloy_evm_provider, new_alloy_provider,
+    new_alloy_provider_with_config, split_urls, NodeConfig,
 };
 mod fees;
 pub use alloy_provider::network::{AnyHeader, AnyNetwork, AnyReceiptEnvelope, AnyTxEnvelope};
@@ -55,14 +60,14 @@ pub use alloy_rpc_types_eth::{
 };
 pub use alloy_rpc_types_trace::geth::{
     CallConfig as GethDebugTracerCallConfig, CallFrame as GethDebugTracerCallFrame,
-    GethDebugBuiltInTracerType, GethDebugTracerType, GethDebugTracingCallOptions,
-    GethDebugTracingOptions, GethTrace,
+    GethDebugBuiltInTracerType, GethDebugTracerConfig, GethDebugTracerType,
+    GethDebugTracingCallOptions, GethDebugTracingOptions, GethTrace,
 };
 // re-export contract types
 pub use rundler_contracts::utils::GetGasUsed::GasUsedResult;
 use rundler_types::{
     v0_6::UserOperation as UserOperationV0_6, v0_7::UserOperation as UserOperationV0_7,
-    UserOperation, UserOperationVariant,
+    v0_8::UserOperation as UserOperationV0_8, UserOperation, UserOperationVariant,
 };
 #[cfg(any(test, feature = "test-utils"))]
 pub use traits::test_utils::*;
@@ -100,6 +105,9 @@ pub trait Providers: Send + Sync + Clone {
     /// The entry point provider for v0.7.
     type EntryPointV0_7: EntryPointProvider<UserOperationV0_7> + Clone;
 
+    /// The entry point provider for v0.8.
+    type EntryPointV0_8: EntryPointProvider<UserOperationV0_8> + Clone;
+
     /// The DA gas oracle provider.
     type DAGasOracle: DAGasOracle + Clone;
 
@@ -118,6 +126,9 @@ pub trait Providers: Send + Sync + Clone {
     /// Returns the entry point provider for v0.7.
     fn ep_v0_7(&self) -> &Option<Self::EntryPointV0_7>;
 
+    /// Returns the entry point provider for v0.8.
+    fn ep_v0_8(&self) -> &Option<Self::EntryPointV0_8>;
+
     /// Returns the DA gas oracle.
     fn da_gas_oracle(&self) -> &Self::DAGasOracle;
 
@@ -170,6 +181,28 @@ pub trait Providers: Send + Sync + Clone {
             _phantom: PhantomData,
         })
     }
+
+    /// Returns the providers with the entry point for v0.8.
+    #[allow(clippy::type_complexity)]
+    fn ep_v0_8_providers(
+        &self,
+    ) -> Option<
+        ProvidersWithEntryPoint<
+            UserOperationV0_8,
+            Self::Evm,
+            Self::EntryPointV0_8,
+            Self::DAGasOracleSync,
+            Self::FeeEstimator,
+        >,
+    > {
+        self.ep_v0_8().as_ref().map(|ep| ProvidersWithEntryPoint {
+            evm: self.evm().clone(),
+            ep: ep.clone(),
+            da_gas_oracle_sync: self.da_gas_oracle_sync().clone(),
+            fee_estimator: self.fee_estimator().clone(),
+            _phantom: PhantomData,
+        })
+    }
 }
 
 /// Trait for providers with a specific entry point.
@@ -232,6 +232,19 @@ pub trait SimulationProvider: Send + Sync {
         state_override: StateOverride,
     ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>>;
 
+    /// Construct the transaction for a call to the entry point contract's
+    /// `simulateHandleOp` function, along with the state overrides needed to execute it
+    fn get_simulate_handle_op_transaction(
+        &self,
+        op: Self::UO,
+        target: Address,
+        target_call_data: Bytes,
+        state_override: StateOverride,
+    ) -> (TransactionRequest, StateOverride);
+
+    /// Decode the return data from a call to `simulateHandleOp` that didn't revert
+    fn decode_simulate_handle_op_output(output: &Bytes) -> ProviderResult<ExecutionResult>;
+
     /// Decode the revert data from a call to `simulateHandleOps`
     fn decode_simulate_handle_ops_revert(
         revert_data: &Bytes,
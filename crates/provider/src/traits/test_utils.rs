@@ -23,7 +23,7 @@ use rundler_contracts::utils::GetGasUsed::GasUsedResult;
 use rundler_types::{
     chain::ChainSpec,
     da::{DAGasBlockData, DAGasData},
-    v0_6, v0_7, EntryPointVersion, ExpectedStorage, GasFees, UserOpsPerAggregator,
+    v0_6, v0_7, v0_8, EntryPointVersion, ExpectedStorage, GasFees, UserOpsPerAggregator,
     ValidationOutput, ValidationRevert,
 };
 
@@ -181,6 +181,14 @@ mockall::mock! {
             block_id: BlockId,
             state_override: StateOverride,
         ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>>;
+        fn get_simulate_handle_op_transaction(
+            &self,
+            op: v0_6::UserOperation,
+            target: Address,
+            target_call_data: Bytes,
+            state_override: StateOverride,
+        ) -> (TransactionRequest, StateOverride);
+        fn decode_simulate_handle_op_output(output: &Bytes) -> ProviderResult<ExecutionResult>;
         fn decode_simulate_handle_ops_revert(
             revert_data: &Bytes,
         ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>>;
@@ -282,6 +290,14 @@ mockall::mock! {
             block_id: BlockId,
             state_override: StateOverride,
         ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>>;
+        fn get_simulate_handle_op_transaction(
+            &self,
+            op: v0_7::UserOperation,
+            target: Address,
+            target_call_data: Bytes,
+            state_override: StateOverride,
+        ) -> (TransactionRequest, StateOverride);
+        fn decode_simulate_handle_op_output(output: &Bytes) -> ProviderResult<ExecutionResult>;
         fn decode_simulate_handle_ops_revert(
             revert_data: &Bytes,
         ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>>;
@@ -330,6 +346,115 @@ mockall::mock! {
     impl EntryPointProvider<v0_7::UserOperation> for EntryPointV0_7 {}
 }
 
+mockall::mock! {
+    pub EntryPointV0_8 {}
+
+    #[async_trait::async_trait]
+    impl EntryPoint for EntryPointV0_8 {
+        fn version(&self) -> EntryPointVersion;
+        fn address(&self) -> &Address;
+        async fn balance_of(&self, address: Address, block_id: Option<BlockId>)
+            -> ProviderResult<U256>;
+        async fn get_deposit_info(&self, address: Address) -> ProviderResult<DepositInfo>;
+        async fn get_balances(&self, addresses: Vec<Address>) -> ProviderResult<Vec<U256>>;
+    }
+
+    #[async_trait::async_trait]
+    impl SignatureAggregator for EntryPointV0_8 {
+        type UO = v0_8::UserOperation;
+        async fn aggregate_signatures(
+            &self,
+            aggregator_address: Address,
+            ops: Vec<v0_8::UserOperation>,
+        ) -> ProviderResult<Option<Bytes>>;
+        async fn validate_user_op_signature(
+            &self,
+            aggregator_address: Address,
+            user_op: v0_8::UserOperation,
+        ) -> ProviderResult<AggregatorOut>;
+    }
+
+    #[async_trait::async_trait]
+    impl SimulationProvider for EntryPointV0_8 {
+        type UO = v0_8::UserOperation;
+        fn get_tracer_simulate_validation_call(
+            &self,
+            user_op: v0_8::UserOperation,
+        ) -> ProviderResult<(TransactionRequest, StateOverride)>;
+        async fn simulate_validation(
+            &self,
+            user_op: v0_8::UserOperation,
+            block_id: Option<BlockId>
+        ) -> ProviderResult<Result<ValidationOutput, ValidationRevert>>;
+        fn get_simulate_handle_op_call(
+            &self,
+            op: v0_8::UserOperation,
+            state_override: StateOverride,
+        ) -> crate::EvmCall;
+        async fn simulate_handle_op(
+            &self,
+            op: v0_8::UserOperation,
+            target: Address,
+            target_call_data: Bytes,
+            block_id: BlockId,
+            state_override: StateOverride,
+        ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>>;
+        fn get_simulate_handle_op_transaction(
+            &self,
+            op: v0_8::UserOperation,
+            target: Address,
+            target_call_data: Bytes,
+            state_override: StateOverride,
+        ) -> (TransactionRequest, StateOverride);
+        fn decode_simulate_handle_op_output(output: &Bytes) -> ProviderResult<ExecutionResult>;
+        fn decode_simulate_handle_ops_revert(
+            revert_data: &Bytes,
+        ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>>;
+        fn simulation_should_revert(&self) -> bool;
+    }
+
+    #[async_trait::async_trait]
+    impl DAGasProvider for EntryPointV0_8 {
+        type UO = v0_8::UserOperation;
+        async fn calc_da_gas(
+            &self,
+            op: v0_8::UserOperation,
+            block: BlockHashOrNumber,
+            gas_price: u128,
+            bundle_size: usize,
+        ) -> ProviderResult<(u128, DAGasData, DAGasBlockData)>;
+    }
+
+    #[async_trait::async_trait]
+    impl<'a> BundleHandler for EntryPointV0_8 {
+        type UO = v0_8::UserOperation;
+        async fn call_handle_ops(
+            &self,
+            ops_per_aggregator: Vec<UserOpsPerAggregator<v0_8::UserOperation>>,
+            sender_eoa: Address,
+            gas_limit: u64,
+            gas_fees: GasFees,
+            proxy: Option<Address>,
+            validation_only: bool,
+        ) -> ProviderResult<HandleOpsOut>;
+        fn get_send_bundle_transaction(
+            &self,
+            ops_per_aggregator: Vec<UserOpsPerAggregator<v0_8::UserOperation>>,
+            beneficiary: Address,
+            gas_limit: u64,
+            gas_fees: GasFees,
+            proxy: Option<Address>,
+        ) -> TransactionRequest;
+        fn decode_handle_ops_revert(message: &str, revert_data: &Option<Bytes>) -> Option<HandleOpsOut>;
+        fn decode_ops_from_calldata(
+            chain_spec: &ChainSpec,
+            calldata: &Bytes,
+        ) -> Vec<UserOpsPerAggregator<v0_8::UserOperation>>;
+    }
+
+    impl EntryPointProvider<v0_8::UserOperation> for EntryPointV0_8 {}
+}
+
 mockall::mock! {
     pub DAGasOracleSync {}
 
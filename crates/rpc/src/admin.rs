@@ -15,10 +15,10 @@ use alloy_primitives::Address;
 use anyhow::Context;
 use async_trait::async_trait;
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
-use rundler_types::pool::Pool;
+use rundler_types::{builder::Builder, pool::Pool};
 
 use crate::{
-    types::{RpcAdminClearState, RpcAdminSetTracking},
+    types::{RpcAdminClearState, RpcAdminSetTracking, RpcQuarantinedSigner},
     utils::{self, InternalRpcResult},
 };
 
@@ -36,22 +36,32 @@ pub trait AdminApi {
         entry_point: Address,
         tracking_info: RpcAdminSetTracking,
     ) -> RpcResult<String>;
+
+    /// Returns the bundle signers that have been quarantined and the reason for each
+    #[method(name = "getQuarantinedSigners")]
+    async fn get_quarantined_signers(&self) -> RpcResult<Vec<RpcQuarantinedSigner>>;
+
+    /// Returns a quarantined bundle signer to the pool of available signers
+    #[method(name = "unquarantineSigner")]
+    async fn unquarantine_signer(&self, address: Address) -> RpcResult<String>;
 }
 
-pub(crate) struct AdminApi<P> {
+pub(crate) struct AdminApi<P, B> {
     pool: P,
+    builder: B,
 }
 
-impl<P> AdminApi<P> {
-    pub(crate) fn new(pool: P) -> Self {
-        Self { pool }
+impl<P, B> AdminApi<P, B> {
+    pub(crate) fn new(pool: P, builder: B) -> Self {
+        Self { pool, builder }
     }
 }
 
 #[async_trait]
-impl<P> AdminApiServer for AdminApi<P>
+impl<P, B> AdminApiServer for AdminApi<P, B>
 where
     P: Pool + 'static,
+    B: Builder + 'static,
 {
     async fn clear_state(&self, clear_params: RpcAdminClearState) -> RpcResult<String> {
         utils::safe_call_rpc_handler(
@@ -72,11 +82,28 @@ where
         )
         .await
     }
+
+    async fn get_quarantined_signers(&self) -> RpcResult<Vec<RpcQuarantinedSigner>> {
+        utils::safe_call_rpc_handler(
+            "admin_getQuarantinedSigners",
+            AdminApi::get_quarantined_signers(self),
+        )
+        .await
+    }
+
+    async fn unquarantine_signer(&self, address: Address) -> RpcResult<String> {
+        utils::safe_call_rpc_handler(
+            "admin_unquarantineSigner",
+            AdminApi::unquarantine_signer(self, address),
+        )
+        .await
+    }
 }
 
-impl<P> AdminApi<P>
+impl<P, B> AdminApi<P, B>
 where
     P: Pool,
+    B: Builder,
 {
     async fn clear_state(&self, clear_params: RpcAdminClearState) -> InternalRpcResult<String> {
         self.pool
@@ -107,4 +134,23 @@ where
 
         Ok("ok".to_string())
     }
+
+    async fn get_quarantined_signers(&self) -> InternalRpcResult<Vec<RpcQuarantinedSigner>> {
+        let signers = self
+            .builder
+            .admin_get_quarantined_signers()
+            .await
+            .context("should get quarantined signers")?;
+
+        Ok(signers.into_iter().map(Into::into).collect())
+    }
+
+    async fn unquarantine_signer(&self, address: Address) -> InternalRpcResult<String> {
+        self.builder
+            .admin_unquarantine_signer(address)
+            .await
+            .context("should unquarantine signer")?;
+
+        Ok("ok".to_string())
+    }
 }
@@ -0,0 +1,504 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use alloy_primitives::U256;
+use futures_util::{future::BoxFuture, FutureExt};
+use http::{header::AUTHORIZATION, HeaderMap, Request as HttpRequest};
+use jsonrpsee::{server::middleware::rpc::RpcServiceT, types::Request, MethodResponse};
+use metrics::Counter;
+use metrics_derive::Metrics;
+use parking_lot::Mutex;
+use rundler_types::{BundlerSponsorship, UserOperationPermissions};
+use rundler_utils::token_bucket::TokenBucket;
+use serde::Deserialize;
+use tower::{Layer, Service};
+
+use crate::{error::rpc_err, types::ApiNamespace};
+
+const UNAUTHORIZED_CODE: i32 = -32001;
+const FORBIDDEN_CODE: i32 = -32002;
+// EIP-1474 limit exceeded code
+const RATE_LIMITED_CODE: i32 = -32005;
+
+/// Authentication config of the RPC server, mapping API keys to what their callers may do
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthConfig {
+    /// API keys accepted as bearer tokens
+    #[serde(default)]
+    pub keys: Vec<ApiKeyConfig>,
+    /// Grant of requests without an API key, which are rejected if not set
+    #[serde(default)]
+    pub anonymous: Option<CallerGrant>,
+}
+
+/// API key config
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyConfig {
+    /// Identity of the key's caller, used to rate limit its user operations in the mempool
+    pub id: String,
+    /// The API key
+    pub key: String,
+    /// What the key's caller may do
+    #[serde(flatten)]
+    pub grant: CallerGrant,
+}
+
+/// What a caller may do
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallerGrant {
+    /// API namespaces the caller may call
+    pub namespaces: Vec<ApiNamespace>,
+    /// Maximum user operation permissions the caller may request
+    #[serde(default)]
+    pub permissions: PermissionsCeiling,
+    /// Maximum rate of requests per second, not limited if not set
+    #[serde(default)]
+    pub requests_per_sec: Option<f64>,
+}
+
+/// Maximum user operation permissions a caller may request.
+///
+/// Requested permissions are capped to the ceiling, and permissions without a ceiling
+/// are dropped.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionsCeiling {
+    /// Whether the caller may submit trusted user operations
+    #[serde(default)]
+    pub trusted: bool,
+    /// Maximum `maxAllowedInPoolForSender` the caller may request
+    #[serde(default)]
+    pub max_allowed_in_pool_for_sender: Option<usize>,
+    /// Minimum `underpricedAcceptPct` the caller may request
+    #[serde(default)]
+    pub min_underpriced_accept_pct: Option<u32>,
+    /// Minimum `underpricedBundlePct` the caller may request
+    #[serde(default)]
+    pub min_underpriced_bundle_pct: Option<u32>,
+    /// Maximum `bundlerSponsorship` cost the caller may request, in wei
+    #[serde(default)]
+    pub max_sponsored_cost: Option<U256>,
+    /// Maximum `priorityTier` the caller may request
+    #[serde(default)]
+    pub max_priority_tier: Option<u32>,
+}
+
+impl PermissionsCeiling {
+    fn apply(&self, requested: UserOperationPermissions) -> UserOperationPermissions {
+        UserOperationPermissions {
+            trusted: requested.trusted && self.trusted,
+            max_allowed_in_pool_for_sender: requested
+                .max_allowed_in_pool_for_sender
+                .zip(self.max_allowed_in_pool_for_sender)
+                .map(|(r, c)| r.min(c)),
+            underpriced_accept_pct: requested
+                .underpriced_accept_pct
+                .zip(self.min_underpriced_accept_pct)
+                .map(|(r, c)| r.max(c)),
+            underpriced_bundle_pct: requested
+                .underpriced_bundle_pct
+                .zip(self.min_underpriced_bundle_pct)
+                .map(|(r, c)| r.max(c)),
+            bundler_sponsorship: requested
+                .bundler_sponsorship
+                .zip(self.max_sponsored_cost)
+                .map(|(r, c)| BundlerSponsorship {
+                    max_cost: r.max_cost.min(c),
+                    valid_until: r.valid_until,
+                }),
+            priority_tier: requested
+                .priority_tier
+                .zip(self.max_priority_tier)
+                .map(|(r, c)| r.min(c)),
+            caller_id: None,
+        }
+    }
+}
+
+/// Result of authenticating a request, attached to the request extensions
+#[derive(Clone, Debug)]
+pub(crate) enum Authentication {
+    Caller(Arc<Caller>),
+    Unauthorized,
+}
+
+/// An authenticated caller
+#[derive(Debug)]
+pub(crate) struct Caller {
+    id: Option<String>,
+    grant: CallerGrant,
+    bucket: Option<Mutex<TokenBucket>>,
+}
+
+// Requests a caller can make at once, one second worth of requests
+fn request_burst(rate: f64) -> f64 {
+    rate.ceil().max(1.0)
+}
+
+impl Caller {
+    fn new(id: Option<String>, grant: CallerGrant) -> Self {
+        let bucket = grant
+            .requests_per_sec
+            .map(|rate| Mutex::new(TokenBucket::new(request_burst(rate), Instant::now())));
+        Self { id, grant, bucket }
+    }
+
+    /// Caps the requested permissions to the caller's ceiling and tags them with its identity
+    pub(crate) fn permissions(
+        &self,
+        requested: UserOperationPermissions,
+    ) -> UserOperationPermissions {
+        UserOperationPermissions {
+            caller_id: self.id.clone(),
+            ..self.grant.permissions.apply(requested)
+        }
+    }
+
+    fn may_call(&self, method: &str) -> bool {
+        let Some((namespace, _)) = method.split_once('_') else {
+            return false;
+        };
+        namespace
+            .parse::<ApiNamespace>()
+            .is_ok_and(|ns| self.grant.namespaces.contains(&ns))
+    }
+
+    fn take_request(&self, now: Instant) -> bool {
+        let (Some(bucket), Some(rate)) = (&self.bucket, self.grant.requests_per_sec) else {
+            return true;
+        };
+        let mut bucket = bucket.lock();
+        bucket.refill(rate, request_burst(rate), now);
+        bucket.take()
+    }
+}
+
+/// Resolves the caller of requests from their bearer API key
+#[derive(Clone, Debug)]
+pub(crate) struct Authenticator {
+    keys: Arc<HashMap<String, Arc<Caller>>>,
+    anonymous: Option<Arc<Caller>>,
+}
+
+impl Authenticator {
+    pub(crate) fn new(config: AuthConfig) -> Self {
+        let keys = config
+            .keys
+            .into_iter()
+            .map(|k| (k.key, Arc::new(Caller::new(Some(k.id), k.grant))))
+            .collect();
+        Self {
+            keys: Arc::new(keys),
+            anonymous: config
+                .anonymous
+                .map(|grant| Arc::new(Caller::new(None, grant))),
+        }
+    }
+
+    fn authenticate(&self, headers: &HeaderMap) -> Authentication {
+        let Some(header) = headers.get(AUTHORIZATION) else {
+            return self
+                .anonymous
+                .clone()
+                .map_or(Authentication::Unauthorized, Authentication::Caller);
+        };
+
+        header
+            .to_str()
+            .ok()
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .and_then(|key| self.keys.get(key.trim()))
+            .cloned()
+            .map_or(Authentication::Unauthorized, Authentication::Caller)
+    }
+}
+
+/// HTTP middleware attaching the [`Authentication`] of each request to its extensions
+#[derive(Clone)]
+pub(crate) struct AuthHttpLayer {
+    authenticator: Authenticator,
+}
+
+impl AuthHttpLayer {
+    pub(crate) fn new(authenticator: Authenticator) -> Self {
+        Self { authenticator }
+    }
+}
+
+impl<S> Layer<S> for AuthHttpLayer {
+    type Service = AuthHttpMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        AuthHttpMiddleware {
+            service,
+            authenticator: self.authenticator.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct AuthHttpMiddleware<S> {
+    service: S,
+    authenticator: Authenticator,
+}
+
+impl<S, R> Service<HttpRequest<R>> for AuthHttpMiddleware<S>
+where
+    S: Service<HttpRequest<R>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: HttpRequest<R>) -> Self::Future {
+        let authentication = self.authenticator.authenticate(req.headers());
+        req.extensions_mut().insert(authentication);
+        self.service.call(req)
+    }
+}
+
+/// RPC middleware enforcing the namespaces and request rate of the caller of each call.
+///
+/// Calls without an [`Authentication`] are passed through, as authentication is disabled.
+#[derive(Clone, Default)]
+pub(crate) struct AuthRpcLayer;
+
+impl<S> Layer<S> for AuthRpcLayer {
+    type Service = AuthRpcMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        AuthRpcMiddleware {
+            service,
+            metrics: AuthMetrics::default(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct AuthRpcMiddleware<S> {
+    service: S,
+    metrics: AuthMetrics,
+}
+
+impl<'a, S> RpcServiceT<'a> for AuthRpcMiddleware<S>
+where
+    S: RpcServiceT<'a> + Send + Sync + Clone + 'static,
+{
+    type Future = BoxFuture<'a, MethodResponse>;
+
+    fn call(&self, req: Request<'a>) -> Self::Future {
+        let err = match req.extensions().get::<Authentication>() {
+            // health checks are always allowed
+            _ if req.method_name().starts_with("system_") => None,
+            None => None,
+            Some(Authentication::Unauthorized) => {
+                self.metrics.unauthorized_requests.increment(1);
+                Some(rpc_err(UNAUTHORIZED_CODE, "missing or invalid API key"))
+            }
+            Some(Authentication::Caller(caller)) => {
+                if !caller.may_call(req.method_name()) {
+                    self.metrics.forbidden_requests.increment(1);
+                    Some(rpc_err(
+                        FORBIDDEN_CODE,
+                        format!("method {} not allowed", req.method_name()),
+                    ))
+                } else if !caller.take_request(Instant::now()) {
+                    self.metrics.rate_limited_requests.increment(1);
+                    Some(rpc_err(RATE_LIMITED_CODE, "request rate limit exceeded"))
+                } else {
+                    None
+                }
+            }
+        };
+
+        match err {
+            Some(err) => {
+                let rp = MethodResponse::error(req.id(), err);
+                async move { rp }.boxed()
+            }
+            None => {
+                let svc = self.service.clone();
+                async move { svc.call(req).await }.boxed()
+            }
+        }
+    }
+}
+
+#[derive(Metrics, Clone)]
+#[metrics(scope = "rpc_auth")]
+struct AuthMetrics {
+    #[metric(describe = "the count of requests with a missing or invalid API key.")]
+    unauthorized_requests: Counter,
+    #[metric(describe = "the count of requests to methods not allowed for the caller.")]
+    forbidden_requests: Counter,
+    #[metric(describe = "the count of requests over the caller's rate limit.")]
+    rate_limited_requests: Counter,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn grant(namespaces: Vec<ApiNamespace>) -> CallerGrant {
+        CallerGrant {
+            namespaces,
+            permissions: PermissionsCeiling::default(),
+            requests_per_sec: None,
+        }
+    }
+
+    fn authenticator(anonymous: Option<CallerGrant>) -> Authenticator {
+        Authenticator::new(AuthConfig {
+            keys: vec![ApiKeyConfig {
+                id: "partner".to_string(),
+                key: "secret".to_string(),
+                grant: grant(vec![ApiNamespace::Eth, ApiNamespace::Rundler]),
+            }],
+            anonymous,
+        })
+    }
+
+    fn headers(authorization: Option<&'static str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(a) = authorization {
+            headers.insert(AUTHORIZATION, HeaderValue::from_static(a));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let auth = authenticator(None);
+        let Authentication::Caller(caller) = auth.authenticate(&headers(Some("Bearer secret")))
+        else {
+            panic!("expected caller");
+        };
+        assert_eq!(caller.id.as_deref(), Some("partner"));
+
+        assert!(matches!(
+            auth.authenticate(&headers(Some("Bearer wrong"))),
+            Authentication::Unauthorized
+        ));
+        assert!(matches!(
+            auth.authenticate(&headers(Some("secret"))),
+            Authentication::Unauthorized
+        ));
+        assert!(matches!(
+            auth.authenticate(&headers(None)),
+            Authentication::Unauthorized
+        ));
+    }
+
+    #[test]
+    fn test_authenticate_anonymous() {
+        let auth = authenticator(Some(grant(vec![ApiNamespace::Eth])));
+        let Authentication::Caller(caller) = auth.authenticate(&headers(None)) else {
+            panic!("expected anonymous caller");
+        };
+        assert_eq!(caller.id, None);
+
+        // an invalid key is not downgraded to anonymous
+        assert!(matches!(
+            auth.authenticate(&headers(Some("Bearer wrong"))),
+            Authentication::Unauthorized
+        ));
+    }
+
+    #[test]
+    fn test_may_call() {
+        let caller = Caller::new(None, grant(vec![ApiNamespace::Eth]));
+        assert!(caller.may_call("eth_sendUserOperation"));
+        assert!(!caller.may_call("debug_bundler_clearState"));
+        assert!(!caller.may_call("admin_clearState"));
+        assert!(!caller.may_call("unknown"));
+    }
+
+    #[test]
+    fn test_request_rate_limit() {
+        let caller = Caller::new(
+            None,
+            CallerGrant {
+                requests_per_sec: Some(2.0),
+                ..grant(vec![ApiNamespace::Eth])
+            },
+        );
+        let now = Instant::now();
+        assert!(caller.take_request(now));
+        assert!(caller.take_request(now));
+        assert!(!caller.take_request(now));
+        assert!(caller.take_request(now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_permissions_ceiling() {
+        let caller = Caller::new(
+            Some("partner".to_string()),
+            CallerGrant {
+                permissions: PermissionsCeiling {
+                    trusted: false,
+                    max_allowed_in_pool_for_sender: Some(10),
+                    min_underpriced_accept_pct: Some(80),
+                    min_underpriced_bundle_pct: None,
+                    max_sponsored_cost: Some(U256::from(1000)),
+                    max_priority_tier: Some(1),
+                },
+                ..grant(vec![ApiNamespace::Eth])
+            },
+        );
+
+        let permissions = caller.permissions(UserOperationPermissions {
+            trusted: true,
+            max_allowed_in_pool_for_sender: Some(100),
+            underpriced_accept_pct: Some(50),
+            underpriced_bundle_pct: Some(50),
+            bundler_sponsorship: Some(BundlerSponsorship {
+                max_cost: U256::from(5000),
+                valid_until: 10,
+            }),
+            priority_tier: Some(5),
+            caller_id: Some("spoofed".to_string()),
+        });
+
+        assert_eq!(
+            permissions,
+            UserOperationPermissions {
+                trusted: false,
+                max_allowed_in_pool_for_sender: Some(10),
+                underpriced_accept_pct: Some(80),
+                underpriced_bundle_pct: None,
+                bundler_sponsorship: Some(BundlerSponsorship {
+                    max_cost: U256::from(1000),
+                    valid_until: 10,
+                }),
+                priority_tier: Some(1),
+                caller_id: Some("partner".to_string()),
+            }
+        );
+    }
+}
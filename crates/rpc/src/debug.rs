@@ -23,8 +23,8 @@ use rundler_types::{
 
 use crate::{
     types::{
-        RpcDebugPaymasterBalance, RpcReputationInput, RpcReputationOutput, RpcStakeInfo,
-        RpcStakeStatus, RpcUserOperation,
+        RpcBundlePreview, RpcDebugPaymasterBalance, RpcReputationInput, RpcReputationOutput,
+        RpcStakeInfo, RpcStakeStatus, RpcUserOperation,
     },
     utils::{self, InternalRpcResult},
 };
@@ -54,6 +54,15 @@ pub trait DebugApi {
     #[method(name = "bundler_setBundlingMode")]
     async fn bundler_set_bundling_mode(&self, mode: BundlingMode) -> RpcResult<String>;
 
+    /// Builds, but does not send, the bundle that the builder for the given entry point
+    /// and filter id would propose at the latest block.
+    #[method(name = "bundler_simulateBundle")]
+    async fn bundler_simulate_bundle(
+        &self,
+        entry_point: Address,
+        filter_id: Option<String>,
+    ) -> RpcResult<RpcBundlePreview>;
+
     /// Sets the reputations of entities on the given entry point.
     #[method(name = "bundler_setReputation")]
     async fn bundler_set_reputation(
@@ -143,6 +152,18 @@ where
         .await
     }
 
+    async fn bundler_simulate_bundle(
+        &self,
+        entry_point: Address,
+        filter_id: Option<String>,
+    ) -> RpcResult<RpcBundlePreview> {
+        utils::safe_call_rpc_handler(
+            "bundler_simulateBundle",
+            DebugApi::bundler_simulate_bundle(self, entry_point, filter_id),
+        )
+        .await
+    }
+
     async fn bundler_set_reputation(
         &self,
         reputations: Vec<RpcReputationInput>,
@@ -282,6 +303,20 @@ where
         Ok("ok".to_string())
     }
 
+    async fn bundler_simulate_bundle(
+        &self,
+        entry_point: Address,
+        filter_id: Option<String>,
+    ) -> InternalRpcResult<RpcBundlePreview> {
+        let preview = self
+            .builder
+            .debug_preview_bundle(entry_point, filter_id)
+            .await
+            .context("should preview bundle")?;
+
+        Ok(preview.into())
+    }
+
     async fn bundler_set_reputation(
         &self,
         reputations: Vec<RpcReputationInput>,
@@ -412,6 +412,7 @@ mod tests {
                     provider.clone(),
                     None,
                     None,
+                    None,
                 ),
             ))
             .build();
@@ -22,7 +22,7 @@ use jsonrpsee::types::{
 use rundler_provider::ProviderError;
 use rundler_sim::GasEstimationError;
 use rundler_types::{
-    pool::{MempoolError, PoolError, PrecheckViolation, SimulationViolation},
+    pool::{MempoolError, PoolError, PrecheckViolation, RateLimitKey, SimulationViolation},
     Entity, EntityType, Opcode, Timestamp, ValidationRevert,
 };
 use serde::Serialize;
@@ -46,6 +46,9 @@ const SIGNATURE_CHECK_FAILED_CODE: i32 = -32507;
 const PAYMASTER_DEPOSIT_TOO_LOW: i32 = -32508;
 const EXECUTION_REVERTED: i32 = -32521;
 
+// EIP-1474 limit exceeded code
+const RATE_LIMITED_CODE: i32 = -32005;
+
 pub(crate) type EthResult<T> = Result<T, EthRpcError>;
 
 /// Error returned by the RPC server eth namespace
@@ -99,6 +102,9 @@ pub enum EthRpcError {
     /// Entity throttled or banned
     #[error("{} {:#032x} throttled or banned", .0.kind, .0.address)]
     ThrottledOrBanned(Entity),
+    /// Operation admissions rate limited
+    #[error("rate limit exceeded for {0}")]
+    RateLimited(RateLimitKey),
     /// Entity stake/unstake delay too low
     #[error("entity stake/unstake delay too low")]
     StakeTooLow(Box<StakeTooLowData>),
@@ -322,6 +328,7 @@ impl From<MempoolError> for EthRpcError {
                 Self::InvalidParams(value.to_string())
             }
             MempoolError::EIPNotSupported(_) => Self::InvalidParams(value.to_string()),
+            MempoolError::RateLimited(key) => Self::RateLimited(key),
         }
     }
 }
@@ -415,6 +422,7 @@ impl From<EthRpcError> for ErrorObjectOwned {
             EthRpcError::ThrottledOrBanned(data) => {
                 rpc_err_with_data(THROTTLED_OR_BANNED_CODE, msg, data)
             }
+            EthRpcError::RateLimited(_) => rpc_err(RATE_LIMITED_CODE, msg),
             EthRpcError::StakeTooLow(data) => rpc_err_with_data(OPCODE_VIOLATION_CODE, msg, data),
             EthRpcError::UnsupportedAggregator(data) => {
                 rpc_err_with_data(UNSUPORTED_AGGREGATOR_CODE, msg, data)
@@ -524,6 +532,9 @@ impl From<GasEstimationError> for EthRpcError {
             error @ GasEstimationError::GasFieldTooLarge(_, _) => {
                 Self::InvalidParams(error.to_string())
             }
+            error @ GasEstimationError::InvalidStateOverride(_, _) => {
+                Self::InvalidParams(error.to_string())
+            }
             GasEstimationError::ProviderError(provider_error) => {
                 EthRpcError::from(ProviderErrorWithContext::from(provider_error))
             }
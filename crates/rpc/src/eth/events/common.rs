@@ -26,7 +26,10 @@ use rundler_types::{chain::ChainSpec, UserOperation, UserOperationVariant};
 use rundler_utils::log::LogOnError;
 use tracing::instrument;
 
-use super::UserOperationEventProvider;
+use super::{
+    index::{IndexedUserOperation, UserOperationIndex},
+    UserOperationEventProvider,
+};
 use crate::types::{RpcUserOperationByHash, RpcUserOperationReceipt};
 
 #[derive(Debug)]
@@ -35,6 +38,7 @@ pub(crate) struct UserOperationEventProviderImpl<P, F> {
     provider: P,
     event_block_distance: Option<u64>,
     event_block_distance_fallback: Option<u64>,
+    index: Option<UserOperationIndex>,
     _f_type: PhantomData<F>,
 }
 
@@ -50,6 +54,15 @@ pub(crate) trait EntryPointEvents: Send + Sync {
         tx_receipt: TransactionReceipt,
     ) -> RpcUserOperationReceipt;
 
+    fn indexed_user_operation(
+        event: Self::UserOperationEvent,
+        entry_point: Address,
+        log: Log,
+    ) -> IndexedUserOperation;
+
+    /// Returns the user operation hash and revert data of a revert reason event
+    fn revert_reason(event: Self::UserOperationRevertReason) -> (B256, Bytes);
+
     fn get_user_operations_from_tx_data(tx_data: Bytes, chain_spec: &ChainSpec) -> Vec<Self::UO>;
 
     fn address(chain_spec: &ChainSpec) -> Address;
@@ -148,12 +161,14 @@ where
         provider: P,
         event_block_distance: Option<u64>,
         event_block_distance_fallback: Option<u64>,
+        index: Option<UserOperationIndex>,
     ) -> Self {
         Self {
             chain_spec,
             provider,
             event_block_distance,
             event_block_distance_fallback,
+            index,
             _f_type: PhantomData,
         }
     }
@@ -167,6 +182,40 @@ where
             None => 0,
         };
 
+        let Some(index) = &self.index else {
+            return self
+                .get_event_by_hash_with_fallback(hash, from_block, to_block)
+                .await;
+        };
+
+        let entry_point = E::address(&self.chain_spec);
+        if let Some(op) = index.get_by_hash(entry_point, hash) {
+            return Ok(Some(op.log));
+        }
+
+        // Only search the blocks the index doesn't cover, most recent first
+        for (from_block, to_block) in index
+            .unindexed_ranges(entry_point, from_block, to_block)
+            .into_iter()
+            .rev()
+        {
+            if let Some(log) = self
+                .get_event_by_hash_with_fallback(hash, from_block, to_block)
+                .await?
+            {
+                return Ok(Some(log));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn get_event_by_hash_with_fallback(
+        &self,
+        hash: B256,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<Option<Log>> {
         match self.get_event_by_hash_at(hash, from_block, to_block).await {
             Ok(logs) => Ok(logs),
             Err(e) => {
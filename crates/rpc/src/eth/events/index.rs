@@ -0,0 +1,492 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use anyhow::Context;
+use parking_lot::{Mutex, RwLock};
+use rundler_provider::Log;
+use serde::{Deserialize, Serialize};
+
+const INDEX_FILE_NAME: &str = "user_operations.jsonl";
+
+/// A mined user operation stored in the index
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct IndexedUserOperation {
+    pub(crate) entry_point: Address,
+    pub(crate) user_op_hash: B256,
+    pub(crate) sender: Address,
+    pub(crate) paymaster: Address,
+    pub(crate) nonce: U256,
+    pub(crate) success: bool,
+    pub(crate) actual_gas_cost: U256,
+    pub(crate) actual_gas_used: U256,
+    /// Revert reason emitted alongside the event if the operation's execution failed
+    pub(crate) revert_reason: Option<Bytes>,
+    /// The `UserOperationEvent` log, including its transaction and block metadata
+    pub(crate) log: Log,
+}
+
+impl IndexedUserOperation {
+    fn block_number(&self) -> u64 {
+        self.log.block_number.unwrap_or_default()
+    }
+
+    fn position(&self) -> (u64, u64) {
+        (self.block_number(), self.log.log_index.unwrap_or_default())
+    }
+}
+
+/// Blocks of an entry point that have been indexed, `[start_block, end_block)`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct IndexedRange {
+    pub(crate) start_block: u64,
+    pub(crate) end_block: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum IndexEntry {
+    UserOperation(Box<IndexedUserOperation>),
+    /// Written after each batch of user operations. Entries after the last checkpoint
+    /// belong to a batch that was never completed and are discarded on load.
+    #[serde(rename_all = "camelCase")]
+    Checkpoint {
+        entry_point: Address,
+        range: IndexedRange,
+    },
+}
+
+/// Embedded store of mined user operations.
+///
+/// Entries are appended to a JSON lines file and the whole index is held in memory,
+/// keyed by user operation hash, sender and paymaster. The index only grows; the indexer
+/// only writes blocks that are deep enough to not be reorged.
+///
+/// Nothing is ever pruned, so memory use and startup time grow with the number of
+/// indexed user operations. Operators can bound them by moving the start block and
+/// removing the index file.
+#[derive(Clone, Debug)]
+pub(crate) struct UserOperationIndex {
+    file: Arc<Mutex<File>>,
+    state: Arc<RwLock<IndexState>>,
+}
+
+#[derive(Debug, Default)]
+struct IndexState {
+    ops: Vec<IndexedUserOperation>,
+    by_hash: HashMap<B256, usize>,
+    /// Operations of each sender, in block order
+    by_sender: HashMap<Address, Vec<usize>>,
+    /// Operations of each paymaster, in block order
+    by_paymaster: HashMap<Address, Vec<usize>>,
+    ranges: HashMap<Address, IndexedRange>,
+}
+
+impl UserOperationIndex {
+    /// Opens the index in the given directory, creating it if it doesn't exist
+    pub(crate) fn open(dir: &Path) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir).context("should create index directory")?;
+        let path = dir.join(INDEX_FILE_NAME);
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("should open index file {}", path.display()))?;
+
+        let mut state = IndexState::default();
+
+        let mut pending = vec![];
+        let mut valid_len = 0;
+        let mut read_len = 0;
+        let mut reader = BufReader::new(&file);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let n = reader
+                .read_line(&mut line)
+                .context("should read index file")?;
+            if n == 0 || !line.ends_with('\n') {
+                break;
+            }
+            read_len += n as u64;
+
+            match serde_json::from_str::<IndexEntry>(&line) {
+                Ok(IndexEntry::UserOperation(op)) => pending.push(*op),
+                Ok(IndexEntry::Checkpoint { entry_point, range }) => {
+                    for op in pending.drain(..) {
+                        state.insert(op);
+                    }
+                    state.ranges.insert(entry_point, range);
+                    valid_len = read_len;
+                }
+                Err(e) => {
+                    tracing::warn!("Invalid entry in user operation index, discarding the rest of the file: {e:?}");
+                    break;
+                }
+            }
+        }
+        drop(reader);
+
+        if valid_len < file.metadata()?.len() {
+            tracing::warn!("Discarding incomplete batch at the end of the user operation index");
+            file.set_len(valid_len)
+                .context("should truncate index file")?;
+        }
+
+        tracing::info!(
+            "Loaded {} user operations from index at {}",
+            state.ops.len(),
+            path.display()
+        );
+
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+            state: Arc::new(RwLock::new(state)),
+        })
+    }
+
+    /// Returns the indexed blocks of an entry point, if any
+    pub(crate) fn range(&self, entry_point: Address) -> Option<IndexedRange> {
+        self.state.read().ranges.get(&entry_point).copied()
+    }
+
+    /// Returns the block ranges within `[from_block, to_block]` that are not covered by the index
+    pub(crate) fn unindexed_ranges(
+        &self,
+        entry_point: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Vec<(u64, u64)> {
+        let Some(range) = self.range(entry_point) else {
+            return vec![(from_block, to_block)];
+        };
+
+        let mut ranges = vec![];
+        if from_block < range.start_block {
+            ranges.push((from_block, to_block.min(range.start_block - 1)));
+        }
+        if to_block >= range.end_block {
+            ranges.push((from_block.max(range.end_block), to_block));
+        }
+        ranges
+    }
+
+    /// Appends a batch of user operations and extends the entry point's indexed range
+    pub(crate) async fn append(
+        &self,
+        entry_point: Address,
+        ops: Vec<IndexedUserOperation>,
+        range: IndexedRange,
+    ) -> anyhow::Result<()> {
+        let mut buf = Vec::new();
+        for op in &ops {
+            serde_json::to_writer(&mut buf, &IndexEntry::UserOperation(Box::new(op.clone())))?;
+            buf.push(b'\n');
+        }
+        serde_json::to_writer(&mut buf, &IndexEntry::Checkpoint { entry_point, range })?;
+        buf.push(b'\n');
+
+        // Write on a blocking thread, the file lock keeps each batch contiguous
+        let file = self.file.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut file = file.lock();
+            file.write_all(&buf).context("should write to index file")?;
+            file.flush().context("should flush index file")
+        })
+        .await
+        .context("index write task should not panic")??;
+
+        let mut state = self.state.write();
+        for op in ops {
+            state.insert(op);
+        }
+        state.ranges.insert(entry_point, range);
+        Ok(())
+    }
+
+    /// Returns the user operation with the given hash mined by the entry point
+    pub(crate) fn get_by_hash(
+        &self,
+        entry_point: Address,
+        hash: B256,
+    ) -> Option<IndexedUserOperation> {
+        let state = self.state.read();
+        state
+            .by_hash
+            .get(&hash)
+            .map(|&i| &state.ops[i])
+            .filter(|op| op.entry_point == entry_point)
+            .cloned()
+    }
+
+    /// Returns up to `limit` user operations of a sender mined within `[from_block, to_block]`
+    pub(crate) fn get_by_sender(
+        &self,
+        sender: Address,
+        from_block: u64,
+        to_block: u64,
+        limit: usize,
+    ) -> Vec<IndexedUserOperation> {
+        let state = self.state.read();
+        state.query(state.by_sender.get(&sender), from_block, to_block, limit)
+    }
+
+    /// Returns up to `limit` user operations sponsored by a paymaster mined within `[from_block, to_block]`
+    pub(crate) fn get_by_paymaster(
+        &self,
+        paymaster: Address,
+        from_block: u64,
+        to_block: u64,
+        limit: usize,
+    ) -> Vec<IndexedUserOperation> {
+        let state = self.state.read();
+        state.query(
+            state.by_paymaster.get(&paymaster),
+            from_block,
+            to_block,
+            limit,
+        )
+    }
+}
+
+impl IndexState {
+    fn insert(&mut self, op: IndexedUserOperation) {
+        let i = self.ops.len();
+        let position = op.position();
+        self.by_hash.insert(op.user_op_hash, i);
+        let sender = op.sender;
+        let paymaster = op.paymaster;
+        self.ops.push(op);
+
+        let ops = &self.ops;
+        // entry points are indexed independently, so ops may not arrive in block order.
+        // Usually they do and this inserts at the end.
+        let insert_sorted = |indexes: &mut Vec<usize>| {
+            let at = indexes.partition_point(|&j| ops[j].position() <= position);
+            indexes.insert(at, i);
+        };
+        insert_sorted(self.by_sender.entry(sender).or_default());
+        if paymaster != Address::ZERO {
+            insert_sorted(self.by_paymaster.entry(paymaster).or_default());
+        }
+    }
+
+    fn query(
+        &self,
+        indexes: Option<&Vec<usize>>,
+        from_block: u64,
+        to_block: u64,
+        limit: usize,
+    ) -> Vec<IndexedUserOperation> {
+        let Some(indexes) = indexes else {
+            return vec![];
+        };
+        let start = indexes.partition_point(|&i| self.ops[i].block_number() < from_block);
+        indexes[start..]
+            .iter()
+            .map(|&i| &self.ops[i])
+            .take_while(|op| op.block_number() <= to_block)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use alloy_primitives::address;
+
+    use super::*;
+
+    const ENTRY_POINT: Address = address!("0000000071727De22E5E9d8BAf0edAc6f37da032");
+    const SENDER: Address = address!("0000000000000000000000000000000000000001");
+    const PAYMASTER: Address = address!("0000000000000000000000000000000000000002");
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rundler-index-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn op(hash: u8, block_number: u64, paymaster: Address) -> IndexedUserOperation {
+        IndexedUserOperation {
+            entry_point: ENTRY_POINT,
+            user_op_hash: B256::repeat_byte(hash),
+            sender: SENDER,
+            paymaster,
+            nonce: U256::from(hash),
+            success: true,
+            actual_gas_cost: U256::from(1000),
+            actual_gas_used: U256::from(100),
+            revert_reason: None,
+            log: Log {
+                block_number: Some(block_number),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn range(start_block: u64, end_block: u64) -> IndexedRange {
+        IndexedRange {
+            start_block,
+            end_block,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queries() {
+        let dir = temp_dir("queries");
+        let index = UserOperationIndex::open(&dir).unwrap();
+        index
+            .append(
+                ENTRY_POINT,
+                vec![op(1, 10, Address::ZERO), op(2, 12, PAYMASTER)],
+                range(10, 13),
+            )
+            .await
+            .unwrap();
+        index
+            .append(ENTRY_POINT, vec![op(3, 15, PAYMASTER)], range(10, 16))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            index.get_by_hash(ENTRY_POINT, B256::repeat_byte(2)),
+            Some(op(2, 12, PAYMASTER))
+        );
+        assert_eq!(index.get_by_hash(Address::ZERO, B256::repeat_byte(2)), None);
+        assert_eq!(index.get_by_sender(SENDER, 0, 100, 100).len(), 3);
+        assert_eq!(
+            index.get_by_sender(SENDER, 11, 100, 1),
+            vec![op(2, 12, PAYMASTER)]
+        );
+        assert_eq!(
+            index.get_by_paymaster(PAYMASTER, 0, 14, 100),
+            vec![op(2, 12, PAYMASTER)]
+        );
+        assert!(index
+            .get_by_paymaster(Address::ZERO, 0, 100, 100)
+            .is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_queries_across_entry_points() {
+        let dir = temp_dir("entry_points");
+        let index = UserOperationIndex::open(&dir).unwrap();
+        let other_entry_point = Address::repeat_byte(0xee);
+        let mut other = op(2, 11, PAYMASTER);
+        other.entry_point = other_entry_point;
+
+        index
+            .append(
+                ENTRY_POINT,
+                vec![op(1, 10, PAYMASTER), op(3, 12, PAYMASTER)],
+                range(0, 13),
+            )
+            .await
+            .unwrap();
+        // the other entry point's indexer lags behind
+        index
+            .append(other_entry_point, vec![other.clone()], range(0, 12))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            index.get_by_sender(SENDER, 0, 100, 100),
+            vec![op(1, 10, PAYMASTER), other.clone(), op(3, 12, PAYMASTER)]
+        );
+        assert_eq!(index.get_by_paymaster(PAYMASTER, 11, 100, 1), vec![other]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unindexed_ranges() {
+        let dir = temp_dir("unindexed");
+        let index = UserOperationIndex::open(&dir).unwrap();
+        assert_eq!(index.unindexed_ranges(ENTRY_POINT, 0, 100), vec![(0, 100)]);
+
+        index
+            .append(ENTRY_POINT, vec![], range(10, 20))
+            .await
+            .unwrap();
+        assert_eq!(
+            index.unindexed_ranges(ENTRY_POINT, 0, 100),
+            vec![(0, 9), (20, 100)]
+        );
+        assert_eq!(index.unindexed_ranges(ENTRY_POINT, 12, 18), vec![]);
+        assert_eq!(index.unindexed_ranges(ENTRY_POINT, 15, 25), vec![(20, 25)]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reopen_discards_incomplete_batch() {
+        let dir = temp_dir("reopen");
+        let index = UserOperationIndex::open(&dir).unwrap();
+        index
+            .append(ENTRY_POINT, vec![op(1, 10, PAYMASTER)], range(0, 11))
+            .await
+            .unwrap();
+        drop(index);
+
+        // simulate a crash in the middle of writing a batch
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.join(INDEX_FILE_NAME))
+            .unwrap();
+        serde_json::to_writer(
+            &mut file,
+            &IndexEntry::UserOperation(Box::new(op(2, 12, PAYMASTER))),
+        )
+        .unwrap();
+        file.write_all(b"\n{\"type\":\"userOper").unwrap();
+        drop(file);
+
+        let index = UserOperationIndex::open(&dir).unwrap();
+        assert_eq!(index.range(ENTRY_POINT), Some(range(0, 11)));
+        assert!(index
+            .get_by_hash(ENTRY_POINT, B256::repeat_byte(1))
+            .is_some());
+        assert!(index
+            .get_by_hash(ENTRY_POINT, B256::repeat_byte(2))
+            .is_none());
+
+        // new batches are appended after the discarded entries
+        index
+            .append(ENTRY_POINT, vec![op(3, 13, PAYMASTER)], range(0, 14))
+            .await
+            .unwrap();
+        drop(index);
+        let index = UserOperationIndex::open(&dir).unwrap();
+        assert_eq!(index.get_by_sender(SENDER, 0, 100, 100).len(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
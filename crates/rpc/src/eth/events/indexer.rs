@@ -0,0 +1,297 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::{collections::HashMap, marker::PhantomData, path::PathBuf, time::Duration};
+
+use alloy_primitives::{Bytes, B256};
+use alloy_sol_types::SolEvent;
+use anyhow::Context;
+use metrics::{Counter, Gauge};
+use metrics_derive::Metrics;
+use rundler_provider::{EvmProvider, Filter};
+use rundler_types::chain::ChainSpec;
+
+use super::{
+    common::EntryPointEvents,
+    index::{IndexedRange, UserOperationIndex},
+};
+
+/// Interval to poll for new blocks once the index has caught up
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Settings for the user operation event indexer
+#[derive(Clone, Debug)]
+pub struct IndexerSettings {
+    /// Directory of the index
+    pub path: PathBuf,
+    /// Block to start indexing from when the index is empty. If not set, indexing starts
+    /// at the latest block.
+    pub start_block: Option<u64>,
+    /// Maximum number of blocks to query logs for at once
+    pub max_block_range: u64,
+}
+
+/// Follows the `UserOperationEvent` logs of an entry point and writes them to the index.
+///
+/// Blocks are only indexed once they are `chain_history_size` blocks deep, so indexed
+/// events are not expected to be reorged. Events in more recent blocks are found by
+/// the event provider with `eth_getLogs`.
+pub(crate) struct UserOperationIndexer<P, E> {
+    chain_spec: ChainSpec,
+    provider: P,
+    index: UserOperationIndex,
+    settings: IndexerSettings,
+    metrics: IndexerMetrics,
+    _e_type: PhantomData<E>,
+}
+
+impl<P, E> UserOperationIndexer<P, E>
+where
+    P: EvmProvider,
+    E: EntryPointEvents,
+{
+    pub(crate) fn new(
+        chain_spec: ChainSpec,
+        provider: P,
+        index: UserOperationIndex,
+        settings: IndexerSettings,
+    ) -> Self {
+        let entry_point = E::address(&chain_spec).to_string();
+        Self {
+            chain_spec,
+            provider,
+            index,
+            settings,
+            metrics: IndexerMetrics::new_with_labels(&[("entry_point", entry_point)]),
+            _e_type: PhantomData,
+        }
+    }
+
+    pub(crate) async fn run(self) {
+        tracing::info!(
+            "Starting user operation indexer for entry point {:?}",
+            E::address(&self.chain_spec)
+        );
+
+        loop {
+            match self.index_next_blocks().await {
+                // more blocks are ready to be indexed
+                Ok(false) => continue,
+                Ok(true) => {}
+                Err(e) => tracing::warn!("Failed to index user operation events: {e:?}"),
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Indexes the next range of blocks, returns true if the index has caught up
+    async fn index_next_blocks(&self) -> anyhow::Result<bool> {
+        let entry_point = E::address(&self.chain_spec);
+        let latest = self
+            .provider
+            .get_block_number()
+            .await
+            .context("should get latest block number")?;
+        let safe_block = latest.saturating_sub(self.chain_spec.chain_history_size);
+
+        let range = self.index.range(entry_point).unwrap_or_else(|| {
+            let start_block = self.settings.start_block.unwrap_or(safe_block);
+            IndexedRange {
+                start_block,
+                end_block: start_block,
+            }
+        });
+        if range.end_block > safe_block {
+            return Ok(true);
+        }
+
+        let from_block = range.end_block;
+        let to_block = safe_block.min(from_block + self.settings.max_block_range.saturating_sub(1));
+
+        let filter = Filter::new()
+            .address(entry_point)
+            .from_block(from_block)
+            .to_block(to_block);
+        let (event_logs, revert_logs) = futures_util::try_join!(
+            self.provider.get_logs(
+                &filter
+                    .clone()
+                    .event_signature(E::UserOperationEvent::SIGNATURE_HASH)
+            ),
+            self.provider.get_logs(
+                &filter
+                    .clone()
+                    .event_signature(E::UserOperationRevertReason::SIGNATURE_HASH)
+            ),
+        )
+        .context("should get user operation logs")?;
+
+        let mut revert_reasons: HashMap<B256, Bytes> = revert_logs
+            .into_iter()
+            .filter_map(|log| log.log_decode::<E::UserOperationRevertReason>().ok())
+            .map(|log| E::revert_reason(log.inner.data))
+            .collect();
+
+        let mut ops = Vec::with_capacity(event_logs.len());
+        for log in event_logs {
+            let event = log
+                .log_decode::<E::UserOperationEvent>()
+                .context("log should be a user operation event")?
+                .inner
+                .data;
+            let mut op = E::indexed_user_operation(event, entry_point, log);
+            if !op.success {
+                op.revert_reason = revert_reasons.remove(&op.user_op_hash);
+            }
+            ops.push(op);
+        }
+
+        let num_ops = ops.len();
+        self.index
+            .append(
+                entry_point,
+                ops,
+                IndexedRange {
+                    start_block: range.start_block,
+                    end_block: to_block + 1,
+                },
+            )
+            .await?;
+
+        tracing::debug!("Indexed {num_ops} user operations in blocks {from_block}..={to_block}");
+        self.metrics.indexed_block.set(to_block as f64);
+        self.metrics
+            .indexed_user_operations
+            .increment(num_ops as u64);
+
+        Ok(to_block == safe_block)
+    }
+}
+
+#[derive(Metrics)]
+#[metrics(scope = "rpc_indexer")]
+struct IndexerMetrics {
+    #[metric(describe = "the latest block written to the user operation index.")]
+    indexed_block: Gauge,
+    #[metric(describe = "the count of user operations written to the index.")]
+    indexed_user_operations: Counter,
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, Address, Log as PrimitiveLog, U256};
+    use rundler_contracts::v0_7::IEntryPoint::{UserOperationEvent, UserOperationRevertReason};
+    use rundler_provider::{Log, MockEvmProvider};
+
+    use super::{super::v0_7::EntryPointFiltersV0_7, *};
+
+    const SENDER: Address = address!("0000000000000000000000000000000000000001");
+
+    fn log(address: Address, event: &impl SolEvent, block_number: u64) -> Log {
+        Log {
+            inner: PrimitiveLog {
+                address,
+                data: event.encode_log_data(),
+            },
+            block_number: Some(block_number),
+            ..Default::default()
+        }
+    }
+
+    fn user_operation_event(hash: B256, success: bool) -> UserOperationEvent {
+        UserOperationEvent {
+            userOpHash: hash,
+            sender: SENDER,
+            paymaster: Address::ZERO,
+            nonce: U256::ZERO,
+            success,
+            actualGasCost: U256::from(1000),
+            actualGasUsed: U256::from(100),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_next_blocks() {
+        let chain_spec = ChainSpec {
+            chain_history_size: 10,
+            ..Default::default()
+        };
+        let entry_point = chain_spec.entry_point_address_v0_7;
+        let success_hash = B256::repeat_byte(1);
+        let failed_hash = B256::repeat_byte(2);
+
+        let mut provider = MockEvmProvider::default();
+        provider.expect_get_block_number().returning(|| Ok(100));
+        let event_logs = vec![
+            log(entry_point, &user_operation_event(success_hash, true), 60),
+            log(entry_point, &user_operation_event(failed_hash, false), 70),
+        ];
+        provider
+            .expect_get_logs()
+            .withf(|filter| filter.topics[0].matches(&UserOperationEvent::SIGNATURE_HASH))
+            .times(1)
+            .returning(move |_| Ok(event_logs.clone()));
+        let revert_logs = vec![log(
+            entry_point,
+            &UserOperationRevertReason {
+                userOpHash: failed_hash,
+                sender: SENDER,
+                nonce: U256::ZERO,
+                revertReason: Bytes::from_static(&[0xde, 0xad]),
+            },
+            70,
+        )];
+        provider
+            .expect_get_logs()
+            .withf(|filter| filter.topics[0].matches(&UserOperationRevertReason::SIGNATURE_HASH))
+            .times(1)
+            .returning(move |_| Ok(revert_logs.clone()));
+
+        let dir = std::env::temp_dir().join(format!("rundler-indexer-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let index = UserOperationIndex::open(&dir).unwrap();
+        let indexer = UserOperationIndexer::<_, EntryPointFiltersV0_7>::new(
+            chain_spec,
+            provider,
+            index.clone(),
+            IndexerSettings {
+                path: dir.clone(),
+                start_block: Some(50),
+                max_block_range: 1000,
+            },
+        );
+
+        assert!(indexer.index_next_blocks().await.unwrap());
+        assert_eq!(
+            index.range(entry_point),
+            Some(IndexedRange {
+                start_block: 50,
+                end_block: 91,
+            })
+        );
+        let success = index.get_by_hash(entry_point, success_hash).unwrap();
+        assert!(success.success);
+        assert_eq!(success.revert_reason, None);
+        let failed = index.get_by_hash(entry_point, failed_hash).unwrap();
+        assert!(!failed.success);
+        assert_eq!(
+            failed.revert_reason,
+            Some(Bytes::from_static(&[0xde, 0xad]))
+        );
+
+        // caught up, nothing left to query
+        assert!(indexer.index_next_blocks().await.unwrap());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -20,10 +20,17 @@ use crate::types::{RpcUserOperationByHash, RpcUserOperationReceipt};
 
 mod common;
 
+mod index;
+pub(crate) use index::{IndexedUserOperation, UserOperationIndex};
+mod indexer;
+pub use indexer::IndexerSettings;
+
 mod v0_6;
-pub(crate) use v0_6::UserOperationEventProviderV0_6;
+pub(crate) use v0_6::{UserOperationEventProviderV0_6, UserOperationIndexerV0_6};
 mod v0_7;
-pub(crate) use v0_7::UserOperationEventProviderV0_7;
+pub(crate) use v0_7::{UserOperationEventProviderV0_7, UserOperationIndexerV0_7};
+mod v0_8;
+pub(crate) use v0_8::{UserOperationEventProviderV0_8, UserOperationIndexerV0_8};
 
 #[async_trait::async_trait]
 pub(crate) trait UserOperationEventProvider: Send + Sync {
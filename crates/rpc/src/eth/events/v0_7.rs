@@ -19,12 +19,18 @@ use rundler_contracts::v0_7::IEntryPoint::{
 use rundler_provider::{Log, TransactionReceipt};
 use rundler_types::{chain::ChainSpec, v0_7::UserOperation};
 
-use super::common::{EntryPointEvents, UserOperationEventProviderImpl};
+use super::{
+    common::{EntryPointEvents, UserOperationEventProviderImpl},
+    index::IndexedUserOperation,
+    indexer::UserOperationIndexer,
+};
 use crate::types::RpcUserOperationReceipt;
 
 pub(crate) type UserOperationEventProviderV0_7<P> =
     UserOperationEventProviderImpl<P, EntryPointFiltersV0_7>;
 
+pub(crate) type UserOperationIndexerV0_7<P> = UserOperationIndexer<P, EntryPointFiltersV0_7>;
+
 pub(crate) struct EntryPointFiltersV0_7;
 
 impl EntryPointEvents for EntryPointFiltersV0_7 {
@@ -72,6 +78,29 @@ impl EntryPointEvents for EntryPointFiltersV0_7 {
         }
     }
 
+    fn indexed_user_operation(
+        event: Self::UserOperationEvent,
+        entry_point: Address,
+        log: Log,
+    ) -> IndexedUserOperation {
+        IndexedUserOperation {
+            entry_point,
+            user_op_hash: event.userOpHash,
+            sender: event.sender,
+            paymaster: event.paymaster,
+            nonce: event.nonce,
+            success: event.success,
+            actual_gas_cost: event.actualGasCost,
+            actual_gas_used: event.actualGasUsed,
+            revert_reason: None,
+            log,
+        }
+    }
+
+    fn revert_reason(event: Self::UserOperationRevertReason) -> (B256, Bytes) {
+        (event.userOpHash, event.revertReason)
+    }
+
     fn get_user_operations_from_tx_data(tx_data: Bytes, chain_spec: &ChainSpec) -> Vec<Self::UO> {
         let uos_per_agg = rundler_provider::decode_v0_7_ops_from_calldata(chain_spec, &tx_data);
 
@@ -0,0 +1,77 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use alloy_primitives::{Address, Bytes, B256};
+use rundler_contracts::v0_7::IEntryPoint::{UserOperationEvent, UserOperationRevertReason};
+use rundler_provider::{Log, TransactionReceipt};
+use rundler_types::{chain::ChainSpec, v0_8::UserOperation};
+
+use super::{
+    common::{EntryPointEvents, UserOperationEventProviderImpl},
+    index::IndexedUserOperation,
+    indexer::UserOperationIndexer,
+    v0_7::EntryPointFiltersV0_7,
+};
+use crate::types::RpcUserOperationReceipt;
+
+pub(crate) type UserOperationEventProviderV0_8<P> =
+    UserOperationEventProviderImpl<P, EntryPointFiltersV0_8>;
+
+pub(crate) type UserOperationIndexerV0_8<P> = UserOperationIndexer<P, EntryPointFiltersV0_8>;
+
+/// The v0.8 entry point emits the same events as v0.7
+pub(crate) struct EntryPointFiltersV0_8;
+
+impl EntryPointEvents for EntryPointFiltersV0_8 {
+    type UO = UserOperation;
+    type UserOperationEvent = UserOperationEvent;
+    type UserOperationRevertReason = UserOperationRevertReason;
+
+    fn construct_receipt(
+        event: Self::UserOperationEvent,
+        entry_point: Address,
+        logs: Vec<Log>,
+        tx_receipt: TransactionReceipt,
+    ) -> RpcUserOperationReceipt {
+        EntryPointFiltersV0_7::construct_receipt(event, entry_point, logs, tx_receipt)
+    }
+
+    fn indexed_user_operation(
+        event: Self::UserOperationEvent,
+        entry_point: Address,
+        log: Log,
+    ) -> IndexedUserOperation {
+        EntryPointFiltersV0_7::indexed_user_operation(event, entry_point, log)
+    }
+
+    fn revert_reason(event: Self::UserOperationRevertReason) -> (B256, Bytes) {
+        EntryPointFiltersV0_7::revert_reason(event)
+    }
+
+    fn get_user_operations_from_tx_data(tx_data: Bytes, chain_spec: &ChainSpec) -> Vec<Self::UO> {
+        let uos_per_agg = rundler_provider::decode_v0_8_ops_from_calldata(chain_spec, &tx_data);
+
+        uos_per_agg
+            .into_iter()
+            .flat_map(|uos_per_agg| uos_per_agg.user_ops)
+            .collect()
+    }
+
+    fn address(chain_spec: &ChainSpec) -> Address {
+        chain_spec.entry_point_address_v0_8
+    }
+
+    fn before_execution_selector() -> B256 {
+        EntryPointFiltersV0_7::before_execution_selector()
+    }
+}
@@ -20,7 +20,12 @@ pub(crate) use router::*;
 mod error;
 pub(crate) use error::{EthResult, EthRpcError};
 mod events;
-pub(crate) use events::{UserOperationEventProviderV0_6, UserOperationEventProviderV0_7};
+pub use events::IndexerSettings;
+pub(crate) use events::{
+    IndexedUserOperation, UserOperationEventProviderV0_6, UserOperationEventProviderV0_7,
+    UserOperationEventProviderV0_8, UserOperationIndex, UserOperationIndexerV0_6,
+    UserOperationIndexerV0_7, UserOperationIndexerV0_8,
+};
 mod server;
 
 use alloy_primitives::{Address, B256, U64};
@@ -37,7 +42,7 @@ use crate::types::{
 #[cfg_attr(test, automock)]
 pub trait EthApi {
     /// Sends a user operation to the pool.
-    #[method(name = "sendUserOperation")]
+    #[method(name = "sendUserOperation", with_extensions)]
     async fn send_user_operation(
         &self,
         op: RpcUserOperation,
@@ -38,6 +38,7 @@ pub(crate) struct EntryPointRouterBuilder {
     entry_points: Vec<Address>,
     v0_6: Option<(Address, Arc<dyn EntryPointRoute>)>,
     v0_7: Option<(Address, Arc<dyn EntryPointRoute>)>,
+    v0_8: Option<(Address, Arc<dyn EntryPointRoute>)>,
 }
 
 impl EntryPointRouterBuilder {
@@ -73,11 +74,28 @@ impl EntryPointRouterBuilder {
         self
     }
 
+    pub(crate) fn v0_8<R>(mut self, route: R) -> Self
+    where
+        R: EntryPointRoute + 'static,
+    {
+        if route.version() != EntryPointVersion::V0_8 {
+            panic!(
+                "Invalid entry point version for route: {:?}",
+                route.version()
+            );
+        }
+
+        self.entry_points.push(route.address());
+        self.v0_8 = Some((route.address(), Arc::new(route)));
+        self
+    }
+
     pub(crate) fn build(self) -> EntryPointRouter {
         EntryPointRouter {
             entry_points: self.entry_points,
             v0_6: self.v0_6,
             v0_7: self.v0_7,
+            v0_8: self.v0_8,
         }
     }
 }
@@ -87,6 +105,7 @@ pub(crate) struct EntryPointRouter {
     entry_points: Vec<Address>,
     v0_6: Option<(Address, Arc<dyn EntryPointRoute>)>,
     v0_7: Option<(Address, Arc<dyn EntryPointRoute>)>,
+    v0_8: Option<(Address, Arc<dyn EntryPointRoute>)>,
 }
 
 impl fmt::Debug for EntryPointRouter {
@@ -126,6 +145,15 @@ impl EntryPointRouter {
                 }
                 Ok(&self.v0_7.as_ref().unwrap().1)
             }
+            EntryPointVersion::V0_8 => {
+                if !matches!(uo, UserOperationVariant::V0_8(_)) {
+                    return Err(EthRpcError::InvalidParams(format!(
+                        "Invalid user operation for entry point: {:?}",
+                        entry_point
+                    )));
+                }
+                Ok(&self.v0_8.as_ref().unwrap().1)
+            }
             EntryPointVersion::Unspecified => unreachable!("unspecified entry point version"),
         }
     }
@@ -219,6 +247,25 @@ impl EntryPointRouter {
 
                 Ok(RpcGasEstimateV0_7::from(e).into())
             }
+            EntryPointVersion::V0_8 => {
+                if !matches!(uo, UserOperationOptionalGas::V0_8(_)) {
+                    return Err(EthRpcError::InvalidParams(format!(
+                        "Invalid user operation for entry point: {:?}",
+                        entry_point
+                    )));
+                }
+
+                let e = self
+                    .v0_8
+                    .as_ref()
+                    .unwrap()
+                    .1
+                    .estimate_gas(uo, state_override)
+                    .await?;
+
+                // v0.8 gas estimates share the v0.7 format
+                Ok(RpcGasEstimateV0_7::from(e).into())
+            }
             EntryPointVersion::Unspecified => unreachable!("unspecified entry point version"),
         }
     }
@@ -245,6 +292,11 @@ impl EntryPointRouter {
                 return Ok(EntryPointVersion::V0_7);
             }
         }
+        if let Some((addr, _)) = self.v0_8 {
+            if addr == *entry_point {
+                return Ok(EntryPointVersion::V0_8);
+            }
+        }
 
         Err(EthRpcError::InvalidParams(format!(
             "No entry point found for address: {:?}",
@@ -258,6 +310,7 @@ impl EntryPointRouter {
         match ep {
             EntryPointVersion::V0_6 => Ok(&self.v0_6.as_ref().unwrap().1),
             EntryPointVersion::V0_7 => Ok(&self.v0_7.as_ref().unwrap().1),
+            EntryPointVersion::V0_8 => Ok(&self.v0_8.as_ref().unwrap().1),
             EntryPointVersion::Unspecified => unreachable!("unspecified entry point version"),
         }
     }
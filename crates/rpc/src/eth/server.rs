@@ -12,6 +12,7 @@
 // If not, see https://www.gnu.org/licenses/.
 
 use alloy_primitives::{Address, B256, U64};
+use http::Extensions;
 use jsonrpsee::core::RpcResult;
 use rundler_provider::StateOverride;
 use rundler_types::{chain::IntoWithSpec, pool::Pool, UserOperationPermissions};
@@ -19,6 +20,7 @@ use tracing::instrument;
 
 use super::{api::EthApi, EthApiServer};
 use crate::{
+    auth::Authentication,
     types::{
         RpcGasEstimate, RpcUserOperation, RpcUserOperationByHash, RpcUserOperationOptionalGas,
         RpcUserOperationPermissions, RpcUserOperationReceipt,
@@ -34,17 +36,25 @@ where
     #[instrument(skip_all, fields(rpc_method = "eth_sendUserOperation"))]
     async fn send_user_operation(
         &self,
+        ext: &Extensions,
         op: RpcUserOperation,
         entry_point: Address,
         permissions: Option<RpcUserOperationPermissions>,
     ) -> RpcResult<B256> {
-        // if permissions are not enabled, default them
-        let mut permissions = if self.permissions_enabled {
-            permissions
-                .map(|p| p.into_with_spec(&self.chain_spec))
-                .unwrap_or_default()
-        } else {
-            UserOperationPermissions::default()
+        let requested = permissions
+            .map(|p| p.into_with_spec(&self.chain_spec))
+            .unwrap_or_default();
+
+        // authenticated callers get permissions up to their ceiling, tagged with the
+        // identity of their API key, else if permissions are not enabled, default them.
+        // The caller identity is never taken from the request.
+        let mut permissions = match ext.get::<Authentication>() {
+            Some(Authentication::Caller(caller)) => caller.permissions(requested),
+            _ if self.permissions_enabled => UserOperationPermissions {
+                caller_id: None,
+                ..requested
+            },
+            _ => UserOperationPermissions::default(),
         };
 
         // cap percentages at 100
@@ -55,7 +65,7 @@ where
             "eth_sendUserOperation",
             EthApi::send_user_operation(
                 self,
-                op.into_with_spec(&self.chain_spec),
+                op.into_variant_for_entry_point(&entry_point, &self.chain_spec),
                 entry_point,
                 permissions,
             ),
@@ -72,7 +82,12 @@ where
     ) -> RpcResult<RpcGasEstimate> {
         utils::safe_call_rpc_handler(
             "eth_estimateUserOperationGas",
-            EthApi::estimate_user_operation_gas(self, op.into(), entry_point, state_override),
+            EthApi::estimate_user_operation_gas(
+                self,
+                op.into_optional_gas_for_entry_point(&entry_point, &self.chain_spec),
+                entry_point,
+                state_override,
+            ),
         )
         .await
     }
@@ -19,6 +19,9 @@
 ))]
 //! JSON-RPC server for the Rundler.
 
+mod auth;
+pub use auth::{ApiKeyConfig, AuthConfig, CallerGrant, PermissionsCeiling};
+
 mod debug;
 pub use debug::DebugApiClient;
 
@@ -28,13 +31,18 @@ pub use admin::AdminApiClient;
 mod error;
 
 mod eth;
-pub use eth::{EthApiClient, EthApiSettings};
+pub use eth::{EthApiClient, EthApiSettings, IndexerSettings};
 
 mod health;
 
 mod rundler;
 pub use rundler::RundlerApiClient;
 
+mod status;
+
+mod subscription;
+pub use subscription::{BundleStatus, EthSubscriptionApiClient, OpStatus, RpcEvent};
+
 mod task;
 pub use task::{Args as RpcTaskArgs, RpcTask};
 
@@ -11,25 +11,28 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use alloy_primitives::{Address, B256, U128};
+use alloy_primitives::{Address, B256, U128, U64};
 use anyhow::Context;
 use async_trait::async_trait;
 use futures_util::future;
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
 use rundler_provider::{EvmProvider, FeeEstimator};
-use rundler_types::{
-    chain::{ChainSpec, IntoWithSpec},
-    pool::Pool,
-    UserOperation, UserOperationVariant,
-};
+use rundler_types::{chain::ChainSpec, pool::Pool, UserOperation, UserOperationVariant};
 use tracing::instrument;
 
 use crate::{
-    eth::{EntryPointRouter, EthResult, EthRpcError},
-    types::{RpcMinedUserOperation, RpcUserOperation},
+    eth::{EntryPointRouter, EthResult, EthRpcError, IndexedUserOperation, UserOperationIndex},
+    status::OpStatusCache,
+    types::{
+        RpcBlockRange, RpcIndexedUserOperation, RpcMinedUserOperation, RpcUserOperation,
+        RpcUserOperationStatus, RpcUserOperationStatusUpdate,
+    },
     utils,
 };
 
+/// Maximum number of user operations returned by the index queries
+const MAX_INDEXED_USER_OPERATIONS: usize = 1000;
+
 #[rpc(client, server, namespace = "rundler")]
 pub trait RundlerApi {
     /// Returns the maximum priority fee per gas required by Rundler
@@ -59,6 +62,35 @@ pub trait RundlerApi {
         tx_hash: B256,
         entry_point: Address,
     ) -> RpcResult<Option<RpcMinedUserOperation>>;
+
+    /// Gets the latest known status of a user operation by user operation hash
+    ///
+    /// Returns none if the user operation is unknown.
+    #[method(name = "getUserOperationStatus")]
+    async fn get_user_operation_status(
+        &self,
+        uo_hash: B256,
+    ) -> RpcResult<Option<RpcUserOperationStatusUpdate>>;
+
+    /// Gets the mined user operations of a sender, oldest first
+    ///
+    /// Requires the user operation indexer. Returns at most 1000 user operations.
+    #[method(name = "getUserOperationsBySender")]
+    async fn get_user_operations_by_sender(
+        &self,
+        sender: Address,
+        block_range: Option<RpcBlockRange>,
+    ) -> RpcResult<Vec<RpcIndexedUserOperation>>;
+
+    /// Gets the mined user operations sponsored by a paymaster, oldest first
+    ///
+    /// Requires the user operation indexer. Returns at most 1000 user operations.
+    #[method(name = "getUserOperationsByPaymaster")]
+    async fn get_user_operations_by_paymaster(
+        &self,
+        paymaster: Address,
+        block_range: Option<RpcBlockRange>,
+    ) -> RpcResult<Vec<RpcIndexedUserOperation>>;
 }
 
 pub(crate) struct RundlerApi<P, F, E> {
@@ -67,6 +99,8 @@ pub(crate) struct RundlerApi<P, F, E> {
     pool_server: P,
     entry_point_router: EntryPointRouter,
     evm_provider: E,
+    status_cache: Option<OpStatusCache>,
+    index: Option<UserOperationIndex>,
 }
 
 #[async_trait]
@@ -111,6 +145,48 @@ where
         )
         .await
     }
+
+    #[instrument(skip_all, fields(rpc_method = "rundler_getUserOperationStatus"))]
+    async fn get_user_operation_status(
+        &self,
+        uo_hash: B256,
+    ) -> RpcResult<Option<RpcUserOperationStatusUpdate>> {
+        utils::safe_call_rpc_handler(
+            "rundler_getUserOperationStatus",
+            RundlerApi::get_user_operation_status(self, uo_hash),
+        )
+        .await
+    }
+
+    #[instrument(skip_all, fields(rpc_method = "rundler_getUserOperationsBySender"))]
+    async fn get_user_operations_by_sender(
+        &self,
+        sender: Address,
+        block_range: Option<RpcBlockRange>,
+    ) -> RpcResult<Vec<RpcIndexedUserOperation>> {
+        utils::safe_call_rpc_handler(
+            "rundler_getUserOperationsBySender",
+            RundlerApi::get_indexed_user_operations(self, block_range, |index, from, to| {
+                index.get_by_sender(sender, from, to, MAX_INDEXED_USER_OPERATIONS)
+            }),
+        )
+        .await
+    }
+
+    #[instrument(skip_all, fields(rpc_method = "rundler_getUserOperationsByPaymaster"))]
+    async fn get_user_operations_by_paymaster(
+        &self,
+        paymaster: Address,
+        block_range: Option<RpcBlockRange>,
+    ) -> RpcResult<Vec<RpcIndexedUserOperation>> {
+        utils::safe_call_rpc_handler(
+            "rundler_getUserOperationsByPaymaster",
+            RundlerApi::get_indexed_user_operations(self, block_range, |index, from, to| {
+                index.get_by_paymaster(paymaster, from, to, MAX_INDEXED_USER_OPERATIONS)
+            }),
+        )
+        .await
+    }
 }
 
 impl<P, F, E> RundlerApi<P, F, E>
@@ -125,6 +201,8 @@ where
         pool_server: P,
         fee_estimator: F,
         evm_provider: E,
+        status_cache: Option<OpStatusCache>,
+        index: Option<UserOperationIndex>,
     ) -> Self {
         Self {
             chain_spec: chain_spec.clone(),
@@ -132,6 +210,8 @@ where
             pool_server,
             fee_estimator,
             evm_provider,
+            status_cache,
+            index,
         }
     }
     #[instrument(skip_all)]
@@ -153,7 +233,8 @@ where
         user_op: RpcUserOperation,
         entry_point: Address,
     ) -> EthResult<Option<B256>> {
-        let uo: UserOperationVariant = user_op.into_with_spec(&self.chain_spec);
+        let uo: UserOperationVariant =
+            user_op.into_variant_for_entry_point(&entry_point, &self.chain_spec);
         let id = uo.id();
 
         if uo.pre_verification_gas() != 0
@@ -224,4 +305,77 @@ where
             _ => Ok(None),
         }
     }
+
+    async fn get_user_operation_status(
+        &self,
+        uo_hash: B256,
+    ) -> EthResult<Option<RpcUserOperationStatusUpdate>> {
+        if uo_hash == B256::ZERO {
+            return Err(EthRpcError::InvalidParams(
+                "Missing/invalid userOpHash".to_string(),
+            ));
+        }
+
+        if let Some(status) = self.status_cache.as_ref().and_then(|c| c.get(uo_hash)) {
+            return Ok(Some(status));
+        }
+
+        // Not tracked, either because it was seen before the cache was populated or because
+        // the events aren't available to this server. Fall back to the pool and the chain.
+        if let Some(op) = self.pool_server.get_op_by_hash(uo_hash).await? {
+            return Ok(Some(RpcUserOperationStatusUpdate {
+                user_operation_hash: uo_hash,
+                entry_point: op.entry_point.into(),
+                status: RpcUserOperationStatus::Pending { reason: None },
+            }));
+        }
+
+        let futs = self
+            .entry_point_router
+            .entry_points()
+            .map(|ep| self.entry_point_router.get_mined_by_hash(ep, uo_hash));
+        let mined = future::try_join_all(futs)
+            .await?
+            .into_iter()
+            .flatten()
+            .find_map(|uo| {
+                Some(RpcUserOperationStatusUpdate {
+                    user_operation_hash: uo_hash,
+                    entry_point: uo.entry_point,
+                    status: RpcUserOperationStatus::Mined {
+                        transaction_hash: uo.transaction_hash?,
+                        block_number: U64::from(uo.block_number?.to::<u64>()),
+                        block_hash: uo.block_hash?,
+                    },
+                })
+            });
+
+        Ok(mined)
+    }
+
+    async fn get_indexed_user_operations(
+        &self,
+        block_range: Option<RpcBlockRange>,
+        query: impl FnOnce(&UserOperationIndex, u64, u64) -> Vec<IndexedUserOperation>,
+    ) -> EthResult<Vec<RpcIndexedUserOperation>> {
+        let Some(index) = &self.index else {
+            return Err(EthRpcError::InvalidParams(
+                "user operation indexer is not enabled".to_string(),
+            ));
+        };
+
+        let block_range = block_range.unwrap_or_default();
+        let from_block = block_range.from_block.map_or(0, |b| b.to());
+        let to_block = block_range.to_block.map_or(u64::MAX, |b| b.to());
+        if from_block > to_block {
+            return Err(EthRpcError::InvalidParams(
+                "fromBlock must not be greater than toBlock".to_string(),
+            ));
+        }
+
+        Ok(query(index, from_block, to_block)
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
 }
@@ -0,0 +1,365 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::sync::Arc;
+
+use alloy_primitives::{Address, B256};
+use parking_lot::Mutex;
+use rundler_utils::{cache::LruMap, emit::WithEntryPoint};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{info, warn};
+
+use crate::{
+    subscription::{BundleStatus, RpcEvent},
+    types::{RpcUserOperationStatus, RpcUserOperationStatusUpdate},
+};
+
+/// Bounded cache of the latest known status of recently seen user operations,
+/// populated from the pool and builder events.
+#[derive(Clone)]
+pub(crate) struct OpStatusCache {
+    statuses: Arc<Mutex<LruMap<B256, (Address, RpcUserOperationStatus)>>>,
+    /// Bundle transactions sent by each builder, by builder tag and nonce
+    bundles: Arc<Mutex<LruMap<(String, u64), SubmittedBundles>>>,
+}
+
+/// Transactions sent at a builder nonce, and the operations they include
+#[derive(Default)]
+struct SubmittedBundles {
+    tx_hashes: Vec<B256>,
+    op_hashes: Vec<B256>,
+}
+
+impl OpStatusCache {
+    pub(crate) fn new(max_size: u32) -> Self {
+        Self {
+            statuses: Arc::new(Mutex::new(LruMap::new(max_size))),
+            bundles: Arc::new(Mutex::new(LruMap::new(max_size))),
+        }
+    }
+
+    pub(crate) fn get(&self, op_hash: B256) -> Option<RpcUserOperationStatusUpdate> {
+        self.statuses
+            .lock()
+            .get(&op_hash)
+            .map(|(entry_point, status)| RpcUserOperationStatusUpdate {
+                user_operation_hash: op_hash,
+                entry_point: (*entry_point).into(),
+                status: status.clone(),
+            })
+    }
+
+    /// Updates the cache from the event stream until it closes
+    pub(crate) async fn run(self, mut rx: broadcast::Receiver<WithEntryPoint<RpcEvent>>) {
+        loop {
+            match rx.recv().await {
+                Ok(event) => self.update(event),
+                Err(RecvError::Lagged(count)) => {
+                    warn!("Op status cache lagged. Missed {count} events.")
+                }
+                Err(RecvError::Closed) => {
+                    info!("Event stream for op status cache closed");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn update(&self, event: WithEntryPoint<RpcEvent>) {
+        let entry_point = event.entry_point;
+        let (op_hash, status) = match event.event {
+            RpcEvent::NewPendingOp { op_hash, .. } => {
+                (op_hash, RpcUserOperationStatus::Pending { reason: None })
+            }
+            RpcEvent::OpStatus { op_hash, status } => (op_hash, status.into()),
+            RpcEvent::Bundle {
+                builder_tag,
+                status,
+            } => {
+                self.update_bundle(builder_tag, status);
+                return;
+            }
+        };
+
+        let mut statuses = self.statuses.lock();
+        let current = statuses.get(&op_hash).map(|(_, status)| status);
+        let keep_current = match (current, &status) {
+            // A skip only adds a reason to an op that is still waiting for a bundle
+            (
+                Some(RpcUserOperationStatus::Bundled { .. })
+                | Some(RpcUserOperationStatus::Mined { .. }),
+                RpcUserOperationStatus::Pending { reason: Some(_) },
+            ) => true,
+            // Rejected ops are removed from the pool, keep the more specific rejection reason
+            (
+                Some(RpcUserOperationStatus::Rejected { .. }),
+                RpcUserOperationStatus::Dropped { .. },
+            ) => true,
+            _ => false,
+        };
+        if !keep_current {
+            statuses.insert(op_hash, (entry_point, status));
+        }
+    }
+
+    /// Tracks the transactions sent at each builder nonce. When the nonce is dropped, or
+    /// mined by another transaction, the operations of its unmined transactions are moved
+    /// back to pending.
+    fn update_bundle(&self, builder_tag: String, status: BundleStatus) {
+        let (key, mined_tx_hash) = match status {
+            BundleStatus::Submitted {
+                tx_hash,
+                nonce,
+                op_hashes,
+            } => {
+                let mut bundles = self.bundles.lock();
+                if let Some(submitted) =
+                    bundles.get_or_insert((builder_tag, nonce), SubmittedBundles::default)
+                {
+                    submitted.tx_hashes.push(tx_hash);
+                    submitted.op_hashes.extend(op_hashes);
+                }
+                return;
+            }
+            BundleStatus::Mined { tx_hash, nonce, .. } => ((builder_tag, nonce), Some(tx_hash)),
+            BundleStatus::Dropped { nonce } => ((builder_tag, nonce), None),
+        };
+        let Some(submitted) = self.bundles.lock().remove(&key) else {
+            return;
+        };
+
+        let mut statuses = self.statuses.lock();
+        for op_hash in submitted.op_hashes {
+            let Some((_, status)) = statuses.get(&op_hash) else {
+                continue;
+            };
+            if let RpcUserOperationStatus::Bundled {
+                transaction_hash, ..
+            } = status
+            {
+                if submitted.tx_hashes.contains(transaction_hash)
+                    && Some(*transaction_hash) != mined_tx_hash
+                {
+                    *status = RpcUserOperationStatus::Pending { reason: None };
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subscription::OpStatus;
+
+    fn event(op_hash: B256, status: OpStatus) -> WithEntryPoint<RpcEvent> {
+        WithEntryPoint {
+            entry_point: Address::ZERO,
+            event: RpcEvent::OpStatus { op_hash, status },
+        }
+    }
+
+    fn status(cache: &OpStatusCache, op_hash: B256) -> Option<RpcUserOperationStatus> {
+        cache.get(op_hash).map(|s| s.status)
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let cache = OpStatusCache::new(10);
+        let hash = B256::repeat_byte(1);
+        let tx_hash = B256::repeat_byte(2);
+
+        cache.update(event(
+            hash,
+            OpStatus::Skipped {
+                reason: "InsufficientFees".to_string(),
+            },
+        ));
+        assert_eq!(
+            status(&cache, hash),
+            Some(RpcUserOperationStatus::Pending {
+                reason: Some("InsufficientFees".to_string())
+            })
+        );
+
+        cache.update(event(
+            hash,
+            OpStatus::Bundled {
+                tx_hash,
+                builder_tag: "builder".to_string(),
+            },
+        ));
+        // a skip from a later bundle attempt doesn't override the in flight bundle
+        cache.update(event(
+            hash,
+            OpStatus::Skipped {
+                reason: "InsufficientFees".to_string(),
+            },
+        ));
+        assert_eq!(
+            status(&cache, hash),
+            Some(RpcUserOperationStatus::Bundled {
+                transaction_hash: tx_hash,
+                builder: "builder".to_string(),
+            })
+        );
+
+        cache.update(event(
+            hash,
+            OpStatus::Mined {
+                tx_hash,
+                block_number: 1,
+                block_hash: B256::ZERO,
+            },
+        ));
+        assert!(matches!(
+            status(&cache, hash),
+            Some(RpcUserOperationStatus::Mined { .. })
+        ));
+    }
+
+    fn bundle(builder_tag: &str, status: BundleStatus) -> WithEntryPoint<RpcEvent> {
+        WithEntryPoint {
+            entry_point: Address::ZERO,
+            event: RpcEvent::Bundle {
+                builder_tag: builder_tag.to_string(),
+                status,
+            },
+        }
+    }
+
+    fn submit(cache: &OpStatusCache, tx_hash: B256, nonce: u64, op_hashes: &[B256]) {
+        for op_hash in op_hashes {
+            cache.update(event(
+                *op_hash,
+                OpStatus::Bundled {
+                    tx_hash,
+                    builder_tag: "builder".to_string(),
+                },
+            ));
+        }
+        cache.update(bundle(
+            "builder",
+            BundleStatus::Submitted {
+                tx_hash,
+                nonce,
+                op_hashes: op_hashes.to_vec(),
+            },
+        ));
+    }
+
+    #[test]
+    fn test_dropped_bundle_returns_to_pending() {
+        let cache = OpStatusCache::new(10);
+        let hash = B256::repeat_byte(1);
+        submit(&cache, B256::repeat_byte(2), 5, &[hash]);
+
+        // another builder's nonce doesn't affect the op
+        cache.update(bundle("other", BundleStatus::Dropped { nonce: 5 }));
+        assert!(matches!(
+            status(&cache, hash),
+            Some(RpcUserOperationStatus::Bundled { .. })
+        ));
+
+        cache.update(bundle("builder", BundleStatus::Dropped { nonce: 5 }));
+        assert_eq!(
+            status(&cache, hash),
+            Some(RpcUserOperationStatus::Pending { reason: None })
+        );
+    }
+
+    #[test]
+    fn test_replaced_bundle_returns_to_pending() {
+        let cache = OpStatusCache::new(10);
+        let kept = B256::repeat_byte(1);
+        let removed = B256::repeat_byte(2);
+        let first_tx = B256::repeat_byte(3);
+        let second_tx = B256::repeat_byte(4);
+
+        submit(&cache, first_tx, 5, &[kept, removed]);
+        // replaced by a transaction without one of the ops
+        submit(&cache, second_tx, 5, &[kept]);
+        cache.update(event(
+            kept,
+            OpStatus::Mined {
+                tx_hash: second_tx,
+                block_number: 1,
+                block_hash: B256::ZERO,
+            },
+        ));
+        cache.update(bundle(
+            "builder",
+            BundleStatus::Mined {
+                tx_hash: second_tx,
+                nonce: 5,
+                block_number: 1,
+            },
+        ));
+
+        assert!(matches!(
+            status(&cache, kept),
+            Some(RpcUserOperationStatus::Mined { .. })
+        ));
+        assert_eq!(
+            status(&cache, removed),
+            Some(RpcUserOperationStatus::Pending { reason: None })
+        );
+    }
+
+    #[test]
+    fn test_rejected_keeps_reason() {
+        let cache = OpStatusCache::new(10);
+        let hash = B256::repeat_byte(1);
+
+        cache.update(event(
+            hash,
+            OpStatus::Rejected {
+                reason: "FailedRevalidation".to_string(),
+            },
+        ));
+        cache.update(event(
+            hash,
+            OpStatus::Dropped {
+                reason: "Requested".to_string(),
+            },
+        ));
+        assert_eq!(
+            status(&cache, hash),
+            Some(RpcUserOperationStatus::Rejected {
+                reason: "FailedRevalidation".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_bounded() {
+        let cache = OpStatusCache::new(1);
+        let first = B256::repeat_byte(1);
+        let second = B256::repeat_byte(2);
+
+        cache.update(event(
+            first,
+            OpStatus::Replaced {
+                replaced_by: second,
+            },
+        ));
+        cache.update(event(
+            second,
+            OpStatus::Dropped {
+                reason: "Expired".to_string(),
+            },
+        ));
+        assert_eq!(status(&cache, first), None);
+        assert!(status(&cache, second).is_some());
+    }
+}
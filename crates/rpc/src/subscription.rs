@@ -0,0 +1,410 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use alloy_primitives::{B256, U64};
+use async_trait::async_trait;
+use jsonrpsee::{
+    core::SubscriptionResult, proc_macros::rpc, PendingSubscriptionSink, SubscriptionMessage,
+};
+use rundler_types::UserOperationVariant;
+use rundler_utils::emit::WithEntryPoint;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::{
+    eth::EthRpcError,
+    types::{
+        RpcBundleUpdate, RpcPendingUserOperation, RpcSubscriptionKind, RpcSubscriptionResult,
+        RpcUserOperationStatus, RpcUserOperationStatusUpdate,
+    },
+};
+
+/// An event from the pool or builder that is forwarded to RPC subscribers
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum RpcEvent {
+    /// A user operation was accepted into the mempool
+    NewPendingOp {
+        /// Operation hash
+        op_hash: B256,
+        /// The full operation
+        op: UserOperationVariant,
+    },
+    /// The status of a user operation changed
+    OpStatus {
+        /// Operation hash
+        op_hash: B256,
+        /// The new status
+        status: OpStatus,
+    },
+    /// The status of a bundle transaction changed
+    Bundle {
+        /// Tag of the builder that sent the bundle
+        builder_tag: String,
+        /// The new status
+        status: BundleStatus,
+    },
+}
+
+/// Status of a user operation after it leaves the pending state
+#[derive(Clone, Debug)]
+pub enum OpStatus {
+    /// Included in a bundle transaction that has not been mined yet
+    Bundled {
+        /// Bundle transaction hash
+        tx_hash: B256,
+        /// Tag of the builder that sent the bundle
+        builder_tag: String,
+    },
+    /// Mined on chain
+    Mined {
+        /// Mined in transaction hash
+        tx_hash: B256,
+        /// Mined at block number
+        block_number: u64,
+        /// Mined at block hash
+        block_hash: B256,
+    },
+    /// Removed from the pool without being mined
+    Dropped {
+        /// Removal reason
+        reason: String,
+    },
+    /// Rejected by the builder during bundle formation
+    Rejected {
+        /// Rejection reason
+        reason: String,
+    },
+    /// Skipped by the builder during bundle formation, but remains in the pool
+    Skipped {
+        /// Skip reason
+        reason: String,
+    },
+    /// Replaced in the pool by another operation with the same sender and nonce
+    Replaced {
+        /// Hash of the replacing operation
+        replaced_by: B256,
+    },
+}
+
+/// Status of a bundle transaction
+#[derive(Clone, Debug)]
+pub enum BundleStatus {
+    /// A bundle transaction was sent
+    Submitted {
+        /// Transaction hash
+        tx_hash: B256,
+        /// Transaction nonce
+        nonce: u64,
+        /// Hashes of the operations in the bundle
+        op_hashes: Vec<B256>,
+    },
+    /// A bundle transaction was mined
+    Mined {
+        /// Transaction hash
+        tx_hash: B256,
+        /// Transaction nonce
+        nonce: u64,
+        /// Block number containing the transaction
+        block_number: u64,
+    },
+    /// The latest bundle transaction was dropped
+    Dropped {
+        /// Nonce of the dropped transaction
+        nonce: u64,
+    },
+}
+
+/// Eth subscription API
+#[rpc(client, server, namespace = "eth")]
+pub trait EthSubscriptionApi {
+    /// Subscribes to user operation and bundle events.
+    ///
+    /// `op_hash` is required for, and only used by, the `userOperationStatus` topic.
+    #[subscription(name = "subscribe" => "subscription", unsubscribe = "unsubscribe", item = RpcSubscriptionResult)]
+    async fn subscribe(
+        &self,
+        kind: RpcSubscriptionKind,
+        op_hash: Option<B256>,
+    ) -> SubscriptionResult;
+}
+
+pub(crate) struct EthSubscriptionApi {
+    event_sender: broadcast::Sender<WithEntryPoint<RpcEvent>>,
+}
+
+impl EthSubscriptionApi {
+    pub(crate) fn new(event_sender: broadcast::Sender<WithEntryPoint<RpcEvent>>) -> Self {
+        Self { event_sender }
+    }
+}
+
+#[async_trait]
+impl EthSubscriptionApiServer for EthSubscriptionApi {
+    async fn subscribe(
+        &self,
+        pending: PendingSubscriptionSink,
+        kind: RpcSubscriptionKind,
+        op_hash: Option<B256>,
+    ) -> SubscriptionResult {
+        if kind == RpcSubscriptionKind::UserOperationStatus && op_hash.is_none() {
+            pending
+                .reject(EthRpcError::InvalidParams(
+                    "userOperationStatus subscription requires a user operation hash".to_string(),
+                ))
+                .await;
+            return Ok(());
+        }
+
+        let mut rx = self.event_sender.subscribe();
+        let sink = pending.accept().await?;
+
+        loop {
+            tokio::select! {
+                _ = sink.closed() => break,
+                event = rx.recv() => match event {
+                    Ok(event) => {
+                        let Some(item) = to_subscription_result(kind, op_hash, event) else {
+                            continue;
+                        };
+                        if sink.send(SubscriptionMessage::from_json(&item)?).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(count)) => {
+                        warn!("Subscription {kind:?} lagged. Missed {count} events.");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn to_subscription_result(
+    kind: RpcSubscriptionKind,
+    op_hash: Option<B256>,
+    event: WithEntryPoint<RpcEvent>,
+) -> Option<RpcSubscriptionResult> {
+    let entry_point = event.entry_point.into();
+    match (kind, event.event) {
+        (RpcSubscriptionKind::NewPendingUserOperations, RpcEvent::NewPendingOp { op_hash, op }) => {
+            Some(RpcSubscriptionResult::PendingUserOperation(
+                RpcPendingUserOperation {
+                    user_operation: op.into(),
+                    user_operation_hash: op_hash,
+                    entry_point,
+                },
+            ))
+        }
+        (
+            RpcSubscriptionKind::UserOperationStatus,
+            RpcEvent::NewPendingOp { op_hash: hash, .. },
+        ) if Some(hash) == op_hash => Some(RpcSubscriptionResult::UserOperationStatus(
+            RpcUserOperationStatusUpdate {
+                user_operation_hash: hash,
+                entry_point,
+                status: RpcUserOperationStatus::Pending { reason: None },
+            },
+        )),
+        (
+            RpcSubscriptionKind::UserOperationStatus,
+            RpcEvent::OpStatus {
+                op_hash: hash,
+                status,
+            },
+        ) if Some(hash) == op_hash => Some(RpcSubscriptionResult::UserOperationStatus(
+            RpcUserOperationStatusUpdate {
+                user_operation_hash: hash,
+                entry_point,
+                status: status.into(),
+            },
+        )),
+        (
+            RpcSubscriptionKind::Bundles,
+            RpcEvent::Bundle {
+                builder_tag,
+                status,
+            },
+        ) => {
+            let update = match status {
+                BundleStatus::Submitted {
+                    tx_hash,
+                    nonce,
+                    op_hashes,
+                } => RpcBundleUpdate::Submitted {
+                    entry_point,
+                    builder: builder_tag,
+                    transaction_hash: tx_hash,
+                    nonce: U64::from(nonce),
+                    user_operation_hashes: op_hashes,
+                },
+                BundleStatus::Mined {
+                    tx_hash,
+                    nonce,
+                    block_number,
+                } => RpcBundleUpdate::Mined {
+                    entry_point,
+                    builder: builder_tag,
+                    transaction_hash: tx_hash,
+                    nonce: U64::from(nonce),
+                    block_number: U64::from(block_number),
+                },
+                BundleStatus::Dropped { nonce } => RpcBundleUpdate::Dropped {
+                    entry_point,
+                    builder: builder_tag,
+                    nonce: U64::from(nonce),
+                },
+            };
+            Some(RpcSubscriptionResult::Bundle(update))
+        }
+        _ => None,
+    }
+}
+
+impl From<OpStatus> for RpcUserOperationStatus {
+    fn from(status: OpStatus) -> Self {
+        match status {
+            OpStatus::Bundled {
+                tx_hash,
+                builder_tag,
+            } => RpcUserOperationStatus::Bundled {
+                transaction_hash: tx_hash,
+                builder: builder_tag,
+            },
+            OpStatus::Mined {
+                tx_hash,
+                block_number,
+                block_hash,
+            } => RpcUserOperationStatus::Mined {
+                transaction_hash: tx_hash,
+                block_number: U64::from(block_number),
+                block_hash,
+            },
+            OpStatus::Dropped { reason } => RpcUserOperationStatus::Dropped { reason },
+            OpStatus::Rejected { reason } => RpcUserOperationStatus::Rejected { reason },
+            OpStatus::Skipped { reason } => RpcUserOperationStatus::Pending {
+                reason: Some(reason),
+            },
+            OpStatus::Replaced { replaced_by } => RpcUserOperationStatus::Replaced { replaced_by },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, Address};
+
+    use super::*;
+
+    const ENTRY_POINT: Address = address!("0000000071727De22E5E9d8BAf0edAc6f37da032");
+
+    fn with_entry_point(event: RpcEvent) -> WithEntryPoint<RpcEvent> {
+        WithEntryPoint {
+            entry_point: ENTRY_POINT,
+            event,
+        }
+    }
+
+    #[test]
+    fn test_status_filters_by_hash() {
+        let hash = B256::repeat_byte(1);
+        let status = || {
+            with_entry_point(RpcEvent::OpStatus {
+                op_hash: hash,
+                status: OpStatus::Dropped {
+                    reason: "Expired".to_string(),
+                },
+            })
+        };
+
+        assert_eq!(
+            to_subscription_result(
+                RpcSubscriptionKind::UserOperationStatus,
+                Some(hash),
+                status()
+            ),
+            Some(RpcSubscriptionResult::UserOperationStatus(
+                RpcUserOperationStatusUpdate {
+                    user_operation_hash: hash,
+                    entry_point: ENTRY_POINT.into(),
+                    status: RpcUserOperationStatus::Dropped {
+                        reason: "Expired".to_string()
+                    },
+                }
+            ))
+        );
+        assert_eq!(
+            to_subscription_result(
+                RpcSubscriptionKind::UserOperationStatus,
+                Some(B256::repeat_byte(2)),
+                status()
+            ),
+            None
+        );
+        assert_eq!(
+            to_subscription_result(RpcSubscriptionKind::Bundles, None, status()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_bundle_update() {
+        let event = with_entry_point(RpcEvent::Bundle {
+            builder_tag: "builder".to_string(),
+            status: BundleStatus::Mined {
+                tx_hash: B256::repeat_byte(3),
+                nonce: 7,
+                block_number: 100,
+            },
+        });
+
+        assert_eq!(
+            to_subscription_result(RpcSubscriptionKind::Bundles, None, event.clone()),
+            Some(RpcSubscriptionResult::Bundle(RpcBundleUpdate::Mined {
+                entry_point: ENTRY_POINT.into(),
+                builder: "builder".to_string(),
+                transaction_hash: B256::repeat_byte(3),
+                nonce: U64::from(7),
+                block_number: U64::from(100),
+            }))
+        );
+        assert_eq!(
+            to_subscription_result(RpcSubscriptionKind::NewPendingUserOperations, None, event),
+            None
+        );
+    }
+
+    #[test]
+    fn test_status_serialization() {
+        let update = RpcUserOperationStatusUpdate {
+            user_operation_hash: B256::ZERO,
+            entry_point: ENTRY_POINT.into(),
+            status: RpcUserOperationStatus::Bundled {
+                transaction_hash: B256::ZERO,
+                builder: "builder".to_string(),
+            },
+        };
+
+        let json = serde_json::to_value(&update).unwrap();
+        assert_eq!(json["status"], "bundled");
+        assert_eq!(json["builder"], "builder");
+        assert!(json.get("transactionHash").is_some());
+        assert_eq!(
+            serde_json::from_value::<RpcUserOperationStatusUpdate>(json).unwrap(),
+            update
+        );
+    }
+}
@@ -21,27 +21,40 @@ use jsonrpsee::{
     RpcModule,
 };
 use rundler_provider::{FeeEstimator, Providers as ProvidersT};
-use rundler_sim::{EstimationSettings, GasEstimatorV0_6, GasEstimatorV0_7, PrecheckSettings};
+use rundler_sim::{
+    EstimationEntryPoint, EstimationSettings, GasEstimatorV0_6, GasEstimatorV0_7, GasEstimatorV0_8,
+    PrecheckSettings,
+};
 use rundler_task::{
     server::{format_socket_addr, HealthCheck},
     TaskSpawnerExt,
 };
 use rundler_types::{builder::Builder as BuilderT, chain::ChainSpec, pool::Pool as PoolT};
+use rundler_utils::emit::WithEntryPoint;
+use tokio::sync::broadcast;
 use tracing::info;
 
 use crate::{
     admin::{AdminApi, AdminApiServer},
+    auth::{AuthConfig, AuthHttpLayer, AuthRpcLayer, Authenticator},
     debug::{DebugApi, DebugApiServer},
     eth::{
         EntryPointRouteImpl, EntryPointRouter, EntryPointRouterBuilder, EthApi, EthApiServer,
-        EthApiSettings, UserOperationEventProviderV0_6, UserOperationEventProviderV0_7,
+        EthApiSettings, IndexerSettings, UserOperationEventProviderV0_6,
+        UserOperationEventProviderV0_7, UserOperationEventProviderV0_8, UserOperationIndex,
+        UserOperationIndexerV0_6, UserOperationIndexerV0_7, UserOperationIndexerV0_8,
     },
     health::{HealthChecker, SystemApiServer},
     rpc_metrics::{HttpMetricMiddlewareLayer, RpcMetricsMiddlewareLayer},
     rundler::{RundlerApi, RundlerApiServer},
+    status::OpStatusCache,
+    subscription::{EthSubscriptionApi, EthSubscriptionApiServer, RpcEvent},
     types::ApiNamespace,
 };
 
+/// Number of user operations tracked by `rundler_getUserOperationStatus`
+const OP_STATUS_CACHE_SIZE: u32 = 100_000;
+
 /// RPC server arguments.
 #[derive(Debug)]
 pub struct Args {
@@ -71,8 +84,14 @@ pub struct Args {
     pub entry_point_v0_6_enabled: bool,
     /// Whether to enable entry point v0.7.
     pub entry_point_v0_7_enabled: bool,
+    /// Whether to enable entry point v0.8.
+    pub entry_point_v0_8_enabled: bool,
     /// What domains to use in the corsdomain
     pub corsdomain: Option<Vec<HeaderValue>>,
+    /// API key authentication config, requests are not authenticated if not set
+    pub auth: Option<AuthConfig>,
+    /// User operation event indexer settings, events are only queried from the node if not set
+    pub indexer: Option<IndexerSettings>,
 }
 
 /// JSON-RPC server task.
@@ -82,16 +101,27 @@ pub struct RpcTask<Pool, Builder, Providers> {
     pool: Pool,
     builder: Builder,
     providers: Providers,
+    event_sender: Option<broadcast::Sender<WithEntryPoint<RpcEvent>>>,
 }
 
 impl<Pool, Builder, Providers> RpcTask<Pool, Builder, Providers> {
     /// Creates a new RPC server task.
-    pub fn new(args: Args, pool: Pool, builder: Builder, providers: Providers) -> Self {
+    ///
+    /// If an event sender is provided, the server also accepts WebSocket connections
+    /// and serves `eth_subscribe` from the events sent on it.
+    pub fn new(
+        args: Args,
+        pool: Pool,
+        builder: Builder,
+        providers: Providers,
+        event_sender: Option<broadcast::Sender<WithEntryPoint<RpcEvent>>>,
+    ) -> Self {
         Self {
             args,
             pool,
             builder,
             providers,
+            event_sender,
         }
     }
 }
@@ -110,6 +140,14 @@ where
         let addr: SocketAddr = format_socket_addr(&self.args.host, self.args.port).parse()?;
         tracing::info!("Starting rpc server on {}", addr);
 
+        let index = self
+            .args
+            .indexer
+            .as_ref()
+            .map(|settings| UserOperationIndex::open(&settings.path))
+            .transpose()
+            .context("should open user operation index")?;
+
         let mut router_builder = EntryPointRouterBuilder::default();
 
         if self.args.entry_point_v0_6_enabled {
@@ -118,16 +156,22 @@ where
                 .ep_v0_6()
                 .clone()
                 .context("entry point v0.6 not supplied")?;
+            let estimation_entry_point = EstimationEntryPoint::new(
+                &self.args.chain_spec,
+                self.providers.evm().clone(),
+                ep.clone(),
+            );
 
             router_builder = router_builder.v0_6(EntryPointRouteImpl::new(
                 ep.clone(),
                 GasEstimatorV0_6::new(
                     self.args.chain_spec.clone(),
                     self.providers.evm().clone(),
-                    ep.clone(),
+                    estimation_entry_point.clone(),
                     self.args.estimation_settings,
                     self.providers.fee_estimator().clone(),
-                ),
+                )
+                .with_gas_used_tracer(estimation_entry_point),
                 UserOperationEventProviderV0_6::new(
                     self.args.chain_spec.clone(),
                     self.providers.evm().clone(),
@@ -137,6 +181,7 @@ where
                     self.args
                         .eth_api_settings
                         .user_operation_event_block_distance_fallback,
+                    index.clone(),
                 ),
             ));
         }
@@ -147,16 +192,22 @@ where
                 .ep_v0_7()
                 .clone()
                 .context("entry point v0.7 not supplied")?;
+            let estimation_entry_point = EstimationEntryPoint::new(
+                &self.args.chain_spec,
+                self.providers.evm().clone(),
+                ep.clone(),
+            );
 
             router_builder = router_builder.v0_7(EntryPointRouteImpl::new(
                 ep.clone(),
                 GasEstimatorV0_7::new(
                     self.args.chain_spec.clone(),
                     self.providers.evm().clone(),
-                    ep.clone(),
+                    estimation_entry_point.clone(),
                     self.args.estimation_settings,
                     self.providers.fee_estimator().clone(),
-                ),
+                )
+                .with_gas_used_tracer(estimation_entry_point),
                 UserOperationEventProviderV0_7::new(
                     self.args.chain_spec.clone(),
                     self.providers.evm().clone(),
@@ -166,6 +217,43 @@ where
                     self.args
                         .eth_api_settings
                         .user_operation_event_block_distance_fallback,
+                    index.clone(),
+                ),
+            ));
+        }
+
+        if self.args.entry_point_v0_8_enabled {
+            let ep = self
+                .providers
+                .ep_v0_8()
+                .clone()
+                .context("entry point v0.8 not supplied")?;
+            let estimation_entry_point = EstimationEntryPoint::new(
+                &self.args.chain_spec,
+                self.providers.evm().clone(),
+                ep.clone(),
+            );
+
+            router_builder = router_builder.v0_8(EntryPointRouteImpl::new(
+                ep.clone(),
+                GasEstimatorV0_8::new(
+                    self.args.chain_spec.clone(),
+                    self.providers.evm().clone(),
+                    estimation_entry_point.clone(),
+                    self.args.estimation_settings,
+                    self.providers.fee_estimator().clone(),
+                )
+                .with_gas_used_tracer(estimation_entry_point),
+                UserOperationEventProviderV0_8::new(
+                    self.args.chain_spec.clone(),
+                    self.providers.evm().clone(),
+                    self.args
+                        .eth_api_settings
+                        .user_operation_event_block_distance,
+                    self.args
+                        .eth_api_settings
+                        .user_operation_event_block_distance_fallback,
+                    index.clone(),
                 ),
             ));
         }
@@ -173,14 +261,38 @@ where
         // create the entry point router
         let router = router_builder.build();
 
+        if let (Some(index), Some(settings)) = (&index, &self.args.indexer) {
+            self.spawn_indexers(&task_spawner, index, settings);
+        }
+
+        let status_cache = self
+            .event_sender
+            .as_ref()
+            .filter(|_| self.args.api_namespaces.contains(&ApiNamespace::Rundler))
+            .map(|event_sender| {
+                let status_cache = OpStatusCache::new(OP_STATUS_CACHE_SIZE);
+                task_spawner.spawn(Box::pin(status_cache.clone().run(event_sender.subscribe())));
+                status_cache
+            });
+
         let mut module = RpcModule::new(());
         self.attach_namespaces(
             self.args.eth_api_settings.permissions_enabled,
             router,
             self.providers.fee_estimator().clone(),
+            status_cache,
+            index,
             &mut module,
         )?;
 
+        let subscriptions_enabled = match &self.event_sender {
+            Some(event_sender) if self.args.api_namespaces.contains(&ApiNamespace::Eth) => {
+                module.merge(EthSubscriptionApi::new(event_sender.clone()).into_rpc())?;
+                true
+            }
+            _ => false,
+        };
+
         let servers: Vec<Box<dyn HealthCheck>> =
             vec![Box::new(self.pool.clone()), Box::new(self.builder.clone())];
         let health_checker = HealthChecker::new(servers);
@@ -208,6 +320,12 @@ where
                         .allow_headers([CONTENT_TYPE]),
                 )
             }))
+            // Resolve the caller of requests from their API key
+            .option_layer(
+                self.args
+                    .auth
+                    .map(|auth| AuthHttpLayer::new(Authenticator::new(auth))),
+            )
             // Proxy `GET /health` requests to internal `system_health` method.
             .layer(ProxyGetRequestLayer::new("/health", "system_health")?)
             .timeout(self.args.rpc_timeout)
@@ -215,11 +333,13 @@ where
                 "rundler-rpc-service-http".to_string(),
             ));
 
-        let rpc_metric_middleware = RpcServiceBuilder::new().layer(RpcMetricsMiddlewareLayer::new(
-            "rundler-rpc-service".to_string(),
-        ));
+        let rpc_metric_middleware = RpcServiceBuilder::new()
+            .layer(RpcMetricsMiddlewareLayer::new(
+                "rundler-rpc-service".to_string(),
+            ))
+            .layer(AuthRpcLayer);
 
-        let server = ServerBuilder::default()
+        let mut server_builder = ServerBuilder::default()
             .set_rpc_middleware(rpc_metric_middleware)
             .set_http_middleware(http_middleware)
             .max_connections(self.args.max_connections)
@@ -229,10 +349,12 @@ where
                 (self.args.chain_spec.max_transaction_size_bytes * 2)
                     .try_into()
                     .expect("max_transaction_size_bytes * 2 overflowed u32"),
-            )
-            .http_only()
-            .build(addr)
-            .await?;
+            );
+        // Subscriptions are the only reason to accept WebSocket connections
+        if !subscriptions_enabled {
+            server_builder = server_builder.http_only();
+        }
+        let server = server_builder.build(addr).await?;
 
         let handle = server.start(module);
 
@@ -250,11 +372,50 @@ where
         Ok(())
     }
 
+    fn spawn_indexers<T: TaskSpawnerExt>(
+        &self,
+        task_spawner: &T,
+        index: &UserOperationIndex,
+        settings: &IndexerSettings,
+    ) {
+        if self.args.entry_point_v0_6_enabled {
+            let indexer = UserOperationIndexerV0_6::new(
+                self.args.chain_spec.clone(),
+                self.providers.evm().clone(),
+                index.clone(),
+                settings.clone(),
+            );
+            task_spawner.spawn(Box::pin(indexer.run()));
+        }
+
+        if self.args.entry_point_v0_7_enabled {
+            let indexer = UserOperationIndexerV0_7::new(
+                self.args.chain_spec.clone(),
+                self.providers.evm().clone(),
+                index.clone(),
+                settings.clone(),
+            );
+            task_spawner.spawn(Box::pin(indexer.run()));
+        }
+
+        if self.args.entry_point_v0_8_enabled {
+            let indexer = UserOperationIndexerV0_8::new(
+                self.args.chain_spec.clone(),
+                self.providers.evm().clone(),
+                index.clone(),
+                settings.clone(),
+            );
+            task_spawner.spawn(Box::pin(indexer.run()));
+        }
+    }
+
     fn attach_namespaces<F: FeeEstimator + 'static>(
         &self,
         permissions_enabled: bool,
         entry_point_router: EntryPointRouter,
         fee_estimator: F,
+        status_cache: Option<OpStatusCache>,
+        index: Option<UserOperationIndex>,
         module: &mut RpcModule<()>,
     ) -> anyhow::Result<()> {
         if self.args.api_namespaces.contains(&ApiNamespace::Eth) {
@@ -274,7 +435,7 @@ where
         }
 
         if self.args.api_namespaces.contains(&ApiNamespace::Admin) {
-            module.merge(AdminApi::new(self.pool.clone()).into_rpc())?;
+            module.merge(AdminApi::new(self.pool.clone(), self.builder.clone()).into_rpc())?;
         }
 
         if self.args.api_namespaces.contains(&ApiNamespace::Rundler) {
@@ -285,6 +446,8 @@ where
                     self.pool.clone(),
                     fee_estimator,
                     self.providers.evm().clone(),
+                    status_cache,
+                    index,
                 )
                 .into_rpc(),
             )?;
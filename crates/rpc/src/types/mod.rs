@@ -11,15 +11,18 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use alloy_primitives::{Address, B256, U128, U256, U64};
+use alloy_primitives::{ruint::UintTryFrom, Address, B256, U128, U256, U64};
 use rundler_provider::{Log, TransactionReceipt};
 use rundler_types::{
+    builder::{BundlePreview, BundlePreviewExcludedOp, BundlePreviewOp, QuarantinedSigner},
     chain::{ChainSpec, FromWithSpec, IntoWithSpec},
     pool::{Reputation, ReputationStatus},
-    UserOperationOptionalGas, UserOperationVariant,
+    ExpectedStorage, GasUsedBreakdown, UserOperationOptionalGas, UserOperationVariant,
 };
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::eth::IndexedUserOperation;
+
 mod permissions;
 pub(crate) use permissions::RpcUserOperationPermissions;
 
@@ -33,12 +36,20 @@ pub(crate) use v0_7::{
     RpcGasEstimate as RpcGasEstimateV0_7, RpcUserOperation as RpcUserOperationV0_7,
     RpcUserOperationOptionalGas as RpcUserOperationOptionalGasV0_7,
 };
+mod v0_8;
 
 mod rpc_authorization;
 
+mod subscription;
+pub(crate) use subscription::{
+    RpcBundleUpdate, RpcPendingUserOperation, RpcSubscriptionKind, RpcSubscriptionResult,
+    RpcUserOperationStatus, RpcUserOperationStatusUpdate,
+};
+
 /// API namespace
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::EnumString, Deserialize)]
 #[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+#[serde(rename_all = "lowercase")]
 pub enum ApiNamespace {
     Eth,
     Debug,
@@ -108,6 +119,7 @@ impl From<UserOperationVariant> for RpcUserOperation {
         match op {
             UserOperationVariant::V0_6(op) => RpcUserOperation::V0_6(op.into()),
             UserOperationVariant::V0_7(op) => RpcUserOperation::V0_7(op.into()),
+            UserOperationVariant::V0_8(op) => RpcUserOperation::V0_7(op.into()),
         }
     }
 }
@@ -121,6 +133,25 @@ impl FromWithSpec<RpcUserOperation> for UserOperationVariant {
     }
 }
 
+impl RpcUserOperation {
+    /// Convert into a user operation for the given entry point.
+    ///
+    /// The v0.7 and v0.8 formats are identical over RPC, so the entry point address
+    /// decides which version an operation in that format is built for.
+    pub(crate) fn into_variant_for_entry_point(
+        self,
+        entry_point: &Address,
+        chain_spec: &ChainSpec,
+    ) -> UserOperationVariant {
+        match self {
+            RpcUserOperation::V0_7(op) if *entry_point == chain_spec.entry_point_address_v0_8 => {
+                UserOperationVariant::V0_8(op.into_with_spec(chain_spec))
+            }
+            op => op.into_with_spec(chain_spec),
+        }
+    }
+}
+
 /// User operation with additional metadata
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -154,6 +185,26 @@ impl From<RpcUserOperationOptionalGas> for UserOperationOptionalGas {
     }
 }
 
+impl RpcUserOperationOptionalGas {
+    /// Convert into a user operation with optional gas for the given entry point.
+    ///
+    /// See `RpcUserOperation::into_variant_for_entry_point`.
+    pub(crate) fn into_optional_gas_for_entry_point(
+        self,
+        entry_point: &Address,
+        chain_spec: &ChainSpec,
+    ) -> UserOperationOptionalGas {
+        match self {
+            RpcUserOperationOptionalGas::V0_7(op)
+                if *entry_point == chain_spec.entry_point_address_v0_8 =>
+            {
+                UserOperationOptionalGas::V0_8(op.into())
+            }
+            op => op.into(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
 pub(crate) enum RpcGasEstimate {
@@ -173,6 +224,29 @@ impl From<RpcGasEstimateV0_7> for RpcGasEstimate {
     }
 }
 
+/// Gas used by each part of a user operation, returned with the gas estimate
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RpcGasUsedBreakdown {
+    factory: U128,
+    verification: U128,
+    paymaster_verification: U128,
+    call: U128,
+    paymaster_post_op: U128,
+}
+
+impl From<GasUsedBreakdown> for RpcGasUsedBreakdown {
+    fn from(gas_used: GasUsedBreakdown) -> Self {
+        RpcGasUsedBreakdown {
+            factory: U128::from(gas_used.factory),
+            verification: U128::from(gas_used.verification),
+            paymaster_verification: U128::from(gas_used.paymaster_verification),
+            call: U128::from(gas_used.call),
+            paymaster_post_op: U128::from(gas_used.paymaster_post_op),
+        }
+    }
+}
+
 /// User operation receipt
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -285,6 +359,118 @@ pub struct RpcDebugPaymasterBalance {
     pub confirmed_balance: U256,
 }
 
+/// Preview of the bundle a builder would send at the latest block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBundlePreview {
+    /// Entry point the bundle targets
+    pub entry_point: Address,
+    /// Address of the builder that would send the bundle
+    pub builder_address: Address,
+    /// Block hash the bundle was simulated against
+    pub block_hash: B256,
+    /// User operations that would be included in the bundle
+    pub user_operations: Vec<RpcBundlePreviewOp>,
+    /// User operations that would be rejected and removed from the mempool
+    pub rejected_user_operations: Vec<RpcBundlePreviewExcludedOp>,
+    /// User operations that would be skipped for this bundle
+    pub skipped_user_operations: Vec<RpcBundlePreviewExcludedOp>,
+    /// Gas estimate of the bundle transaction
+    pub gas_estimate: U64,
+    /// Max fee per gas of the bundle transaction
+    pub max_fee_per_gas: U128,
+    /// Max priority fee per gas of the bundle transaction
+    pub max_priority_fee_per_gas: U128,
+    /// Storage slots the bundle expects to be unchanged at inclusion
+    pub expected_storage: ExpectedStorage,
+    /// Total DA gas of the included user operations
+    pub da_gas: U128,
+}
+
+impl From<BundlePreview> for RpcBundlePreview {
+    fn from(preview: BundlePreview) -> Self {
+        Self {
+            entry_point: preview.entry_point,
+            builder_address: preview.builder_address,
+            block_hash: preview.block_hash,
+            user_operations: preview.ops.into_iter().map(Into::into).collect(),
+            rejected_user_operations: preview.rejected_ops.into_iter().map(Into::into).collect(),
+            skipped_user_operations: preview.skipped_ops.into_iter().map(Into::into).collect(),
+            gas_estimate: U64::from(preview.gas_estimate),
+            max_fee_per_gas: U128::from(preview.gas_fees.max_fee_per_gas),
+            max_priority_fee_per_gas: U128::from(preview.gas_fees.max_priority_fee_per_gas),
+            expected_storage: preview.expected_storage,
+            da_gas: U128::from(preview.da_gas),
+        }
+    }
+}
+
+/// User operation included in a bundle preview
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBundlePreviewOp {
+    /// User operation hash
+    pub user_op_hash: B256,
+    /// User operation sender
+    pub sender: Address,
+    /// User operation nonce
+    pub nonce: U256,
+    /// Aggregator used by the user operation, if any
+    pub aggregator: Option<Address>,
+    /// DA gas attributed to the user operation
+    pub da_gas: U128,
+}
+
+impl From<BundlePreviewOp> for RpcBundlePreviewOp {
+    fn from(op: BundlePreviewOp) -> Self {
+        Self {
+            user_op_hash: op.hash,
+            sender: op.sender,
+            nonce: op.nonce,
+            aggregator: op.aggregator,
+            da_gas: U128::from(op.da_gas),
+        }
+    }
+}
+
+/// User operation excluded from a bundle preview
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBundlePreviewExcludedOp {
+    /// User operation hash
+    pub user_op_hash: B256,
+    /// Reason the user operation was excluded
+    pub reason: String,
+}
+
+impl From<BundlePreviewExcludedOp> for RpcBundlePreviewExcludedOp {
+    fn from(op: BundlePreviewExcludedOp) -> Self {
+        Self {
+            user_op_hash: op.hash,
+            reason: op.reason,
+        }
+    }
+}
+
+/// Bundle signer that has been quarantined
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcQuarantinedSigner {
+    /// Signer address
+    pub address: Address,
+    /// Reason the signer was quarantined
+    pub reason: String,
+}
+
+impl From<QuarantinedSigner> for RpcQuarantinedSigner {
+    fn from(signer: QuarantinedSigner) -> Self {
+        Self {
+            address: signer.address,
+            reason: signer.reason,
+        }
+    }
+}
+
 /// A user operation that has been mined
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -292,3 +478,49 @@ pub(crate) struct RpcMinedUserOperation {
     pub(crate) user_operation: RpcUserOperation,
     pub(crate) receipt: RpcUserOperationReceipt,
 }
+
+/// Inclusive block range to query, unbounded if a side is not set
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RpcBlockRange {
+    pub(crate) from_block: Option<U64>,
+    pub(crate) to_block: Option<U64>,
+}
+
+/// A mined user operation from the user operation index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RpcIndexedUserOperation {
+    pub(crate) user_op_hash: B256,
+    pub(crate) entry_point: RpcAddress,
+    pub(crate) sender: RpcAddress,
+    pub(crate) nonce: U256,
+    pub(crate) paymaster: RpcAddress,
+    pub(crate) actual_gas_cost: U256,
+    pub(crate) actual_gas_used: U128,
+    pub(crate) success: bool,
+    /// If not successful, the revert reason
+    pub(crate) reason: String,
+    pub(crate) transaction_hash: B256,
+    pub(crate) block_hash: B256,
+    pub(crate) block_number: U64,
+}
+
+impl From<IndexedUserOperation> for RpcIndexedUserOperation {
+    fn from(op: IndexedUserOperation) -> Self {
+        Self {
+            user_op_hash: op.user_op_hash,
+            entry_point: op.entry_point.into(),
+            sender: op.sender.into(),
+            nonce: op.nonce,
+            paymaster: op.paymaster.into(),
+            actual_gas_cost: op.actual_gas_cost,
+            actual_gas_used: U128::uint_try_from(op.actual_gas_used).unwrap_or(U128::MAX),
+            success: op.success,
+            reason: op.revert_reason.map(|r| r.to_string()).unwrap_or_default(),
+            transaction_hash: op.log.transaction_hash.unwrap_or_default(),
+            block_hash: op.log.block_hash.unwrap_or_default(),
+            block_number: U64::from(op.log.block_number.unwrap_or_default()),
+        }
+    }
+}
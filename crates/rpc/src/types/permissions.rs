@@ -37,6 +37,9 @@ pub(crate) struct RpcUserOperationPermissions {
     /// Bundler sponsorship settings
     #[serde(default)]
     pub(crate) bundler_sponsorship: Option<RpcBundlerSponsorship>,
+    /// Priority tier of the user operation
+    #[serde(default)]
+    pub(crate) priority_tier: Option<U64>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -58,6 +61,9 @@ impl FromWithSpec<RpcUserOperationPermissions> for UserOperationPermissions {
             bundler_sponsorship: rpc
                 .bundler_sponsorship
                 .map(|c| c.into_with_spec(chain_spec)),
+            priority_tier: rpc.priority_tier.map(|c| c.saturating_to()),
+            // set by the server from the caller's API key, never by the client
+            caller_id: None,
         }
     }
 }
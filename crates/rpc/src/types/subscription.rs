@@ -0,0 +1,157 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use alloy_primitives::{B256, U64};
+use serde::{Deserialize, Serialize};
+
+use super::{RpcAddress, RpcUserOperation};
+
+/// Topics that can be subscribed to with `eth_subscribe`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum RpcSubscriptionKind {
+    /// User operations accepted into the mempool
+    NewPendingUserOperations,
+    /// Lifecycle updates of a single user operation, requires its hash as a parameter
+    UserOperationStatus,
+    /// Bundle transactions submitted, mined or dropped by the builder
+    Bundles,
+}
+
+/// Item sent to `eth_subscribe` subscribers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum RpcSubscriptionResult {
+    /// Item for `newPendingUserOperations`
+    PendingUserOperation(RpcPendingUserOperation),
+    /// Item for `userOperationStatus`
+    UserOperationStatus(RpcUserOperationStatusUpdate),
+    /// Item for `bundles`
+    Bundle(RpcBundleUpdate),
+}
+
+/// A user operation that was accepted into the mempool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RpcPendingUserOperation {
+    /// The full user operation
+    pub(crate) user_operation: RpcUserOperation,
+    /// The hash of the user operation
+    pub(crate) user_operation_hash: B256,
+    /// The entry point address this operation was sent to
+    pub(crate) entry_point: RpcAddress,
+}
+
+/// A change in the status of a user operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RpcUserOperationStatusUpdate {
+    /// The hash of the user operation
+    pub(crate) user_operation_hash: B256,
+    /// The entry point address this operation was sent to
+    pub(crate) entry_point: RpcAddress,
+    /// The new status
+    #[serde(flatten)]
+    pub(crate) status: RpcUserOperationStatus,
+}
+
+/// Status of a user operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub(crate) enum RpcUserOperationStatus {
+    /// The operation is in the mempool
+    Pending {
+        /// The latest reason the builder skipped the operation, if any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    /// The operation was included in a bundle transaction that has not been mined yet
+    #[serde(rename_all = "camelCase")]
+    Bundled {
+        /// Hash of the bundle transaction
+        transaction_hash: B256,
+        /// Tag of the builder that sent the bundle
+        builder: String,
+    },
+    /// The operation was mined
+    #[serde(rename_all = "camelCase")]
+    Mined {
+        /// Hash of the transaction that included the operation
+        transaction_hash: B256,
+        /// Number of the block that included the operation
+        block_number: U64,
+        /// Hash of the block that included the operation
+        block_hash: B256,
+    },
+    /// The operation was removed from the mempool without being mined
+    Dropped {
+        /// Reason for the removal
+        reason: String,
+    },
+    /// The operation was rejected by the builder during bundle formation
+    Rejected {
+        /// Reason for the rejection
+        reason: String,
+    },
+    /// The operation was replaced by another with the same sender and nonce
+    #[serde(rename_all = "camelCase")]
+    Replaced {
+        /// Hash of the replacing operation
+        replaced_by: B256,
+    },
+}
+
+/// A change in the status of a bundle transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub(crate) enum RpcBundleUpdate {
+    /// A bundle transaction was sent, either for the first time or with increased fees
+    #[serde(rename_all = "camelCase")]
+    Submitted {
+        /// The entry point address the bundle was sent to
+        entry_point: RpcAddress,
+        /// Tag of the builder that sent the bundle
+        builder: String,
+        /// Hash of the bundle transaction
+        transaction_hash: B256,
+        /// Nonce of the bundle transaction
+        nonce: U64,
+        /// Hashes of the user operations in the bundle
+        user_operation_hashes: Vec<B256>,
+    },
+    /// A bundle transaction was mined
+    #[serde(rename_all = "camelCase")]
+    Mined {
+        /// The entry point address the bundle was sent to
+        entry_point: RpcAddress,
+        /// Tag of the builder that sent the bundle
+        builder: String,
+        /// Hash of the bundle transaction
+        transaction_hash: B256,
+        /// Nonce of the bundle transaction
+        nonce: U64,
+        /// Number of the block that included the bundle
+        block_number: U64,
+    },
+    /// The latest bundle transaction was dropped
+    #[serde(rename_all = "camelCase")]
+    Dropped {
+        /// The entry point address the bundle was sent to
+        entry_point: RpcAddress,
+        /// Tag of the builder that sent the bundle
+        builder: String,
+        /// Nonce of the dropped transaction
+        nonce: U64,
+    },
+}
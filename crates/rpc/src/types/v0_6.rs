@@ -21,7 +21,7 @@ use rundler_types::{
 };
 use serde::{Deserialize, Serialize};
 
-use super::{rpc_authorization::RpcEip7702Auth, RpcAddress};
+use super::{rpc_authorization::RpcEip7702Auth, RpcAddress, RpcGasUsedBreakdown};
 
 /// User operation definition for RPC
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -140,6 +140,8 @@ pub(crate) struct RpcGasEstimate {
     pre_verification_gas: U128,
     call_gas_limit: U128,
     verification_gas_limit: U128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_used: Option<RpcGasUsedBreakdown>,
 }
 
 impl From<GasEstimate> for RpcGasEstimate {
@@ -148,6 +150,7 @@ impl From<GasEstimate> for RpcGasEstimate {
             pre_verification_gas: U128::from(estimate.pre_verification_gas),
             call_gas_limit: U128::from(estimate.call_gas_limit),
             verification_gas_limit: U128::from(estimate.verification_gas_limit),
+            gas_used: estimate.gas_used.map(Into::into),
         }
     }
 }
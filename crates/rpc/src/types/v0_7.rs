@@ -21,7 +21,7 @@ use rundler_types::{
 };
 use serde::{Deserialize, Serialize};
 
-use super::{rpc_authorization::RpcEip7702Auth, RpcAddress};
+use super::{rpc_authorization::RpcEip7702Auth, RpcAddress, RpcGasUsedBreakdown};
 
 /// User operation definition for RPC inputs
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -203,6 +203,8 @@ pub(crate) struct RpcGasEstimate {
     call_gas_limit: U128,
     verification_gas_limit: U128,
     paymaster_verification_gas_limit: Option<U128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_used: Option<RpcGasUsedBreakdown>,
 }
 
 impl From<GasEstimate> for RpcGasEstimate {
@@ -214,6 +216,7 @@ impl From<GasEstimate> for RpcGasEstimate {
             paymaster_verification_gas_limit: estimate
                 .paymaster_verification_gas_limit
                 .map(|x| U128::from(x)),
+            gas_used: estimate.gas_used.map(Into::into),
         }
     }
 }
@@ -0,0 +1,34 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+//! The v0.8 RPC user operation format is identical to v0.7, these conversions reuse
+//! the v0.7 RPC types and only differ in the entry point the operation is built for.
+
+use rundler_types::{
+    chain::{ChainSpec, FromWithSpec, IntoWithSpec},
+    v0_8::{UserOperation, UserOperationBuilder},
+};
+
+use super::v0_7::RpcUserOperation;
+
+impl From<UserOperation> for RpcUserOperation {
+    fn from(op: UserOperation) -> Self {
+        op.into_v0_7().into()
+    }
+}
+
+impl FromWithSpec<RpcUserOperation> for UserOperation {
+    fn from_with_spec(def: RpcUserOperation, chain_spec: &ChainSpec) -> Self {
+        UserOperationBuilder::from_v0_7(def.into_with_spec(chain_spec), chain_spec).build()
+    }
+}
@@ -31,7 +31,7 @@ mod aws;
 use aws::LockingKmsSigner;
 
 mod error;
-use alloy_primitives::U256;
+use alloy_primitives::{Address, PrimitiveSignature, U256};
 pub use error::{Error, Result};
 
 mod funding;
@@ -41,7 +41,13 @@ mod local;
 
 mod manager;
 use manager::FundingSignerManager;
-pub use manager::{SignerLease, SignerManager};
+#[cfg(feature = "test-utils")]
+pub use manager::MockSignerManager;
+pub use manager::{QuarantinedSigner, SignerLease, SignerManager};
+
+mod remote;
+
+mod vault;
 
 pub mod utils;
 
@@ -54,6 +60,17 @@ pub struct KmsLockingSettings {
     pub ttl_millis: u64,
 }
 
+/// Settings for a Vault transit secrets engine
+#[derive(Debug, Clone)]
+pub struct VaultSettings {
+    /// Vault server address
+    pub address: String,
+    /// Vault token
+    pub token: String,
+    /// Mount path of the transit secrets engine
+    pub mount: String,
+}
+
 /// Settings for funding
 #[derive(Default, Debug, Clone)]
 pub struct FundingSettings {
@@ -77,6 +94,8 @@ pub struct FundingSettings {
 /// 1. Private keys w/o funding
 /// 2. KMS locking w/o funding
 /// 3. Mnemonic w/ KMS funding & locking
+/// 4. Remote signer or Vault transit w/o funding
+/// 5. Any of the above unfunded schemes w/ a remote signer or Vault transit funding key
 #[derive(Debug, Clone)]
 pub enum SigningScheme {
     /// List of private keys
@@ -114,12 +133,38 @@ pub enum SigningScheme {
         /// Funding settings
         funding_settings: FundingSettings,
     },
+    /// Remote signer speaking the `eth_signTransaction` JSON-RPC protocol, i.e. Web3Signer
+    Remote {
+        /// Remote signer URL
+        url: String,
+        /// Addresses to sign with, all of the remote signer's accounts if empty
+        addresses: Vec<Address>,
+    },
+    /// Vault transit secrets engine keys
+    VaultTransit {
+        /// Vault settings
+        settings: VaultSettings,
+        /// Transit key names
+        key_names: Vec<String>,
+    },
+    /// Funding key from an unfunded scheme and associated keys
+    Funding {
+        /// Scheme of the funding key, the first key of the scheme is used
+        funder: Box<SigningScheme>,
+        /// Scheme of the funded keys
+        subkeys: Box<SigningScheme>,
+        /// Funding settings
+        funding_settings: FundingSettings,
+    },
 }
 
 impl SigningScheme {
     /// Returns true if the signing scheme supports funding
     pub fn supports_funding(&self) -> bool {
-        matches!(self, SigningScheme::KmsFunding { .. })
+        matches!(
+            self,
+            SigningScheme::KmsFunding { .. } | SigningScheme::Funding { .. }
+        )
     }
 }
 
@@ -193,6 +238,34 @@ pub async fn new_signer_manager<
             )
             .await
         }
+        SigningScheme::Remote { .. } | SigningScheme::VaultTransit { .. } => {
+            let wallet = create_wallet(scheme, chain_spec.id).await?;
+            Ok(Arc::new(FundingSignerManager::new(
+                chain_spec.id,
+                wallet,
+                None,
+                false,
+                task_spawner,
+                provider.clone(),
+            )) as Arc<dyn SignerManager>)
+        }
+        SigningScheme::Funding {
+            funder,
+            subkeys,
+            funding_settings,
+        } => {
+            new_funding_signer_manager(
+                task_spawner,
+                provider.clone(),
+                da_gas_oracle,
+                funder,
+                subkeys,
+                funding_settings,
+                chain_spec,
+                auto_fund,
+            )
+            .await
+        }
     }?;
 
     init_balances(&manager, &provider).await?;
@@ -314,22 +387,66 @@ async fn new_kms_funding_signer_manager<
         .get(&key_id)
         .context("funding key id should be in key ids by funding key id")?;
 
-    let wallet = match subkeys {
-        SigningScheme::PrivateKeys { private_keys } => {
-            local::construct_local_wallet_from_private_keys(private_keys, chain_spec.id)?
-        }
-        SigningScheme::AwsKms { key_ids } => {
-            aws::create_wallet_from_key_ids(key_ids.clone(), chain_spec.id).await?
-        }
-        SigningScheme::Mnemonic { mnemonic, num_keys } => {
-            local::construct_local_wallet_from_mnemonic(mnemonic.clone(), chain_spec.id, *num_keys)?
-        }
-        _ => Err(anyhow::anyhow!(
-            "Signing scheme {subkeys:?} not supported in kms funding"
-        ))?,
-    };
+    let wallet = create_wallet(subkeys, chain_spec.id).await?;
+
+    Ok(Arc::new(FundingSignerManager::new(
+        chain_spec.id,
+        wallet,
+        Some(funder_settings(
+            funding_signer,
+            settings,
+            chain_spec,
+            da_gas_oracle,
+        )),
+        auto_fund,
+        task_spawner,
+        provider,
+    )))
+}
 
-    let funder_settings = FunderSettings {
+#[allow(clippy::too_many_arguments)]
+async fn new_funding_signer_manager<
+    P: EvmProvider + 'static,
+    T: TaskSpawner,
+    D: DAGasOracle + 'static,
+>(
+    task_spawner: &T,
+    provider: P,
+    da_gas_oracle: D,
+    funder: &SigningScheme,
+    subkeys: &SigningScheme,
+    settings: &FundingSettings,
+    chain_spec: &ChainSpec,
+    auto_fund: bool,
+) -> Result<Arc<dyn SignerManager>> {
+    let funder_wallet = create_wallet(funder, chain_spec.id).await?;
+    if funder_wallet.signer_addresses().next().is_none() {
+        Err(anyhow::anyhow!("no funding key configured"))?;
+    }
+    let wallet = create_wallet(subkeys, chain_spec.id).await?;
+
+    Ok(Arc::new(FundingSignerManager::new(
+        chain_spec.id,
+        wallet,
+        Some(funder_settings(
+            funder_wallet.default_signer(),
+            settings,
+            chain_spec,
+            da_gas_oracle,
+        )),
+        auto_fund,
+        task_spawner,
+        provider,
+    )))
+}
+
+fn funder_settings<D: DAGasOracle + 'static>(
+    signer: Arc<dyn TxSigner<PrimitiveSignature> + Send + Sync + 'static>,
+    settings: &FundingSettings,
+    chain_spec: &ChainSpec,
+    da_gas_oracle: D,
+) -> FunderSettings {
+    FunderSettings {
         fund_below_balance: settings.fund_below_balance,
         fund_to_balance: settings.fund_to_balance,
         poll_interval: settings.poll_interval,
@@ -338,18 +455,34 @@ async fn new_kms_funding_signer_manager<
         base_fee_multiplier: settings.base_fee_multiplier,
         chain_spec: chain_spec.clone(),
         multicall3_address: chain_spec.multicall3_address,
-        signer: funding_signer,
+        signer,
         da_gas_oracle: Arc::new(da_gas_oracle),
-    };
+    }
+}
 
-    Ok(Arc::new(FundingSignerManager::new(
-        chain_spec.id,
-        wallet,
-        Some(funder_settings),
-        auto_fund,
-        task_spawner,
-        provider,
-    )))
+/// Creates a wallet for the keys of an unfunded, non-locking, signing scheme
+async fn create_wallet(scheme: &SigningScheme, chain_id: u64) -> Result<EthereumWallet> {
+    match scheme {
+        SigningScheme::PrivateKeys { private_keys } => {
+            local::construct_local_wallet_from_private_keys(private_keys, chain_id)
+        }
+        SigningScheme::AwsKms { key_ids } => {
+            aws::create_wallet_from_key_ids(key_ids.clone(), chain_id).await
+        }
+        SigningScheme::Mnemonic { mnemonic, num_keys } => {
+            local::construct_local_wallet_from_mnemonic(mnemonic.clone(), chain_id, *num_keys)
+        }
+        SigningScheme::Remote { url, addresses } => {
+            remote::create_wallet(url, addresses, chain_id).await
+        }
+        SigningScheme::VaultTransit {
+            settings,
+            key_names,
+        } => vault::create_wallet(settings, key_names, chain_id).await,
+        _ => Err(anyhow::anyhow!(
+            "Signing scheme {scheme:?} not supported as funding key or subkeys"
+        ))?,
+    }
 }
 
 async fn init_balances<P: EvmProvider>(
@@ -25,6 +25,8 @@ use alloy_network::{
 use alloy_primitives::{Address, Bytes, PrimitiveSignature, U256};
 use metrics::Gauge;
 use metrics_derive::Metrics;
+#[cfg(feature = "test-utils")]
+use mockall::automock;
 use parking_lot::RwLock;
 use rundler_provider::{EvmProvider, TransactionRequest};
 use rundler_task::TaskSpawner;
@@ -38,6 +40,7 @@ use crate::{
 /// Trait for a signer manager
 ///
 /// Leases available signers and manages their balances
+#[cfg_attr(feature = "test-utils", automock)]
 #[async_trait::async_trait]
 pub trait SignerManager: Send + Sync {
     /// Get the addresses of the signers
@@ -65,6 +68,26 @@ pub trait SignerManager: Send + Sync {
     ///
     /// Returns an error if the signer manager does not support funding
     fn fund_signers(&self) -> Result<()>;
+
+    /// Quarantine a signer so that it is no longer leased
+    ///
+    /// The signer may still be leased, its lease is dropped instead of being returned.
+    fn quarantine_signer(&self, address: &Address, reason: String) -> Result<()>;
+
+    /// Remove a signer from quarantine, making it available to lease
+    fn unquarantine_signer(&self, address: &Address) -> Result<()>;
+
+    /// Get the quarantined signers
+    fn quarantined_signers(&self) -> Vec<QuarantinedSigner>;
+}
+
+/// A signer that has been quarantined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedSigner {
+    /// Address of the signer
+    pub address: Address,
+    /// Reason the signer was quarantined
+    pub reason: String,
 }
 
 /// A leased signer
@@ -142,6 +165,7 @@ pub(crate) struct FundingSignerManager {
     chain_id: u64,
     wallet: EthereumWallet,
     signer_statuses: Arc<RwLock<HashMap<Address, SignerStatus>>>,
+    quarantine_reasons: RwLock<HashMap<Address, String>>,
     auto_fund: bool,
     funder_settings: Option<FunderSettings>,
     funding_notify: Arc<Notify>,
@@ -153,6 +177,8 @@ pub(crate) enum SignerStatus {
     NeedsFunding,
     LeasedNeedsFunding,
     Leased,
+    // Remembers the lease and funding status so they can be restored when unquarantined
+    Quarantined { leased: bool, needs_funding: bool },
 }
 
 #[async_trait::async_trait]
@@ -234,6 +260,8 @@ impl SignerManager for FundingSignerManager {
         match *status {
             SignerStatus::Leased => *status = SignerStatus::Available,
             SignerStatus::LeasedNeedsFunding => *status = SignerStatus::NeedsFunding,
+            // the signer was quarantined while leased
+            SignerStatus::Quarantined { ref mut leased, .. } => *leased = false,
             _ => unreachable!(),
         }
     }
@@ -257,6 +285,81 @@ impl SignerManager for FundingSignerManager {
         self.funding_notify.notify_one();
         Ok(())
     }
+
+    fn quarantine_signer(&self, address: &Address, reason: String) -> Result<()> {
+        let mut statuses = self.signer_statuses.write();
+        let Some(status) = statuses.get_mut(address) else {
+            Err(anyhow::anyhow!("Signer {address:?} not found"))?
+        };
+
+        tracing::warn!("Quarantining signer {address:?}: {reason}");
+        *status = match status {
+            SignerStatus::Available => SignerStatus::Quarantined {
+                leased: false,
+                needs_funding: false,
+            },
+            SignerStatus::NeedsFunding => SignerStatus::Quarantined {
+                leased: false,
+                needs_funding: true,
+            },
+            SignerStatus::Leased => SignerStatus::Quarantined {
+                leased: true,
+                needs_funding: false,
+            },
+            SignerStatus::LeasedNeedsFunding => SignerStatus::Quarantined {
+                leased: true,
+                needs_funding: true,
+            },
+            SignerStatus::Quarantined {
+                leased,
+                needs_funding,
+            } => SignerStatus::Quarantined {
+                leased: *leased,
+                needs_funding: *needs_funding,
+            },
+        };
+        self.quarantine_reasons.write().insert(*address, reason);
+        Ok(())
+    }
+
+    fn unquarantine_signer(&self, address: &Address) -> Result<()> {
+        let mut statuses = self.signer_statuses.write();
+        let Some(status) = statuses.get_mut(address) else {
+            Err(anyhow::anyhow!("Signer {address:?} not found"))?
+        };
+        let SignerStatus::Quarantined {
+            leased,
+            needs_funding,
+        } = *status
+        else {
+            Err(anyhow::anyhow!("Signer {address:?} is not quarantined"))?
+        };
+        if leased {
+            Err(anyhow::anyhow!(
+                "Signer {address:?} is still leased by its builder"
+            ))?
+        }
+
+        tracing::info!("Removing signer {address:?} from quarantine");
+        *status = if needs_funding {
+            SignerStatus::NeedsFunding
+        } else {
+            SignerStatus::Available
+        };
+        self.quarantine_reasons.write().remove(address);
+        Ok(())
+    }
+
+    fn quarantined_signers(&self) -> Vec<QuarantinedSigner> {
+        self.quarantine_reasons
+            .read()
+            .iter()
+            .map(|(address, reason)| QuarantinedSigner {
+                address: *address,
+                reason: reason.clone(),
+            })
+            .collect()
+    }
 }
 
 impl FundingSignerManager {
@@ -301,6 +404,7 @@ impl FundingSignerManager {
             chain_id,
             wallet,
             signer_statuses,
+            quarantine_reasons: RwLock::new(HashMap::new()),
             auto_fund,
             funder_settings,
             funding_notify,
@@ -327,6 +431,7 @@ impl FundingSignerManager {
                     match status {
                         SignerStatus::Available => *status = SignerStatus::NeedsFunding,
                         SignerStatus::Leased => *status = SignerStatus::LeasedNeedsFunding,
+                        SignerStatus::Quarantined { needs_funding, .. } => *needs_funding = true,
                         SignerStatus::LeasedNeedsFunding | SignerStatus::NeedsFunding => {}
                     }
                     needs_funding = true;
@@ -334,6 +439,7 @@ impl FundingSignerManager {
                     match status {
                         SignerStatus::NeedsFunding => *status = SignerStatus::Available,
                         SignerStatus::LeasedNeedsFunding => *status = SignerStatus::Leased,
+                        SignerStatus::Quarantined { needs_funding, .. } => *needs_funding = false,
                         SignerStatus::Available | SignerStatus::Leased => {}
                     }
                 }
@@ -512,6 +618,57 @@ mod tests {
         assert!(manager.lease_signer_by_address(&addresses[0]).is_none());
     }
 
+    #[tokio::test]
+    async fn test_quarantine() {
+        let manager = create_test_manager(vec![
+            address!("0000000000000000000000000000000000000000"),
+            address!("0000000000000000000000000000000000000001"),
+        ]);
+        let addresses = manager.addresses();
+
+        // quarantine a leased signer, it can't be unquarantined while leased
+        let lease = manager.lease_signer_by_address(&addresses[0]).unwrap();
+        manager
+            .quarantine_signer(&addresses[0], "nonce too low".to_string())
+            .unwrap();
+        assert!(manager.unquarantine_signer(&addresses[0]).is_err());
+
+        // returning its lease keeps it quarantined
+        manager.return_lease(lease);
+        assert_eq!(manager.available(), 1);
+        assert!(manager.lease_signer_by_address(&addresses[0]).is_none());
+        assert_eq!(
+            manager.quarantined_signers(),
+            vec![QuarantinedSigner {
+                address: addresses[0],
+                reason: "nonce too low".to_string(),
+            }]
+        );
+
+        // balance updates are remembered but don't lift the quarantine
+        manager.update_balances(vec![(addresses[0], U256::from(50))]);
+        assert_eq!(
+            manager.signer_statuses.read().get(&addresses[0]),
+            Some(&SignerStatus::Quarantined {
+                leased: false,
+                needs_funding: true,
+            })
+        );
+
+        // an underfunded signer goes back to needing funding
+        assert!(manager.unquarantine_signer(&addresses[1]).is_err());
+        manager.unquarantine_signer(&addresses[0]).unwrap();
+        assert!(manager.quarantined_signers().is_empty());
+        assert_eq!(
+            manager.signer_statuses.read().get(&addresses[0]),
+            Some(&SignerStatus::NeedsFunding)
+        );
+        assert!(manager.lease_signer_by_address(&addresses[0]).is_none());
+
+        manager.update_balances(vec![(addresses[0], U256::from(500))]);
+        assert!(manager.lease_signer_by_address(&addresses[0]).is_some());
+    }
+
     #[derive(Clone)]
     struct MockTxSigner {
         address: Address,
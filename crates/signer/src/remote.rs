@@ -0,0 +1,390 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use alloy_consensus::{SignableTransaction, Transaction, TxEnvelope};
+use alloy_eips::{eip2718::Decodable2718, eip2930::AccessList, eip7702::SignedAuthorization};
+use alloy_network::{EthereumWallet, TxSigner};
+use alloy_primitives::{Address, Bytes, PrimitiveSignature, U128, U256, U64};
+use anyhow::{bail, Context};
+use reqwest::{header::CONTENT_TYPE, Client};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{utils, Result};
+
+/// Creates a wallet with a signer for each of the addresses served by a remote signer.
+///
+/// If no addresses are given, all of the accounts returned by `eth_accounts` are used.
+pub(crate) async fn create_wallet(
+    url: &str,
+    addresses: &[Address],
+    chain_id: u64,
+) -> Result<EthereumWallet> {
+    let client = RemoteSignerClient::new(url.to_string())?;
+    let accounts = client.accounts().await?;
+
+    let addresses = if addresses.is_empty() {
+        accounts
+    } else {
+        for address in addresses {
+            if !accounts.contains(address) {
+                Err(anyhow::anyhow!(
+                    "address {address:?} is not served by the remote signer"
+                ))?;
+            }
+        }
+        addresses.to_vec()
+    };
+    if addresses.is_empty() {
+        Err(anyhow::anyhow!("remote signer returned no accounts"))?;
+    }
+
+    let mut wallet = EthereumWallet::default();
+    for address in addresses {
+        wallet.register_signer(RemoteSigner {
+            client: client.clone(),
+            address,
+            chain_id,
+        });
+    }
+
+    Ok(wallet)
+}
+
+/// Signer that delegates signing to a remote service speaking the `eth_signTransaction`
+/// JSON-RPC protocol, i.e. Web3Signer.
+///
+/// The remote service returns the full signed transaction. Its signature is checked to
+/// be over the transaction that was requested before being used.
+pub(crate) struct RemoteSigner {
+    client: RemoteSignerClient,
+    address: Address,
+    chain_id: u64,
+}
+
+#[async_trait::async_trait]
+impl TxSigner<PrimitiveSignature> for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn SignableTransaction<PrimitiveSignature>,
+    ) -> alloy_signer::Result<PrimitiveSignature> {
+        if !tx.set_chain_id_checked(self.chain_id) {
+            return Err(alloy_signer::Error::TransactionChainIdMismatch {
+                signer: self.chain_id,
+                tx: tx.chain_id().unwrap_or_default(),
+            });
+        }
+
+        self.sign(tx)
+            .await
+            .map_err(|e| alloy_signer::Error::other(format!("{e:#}")))
+    }
+}
+
+impl RemoteSigner {
+    async fn sign(
+        &self,
+        tx: &dyn SignableTransaction<PrimitiveSignature>,
+    ) -> anyhow::Result<PrimitiveSignature> {
+        let request = RemoteTransaction::new(self.address, tx);
+        let signed: SignedTransaction = self
+            .client
+            .request("eth_signTransaction", json!([request]))
+            .await?;
+
+        let raw = match signed {
+            SignedTransaction::Raw(raw) => raw,
+            SignedTransaction::Object { raw } => raw,
+        };
+        let envelope = TxEnvelope::decode_2718(&mut raw.as_ref())
+            .context("remote signer returned an invalid transaction")?;
+        let signature = *envelope.signature();
+
+        let recovered = signature
+            .recover_address_from_prehash(&tx.signature_hash())
+            .context("remote signer returned an invalid signature")?;
+        if recovered != self.address {
+            bail!(
+                "remote signer returned a signature from {recovered:?} over a different transaction, expected {:?}",
+                self.address
+            );
+        }
+
+        Ok(signature)
+    }
+}
+
+#[derive(Clone)]
+struct RemoteSignerClient {
+    client: Client,
+    url: String,
+}
+
+impl RemoteSignerClient {
+    fn new(url: String) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: utils::http_client()?,
+            url,
+        })
+    }
+
+    async fn accounts(&self) -> anyhow::Result<Vec<Address>> {
+        self.request("eth_accounts", json!([])).await
+    }
+
+    async fn request<R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> anyhow::Result<R> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let response = self
+            .client
+            .post(&self.url)
+            .header(CONTENT_TYPE, "application/json")
+            .json(&body)
+            .send()
+            .await
+            .context(format!("failed to send {method} to remote signer"))?
+            .error_for_status()
+            .context(format!("remote signer returned error status for {method}"))?
+            .json::<JsonRpcResponse<R>>()
+            .await
+            .context(format!(
+                "failed to parse {method} response from remote signer"
+            ))?;
+
+        if let Some(error) = response.error {
+            bail!(
+                "remote signer returned error for {method}: {} ({})",
+                error.message,
+                error.code
+            );
+        }
+        response
+            .result
+            .context(format!("remote signer returned no result for {method}"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse<R> {
+    result: Option<R>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// Result of `eth_signTransaction`. Web3Signer returns the raw transaction, geth and clef
+/// return an object containing it.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SignedTransaction {
+    Raw(Bytes),
+    Object { raw: Bytes },
+}
+
+/// Transaction parameter of `eth_signTransaction`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteTransaction {
+    from: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<Address>,
+    gas: U64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_price: Option<U128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_fee_per_gas: Option<U128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_priority_fee_per_gas: Option<U128>,
+    value: U256,
+    data: Bytes,
+    nonce: U64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chain_id: Option<U64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access_list: Option<AccessList>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    authorization_list: Option<Vec<SignedAuthorization>>,
+}
+
+impl RemoteTransaction {
+    fn new(from: Address, tx: &dyn SignableTransaction<PrimitiveSignature>) -> Self {
+        let (gas_price, max_fee_per_gas, max_priority_fee_per_gas) = if tx.is_dynamic_fee() {
+            (
+                None,
+                Some(U128::from(tx.max_fee_per_gas())),
+                tx.max_priority_fee_per_gas().map(U128::from),
+            )
+        } else {
+            (tx.gas_price().map(U128::from), None, None)
+        };
+
+        Self {
+            from,
+            to: tx.kind().to().copied(),
+            gas: U64::from(tx.gas_limit()),
+            gas_price,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            value: tx.value(),
+            data: tx.input().clone(),
+            nonce: U64::from(tx.nonce()),
+            chain_id: tx.chain_id().map(U64::from),
+            access_list: tx.access_list().filter(|l| !l.is_empty()).cloned(),
+            authorization_list: tx.authorization_list().map(|l| l.to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, thread};
+
+    use alloy_consensus::TxEip1559;
+    use alloy_eips::eip2718::Encodable2718;
+    use alloy_network::TxSignerSync;
+    use alloy_primitives::TxKind;
+    use alloy_signer_local::PrivateKeySigner;
+    use tiny_http::{Response, Server};
+
+    use super::*;
+
+    const CHAIN_ID: u64 = 1337;
+
+    fn tx() -> TxEip1559 {
+        TxEip1559 {
+            chain_id: CHAIN_ID,
+            nonce: 7,
+            gas_limit: 100_000,
+            max_fee_per_gas: 20_000_000_000,
+            max_priority_fee_per_gas: 1_000_000_000,
+            to: TxKind::Call(Address::random()),
+            value: U256::from(1),
+            input: Bytes::from_static(&[0xde, 0xad]),
+            ..Default::default()
+        }
+    }
+
+    fn sign_raw(signer: &PrivateKeySigner, mut tx: TxEip1559) -> Bytes {
+        let signature = signer.sign_transaction_sync(&mut tx).unwrap();
+        TxEnvelope::from(tx.into_signed(signature))
+            .encoded_2718()
+            .into()
+    }
+
+    // Serves `eth_accounts` and `eth_signTransaction` on a local port, returning `raw` for
+    // every signing request.
+    fn stand_in(accounts: Vec<Address>, raw: Bytes) -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+
+        thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+                let result = match body["method"].as_str().unwrap() {
+                    "eth_accounts" => json!(accounts),
+                    "eth_signTransaction" => {
+                        assert_eq!(body["params"][0]["nonce"], "0x7");
+                        assert_eq!(body["params"][0]["gas"], "0x186a0");
+                        assert!(body["params"][0].get("gasPrice").is_none());
+                        json!(raw)
+                    }
+                    method => panic!("unexpected method {method}"),
+                };
+                let response = json!({"jsonrpc": "2.0", "id": body["id"], "result": result});
+                let _ = request.respond(Response::from_string(response.to_string()));
+            }
+        });
+
+        format!("http://127.0.0.1:{port}")
+    }
+
+    #[tokio::test]
+    async fn test_sign_transaction() {
+        let local = PrivateKeySigner::random();
+        let url = stand_in(vec![local.address()], sign_raw(&local, tx()));
+
+        let wallet = create_wallet(&url, &[], CHAIN_ID).await.unwrap();
+        let signer = wallet.default_signer();
+        assert_eq!(signer.address(), local.address());
+
+        let mut expected_tx = tx();
+        let expected = local.sign_transaction_sync(&mut expected_tx).unwrap();
+        let mut tx = tx();
+        let signature = signer.sign_transaction(&mut tx).await.unwrap();
+        assert_eq!(signature, expected);
+    }
+
+    #[tokio::test]
+    async fn test_sign_transaction_other_tx() {
+        let local = PrivateKeySigner::random();
+        let mut other = tx();
+        other.nonce = 8;
+        let url = stand_in(vec![local.address()], sign_raw(&local, other));
+
+        let wallet = create_wallet(&url, &[local.address()], CHAIN_ID)
+            .await
+            .unwrap();
+        let mut tx = tx();
+        assert!(wallet
+            .default_signer()
+            .sign_transaction(&mut tx)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_address() {
+        let url = stand_in(vec![Address::random()], Bytes::new());
+        assert!(create_wallet(&url, &[Address::random()], CHAIN_ID)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chain_id_mismatch() {
+        let local = PrivateKeySigner::random();
+        let url = stand_in(vec![local.address()], sign_raw(&local, tx()));
+
+        let wallet = create_wallet(&url, &[], CHAIN_ID + 1).await.unwrap();
+        let mut tx = tx();
+        let err = wallet
+            .default_signer()
+            .sign_transaction(&mut tx)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            alloy_signer::Error::TransactionChainIdMismatch { .. }
+        ));
+    }
+}
@@ -16,9 +16,24 @@
 use std::time::Duration;
 
 use alloy_primitives::{Address, B256, U256};
+use anyhow::Context;
 use metrics::Gauge;
 use rundler_provider::{EvmProvider, TransactionReceipt};
 
+// Timeouts of requests to remote signing services, so that a hung service fails the
+// signing request instead of stalling the sender
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// HTTP client for requests to remote signing services
+pub(crate) fn http_client() -> anyhow::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .connect_timeout(HTTP_CONNECT_TIMEOUT)
+        .timeout(HTTP_REQUEST_TIMEOUT)
+        .build()
+        .context("failed to build http client")
+}
+
 pub(crate) fn set_balance_gauge(gauge: &Gauge, address: Address, balance: U256) {
     let eth_string = alloy_primitives::utils::format_ether(balance);
     match eth_string.parse::<f64>() {
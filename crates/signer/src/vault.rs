@@ -0,0 +1,313 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::collections::HashMap;
+
+use alloy_consensus::SignableTransaction;
+use alloy_network::{EthereumWallet, TxSigner};
+use alloy_primitives::{uint, Address, PrimitiveSignature, B256, U256};
+use anyhow::{bail, Context};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+
+use crate::{utils, Result, VaultSettings};
+
+const VAULT_TOKEN_HEADER: &str = "X-Vault-Token";
+
+/// Order of the secp256k1 curve
+const SECP256K1_N: U256 =
+    uint!(0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141_U256);
+
+/// Creates a wallet with a signer for each of the transit keys
+pub(crate) async fn create_wallet(
+    settings: &VaultSettings,
+    key_names: &[String],
+    chain_id: u64,
+) -> Result<EthereumWallet> {
+    let mut wallet = EthereumWallet::default();
+    let client = utils::http_client()?;
+
+    for key_name in key_names {
+        let signer = VaultTransitSigner::connect(
+            client.clone(),
+            settings.clone(),
+            key_name.clone(),
+            chain_id,
+        )
+        .await?;
+        wallet.register_signer(signer);
+    }
+
+    Ok(wallet)
+}
+
+/// Signer backed by a secp256k1 key in a Vault transit secrets engine.
+///
+/// The transit engine signs the transaction hash and never exposes the private key. The
+/// address is derived from the public key of the latest key version.
+pub(crate) struct VaultTransitSigner {
+    client: Client,
+    settings: VaultSettings,
+    key_name: String,
+    address: Address,
+    chain_id: u64,
+}
+
+#[async_trait::async_trait]
+impl TxSigner<PrimitiveSignature> for VaultTransitSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn SignableTransaction<PrimitiveSignature>,
+    ) -> alloy_signer::Result<PrimitiveSignature> {
+        if !tx.set_chain_id_checked(self.chain_id) {
+            return Err(alloy_signer::Error::TransactionChainIdMismatch {
+                signer: self.chain_id,
+                tx: tx.chain_id().unwrap_or_default(),
+            });
+        }
+
+        self.sign_hash(tx.signature_hash())
+            .await
+            .map_err(|e| alloy_signer::Error::other(format!("{e:#}")))
+    }
+}
+
+impl VaultTransitSigner {
+    async fn connect(
+        client: Client,
+        settings: VaultSettings,
+        key_name: String,
+        chain_id: u64,
+    ) -> anyhow::Result<Self> {
+        let mut signer = Self {
+            client,
+            settings,
+            key_name,
+            address: Address::ZERO,
+            chain_id,
+        };
+
+        let key: KeyData = signer.request(reqwest::Method::GET, "keys", None).await?;
+        let public_key = key
+            .keys
+            .get(&key.latest_version.to_string())
+            .context("vault key is missing its latest version")?;
+        signer.address = address_from_pem(&public_key.public_key).context(format!(
+            "invalid public key for vault key {}",
+            signer.key_name
+        ))?;
+
+        Ok(signer)
+    }
+
+    async fn sign_hash(&self, hash: B256) -> anyhow::Result<PrimitiveSignature> {
+        let body = json!({
+            "input": STANDARD.encode(hash),
+            "prehashed": true,
+            "hash_algorithm": "sha2-256",
+            "marshaling_algorithm": "jws",
+        });
+        let data: SignData = self
+            .request(reqwest::Method::POST, "sign", Some(body))
+            .await?;
+
+        // signatures are formatted as vault:<key version>:<signature>
+        let encoded = data
+            .signature
+            .rsplit(':')
+            .next()
+            .context("vault returned an empty signature")?;
+        let rs = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .context("vault returned an invalid signature encoding")?;
+
+        signature_from_rs(&rs, &hash, self.address)
+    }
+
+    async fn request<R: DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> anyhow::Result<R> {
+        let url = format!(
+            "{}/v1/{}/{}/{}",
+            self.settings.address.trim_end_matches('/'),
+            self.settings.mount,
+            path,
+            self.key_name
+        );
+
+        let mut request = self
+            .client
+            .request(method, &url)
+            .header(VAULT_TOKEN_HEADER, &self.settings.token);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context(format!("failed to send request to vault {url}"))?
+            .error_for_status()
+            .context(format!("vault returned error status for {url}"))?
+            .json::<VaultResponse<R>>()
+            .await
+            .context(format!("failed to parse vault response from {url}"))?;
+
+        Ok(response.data)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct VaultResponse<T> {
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyData {
+    latest_version: u64,
+    keys: HashMap<String, KeyVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyVersion {
+    public_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SignData {
+    signature: String,
+}
+
+/// Derives the address of a PEM encoded secp256k1 public key.
+///
+/// The uncompressed point is the last 65 bytes of the DER encoded `SubjectPublicKeyInfo`.
+fn address_from_pem(pem: &str) -> anyhow::Result<Address> {
+    let encoded = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect::<String>();
+    let der = STANDARD
+        .decode(encoded.trim())
+        .context("public key is not valid base64")?;
+
+    if der.len() < 65 {
+        bail!("public key is too short");
+    }
+    let point = &der[der.len() - 65..];
+    if point[0] != 0x04 {
+        bail!("public key is not an uncompressed point");
+    }
+
+    Ok(Address::from_raw_public_key(&point[1..]))
+}
+
+/// Builds a recoverable signature from a raw `r || s` signature.
+///
+/// `s` is normalized to the lower half of the curve order and the parity is found by
+/// recovering the signer's address.
+fn signature_from_rs(
+    rs: &[u8],
+    hash: &B256,
+    address: Address,
+) -> anyhow::Result<PrimitiveSignature> {
+    if rs.len() != 64 {
+        bail!("invalid signature length {}", rs.len());
+    }
+    let r = U256::from_be_slice(&rs[..32]);
+    let mut s = U256::from_be_slice(&rs[32..]);
+    if s > SECP256K1_N >> 1 {
+        s = SECP256K1_N - s;
+    }
+
+    for y_parity in [false, true] {
+        let signature = PrimitiveSignature::new(r, s, y_parity);
+        if signature
+            .recover_address_from_prehash(hash)
+            .is_ok_and(|recovered| recovered == address)
+        {
+            return Ok(signature);
+        }
+    }
+
+    bail!("vault signature does not recover to {address:?}")
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_signer::SignerSync;
+    use alloy_signer_local::PrivateKeySigner;
+
+    use super::*;
+
+    // DER prefix of a secp256k1 SubjectPublicKeyInfo
+    const SPKI_PREFIX: &str = "3056301006072a8648ce3d020106052b8104000a034200";
+
+    fn pem(signer: &PrivateKeySigner) -> String {
+        let point = signer.credential().verifying_key().to_encoded_point(false);
+        let mut der = alloy_primitives::hex::decode(SPKI_PREFIX).unwrap();
+        der.extend_from_slice(point.as_bytes());
+        format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            STANDARD.encode(der)
+        )
+    }
+
+    fn rs(signature: &PrimitiveSignature, high_s: bool) -> Vec<u8> {
+        let s = if high_s {
+            SECP256K1_N - signature.s()
+        } else {
+            signature.s()
+        };
+        [signature.r().to_be_bytes::<32>(), s.to_be_bytes::<32>()].concat()
+    }
+
+    #[test]
+    fn test_address_from_pem() {
+        let signer = PrivateKeySigner::random();
+        assert_eq!(address_from_pem(&pem(&signer)).unwrap(), signer.address());
+    }
+
+    #[test]
+    fn test_signature_from_rs() {
+        let signer = PrivateKeySigner::random();
+        let hash = B256::random();
+        let expected = signer.sign_hash_sync(&hash).unwrap();
+
+        for high_s in [false, true] {
+            let signature =
+                signature_from_rs(&rs(&expected, high_s), &hash, signer.address()).unwrap();
+            assert_eq!(signature, expected);
+        }
+    }
+
+    #[test]
+    fn test_signature_from_rs_wrong_signer() {
+        let signer = PrivateKeySigner::random();
+        let hash = B256::random();
+        let signature = signer.sign_hash_sync(&hash).unwrap();
+
+        assert!(signature_from_rs(&rs(&signature, false), &hash, Address::random()).is_err());
+    }
+}
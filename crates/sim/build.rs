@@ -11,13 +11,21 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::{error, io::ErrorKind, process::Command};
+use std::{env, error, io::ErrorKind, process::Command};
 
 fn main() -> Result<(), Box<dyn error::Error>> {
     println!("cargo:rerun-if-changed=tracer/package.json");
     println!("cargo:rerun-if-changed=tracer/src/validationTracerV0_6.ts");
     println!("cargo:rerun-if-changed=tracer/src/validationTracerV0_7.ts");
-    compile_tracer()?;
+    println!("cargo:rerun-if-env-changed=RUNDLER_SKIP_JS_TRACER");
+    println!("cargo:rustc-check-cfg=cfg(js_tracer)");
+
+    // Deployments that only use the node's native tracer or revm can skip
+    // compiling the javascript tracer, removing the node and yarn build dependency.
+    if env::var_os("RUNDLER_SKIP_JS_TRACER").is_none() {
+        compile_tracer()?;
+        println!("cargo:rustc-cfg=js_tracer");
+    }
     Ok(())
 }
 
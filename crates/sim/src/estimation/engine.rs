@@ -0,0 +1,531 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use revm::{
+    db::CacheDB,
+    inspectors::NoOpInspector,
+    interpreter::{CallInputs, CallOutcome},
+    primitives::ExecutionResult as EvmExecutionResult,
+    Database, EvmContext, GetInspector, Inspector,
+};
+use rundler_provider::{
+    BlockHashOrNumber, BlockId, DAGasProvider, DepositInfo, EntryPoint, EvmCall, EvmProvider,
+    ExecutionResult, ProviderResult, SimulationProvider, StateOverride, TransactionRequest,
+};
+use rundler_types::{
+    chain::{ChainSpec, EstimationEngine},
+    da::{DAGasBlockData, DAGasData},
+    EntryPointVersion, GasUsedBreakdown, UserOperation, ValidationOutput, ValidationRevert,
+};
+use tokio::runtime::Handle;
+
+use super::{GasEstimationError, GasUsedTracer};
+use crate::evm::{self, ForkDb, ProviderDb};
+
+/// Number of forked blocks to keep state for
+///
+/// Estimations started around a new block may still be running against the
+/// previous one, so keep a few.
+const FORK_CACHE_SIZE: usize = 4;
+
+/// Entry point used by the gas estimators, running `simulateHandleOp` with the
+/// estimation engine selected by the chain spec.
+///
+/// With [`EstimationEngine::Revm`] the node's state at the estimated block is forked
+/// into an in-process revm instance, and the `simulateHandleOp` calls made by the
+/// binary searches execute locally against it. Each account and storage slot is
+/// then fetched from the node once per block instead of once per call. The local
+/// execution also traces the gas used by the estimated operation, see
+/// [`GasUsedTracer`]. All other calls are passed through to the wrapped entry point.
+#[derive(Clone)]
+pub struct EstimationEntryPoint<E, P> {
+    entry_point: E,
+    local: Option<Arc<LocalExecutor<P>>>,
+}
+
+/// Rejects state overrides the estimation engine cannot apply.
+///
+/// Only the local EVM decodes override code, the node accepts any bytes.
+pub(crate) fn check_state_override(
+    chain_spec: &ChainSpec,
+    state_override: &StateOverride,
+) -> Result<(), GasEstimationError> {
+    if chain_spec.estimation_engine != EstimationEngine::Revm {
+        return Ok(());
+    }
+    for (address, account) in state_override {
+        if let Some(code) = &account.code {
+            evm::decode_code(code.clone())
+                .map_err(|e| GasEstimationError::InvalidStateOverride(*address, e))?;
+        }
+    }
+    Ok(())
+}
+
+impl<E, P> EstimationEntryPoint<E, P> {
+    /// Create a new estimation entry point wrapping `entry_point`
+    pub fn new(chain_spec: &ChainSpec, provider: P, entry_point: E) -> Self {
+        let local = match chain_spec.estimation_engine {
+            EstimationEngine::Node => None,
+            EstimationEngine::Revm => Some(Arc::new(LocalExecutor {
+                provider,
+                chain_spec: chain_spec.clone(),
+                forks: Mutex::default(),
+            })),
+        };
+
+        Self { entry_point, local }
+    }
+}
+
+#[async_trait::async_trait]
+impl<E, P> EntryPoint for EstimationEntryPoint<E, P>
+where
+    E: EntryPoint,
+    P: Send + Sync,
+{
+    fn version(&self) -> EntryPointVersion {
+        self.entry_point.version()
+    }
+
+    fn address(&self) -> &Address {
+        self.entry_point.address()
+    }
+
+    async fn balance_of(
+        &self,
+        address: Address,
+        block_id: Option<BlockId>,
+    ) -> ProviderResult<U256> {
+        self.entry_point.balance_of(address, block_id).await
+    }
+
+    async fn get_deposit_info(&self, address: Address) -> ProviderResult<DepositInfo> {
+        self.entry_point.get_deposit_info(address).await
+    }
+
+    async fn get_balances(&self, addresses: Vec<Address>) -> ProviderResult<Vec<U256>> {
+        self.entry_point.get_balances(addresses).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<E, P> SimulationProvider for EstimationEntryPoint<E, P>
+where
+    E: SimulationProvider,
+    P: EvmProvider + Clone + 'static,
+{
+    type UO = E::UO;
+
+    fn get_tracer_simulate_validation_call(
+        &self,
+        user_op: Self::UO,
+    ) -> ProviderResult<(TransactionRequest, StateOverride)> {
+        self.entry_point
+            .get_tracer_simulate_validation_call(user_op)
+    }
+
+    async fn simulate_validation(
+        &self,
+        user_op: Self::UO,
+        block_id: Option<BlockId>,
+    ) -> ProviderResult<Result<ValidationOutput, ValidationRevert>> {
+        self.entry_point
+            .simulate_validation(user_op, block_id)
+            .await
+    }
+
+    fn get_simulate_handle_op_call(&self, op: Self::UO, state_override: StateOverride) -> EvmCall {
+        self.entry_point
+            .get_simulate_handle_op_call(op, state_override)
+    }
+
+    async fn simulate_handle_op(
+        &self,
+        op: Self::UO,
+        target: Address,
+        target_call_data: Bytes,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>> {
+        let Some(local) = &self.local else {
+            return self
+                .entry_point
+                .simulate_handle_op(op, target, target_call_data, block_id, state_override)
+                .await;
+        };
+
+        let (tx, state_override) = self.entry_point.get_simulate_handle_op_transaction(
+            op,
+            target,
+            target_call_data,
+            state_override,
+        );
+        let result = local.execute(tx, block_id, state_override).await?;
+
+        match result {
+            EvmExecutionResult::Success { output, .. } => Ok(Ok(
+                E::decode_simulate_handle_op_output(&output.into_data())?,
+            )),
+            EvmExecutionResult::Revert { output, .. } => {
+                E::decode_simulate_handle_ops_revert(&output)
+            }
+            // the node returns an error without revert data in this case
+            EvmExecutionResult::Halt { .. } => Ok(Err(ValidationRevert::Unknown(Bytes::new()))),
+        }
+    }
+
+    fn get_simulate_handle_op_transaction(
+        &self,
+        op: Self::UO,
+        target: Address,
+        target_call_data: Bytes,
+        state_override: StateOverride,
+    ) -> (TransactionRequest, StateOverride) {
+        self.entry_point.get_simulate_handle_op_transaction(
+            op,
+            target,
+            target_call_data,
+            state_override,
+        )
+    }
+
+    fn decode_simulate_handle_op_output(output: &Bytes) -> ProviderResult<ExecutionResult> {
+        E::decode_simulate_handle_op_output(output)
+    }
+
+    fn decode_simulate_handle_ops_revert(
+        revert_data: &Bytes,
+    ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>> {
+        E::decode_simulate_handle_ops_revert(revert_data)
+    }
+
+    fn simulation_should_revert(&self) -> bool {
+        self.entry_point.simulation_should_revert()
+    }
+}
+
+#[async_trait::async_trait]
+impl<E, P> DAGasProvider for EstimationEntryPoint<E, P>
+where
+    E: DAGasProvider,
+    P: Send + Sync,
+{
+    type UO = E::UO;
+
+    async fn calc_da_gas(
+        &self,
+        uo: Self::UO,
+        block: BlockHashOrNumber,
+        gas_price: u128,
+        bundle_size: usize,
+    ) -> ProviderResult<(u128, DAGasData, DAGasBlockData)> {
+        self.entry_point
+            .calc_da_gas(uo, block, gas_price, bundle_size)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<E, P> GasUsedTracer for EstimationEntryPoint<E, P>
+where
+    E: EntryPoint + SimulationProvider,
+    P: EvmProvider + Clone + 'static,
+{
+    type UO = E::UO;
+
+    async fn trace_gas_used(
+        &self,
+        op: Self::UO,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> anyhow::Result<Option<GasUsedBreakdown>> {
+        let Some(local) = &self.local else {
+            return Ok(None);
+        };
+
+        let inspector =
+            GasUsedInspector::new(*self.entry_point.address(), op.sender(), op.paymaster());
+        // without a target only the user operation is executed
+        let (tx, state_override) = self.entry_point.get_simulate_handle_op_transaction(
+            op,
+            Address::ZERO,
+            Bytes::new(),
+            state_override,
+        );
+        let (result, inspector) = local
+            .execute_with_inspector(tx, block_id, state_override, inspector)
+            .await?;
+
+        // the gas used by a failed operation doesn't describe the operation
+        let succeeded = match result {
+            EvmExecutionResult::Success { .. } => true,
+            EvmExecutionResult::Revert { output, .. } => {
+                matches!(E::decode_simulate_handle_ops_revert(&output), Ok(Ok(_)))
+            }
+            EvmExecutionResult::Halt { .. } => false,
+        };
+        Ok(succeeded.then_some(inspector.gas_used))
+    }
+}
+
+/// Sums the gas used by the entry point's calls to the entities of a user operation.
+///
+/// The account is called first to validate and then to execute, and the paymaster
+/// first to validate and then for `postOp`. Calls to other contracts before the
+/// account's validation deploy the account.
+struct GasUsedInspector {
+    entry_point: Address,
+    sender: Address,
+    paymaster: Option<Address>,
+    sender_calls: usize,
+    paymaster_calls: usize,
+    gas_used: GasUsedBreakdown,
+}
+
+impl GasUsedInspector {
+    fn new(entry_point: Address, sender: Address, paymaster: Option<Address>) -> Self {
+        Self {
+            entry_point,
+            sender,
+            paymaster,
+            sender_calls: 0,
+            paymaster_calls: 0,
+            gas_used: GasUsedBreakdown::default(),
+        }
+    }
+
+    fn record_call(&mut self, to: Address, gas_used: u128) {
+        if to == self.sender {
+            if self.sender_calls == 0 {
+                self.gas_used.verification += gas_used;
+            } else {
+                self.gas_used.call += gas_used;
+            }
+            self.sender_calls += 1;
+        } else if Some(to) == self.paymaster {
+            if self.paymaster_calls == 0 {
+                self.gas_used.paymaster_verification += gas_used;
+            } else {
+                self.gas_used.paymaster_post_op += gas_used;
+            }
+            self.paymaster_calls += 1;
+        } else if to != self.entry_point && self.sender_calls == 0 {
+            self.gas_used.factory += gas_used;
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for GasUsedInspector {
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        if inputs.caller == self.entry_point {
+            self.record_call(inputs.target_address, outcome.result.gas.spent().into());
+        }
+        outcome
+    }
+}
+
+struct LocalExecutor<P> {
+    provider: P,
+    chain_spec: ChainSpec,
+    // Most recently forked blocks, oldest first
+    forks: Mutex<VecDeque<(B256, Arc<ForkDb<P>>)>>,
+}
+
+impl<P> LocalExecutor<P>
+where
+    P: EvmProvider + Clone + 'static,
+{
+    async fn execute(
+        &self,
+        tx: TransactionRequest,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> anyhow::Result<EvmExecutionResult> {
+        let (result, _) = self
+            .execute_with_inspector(tx, block_id, state_override, NoOpInspector)
+            .await?;
+        Ok(result)
+    }
+
+    async fn execute_with_inspector<I>(
+        &self,
+        tx: TransactionRequest,
+        block_id: BlockId,
+        state_override: StateOverride,
+        inspector: I,
+    ) -> anyhow::Result<(EvmExecutionResult, I)>
+    where
+        I: GetInspector<CacheDB<Arc<ForkDb<P>>>> + Send + 'static,
+    {
+        let fork = self.fork(block_id).await?;
+        let env = fork.env().clone();
+        let tx = evm::tx_env(&tx, &env.block);
+
+        tokio::task::spawn_blocking(move || {
+            // Overrides and writes go to a layer private to this call
+            let mut db = CacheDB::new(fork);
+            evm::apply_state_overrides(&mut db, state_override)?;
+            evm::transact(db, inspector, env, tx)
+        })
+        .await?
+    }
+
+    // Returns the fork of the state at `block_id`. Forks of blocks requested by hash
+    // are shared by all calls, other block ids get a new fork as the block they refer
+    // to can change.
+    async fn fork(&self, block_id: BlockId) -> anyhow::Result<Arc<ForkDb<P>>> {
+        let block_hash = match block_id {
+            BlockId::Hash(hash) => Some(hash.block_hash),
+            BlockId::Number(_) => None,
+        };
+        if let Some(fork) = block_hash.and_then(|hash| self.cached_fork(hash)) {
+            return Ok(fork);
+        }
+
+        let env = evm::execution_env(&self.provider, &self.chain_spec, block_id).await?;
+        let db = ProviderDb::new(self.provider.clone(), block_id, Handle::current());
+        let fork = Arc::new(ForkDb::new(db, env));
+
+        let Some(hash) = block_hash else {
+            return Ok(fork);
+        };
+        let mut forks = self.forks.lock().unwrap();
+        // another call may have forked the block while this one was fetching it
+        if let Some((_, fork)) = forks.iter().find(|(h, _)| *h == hash) {
+            return Ok(Arc::clone(fork));
+        }
+        if forks.len() >= FORK_CACHE_SIZE {
+            forks.pop_front();
+        }
+        forks.push_back((hash, Arc::clone(&fork)));
+        Ok(fork)
+    }
+
+    fn cached_fork(&self, block_hash: B256) -> Option<Arc<ForkDb<P>>> {
+        self.forks
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(hash, _)| *hash == block_hash)
+            .map(|(_, fork)| Arc::clone(fork))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::DatabaseRef;
+    use rundler_provider::MockEvmProvider;
+
+    use super::*;
+    use crate::evm::ExecutionEnv;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fork_fetches_storage_once() {
+        let mut provider = MockEvmProvider::new();
+        provider
+            .expect_request::<(Address, B256, BlockId), U256>()
+            .times(1)
+            .returning(|_, _| Ok(U256::from(7)));
+
+        let db = ProviderDb::new(
+            Arc::new(provider),
+            BlockId::Hash(B256::ZERO.into()),
+            Handle::current(),
+        );
+        let fork = Arc::new(ForkDb::new(db, ExecutionEnv::default()));
+
+        tokio::task::spawn_blocking(move || {
+            for _ in 0..2 {
+                let value = fork.storage_ref(Address::ZERO, U256::from(1)).unwrap();
+                assert_eq!(value, U256::from(7));
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn test_engine_selection() {
+        let mut chain_spec = ChainSpec::default();
+        let entry_point = EstimationEntryPoint::new(&chain_spec, (), ());
+        assert!(entry_point.local.is_none());
+
+        chain_spec.estimation_engine = EstimationEngine::Revm;
+        let entry_point = EstimationEntryPoint::new(&chain_spec, (), ());
+        assert!(entry_point.local.is_some());
+    }
+
+    #[test]
+    fn test_check_state_override() {
+        let account = Address::repeat_byte(1);
+        let state_override = StateOverride::from_iter([(
+            account,
+            rundler_provider::AccountOverride {
+                // EOF magic with a truncated container
+                code: Some(Bytes::from_static(&[0xef, 0x00, 0x01])),
+                ..Default::default()
+            },
+        )]);
+
+        let mut chain_spec = ChainSpec::default();
+        check_state_override(&chain_spec, &state_override).unwrap();
+
+        chain_spec.estimation_engine = EstimationEngine::Revm;
+        let err = check_state_override(&chain_spec, &state_override).unwrap_err();
+        assert!(matches!(
+            err,
+            GasEstimationError::InvalidStateOverride(address, _) if address == account
+        ));
+    }
+
+    #[test]
+    fn test_gas_used_breakdown() {
+        let entry_point = Address::repeat_byte(1);
+        let sender = Address::repeat_byte(2);
+        let paymaster = Address::repeat_byte(3);
+        let sender_creator = Address::repeat_byte(4);
+
+        let mut inspector = GasUsedInspector::new(entry_point, sender, Some(paymaster));
+        inspector.record_call(sender_creator, 1);
+        inspector.record_call(sender, 2);
+        inspector.record_call(paymaster, 3);
+        // the entry point calling itself to execute
+        inspector.record_call(entry_point, 100);
+        inspector.record_call(sender, 4);
+        inspector.record_call(paymaster, 5);
+        inspector.record_call(sender_creator, 100);
+
+        assert_eq!(
+            inspector.gas_used,
+            GasUsedBreakdown {
+                factory: 1,
+                verification: 2,
+                paymaster_verification: 3,
+                call: 4,
+                paymaster_post_op: 5,
+            }
+        );
+    }
+}
@@ -16,8 +16,8 @@ use metrics::Histogram;
 use metrics_derive::Metrics;
 #[cfg(feature = "test-utils")]
 use mockall::automock;
-use rundler_provider::{ProviderError, StateOverride};
-use rundler_types::{GasEstimate, ValidationRevert};
+use rundler_provider::{BlockId, ProviderError, StateOverride};
+use rundler_types::{GasEstimate, GasUsedBreakdown, ValidationRevert};
 
 use crate::precheck::MIN_CALL_GAS_LIMIT;
 
@@ -27,12 +27,16 @@ mod estimate_call_gas;
 pub use estimate_call_gas::{
     CallGasEstimator, CallGasEstimatorImpl, CallGasEstimatorSpecialization,
 };
+mod engine;
+pub use engine::EstimationEntryPoint;
 
 /// Gas estimation module for Entry Point v0.6
 mod v0_6;
 pub use v0_6::GasEstimator as GasEstimatorV0_6;
 mod v0_7;
 pub use v0_7::GasEstimator as GasEstimatorV0_7;
+mod v0_8;
+pub use v0_8::GasEstimator as GasEstimatorV0_8;
 
 /// Percentage by which to increase the verification gas limit after binary search
 const VERIFICATION_GAS_BUFFER_PERCENT: u32 = 10;
@@ -63,6 +67,9 @@ pub enum GasEstimationError {
     /// Unsupported signature aggregator
     #[error("unsupported signature aggregator: {0:?}")]
     UnsupportedAggregator(Address),
+    /// A state override cannot be applied to the local EVM
+    #[error("invalid state override for {0:?}: {1}")]
+    InvalidStateOverride(Address, String),
     /// Error from provider
     #[error(transparent)]
     ProviderError(#[from] ProviderError),
@@ -87,6 +94,22 @@ pub trait GasEstimator: Send + Sync {
     ) -> Result<GasEstimate, GasEstimationError>;
 }
 
+/// Traces the gas used by each part of a user operation
+#[async_trait::async_trait]
+pub trait GasUsedTracer: Send + Sync {
+    /// The user operation type traced by this tracer
+    type UO;
+
+    /// Returns the gas used by the user operation at the block, or `None` if the
+    /// operation fails or the tracer doesn't execute operations locally.
+    async fn trace_gas_used(
+        &self,
+        op: Self::UO,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> anyhow::Result<Option<GasUsedBreakdown>>;
+}
+
 /// Settings for gas estimation
 #[derive(Clone, Copy, Debug)]
 pub struct Settings {
@@ -11,7 +11,7 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::{cmp, ops::Add};
+use std::{cmp, ops::Add, sync::Arc};
 
 use alloy_primitives::{Address, Bytes, B256, U256};
 use alloy_sol_types::SolInterface;
@@ -35,11 +35,11 @@ use rundler_types::{
 };
 use rundler_utils::{guard_timer::CustomTimerGuard, math};
 use tokio::join;
-use tracing::instrument;
+use tracing::{instrument, warn};
 
 use super::{
-    CallGasEstimator, CallGasEstimatorImpl, CallGasEstimatorSpecialization, GasEstimationError,
-    Metrics, Settings, VerificationGasEstimator,
+    engine, CallGasEstimator, CallGasEstimatorImpl, CallGasEstimatorSpecialization,
+    GasEstimationError, GasUsedTracer, Metrics, Settings, VerificationGasEstimator,
 };
 use crate::{
     estimation::estimate_verification_gas::GetOpWithLimitArgs, gas, precheck::MIN_CALL_GAS_LIMIT,
@@ -55,6 +55,7 @@ pub struct GasEstimator<P, E, VGE, CGE, F> {
     fee_estimator: F,
     verification_gas_estimator: VGE,
     call_gas_estimator: CGE,
+    gas_used_tracer: Option<Arc<dyn GasUsedTracer<UO = UserOperation>>>,
     metrics: Metrics,
 }
 
@@ -77,6 +78,7 @@ where
     ) -> Result<GasEstimate, GasEstimationError> {
         let _timer = CustomTimerGuard::new(self.metrics.total_gas_estimate_ms.clone());
         self.check_provided_limits(&op)?;
+        engine::check_state_override(&self.chain_spec, &state_override)?;
 
         let agg = op
             .aggregator
@@ -116,7 +118,8 @@ where
 
         let verification_future =
             self.estimate_verification_gas(&op, &full_op, block_hash, state_override.clone());
-        let call_future = self.estimate_call_gas(&op, full_op.clone(), block_hash, state_override);
+        let call_future =
+            self.estimate_call_gas(&op, full_op.clone(), block_hash, state_override.clone());
 
         // Not try_join! because then the output is nondeterministic if both
         // verification and call estimation fail.
@@ -162,11 +165,32 @@ where
             )
         };
 
+        let gas_used = match &self.gas_used_tracer {
+            Some(tracer) => {
+                let op_with_gas = UserOperationBuilder::from_uo(op_with_gas, &self.chain_spec)
+                    .pre_verification_gas(pre_verification_gas)
+                    .build();
+                // The breakdown is informational, don't fail the estimate without it
+                match tracer
+                    .trace_gas_used(op_with_gas, block_hash.into(), state_override)
+                    .await
+                {
+                    Ok(gas_used) => gas_used,
+                    Err(error) => {
+                        warn!("failed to trace gas used by user operation: {error:?}");
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
         Ok(GasEstimate {
             pre_verification_gas,
             verification_gas_limit,
             call_gas_limit,
             paymaster_verification_gas_limit: None,
+            gas_used,
         })
     }
 }
@@ -220,9 +244,20 @@ where
             fee_estimator,
             verification_gas_estimator,
             call_gas_estimator,
+            gas_used_tracer: None,
             metrics: Metrics::default(),
         }
     }
+
+    /// Traces the gas used by the estimated operations with `tracer`, returning it with
+    /// the estimates
+    pub fn with_gas_used_tracer<T>(mut self, tracer: T) -> Self
+    where
+        T: GasUsedTracer<UO = UserOperation> + 'static,
+    {
+        self.gas_used_tracer = Some(Arc::new(tracer));
+        self
+    }
 }
 
 impl<P, E, VGE, CGE, F> GasEstimator<P, E, VGE, CGE, F>
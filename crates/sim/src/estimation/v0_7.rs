@@ -11,7 +11,7 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::{cmp, ops::Add};
+use std::{cmp, ops::Add, sync::Arc};
 
 use alloy_primitives::{Address, Bytes, B256, U256};
 use alloy_sol_types::SolInterface;
@@ -34,9 +34,12 @@ use rundler_types::{
 };
 use rundler_utils::{guard_timer::CustomTimerGuard, math};
 use tokio::join;
-use tracing::instrument;
+use tracing::{instrument, warn};
 
-use super::{estimate_verification_gas::GetOpWithLimitArgs, GasEstimationError, Metrics, Settings};
+use super::{
+    engine, estimate_verification_gas::GetOpWithLimitArgs, GasEstimationError, GasUsedTracer,
+    Metrics, Settings,
+};
 use crate::{
     gas, CallGasEstimator, CallGasEstimatorImpl, CallGasEstimatorSpecialization,
     VerificationGasEstimator, VerificationGasEstimatorImpl, MIN_CALL_GAS_LIMIT,
@@ -51,6 +54,7 @@ pub struct GasEstimator<P, E, VGE, CGE, F> {
     fee_estimator: F,
     verification_gas_estimator: VGE,
     call_gas_estimator: CGE,
+    gas_used_tracer: Option<Arc<dyn GasUsedTracer<UO = UserOperation>>>,
     metrics: Metrics,
 }
 
@@ -76,6 +80,7 @@ where
     ) -> Result<GasEstimate, GasEstimationError> {
         let _timer = CustomTimerGuard::new(self.metrics.total_gas_estimate_ms.clone());
         self.check_provided_limits(&op)?;
+        engine::check_state_override(&self.chain_spec, &state_override)?;
 
         let Self {
             provider, settings, ..
@@ -127,7 +132,7 @@ where
             state_override.clone(),
         );
         let call_gas_future =
-            self.estimate_call_gas(&op, full_op.clone(), block_hash, state_override);
+            self.estimate_call_gas(&op, full_op.clone(), block_hash, state_override.clone());
 
         // Not try_join! because then the output is nondeterministic if multiple calls fail.
         let (verification_gas_limit, paymaster_verification_gas_limit, call_gas_limit) = join!(
@@ -176,6 +181,26 @@ where
             )
         };
 
+        let gas_used = match &self.gas_used_tracer {
+            Some(tracer) => {
+                let op_with_gas = UserOperationBuilder::from_uo(op_with_gas, &self.chain_spec)
+                    .pre_verification_gas(pre_verification_gas)
+                    .build();
+                // The breakdown is informational, don't fail the estimate without it
+                match tracer
+                    .trace_gas_used(op_with_gas, block_hash.into(), state_override)
+                    .await
+                {
+                    Ok(gas_used) => gas_used,
+                    Err(error) => {
+                        warn!("failed to trace gas used by user operation: {error:?}");
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
         Ok(GasEstimate {
             pre_verification_gas,
             call_gas_limit,
@@ -183,6 +208,7 @@ where
             paymaster_verification_gas_limit: op
                 .paymaster
                 .map(|_| paymaster_verification_gas_limit),
+            gas_used,
         })
     }
 }
@@ -210,6 +236,26 @@ where
         entry_point: E,
         settings: Settings,
         fee_estimator: F,
+    ) -> Self {
+        Self::new_with_simulations_bytecode(
+            chain_spec,
+            provider,
+            entry_point,
+            settings,
+            fee_estimator,
+            ENTRY_POINT_SIMULATIONS_V0_7_DEPLOYED_BYTECODE.clone(),
+        )
+    }
+
+    /// Create a new gas estimator for an entry point that shares the v0.7 interface
+    /// but whose simulations contract differs
+    pub(super) fn new_with_simulations_bytecode(
+        chain_spec: ChainSpec,
+        provider: P,
+        entry_point: E,
+        settings: Settings,
+        fee_estimator: F,
+        simulations_bytecode: Bytes,
     ) -> Self {
         if let Some(err) = settings.validate() {
             panic!("Invalid gas estimator settings: {}", err);
@@ -226,6 +272,7 @@ where
             settings,
             CallGasEstimatorSpecializationV07 {
                 chain_spec: chain_spec.clone(),
+                simulations_bytecode,
             },
         );
         Self {
@@ -236,9 +283,20 @@ where
             fee_estimator,
             verification_gas_estimator,
             call_gas_estimator,
+            gas_used_tracer: None,
             metrics: Metrics::default(),
         }
     }
+
+    /// Traces the gas used by the estimated operations with `tracer`, returning it with
+    /// the estimates
+    pub fn with_gas_used_tracer<T>(mut self, tracer: T) -> Self
+    where
+        T: GasUsedTracer<UO = UserOperation> + 'static,
+    {
+        self.gas_used_tracer = Some(Arc::new(tracer));
+        self
+    }
 }
 
 impl<P, E, VGE, CGE, F> GasEstimator<P, E, VGE, CGE, F>
@@ -474,6 +532,7 @@ where
 #[derive(Debug)]
 pub struct CallGasEstimatorSpecializationV07 {
     chain_spec: ChainSpec,
+    simulations_bytecode: Bytes,
 }
 
 impl CallGasEstimatorSpecialization for CallGasEstimatorSpecializationV07 {
@@ -490,7 +549,7 @@ impl CallGasEstimatorSpecialization for CallGasEstimatorSpecializationV07 {
         state_override.insert(
             moved_entry_point_address,
             AccountOverride {
-                code: Some(ENTRY_POINT_SIMULATIONS_V0_7_DEPLOYED_BYTECODE.clone()),
+                code: Some(self.simulations_bytecode.clone()),
                 ..Default::default()
             },
         );
@@ -0,0 +1,285 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use alloy_primitives::{Address, Bytes, U256};
+use rundler_contracts::v0_8::ENTRY_POINT_SIMULATIONS_V0_8_DEPLOYED_BYTECODE;
+use rundler_provider::{
+    BlockHashOrNumber, BlockId, DAGasProvider, DepositInfo, EntryPoint, EvmCall, EvmProvider,
+    ExecutionResult, FeeEstimator, ProviderResult, SimulationProvider, StateOverride,
+    TransactionRequest,
+};
+use rundler_types::{
+    chain::ChainSpec,
+    da::{DAGasBlockData, DAGasData},
+    v0_7,
+    v0_8::{UserOperation, UserOperationBuilder, UserOperationOptionalGas},
+    EntryPointVersion, GasEstimate, GasUsedBreakdown, ValidationOutput, ValidationRevert,
+};
+
+use super::{v0_7::CallGasEstimatorSpecializationV07, GasEstimationError, GasUsedTracer, Settings};
+use crate::{CallGasEstimatorImpl, VerificationGasEstimatorImpl};
+
+type InnerGasEstimator<P, E, F> = super::v0_7::GasEstimator<
+    P,
+    EntryPointAdapter<E>,
+    VerificationGasEstimatorImpl<P, EntryPointAdapter<E>>,
+    CallGasEstimatorImpl<EntryPointAdapter<E>, CallGasEstimatorSpecializationV07>,
+    F,
+>;
+
+/// Gas estimator for entry point v0.8
+///
+/// The v0.8 entry point keeps the v0.7 packed user operation format and simulation
+/// interface, so estimation is delegated to the v0.7 estimator. Operations are
+/// converted to v0.8 whenever they reach the entry point so that they are hashed
+/// and priced for v0.8.
+pub struct GasEstimator<P, E, F> {
+    inner: InnerGasEstimator<P, E, F>,
+    chain_spec: ChainSpec,
+}
+
+impl<P, E, F> GasEstimator<P, E, F>
+where
+    P: EvmProvider + Clone,
+    E: EntryPoint
+        + SimulationProvider<UO = UserOperation>
+        + DAGasProvider<UO = UserOperation>
+        + Clone,
+    F: FeeEstimator,
+{
+    /// Create a new gas estimator
+    pub fn new(
+        chain_spec: ChainSpec,
+        provider: P,
+        entry_point: E,
+        settings: Settings,
+        fee_estimator: F,
+    ) -> Self {
+        // The v0.7 estimator computes static pre-verification gas using the v0.7
+        // per user operation overhead, use the v0.8 overhead in its place.
+        let inner_chain_spec = ChainSpec {
+            per_user_op_v0_7_gas: chain_spec.per_user_op_v0_8_gas,
+            ..chain_spec.clone()
+        };
+
+        Self {
+            inner: super::v0_7::GasEstimator::new_with_simulations_bytecode(
+                inner_chain_spec,
+                provider,
+                EntryPointAdapter {
+                    entry_point,
+                    chain_spec: chain_spec.clone(),
+                },
+                settings,
+                fee_estimator,
+                ENTRY_POINT_SIMULATIONS_V0_8_DEPLOYED_BYTECODE.clone(),
+            ),
+            chain_spec,
+        }
+    }
+
+    /// Traces the gas used by the estimated operations with `tracer`, returning it with
+    /// the estimates
+    pub fn with_gas_used_tracer<T>(self, tracer: T) -> Self
+    where
+        T: GasUsedTracer<UO = UserOperation> + 'static,
+    {
+        let tracer = EntryPointAdapter {
+            entry_point: tracer,
+            chain_spec: self.chain_spec.clone(),
+        };
+        Self {
+            inner: self.inner.with_gas_used_tracer(tracer),
+            chain_spec: self.chain_spec,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<P, E, F> super::GasEstimator for GasEstimator<P, E, F>
+where
+    P: EvmProvider,
+    E: EntryPoint + SimulationProvider<UO = UserOperation> + DAGasProvider<UO = UserOperation>,
+    F: FeeEstimator,
+{
+    type UserOperationOptionalGas = UserOperationOptionalGas;
+
+    async fn estimate_op_gas(
+        &self,
+        op: UserOperationOptionalGas,
+        state_override: StateOverride,
+    ) -> Result<GasEstimate, GasEstimationError> {
+        self.inner.estimate_op_gas(op, state_override).await
+    }
+}
+
+/// Exposes a v0.8 entry point as a v0.7 entry point to the v0.7 estimator
+#[derive(Clone)]
+struct EntryPointAdapter<E> {
+    entry_point: E,
+    chain_spec: ChainSpec,
+}
+
+impl<E> EntryPointAdapter<E> {
+    fn to_v0_8(&self, op: v0_7::UserOperation) -> UserOperation {
+        UserOperationBuilder::from_v0_7(op, &self.chain_spec).build()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> GasUsedTracer for EntryPointAdapter<T>
+where
+    T: GasUsedTracer<UO = UserOperation>,
+{
+    type UO = v0_7::UserOperation;
+
+    async fn trace_gas_used(
+        &self,
+        op: v0_7::UserOperation,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> anyhow::Result<Option<GasUsedBreakdown>> {
+        self.entry_point
+            .trace_gas_used(self.to_v0_8(op), block_id, state_override)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<E> EntryPoint for EntryPointAdapter<E>
+where
+    E: EntryPoint,
+{
+    fn version(&self) -> EntryPointVersion {
+        self.entry_point.version()
+    }
+
+    fn address(&self) -> &Address {
+        self.entry_point.address()
+    }
+
+    async fn balance_of(
+        &self,
+        address: Address,
+        block_id: Option<BlockId>,
+    ) -> ProviderResult<U256> {
+        self.entry_point.balance_of(address, block_id).await
+    }
+
+    async fn get_deposit_info(&self, address: Address) -> ProviderResult<DepositInfo> {
+        self.entry_point.get_deposit_info(address).await
+    }
+
+    async fn get_balances(&self, addresses: Vec<Address>) -> ProviderResult<Vec<U256>> {
+        self.entry_point.get_balances(addresses).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<E> SimulationProvider for EntryPointAdapter<E>
+where
+    E: SimulationProvider<UO = UserOperation>,
+{
+    type UO = v0_7::UserOperation;
+
+    fn get_tracer_simulate_validation_call(
+        &self,
+        user_op: Self::UO,
+    ) -> ProviderResult<(TransactionRequest, StateOverride)> {
+        self.entry_point
+            .get_tracer_simulate_validation_call(self.to_v0_8(user_op))
+    }
+
+    async fn simulate_validation(
+        &self,
+        user_op: Self::UO,
+        block_id: Option<BlockId>,
+    ) -> ProviderResult<Result<ValidationOutput, ValidationRevert>> {
+        self.entry_point
+            .simulate_validation(self.to_v0_8(user_op), block_id)
+            .await
+    }
+
+    fn get_simulate_handle_op_call(&self, op: Self::UO, state_override: StateOverride) -> EvmCall {
+        self.entry_point
+            .get_simulate_handle_op_call(self.to_v0_8(op), state_override)
+    }
+
+    async fn simulate_handle_op(
+        &self,
+        op: Self::UO,
+        target: Address,
+        target_call_data: Bytes,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>> {
+        self.entry_point
+            .simulate_handle_op(
+                self.to_v0_8(op),
+                target,
+                target_call_data,
+                block_id,
+                state_override,
+            )
+            .await
+    }
+
+    fn get_simulate_handle_op_transaction(
+        &self,
+        op: Self::UO,
+        target: Address,
+        target_call_data: Bytes,
+        state_override: StateOverride,
+    ) -> (TransactionRequest, StateOverride) {
+        self.entry_point.get_simulate_handle_op_transaction(
+            self.to_v0_8(op),
+            target,
+            target_call_data,
+            state_override,
+        )
+    }
+
+    fn decode_simulate_handle_op_output(output: &Bytes) -> ProviderResult<ExecutionResult> {
+        E::decode_simulate_handle_op_output(output)
+    }
+
+    fn decode_simulate_handle_ops_revert(
+        revert_data: &Bytes,
+    ) -> ProviderResult<Result<ExecutionResult, ValidationRevert>> {
+        E::decode_simulate_handle_ops_revert(revert_data)
+    }
+
+    fn simulation_should_revert(&self) -> bool {
+        self.entry_point.simulation_should_revert()
+    }
+}
+
+#[async_trait::async_trait]
+impl<E> DAGasProvider for EntryPointAdapter<E>
+where
+    E: DAGasProvider<UO = UserOperation>,
+{
+    type UO = v0_7::UserOperation;
+
+    async fn calc_da_gas(
+        &self,
+        uo: Self::UO,
+        block: BlockHashOrNumber,
+        gas_price: u128,
+        bundle_size: usize,
+    ) -> ProviderResult<(u128, DAGasData, DAGasBlockData)> {
+        self.entry_point
+            .calc_da_gas(self.to_v0_8(uo), block, gas_price, bundle_size)
+            .await
+    }
+}
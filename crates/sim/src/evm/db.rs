@@ -0,0 +1,94 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::future::Future;
+
+use alloy_primitives::{Address, B256, U256, U64};
+use anyhow::anyhow;
+use revm::{
+    primitives::{AccountInfo, Bytecode},
+    DatabaseRef,
+};
+use rundler_provider::{BlockId, EvmProvider, ProviderError};
+use tokio::runtime::Handle;
+
+/// A revm database that lazily loads state at a fixed block from an `EvmProvider`.
+///
+/// Every lookup blocks on a provider request, so this must only be used from a blocking
+/// thread (i.e. within `tokio::task::spawn_blocking`). Wrap it in a `CacheDB` so that
+/// each account and slot is only fetched once per execution.
+pub(crate) struct ProviderDb<P> {
+    provider: P,
+    block_id: BlockId,
+    handle: Handle,
+}
+
+impl<P> ProviderDb<P> {
+    pub(crate) fn new(provider: P, block_id: BlockId, handle: Handle) -> Self {
+        Self {
+            provider,
+            block_id,
+            handle,
+        }
+    }
+
+    fn block_on<F: Future>(&self, f: F) -> F::Output {
+        self.handle.block_on(f)
+    }
+}
+
+impl<P: EvmProvider> DatabaseRef for ProviderDb<P> {
+    type Error = ProviderError;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let (balance, nonce, code) = self.block_on(async {
+            tokio::try_join!(
+                self.provider.get_balance(address, Some(self.block_id)),
+                self.provider
+                    .request::<_, U64>("eth_getTransactionCount", (address, self.block_id)),
+                self.provider.get_code(address, Some(self.block_id)),
+            )
+        })?;
+
+        let code = super::decode_code(code)
+            .map_err(|e| ProviderError::Other(anyhow!("invalid code of {address:?}: {e}")))?;
+        Ok(Some(AccountInfo::new(
+            balance,
+            nonce.to(),
+            code.hash_slow(),
+            code,
+        )))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        // Code is always returned with the account in `basic_ref`, so the cache
+        // never needs to look it up by hash.
+        Err(ProviderError::Other(anyhow!(
+            "code lookup by hash is not supported, hash: {code_hash:?}"
+        )))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.block_on(self.provider.request(
+            "eth_getStorageAt",
+            (address, B256::from(index), self.block_id),
+        ))
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        let block = self.block_on(self.provider.get_block(number.into()))?;
+        block
+            .map(|b| b.header.hash)
+            .ok_or_else(|| ProviderError::Other(anyhow!("block {number} not found")))
+    }
+}
@@ -0,0 +1,91 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::{collections::HashMap, sync::RwLock};
+
+use alloy_primitives::{Address, B256, U256};
+use revm::{
+    primitives::{AccountInfo, Bytecode},
+    DatabaseRef,
+};
+use rundler_provider::{EvmProvider, ProviderError};
+
+use super::{ExecutionEnv, ProviderDb};
+
+/// A fork of the chain state at a fixed block that can be shared between executions.
+///
+/// Unlike a `CacheDB`, loaded state is cached behind shared references, so concurrent
+/// executions each layer their own `CacheDB` (holding their state overrides and writes)
+/// over the same fork and every account and slot is fetched from the provider only once.
+pub(crate) struct ForkDb<P> {
+    db: ProviderDb<P>,
+    env: ExecutionEnv,
+    accounts: RwLock<HashMap<Address, AccountInfo>>,
+    storage: RwLock<HashMap<(Address, U256), U256>>,
+    block_hashes: RwLock<HashMap<u64, B256>>,
+}
+
+impl<P> ForkDb<P> {
+    pub(crate) fn new(db: ProviderDb<P>, env: ExecutionEnv) -> Self {
+        Self {
+            db,
+            env,
+            accounts: RwLock::default(),
+            storage: RwLock::default(),
+            block_hashes: RwLock::default(),
+        }
+    }
+
+    /// The execution environment of the block the state is forked at
+    pub(crate) fn env(&self) -> &ExecutionEnv {
+        &self.env
+    }
+}
+
+impl<P: EvmProvider> DatabaseRef for ForkDb<P> {
+    type Error = ProviderError;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        if let Some(info) = self.accounts.read().unwrap().get(&address) {
+            return Ok(Some(info.clone()));
+        }
+        let info = self.db.basic_ref(address)?.unwrap_or_default();
+        self.accounts.write().unwrap().insert(address, info.clone());
+        Ok(Some(info))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.db.code_by_hash_ref(code_hash)
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        if let Some(value) = self.storage.read().unwrap().get(&(address, index)) {
+            return Ok(*value);
+        }
+        let value = self.db.storage_ref(address, index)?;
+        self.storage
+            .write()
+            .unwrap()
+            .insert((address, index), value);
+        Ok(value)
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        if let Some(hash) = self.block_hashes.read().unwrap().get(&number) {
+            return Ok(*hash);
+        }
+        let hash = self.db.block_hash_ref(number)?;
+        self.block_hashes.write().unwrap().insert(number, hash);
+        Ok(hash)
+    }
+}
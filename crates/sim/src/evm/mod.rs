@@ -0,0 +1,243 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+//! Local EVM execution with revm, using state fetched from a provider
+
+use std::sync::Arc;
+
+use alloy_consensus::Header;
+use alloy_primitives::{keccak256, Bytes, U256};
+use anyhow::Context;
+use revm::{
+    db::CacheDB,
+    handler::register::EvmHandler,
+    inspector_handle_register,
+    precompile::{secp256r1::P256VERIFY, PrecompileSpecId},
+    primitives::{BlobExcessGasAndPrice, BlockEnv, Bytecode, ExecutionResult, SpecId, TxEnv},
+    ContextPrecompiles, Database, DatabaseRef, Evm, GetInspector,
+};
+use rundler_provider::{BlockId, EvmProvider, StateOverride, TransactionRequest};
+use rundler_types::chain::{ChainSpec, EvmHardfork};
+
+mod db;
+pub(crate) use db::ProviderDb;
+
+mod fork;
+pub(crate) use fork::ForkDb;
+
+/// Environment of a local execution: the chain's rules at a block, and the block
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExecutionEnv {
+    pub(crate) chain_id: u64,
+    pub(crate) spec_id: SpecId,
+    /// Whether the RIP-7212 P256VERIFY precompile is available
+    pub(crate) p256verify: bool,
+    pub(crate) block: BlockEnv,
+}
+
+/// Builds the execution environment of the given block from its header and the chain spec
+pub(crate) async fn execution_env<P: EvmProvider>(
+    provider: &P,
+    chain_spec: &ChainSpec,
+    block_id: BlockId,
+) -> anyhow::Result<ExecutionEnv> {
+    let block = provider
+        .get_block(block_id)
+        .await?
+        .context("block to execute on should exist")?;
+    let header = &block.header;
+
+    Ok(ExecutionEnv {
+        chain_id: chain_spec.id,
+        spec_id: spec_id(chain_spec, &header.inner),
+        p256verify: chain_spec.rip7212_enabled,
+        block: BlockEnv {
+            number: U256::from(header.number),
+            coinbase: header.beneficiary,
+            timestamp: U256::from(header.timestamp),
+            gas_limit: U256::from(header.gas_limit),
+            basefee: U256::from(header.base_fee_per_gas.unwrap_or_default()),
+            difficulty: header.difficulty,
+            prevrandao: header.mix_hash,
+            blob_excess_gas_and_price: header.excess_blob_gas.map(BlobExcessGasAndPrice::new),
+        },
+    })
+}
+
+/// Adds the RIP-7212 P256VERIFY precompile to the precompiles of the spec
+fn register_p256verify<EXT, DB: Database>(handler: &mut EvmHandler<'_, EXT, DB>) {
+    let spec_id = handler.cfg.spec_id;
+    handler.pre_execution.load_precompiles = Arc::new(move || {
+        let mut precompiles = ContextPrecompiles::new(PrecompileSpecId::from_spec_id(spec_id));
+        precompiles.extend([P256VERIFY]);
+        precompiles
+    });
+}
+
+/// Returns the hardfork rules of a block.
+///
+/// Uses the hardfork of the chain spec if set, else detects the latest hardfork from the
+/// header fields it added.
+fn spec_id(chain_spec: &ChainSpec, header: &Header) -> SpecId {
+    match chain_spec.evm_hardfork {
+        Some(EvmHardfork::London) => SpecId::LONDON,
+        Some(EvmHardfork::Shanghai) => SpecId::SHANGHAI,
+        Some(EvmHardfork::Cancun) => SpecId::CANCUN,
+        Some(EvmHardfork::Prague) => SpecId::PRAGUE,
+        None if header.requests_hash.is_some() => SpecId::PRAGUE,
+        None if header.parent_beacon_block_root.is_some() => SpecId::CANCUN,
+        None if header.withdrawals_root.is_some() => SpecId::SHANGHAI,
+        None => SpecId::LONDON,
+    }
+}
+
+/// Builds the transaction environment for an `eth_call` style request.
+///
+/// Nonce, fee and balance checks are disabled, matching the behavior of `eth_call`.
+pub(crate) fn tx_env(tx: &TransactionRequest, block: &BlockEnv) -> TxEnv {
+    TxEnv {
+        caller: tx.from.unwrap_or_default(),
+        gas_limit: tx.gas.unwrap_or(block.gas_limit.saturating_to()),
+        gas_price: U256::ZERO,
+        transact_to: tx.to.unwrap_or_default(),
+        value: tx.value.unwrap_or_default(),
+        data: tx.input.input().cloned().unwrap_or_default(),
+        nonce: None,
+        chain_id: None,
+        ..Default::default()
+    }
+}
+
+/// Decodes raw account code, rejecting malformed EOF code that revm cannot execute
+pub(crate) fn decode_code(code: Bytes) -> Result<Bytecode, String> {
+    Bytecode::new_raw_checked(code).map_err(|e| e.to_string())
+}
+
+/// Applies RPC style state overrides to the cached database
+pub(crate) fn apply_state_overrides<ExtDB>(
+    db: &mut CacheDB<ExtDB>,
+    state_override: StateOverride,
+) -> anyhow::Result<()>
+where
+    ExtDB: DatabaseRef,
+    ExtDB::Error: std::error::Error + Send + Sync + 'static,
+{
+    for (address, account) in state_override {
+        let mut info = db.basic(address)?.unwrap_or_default();
+        if let Some(balance) = account.balance {
+            info.balance = balance;
+        }
+        if let Some(nonce) = account.nonce {
+            info.nonce = nonce;
+        }
+        if let Some(code) = account.code {
+            info.code_hash = keccak256(&code);
+            info.code = Some(
+                decode_code(code)
+                    .map_err(|e| anyhow::anyhow!("invalid code override for {address:?}: {e}"))?,
+            );
+        }
+        db.insert_account_info(address, info);
+
+        if let Some(state) = account.state {
+            db.replace_account_storage(
+                address,
+                state
+                    .into_iter()
+                    .map(|(slot, value)| (slot.into(), value.into()))
+                    .collect(),
+            )?;
+        }
+        if let Some(state_diff) = account.state_diff {
+            for (slot, value) in state_diff {
+                db.insert_account_storage(address, slot.into(), value.into())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Executes a transaction with the given inspector without committing any state.
+///
+/// Returns the execution result along with the inspector.
+pub(crate) fn transact<DB, I>(
+    db: DB,
+    inspector: I,
+    env: ExecutionEnv,
+    tx: TxEnv,
+) -> anyhow::Result<(ExecutionResult, I)>
+where
+    DB: Database,
+    DB::Error: std::fmt::Display,
+    I: GetInspector<DB>,
+{
+    let p256verify = env.p256verify;
+    let mut builder = Evm::builder()
+        .with_db(db)
+        .with_external_context(inspector)
+        .with_spec_id(env.spec_id)
+        .modify_cfg_env(|cfg| {
+            cfg.chain_id = env.chain_id;
+            cfg.disable_balance_check = true;
+            cfg.disable_block_gas_limit = true;
+            cfg.disable_eip3607 = true;
+            cfg.disable_base_fee = true;
+        })
+        .with_block_env(env.block)
+        .with_tx_env(tx)
+        .append_handler_register(inspector_handle_register);
+    if p256verify {
+        builder = builder.append_handler_register(register_p256verify);
+    }
+    let mut evm = builder.build();
+
+    let result = evm
+        .transact()
+        .map_err(|e| anyhow::anyhow!("local execution failed: {e}"))?
+        .result;
+
+    Ok((result, evm.into_context().external))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::B256;
+
+    use super::*;
+
+    #[test]
+    fn test_spec_id_from_header() {
+        let chain_spec = ChainSpec::default();
+        let mut header = Header::default();
+        assert_eq!(spec_id(&chain_spec, &header), SpecId::LONDON);
+
+        header.withdrawals_root = Some(B256::ZERO);
+        assert_eq!(spec_id(&chain_spec, &header), SpecId::SHANGHAI);
+
+        header.parent_beacon_block_root = Some(B256::ZERO);
+        assert_eq!(spec_id(&chain_spec, &header), SpecId::CANCUN);
+
+        header.requests_hash = Some(B256::ZERO);
+        assert_eq!(spec_id(&chain_spec, &header), SpecId::PRAGUE);
+    }
+
+    #[test]
+    fn test_spec_id_from_chain_spec() {
+        let chain_spec = ChainSpec {
+            evm_hardfork: Some(EvmHardfork::Prague),
+            ..Default::default()
+        };
+        assert_eq!(spec_id(&chain_spec, &Header::default()), SpecId::PRAGUE);
+    }
+}
@@ -35,14 +35,18 @@ mod estimation;
 #[cfg(feature = "test-utils")]
 pub use estimation::MockGasEstimator;
 pub use estimation::{
-    CallGasEstimator, CallGasEstimatorImpl, CallGasEstimatorSpecialization, GasEstimationError,
-    GasEstimator, GasEstimatorV0_6, GasEstimatorV0_7, Settings as EstimationSettings,
-    VerificationGasEstimator, VerificationGasEstimatorImpl,
+    CallGasEstimator, CallGasEstimatorImpl, CallGasEstimatorSpecialization, EstimationEntryPoint,
+    GasEstimationError, GasEstimator, GasEstimatorV0_6, GasEstimatorV0_7, GasEstimatorV0_8,
+    GasUsedTracer, Settings as EstimationSettings, VerificationGasEstimator,
+    VerificationGasEstimatorImpl,
 };
 
 /// Gas estimation utilities
 pub mod gas;
 
+/// Local EVM execution
+mod evm;
+
 mod precheck;
 #[cfg(feature = "test-utils")]
 pub use precheck::MockPrechecker;
@@ -13,7 +13,7 @@
 
 use std::collections::{BTreeSet, HashMap, HashSet};
 
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{address, Address, U256};
 use anyhow::Context;
 use rundler_provider::BlockId;
 use rundler_types::{
@@ -121,6 +121,37 @@ pub trait ValidationContextProvider: Send + Sync {
     ) -> anyhow::Result<Vec<SimulationViolation>>;
 }
 
+/// Opcodes banned during validation, shared by all entry point versions and tracers.
+///
+/// Some banned opcodes (i.e. CREATE2) have special handling and aren't on this list. GAS is
+/// allowed when immediately followed by a call. Must be kept in sync with `FORBIDDEN_OPCODES`
+/// of the javascript tracer, which handles GAS separately.
+pub(crate) const BANNED_OPCODES: &[Opcode] = &[
+    Opcode::GAS,
+    Opcode::GASPRICE,
+    Opcode::GASLIMIT,
+    Opcode::DIFFICULTY,
+    Opcode::TIMESTAMP,
+    Opcode::BASEFEE,
+    Opcode::BLOCKHASH,
+    Opcode::BLOBBASEFEE,
+    Opcode::BLOBHASH,
+    Opcode::NUMBER,
+    Opcode::SELFBALANCE,
+    Opcode::BALANCE,
+    Opcode::ORIGIN,
+    Opcode::CREATE,
+    Opcode::COINBASE,
+    Opcode::SELFDESTRUCT,
+];
+
+/// Selector of `depositTo`, the only entry point method entities may call during validation
+pub(crate) const DEPOSIT_TO_METHOD: &str = "0xb760faf9";
+
+/// Max precompile address 0x10000
+pub(crate) const MAX_PRECOMPILE_ADDRESS: Address =
+    address!("0000000000000000000000000000000000010000");
+
 pub(crate) fn entity_type_from_simulation_phase(i: usize) -> Option<EntityType> {
     match i {
         0 => Some(EntityType::Factory),
@@ -130,6 +161,13 @@ pub(crate) fn entity_type_from_simulation_phase(i: usize) -> Option<EntityType>
     }
 }
 
+// not using precompile checks to only allow the ones defined by the ERC-4337 as stateless precompiles
+// MODIFICATION: allow precompile RIP-7212 through - which is at 256
+pub(crate) fn is_allowed_precompile(address: Address) -> bool {
+    let address = U256::from_be_slice(address.as_slice());
+    (address > U256::ZERO && address < U256::from(10)) || address == U256::from(256)
+}
+
 pub(crate) fn infos_from_validation_output(
     factory_address: Option<Address>,
     sender_address: Address,
@@ -178,3 +216,32 @@ where
         .context("tracer combined should contain two parts")?;
     Ok((a.parse()?, b.parse()?))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_banned_opcodes_match_js_tracer() {
+        let tracer = include_str!("../../tracer/src/validationTracerV0_6.ts");
+        let start = tracer
+            .find("const FORBIDDEN_OPCODES = stringSet([")
+            .unwrap();
+        let end = start + tracer[start..].find("]);").unwrap();
+        let mut js_opcodes: Vec<Opcode> = tracer[start..end]
+            .split('"')
+            .skip(1)
+            .step_by(2)
+            .map(|name| Opcode::from_str(name).unwrap())
+            .collect();
+        // the javascript tracer bans GAS with its own check
+        js_opcodes.push(Opcode::GAS);
+
+        let mut opcodes = BANNED_OPCODES.to_vec();
+        opcodes.sort();
+        js_opcodes.sort();
+        assert_eq!(opcodes, js_opcodes);
+    }
+}
@@ -0,0 +1,566 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+//! Support for the native `erc7562Tracer` shipped by geth and reth.
+//!
+//! The native tracer returns a call frame tree annotated with the opcodes, storage
+//! slots and contracts accessed by each frame. The entry point specific tracers map
+//! this tree into the same output as the javascript tracers.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+};
+
+use alloy_primitives::{hex, Address, Bytes, U256, U64};
+use anyhow::Context;
+use futures_util::future;
+use rundler_provider::{
+    BlockId, EvmProvider, GethDebugTracerConfig, GethDebugTracerType, GethDebugTracingCallOptions,
+    GethDebugTracingOptions, ProviderError, StateOverride, TransactionRequest,
+};
+use rundler_types::{ExpectedStorage, Opcode};
+use rundler_utils::cache::LruMap;
+use serde::Deserialize;
+use tracing::warn;
+
+use super::context::{self, ContractInfo};
+
+/// Name of the native tracer
+const TRACER_NAME: &str = "erc7562Tracer";
+/// Number of contract code headers cached
+const CODE_HEADER_CACHE_SIZE: u32 = 1024;
+/// Code prefix of an account delegated with EIP-7702
+const EIP7702_DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// A call frame produced by the native tracer
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Frame {
+    #[serde(rename = "type")]
+    pub(crate) call_type: Opcode,
+    pub(crate) from: Address,
+    #[serde(default)]
+    pub(crate) to: Option<Address>,
+    #[serde(default)]
+    pub(crate) value: Option<U256>,
+    pub(crate) gas: U64,
+    pub(crate) gas_used: U64,
+    #[serde(default)]
+    pub(crate) input: Bytes,
+    #[serde(default)]
+    pub(crate) output: Option<Bytes>,
+    #[serde(default)]
+    pub(crate) error: Option<String>,
+    #[serde(default)]
+    pub(crate) logs: Vec<FrameLog>,
+    #[serde(default)]
+    pub(crate) accessed_slots: AccessedSlots,
+    #[serde(default)]
+    pub(crate) ext_code_access_info: Vec<Address>,
+    #[serde(default)]
+    pub(crate) used_opcodes: HashMap<String, u64>,
+    #[serde(default)]
+    pub(crate) contract_size: HashMap<Address, ContractSize>,
+    #[serde(default)]
+    pub(crate) out_of_gas: bool,
+    /// Keccak preimages, only set on the root frame
+    #[serde(default)]
+    pub(crate) keccak: Vec<Bytes>,
+    #[serde(default)]
+    pub(crate) calls: Vec<Frame>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub(crate) struct FrameLog {
+    #[serde(default)]
+    pub(crate) topics: Vec<U256>,
+    #[serde(default)]
+    pub(crate) data: Bytes,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AccessedSlots {
+    /// Values of slots read before being written by the frame, in read order
+    #[serde(default)]
+    pub(crate) reads: HashMap<U256, Vec<U256>>,
+    /// Count of writes to each slot
+    #[serde(default)]
+    pub(crate) writes: HashMap<U256, u64>,
+    /// Count of reads of each transient slot
+    #[serde(default)]
+    pub(crate) transient_reads: HashMap<U256, u64>,
+    /// Count of writes to each transient slot
+    #[serde(default)]
+    pub(crate) transient_writes: HashMap<U256, u64>,
+}
+
+impl AccessedSlots {
+    /// Slots read by the frame with the first value read.
+    ///
+    /// [OP-070] Transient slots are checked as storage slots. Their value isn't returned by
+    /// the native tracer and is reported as zero.
+    pub(crate) fn read_slots(&self) -> impl Iterator<Item = (U256, U256)> + '_ {
+        self.reads
+            .iter()
+            .filter_map(|(slot, values)| values.first().map(|value| (*slot, *value)))
+            .chain(self.transient_reads.keys().map(|slot| (*slot, U256::ZERO)))
+    }
+
+    /// Slots written by the frame with the count of writes, including transient slots
+    pub(crate) fn written_slots(&self) -> impl Iterator<Item = (U256, u64)> + '_ {
+        self.writes
+            .iter()
+            .chain(&self.transient_writes)
+            .map(|(slot, count)| (*slot, *count))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ContractSize {
+    pub(crate) contract_size: u64,
+    pub(crate) opcode: RawOpcode,
+}
+
+/// Opcodes are serialized either by number or by name depending on the client
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum RawOpcode {
+    Number(u8),
+    Name(String),
+}
+
+impl RawOpcode {
+    fn parse(&self) -> Option<Opcode> {
+        match self {
+            RawOpcode::Number(n) => Opcode::try_from(*n).ok(),
+            RawOpcode::Name(name) => parse_opcode(name),
+        }
+    }
+}
+
+fn parse_opcode(s: &str) -> Option<Opcode> {
+    if let Ok(n) = s.parse::<u8>() {
+        return Opcode::try_from(n).ok();
+    }
+    if let Some(n) = s.strip_prefix("0x") {
+        return u8::from_str_radix(n, 16)
+            .ok()
+            .and_then(|n| Opcode::try_from(n).ok());
+    }
+    Opcode::from_str(s).ok()
+}
+
+impl Frame {
+    /// The address whose storage is accessed by this frame
+    pub(crate) fn storage_address(&self) -> Address {
+        match self.call_type {
+            Opcode::DELEGATECALL | Opcode::CALLCODE => self.from,
+            _ => self.to.unwrap_or_default(),
+        }
+    }
+
+    /// The hex encoded 4 byte method selector of the input, shorter if the input is
+    pub(crate) fn method_sig(&self) -> String {
+        hex::encode_prefixed(&self.input[..self.input.len().min(4)])
+    }
+
+    /// This frame followed by all of its descendants, depth first in call order
+    pub(crate) fn frames(&self) -> Vec<&Frame> {
+        let mut frames = vec![self];
+        for call in &self.calls {
+            frames.extend(call.frames());
+        }
+        frames
+    }
+
+    /// Opcodes used directly by this frame, skipping any unknown to rundler
+    pub(crate) fn opcodes(&self) -> impl Iterator<Item = (Opcode, u64)> + '_ {
+        self.used_opcodes
+            .iter()
+            .filter_map(|(opcode, count)| parse_opcode(opcode).map(|opcode| (opcode, *count)))
+    }
+
+    /// The first external code opcode used directly by this frame
+    pub(crate) fn ext_code_opcode(&self) -> Opcode {
+        [
+            Opcode::EXTCODESIZE,
+            Opcode::EXTCODECOPY,
+            Opcode::EXTCODEHASH,
+        ]
+        .into_iter()
+        .find(|opcode| self.opcodes().any(|(used, _)| used == *opcode))
+        .unwrap_or(Opcode::EXTCODESIZE)
+    }
+
+    /// Contracts accessed by this frame, excluding the allowed precompiles.
+    ///
+    /// The code header isn't returned by the native tracer and is left empty,
+    /// see [`NativeTracer::fill_contract_headers`].
+    pub(crate) fn contract_info(&self) -> impl Iterator<Item = (Address, ContractInfo)> + '_ {
+        self.contract_size
+            .iter()
+            .filter(|(address, _)| !context::is_allowed_precompile(**address))
+            .map(|(address, size)| {
+                (
+                    *address,
+                    ContractInfo {
+                        header: String::new(),
+                        opcode: size.opcode.parse().unwrap_or_default(),
+                        length: size.contract_size,
+                    },
+                )
+            })
+    }
+}
+
+/// Storage values read before being written across the whole trace
+pub(crate) fn expected_storage(root: &Frame) -> ExpectedStorage {
+    let mut initial_values: HashMap<(Address, U256), Option<U256>> = HashMap::new();
+    for frame in root.frames() {
+        let address = frame.storage_address();
+        for (slot, values) in &frame.accessed_slots.reads {
+            if let Entry::Vacant(e) = initial_values.entry((address, *slot)) {
+                e.insert(values.first().copied());
+            }
+        }
+        for slot in frame.accessed_slots.writes.keys() {
+            initial_values.entry((address, *slot)).or_insert(None);
+        }
+    }
+
+    let mut expected_storage = ExpectedStorage::default();
+    for ((address, slot), value) in initial_values {
+        if let Some(value) = value {
+            expected_storage.insert(address, slot, value);
+        }
+    }
+    expected_storage
+}
+
+/// Runs the native tracer on the node, detecting whether the node supports it.
+#[derive(Debug)]
+pub(crate) struct NativeTracer<P> {
+    provider: P,
+    tracer_timeout: String,
+    supported: OnceLock<bool>,
+    code_headers: Mutex<LruMap<Address, String>>,
+}
+
+impl<P: EvmProvider> NativeTracer<P> {
+    pub(crate) fn new(provider: P, tracer_timeout: String) -> Self {
+        Self {
+            provider,
+            tracer_timeout,
+            supported: OnceLock::new(),
+            code_headers: Mutex::new(LruMap::new(CODE_HEADER_CACHE_SIZE)),
+        }
+    }
+
+    /// Traces the call with the native tracer.
+    ///
+    /// Returns `None` if the node doesn't support the native tracer. Support is
+    /// determined by the first trace and cached.
+    pub(crate) async fn trace_call(
+        &self,
+        tx: TransactionRequest,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> anyhow::Result<Option<Frame>> {
+        if self.supported.get() == Some(&false) {
+            return Ok(None);
+        }
+
+        let options = GethDebugTracingCallOptions {
+            tracing_options: GethDebugTracingOptions {
+                tracer: Some(GethDebugTracerType::JsTracer(TRACER_NAME.to_string())),
+                tracer_config: GethDebugTracerConfig(serde_json::json!({ "withLog": true })),
+                timeout: Some(self.tracer_timeout.clone()),
+                ..Default::default()
+            },
+            state_overrides: Some(state_override),
+            block_overrides: None,
+        };
+
+        // Use a raw request, the frame format isn't known to the provider's trace types
+        match self
+            .provider
+            .request::<_, serde_json::Value>("debug_traceCall", (tx, block_id, options))
+            .await
+        {
+            Ok(out) => {
+                let _ = self.supported.set(true);
+                Ok(Some(
+                    Frame::deserialize(&out).context("should deserialize native tracer frame")?,
+                ))
+            }
+            Err(e) if self.supported.get().is_none() && is_unsupported_tracer_error(&e) => {
+                warn!("Node does not support the native {TRACER_NAME}, falling back to the javascript tracer: {e}");
+                let _ = self.supported.set(false);
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Fills in the code header of the accessed contracts.
+    ///
+    /// The code of contracts deployed during the trace is taken from the output of their
+    /// creation frame and overridden code from `state_override`. Other contracts are fetched
+    /// from the node, caching the header of code that can't change.
+    pub(crate) async fn fill_contract_headers(
+        &self,
+        root: &Frame,
+        state_override: &StateOverride,
+        block_id: BlockId,
+        contracts: &mut HashMap<Address, ContractInfo>,
+    ) -> anyhow::Result<()> {
+        let created: HashMap<Address, &Bytes> = root
+            .frames()
+            .into_iter()
+            .filter(|frame| matches!(frame.call_type, Opcode::CREATE | Opcode::CREATE2))
+            .filter_map(|frame| Some((frame.to?, frame.output.as_ref()?)))
+            .collect();
+
+        let mut to_fetch = vec![];
+        {
+            let mut code_headers = self.code_headers.lock().unwrap();
+            for (address, info) in contracts.iter_mut() {
+                let code = created.get(address).copied().or_else(|| {
+                    state_override
+                        .get(address)
+                        .and_then(|account| account.code.as_ref())
+                });
+                if let Some(code) = code {
+                    info.header = code_header(code);
+                } else if info.length == 0 {
+                    info.header = "0x".to_string();
+                } else if let Some(header) = code_headers.get(address) {
+                    info.header = header.clone();
+                } else {
+                    to_fetch.push(*address);
+                }
+            }
+        }
+
+        let codes = future::try_join_all(to_fetch.into_iter().map(|address| async move {
+            let code = self.provider.get_code(address, Some(block_id)).await?;
+            Ok::<_, ProviderError>((address, code))
+        }))
+        .await?;
+
+        let mut code_headers = self.code_headers.lock().unwrap();
+        for (address, code) in codes {
+            let header = code_header(&code);
+            // EIP-7702 delegations can change, other deployed code can't
+            if !code.is_empty() && !code.starts_with(&EIP7702_DELEGATION_PREFIX) {
+                code_headers.insert(address, header.clone());
+            }
+            if let Some(info) = contracts.get_mut(&address) {
+                info.header = header;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn code_header(code: &Bytes) -> String {
+    hex::encode_prefixed(&code[..code.len().min(3)])
+}
+
+fn is_unsupported_tracer_error(error: &ProviderError) -> bool {
+    let ProviderError::RPC(error) = error else {
+        return false;
+    };
+    let Some(payload) = error.as_error_resp() else {
+        return false;
+    };
+    let message = payload.message.to_lowercase();
+    message.contains("tracer")
+        && ["not found", "not supported", "unsupported", "unknown"]
+            .iter()
+            .any(|m| message.contains(m))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use alloy_primitives::{address, bytes};
+    use rundler_provider::{AccountOverride, BlockNumberOrTag, MockEvmProvider};
+
+    use super::*;
+
+    pub(crate) const ENTRY_POINT: Address = address!("0000000071727De22E5E9d8BAf0edAc6f37da032");
+    pub(crate) const ACCOUNT: Address = address!("1000000000000000000000000000000000000001");
+    pub(crate) const LIBRARY: Address = address!("2000000000000000000000000000000000000002");
+
+    /// An entry point calling an account that delegate calls a library
+    pub(crate) fn frame(account_method: &str) -> Frame {
+        serde_json::from_value(serde_json::json!({
+            "type": "CALL",
+            "from": "0x0000000000000000000000000000000000000000",
+            "to": ENTRY_POINT,
+            "gas": "0x1000000",
+            "gasUsed": "0x10000",
+            "input": "0xee219423",
+            "output": "0x1234",
+            "error": "execution reverted",
+            "accessedSlots": {
+                "reads": { "0x01": ["0x05"] },
+                "writes": { "0x02": 1 },
+            },
+            "usedOpcodes": { "67": 1, "85": 1 },
+            "keccak": ["0x0000000000000000000000001000000000000000000000000000000000000001"],
+            "calls": [{
+                "type": "CALL",
+                "from": ENTRY_POINT,
+                "to": ACCOUNT,
+                "gas": "0x100000",
+                "gasUsed": "0x1000",
+                "input": account_method,
+                "output": "0x",
+                "logs": [{
+                    "address": ACCOUNT,
+                    "topics": ["0x0000000000000000000000000000000000000000000000000000000000000001"],
+                    "data": "0xabcd",
+                }],
+                "accessedSlots": {
+                    "reads": { "0x01": ["0x06"], "0x03": ["0x07", "0x08"] },
+                    "writes": { "0x04": 2 },
+                },
+                "usedOpcodes": { "TIMESTAMP": 2, "0x5a": 1, "SLOAD": 1 },
+                "contractSize": {
+                    "0x2000000000000000000000000000000000000002": { "contractSize": 100, "opcode": 244 },
+                    "0x0000000000000000000000000000000000000001": { "contractSize": 0, "opcode": "STATICCALL" },
+                    "0x0000000000000000000000000000000000000011": { "contractSize": 0, "opcode": "STATICCALL" },
+                },
+                "extCodeAccessInfo": [ENTRY_POINT],
+                "calls": [{
+                    "type": "DELEGATECALL",
+                    "from": ACCOUNT,
+                    "to": LIBRARY,
+                    "gas": "0x10000",
+                    "gasUsed": "0x100",
+                    "input": "0x",
+                    "accessedSlots": {
+                        "reads": {},
+                        "writes": { "0x03": 1 },
+                        "transientReads": { "0x09": 1 },
+                        "transientWrites": { "0x0a": 3 },
+                    },
+                    "usedOpcodes": { "EXTCODEHASH": 1 },
+                    "outOfGas": true,
+                }],
+            }],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_frame_parsing() {
+        let root = frame("0x19822f7c");
+        let frames = root.frames();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1].method_sig(), "0x19822f7c");
+        assert_eq!(frames[2].storage_address(), ACCOUNT);
+        assert_eq!(frames[2].ext_code_opcode(), Opcode::EXTCODEHASH);
+
+        let opcodes: HashMap<_, _> = frames[1].opcodes().collect();
+        assert_eq!(
+            opcodes,
+            HashMap::from([(Opcode::TIMESTAMP, 2), (Opcode::GAS, 1), (Opcode::SLOAD, 1)])
+        );
+
+        // transient slots are merged into the storage accesses
+        let reads: HashMap<_, _> = frames[2].accessed_slots.read_slots().collect();
+        assert_eq!(reads, HashMap::from([(U256::from(9), U256::ZERO)]));
+        let writes: HashMap<_, _> = frames[2].accessed_slots.written_slots().collect();
+        assert_eq!(
+            writes,
+            HashMap::from([(U256::from(3), 1), (U256::from(10), 3)])
+        );
+
+        let contracts: HashMap<_, _> = frames[1].contract_info().collect();
+        assert_eq!(contracts.len(), 2);
+        assert_eq!(contracts[&LIBRARY].opcode, Opcode::DELEGATECALL);
+        assert_eq!(contracts[&LIBRARY].length, 100);
+    }
+
+    #[test]
+    fn test_expected_storage() {
+        let root = frame("0x19822f7c");
+        let mut expected = ExpectedStorage::default();
+        expected.insert(ENTRY_POINT, U256::from(1), U256::from(5));
+        expected.insert(ACCOUNT, U256::from(1), U256::from(6));
+        expected.insert(ACCOUNT, U256::from(3), U256::from(7));
+        assert_eq!(expected_storage(&root).0, expected.0);
+    }
+
+    #[tokio::test]
+    async fn test_fill_contract_headers() {
+        const CREATED: Address = address!("3000000000000000000000000000000000000003");
+        const OVERRIDDEN: Address = address!("4000000000000000000000000000000000000004");
+
+        let mut root = frame("0x19822f7c");
+        let mut create = root.calls[0].clone();
+        create.call_type = Opcode::CREATE2;
+        create.to = Some(CREATED);
+        create.output = Some(bytes!("eff00012"));
+        create.calls.clear();
+        root.calls.push(create);
+
+        let mut state_override = StateOverride::default();
+        state_override.insert(
+            OVERRIDDEN,
+            AccountOverride {
+                code: Some(bytes!("60806040")),
+                ..Default::default()
+            },
+        );
+
+        // only the library is fetched from the node, once
+        let mut provider = MockEvmProvider::new();
+        provider
+            .expect_get_code()
+            .withf(|address, _| *address == LIBRARY)
+            .once()
+            .returning(|_, _| Ok(bytes!("60016002")));
+        let tracer = NativeTracer::new(provider, "10s".to_string());
+
+        let info = |length| ContractInfo {
+            header: String::new(),
+            opcode: Opcode::CALL,
+            length,
+        };
+        let block_id = BlockId::Number(BlockNumberOrTag::Latest);
+        for _ in 0..2 {
+            let mut contracts = HashMap::from([
+                (LIBRARY, info(4)),
+                (CREATED, info(4)),
+                (OVERRIDDEN, info(4)),
+                (ENTRY_POINT, info(0)),
+            ]);
+            tracer
+                .fill_contract_headers(&root, &state_override, block_id, &mut contracts)
+                .await
+                .unwrap();
+
+            assert_eq!(contracts[&LIBRARY].header, "0x600160");
+            assert_eq!(contracts[&CREATED].header, "0xeff000");
+            assert_eq!(contracts[&OVERRIDDEN].header, "0x608060");
+            assert_eq!(contracts[&ENTRY_POINT].header, "0x");
+        }
+    }
+}
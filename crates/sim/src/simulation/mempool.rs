@@ -11,9 +11,13 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
 
-use alloy_primitives::{Address, B256, U256};
+use alloy_primitives::{Address, FixedBytes, B256, U256};
+use alloy_sol_types::{sol, SolCall};
 use rundler_types::{Entity, EntityType, Opcode, UserOperation, UserOperationVariant};
 use serde::Deserialize;
 use serde_with::{serde_as, DisplayFromStr};
@@ -116,18 +120,98 @@ struct MempoolFilter {
 impl MempoolFilter {
     /// Apply the filter to an operation
     fn apply(&self, operation: &UserOperationVariant) -> bool {
-        match &self.filter {
-            Filter::Aggregator(address) => operation.aggregator().is_some_and(|a| a == *address),
-        }
+        self.filter.matches(operation)
     }
 }
 
+sol! {
+    /// `execute` function of the ERC-4337 reference account
+    function execute(address dest, uint256 value, bytes func);
+}
+
 /// A filter kind
+///
+/// Filters can be composed with `all`, `any` and `not`, for example:
+///
+/// ```json
+/// {
+///   "all": [
+///     { "paymaster": "0x..." },
+///     { "not": { "callGasLimit": { "max": 1000000 } } }
+///   ]
+/// }
+/// ```
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 enum Filter {
     /// Filter operations by aggregator address
     Aggregator(Address),
+    /// Filter operations by paymaster address
+    Paymaster(Address),
+    /// Filter operations by the factory deploying their sender
+    Factory(Address),
+    /// Filter operations from a set of senders
+    Senders(HashSet<Address>),
+    /// Filter operations by the selector of their call data
+    CallDataSelector(FixedBytes<4>),
+    /// Filter operations calling the account's `execute(address,uint256,bytes)` function
+    /// by the selector of the call the account makes
+    ExecuteSelector(FixedBytes<4>),
+    /// Filter operations with an EIP-7702 authorization
+    Eip7702Authorized,
+    /// Filter operations with at least this max priority fee per gas
+    MinPriorityFee(u128),
+    /// Filter operations by call gas limit
+    CallGasLimit(GasLimitBand),
+    /// Filter operations by total verification gas limit, including the paymaster's
+    VerificationGasLimit(GasLimitBand),
+    /// Matches if all of the filters match
+    All(Vec<Filter>),
+    /// Matches if any of the filters match
+    Any(Vec<Filter>),
+    /// Matches if the filter does not match
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    fn matches(&self, operation: &UserOperationVariant) -> bool {
+        match self {
+            Filter::Aggregator(address) => operation.aggregator().is_some_and(|a| a == *address),
+            Filter::Paymaster(address) => operation.paymaster().is_some_and(|p| p == *address),
+            Filter::Factory(address) => operation.factory().is_some_and(|f| f == *address),
+            Filter::Senders(senders) => senders.contains(&operation.sender()),
+            Filter::CallDataSelector(selector) => operation.call_data().starts_with(&selector[..]),
+            Filter::ExecuteSelector(selector) => {
+                executeCall::abi_decode(operation.call_data(), false)
+                    .is_ok_and(|call| call.func.starts_with(&selector[..]))
+            }
+            Filter::Eip7702Authorized => operation.authorization_tuple().is_some(),
+            Filter::MinPriorityFee(min) => operation.max_priority_fee_per_gas() >= *min,
+            Filter::CallGasLimit(band) => band.contains(operation.call_gas_limit()),
+            Filter::VerificationGasLimit(band) => {
+                band.contains(operation.total_verification_gas_limit())
+            }
+            Filter::All(filters) => filters.iter().all(|f| f.matches(operation)),
+            Filter::Any(filters) => filters.iter().any(|f| f.matches(operation)),
+            Filter::Not(filter) => !filter.matches(operation),
+        }
+    }
+}
+
+/// An inclusive range of gas limits, unbounded if a limit is not set
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GasLimitBand {
+    #[serde(default)]
+    min: Option<u128>,
+    #[serde(default)]
+    max: Option<u128>,
+}
+
+impl GasLimitBand {
+    fn contains(&self, gas_limit: u128) -> bool {
+        self.min.is_none_or(|min| gas_limit >= min) && self.max.is_none_or(|max| gas_limit <= max)
+    }
 }
 
 /// An allowlist rule.
@@ -264,8 +348,14 @@ pub(crate) fn match_mempools(
 
 #[cfg(test)]
 mod tests {
-    use alloy_primitives::U256;
-    use rundler_types::{pool::NeedsStakeInformation, StorageSlot, ViolationOpCode};
+    use alloy_primitives::{Bytes, U256};
+    use rundler_types::{
+        chain::ChainSpec,
+        pool::NeedsStakeInformation,
+        v0_6::{UserOperationBuilder, UserOperationRequiredFields},
+        StorageSlot, ViolationOpCode,
+    };
+    use serde_json::json;
 
     use super::*;
 
@@ -728,4 +818,99 @@ mod tests {
             _ => panic!("Expected matches"),
         }
     }
+
+    #[test]
+    fn test_match_filter_composition() {
+        let paymaster = Address::random();
+        let config: MempoolConfig = serde_json::from_value(json!({
+            "entryPoint": Address::ZERO,
+            "filters": [
+                {
+                    "id": "sponsored",
+                    "filter": {
+                        "all": [
+                            { "paymaster": paymaster },
+                            { "not": { "callGasLimit": { "min": 1000000 } } }
+                        ]
+                    }
+                },
+                {
+                    "id": "priority",
+                    "filter": { "any": ["eip7702Authorized", { "minPriorityFee": 10 }] }
+                }
+            ]
+        }))
+        .unwrap();
+
+        let sponsored = UserOperationRequiredFields {
+            paymaster_and_data: Bytes::copy_from_slice(paymaster.as_slice()),
+            call_gas_limit: 100_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.match_filter(&test_op(sponsored.clone())),
+            Some("sponsored".to_string())
+        );
+
+        let sponsored_large = UserOperationRequiredFields {
+            call_gas_limit: 2_000_000,
+            ..sponsored.clone()
+        };
+        assert_eq!(config.match_filter(&test_op(sponsored_large.clone())), None);
+
+        let priority = UserOperationRequiredFields {
+            max_priority_fee_per_gas: 20,
+            ..sponsored_large
+        };
+        assert_eq!(
+            config.match_filter(&test_op(priority)),
+            Some("priority".to_string())
+        );
+    }
+
+    #[test]
+    fn test_filter_selectors() {
+        let call_data = executeCall {
+            dest: Address::random(),
+            value: U256::ZERO,
+            func: Bytes::from_static(&[0xa9, 0x05, 0x9c, 0xbb, 0x00, 0x01]),
+        }
+        .abi_encode();
+        let op = test_op(UserOperationRequiredFields {
+            call_data: call_data.into(),
+            ..Default::default()
+        });
+
+        let filter: Filter = serde_json::from_value(json!({
+            "callDataSelector": FixedBytes::from(executeCall::SELECTOR)
+        }))
+        .unwrap();
+        assert!(filter.matches(&op));
+
+        let filter: Filter =
+            serde_json::from_value(json!({ "executeSelector": "0xa9059cbb" })).unwrap();
+        assert!(filter.matches(&op));
+
+        let filter: Filter =
+            serde_json::from_value(json!({ "executeSelector": "0x095ea7b3" })).unwrap();
+        assert!(!filter.matches(&op));
+    }
+
+    #[test]
+    fn test_filter_senders() {
+        let sender = Address::random();
+        let filter: Filter = serde_json::from_value(json!({ "senders": [sender] })).unwrap();
+
+        assert!(filter.matches(&test_op(UserOperationRequiredFields {
+            sender,
+            ..Default::default()
+        })));
+        assert!(!filter.matches(&test_op(UserOperationRequiredFields::default())));
+    }
+
+    fn test_op(required: UserOperationRequiredFields) -> UserOperationVariant {
+        UserOperationBuilder::new(&ChainSpec::default(), required)
+            .build()
+            .into()
+    }
 }
@@ -11,7 +11,7 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::collections::HashSet;
+use std::{collections::HashSet, sync::Arc};
 
 #[cfg(feature = "test-utils")]
 use alloy_primitives::uint;
@@ -20,6 +20,7 @@ use alloy_primitives::{Address, B256, U256};
 use mockall::automock;
 use rundler_provider::ProviderError;
 use rundler_types::{
+    chain::{ChainSpec, SimulationEngine},
     pool::{MempoolError, SimulationViolation},
     EntityInfos, ExpectedStorage, UserOperation, ValidTimeRange,
 };
@@ -27,11 +28,13 @@ use rundler_types::{
 mod context;
 pub use context::ValidationContextProvider;
 
+mod erc7562;
+
 mod mempool;
 pub use mempool::{MempoolConfig, MempoolConfigs};
 
 mod simulator;
-pub use simulator::{new_v0_6_simulator, new_v0_7_simulator, SimulatorImpl};
+pub use simulator::{new_v0_6_simulator, new_v0_7_simulator, new_v0_8_simulator, SimulatorImpl};
 
 mod unsafe_sim;
 pub use unsafe_sim::UnsafeSimulator;
@@ -146,6 +149,23 @@ pub trait Simulator: Send + Sync {
     ) -> Result<SimulationResult, SimulationError>;
 }
 
+#[async_trait::async_trait]
+impl<S: Simulator + ?Sized> Simulator for Arc<S> {
+    type UO = S::UO;
+
+    async fn simulate_validation(
+        &self,
+        op: Self::UO,
+        trusted: bool,
+        block_hash: B256,
+        expected_code_hash: Option<B256>,
+    ) -> Result<SimulationResult, SimulationError> {
+        (**self)
+            .simulate_validation(op, trusted, block_hash, expected_code_hash)
+            .await
+    }
+}
+
 /// Simulation Settings
 #[derive(Debug, Clone)]
 pub struct Settings {
@@ -160,6 +180,10 @@ pub struct Settings {
     pub tracer_timeout: String,
     /// If set, allows the simulator to fallback to unsafe mode if the simulation tracer fails
     pub enable_unsafe_fallback: bool,
+    /// The engine used to run validation for entry point v0.7 and later
+    pub simulation_engine: SimulationEngine,
+    /// The chain spec, used when executing validation locally
+    pub chain_spec: ChainSpec,
 }
 
 #[cfg(any(test, feature = "test-utils"))]
@@ -172,6 +196,8 @@ impl Default for Settings {
             min_stake_value: uint!(1_000_000_000_000_000_000_U256),
             tracer_timeout: "10s".to_string(),
             enable_unsafe_fallback: false,
+            simulation_engine: SimulationEngine::JsTracer,
+            chain_spec: ChainSpec::default(),
         }
     }
 }
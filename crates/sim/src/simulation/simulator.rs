@@ -24,6 +24,7 @@ use rundler_types::{
     pool::{NeedsStakeInformation, SimulationViolation},
     v0_6::UserOperation as UserOperationV0_6,
     v0_7::UserOperation as UserOperationV0_7,
+    v0_8::UserOperation as UserOperationV0_8,
     Entity, EntityInfo, EntityInfos, EntityType, Opcode, StorageSlot, UserOperation,
     ValidTimeRange, ValidationOutput, ValidationReturnInfo, ViolationOpCode,
 };
@@ -46,6 +47,10 @@ use crate::{
 };
 
 /// Create a new simulator for v0.6 entry point contracts
+///
+/// The simulation engine in the settings selects the tracer used for validation,
+/// revm isn't supported for v0.6 and the javascript tracer is used instead. The CLI
+/// rejects this combination at startup.
 pub fn new_v0_6_simulator<P, E>(
     provider: P,
     entry_point: E,
@@ -53,8 +58,8 @@ pub fn new_v0_6_simulator<P, E>(
     mempool_configs: HashMap<B256, MempoolConfig>,
 ) -> impl Simulator<UO = UserOperationV0_6>
 where
-    P: EvmProvider + Clone,
-    E: EntryPoint + SimulationProvider<UO = UserOperationV0_6> + Clone,
+    P: EvmProvider + Clone + 'static,
+    E: EntryPoint + SimulationProvider<UO = UserOperationV0_6> + Clone + 'static,
 {
     SimulatorImpl::new(
         provider.clone(),
@@ -65,7 +70,9 @@ where
     )
 }
 
-/// Create a new simulator for v0.6 entry point contracts
+/// Create a new simulator for v0.7 entry point contracts
+///
+/// The simulation engine in the settings selects the tracer used for validation.
 pub fn new_v0_7_simulator<P, E>(
     provider: P,
     entry_point: E,
@@ -73,8 +80,31 @@ pub fn new_v0_7_simulator<P, E>(
     mempool_configs: HashMap<B256, MempoolConfig>,
 ) -> impl Simulator<UO = UserOperationV0_7>
 where
-    P: EvmProvider + Clone,
-    E: EntryPoint + SimulationProvider<UO = UserOperationV0_7> + Clone,
+    P: EvmProvider + Clone + 'static,
+    E: EntryPoint + SimulationProvider<UO = UserOperationV0_7> + Clone + 'static,
+{
+    SimulatorImpl::new(
+        provider.clone(),
+        entry_point.clone(),
+        ValidationContextProviderV0_7::new(provider, entry_point, sim_settings.clone()),
+        sim_settings,
+        mempool_configs,
+    )
+}
+
+/// Create a new simulator for v0.8 entry point contracts
+///
+/// The v0.8 entry point keeps the v0.7 simulation interface, so the v0.7 validation
+/// context provider and tracer are reused.
+pub fn new_v0_8_simulator<P, E>(
+    provider: P,
+    entry_point: E,
+    sim_settings: Settings,
+    mempool_configs: HashMap<B256, MempoolConfig>,
+) -> impl Simulator<UO = UserOperationV0_8>
+where
+    P: EvmProvider + Clone + 'static,
+    E: EntryPoint + SimulationProvider<UO = UserOperationV0_8> + Clone + 'static,
 {
     SimulatorImpl::new(
         provider.clone(),
@@ -342,7 +372,7 @@ where
         }
 
         for (address, contract_info) in &tracer_out.accessed_contracts {
-            if contract_info.header.as_str() == "0xEFF000" {
+            if contract_info.header.eq_ignore_ascii_case("0xEFF000") {
                 // All arbitrum stylus contracts start with 0xEFF000
                 violations.push(SimulationViolation::AccessedUnsupportedContractType(
                     "Arbitrum Stylus".to_string(),
@@ -19,12 +19,14 @@ use anyhow::Context;
 use rundler_contracts::v0_6::IEntryPoint::FailedOp;
 use rundler_provider::{BlockId, EvmProvider, SimulationProvider};
 use rundler_types::{
-    pool::SimulationViolation, v0_6::UserOperation, EntityType,
+    chain::SimulationEngine, pool::SimulationViolation, v0_6::UserOperation, EntityType,
     UserOperation as UserOperationTrait, ValidationOutput,
 };
 
 use super::{
-    tracer::{SimulateValidationTracer, SimulateValidationTracerImpl},
+    tracer::{
+        NativeSimulateValidationTracer, SimulateValidationTracer, SimulateValidationTracerImpl,
+    },
     REQUIRED_VERIFICATION_GAS_LIMIT_BUFFER,
 };
 use crate::{
@@ -175,19 +177,40 @@ where
     }
 }
 
-impl<P, E> ValidationContextProvider<SimulateValidationTracerImpl<P, E>>
-where
-    P: EvmProvider,
-    E: SimulationProvider<UO = UserOperation>,
-{
+impl ValidationContextProvider<Box<dyn SimulateValidationTracer>> {
     /// Creates a new `ValidationContextProvider` for entry point v0.6 with the given provider and entry point.
-    pub(crate) fn new(provider: P, entry_point: E, sim_settings: SimulationSettings) -> Self {
+    ///
+    /// The tracer is selected by the simulation engine in the settings.
+    ///
+    /// # Panics
+    ///
+    /// If the simulation engine is revm, which isn't supported for entry point v0.6 and is
+    /// rejected when the settings are parsed.
+    pub(crate) fn new<P, E>(provider: P, entry_point: E, sim_settings: SimulationSettings) -> Self
+    where
+        P: EvmProvider + Clone + 'static,
+        E: SimulationProvider<UO = UserOperation> + Clone + 'static,
+    {
+        let tracer_timeout = sim_settings.tracer_timeout.clone();
+        let simulate_validation_tracer: Box<dyn SimulateValidationTracer> =
+            match sim_settings.simulation_engine {
+                SimulationEngine::JsTracer => Box::new(SimulateValidationTracerImpl::new(
+                    provider,
+                    entry_point,
+                    tracer_timeout,
+                )),
+                SimulationEngine::Revm => {
+                    unreachable!("simulation engine REVM is not supported for entry point v0.6")
+                }
+                SimulationEngine::NativeTracer => Box::new(NativeSimulateValidationTracer::new(
+                    provider,
+                    entry_point,
+                    tracer_timeout,
+                )),
+            };
+
         Self {
-            simulate_validation_tracer: SimulateValidationTracerImpl::new(
-                provider,
-                entry_point,
-                sim_settings.tracer_timeout.clone(),
-            ),
+            simulate_validation_tracer,
             sim_settings,
         }
     }
@@ -10,18 +10,32 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::{convert::TryFrom, fmt::Debug};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    convert::TryFrom,
+    fmt::Debug,
+};
 
+use alloy_primitives::{hex, keccak256, Address, U256};
 use anyhow::{bail, Context};
 use async_trait::async_trait;
 use rundler_provider::{
     BlockId, EvmProvider, GethDebugTracerType, GethDebugTracingCallOptions,
     GethDebugTracingOptions, GethTrace, SimulationProvider,
 };
-use rundler_types::v0_6::UserOperation;
+use rundler_types::{v0_6::UserOperation, Opcode};
 use serde::Deserialize;
 
-use crate::simulation::context::TracerOutput;
+use crate::simulation::{
+    context::{
+        AccessInfo, AssociatedSlotsByAddress, ContractInfo, Phase, TracerOutput, BANNED_OPCODES,
+        DEPOSIT_TO_METHOD, MAX_PRECOMPILE_ADDRESS,
+    },
+    erc7562::{self, Frame, NativeTracer},
+};
+
+// Pre calculated method signatures
+const VALIDATE_USER_OP_METHOD: &str = "0x3a871cdd";
 
 impl TryFrom<GethTrace> for TracerOutput {
     type Error = anyhow::Error;
@@ -35,6 +49,158 @@ impl TryFrom<GethTrace> for TracerOutput {
     }
 }
 
+impl From<&Frame> for TracerOutput {
+    fn from(root: &Frame) -> Self {
+        let entry_point = root.to.unwrap_or_default();
+
+        // Like the javascript tracer, phases are split on the entry point's `NUMBER` markers.
+        // The native tracer only counts the markers, so calls are placed around them by the
+        // entry point's order: the first marker is right before the account's
+        // `validateUserOp` call and the second right after it.
+        let markers = root
+            .opcodes()
+            .find(|(opcode, _)| *opcode == Opcode::NUMBER)
+            .map_or(0, |(_, count)| count as usize);
+        let mut phases = vec![Phase::default(); markers + 1];
+        let mut account_called = false;
+        let mut factory_create2_count = 0;
+        let mut accessed_contracts = HashMap::new();
+        for call in &root.calls {
+            let phase_index = if !account_called && call.method_sig() == VALIDATE_USER_OP_METHOD {
+                account_called = true;
+                markers.min(1)
+            } else if account_called {
+                markers
+            } else {
+                0
+            };
+            add_call_to_phase(
+                &mut phases[phase_index],
+                phase_index,
+                call,
+                entry_point,
+                &mut factory_create2_count,
+                &mut accessed_contracts,
+            );
+        }
+
+        let revert_data = root
+            .error
+            .as_ref()
+            .map(|_| root.output.clone().unwrap_or_default());
+
+        let mut associated_slots_by_address: HashMap<Address, BTreeSet<U256>> = HashMap::new();
+        for preimage in &root.keccak {
+            // The preimage may be keccak(addr || X), where addr is padded to a word
+            if preimage.len() >= 32 && preimage[..12].iter().all(|b| *b == 0) {
+                associated_slots_by_address
+                    .entry(Address::from_slice(&preimage[12..32]))
+                    .or_default()
+                    .insert(keccak256(preimage).into());
+            }
+        }
+
+        TracerOutput {
+            phases,
+            revert_data: revert_data.map(hex::encode_prefixed),
+            accessed_contracts,
+            associated_slots_by_address: AssociatedSlotsByAddress(associated_slots_by_address),
+            factory_called_create2_twice: factory_create2_count > 1,
+            expected_storage: erc7562::expected_storage(root),
+        }
+    }
+}
+
+fn add_call_to_phase(
+    phase: &mut Phase,
+    phase_index: usize,
+    call: &Frame,
+    entry_point: Address,
+    factory_create2_count: &mut u64,
+    accessed_contracts: &mut HashMap<Address, ContractInfo>,
+) {
+    let frames = call.frames();
+    // Accessing code deployed during the phase is allowed
+    let created: HashSet<Address> = frames
+        .iter()
+        .filter(|frame| matches!(frame.call_type, Opcode::CREATE | Opcode::CREATE2))
+        .filter_map(|frame| frame.to)
+        .collect();
+
+    let mut forbidden_opcodes_used = HashSet::new();
+    let mut forbidden_precompiles_used = HashSet::new();
+    let mut undeployed_contract_accesses = HashSet::new();
+    for frame in frames {
+        let contract = frame.storage_address();
+
+        for (opcode, count) in frame.opcodes() {
+            if opcode == Opcode::CREATE2 && phase_index == 0 {
+                *factory_create2_count += count;
+            } else if opcode == Opcode::CREATE2 || BANNED_OPCODES.contains(&opcode) {
+                forbidden_opcodes_used.insert(format!("{contract:#x}:{opcode}"));
+            }
+        }
+
+        let access = phase
+            .storage_accesses
+            .entry(contract)
+            .or_insert_with(|| AccessInfo {
+                reads: HashMap::new(),
+                writes: HashMap::new(),
+            });
+        for (slot, value) in frame.accessed_slots.read_slots() {
+            if !access.writes.contains_key(&slot) {
+                access.reads.entry(slot).or_insert(value);
+            }
+        }
+        for (slot, count) in frame.accessed_slots.written_slots() {
+            *access.writes.entry(slot).or_default() += count;
+        }
+
+        for (address, info) in frame.contract_info() {
+            if address < MAX_PRECOMPILE_ADDRESS {
+                forbidden_precompiles_used.insert(format!("{contract:#x}:{address:#x}"));
+                continue;
+            }
+            if info.length == 0 && !created.contains(&address) {
+                undeployed_contract_accesses.insert(address);
+            }
+            accessed_contracts.insert(address, info);
+        }
+
+        for address in &frame.ext_code_access_info {
+            phase
+                .ext_code_access_info
+                .insert(*address, frame.ext_code_opcode());
+        }
+        phase.ran_out_of_gas |= frame.out_of_gas;
+
+        // Calls from the entry point to the entities are expected
+        if frame.from == entry_point {
+            continue;
+        }
+        if frame.to == Some(entry_point) {
+            // Calling the entry point with no calldata is equivalent to `depositTo`
+            if !frame.input.is_empty() && frame.method_sig() != DEPOSIT_TO_METHOD {
+                phase.called_banned_entry_point_method = true;
+            }
+        } else if frame.value.is_some_and(|v| v != U256::ZERO) {
+            phase.called_non_entry_point_with_value = true;
+        }
+    }
+
+    phase
+        .storage_accesses
+        .retain(|_, access| !access.reads.is_empty() || !access.writes.is_empty());
+    phase.forbidden_opcodes_used.extend(forbidden_opcodes_used);
+    phase
+        .forbidden_precompiles_used
+        .extend(forbidden_precompiles_used);
+    phase
+        .undeployed_contract_accesses
+        .extend(undeployed_contract_accesses);
+}
+
 /// Trait for tracing the simulation of a user operation.
 #[async_trait]
 pub(super) trait SimulateValidationTracer: Send + Sync {
@@ -46,6 +212,17 @@ pub(super) trait SimulateValidationTracer: Send + Sync {
     ) -> anyhow::Result<TracerOutput>;
 }
 
+#[async_trait]
+impl SimulateValidationTracer for Box<dyn SimulateValidationTracer> {
+    async fn trace_simulate_validation(
+        &self,
+        op: UserOperation,
+        block_id: BlockId,
+    ) -> anyhow::Result<TracerOutput> {
+        (**self).trace_simulate_validation(op, block_id).await
+    }
+}
+
 /// Tracer implementation for the bundler's custom tracer.
 #[derive(Debug)]
 pub(crate) struct SimulateValidationTracerImpl<P, E> {
@@ -81,7 +258,7 @@ where
                     GethDebugTracingCallOptions {
                         tracing_options: GethDebugTracingOptions {
                             tracer: Some(GethDebugTracerType::JsTracer(
-                                validation_tracer_js().to_string(),
+                                validation_tracer_js()?.to_string(),
                             )),
                             timeout: Some(self.tracer_timeout.clone()),
                             ..Default::default()
@@ -106,6 +283,137 @@ impl<P, E> SimulateValidationTracerImpl<P, E> {
     }
 }
 
-fn validation_tracer_js() -> &'static str {
-    include_str!("../../../tracer/dist/validationTracerV0_6.js").trim_end_matches(";export{};")
+/// Tracer implementation for the node's native ERC-7562 tracer.
+///
+/// Falls back to the bundler's custom tracer if the node doesn't support the native tracer.
+pub(crate) struct NativeSimulateValidationTracer<P, E> {
+    native_tracer: NativeTracer<P>,
+    entry_point: E,
+    fallback: SimulateValidationTracerImpl<P, E>,
+}
+
+#[async_trait]
+impl<P, E> SimulateValidationTracer for NativeSimulateValidationTracer<P, E>
+where
+    P: EvmProvider,
+    E: SimulationProvider<UO = UserOperation>,
+{
+    async fn trace_simulate_validation(
+        &self,
+        op: UserOperation,
+        block_id: BlockId,
+    ) -> anyhow::Result<TracerOutput> {
+        let (tx, state_override) = self
+            .entry_point
+            .get_tracer_simulate_validation_call(op.clone())
+            .context("should get simulate validation call")?;
+
+        let Some(frame) = self
+            .native_tracer
+            .trace_call(tx, block_id, state_override.clone())
+            .await?
+        else {
+            return self.fallback.trace_simulate_validation(op, block_id).await;
+        };
+
+        let mut out = TracerOutput::from(&frame);
+        self.native_tracer
+            .fill_contract_headers(
+                &frame,
+                &state_override,
+                block_id,
+                &mut out.accessed_contracts,
+            )
+            .await?;
+        Ok(out)
+    }
+}
+
+impl<P: Clone, E: Clone> NativeSimulateValidationTracer<P, E> {
+    /// Creates a new instance of the native tracer.
+    pub(crate) fn new(provider: P, entry_point: E, tracer_timeout: String) -> Self {
+        Self {
+            native_tracer: NativeTracer::new(provider.clone(), tracer_timeout.clone()),
+            fallback: SimulateValidationTracerImpl::new(
+                provider,
+                entry_point.clone(),
+                tracer_timeout,
+            ),
+            entry_point,
+        }
+    }
+}
+
+#[cfg(js_tracer)]
+fn validation_tracer_js() -> anyhow::Result<&'static str> {
+    Ok(include_str!("../../../tracer/dist/validationTracerV0_6.js").trim_end_matches(";export{};"))
+}
+
+#[cfg(not(js_tracer))]
+fn validation_tracer_js() -> anyhow::Result<&'static str> {
+    bail!("rundler was built without the javascript tracer, use the native tracer instead")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::erc7562::tests::{frame, ACCOUNT, ENTRY_POINT, LIBRARY};
+
+    #[test]
+    fn test_from_frame() {
+        let out = TracerOutput::from(&frame(VALIDATE_USER_OP_METHOD));
+
+        // the entry point reverted after the first marker, so the account is the last phase
+        assert_eq!(out.phases.len(), 2);
+        assert_eq!(out.revert_data.as_deref(), Some("0x1234"));
+
+        let phase = &out.phases[1];
+        let mut forbidden = phase.forbidden_opcodes_used.clone();
+        forbidden.sort();
+        assert_eq!(
+            forbidden,
+            vec![
+                format!("{ACCOUNT:#x}:GAS"),
+                format!("{ACCOUNT:#x}:TIMESTAMP")
+            ]
+        );
+        assert_eq!(
+            phase.forbidden_precompiles_used,
+            vec![format!(
+                "{ACCOUNT:#x}:0x0000000000000000000000000000000000000011"
+            )]
+        );
+        assert!(phase.ran_out_of_gas);
+        assert!(!phase.called_banned_entry_point_method);
+        assert_eq!(
+            phase.ext_code_access_info,
+            HashMap::from([(ENTRY_POINT, Opcode::EXTCODESIZE)])
+        );
+        assert!(phase.storage_accesses.contains_key(&ACCOUNT));
+        assert!(out.accessed_contracts.contains_key(&LIBRARY));
+        assert!(phase.undeployed_contract_accesses.is_empty());
+
+        assert!(out
+            .associated_slots_by_address
+            .addresses()
+            .contains(&ACCOUNT));
+    }
+
+    #[test]
+    fn test_from_frame_phases() {
+        let mut root = frame(VALIDATE_USER_OP_METHOD);
+        // a paymaster call after the second marker
+        let mut paymaster_call = root.calls[0].clone();
+        paymaster_call.input = "0xf465c77e".parse().unwrap();
+        paymaster_call.calls.clear();
+        root.calls.push(paymaster_call);
+        // NUMBER
+        root.used_opcodes.insert("67".to_string(), 2);
+
+        let out = TracerOutput::from(&root);
+        assert_eq!(out.phases.len(), 3);
+        assert!(out.phases[0].forbidden_opcodes_used.is_empty());
+        assert!(!out.phases[1].forbidden_opcodes_used.is_empty());
+        assert!(!out.phases[2].forbidden_opcodes_used.is_empty());
+    }
 }
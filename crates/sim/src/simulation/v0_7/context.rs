@@ -14,7 +14,6 @@
 use std::collections::{BTreeSet, HashMap, HashSet};
 
 use alloy_primitives::{
-    address,
     hex::{self, FromHex},
     keccak256, Address, Bytes, U256,
 };
@@ -23,55 +22,34 @@ use anyhow::{bail, Context};
 use rundler_contracts::v0_7::ValidationResult;
 use rundler_provider::{BlockId, EntryPoint, EvmProvider, SimulationProvider};
 use rundler_types::{
-    pool::SimulationViolation, v0_7::UserOperation, EntityInfos, EntityType, Opcode,
-    UserOperation as UserOperationTrait, ValidationOutput, ValidationRevert,
+    chain::SimulationEngine, pool::SimulationViolation, EntityInfos, EntityType, Opcode,
+    UserOperation, ValidationOutput, ValidationRevert,
 };
 
-use super::tracer::{
-    CallInfo, ExitType, MethodInfo, SimulateValidationTracer, SimulateValidationTracerImpl,
-    TopLevelCallInfo, TracerOutput,
+use super::{
+    revm_tracer::RevmSimulateValidationTracer,
+    tracer::{
+        CallInfo, ExitType, MethodInfo, NativeSimulateValidationTracer, SimulateValidationTracer,
+        SimulateValidationTracerImpl, TopLevelCallInfo, TracerOutput,
+    },
 };
 use crate::{
     simulation::context::{
         self as sim_context, AccessInfo, AssociatedSlotsByAddress, Phase,
         TracerOutput as ContextTracerOutput, ValidationContext,
-        ValidationContextProvider as ValidationContextProviderTrait,
+        ValidationContextProvider as ValidationContextProviderTrait, BANNED_OPCODES,
+        DEPOSIT_TO_METHOD, MAX_PRECOMPILE_ADDRESS,
     },
     SimulationSettings, ViolationError,
 };
 
-// Banned opcodes
-//
-// Some banned opcodes (i.e. CREATE2) have special handling and aren't on this list.
-const BANNED_OPCODES: &[Opcode] = &[
-    Opcode::GAS,
-    Opcode::GASPRICE,
-    Opcode::GASLIMIT,
-    Opcode::DIFFICULTY,
-    Opcode::TIMESTAMP,
-    Opcode::BASEFEE,
-    Opcode::BLOCKHASH,
-    Opcode::BLOBBASEFEE,
-    Opcode::BLOBHASH,
-    Opcode::NUMBER,
-    Opcode::SELFBALANCE,
-    Opcode::BALANCE,
-    Opcode::ORIGIN,
-    Opcode::CREATE,
-    Opcode::COINBASE,
-    Opcode::SELFDESTRUCT,
-];
-
 // Pre calculated method signatures
 const SIMULATE_VALIDATION_METHOD: &str = "0xee219423";
 const CREATE_SENDER_METHOD: &str = "0x570e1a36";
 const VALIDATE_USER_OP_METHOD: &str = "0x19822f7c";
 const VALIDATE_PAYMASTER_USER_OP_METHOD: &str = "0x52b7512c";
-const DEPOSIT_TO_METHOD: &str = "0xb760faf9";
-// Max precompile address 0x10000
-const MAX_PRECOMPILE_ADDRESS: Address = address!("0000000000000000000000000000000000010000");
 
-/// A provider for creating `ValidationContext` for entry point v0.7.
+/// A provider for creating `ValidationContext` for entry point v0.7 and later.
 pub(crate) struct ValidationContextProvider<T> {
     simulate_validation_tracer: T,
     sim_settings: SimulationSettings,
@@ -83,7 +61,7 @@ impl<T> ValidationContextProviderTrait for ValidationContextProvider<T>
 where
     T: SimulateValidationTracer,
 {
-    type UO = UserOperation;
+    type UO = T::UO;
 
     async fn get_context(
         &self,
@@ -282,7 +260,7 @@ impl<T> ValidationContextProvider<T> {
 
     fn parse_tracer_out(
         &self,
-        op: &UserOperation,
+        op: &impl UserOperation,
         tracer_out: TracerOutput,
     ) -> anyhow::Result<ContextTracerOutput> {
         let mut phases = vec![Phase::default(); 3];
@@ -456,20 +434,38 @@ fn entity_type_to_phase(entity_type: EntityType) -> usize {
     }
 }
 
-impl<P, E> ValidationContextProvider<SimulateValidationTracerImpl<P, E>>
-where
-    P: EvmProvider,
-    E: EntryPoint + SimulationProvider<UO = UserOperation>,
-{
-    /// Creates a new `ValidationContextProvider` for entry point v0.7 with the given provider and entry point.
-    pub(crate) fn new(provider: P, entry_point: E, sim_settings: SimulationSettings) -> Self {
+impl<UO: UserOperation> ValidationContextProvider<Box<dyn SimulateValidationTracer<UO = UO>>> {
+    /// Creates a new `ValidationContextProvider` for entry point v0.7 or later with the given provider and entry point.
+    ///
+    /// The tracer is selected by the simulation engine in the settings.
+    pub(crate) fn new<P, E>(provider: P, entry_point: E, sim_settings: SimulationSettings) -> Self
+    where
+        P: EvmProvider + Clone + 'static,
+        E: EntryPoint + SimulationProvider<UO = UO> + Clone + 'static,
+    {
+        let entry_point_address = *entry_point.address();
+        let simulate_validation_tracer: Box<dyn SimulateValidationTracer<UO = UO>> =
+            match sim_settings.simulation_engine {
+                SimulationEngine::JsTracer => Box::new(SimulateValidationTracerImpl::new(
+                    provider,
+                    entry_point,
+                    sim_settings.tracer_timeout.clone(),
+                )),
+                SimulationEngine::NativeTracer => Box::new(NativeSimulateValidationTracer::new(
+                    provider,
+                    entry_point,
+                    sim_settings.tracer_timeout.clone(),
+                )),
+                SimulationEngine::Revm => Box::new(RevmSimulateValidationTracer::new(
+                    provider,
+                    entry_point,
+                    sim_settings.chain_spec.clone(),
+                )),
+            };
+
         Self {
-            entry_point_address: *entry_point.address(),
-            simulate_validation_tracer: SimulateValidationTracerImpl::new(
-                provider,
-                entry_point,
-                sim_settings.tracer_timeout.clone(),
-            ),
+            entry_point_address,
+            simulate_validation_tracer,
             sim_settings,
         }
     }
@@ -14,4 +14,6 @@
 mod context;
 pub(crate) use context::ValidationContextProvider;
 
+mod revm_tracer;
+
 mod tracer;
@@ -0,0 +1,709 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::collections::{HashMap, VecDeque};
+
+use alloy_primitives::{b256, hex, Address, Bytes, B256, U256};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use revm::{
+    db::CacheDB,
+    interpreter::{
+        CallInputs, CallOutcome, CallScheme, CreateInputs, CreateOutcome, CreateScheme,
+        InstructionResult, Interpreter, InterpreterResult,
+    },
+    primitives::KECCAK_EMPTY,
+    Database, EvmContext, Inspector,
+};
+use rundler_provider::{BlockId, EvmProvider, SimulationProvider};
+use rundler_types::{chain::ChainSpec, ExpectedStorage, Opcode};
+use tokio::runtime::Handle;
+
+use super::tracer::{
+    AccessInfo, CallInfo, ExitInfo, ExitType, LogInfo, MethodInfo, SimulateValidationTracer,
+    TopLevelCallInfo, TracerOutput,
+};
+use crate::{
+    evm::{self, ProviderDb},
+    simulation::context::{is_allowed_precompile, ContractInfo},
+};
+
+// keccak("BeforeExecution()"), emitted by the entry point after all validations are done
+const STOP_COLLECTING_TOPIC: B256 =
+    b256!("bb47ee3e183a558b1a2ff0874b079f3fc5478b7454eacf2bfc5af2ff5878f972");
+// Exit data is truncated to this many hex characters, including the 0x prefix
+const MAX_EXIT_DATA_LEN: usize = 4000;
+
+/// Tracer implementation that executes `simulateValidation` locally in revm.
+///
+/// State is fetched lazily from the provider at the simulated block. The produced
+/// output is identical to that of the javascript tracer so the same rules apply.
+pub(crate) struct RevmSimulateValidationTracer<P, E> {
+    provider: P,
+    entry_point: E,
+    chain_spec: ChainSpec,
+}
+
+impl<P, E> RevmSimulateValidationTracer<P, E> {
+    /// Creates a new instance of the revm tracer.
+    pub(crate) fn new(provider: P, entry_point: E, chain_spec: ChainSpec) -> Self {
+        Self {
+            provider,
+            entry_point,
+            chain_spec,
+        }
+    }
+}
+
+#[async_trait]
+impl<P, E> SimulateValidationTracer for RevmSimulateValidationTracer<P, E>
+where
+    P: EvmProvider + Clone + 'static,
+    E: SimulationProvider,
+{
+    type UO = E::UO;
+
+    async fn trace_simulate_validation(
+        &self,
+        op: Self::UO,
+        block_id: BlockId,
+    ) -> anyhow::Result<TracerOutput> {
+        let (tx, state_override) = self
+            .entry_point
+            .get_tracer_simulate_validation_call(op)
+            .context("should get tracer simulate validation call")?;
+
+        let env = evm::execution_env(&self.provider, &self.chain_spec, block_id).await?;
+        let tx = evm::tx_env(&tx, &env.block);
+        let db = ProviderDb::new(self.provider.clone(), block_id, Handle::current());
+
+        tokio::task::spawn_blocking(move || {
+            let mut db = CacheDB::new(db);
+            evm::apply_state_overrides(&mut db, state_override)?;
+            let (_, inspector) = evm::transact(db, ValidationInspector::default(), env, tx)?;
+            inspector.into_tracer_output()
+        })
+        .await
+        .context("revm simulation task should not panic")?
+    }
+}
+
+#[derive(Debug)]
+struct StepData {
+    opcode: Opcode,
+    stack_top3: Vec<U256>,
+}
+
+/// Port of the javascript validation tracer to a revm inspector.
+///
+/// Collects opcodes, storage accesses and contract info split by the top level calls
+/// from the entry point, along with the keccak inputs, call stack and logs.
+#[derive(Debug, Default)]
+struct ValidationInspector {
+    calls_from_entry_point: Vec<TopLevelCallInfo>,
+    keccak: Vec<String>,
+    calls: Vec<CallInfo>,
+    logs: Vec<LogInfo>,
+    last_op: Option<Opcode>,
+    last_three_opcodes: VecDeque<StepData>,
+    stop_collecting: bool,
+    // initial value of each accessed slot, None if it was written before being read
+    all_storage_accesses: HashMap<Address, HashMap<U256, Option<U256>>>,
+    error: Option<String>,
+}
+
+impl ValidationInspector {
+    fn into_tracer_output(self) -> anyhow::Result<TracerOutput> {
+        if let Some(error) = self.error {
+            return Err(anyhow!("failed to load state during simulation: {error}"));
+        }
+
+        let mut expected_storage = ExpectedStorage::default();
+        for (address, slots) in self.all_storage_accesses {
+            for (slot, value) in slots {
+                if let Some(value) = value {
+                    expected_storage.insert(address, slot, value);
+                }
+            }
+        }
+
+        Ok(TracerOutput {
+            calls_from_entry_point: self.calls_from_entry_point,
+            keccak: self.keccak,
+            calls: self.calls,
+            expected_storage,
+            logs: self.logs,
+            debug: None,
+        })
+    }
+
+    fn push_exit(&mut self, result: &InterpreterResult) {
+        self.calls.push(CallInfo::Exit(ExitInfo {
+            exit_type: if result.result.is_ok() {
+                ExitType::Return
+            } else {
+                ExitType::Revert
+            },
+            gas_used: result.gas.spent(),
+            data: truncate_exit_data(hex::encode_prefixed(&result.output)),
+        }));
+    }
+}
+
+impl<DB> Inspector<DB> for ValidationInspector
+where
+    DB: Database,
+    DB::Error: std::fmt::Display,
+{
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        if self.stop_collecting {
+            return;
+        }
+
+        let opcode = Opcode::try_from(interp.current_opcode()).unwrap_or(Opcode::INVALID);
+        let peek = |i: usize| interp.stack.peek(i).unwrap_or_default();
+        let stack_top3 = (0..3).map_while(|i| interp.stack.peek(i).ok()).collect();
+        self.last_three_opcodes
+            .push_back(StepData { opcode, stack_top3 });
+        if self.last_three_opcodes.len() > 3 {
+            self.last_three_opcodes.pop_front();
+        }
+
+        // special rule for SSTORE with gas metering, other out of gas cases are caught in step_end
+        if opcode == Opcode::SSTORE && interp.gas.remaining() < 2300 {
+            self.set_oog();
+        }
+
+        let depth = context.journaled_state.depth();
+        if opcode == Opcode::REVERT || opcode == Opcode::RETURN {
+            if depth == 1 {
+                // the top level frame doesn't produce an exit, so reconstruct it from the opcode
+                let data = read_memory(interp, peek(0), peek(1));
+                self.calls.push(CallInfo::Exit(ExitInfo {
+                    exit_type: if opcode == Opcode::REVERT {
+                        ExitType::Revert
+                    } else {
+                        ExitType::Return
+                    },
+                    gas_used: 0,
+                    data: truncate_exit_data(hex::encode_prefixed(data)),
+                }));
+            }
+            self.last_three_opcodes.clear();
+        }
+
+        if depth == 1 {
+            if opcode == Opcode::CALL || opcode == Opcode::STATICCALL {
+                let target = address_from_word(peek(1));
+                // matches the javascript tracer, which reads the args offset of a CALL for both
+                let method_sig = read_memory(interp, peek(3), U256::from(4));
+                self.calls_from_entry_point.push(TopLevelCallInfo {
+                    top_level_method_sig: hex::encode_prefixed(method_sig),
+                    top_level_target_address: format!("{target:#x}"),
+                    opcodes: HashMap::new(),
+                    access: HashMap::new(),
+                    contract_info: HashMap::new(),
+                    ext_code_access_info: HashMap::new(),
+                    oog: None,
+                });
+            } else if opcode == Opcode::LOG1 && B256::from(peek(2)) == STOP_COLLECTING_TOPIC {
+                self.stop_collecting = true;
+            }
+            self.last_op = None;
+            return;
+        }
+
+        let Some(level) = self.calls_from_entry_point.last_mut() else {
+            return;
+        };
+
+        // store all addresses touched by EXTCODE* opcodes
+        // [OP-051]
+        let last_three: Vec<_> = self.last_three_opcodes.iter().collect();
+        if let Some(last) = last_three.iter().rev().nth(1) {
+            if is_ext_code_opcode(last.opcode) {
+                let address =
+                    address_from_word(last.stack_top3.first().copied().unwrap_or_default());
+                let is_size_check = last_three.len() == 3
+                    && last_three[1].opcode == Opcode::EXTCODESIZE
+                    && last_three[2].opcode == Opcode::ISZERO;
+                if !is_size_check {
+                    level.ext_code_access_info.insert(address, opcode);
+                }
+            }
+        }
+
+        // [OP-041]
+        let is_ext = is_ext_code_opcode(opcode);
+        if is_ext
+            || matches!(
+                opcode,
+                Opcode::CALL | Opcode::CALLCODE | Opcode::DELEGATECALL | Opcode::STATICCALL
+            )
+        {
+            let address = address_from_word(peek(if is_ext { 0 } else { 1 }));
+            // [OP-062]
+            if !level.contract_info.contains_key(&address) && !is_allowed_precompile(address) {
+                match account_code(context, address) {
+                    Ok(code) => {
+                        level.contract_info.insert(
+                            address,
+                            ContractInfo {
+                                header: hex::encode_prefixed(&code[..code.len().min(3)]),
+                                opcode,
+                                length: code.len() as u64,
+                            },
+                        );
+                    }
+                    Err(e) => {
+                        if self.error.is_none() {
+                            self.error = Some(e.to_string());
+                        }
+                    }
+                }
+            }
+        }
+
+        // [OP-012]
+        if self.last_op == Some(Opcode::GAS) && !opcode.as_ref().contains("CALL") {
+            // count "GAS" opcode only if not followed by "CALL"
+            *level.opcodes.entry(Opcode::GAS).or_default() += 1;
+        }
+        if opcode != Opcode::GAS && !is_unimportant_opcode(opcode) {
+            *level.opcodes.entry(opcode).or_default() += 1;
+        }
+        self.last_op = Some(opcode);
+
+        // [OP-070] - Treat TLOAD and TSTORE as SLOAD and SSTORE
+        if matches!(
+            opcode,
+            Opcode::SLOAD | Opcode::SSTORE | Opcode::TLOAD | Opcode::TSTORE
+        ) {
+            let slot = peek(0);
+            let address = interp.contract.target_address;
+            let initial_values = self.all_storage_accesses.entry(address).or_default();
+            let access = level.access.entry(address).or_insert_with(|| AccessInfo {
+                reads: HashMap::new(),
+                writes: HashMap::new(),
+            });
+
+            if opcode == Opcode::SLOAD || opcode == Opcode::TLOAD {
+                // matches the javascript tracer, which reads persistent storage for TLOAD as well
+                let value = match storage_value(context, address, slot) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        if self.error.is_none() {
+                            self.error = Some(e.to_string());
+                        }
+                        U256::ZERO
+                    }
+                };
+                // read slot values before this UserOp was created
+                // (so saving it if it was written before the first read)
+                if !access.reads.contains_key(&slot) && !access.writes.contains_key(&slot) {
+                    access.reads.insert(slot, value);
+                }
+                initial_values.entry(slot).or_insert(Some(value));
+            } else {
+                *access.writes.entry(slot).or_default() += 1;
+                initial_values.entry(slot).or_insert(None);
+            }
+        }
+
+        if opcode == Opcode::SHA3 {
+            // currently, solidity uses only 2-word (6-byte) for a key. this might change..
+            // still, no need to return too much
+            let len = peek(1);
+            if len > U256::from(20) && len < U256::from(512) {
+                self.keccak
+                    .push(hex::encode_prefixed(read_memory(interp, peek(0), len)));
+            }
+        } else if (Opcode::LOG0 as u8..=Opcode::LOG4 as u8).contains(&(opcode as u8)) {
+            let count = (opcode as u8 - Opcode::LOG0 as u8) as usize;
+            let topics = (0..count).map(|i| format!("0x{:x}", peek(2 + i))).collect();
+            let data = hex::encode_prefixed(read_memory(interp, peek(0), peek(1)));
+            self.logs.push(LogInfo { topics, data });
+        }
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        if self.stop_collecting {
+            return;
+        }
+        if matches!(
+            interp.instruction_result,
+            InstructionResult::OutOfGas
+                | InstructionResult::MemoryOOG
+                | InstructionResult::MemoryLimitOOG
+                | InstructionResult::PrecompileOOG
+                | InstructionResult::InvalidOperandOOG
+                | InstructionResult::ReentrancySentryOOG
+        ) {
+            self.set_oog();
+        }
+    }
+
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        // the top level call is not reported, matching the javascript tracer
+        if self.stop_collecting || context.journaled_state.depth() == 0 {
+            return None;
+        }
+
+        let (method_type, from, value) = match inputs.scheme {
+            CallScheme::Call => (Opcode::CALL, inputs.caller, Some(inputs.value.get())),
+            CallScheme::CallCode => (Opcode::CALLCODE, inputs.caller, Some(inputs.value.get())),
+            // delegate calls report the executing contract as the sender and inherit the value
+            CallScheme::DelegateCall => (
+                Opcode::DELEGATECALL,
+                inputs.target_address,
+                Some(inputs.value.get()),
+            ),
+            CallScheme::StaticCall => (Opcode::STATICCALL, inputs.caller, None),
+            _ => return None,
+        };
+
+        self.calls.push(CallInfo::Method(MethodInfo {
+            method_type,
+            from,
+            to: inputs.bytecode_address,
+            method: method_selector(&inputs.input),
+            value,
+            gas: inputs.gas_limit,
+        }));
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        if !self.stop_collecting && context.journaled_state.depth() != 0 {
+            self.push_exit(&outcome.result);
+        }
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        if self.stop_collecting || context.journaled_state.depth() == 0 {
+            return None;
+        }
+
+        let method_type = match inputs.scheme {
+            CreateScheme::Create => Opcode::CREATE,
+            CreateScheme::Create2 { .. } => Opcode::CREATE2,
+        };
+        let nonce = context
+            .journaled_state
+            .state
+            .get(&inputs.caller)
+            .map(|account| account.info.nonce)
+            .unwrap_or_default();
+
+        self.calls.push(CallInfo::Method(MethodInfo {
+            method_type,
+            from: inputs.caller,
+            to: inputs.created_address(nonce),
+            method: method_selector(&inputs.init_code),
+            value: Some(inputs.value),
+            gas: inputs.gas_limit,
+        }));
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        if !self.stop_collecting && context.journaled_state.depth() != 0 {
+            self.push_exit(&outcome.result);
+        }
+        outcome
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        if self.stop_collecting {
+            return;
+        }
+        self.calls.push(CallInfo::Method(MethodInfo {
+            method_type: Opcode::SELFDESTRUCT,
+            from: contract,
+            to: target,
+            method: "0x".to_string(),
+            value: Some(value),
+            gas: 0,
+        }));
+        self.calls.push(CallInfo::Exit(ExitInfo {
+            exit_type: ExitType::Return,
+            gas_used: 0,
+            data: "0x".to_string(),
+        }));
+    }
+}
+
+impl ValidationInspector {
+    fn set_oog(&mut self) {
+        if let Some(level) = self.calls_from_entry_point.last_mut() {
+            level.oog = Some(true);
+        }
+    }
+}
+
+fn is_ext_code_opcode(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::EXTCODESIZE | Opcode::EXTCODECOPY | Opcode::EXTCODEHASH
+    )
+}
+
+// opcodes that are never relevant to validation rules and aren't counted
+fn is_unimportant_opcode(opcode: Opcode) -> bool {
+    let byte = opcode as u8;
+    (Opcode::PUSH0 as u8..=Opcode::SWAP16 as u8).contains(&byte)
+        || matches!(
+            opcode,
+            Opcode::POP
+                | Opcode::ADD
+                | Opcode::SUB
+                | Opcode::MUL
+                | Opcode::DIV
+                | Opcode::EQ
+                | Opcode::LT
+                | Opcode::GT
+                | Opcode::SLT
+                | Opcode::SGT
+                | Opcode::SHL
+                | Opcode::SHR
+                | Opcode::AND
+                | Opcode::OR
+                | Opcode::NOT
+                | Opcode::ISZERO
+        )
+}
+
+fn address_from_word(word: U256) -> Address {
+    Address::from_word(word.into())
+}
+
+fn method_selector(input: &Bytes) -> String {
+    hex::encode_prefixed(&input[..input.len().min(4)])
+}
+
+fn truncate_exit_data(mut data: String) -> String {
+    data.truncate(MAX_EXIT_DATA_LEN);
+    data
+}
+
+// Reads memory of the current context, clamped to its current size
+fn read_memory(interp: &Interpreter, offset: U256, len: U256) -> Vec<u8> {
+    let memory = interp.shared_memory.slice(0, interp.shared_memory.len());
+    let start = offset.saturating_to::<usize>().min(memory.len());
+    let end = start.saturating_add(len.saturating_to()).min(memory.len());
+    memory[start..end].to_vec()
+}
+
+fn account_code<DB: Database>(
+    context: &mut EvmContext<DB>,
+    address: Address,
+) -> Result<Bytes, DB::Error> {
+    let code_hash = match context.journaled_state.state.get(&address) {
+        Some(account) => match &account.info.code {
+            Some(code) => return Ok(code.original_bytes()),
+            None => Some(account.info.code_hash),
+        },
+        None => None,
+    };
+
+    let code_hash = match code_hash {
+        Some(code_hash) => code_hash,
+        None => match context.db.basic(address)? {
+            Some(info) => match info.code {
+                Some(code) => return Ok(code.original_bytes()),
+                None => info.code_hash,
+            },
+            None => return Ok(Bytes::new()),
+        },
+    };
+
+    if code_hash == KECCAK_EMPTY {
+        return Ok(Bytes::new());
+    }
+    Ok(context.db.code_by_hash(code_hash)?.original_bytes())
+}
+
+fn storage_value<DB: Database>(
+    context: &mut EvmContext<DB>,
+    address: Address,
+    slot: U256,
+) -> Result<U256, DB::Error> {
+    if let Some(account) = context.journaled_state.state.get(&address) {
+        if let Some(value) = account.storage.get(&slot) {
+            return Ok(value.present_value);
+        }
+        if account.is_created() {
+            return Ok(U256::ZERO);
+        }
+    }
+    context.db.storage(address, slot)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, bytes};
+    use revm::{
+        db::EmptyDB,
+        primitives::{AccountInfo, Bytecode, TxEnv, TxKind},
+    };
+
+    use super::*;
+    use crate::evm::ExecutionEnv;
+
+    const ENTRY_POINT: Address = address!("0000000071727De22E5E9d8BAf0edAc6f37da032");
+    const ACCOUNT: Address = address!("1000000000000000000000000000000000000001");
+    const OTHER: Address = address!("1000000000000000000000000000000000000002");
+
+    fn run(account_code: Bytes) -> TracerOutput {
+        let mut db = CacheDB::new(EmptyDB::default());
+        for (address, code) in [
+            (ENTRY_POINT, entry_point_code()),
+            (ACCOUNT, account_code),
+            // STOP
+            (OTHER, bytes!("00")),
+        ] {
+            let code = Bytecode::new_raw(code);
+            db.insert_account_info(
+                address,
+                AccountInfo::new(U256::ZERO, 1, code.hash_slow(), code),
+            );
+        }
+        let tx = TxEnv {
+            transact_to: TxKind::Call(ENTRY_POINT),
+            gas_limit: 1_000_000,
+            gas_price: U256::ZERO,
+            nonce: None,
+            ..Default::default()
+        };
+
+        let (_, inspector) = evm::transact(
+            db,
+            ValidationInspector::default(),
+            ExecutionEnv::default(),
+            tx,
+        )
+        .unwrap();
+        inspector.into_tracer_output().unwrap()
+    }
+
+    // Stores the validateUserOp selector in memory, calls the account with it and returns
+    fn entry_point_code() -> Bytes {
+        let mut code = bytes!("6319822f7c60e01b600052").to_vec();
+        // retLen, retOffset, argsLen, argsOffset, value
+        code.extend_from_slice(&hex::decode("60006000600460006000").unwrap());
+        code.push(0x73); // PUSH20
+        code.extend_from_slice(ACCOUNT.as_slice());
+        // PUSH2 gas, CALL, POP, then RETURN with an empty result
+        code.extend_from_slice(&hex::decode("61fffff15060006000f3").unwrap());
+        code.into()
+    }
+
+    #[test]
+    fn test_collects_top_level_call() {
+        // TIMESTAMP POP PUSH1 0x01 SLOAD POP STOP
+        let out = run(bytes!("42506001545000"));
+
+        assert_eq!(out.calls_from_entry_point.len(), 1);
+        let level = &out.calls_from_entry_point[0];
+        assert_eq!(level.top_level_method_sig, "0x19822f7c");
+        assert_eq!(level.top_level_target_address, format!("{:#x}", ACCOUNT));
+        assert_eq!(level.opcodes.get(&Opcode::TIMESTAMP), Some(&1));
+        assert_eq!(level.opcodes.get(&Opcode::SLOAD), Some(&1));
+        assert!(!level.opcodes.contains_key(&Opcode::POP));
+        assert!(!level.opcodes.contains_key(&Opcode::PUSH1));
+        assert_eq!(
+            level.access[&ACCOUNT].reads.get(&U256::from(1)),
+            Some(&U256::ZERO)
+        );
+        assert_eq!(
+            out.expected_storage.0[&ACCOUNT].get(&B256::from(U256::from(1))),
+            Some(&B256::ZERO)
+        );
+
+        assert_eq!(out.calls.len(), 3);
+        match &out.calls[0] {
+            CallInfo::Method(method) => {
+                assert_eq!(method.method_type, Opcode::CALL);
+                assert_eq!(method.from, ENTRY_POINT);
+                assert_eq!(method.to, ACCOUNT);
+                assert_eq!(method.method, "0x19822f7c");
+                assert_eq!(method.value, Some(U256::ZERO));
+            }
+            call => panic!("expected method, got {call:?}"),
+        }
+        assert!(matches!(
+            &out.calls[1],
+            CallInfo::Exit(ExitInfo {
+                exit_type: ExitType::Return,
+                ..
+            })
+        ));
+        match &out.calls[2] {
+            CallInfo::Exit(exit) => {
+                assert!(matches!(exit.exit_type, ExitType::Return));
+                assert_eq!(exit.data, "0x");
+            }
+            call => panic!("expected exit, got {call:?}"),
+        }
+    }
+
+    #[test]
+    fn test_gas_before_call_not_counted() {
+        // PUSH1 0x00 (x5) PUSH20 other GAS CALL POP STOP, i.e. GAS is only followed by CALL
+        let mut code = hex::decode("60006000600060006000").unwrap();
+        code.push(0x73);
+        code.extend_from_slice(OTHER.as_slice());
+        code.extend_from_slice(&hex::decode("5af15000").unwrap());
+        let out = run(code.into());
+
+        let level = &out.calls_from_entry_point[0];
+        assert!(!level.opcodes.contains_key(&Opcode::GAS));
+        assert_eq!(level.opcodes.get(&Opcode::CALL), Some(&1));
+        assert_eq!(level.contract_info[&OTHER].opcode, Opcode::CALL);
+        assert_eq!(level.contract_info[&OTHER].length, 1);
+        assert_eq!(level.contract_info[&OTHER].header, "0x00");
+    }
+
+    #[test]
+    fn test_allowed_precompiles() {
+        assert!(is_allowed_precompile(address!(
+            "0000000000000000000000000000000000000001"
+        )));
+        assert!(is_allowed_precompile(address!(
+            "0000000000000000000000000000000000000100"
+        )));
+        assert!(!is_allowed_precompile(Address::ZERO));
+        assert!(!is_allowed_precompile(address!(
+            "000000000000000000000000000000000000000a"
+        )));
+    }
+}
@@ -10,19 +10,29 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
-use std::{collections::HashMap, convert::TryFrom, fmt::Debug};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    convert::TryFrom,
+    fmt::Debug,
+};
 
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{hex, Address, U256};
 use anyhow::{bail, Context};
 use async_trait::async_trait;
 use rundler_provider::{
     BlockId, EvmProvider, GethDebugTracerType, GethDebugTracingCallOptions,
     GethDebugTracingOptions, GethTrace, SimulationProvider,
 };
-use rundler_types::{v0_7::UserOperation, ExpectedStorage, Opcode};
+use rundler_types::{ExpectedStorage, Opcode, UserOperation};
 use serde::Deserialize;
 
-use crate::simulation::context::ContractInfo;
+use crate::simulation::{
+    context::ContractInfo,
+    erc7562::{self, Frame, NativeTracer},
+};
+
+// Exit data is truncated to this many hex characters, including the 0x prefix
+const MAX_EXIT_DATA_LEN: usize = 4000;
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -109,17 +119,152 @@ impl TryFrom<GethTrace> for TracerOutput {
     }
 }
 
+impl From<&Frame> for TracerOutput {
+    fn from(root: &Frame) -> Self {
+        let mut calls = vec![];
+        for call in &root.calls {
+            push_calls(call, &mut calls);
+        }
+        calls.push(exit_info(root));
+
+        let logs = root
+            .frames()
+            .into_iter()
+            .flat_map(|frame| &frame.logs)
+            .map(|log| LogInfo {
+                topics: log
+                    .topics
+                    .iter()
+                    .map(|topic| format!("0x{topic:x}"))
+                    .collect(),
+                data: hex::encode_prefixed(&log.data),
+            })
+            .collect();
+
+        TracerOutput {
+            calls_from_entry_point: root.calls.iter().map(top_level_call_info).collect(),
+            keccak: root.keccak.iter().map(hex::encode_prefixed).collect(),
+            calls,
+            expected_storage: erc7562::expected_storage(root),
+            logs,
+            debug: None,
+        }
+    }
+}
+
+fn top_level_call_info(call: &Frame) -> TopLevelCallInfo {
+    let mut info = TopLevelCallInfo {
+        top_level_method_sig: call.method_sig(),
+        top_level_target_address: format!("{:#x}", call.to.unwrap_or_default()),
+        opcodes: HashMap::new(),
+        access: HashMap::new(),
+        contract_info: HashMap::new(),
+        ext_code_access_info: HashMap::new(),
+        oog: Some(false),
+    };
+
+    for frame in call.frames() {
+        for (opcode, count) in frame.opcodes() {
+            *info.opcodes.entry(opcode).or_default() += count;
+        }
+
+        let access = info
+            .access
+            .entry(frame.storage_address())
+            .or_insert_with(|| AccessInfo {
+                reads: HashMap::new(),
+                writes: HashMap::new(),
+            });
+        for (slot, value) in frame.accessed_slots.read_slots() {
+            if !access.writes.contains_key(&slot) {
+                access.reads.entry(slot).or_insert(value);
+            }
+        }
+        for (slot, count) in frame.accessed_slots.written_slots() {
+            *access.writes.entry(slot).or_default() += count;
+        }
+
+        for (address, contract_info) in frame.contract_info() {
+            if let Entry::Vacant(e) = info.contract_info.entry(address) {
+                e.insert(contract_info);
+            }
+        }
+        for address in &frame.ext_code_access_info {
+            info.ext_code_access_info
+                .insert(*address, frame.ext_code_opcode());
+        }
+        if frame.out_of_gas {
+            info.oog = Some(true);
+        }
+    }
+
+    info.access
+        .retain(|_, access| !access.reads.is_empty() || !access.writes.is_empty());
+    info
+}
+
+// Flattens the frame into the method and exit entries produced by the javascript tracer
+fn push_calls(frame: &Frame, calls: &mut Vec<CallInfo>) {
+    calls.push(CallInfo::Method(MethodInfo {
+        method_type: frame.call_type,
+        from: frame.from,
+        to: frame.to.unwrap_or_default(),
+        method: frame.method_sig(),
+        value: frame.value,
+        gas: frame.gas.to(),
+    }));
+    for call in &frame.calls {
+        push_calls(call, calls);
+    }
+    calls.push(exit_info(frame));
+}
+
+fn exit_info(frame: &Frame) -> CallInfo {
+    let mut data = frame
+        .output
+        .as_ref()
+        .map(hex::encode_prefixed)
+        .unwrap_or_else(|| "0x".to_string());
+    data.truncate(MAX_EXIT_DATA_LEN);
+
+    CallInfo::Exit(ExitInfo {
+        exit_type: if frame.error.is_some() {
+            ExitType::Revert
+        } else {
+            ExitType::Return
+        },
+        gas_used: frame.gas_used.to(),
+        data,
+    })
+}
+
 /// Trait for tracing the simulation of a user operation.
 #[async_trait]
 pub(super) trait SimulateValidationTracer: Send + Sync {
+    /// The type of user operation this tracer supports
+    type UO: UserOperation;
+
     /// Traces the simulation of a user operation.
     async fn trace_simulate_validation(
         &self,
-        op: UserOperation,
+        op: Self::UO,
         block_id: BlockId,
     ) -> anyhow::Result<TracerOutput>;
 }
 
+#[async_trait]
+impl<UO: UserOperation> SimulateValidationTracer for Box<dyn SimulateValidationTracer<UO = UO>> {
+    type UO = UO;
+
+    async fn trace_simulate_validation(
+        &self,
+        op: Self::UO,
+        block_id: BlockId,
+    ) -> anyhow::Result<TracerOutput> {
+        (**self).trace_simulate_validation(op, block_id).await
+    }
+}
+
 /// Tracer implementation for the bundler's custom tracer.
 #[derive(Debug)]
 pub(crate) struct SimulateValidationTracerImpl<P, E> {
@@ -135,11 +280,13 @@ pub(crate) struct SimulateValidationTracerImpl<P, E> {
 impl<P, E> SimulateValidationTracer for SimulateValidationTracerImpl<P, E>
 where
     P: EvmProvider,
-    E: SimulationProvider<UO = UserOperation>,
+    E: SimulationProvider,
 {
+    type UO = E::UO;
+
     async fn trace_simulate_validation(
         &self,
-        op: UserOperation,
+        op: Self::UO,
         block_id: BlockId,
     ) -> anyhow::Result<TracerOutput> {
         let (tx, state_override) = self
@@ -155,7 +302,7 @@ where
                 GethDebugTracingCallOptions {
                     tracing_options: GethDebugTracingOptions {
                         tracer: Some(GethDebugTracerType::JsTracer(
-                            validation_tracer_js().to_string(),
+                            validation_tracer_js()?.to_string(),
                         )),
                         timeout: Some(self.tracer_timeout.clone()),
                         ..Default::default()
@@ -181,6 +328,128 @@ impl<P, E> SimulateValidationTracerImpl<P, E> {
     }
 }
 
-fn validation_tracer_js() -> &'static str {
-    include_str!("../../../tracer/dist/validationTracerV0_7.js").trim_end_matches(";export{};")
+/// Tracer implementation for the node's native ERC-7562 tracer.
+///
+/// Falls back to the bundler's custom tracer if the node doesn't support the native tracer.
+pub(crate) struct NativeSimulateValidationTracer<P, E> {
+    native_tracer: NativeTracer<P>,
+    entry_point: E,
+    fallback: SimulateValidationTracerImpl<P, E>,
+}
+
+#[async_trait]
+impl<P, E> SimulateValidationTracer for NativeSimulateValidationTracer<P, E>
+where
+    P: EvmProvider,
+    E: SimulationProvider,
+{
+    type UO = E::UO;
+
+    async fn trace_simulate_validation(
+        &self,
+        op: Self::UO,
+        block_id: BlockId,
+    ) -> anyhow::Result<TracerOutput> {
+        let (tx, state_override) = self
+            .entry_point
+            .get_tracer_simulate_validation_call(op.clone())
+            .context("should get tracer simulate validation call")?;
+
+        let Some(frame) = self
+            .native_tracer
+            .trace_call(tx, block_id, state_override.clone())
+            .await?
+        else {
+            return self.fallback.trace_simulate_validation(op, block_id).await;
+        };
+
+        let mut out = TracerOutput::from(&frame);
+        for call in &mut out.calls_from_entry_point {
+            self.native_tracer
+                .fill_contract_headers(&frame, &state_override, block_id, &mut call.contract_info)
+                .await?;
+        }
+        Ok(out)
+    }
+}
+
+impl<P: Clone, E: Clone> NativeSimulateValidationTracer<P, E> {
+    /// Creates a new instance of the native tracer.
+    pub(crate) fn new(provider: P, entry_point: E, tracer_timeout: String) -> Self {
+        Self {
+            native_tracer: NativeTracer::new(provider.clone(), tracer_timeout.clone()),
+            fallback: SimulateValidationTracerImpl::new(
+                provider,
+                entry_point.clone(),
+                tracer_timeout,
+            ),
+            entry_point,
+        }
+    }
+}
+
+#[cfg(js_tracer)]
+fn validation_tracer_js() -> anyhow::Result<&'static str> {
+    Ok(include_str!("../../../tracer/dist/validationTracerV0_7.js").trim_end_matches(";export{};"))
+}
+
+#[cfg(not(js_tracer))]
+fn validation_tracer_js() -> anyhow::Result<&'static str> {
+    bail!("rundler was built without the javascript tracer, use the native tracer instead")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::erc7562::tests::{frame, ACCOUNT, ENTRY_POINT, LIBRARY};
+
+    #[test]
+    fn test_from_frame() {
+        let out = TracerOutput::from(&frame("0x19822f7c"));
+
+        assert_eq!(out.calls_from_entry_point.len(), 1);
+        let call = &out.calls_from_entry_point[0];
+        assert_eq!(call.top_level_method_sig, "0x19822f7c");
+        assert_eq!(call.top_level_target_address, format!("{ACCOUNT:#x}"));
+        assert_eq!(call.opcodes.get(&Opcode::TIMESTAMP), Some(&2));
+        assert_eq!(call.opcodes.get(&Opcode::EXTCODEHASH), Some(&1));
+        assert_eq!(call.oog, Some(true));
+        assert_eq!(
+            call.ext_code_access_info,
+            HashMap::from([(ENTRY_POINT, Opcode::EXTCODESIZE)])
+        );
+        assert!(call.contract_info.contains_key(&LIBRARY));
+
+        // delegate call storage is attributed to the caller
+        let access = &call.access[&ACCOUNT];
+        assert_eq!(access.reads.get(&U256::from(3)), Some(&U256::from(7)));
+        assert_eq!(access.writes.get(&U256::from(3)), Some(&1));
+        assert_eq!(access.writes.get(&U256::from(4)), Some(&2));
+        assert_eq!(access.reads.get(&U256::from(9)), Some(&U256::ZERO));
+        assert_eq!(access.writes.get(&U256::from(10)), Some(&3));
+        assert!(!call.access.contains_key(&LIBRARY));
+
+        assert_eq!(
+            out.keccak,
+            vec!["0x0000000000000000000000001000000000000000000000000000000000000001"]
+        );
+        assert_eq!(out.logs.len(), 1);
+        assert_eq!(out.logs[0].data, "0xabcd");
+
+        // method and exit for the account and library, then the final exit
+        assert_eq!(out.calls.len(), 5);
+        assert!(matches!(
+            &out.calls[1],
+            CallInfo::Method(MethodInfo {
+                method_type: Opcode::DELEGATECALL,
+                ..
+            })
+        ));
+        let CallInfo::Exit(exit) = &out.calls[4] else {
+            panic!("last call should be an exit");
+        };
+        assert!(matches!(exit.exit_type, ExitType::Revert));
+        assert_eq!(exit.data, "0x1234");
+        assert_eq!(exit.gas_used, 0x10000);
+    }
 }
@@ -83,6 +83,7 @@ type StringSet = Record<string, boolean | undefined>;
 
   const DEPOSIT_TO_SELECTOR = "0xb760faf9";
   const SSTORE_REQUIRED_GAS = 2300;
+  // Keep in sync with BANNED_OPCODES in crates/sim/src/simulation/context.rs
   const FORBIDDEN_OPCODES = stringSet([
     "GASPRICE",
     "GASLIMIT",
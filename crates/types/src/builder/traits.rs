@@ -15,7 +15,10 @@ use alloy_primitives::{Address, B256};
 #[cfg(feature = "test-utils")]
 use mockall::automock;
 
-use super::{error::BuilderError, types::BundlingMode};
+use super::{
+    error::BuilderError,
+    types::{BundlePreview, BundlingMode, QuarantinedSigner},
+};
 
 /// Builder result
 pub type BuilderResult<T> = std::result::Result<T, BuilderError>;
@@ -34,4 +37,18 @@ pub trait Builder: Send + Sync {
 
     /// Set the bundling mode
     async fn debug_set_bundling_mode(&self, mode: BundlingMode) -> BuilderResult<()>;
+
+    /// Build, but do not send, the bundle the builder for the given entry point
+    /// and filter id would propose at the latest block.
+    async fn debug_preview_bundle(
+        &self,
+        entry_point: Address,
+        filter_id: Option<String>,
+    ) -> BuilderResult<BundlePreview>;
+
+    /// Get the signers that have been quarantined and are no longer used to send bundles
+    async fn admin_get_quarantined_signers(&self) -> BuilderResult<Vec<QuarantinedSigner>>;
+
+    /// Remove a signer from quarantine, making it available to builders again
+    async fn admin_unquarantine_signer(&self, address: Address) -> BuilderResult<()>;
 }
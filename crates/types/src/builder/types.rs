@@ -11,9 +11,12 @@
 // You should have received a copy of the GNU General Public License along with Rundler.
 // If not, see https://www.gnu.org/licenses/.
 
+use alloy_primitives::{Address, B256, U256};
 use parse_display::Display;
 use serde::{Deserialize, Serialize};
 
+use crate::{ExpectedStorage, GasFees};
+
 /// Builder bundling mode
 #[derive(Display, Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 #[display(style = "lowercase")]
@@ -28,3 +31,61 @@ pub enum BundlingMode {
     /// Bundles will be sent automatically.
     Auto,
 }
+
+/// Preview of the bundle a builder would propose at the latest block, without sending it
+#[derive(Debug, Clone, Default)]
+pub struct BundlePreview {
+    /// Entry point the bundle targets
+    pub entry_point: Address,
+    /// Address of the builder that would send the bundle
+    pub builder_address: Address,
+    /// Block hash the bundle was simulated against
+    pub block_hash: B256,
+    /// Operations that would be included in the bundle
+    pub ops: Vec<BundlePreviewOp>,
+    /// Operations that would be rejected and removed from the pool
+    pub rejected_ops: Vec<BundlePreviewExcludedOp>,
+    /// Operations that would be skipped for this bundle but remain in the pool
+    pub skipped_ops: Vec<BundlePreviewExcludedOp>,
+    /// Gas estimate for the bundle transaction
+    pub gas_estimate: u64,
+    /// Gas fees the bundle transaction would be sent with
+    pub gas_fees: GasFees,
+    /// Storage slots the bundle expects to be unchanged at inclusion
+    pub expected_storage: ExpectedStorage,
+    /// Total DA gas of the included operations, zero on chains without DA gas
+    pub da_gas: u128,
+}
+
+/// An operation included in a bundle preview
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundlePreviewOp {
+    /// Operation hash
+    pub hash: B256,
+    /// Operation sender
+    pub sender: Address,
+    /// Operation nonce
+    pub nonce: U256,
+    /// Aggregator used by the operation, if any
+    pub aggregator: Option<Address>,
+    /// DA gas attributed to the operation
+    pub da_gas: u128,
+}
+
+/// An operation excluded from a bundle preview
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundlePreviewExcludedOp {
+    /// Operation hash
+    pub hash: B256,
+    /// Reason the operation was excluded
+    pub reason: String,
+}
+
+/// A builder signer that has been quarantined and is no longer used to send bundles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedSigner {
+    /// Address of the signer
+    pub address: Address,
+    /// Reason the signer was quarantined
+    pub reason: String,
+}
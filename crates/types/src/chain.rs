@@ -22,6 +22,7 @@ use crate::{aggregator::SignatureAggregator, da::DAGasOracleType, proxy::Submiss
 
 const ENTRY_POINT_ADDRESS_V0_6: &str = "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789";
 const ENTRY_POINT_ADDRESS_V0_7: &str = "0x0000000071727De22E5E9d8BAf0edAc6f37da032";
+const ENTRY_POINT_ADDRESS_V0_8: &str = "0x4337084D9E255Ff0702461CF8895CE9E3b5Ff108";
 const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
 
 /// Chain specification for Rundler
@@ -38,6 +39,8 @@ pub struct ChainSpec {
     pub entry_point_address_v0_6: Address,
     /// entry point address for v0_7
     pub entry_point_address_v0_7: Address,
+    /// entry point address for v0_8
+    pub entry_point_address_v0_8: Address,
     /// address of the multicall3 contract
     pub multicall3_address: Address,
 
@@ -57,6 +60,8 @@ pub struct ChainSpec {
     pub per_user_op_v0_6_gas: u64,
     /// Per user operation gas cost for v0.7
     pub per_user_op_v0_7_gas: u64,
+    /// Per user operation gas cost for v0.8
+    pub per_user_op_v0_8_gas: u64,
     /// Per user operation deploy gas cost overhead, to capture
     /// deploy costs that are not metered by the entry point
     pub per_user_op_deploy_overhead_gas: u64,
@@ -127,6 +132,20 @@ pub struct ChainSpec {
     /// Size of the chain history to keep to handle reorgs
     pub chain_history_size: u64,
 
+    /*
+     * Simulation
+     */
+    /// Engine used to run ERC-7562 validation of user operations
+    pub simulation_engine: SimulationEngine,
+    /// Engine used to run the simulations of user operation gas estimation
+    pub estimation_engine: EstimationEngine,
+    /// Hardfork rules of local execution with the revm engines. If not set, derived from the
+    /// fields of the executed block's header, which requires Ethereum style headers.
+    pub evm_hardfork: Option<EvmHardfork>,
+    /// True if the chain has the RIP-7212 P256VERIFY precompile at address 0x100, made
+    /// available to local execution with the revm engines
+    pub rip7212_enabled: bool,
+
     /*
      * Contracts
      */
@@ -153,6 +172,46 @@ pub enum PriorityFeeOracleType {
     UsageBased,
 }
 
+/// Engine used to run validation simulation
+#[derive(Clone, Copy, Debug, Deserialize, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SimulationEngine {
+    /// Trace `simulateValidation` on the node with the bundler's javascript tracer
+    #[default]
+    JsTracer,
+    /// Trace `simulateValidation` on the node with its native `erc7562Tracer`, falling back
+    /// to the javascript tracer if the node doesn't support it
+    NativeTracer,
+    /// Execute `simulateValidation` locally in revm, fetching state from the node as needed
+    Revm,
+}
+
+/// Engine used to run the simulations of gas estimation
+#[derive(Clone, Copy, Debug, Deserialize, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EstimationEngine {
+    /// Run each `simulateHandleOp` call of the estimation with `eth_call` on the node
+    #[default]
+    Node,
+    /// Run `simulateHandleOp` locally in revm against a fork of the node's state, fetching
+    /// each account and storage slot only once per block
+    Revm,
+}
+
+/// Hardfork rules of local EVM execution
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EvmHardfork {
+    /// London rules
+    London,
+    /// Shanghai rules
+    Shanghai,
+    /// Cancun rules
+    Cancun,
+    /// Prague rules
+    Prague,
+}
+
 impl Default for ChainSpec {
     fn default() -> Self {
         Self {
@@ -161,11 +220,13 @@ impl Default for ChainSpec {
             block_gas_limit: 30_000_000,
             entry_point_address_v0_6: Address::from_str(ENTRY_POINT_ADDRESS_V0_6).unwrap(),
             entry_point_address_v0_7: Address::from_str(ENTRY_POINT_ADDRESS_V0_7).unwrap(),
+            entry_point_address_v0_8: Address::from_str(ENTRY_POINT_ADDRESS_V0_8).unwrap(),
             multicall3_address: Address::from_str(MULTICALL3_ADDRESS).unwrap(),
             deposit_transfer_overhead: 30_000,
             transaction_intrinsic_gas: 21_000,
             per_user_op_v0_6_gas: 18_300,
             per_user_op_v0_7_gas: 19_500,
+            per_user_op_v0_8_gas: 19_500,
             per_user_op_deploy_overhead_gas: 0,
             per_user_op_word_gas: 4,
             calldata_zero_byte_gas: 4,
@@ -187,6 +248,10 @@ impl Default for ChainSpec {
             flashbots_relay_url: None,
             bloxroute_enabled: false,
             chain_history_size: 64,
+            simulation_engine: SimulationEngine::default(),
+            estimation_engine: EstimationEngine::default(),
+            evm_hardfork: None,
+            rip7212_enabled: false,
             signature_aggregators: Arc::new(ContractRegistry::default()),
             submission_proxies: Arc::new(ContractRegistry::default()),
         }
@@ -229,6 +294,11 @@ impl ChainSpec {
         self.per_user_op_v0_7_gas as u128
     }
 
+    /// Get the per user operation v0_8 gas
+    pub fn per_user_op_v0_8_gas(&self) -> u128 {
+        self.per_user_op_v0_8_gas as u128
+    }
+
     /// Get the calldata zero byte gas
     pub fn calldata_zero_byte_gas(&self) -> u128 {
         self.calldata_zero_byte_gas as u128
@@ -93,4 +93,20 @@ pub struct BedrockDAGasBlockData {
     pub blob_base_fee_scalar: u64,
     /// Blob base fee retrieved from the bedrock gas oracle.
     pub blob_base_fee: u64,
+    /// Whether the Fjord upgrade is active, if not the Ecotone fee formula applies.
+    pub is_fjord: bool,
+    /// Operator fee constant charged per transaction since the Isthmus upgrade, zero before.
+    pub operator_fee_constant: u64,
+}
+
+impl DAGasBlockData {
+    /// Fixed fee in wei charged once per bundle transaction, on top of its gas.
+    ///
+    /// Not included in the DA gas of user operations, as it doesn't scale with their data.
+    pub fn bundle_fee(&self) -> u128 {
+        match self {
+            DAGasBlockData::Bedrock(block_data) => block_data.operator_fee_constant as u128,
+            _ => 0,
+        }
+    }
 }
@@ -0,0 +1,390 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use alloy_primitives::Address;
+
+/// Assignments of user operation senders to bundle builders.
+///
+/// Ensures that no two builders attempt to include operations from the same sender in
+/// bundles simultaneously. Senders are first assigned to a builder when it receives their
+/// operations, and confirmed once the builder includes them in a bundle. Senders that are
+/// not confirmed are dropped when the builder finishes forming the bundle, confirmed
+/// senders are held until the builder releases them.
+///
+/// If a lease duration is set, a builder that has not touched or renewed its assignments
+/// within the duration is considered gone and all of its senders are released the next time
+/// another builder touches its assignments. Builders waiting for a bundle to mine renew their
+/// lease with `renew_lease`.
+#[derive(Debug, Default)]
+pub struct SenderAssignments {
+    senders: HashMap<Address, (Address, LockState)>,
+    builders: HashMap<Address, BuilderLease>,
+    lease_duration: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct BuilderLease {
+    senders: HashSet<Address>,
+    renewed_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LockState {
+    // The sender is assigned to the builder, but has not yet been confirmed.
+    // The builder has yet to include the sender in a bundle.
+    Assigned,
+    // The sender is confirmed to the builder. The builder has included the sender in a bundle.
+    Confirmed,
+}
+
+/// Error confirming the senders of a builder
+#[derive(Debug, thiserror::Error)]
+pub enum SenderAssignmentError {
+    /// The sender is not assigned to any builder
+    #[error("sender {0:?} is not assigned to any builder")]
+    NotAssigned(Address),
+    /// The sender is assigned to another builder
+    #[error("sender {sender:?} is assigned to builder {assigned:?}, expected {expected:?}")]
+    AssignedToOther {
+        /// The sender
+        sender: Address,
+        /// The builder the sender was expected to be assigned to
+        expected: Address,
+        /// The builder the sender is assigned to
+        assigned: Address,
+    },
+}
+
+impl SenderAssignments {
+    /// Create a new set of assignments, optionally expiring the senders of builders
+    /// that have not renewed their lease within `lease_duration`
+    pub fn new(lease_duration: Option<Duration>) -> Self {
+        Self {
+            lease_duration,
+            ..Default::default()
+        }
+    }
+
+    /// Assign senders to a builder.
+    ///
+    /// `senders` are the senders of the candidate operations, in order, one per operation.
+    /// Operations whose sender is free or already held by the builder are accepted until
+    /// `max_ops` operations are accepted. Returns the senders of the accepted operations.
+    pub fn assign(
+        &mut self,
+        builder: Address,
+        senders: impl IntoIterator<Item = Address>,
+        max_ops: usize,
+    ) -> Vec<Address> {
+        self.expire_leases(builder);
+        self.renew(builder);
+
+        let mut accepted_ops = 0;
+        let mut assigned = vec![];
+        let mut seen = HashSet::new();
+        for sender in senders {
+            if accepted_ops >= max_ops {
+                break;
+            }
+            let (locked_builder, _) = self
+                .senders
+                .entry(sender)
+                .or_insert((builder, LockState::Assigned));
+            if *locked_builder != builder {
+                continue;
+            }
+
+            self.builders
+                .get_mut(&builder)
+                .unwrap()
+                .senders
+                .insert(sender);
+            if seen.insert(sender) {
+                assigned.push(sender);
+            }
+            accepted_ops += 1;
+        }
+
+        self.drop_if_empty(builder);
+        assigned
+    }
+
+    /// Confirm the `confirmed` senders to the builder and drop its senders that are still
+    /// only assigned.
+    ///
+    /// Senders previously confirmed to the builder are kept regardless of whether they are
+    /// in `confirmed` or not. If any of the senders is not assigned to the builder no
+    /// changes are made.
+    pub fn confirm(
+        &mut self,
+        builder: Address,
+        confirmed: impl IntoIterator<Item = Address>,
+    ) -> Result<(), SenderAssignmentError> {
+        self.expire_leases(builder);
+
+        let confirmed = confirmed.into_iter().collect::<Vec<_>>();
+        for sender in &confirmed {
+            match self.senders.get(sender) {
+                None => return Err(SenderAssignmentError::NotAssigned(*sender)),
+                Some((assigned, _)) if *assigned != builder => {
+                    return Err(SenderAssignmentError::AssignedToOther {
+                        sender: *sender,
+                        expected: builder,
+                        assigned: *assigned,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        for sender in &confirmed {
+            let (_, lock_state) = self.senders.get_mut(sender).unwrap();
+            *lock_state = LockState::Confirmed;
+        }
+
+        let Some(lease) = self.builders.get_mut(&builder) else {
+            return Ok(());
+        };
+        lease.renewed_at = Instant::now();
+        lease.senders.retain(|sender| {
+            if self.senders[sender].1 == LockState::Confirmed {
+                return true;
+            }
+            self.senders.remove(sender);
+            false
+        });
+        self.drop_if_empty(builder);
+
+        Ok(())
+    }
+
+    /// Release all senders of the builder except for `kept`
+    pub fn release_all_except(
+        &mut self,
+        builder: Address,
+        kept: impl IntoIterator<Item = Address>,
+    ) {
+        self.expire_leases(builder);
+
+        let kept = kept.into_iter().collect::<HashSet<_>>();
+        let Some(lease) = self.builders.get_mut(&builder) else {
+            return;
+        };
+        lease.renewed_at = Instant::now();
+        lease.senders.retain(|sender| {
+            if kept.contains(sender) {
+                return true;
+            }
+            self.senders.remove(sender);
+            false
+        });
+        self.drop_if_empty(builder);
+    }
+
+    /// Renew the lease of the builder, keeping its senders while it waits without touching them
+    pub fn renew_lease(&mut self, builder: Address) {
+        if let Some(lease) = self.builders.get_mut(&builder) {
+            lease.renewed_at = Instant::now();
+        }
+    }
+
+    /// Number of senders (assigned, confirmed) to the builder
+    pub fn num_senders(&self, builder: Address) -> (usize, usize) {
+        let Some(lease) = self.builders.get(&builder) else {
+            return (0, 0);
+        };
+        let confirmed = lease
+            .senders
+            .iter()
+            .filter(|sender| self.senders[*sender].1 == LockState::Confirmed)
+            .count();
+        (lease.senders.len() - confirmed, confirmed)
+    }
+
+    /// Number of builders holding at least one sender
+    pub fn num_builders(&self) -> usize {
+        self.builders.len()
+    }
+
+    fn renew(&mut self, builder: Address) {
+        self.builders
+            .entry(builder)
+            .or_insert_with(|| BuilderLease {
+                senders: HashSet::new(),
+                renewed_at: Instant::now(),
+            })
+            .renewed_at = Instant::now();
+    }
+
+    fn drop_if_empty(&mut self, builder: Address) {
+        if self
+            .builders
+            .get(&builder)
+            .is_some_and(|lease| lease.senders.is_empty())
+        {
+            self.builders.remove(&builder);
+        }
+    }
+
+    // Releases the senders of builders, other than `caller`, whose lease has expired
+    fn expire_leases(&mut self, caller: Address) {
+        let Some(lease_duration) = self.lease_duration else {
+            return;
+        };
+        let now = Instant::now();
+        let senders = &mut self.senders;
+        self.builders.retain(|builder, lease| {
+            if *builder == caller || now.duration_since(lease.renewed_at) < lease_duration {
+                return true;
+            }
+            for sender in &lease.senders {
+                senders.remove(sender);
+            }
+            false
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assign_skips_other_builders() {
+        let mut assignments = SenderAssignments::default();
+        let senders = [address(1), address(2)];
+
+        assert_eq!(assignments.assign(address(0), senders, 10), senders);
+        assert_eq!(assignments.assign(address(0), senders, 10), senders);
+        assert!(assignments.assign(address(3), senders, 10).is_empty());
+        assert_eq!(assignments.num_senders(address(0)), (2, 0));
+        assert_eq!(assignments.num_builders(), 1);
+    }
+
+    #[test]
+    fn test_assign_max_ops() {
+        let mut assignments = SenderAssignments::default();
+
+        let assigned = assignments.assign(address(0), [address(1), address(1), address(2)], 2);
+        assert_eq!(assigned, vec![address(1)]);
+        assert_eq!(
+            assignments.assign(address(3), [address(2)], 2),
+            vec![address(2)]
+        );
+    }
+
+    #[test]
+    fn test_confirm_drops_unconfirmed() {
+        let mut assignments = SenderAssignments::default();
+        assignments.assign(address(0), [address(1), address(2)], 10);
+
+        assignments.confirm(address(0), [address(1)]).unwrap();
+        assert_eq!(assignments.num_senders(address(0)), (0, 1));
+        // confirmed senders are kept
+        assignments.confirm(address(0), []).unwrap();
+        assert_eq!(
+            assignments.assign(address(3), [address(1), address(2)], 10),
+            vec![address(2)]
+        );
+    }
+
+    #[test]
+    fn test_confirm_errors() {
+        let mut assignments = SenderAssignments::default();
+        assert!(matches!(
+            assignments.confirm(address(0), [address(1)]),
+            Err(SenderAssignmentError::NotAssigned(_))
+        ));
+
+        assignments.assign(address(0), [address(1)], 10);
+        assignments.assign(address(2), [address(3)], 10);
+        assert!(matches!(
+            assignments.confirm(address(0), [address(1), address(3)]),
+            Err(SenderAssignmentError::AssignedToOther { .. })
+        ));
+        // nothing changed
+        assert_eq!(assignments.num_senders(address(0)), (1, 0));
+    }
+
+    #[test]
+    fn test_release_all_except() {
+        let mut assignments = SenderAssignments::default();
+        assignments.assign(address(0), [address(1), address(2)], 10);
+        assignments
+            .confirm(address(0), [address(1), address(2)])
+            .unwrap();
+
+        assignments.release_all_except(address(0), [address(1)]);
+        assert_eq!(assignments.num_senders(address(0)), (0, 1));
+        assignments.release_all_except(address(0), []);
+        assert_eq!(assignments.num_builders(), 0);
+    }
+
+    #[test]
+    fn test_expired_lease() {
+        let mut assignments = SenderAssignments::new(Some(Duration::ZERO));
+        assignments.assign(address(0), [address(1)], 10);
+        assignments.confirm(address(0), [address(1)]).unwrap();
+
+        assert_eq!(
+            assignments.assign(address(2), [address(1)], 10),
+            vec![address(1)]
+        );
+        assert_eq!(assignments.num_builders(), 1);
+    }
+
+    #[test]
+    fn test_renewed_lease() {
+        let lease_duration = Duration::from_secs(60);
+        let mut assignments = SenderAssignments::new(Some(lease_duration));
+        assignments.assign(address(0), [address(1)], 10);
+        assignments.confirm(address(0), [address(1)]).unwrap();
+
+        // the builder renews its lease while its bundle is pending
+        let expired_at = Instant::now() - 2 * lease_duration;
+        assignments
+            .builders
+            .get_mut(&address(0))
+            .unwrap()
+            .renewed_at = expired_at;
+        assignments.renew_lease(address(0));
+        assert!(assignments.assign(address(2), [address(1)], 10).is_empty());
+        assert_eq!(assignments.num_senders(address(0)), (0, 1));
+
+        // renewing doesn't create a lease for a builder without senders
+        assignments.renew_lease(address(3));
+        assert_eq!(assignments.num_builders(), 1);
+
+        // the senders are released once the builder stops renewing
+        assignments
+            .builders
+            .get_mut(&address(0))
+            .unwrap()
+            .renewed_at = expired_at;
+        assert_eq!(
+            assignments.assign(address(2), [address(1)], 10),
+            vec![address(1)]
+        );
+    }
+
+    fn address(n: u8) -> Address {
+        let mut bytes = [0_u8; 20];
+        bytes[0] = n;
+        Address::from_slice(&bytes)
+    }
+}
@@ -106,6 +106,28 @@ pub enum MempoolError {
     /// Use unsupported EIP
     #[error("{0} is not supported")]
     EIPNotSupported(String),
+    /// Operation admissions for a key of the operation exceeded the configured rate
+    #[error("Rate limit exceeded for {0}")]
+    RateLimited(RateLimitKey),
+}
+
+/// Key that operation admissions to the mempool are rate limited by
+#[derive(Clone, Debug, parse_display::Display, Eq, PartialEq, Hash)]
+pub enum RateLimitKey {
+    /// The sender of the operation
+    #[display("sender {0:?}")]
+    Sender(Address),
+    /// The paymaster of the operation
+    #[display("paymaster {0:?}")]
+    Paymaster(Address),
+    /// The factory of the operation
+    #[display("factory {0:?}")]
+    Factory(Address),
+    /// The identity of the caller that submitted the operation, from its permissions.
+    ///
+    /// Not displayed, as the identity may be an API key.
+    #[display("caller")]
+    Caller(String),
 }
 
 /// Precheck violation enumeration
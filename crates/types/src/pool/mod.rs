@@ -13,6 +13,9 @@
 
 //! Rundler pool types
 
+mod assignment;
+pub use assignment::*;
+
 mod error;
 pub use error::*;
 
@@ -154,6 +154,27 @@ pub trait Pool: Send + Sync {
         paymaster: bool,
         reputation: bool,
     ) -> PoolResult<()>;
+
+    /// Assign the senders of operations to a builder, used to coordinate builders running in
+    /// separate processes.
+    ///
+    /// `senders` are the senders of the candidate operations, in order. Returns the senders now
+    /// assigned to the builder, covering at most `max_ops` of the operations.
+    async fn assign_senders(
+        &self,
+        builder: Address,
+        senders: Vec<Address>,
+        max_ops: u64,
+    ) -> PoolResult<Vec<Address>>;
+
+    /// Confirm senders to a builder and drop its senders that are still unconfirmed
+    async fn confirm_senders(&self, builder: Address, confirmed: Vec<Address>) -> PoolResult<()>;
+
+    /// Release all senders of a builder except for `kept`
+    async fn release_senders(&self, builder: Address, kept: Vec<Address>) -> PoolResult<()>;
+
+    /// Renew the lease of a builder, keeping its senders assigned while it waits
+    async fn renew_senders(&self, builder: Address) -> PoolResult<()>;
 }
 
 impl From<&PoolOperation> for PoolOperationSummary {
@@ -227,6 +248,15 @@ mockall::mock! {
             paymaster: bool,
             reputation: bool,
         ) -> PoolResult<()>;
+        async fn assign_senders(
+            &self,
+            builder: Address,
+            senders: Vec<Address>,
+            max_ops: u64,
+        ) -> PoolResult<Vec<Address>>;
+        async fn confirm_senders(&self, builder: Address, confirmed: Vec<Address>) -> PoolResult<()>;
+        async fn release_senders(&self, builder: Address, kept: Vec<Address>) -> PoolResult<()>;
+        async fn renew_senders(&self, builder: Address) -> PoolResult<()>;
         async fn debug_clear_state(
             &self,
             clear_mempool: bool,
@@ -15,6 +15,7 @@ use std::{fmt::Debug, time::Duration};
 
 use alloy_primitives::{Address, Bytes, B256, U256};
 use alloy_sol_types::SolValue;
+use serde::Deserialize;
 
 /// User operation permissions
 mod permissions;
@@ -24,6 +25,8 @@ pub use permissions::{BundlerSponsorship, UserOperationPermissions};
 pub mod v0_6;
 /// User Operation types for Entry Point v0.7
 pub mod v0_7;
+/// User Operation types for Entry Point v0.8
+pub mod v0_8;
 
 use crate::{aggregator::AggregatorCosts, authorization::Eip7702Auth, chain::ChainSpec, Entity};
 
@@ -44,14 +47,20 @@ pub const BUNDLE_BYTE_OVERHEAD: usize = 4 + 32 + 32 + 32;
 pub const USER_OP_OFFSET_WORD_SIZE: usize = 32;
 
 /// ERC-4337 Entry point version
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
 pub enum EntryPointVersion {
     /// Unspecified version
+    #[serde(skip)]
     Unspecified,
     /// Version 0.6
+    #[serde(rename = "v0.6")]
     V0_6,
     /// Version 0.7
+    #[serde(rename = "v0.7")]
     V0_7,
+    /// Version 0.8
+    #[serde(rename = "v0.8")]
+    V0_8,
 }
 
 /// Unique identifier for a user operation from a given sender
@@ -476,6 +485,8 @@ pub enum UserOperationVariant {
     V0_6(v0_6::UserOperation),
     /// User operation version 0.7
     V0_7(v0_7::UserOperation),
+    /// User operation version 0.8
+    V0_8(v0_8::UserOperation),
 }
 
 impl UserOperation for UserOperationVariant {
@@ -489,6 +500,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.entry_point(),
             UserOperationVariant::V0_7(op) => op.entry_point(),
+            UserOperationVariant::V0_8(op) => op.entry_point(),
         }
     }
 
@@ -496,6 +508,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.chain_id(),
             UserOperationVariant::V0_7(op) => op.chain_id(),
+            UserOperationVariant::V0_8(op) => op.chain_id(),
         }
     }
 
@@ -503,6 +516,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.hash(),
             UserOperationVariant::V0_7(op) => op.hash(),
+            UserOperationVariant::V0_8(op) => op.hash(),
         }
     }
 
@@ -510,6 +524,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.id(),
             UserOperationVariant::V0_7(op) => op.id(),
+            UserOperationVariant::V0_8(op) => op.id(),
         }
     }
 
@@ -517,6 +532,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.sender(),
             UserOperationVariant::V0_7(op) => op.sender(),
+            UserOperationVariant::V0_8(op) => op.sender(),
         }
     }
 
@@ -524,6 +540,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.nonce(),
             UserOperationVariant::V0_7(op) => op.nonce(),
+            UserOperationVariant::V0_8(op) => op.nonce(),
         }
     }
 
@@ -531,6 +548,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.paymaster(),
             UserOperationVariant::V0_7(op) => op.paymaster(),
+            UserOperationVariant::V0_8(op) => op.paymaster(),
         }
     }
 
@@ -538,6 +556,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.factory(),
             UserOperationVariant::V0_7(op) => op.factory(),
+            UserOperationVariant::V0_8(op) => op.factory(),
         }
     }
 
@@ -545,6 +564,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.aggregator(),
             UserOperationVariant::V0_7(op) => op.aggregator(),
+            UserOperationVariant::V0_8(op) => op.aggregator(),
         }
     }
 
@@ -552,6 +572,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.call_data(),
             UserOperationVariant::V0_7(op) => op.call_data(),
+            UserOperationVariant::V0_8(op) => op.call_data(),
         }
     }
 
@@ -559,6 +580,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.max_gas_cost(),
             UserOperationVariant::V0_7(op) => op.max_gas_cost(),
+            UserOperationVariant::V0_8(op) => op.max_gas_cost(),
         }
     }
 
@@ -566,6 +588,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.entities(),
             UserOperationVariant::V0_7(op) => op.entities(),
+            UserOperationVariant::V0_8(op) => op.entities(),
         }
     }
 
@@ -573,6 +596,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.heap_size(),
             UserOperationVariant::V0_7(op) => op.heap_size(),
+            UserOperationVariant::V0_8(op) => op.heap_size(),
         }
     }
 
@@ -580,6 +604,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.call_gas_limit(),
             UserOperationVariant::V0_7(op) => op.call_gas_limit(),
+            UserOperationVariant::V0_8(op) => op.call_gas_limit(),
         }
     }
 
@@ -587,6 +612,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.verification_gas_limit(),
             UserOperationVariant::V0_7(op) => op.verification_gas_limit(),
+            UserOperationVariant::V0_8(op) => op.verification_gas_limit(),
         }
     }
 
@@ -594,6 +620,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.total_verification_gas_limit(),
             UserOperationVariant::V0_7(op) => op.total_verification_gas_limit(),
+            UserOperationVariant::V0_8(op) => op.total_verification_gas_limit(),
         }
     }
 
@@ -601,6 +628,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.paymaster_post_op_gas_limit(),
             UserOperationVariant::V0_7(op) => op.paymaster_post_op_gas_limit(),
+            UserOperationVariant::V0_8(op) => op.paymaster_post_op_gas_limit(),
         }
     }
 
@@ -608,6 +636,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.required_pre_execution_buffer(),
             UserOperationVariant::V0_7(op) => op.required_pre_execution_buffer(),
+            UserOperationVariant::V0_8(op) => op.required_pre_execution_buffer(),
         }
     }
 
@@ -615,6 +644,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.pre_verification_gas(),
             UserOperationVariant::V0_7(op) => op.pre_verification_gas(),
+            UserOperationVariant::V0_8(op) => op.pre_verification_gas(),
         }
     }
 
@@ -622,6 +652,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.static_pre_verification_gas(chain_spec),
             UserOperationVariant::V0_7(op) => op.static_pre_verification_gas(chain_spec),
+            UserOperationVariant::V0_8(op) => op.static_pre_verification_gas(chain_spec),
         }
     }
 
@@ -629,6 +660,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.calldata_floor_gas_limit(),
             UserOperationVariant::V0_7(op) => op.calldata_floor_gas_limit(),
+            UserOperationVariant::V0_8(op) => op.calldata_floor_gas_limit(),
         }
     }
 
@@ -636,6 +668,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.max_fee_per_gas(),
             UserOperationVariant::V0_7(op) => op.max_fee_per_gas(),
+            UserOperationVariant::V0_8(op) => op.max_fee_per_gas(),
         }
     }
 
@@ -643,6 +676,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.max_priority_fee_per_gas(),
             UserOperationVariant::V0_7(op) => op.max_priority_fee_per_gas(),
+            UserOperationVariant::V0_8(op) => op.max_priority_fee_per_gas(),
         }
     }
 
@@ -650,6 +684,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.signature(),
             UserOperationVariant::V0_7(op) => op.signature(),
+            UserOperationVariant::V0_8(op) => op.signature(),
         }
     }
 
@@ -657,6 +692,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.aggregator_gas_limit(chain_spec, bundle_size),
             UserOperationVariant::V0_7(op) => op.aggregator_gas_limit(chain_spec, bundle_size),
+            UserOperationVariant::V0_8(op) => op.aggregator_gas_limit(chain_spec, bundle_size),
         }
     }
 
@@ -684,6 +720,14 @@ impl UserOperation for UserOperationVariant {
                     new_signature,
                 ))
             }
+            UserOperationVariant::V0_8(op) => {
+                UserOperationVariant::V0_8(op.transform_for_aggregator(
+                    chain_spec,
+                    aggregator,
+                    aggregator_costs,
+                    new_signature,
+                ))
+            }
         }
     }
 
@@ -691,6 +735,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.original_signature(),
             UserOperationVariant::V0_7(op) => op.original_signature(),
+            UserOperationVariant::V0_8(op) => op.original_signature(),
         }
     }
 
@@ -702,6 +747,9 @@ impl UserOperation for UserOperationVariant {
             UserOperationVariant::V0_7(op) => {
                 UserOperationVariant::V0_7(op.with_original_signature())
             }
+            UserOperationVariant::V0_8(op) => {
+                UserOperationVariant::V0_8(op.with_original_signature())
+            }
         }
     }
 
@@ -709,6 +757,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.extra_data_len(bundle_size),
             UserOperationVariant::V0_7(op) => op.extra_data_len(bundle_size),
+            UserOperationVariant::V0_8(op) => op.extra_data_len(bundle_size),
         }
     }
 
@@ -716,6 +765,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.abi_encoded_size(),
             UserOperationVariant::V0_7(op) => op.abi_encoded_size(),
+            UserOperationVariant::V0_8(op) => op.abi_encoded_size(),
         }
     }
 
@@ -723,6 +773,7 @@ impl UserOperation for UserOperationVariant {
         match self {
             UserOperationVariant::V0_6(op) => op.authorization_tuple(),
             UserOperationVariant::V0_7(op) => op.authorization_tuple(),
+            UserOperationVariant::V0_8(op) => op.authorization_tuple(),
         }
     }
 
@@ -739,6 +790,10 @@ impl UserOperation for UserOperationVariant {
                 .effective_verification_gas_limit_efficiency_reject_threshold(
                     verification_gas_limit_efficiency_reject_threshold,
                 ),
+            UserOperationVariant::V0_8(op) => op
+                .effective_verification_gas_limit_efficiency_reject_threshold(
+                    verification_gas_limit_efficiency_reject_threshold,
+                ),
         }
     }
 }
@@ -758,11 +813,19 @@ impl UserOperationVariant {
         }
     }
 
+    fn into_v0_8(self) -> Option<v0_8::UserOperation> {
+        match self {
+            UserOperationVariant::V0_8(op) => Some(op),
+            _ => None,
+        }
+    }
+
     /// Returns the user operation type
     pub fn uo_type(&self) -> EntryPointVersion {
         match self {
             UserOperationVariant::V0_6(_) => EntryPointVersion::V0_6,
             UserOperationVariant::V0_7(_) => EntryPointVersion::V0_7,
+            UserOperationVariant::V0_8(_) => EntryPointVersion::V0_8,
         }
     }
 
@@ -775,6 +838,11 @@ impl UserOperationVariant {
     pub fn is_v0_6(&self) -> bool {
         matches!(self, UserOperationVariant::V0_6(_))
     }
+
+    /// True if the UO is v0.8 type
+    pub fn is_v0_8(&self) -> bool {
+        matches!(self, UserOperationVariant::V0_8(_))
+    }
 }
 
 /// User operation optional gas enum
@@ -784,6 +852,8 @@ pub enum UserOperationOptionalGas {
     V0_6(v0_6::UserOperationOptionalGas),
     /// User operation optional gas for version 0.7
     V0_7(v0_7::UserOperationOptionalGas),
+    /// User operation optional gas for version 0.8
+    V0_8(v0_8::UserOperationOptionalGas),
 }
 
 impl UserOperationOptionalGas {
@@ -792,6 +862,7 @@ impl UserOperationOptionalGas {
         let abi_size = match self {
             UserOperationOptionalGas::V0_6(op) => op.abi_encoded_size(),
             UserOperationOptionalGas::V0_7(op) => op.abi_encoded_size(),
+            UserOperationOptionalGas::V0_8(op) => op.abi_encoded_size(),
         };
         abi_size + BUNDLE_BYTE_OVERHEAD + USER_OP_OFFSET_WORD_SIZE
     }
@@ -810,8 +881,27 @@ pub struct GasEstimate {
     ///
     /// v0.6: unused
     ///
-    /// v0.7: populated only if the user operation has a paymaster
+    /// v0.7, v0.8: populated only if the user operation has a paymaster
     pub paymaster_verification_gas_limit: Option<u128>,
+    /// Gas used by the user operation with the estimated limits
+    ///
+    /// Only traced by the revm estimation engine
+    pub gas_used: Option<GasUsedBreakdown>,
+}
+
+/// Gas used by each part of a user operation, traced by executing it locally
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasUsedBreakdown {
+    /// Gas used by the factory to deploy the account
+    pub factory: u128,
+    /// Gas used by the account's `validateUserOp`
+    pub verification: u128,
+    /// Gas used by the paymaster's `validatePaymasterUserOp`
+    pub paymaster_verification: u128,
+    /// Gas used by the account to execute the call data
+    pub call: u128,
+    /// Gas used by the paymaster's `postOp`
+    pub paymaster_post_op: u128,
 }
 
 /// User operations per aggregator
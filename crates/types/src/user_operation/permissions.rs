@@ -26,6 +26,12 @@ pub struct UserOperationPermissions {
     pub underpriced_bundle_pct: Option<u32>,
     /// Bundler sponsorship settings
     pub bundler_sponsorship: Option<BundlerSponsorship>,
+    /// Priority tier of the user operation, higher tiers are bundled first by builders
+    /// ordering by priority tier
+    pub priority_tier: Option<u32>,
+    /// Identity of the caller submitting the user operation, i.e. an API key, used to
+    /// rate limit the caller's admissions to the mempool
+    pub caller_id: Option<String>,
 }
 
 /// Bundler sponsorship settings
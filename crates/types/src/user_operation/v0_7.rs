@@ -424,6 +424,11 @@ impl UserOperation {
     pub fn paymaster_post_op_gas_limit(&self) -> u128 {
         self.paymaster_post_op_gas_limit
     }
+
+    /// Get the gas cost of the calldata
+    pub(crate) fn calldata_gas_cost(&self) -> u128 {
+        self.calldata_gas_cost
+    }
 }
 
 #[cfg(feature = "test-utils")]
@@ -730,11 +735,12 @@ impl UserOperationOptionalGas {
 impl From<super::UserOperationOptionalGas> for UserOperationOptionalGas {
     /// # Panics
     ///
-    /// Panics if the variant is not v0.7. This is for use in contexts
-    /// where the variant is known to be v0.7.
+    /// Panics if the variant is not v0.7 or v0.8. This is for use in contexts
+    /// where the variant is known to be v0.7 or v0.8, which share a format.
     fn from(op: super::UserOperationOptionalGas) -> Self {
         match op {
             super::UserOperationOptionalGas::V0_7(op) => op,
+            super::UserOperationOptionalGas::V0_8(op) => op,
             _ => panic!("Expected UserOperationOptionalGasV0_7"),
         }
     }
@@ -786,6 +792,20 @@ pub struct UserOperationRequiredFields {
     pub signature: Bytes,
 }
 
+/// Error unpacking a packed user operation
+#[derive(Debug, thiserror::Error)]
+pub enum FromPackedError {
+    /// The pre-verification gas doesn't fit in a `u128`
+    #[error("invalid pre-verification gas: {0}")]
+    PreVerificationGas(#[from] FromUintError<u128>),
+    /// The `initCode` is shorter than the factory address
+    #[error("init code shorter than an address")]
+    InitCodeTooShort,
+    /// The `paymasterAndData` is shorter than the paymaster address and gas limits
+    #[error("paymaster and data shorter than 52 bytes")]
+    PaymasterAndDataTooShort,
+}
+
 impl<'a> UserOperationBuilder<'a> {
     /// Creates a new builder
     pub fn new(chain_spec: &'a ChainSpec, required: UserOperationRequiredFields) -> Self {
@@ -805,10 +825,20 @@ impl<'a> UserOperationBuilder<'a> {
     }
 
     /// Creates a builder from a packed user operation
+    ///
+    /// Fails if a field doesn't fit its unpacked type, or if `initCode` or
+    /// `paymasterAndData` is too short to hold its packed fields.
     pub fn from_packed(
         puo: PackedUserOperation,
         chain_spec: &'a ChainSpec,
-    ) -> Result<Self, FromUintError<u128>> {
+    ) -> Result<Self, FromPackedError> {
+        if !puo.initCode.is_empty() && puo.initCode.len() < 20 {
+            return Err(FromPackedError::InitCodeTooShort);
+        }
+        if !puo.paymasterAndData.is_empty() && puo.paymasterAndData.len() < 52 {
+            return Err(FromPackedError::PaymasterAndDataTooShort);
+        }
+
         let mut builder = UserOperationBuilder::new(
             chain_spec,
             UserOperationRequiredFields {
@@ -974,6 +1004,22 @@ impl<'a> UserOperationBuilder<'a> {
 
     /// Builds the UserOperation
     pub fn build(self) -> UserOperation {
+        let entry_point = self.chain_spec.entry_point_address_v0_7;
+        let chain_id = self.chain_spec.id;
+        self.build_with_hasher(entry_point, |packed, _| {
+            hash_packed_user_operation(packed, entry_point, chain_id)
+        })
+    }
+
+    /// Builds the UserOperation for the given entry point, using `hasher` to
+    /// compute the user operation hash from the packed user operation.
+    ///
+    /// Used by entry point versions that share the v0.7 packed format.
+    pub(crate) fn build_with_hasher(
+        self,
+        entry_point: Address,
+        hasher: impl FnOnce(&PackedUserOperation, Option<&Eip7702Auth>) -> B256,
+    ) -> UserOperation {
         let uo = UserOperation {
             sender: self.required.sender,
             nonce: self.required.nonce,
@@ -991,7 +1037,7 @@ impl<'a> UserOperationBuilder<'a> {
             paymaster_data: self.paymaster_data,
             authorization_tuple: self.authorization_tuple,
             signature: self.required.signature,
-            entry_point: self.chain_spec.entry_point_address_v0_7,
+            entry_point,
             chain_id: self.chain_spec.id,
             hash: B256::ZERO,
             packed: PackedUserOperation::default(),
@@ -1007,11 +1053,7 @@ impl<'a> UserOperationBuilder<'a> {
         let packed = self
             .packed_uo
             .unwrap_or_else(|| pack_user_operation(uo.clone()));
-        let hash = hash_packed_user_operation(
-            &packed,
-            self.chain_spec.entry_point_address_v0_7,
-            self.chain_spec.id,
-        );
+        let hash = hasher(&packed, uo.authorization_tuple.as_ref());
 
         let (calldata_gas_cost, calldata_floor_gas_limit) =
             super::calc_calldata_gas_costs(&packed, self.chain_spec);
@@ -1222,6 +1264,29 @@ mod tests {
         assert_eq!(uo.hash(), hash);
     }
 
+    #[test]
+    fn test_from_packed_short_fields() {
+        let cs = ChainSpec::default();
+
+        let puo = PackedUserOperation {
+            initCode: bytes!("1234"),
+            ..Default::default()
+        };
+        assert!(matches!(
+            UserOperationBuilder::from_packed(puo, &cs),
+            Err(FromPackedError::InitCodeTooShort)
+        ));
+
+        let puo = PackedUserOperation {
+            paymasterAndData: Bytes::from(vec![1; 51]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            UserOperationBuilder::from_packed(puo, &cs),
+            Err(FromPackedError::PaymasterAndDataTooShort)
+        ));
+    }
+
     #[test]
     fn test_builder() {
         let factory_address = Address::random();
@@ -0,0 +1,800 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+use alloy_primitives::{address, keccak256, Address, Bytes, B256, U256};
+use alloy_sol_types::{sol, Eip712Domain, SolValue};
+use rundler_contracts::v0_8::PackedUserOperation;
+
+pub use super::v0_7::{
+    UnstructuredUserOperation, UserOperationOptionalGas, UserOperationRequiredFields,
+};
+use super::{v0_7, UserOperation as UserOperationTrait, UserOperationId, UserOperationVariant};
+use crate::{
+    aggregator::AggregatorCosts, authorization::Eip7702Auth, chain::ChainSpec, Entity,
+    EntryPointVersion,
+};
+
+/// Marker placed at the start of `initCode` to signal an EIP-7702 delegated sender
+///
+/// The entry point matches the first 20 bytes of `initCode` against this value.
+pub const EIP7702_INIT_CODE_MARKER: Address = address!("7702000000000000000000000000000000000000");
+
+/// EIP-712 type hash of `PackedUserOperation`
+///
+/// keccak256("PackedUserOperation(address sender,uint256 nonce,bytes initCode,bytes callData,bytes32 accountGasLimits,uint256 preVerificationGas,bytes32 gasFees,bytes paymasterAndData)")
+const PACKED_USER_OPERATION_TYPE_HASH: B256 = B256::new(alloy_primitives::hex!(
+    "29a0bca4af4be3421398da00295e58e6d7de38cb492214754cb6a47507dd6f8e"
+));
+
+/// EIP-712 domain name of the v0.8 entry point
+const DOMAIN_NAME: &str = "ERC4337";
+/// EIP-712 domain version of the v0.8 entry point
+const DOMAIN_VERSION: &str = "1";
+
+/// User Operation for Entry Point v0.8
+///
+/// Offchain version, must be packed before sending onchain
+///
+/// Entry point v0.8 shares the packed user operation format with v0.7. The differences are:
+///
+/// - The user operation hash is an EIP-712 typed data hash over the packed user operation.
+/// - An `initCode` starting with [`EIP7702_INIT_CODE_MARKER`] signals that the sender is an
+///   EIP-7702 delegated EOA. The marker is not a factory, and the remainder of the `initCode`
+///   is passed to the sender as initialization calldata.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UserOperation {
+    inner: v0_7::UserOperation,
+    /// Current EIP-7702 delegate of the sender, used in place of the marker when hashing
+    /// an operation without an authorization tuple
+    eip7702_delegate: Option<Address>,
+}
+
+impl UserOperationTrait for UserOperation {
+    type OptionalGas = UserOperationOptionalGas;
+
+    fn entry_point_version() -> EntryPointVersion {
+        EntryPointVersion::V0_8
+    }
+
+    fn entry_point(&self) -> Address {
+        self.inner.entry_point()
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.inner.chain_id()
+    }
+
+    fn hash(&self) -> B256 {
+        self.inner.hash()
+    }
+
+    fn id(&self) -> UserOperationId {
+        self.inner.id()
+    }
+
+    fn sender(&self) -> Address {
+        self.inner.sender()
+    }
+
+    fn nonce(&self) -> U256 {
+        self.inner.nonce()
+    }
+
+    fn paymaster(&self) -> Option<Address> {
+        self.inner.paymaster()
+    }
+
+    fn factory(&self) -> Option<Address> {
+        // The EIP-7702 marker is not a factory entity
+        self.inner
+            .factory()
+            .filter(|f| *f != EIP7702_INIT_CODE_MARKER)
+    }
+
+    fn aggregator(&self) -> Option<Address> {
+        self.inner.aggregator()
+    }
+
+    fn call_data(&self) -> &Bytes {
+        self.inner.call_data()
+    }
+
+    fn max_gas_cost(&self) -> U256 {
+        self.inner.max_gas_cost()
+    }
+
+    fn entities(&self) -> Vec<Entity> {
+        let mut ret = vec![Entity::account(self.sender())];
+        if let Some(factory) = self.factory() {
+            ret.push(Entity::factory(factory));
+        }
+        if let Some(paymaster) = self.paymaster() {
+            ret.push(Entity::paymaster(paymaster));
+        }
+        ret
+    }
+
+    fn heap_size(&self) -> usize {
+        self.inner.heap_size()
+    }
+
+    fn max_fee_per_gas(&self) -> u128 {
+        self.inner.max_fee_per_gas()
+    }
+
+    fn max_priority_fee_per_gas(&self) -> u128 {
+        self.inner.max_priority_fee_per_gas()
+    }
+
+    fn signature(&self) -> &Bytes {
+        self.inner.signature()
+    }
+
+    fn pre_verification_gas(&self) -> u128 {
+        self.inner.pre_verification_gas()
+    }
+
+    fn call_gas_limit(&self) -> u128 {
+        self.inner.call_gas_limit()
+    }
+
+    fn verification_gas_limit(&self) -> u128 {
+        self.inner.verification_gas_limit()
+    }
+
+    fn total_verification_gas_limit(&self) -> u128 {
+        self.inner.total_verification_gas_limit()
+    }
+
+    fn paymaster_post_op_gas_limit(&self) -> u128 {
+        UserOperationTrait::paymaster_post_op_gas_limit(&self.inner)
+    }
+
+    fn static_pre_verification_gas(&self, chain_spec: &ChainSpec) -> u128 {
+        self.inner.calldata_gas_cost()
+            + chain_spec.per_user_op_v0_8_gas()
+            + (if self.factory().is_some() {
+                chain_spec.per_user_op_deploy_overhead_gas()
+            } else {
+                0
+            })
+    }
+
+    fn calldata_floor_gas_limit(&self) -> u128 {
+        self.inner.calldata_floor_gas_limit()
+    }
+
+    fn required_pre_execution_buffer(&self) -> u128 {
+        self.inner.required_pre_execution_buffer()
+    }
+
+    fn aggregator_gas_limit(&self, chain_spec: &ChainSpec, bundle_size: Option<usize>) -> u128 {
+        self.inner.aggregator_gas_limit(chain_spec, bundle_size)
+    }
+
+    fn transform_for_aggregator(
+        self,
+        chain_spec: &ChainSpec,
+        aggregator: Address,
+        aggregator_costs: AggregatorCosts,
+        new_signature: Bytes,
+    ) -> Self {
+        // hash stays the same as only signature changed
+        Self {
+            inner: self.inner.transform_for_aggregator(
+                chain_spec,
+                aggregator,
+                aggregator_costs,
+                new_signature,
+            ),
+            eip7702_delegate: self.eip7702_delegate,
+        }
+    }
+
+    fn original_signature(&self) -> &Bytes {
+        self.inner.original_signature()
+    }
+
+    fn with_original_signature(self) -> Self {
+        Self {
+            inner: self.inner.with_original_signature(),
+            eip7702_delegate: self.eip7702_delegate,
+        }
+    }
+
+    fn extra_data_len(&self, bundle_size: usize) -> usize {
+        self.inner.extra_data_len(bundle_size)
+    }
+
+    fn abi_encoded_size(&self) -> usize {
+        self.inner.abi_encoded_size()
+    }
+
+    fn authorization_tuple(&self) -> Option<&Eip7702Auth> {
+        self.inner.authorization_tuple()
+    }
+
+    fn effective_verification_gas_limit_efficiency_reject_threshold(
+        &self,
+        verification_gas_limit_efficiency_reject_threshold: f64,
+    ) -> f64 {
+        verification_gas_limit_efficiency_reject_threshold
+    }
+}
+
+impl UserOperation {
+    /// Packs the user operation to its offchain representation
+    pub fn pack(self) -> PackedUserOperation {
+        self.inner.pack()
+    }
+
+    /// Returns a reference to the packed user operation
+    pub fn packed(&self) -> &PackedUserOperation {
+        self.inner.packed()
+    }
+
+    /// Converts the user operation into an unstructured user operation
+    pub fn into_unstructured(self) -> UnstructuredUserOperation {
+        self.inner.into_unstructured()
+    }
+
+    /// Converts the user operation into its v0.7 representation
+    ///
+    /// The v0.7 and v0.8 packed formats are identical. The returned operation keeps the
+    /// v0.8 entry point address and hash, and is only meant for reusing v0.7 logic that
+    /// operates on the packed format.
+    pub fn into_v0_7(self) -> v0_7::UserOperation {
+        self.inner
+    }
+
+    /// Get the paymaster data
+    pub fn paymaster_data(&self) -> &Bytes {
+        self.inner.paymaster_data()
+    }
+
+    /// Get the factory data
+    ///
+    /// If the EIP-7702 marker is used, this is the calldata used to initialize the sender
+    pub fn factory_data(&self) -> &Bytes {
+        self.inner.factory_data()
+    }
+
+    /// Get the paymaster verification gas limit
+    pub fn paymaster_verification_gas_limit(&self) -> u128 {
+        self.inner.paymaster_verification_gas_limit()
+    }
+
+    /// Get the paymaster post-op gas limit
+    pub fn paymaster_post_op_gas_limit(&self) -> u128 {
+        UserOperationTrait::paymaster_post_op_gas_limit(&self.inner)
+    }
+
+    /// True if the `initCode` of this user operation starts with the EIP-7702 marker
+    pub fn is_eip7702_init_code(&self) -> bool {
+        self.inner.factory() == Some(EIP7702_INIT_CODE_MARKER)
+    }
+
+    /// The delegate that replaces the EIP-7702 marker when hashing the `initCode`
+    ///
+    /// This is the authorization tuple address if present, otherwise the sender's current
+    /// delegate if it was resolved.
+    pub fn eip7702_delegate(&self) -> Option<Address> {
+        self.authorization_tuple()
+            .map(|auth| auth.address)
+            .or(self.eip7702_delegate)
+    }
+
+    /// True if the `initCode` uses the EIP-7702 marker but the sender's delegate is unknown
+    ///
+    /// The hash of such an operation does not match the entry point's hash until the delegate
+    /// is resolved from the sender's code and set with [`UserOperationBuilder::eip7702_delegate`].
+    pub fn requires_eip7702_delegate(&self) -> bool {
+        self.is_eip7702_init_code() && self.eip7702_delegate().is_none()
+    }
+}
+
+#[cfg(feature = "test-utils")]
+impl Default for UserOperation {
+    fn default() -> Self {
+        UserOperationBuilder::new(
+            &ChainSpec::default(),
+            UserOperationRequiredFields {
+                sender: Address::ZERO,
+                nonce: U256::ZERO,
+                call_data: Bytes::new(),
+                signature: Bytes::new(),
+                call_gas_limit: 0,
+                verification_gas_limit: 0,
+                pre_verification_gas: 0,
+                max_fee_per_gas: 0,
+                max_priority_fee_per_gas: 0,
+            },
+        )
+        .build()
+    }
+}
+
+impl From<UserOperationVariant> for UserOperation {
+    /// Converts a UserOperationVariant to a UserOperation 0.8
+    ///
+    /// # Panics
+    ///
+    /// Panics if the variant is not v0.8. This is for use in contexts
+    /// where the variant is known to be v0.8.
+    fn from(value: UserOperationVariant) -> Self {
+        value.into_v0_8().expect("Expected UserOperationV0_8")
+    }
+}
+
+impl From<UserOperation> for super::UserOperationVariant {
+    fn from(op: UserOperation) -> Self {
+        super::UserOperationVariant::V0_8(op)
+    }
+}
+
+impl AsRef<UserOperation> for super::UserOperationVariant {
+    /// # Panics
+    ///
+    /// Panics if the variant is not v0.8. This is for use in contexts
+    /// where the variant is known to be v0.8.
+    fn as_ref(&self) -> &UserOperation {
+        match self {
+            super::UserOperationVariant::V0_8(op) => op,
+            _ => panic!("Expected UserOperationV0_8"),
+        }
+    }
+}
+
+impl AsMut<UserOperation> for super::UserOperationVariant {
+    /// # Panics
+    ///
+    /// Panics if the variant is not v0.8. This is for use in contexts
+    /// where the variant is known to be v0.8.
+    fn as_mut(&mut self) -> &mut UserOperation {
+        match self {
+            super::UserOperationVariant::V0_8(op) => op,
+            _ => panic!("Expected UserOperationV0_8"),
+        }
+    }
+}
+
+/// Builder for UserOperation
+///
+/// Used to create a v0.8 while ensuring all required fields and grouped fields are present
+pub struct UserOperationBuilder<'a> {
+    chain_spec: &'a ChainSpec,
+    inner: v0_7::UserOperationBuilder<'a>,
+    eip7702_delegate: Option<Address>,
+}
+
+impl<'a> UserOperationBuilder<'a> {
+    /// Creates a new builder
+    pub fn new(chain_spec: &'a ChainSpec, required: UserOperationRequiredFields) -> Self {
+        Self {
+            chain_spec,
+            inner: v0_7::UserOperationBuilder::new(chain_spec, required),
+            eip7702_delegate: None,
+        }
+    }
+
+    /// Creates a builder from a packed user operation
+    pub fn from_packed(
+        puo: PackedUserOperation,
+        chain_spec: &'a ChainSpec,
+    ) -> Result<Self, v0_7::FromPackedError> {
+        // The entry point accepts a marker shorter than 20 bytes. Its padded form is used
+        // to represent it as a factory address, while the packed operation keeps the
+        // submitted `initCode`.
+        let mut unpacked = puo.clone();
+        if unpacked.initCode.len() < 20 && is_eip7702_init_code(&unpacked.initCode) {
+            unpacked.initCode = EIP7702_INIT_CODE_MARKER.to_vec().into();
+        }
+
+        Ok(Self {
+            chain_spec,
+            inner: v0_7::UserOperationBuilder::from_packed(unpacked, chain_spec)?.packed(puo),
+            eip7702_delegate: None,
+        })
+    }
+
+    /// Creates a builder from an existing UO
+    pub fn from_uo(uo: UserOperation, chain_spec: &'a ChainSpec) -> Self {
+        Self {
+            eip7702_delegate: uo.eip7702_delegate,
+            ..Self::from_v0_7(uo.inner, chain_spec)
+        }
+    }
+
+    /// Creates a builder from a v0.7 user operation
+    ///
+    /// The v0.7 and v0.8 packed formats are identical, the built operation targets the
+    /// v0.8 entry point and uses the v0.8 hash.
+    pub fn from_v0_7(uo: v0_7::UserOperation, chain_spec: &'a ChainSpec) -> Self {
+        Self {
+            chain_spec,
+            inner: v0_7::UserOperationBuilder::from_uo(uo, chain_spec),
+            eip7702_delegate: None,
+        }
+    }
+
+    /// Sets the factory and factory data
+    pub fn factory(mut self, factory: Address, factory_data: Bytes) -> Self {
+        self.inner = self.inner.factory(factory, factory_data);
+        self
+    }
+
+    /// Sets the EIP-7702 marker as the `initCode`, with optional initialization calldata
+    /// for the sender
+    pub fn eip7702_init_code(self, init_data: Bytes) -> Self {
+        self.factory(EIP7702_INIT_CODE_MARKER, init_data)
+    }
+
+    /// Sets the paymaster and associated fields
+    pub fn paymaster(
+        mut self,
+        paymaster: Address,
+        paymaster_verification_gas_limit: u128,
+        paymaster_post_op_gas_limit: u128,
+        paymaster_data: Bytes,
+    ) -> Self {
+        self.inner = self.inner.paymaster(
+            paymaster,
+            paymaster_verification_gas_limit,
+            paymaster_post_op_gas_limit,
+            paymaster_data,
+        );
+        self
+    }
+
+    /// Clears the paymaster and associated fields
+    pub fn clear_paymaster(mut self) -> Self {
+        self.inner = self.inner.clear_paymaster();
+        self
+    }
+
+    /// Sets the pre-verification gas
+    pub fn pre_verification_gas(mut self, pre_verification_gas: u128) -> Self {
+        self.inner = self.inner.pre_verification_gas(pre_verification_gas);
+        self
+    }
+
+    /// Sets the verification gas limit
+    pub fn verification_gas_limit(mut self, verification_gas_limit: u128) -> Self {
+        self.inner = self.inner.verification_gas_limit(verification_gas_limit);
+        self
+    }
+
+    /// Sets the call gas limit
+    pub fn call_gas_limit(mut self, call_gas_limit: u128) -> Self {
+        self.inner = self.inner.call_gas_limit(call_gas_limit);
+        self
+    }
+
+    /// Sets the max fee per gas
+    pub fn max_fee_per_gas(mut self, max_fee_per_gas: u128) -> Self {
+        self.inner = self.inner.max_fee_per_gas(max_fee_per_gas);
+        self
+    }
+
+    /// Sets the max priority fee per gas
+    pub fn max_priority_fee_per_gas(mut self, max_priority_fee_per_gas: u128) -> Self {
+        self.inner = self
+            .inner
+            .max_priority_fee_per_gas(max_priority_fee_per_gas);
+        self
+    }
+
+    /// Sets the paymaster verification gas limit
+    pub fn paymaster_verification_gas_limit(
+        mut self,
+        paymaster_verification_gas_limit: u128,
+    ) -> Self {
+        self.inner = self
+            .inner
+            .paymaster_verification_gas_limit(paymaster_verification_gas_limit);
+        self
+    }
+
+    /// Sets the paymaster post-op gas limit
+    pub fn paymaster_post_op_gas_limit(mut self, paymaster_post_op_gas_limit: u128) -> Self {
+        self.inner = self
+            .inner
+            .paymaster_post_op_gas_limit(paymaster_post_op_gas_limit);
+        self
+    }
+
+    /// Sets the packed user operation, if known beforehand
+    pub fn packed(mut self, packed: PackedUserOperation) -> Self {
+        self.inner = self.inner.packed(packed);
+        self
+    }
+
+    /// Sets the authorization list
+    pub fn authorization_tuple(mut self, authorization_tuple: Eip7702Auth) -> Self {
+        self.inner = self.inner.authorization_tuple(authorization_tuple);
+        self
+    }
+
+    /// Sets the aggregator
+    pub fn aggregator(mut self, aggregator: Address) -> Self {
+        self.inner = self.inner.aggregator(aggregator);
+        self
+    }
+
+    /// Sets the sender's current EIP-7702 delegate, as read from its code
+    ///
+    /// Used when hashing an `initCode` with the EIP-7702 marker if the operation doesn't
+    /// carry an authorization tuple.
+    pub fn eip7702_delegate(mut self, delegate: Address) -> Self {
+        self.eip7702_delegate = Some(delegate);
+        self
+    }
+
+    /// Builds the UserOperation
+    pub fn build(self) -> UserOperation {
+        let entry_point = self.chain_spec.entry_point_address_v0_8;
+        let chain_id = self.chain_spec.id;
+        let eip7702_delegate = self.eip7702_delegate;
+        UserOperation {
+            inner: self.inner.build_with_hasher(entry_point, |packed, auth| {
+                let delegate = auth.map(|auth| auth.address).or(eip7702_delegate);
+                hash_packed_user_operation(packed, delegate, entry_point, chain_id)
+            }),
+            eip7702_delegate,
+        }
+    }
+}
+
+/// Returns true if the `initCode` starts with the EIP-7702 marker
+///
+/// Mirrors the entry point check: at least 2 bytes, with the first 20 bytes (zero padded)
+/// equal to the marker.
+pub fn is_eip7702_init_code(init_code: &[u8]) -> bool {
+    if init_code.len() < 2 {
+        return false;
+    }
+    let mut start = [0_u8; 20];
+    let len = init_code.len().min(20);
+    start[..len].copy_from_slice(&init_code[..len]);
+    Address::from(start) == EIP7702_INIT_CODE_MARKER
+}
+
+sol! {
+    #[allow(missing_docs)]
+    #[derive(Default, Debug, PartialEq, Eq)]
+    struct UserOperationTypedForHash {
+        bytes32 typeHash;
+        address sender;
+        uint256 nonce;
+        bytes32 hashInitCode;
+        bytes32 hashCallData;
+        bytes32 accountGasLimits;
+        uint256 preVerificationGas;
+        bytes32 gasFees;
+        bytes32 hashPaymasterAndData;
+    }
+}
+
+fn hash_packed_user_operation(
+    puo: &PackedUserOperation,
+    eip7702_delegate: Option<Address>,
+    entry_point: Address,
+    chain_id: u64,
+) -> B256 {
+    let hash_init_code = hash_init_code(&puo.initCode, eip7702_delegate);
+    let hash_call_data = keccak256(&puo.callData);
+    let hash_paymaster_and_data = keccak256(&puo.paymasterAndData);
+
+    let struct_hash = keccak256(
+        UserOperationTypedForHash {
+            typeHash: PACKED_USER_OPERATION_TYPE_HASH,
+            sender: puo.sender,
+            nonce: puo.nonce,
+            hashInitCode: hash_init_code,
+            hashCallData: hash_call_data,
+            accountGasLimits: puo.accountGasLimits,
+            preVerificationGas: puo.preVerificationGas,
+            gasFees: puo.gasFees,
+            hashPaymasterAndData: hash_paymaster_and_data,
+        }
+        .abi_encode(),
+    );
+
+    let domain = Eip712Domain::new(
+        Some(DOMAIN_NAME.into()),
+        Some(DOMAIN_VERSION.into()),
+        Some(U256::from(chain_id)),
+        Some(entry_point),
+        None,
+    );
+
+    let mut digest_input = [0_u8; 66];
+    digest_input[0] = 0x19;
+    digest_input[1] = 0x01;
+    digest_input[2..34].copy_from_slice(domain.separator().as_slice());
+    digest_input[34..66].copy_from_slice(struct_hash.as_slice());
+    keccak256(digest_input)
+}
+
+// When the EIP-7702 marker is used, the entry point replaces the marker with the
+// sender's delegate address before hashing the init code.
+//
+// If the delegate is unknown the raw init code is hashed, the resulting hash is not
+// accepted by the entry point.
+fn hash_init_code(init_code: &Bytes, eip7702_delegate: Option<Address>) -> B256 {
+    match eip7702_delegate {
+        Some(delegate) if is_eip7702_init_code(init_code) => {
+            let mut preimage = delegate.to_vec();
+            if init_code.len() > 20 {
+                preimage.extend_from_slice(&init_code[20..]);
+            }
+            keccak256(preimage)
+        }
+        _ => keccak256(init_code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::bytes;
+
+    use super::*;
+
+    fn base_builder(cs: &ChainSpec) -> UserOperationBuilder<'_> {
+        UserOperationBuilder::new(
+            cs,
+            UserOperationRequiredFields {
+                sender: Address::ZERO,
+                nonce: U256::ZERO,
+                call_data: Bytes::new(),
+                call_gas_limit: 0,
+                verification_gas_limit: 0,
+                pre_verification_gas: 0,
+                max_priority_fee_per_gas: 0,
+                max_fee_per_gas: 0,
+                signature: Bytes::new(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_type_hash() {
+        assert_eq!(
+            keccak256("PackedUserOperation(address sender,uint256 nonce,bytes initCode,bytes callData,bytes32 accountGasLimits,uint256 preVerificationGas,bytes32 gasFees,bytes paymasterAndData)"),
+            PACKED_USER_OPERATION_TYPE_HASH
+        );
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        let cs = ChainSpec::default();
+        let uo = base_builder(&cs)
+            .factory(Address::random(), "0xdeadbeef".parse().unwrap())
+            .paymaster(Address::random(), 0, 0, Bytes::new())
+            .build();
+        let packed = uo.clone().pack();
+        let unpacked = UserOperationBuilder::from_packed(packed, &cs)
+            .unwrap()
+            .build();
+
+        assert_eq!(uo, unpacked);
+        assert_eq!(uo.entry_point(), cs.entry_point_address_v0_8);
+    }
+
+    #[test]
+    fn test_hash_differs_from_v0_7() {
+        let cs = ChainSpec::default();
+        let uo = base_builder(&cs).build();
+        let uo_v0_7 = v0_7::UserOperationBuilder::from_uo(uo.clone().into_v0_7(), &cs).build();
+
+        assert_eq!(uo.packed(), uo_v0_7.packed());
+        assert_ne!(uo.hash(), uo_v0_7.hash());
+    }
+
+    #[test]
+    fn test_eip7702_marker() {
+        assert!(is_eip7702_init_code(&bytes!("7702")));
+        assert!(is_eip7702_init_code(&bytes!(
+            "7702000000000000000000000000000000000000deadbeef"
+        )));
+        assert!(!is_eip7702_init_code(&bytes!("77")));
+        assert!(!is_eip7702_init_code(&bytes!(
+            "7702000000000000000000000000000000000001"
+        )));
+
+        let cs = ChainSpec::default();
+        let uo = base_builder(&cs)
+            .eip7702_init_code(bytes!("deadbeef"))
+            .build();
+        assert!(uo.is_eip7702_init_code());
+        assert_eq!(uo.factory(), None);
+        assert_eq!(uo.entities(), vec![Entity::account(Address::ZERO)]);
+    }
+
+    #[test]
+    fn test_eip7702_marker_hash_uses_delegate() {
+        let cs = ChainSpec::default();
+        let auth = Eip7702Auth {
+            address: Address::random(),
+            ..Default::default()
+        };
+        let uo = base_builder(&cs)
+            .eip7702_init_code(bytes!("deadbeef"))
+            .authorization_tuple(auth.clone())
+            .build();
+
+        let mut preimage = auth.address.to_vec();
+        preimage.extend_from_slice(&bytes!("deadbeef"));
+        assert_eq!(
+            hash_init_code(&uo.packed().initCode, Some(auth.address)),
+            keccak256(preimage)
+        );
+
+        let other_auth = Eip7702Auth {
+            address: Address::random(),
+            ..Default::default()
+        };
+        let other = base_builder(&cs)
+            .eip7702_init_code(bytes!("deadbeef"))
+            .authorization_tuple(other_auth)
+            .build();
+        assert_ne!(uo.hash(), other.hash());
+    }
+
+    #[test]
+    fn test_short_marker_from_packed() {
+        let cs = ChainSpec::default();
+        let mut packed = base_builder(&cs).build().pack();
+        packed.initCode = bytes!("7702");
+
+        let uo = UserOperationBuilder::from_packed(packed, &cs)
+            .unwrap()
+            .build();
+        assert!(uo.is_eip7702_init_code());
+        assert!(uo.factory_data().is_empty());
+        assert_eq!(uo.packed().initCode, bytes!("7702"));
+    }
+
+    #[test]
+    fn test_eip7702_marker_hash_uses_resolved_delegate() {
+        let cs = ChainSpec::default();
+        let delegate = Address::random();
+        let unresolved = base_builder(&cs)
+            .eip7702_init_code(bytes!("deadbeef"))
+            .build();
+        assert!(unresolved.requires_eip7702_delegate());
+
+        let resolved = UserOperationBuilder::from_uo(unresolved.clone(), &cs)
+            .eip7702_delegate(delegate)
+            .build();
+        assert!(!resolved.requires_eip7702_delegate());
+        assert_eq!(resolved.eip7702_delegate(), Some(delegate));
+        assert_ne!(unresolved.hash(), resolved.hash());
+
+        // Same hash as an operation authorizing the same delegate
+        let authorized = base_builder(&cs)
+            .eip7702_init_code(bytes!("deadbeef"))
+            .authorization_tuple(Eip7702Auth {
+                address: delegate,
+                ..Default::default()
+            })
+            .build();
+        assert_eq!(resolved.hash(), authorized.hash());
+
+        // The delegate is kept when rebuilding
+        let rebuilt = UserOperationBuilder::from_uo(resolved.clone(), &cs)
+            .call_gas_limit(1)
+            .build();
+        assert_eq!(rebuilt.eip7702_delegate(), Some(delegate));
+    }
+}
@@ -16,6 +16,9 @@
 use alloy_primitives::{fixed_bytes, Address, FixedBytes};
 use alloy_rpc_types_eth::state::{AccountOverride, StateOverride};
 
+/// Prefix of the code of an account delegated with EIP-7702
+const EIP7702_CODE_PREFIX: FixedBytes<3> = fixed_bytes!("ef0100");
+
 /// Apply a [7702](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7702.md)
 /// overrides to `StateOverride`.
 pub fn apply_7702_overrides(
@@ -24,8 +27,7 @@ pub fn apply_7702_overrides(
     eip7702_auth_address: Address,
 ) {
     state_override.entry(sender).or_insert({
-        let prefix: FixedBytes<3> = fixed_bytes!("ef0100");
-        let code: FixedBytes<23> = prefix.concat_const(eip7702_auth_address.into());
+        let code: FixedBytes<23> = EIP7702_CODE_PREFIX.concat_const(eip7702_auth_address.into());
 
         AccountOverride {
             code: Some((code).into()),
@@ -33,3 +35,32 @@ pub fn apply_7702_overrides(
         }
     });
 }
+
+/// Returns the delegate address of an account from its code, if it is delegated
+/// with [7702](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7702.md)
+pub fn eip7702_delegate(code: &[u8]) -> Option<Address> {
+    if code.len() != 23 || code[..3] != EIP7702_CODE_PREFIX[..] {
+        return None;
+    }
+    Some(Address::from_slice(&code[3..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::address;
+
+    use super::*;
+
+    #[test]
+    fn test_eip7702_delegate() {
+        let delegate = address!("1234567890123456789012345678901234567890");
+        let mut state_override = StateOverride::default();
+        apply_7702_overrides(&mut state_override, Address::ZERO, delegate);
+        let code = state_override[&Address::ZERO].code.clone().unwrap();
+
+        assert_eq!(eip7702_delegate(&code), Some(delegate));
+        assert_eq!(eip7702_delegate(&code[..22]), None);
+        assert_eq!(eip7702_delegate(&[0x60; 23]), None);
+        assert_eq!(eip7702_delegate(&[]), None);
+    }
+}
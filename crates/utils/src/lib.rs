@@ -30,3 +30,4 @@ pub mod math;
 pub mod random;
 pub mod retry;
 pub mod strs;
+pub mod token_bucket;
@@ -0,0 +1,89 @@
+// This file is part of Rundler.
+//
+// Rundler is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free Software
+// Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// Rundler is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with Rundler.
+// If not, see https://www.gnu.org/licenses/.
+
+//! Token bucket rate limiting utilities.
+
+use std::time::Instant;
+
+/// A token bucket refilling continuously at `rate` tokens per second, up to `burst` tokens.
+///
+/// The rate and burst are passed to each call rather than stored, so that callers keeping
+/// a bucket per key don't store the same limit in every bucket.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket
+    pub fn new(burst: f64, now: Instant) -> Self {
+        Self {
+            tokens: burst,
+            last_refill: now,
+        }
+    }
+
+    /// Refills the bucket for the time elapsed since its last refill
+    pub fn refill(&mut self, rate: f64, burst: f64, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst);
+        self.last_refill = now;
+    }
+
+    /// Returns true if the bucket has a token to take
+    pub fn has_token(&self) -> bool {
+        self.tokens >= 1.0
+    }
+
+    /// Takes a token from the bucket, returning false if it is empty
+    pub fn take(&mut self) -> bool {
+        if !self.has_token() {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    /// Returns true if the bucket holds `burst` tokens, i.e. it is equivalent to a new bucket
+    pub fn is_full(&self, burst: f64) -> bool {
+        self.tokens >= burst
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_take_and_refill() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, now);
+        assert!(bucket.take());
+        assert!(bucket.take());
+        assert!(!bucket.take());
+
+        bucket.refill(1.0, 2.0, now + Duration::from_millis(500));
+        assert!(!bucket.has_token());
+        bucket.refill(1.0, 2.0, now + Duration::from_secs(1));
+        assert!(bucket.take());
+
+        // refills are capped at the burst
+        bucket.refill(1.0, 2.0, now + Duration::from_secs(100));
+        assert!(bucket.is_full(2.0));
+    }
+}